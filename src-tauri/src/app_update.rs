@@ -0,0 +1,482 @@
+//! Update checks for SparrowAI itself.
+//!
+//! Releases come from the GitHub releases API (`updates.releases_url`). The
+//! stable channel only sees full releases; beta also sees pre-releases. Each
+//! release ships its installers, a checksum file (`<installer>.sha256` or a
+//! `SHA256SUMS` listing) and, for tauri-updater, a `latest.json` manifest;
+//! its URL is reported as `updater_manifest_url` so the updater plugin
+//! follows the same channel. Installers downloaded here are checked against
+//! the published SHA256 before they are handed back.
+//!
+//! Skipped versions and "remind me later" are kept in the `updates` settings.
+
+use std::cmp::Ordering;
+
+use futures::StreamExt;
+use serde::{ Deserialize, Serialize };
+use sha2::{ Digest, Sha256 };
+use tauri::{ AppHandle, Emitter };
+use tokio::io::AsyncWriteExt;
+
+use crate::settings::{ self, UpdateChannel };
+use crate::{ constants, paths };
+
+const UPDATER_MANIFEST: &str = "latest.json";
+const CHECKSUM_LISTS: &[&str] = &["SHA256SUMS", "SHA256SUMS.txt", "checksums.txt"];
+
+#[derive(Debug, Clone, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    name: Option<String>,
+    body: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    published_at: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUpdateInfo {
+    pub version: String,
+    pub prerelease: bool,
+    pub published_at: Option<String>,
+    pub release_url: String,
+    /// Installer for this platform, if the release has one
+    pub installer_name: Option<String>,
+    pub installer_size: Option<u64>,
+    /// tauri-updater manifest of this release
+    pub updater_manifest_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUpdateCheck {
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// Newest release above the current version, skipped or not
+    pub latest: Option<AppUpdateInfo>,
+    /// Whether to prompt: `latest` exists, is not skipped, and no deferral is active
+    pub should_prompt: bool,
+    pub skipped: bool,
+    pub deferred_until: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub title: String,
+    /// Markdown body of the release
+    pub notes: String,
+    pub published_at: Option<String>,
+    pub release_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadedAppUpdate {
+    pub version: String,
+    pub path: String,
+    pub sha256: String,
+    pub bytes: u64,
+}
+
+/// Dotted version with an optional pre-release part, e.g. `0.6.0-beta.2`
+#[derive(Debug, Clone)]
+struct Version {
+    numbers: Vec<u64>,
+    pre: Option<String>,
+}
+
+impl Version {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches(['v', 'V']);
+        let text = text.split('+').next().unwrap_or(text);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (text, None),
+        };
+        let numbers = core.split('.').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?;
+        (!numbers.is_empty()).then_some(Self { numbers, pre })
+    }
+}
+
+/// Pre-release identifiers compare numerically when both are numbers
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut left = a.split('.');
+    let mut right = b.split('.');
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.numbers.len().max(other.numbers.len());
+        for i in 0..len {
+            let order = self.numbers.get(i).unwrap_or(&0).cmp(other.numbers.get(i).unwrap_or(&0));
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        // A release sorts above its own pre-releases
+        match (&self.pre, &other.pre) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => compare_pre(a, b),
+        }
+    }
+}
+
+// Missing trailing numbers count as zero, so `1.0` equals `1.0.0`
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn current_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+fn version_of(release: &GithubRelease) -> String {
+    release.tag_name.trim_start_matches(['v', 'V']).to_string()
+}
+
+/// The newest release the channel allows that is newer than `current`
+fn newest_release<'a>(releases: &'a [GithubRelease], channel: UpdateChannel, current: &str) -> Option<&'a GithubRelease> {
+    let current = Version::parse(current)?;
+    releases
+        .iter()
+        .filter(|release| !release.draft && (channel == UpdateChannel::Beta || !release.prerelease))
+        .filter_map(|release| Version::parse(&release.tag_name).map(|version| (version, release)))
+        .filter(|(version, _)| *version > current)
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release)
+}
+
+/// Installer suffixes for this platform, most preferred first
+fn installer_suffixes() -> &'static [&'static str] {
+    match std::env::consts::OS {
+        "windows" => &["-setup.exe", ".msi", ".exe"],
+        "macos" => &[".dmg"],
+        _ => &[".AppImage", ".deb"],
+    }
+}
+
+fn find_installer<'a>(assets: &'a [GithubAsset], suffixes: &[&str], arch: &str) -> Option<&'a GithubAsset> {
+    // Releases may carry installers for several architectures
+    let arch_aliases: &[&str] = match arch {
+        "x86_64" => &["x64", "x86_64", "amd64"],
+        "aarch64" => &["arm64", "aarch64"],
+        _ => &[],
+    };
+    let other_arch = |name: &str| {
+        let name = name.to_lowercase();
+        ["x64", "x86_64", "amd64", "arm64", "aarch64"].iter().any(|a| name.contains(a))
+            && !arch_aliases.iter().any(|a| name.contains(a))
+    };
+    suffixes.iter().find_map(|suffix| {
+        assets.iter().find(|asset| asset.name.ends_with(suffix) && !other_arch(&asset.name))
+    })
+}
+
+/// SHA256 for `file_name` from a `<hash>  <name>` listing, or from a
+/// single-file `.sha256` whose only content is the hash
+fn parse_checksum(listing: &str, file_name: &str) -> Option<String> {
+    let is_hash = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    let lines: Vec<&str> = listing.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+
+    for line in &lines {
+        let mut parts = line.split_whitespace();
+        let (Some(hash), name) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        match name {
+            Some(name) if name.trim_start_matches('*') == file_name && is_hash(hash) => {
+                return Some(hash.to_lowercase());
+            }
+            None if lines.len() == 1 && is_hash(hash) => return Some(hash.to_lowercase()),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    crate::http::client_with_timeout(std::time::Duration::from_secs(30))
+}
+
+async fn fetch_releases(client: &reqwest::Client) -> Result<Vec<GithubRelease>, String> {
+    let url = settings::current().updates.releases_url;
+    let response = client
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .send().await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Update check failed with status: {}", response.status()));
+    }
+    response.json().await.map_err(|e| format!("Failed to parse release list: {}", e))
+}
+
+fn update_info(release: &GithubRelease) -> AppUpdateInfo {
+    let installer = find_installer(&release.assets, installer_suffixes(), std::env::consts::ARCH);
+    AppUpdateInfo {
+        version: version_of(release),
+        prerelease: release.prerelease,
+        published_at: release.published_at.clone(),
+        release_url: release.html_url.clone(),
+        installer_name: installer.map(|asset| asset.name.clone()),
+        installer_size: installer.map(|asset| asset.size),
+        updater_manifest_url: release.assets
+            .iter()
+            .find(|asset| asset.name == UPDATER_MANIFEST)
+            .map(|asset| asset.browser_download_url.clone()),
+    }
+}
+
+/// Look for a newer release on the configured channel
+#[tauri::command]
+pub async fn check_for_app_update() -> Result<AppUpdateCheck, String> {
+    let updates = settings::current().updates;
+    let current = current_version();
+
+    let releases = fetch_releases(&client()?).await?;
+    let latest = newest_release(&releases, updates.channel, &current).map(update_info);
+
+    let skipped = latest.as_ref().is_some_and(|info| updates.skipped_versions.contains(&info.version));
+    let now = chrono::Utc::now().timestamp_millis();
+    let deferred_until = updates.deferred_until.filter(|until| *until > now);
+
+    tracing::info!(current = %current, channel = ?updates.channel, latest = ?latest.as_ref().map(|i| &i.version), "Checked for app updates");
+    Ok(AppUpdateCheck {
+        current_version: current,
+        channel: updates.channel,
+        should_prompt: latest.is_some() && !skipped && deferred_until.is_none(),
+        latest,
+        skipped,
+        deferred_until,
+    })
+}
+
+/// Notes of `version`, or of the newest release on the channel
+#[tauri::command]
+pub async fn get_app_release_notes(version: Option<String>) -> Result<ReleaseNotes, String> {
+    let releases = fetch_releases(&client()?).await?;
+    let release = match &version {
+        Some(version) => {
+            let wanted = Version::parse(version).ok_or_else(|| format!("Invalid version: {}", version))?;
+            releases.iter().find(|release| Version::parse(&release.tag_name).as_ref() == Some(&wanted))
+        }
+        // Every release is newer than 0
+        None => newest_release(&releases, settings::current().updates.channel, "0"),
+    }
+    .ok_or_else(|| format!("No release found for {}", version.as_deref().unwrap_or("this channel")))?;
+
+    Ok(ReleaseNotes {
+        version: version_of(release),
+        title: release.name.clone().unwrap_or_else(|| release.tag_name.clone()),
+        notes: release.body.clone().unwrap_or_default(),
+        published_at: release.published_at.clone(),
+        release_url: release.html_url.clone(),
+    })
+}
+
+/// Download the installer of `version` and check it against the published
+/// SHA256, emitting `app-update-progress`. Releases without a checksum are refused.
+#[tauri::command]
+pub async fn download_app_update(app: AppHandle, version: String) -> Result<DownloadedAppUpdate, String> {
+    log_operation_start!("App update download", version = %version);
+    let client = client()?;
+    let wanted = Version::parse(&version).ok_or_else(|| format!("Invalid version: {}", version))?;
+
+    let releases = fetch_releases(&client).await?;
+    let release = releases
+        .iter()
+        .find(|release| Version::parse(&release.tag_name).as_ref() == Some(&wanted))
+        .ok_or_else(|| format!("No release found for {}", version))?;
+    let installer = find_installer(&release.assets, installer_suffixes(), std::env::consts::ARCH)
+        .ok_or_else(|| format!("Release {} has no installer for this platform", version))?;
+
+    // Prefer the installer's own checksum file over a release-wide listing
+    let checksum_asset = release.assets
+        .iter()
+        .find(|asset| asset.name == format!("{}.sha256", installer.name))
+        .or_else(|| release.assets.iter().find(|asset| CHECKSUM_LISTS.contains(&asset.name.as_str())))
+        .ok_or_else(|| format!("Release {} publishes no checksum; not downloading it", version))?;
+    let listing = client
+        .get(&checksum_asset.browser_download_url)
+        .send().await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch checksum: {}", e))?
+        .text().await
+        .map_err(|e| format!("Failed to read checksum: {}", e))?;
+    let expected = parse_checksum(&listing, &installer.name)
+        .ok_or_else(|| format!("{} has no checksum for {}", checksum_asset.name, installer.name))?;
+
+    let target = paths::get_updates_dir().map_err(|e| e.to_string())?.join(&installer.name);
+    let response = client
+        .get(&installer.browser_download_url)
+        .timeout(std::time::Duration::from_secs(constants::DOWNLOAD_TIMEOUT_SECS))
+        .send().await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to download update: {}", e))?;
+    let total = response.content_length().unwrap_or(installer.size);
+
+    let mut file = tokio::fs::File::create(&target).await
+        .map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    let mut last_emit = std::time::Instant::now();
+    let mut stream = response.bytes_stream();
+
+    let written: Result<(), String> = async {
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
+            file.write_all(&chunk).await.map_err(|e| format!("Failed to write chunk: {}", e))?;
+            hasher.update(&chunk);
+            downloaded += chunk.len() as u64;
+
+            if last_emit.elapsed().as_millis() > constants::DOWNLOAD_PROGRESS_INTERVAL_MS || downloaded == total {
+                last_emit = std::time::Instant::now();
+                let _ = app.emit("app-update-progress", serde_json::json!({
+                    "version": version,
+                    "downloadedBytes": downloaded,
+                    "totalBytes": total,
+                }));
+            }
+        }
+        file.flush().await.map_err(|e| format!("Failed to flush file: {}", e))
+    }.await;
+    drop(file);
+
+    // A partial installer must not be left where it could be run
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&target).await;
+        log_operation_error!("App update download", &e, version = %version);
+        return Err(e);
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != expected {
+        let _ = tokio::fs::remove_file(&target).await;
+        let e = format!("The downloaded installer failed verification: SHA256 is {}, expected {}", actual, expected);
+        log_operation_error!("App update download", &e, version = %version);
+        return Err(e);
+    }
+
+    log_operation_success!("App update download", version = %version, bytes = downloaded);
+    Ok(DownloadedAppUpdate {
+        version: version_of(release),
+        path: target.to_string_lossy().to_string(),
+        sha256: actual,
+        bytes: downloaded,
+    })
+}
+
+/// Stop offering `version`; newer releases are still offered
+#[tauri::command]
+pub async fn skip_app_update_version(version: String) -> Result<(), String> {
+    let version = version.trim_start_matches(['v', 'V']).to_string();
+    settings::update(|settings| {
+        if !settings.updates.skipped_versions.contains(&version) {
+            settings.updates.skipped_versions.push(version.clone());
+        }
+    })?;
+    Ok(())
+}
+
+/// Hold off update prompts for `hours`; 0 clears a deferral
+#[tauri::command]
+pub async fn defer_app_update(hours: u32) -> Result<Option<i64>, String> {
+    let until = (hours > 0).then(|| chrono::Utc::now().timestamp_millis() + i64::from(hours) * 3_600_000);
+    settings::update(|settings| settings.updates.deferred_until = until)?;
+    Ok(until)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool) -> GithubRelease {
+        GithubRelease {
+            tag_name: tag.to_string(),
+            name: None,
+            body: None,
+            prerelease,
+            draft: false,
+            published_at: None,
+            html_url: String::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_version_order() {
+        let v = |s: &str| Version::parse(s).unwrap();
+        assert!(v("v0.6.0") > v("0.5.9"));
+        assert!(v("0.6.0") > v("0.6.0-beta.2"));
+        assert!(v("0.6.0-beta.10") > v("0.6.0-beta.2"));
+        assert!(v("0.6.0-rc.1") > v("0.6.0-beta.3"));
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert!(Version::parse("nightly").is_none());
+    }
+
+    #[test]
+    fn test_newest_release_respects_channel() {
+        let releases = vec![release("v0.5.0", false), release("v0.5.1", false), release("v0.6.0-beta.1", true)];
+        assert_eq!(newest_release(&releases, UpdateChannel::Stable, "0.5.0").unwrap().tag_name, "v0.5.1");
+        assert_eq!(newest_release(&releases, UpdateChannel::Beta, "0.5.0").unwrap().tag_name, "v0.6.0-beta.1");
+        assert!(newest_release(&releases, UpdateChannel::Stable, "0.5.1").is_none());
+    }
+
+    #[test]
+    fn test_find_installer_skips_other_architectures() {
+        let asset = |name: &str| GithubAsset { name: name.to_string(), browser_download_url: String::new(), size: 0 };
+        let assets = vec![asset("sparrow_0.6.0_arm64-setup.exe"), asset("sparrow_0.6.0_x64-setup.exe"), asset("latest.json")];
+        let found = find_installer(&assets, &["-setup.exe"], "x86_64").unwrap();
+        assert_eq!(found.name, "sparrow_0.6.0_x64-setup.exe");
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let hash = "a".repeat(64);
+        assert_eq!(parse_checksum(&format!("{}  app.msi\n{}  other.msi", hash, "b".repeat(64)), "app.msi"), Some(hash.clone()));
+        assert_eq!(parse_checksum(&format!("{} *app.msi", hash), "app.msi"), Some(hash.clone()));
+        assert_eq!(parse_checksum(&hash, "app.msi"), Some(hash));
+        assert_eq!(parse_checksum("not a hash  app.msi", "app.msi"), None);
+    }
+}
@@ -9,6 +9,8 @@ pub enum AutostartError {
     DisableFailed(String),
     #[error("Failed to check autostart status: {0}")]
     StatusCheckFailed(String),
+    #[error("Failed to save autostart settings: {0}")]
+    SettingsFailed(String),
 }
 
 impl serde::Serialize for AutostartError {
@@ -57,3 +59,47 @@ pub async fn toggle_autostart<R: Runtime>(app: tauri::AppHandle<R>) -> Result<bo
         Ok(true)
     }
 }
+
+/// Argument the OS launch entry passes when starting the app at login
+pub const AUTOSTART_ARG: &str = "--minimized";
+
+/// Whether this process was started by the autostart entry rather than the user
+pub fn launched_by_autostart() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// Autostart registration plus the launch behavior stored in settings
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AutostartConfig {
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub options: crate::settings::AutostartSettings,
+}
+
+/// Get autostart registration and launch options
+#[tauri::command]
+pub async fn get_autostart_config<R: Runtime>(app: tauri::AppHandle<R>) -> Result<AutostartConfig, AutostartError> {
+    Ok(AutostartConfig {
+        enabled: is_autostart_enabled(app).await?,
+        options: crate::settings::current().autostart,
+    })
+}
+
+/// Register/unregister autostart and save the launch options in one call
+#[tauri::command]
+pub async fn set_autostart_config<R: Runtime>(
+    app: tauri::AppHandle<R>,
+    config: AutostartConfig,
+) -> Result<AutostartConfig, AutostartError> {
+    if config.enabled {
+        enable_autostart(app.clone()).await?;
+    } else {
+        disable_autostart(app.clone()).await?;
+    }
+
+    let options = config.options.clone();
+    crate::settings::update(|settings| settings.autostart = options)
+        .map_err(AutostartError::SettingsFailed)?;
+
+    get_autostart_config(app).await
+}
@@ -0,0 +1,193 @@
+//! Cancellable operations, keyed by job id.
+//!
+//! Chat and text-assist streams, model downloads, document ingestion,
+//! embedding requests and research runs register a `CancellationToken` under
+//! an id the caller already knows: the session, request or research id, the
+//! model id, or the `job_id` passed in (the file path when an ingestion has
+//! none). `cancel_operation` cancels any of them; the operation notices at its
+//! next await point, cleans up as it would after a failure and reports itself
+//! as cancelled. An operation stays registered until its `OperationGuard` is
+//! dropped.
+
+use std::collections::HashMap;
+use std::sync::atomic::{ AtomicU64, Ordering };
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager, State };
+use tokio_util::sync::CancellationToken;
+
+use crate::state::AppState;
+
+static NEXT_REGISTRATION: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Chat,
+    TextAssist,
+    Download,
+    Ingestion,
+    Embeddings,
+    Research,
+}
+
+pub struct Operation {
+    pub kind: OperationKind,
+    pub started_at: DateTime<Utc>,
+    token: CancellationToken,
+    /// Tells a newer operation under the same id apart from the one being dropped
+    registration: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub id: String,
+    pub kind: OperationKind,
+    pub started_at: DateTime<Utc>,
+    /// Cancel was requested but the operation has not wound down yet
+    pub cancelled: bool,
+}
+
+/// Keeps an operation registered; dropping it unregisters the id
+pub struct OperationGuard {
+    app: AppHandle,
+    id: String,
+    token: CancellationToken,
+    registration: u64,
+}
+
+impl OperationGuard {
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Resolves once the operation is cancelled
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let state = self.app.state::<AppState>();
+        unregister(&mut state.operations.lock(), &self.id, self.registration);
+    }
+}
+
+fn insert(
+    operations: &mut HashMap<String, Operation>,
+    id: &str,
+    kind: OperationKind,
+    replace: bool
+) -> Result<(CancellationToken, u64), String> {
+    if !replace && operations.contains_key(id) {
+        return Err(format!("An operation with id {} is already running", id));
+    }
+    let token = CancellationToken::new();
+    let registration = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+    operations.insert(id.to_string(), Operation { kind, started_at: Utc::now(), token: token.clone(), registration });
+    Ok((token, registration))
+}
+
+fn unregister(operations: &mut HashMap<String, Operation>, id: &str, registration: u64) {
+    if operations.get(id).is_some_and(|operation| operation.registration == registration) {
+        operations.remove(id);
+    }
+}
+
+fn guard(app: &AppHandle, id: &str, kind: OperationKind, replace: bool) -> Result<OperationGuard, String> {
+    let (token, registration) = insert(&mut app.state::<AppState>().operations.lock(), id, kind, replace)?;
+    Ok(OperationGuard { app: app.clone(), id: id.to_string(), token, registration })
+}
+
+/// Register `id`; an earlier operation under the same id keeps running but
+/// can no longer be cancelled
+pub fn register(app: &AppHandle, id: &str, kind: OperationKind) -> OperationGuard {
+    guard(app, id, kind, true).expect("replacing registration cannot fail")
+}
+
+/// Register `id` unless an operation with that id is running
+pub fn try_register(app: &AppHandle, id: &str, kind: OperationKind) -> Result<OperationGuard, String> {
+    guard(app, id, kind, false)
+}
+
+/// Ask the operation under `id` to stop; returns what kind it was
+pub fn cancel(app: &AppHandle, id: &str) -> Result<OperationKind, String> {
+    let kind = {
+        let state = app.state::<AppState>();
+        let operations = state.operations.lock();
+        let operation = operations.get(id).ok_or_else(|| format!("No running operation with id {}", id))?;
+        operation.token.cancel();
+        operation.kind
+    };
+    tracing::info!(id = %id, kind = ?kind, "Operation cancelled");
+    let _ = app.emit("operation-cancelled", serde_json::json!({ "id": id, "kind": kind }));
+    Ok(kind)
+}
+
+/// Token of the operation under `id`, for code that runs on its behalf
+pub fn token(app: &AppHandle, id: &str) -> Option<CancellationToken> {
+    app.state::<AppState>().operations.lock().get(id).map(|operation| operation.token.clone())
+}
+
+/// Running operations of the given kinds (all when empty), by id
+pub fn running(state: &AppState, kinds: &[OperationKind]) -> Vec<OperationInfo> {
+    let mut operations: Vec<OperationInfo> = state.operations
+        .lock()
+        .iter()
+        .filter(|(_, operation)| kinds.is_empty() || kinds.contains(&operation.kind))
+        .map(|(id, operation)| OperationInfo {
+            id: id.clone(),
+            kind: operation.kind,
+            started_at: operation.started_at,
+            cancelled: operation.token.is_cancelled(),
+        })
+        .collect();
+    operations.sort_by(|a, b| a.id.cmp(&b.id));
+    operations
+}
+
+#[tauri::command]
+pub async fn cancel_operation(app: AppHandle, id: String) -> Result<OperationKind, String> {
+    // A download also records the cancel in its pause/resume state
+    if app.state::<AppState>().downloads.lock().contains_key(&id) {
+        crate::huggingface::cancel_model_download(app.clone(), id.clone()).await?;
+        return Ok(OperationKind::Download);
+    }
+    // A queued ingestion has nothing running to cancel yet
+    if crate::rag::ingest_queue::cancel_queued(&app, &id) {
+        return Ok(OperationKind::Ingestion);
+    }
+    cancel(&app, &id)
+}
+
+#[tauri::command]
+pub async fn list_operations(state: State<'_, AppState>) -> Result<Vec<OperationInfo>, String> {
+    Ok(running(&state, &[]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_unregister_respect_registration() {
+        let mut operations = HashMap::new();
+        let (first, first_registration) = insert(&mut operations, "session", OperationKind::Chat, true).unwrap();
+        assert!(insert(&mut operations, "session", OperationKind::Chat, false).is_err());
+
+        // A newer stream on the same session takes the id over
+        let (second, _) = insert(&mut operations, "session", OperationKind::Chat, true).unwrap();
+        unregister(&mut operations, "session", first_registration);
+        assert!(operations.contains_key("session"));
+
+        operations["session"].token.cancel();
+        assert!(second.is_cancelled());
+        assert!(!first.is_cancelled());
+    }
+}
@@ -0,0 +1,255 @@
+//! PNG charts for the `render_chart` tool.
+//!
+//! The model passes the data and a small spec; the image is written to the
+//! chat session's assets folder and its path returned, so the frontend can
+//! show it inline like a generated image.
+
+use std::ops::Range;
+use std::path::{ Path, PathBuf };
+
+use plotters::prelude::*;
+use serde::{ Deserialize, Serialize };
+
+use crate::paths;
+
+const DEFAULT_WIDTH: u32 = 900;
+const DEFAULT_HEIGHT: u32 = 540;
+const MAX_SIDE: u32 = 2400;
+const MAX_SERIES: usize = 12;
+const MAX_POINTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartKind {
+    Bar,
+    Line,
+    Scatter,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChartSeries {
+    #[serde(default)]
+    pub name: String,
+    /// One value per label (bar and line charts)
+    #[serde(default)]
+    pub values: Vec<f64>,
+    /// `[x, y]` pairs (scatter charts)
+    #[serde(default)]
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChartSpec {
+    #[serde(rename = "type")]
+    pub kind: ChartKind,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub x_label: Option<String>,
+    #[serde(default)]
+    pub y_label: Option<String>,
+    /// Category names along the x axis (bar and line charts)
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub series: Vec<ChartSeries>,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedChart {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn validate(spec: &ChartSpec) -> Result<(), String> {
+    if spec.series.is_empty() {
+        return Err("A chart needs at least one series".to_string());
+    }
+    if spec.series.len() > MAX_SERIES {
+        return Err(format!("At most {} series are supported", MAX_SERIES));
+    }
+
+    for series in &spec.series {
+        let count = match spec.kind {
+            ChartKind::Scatter => series.points.len(),
+            ChartKind::Bar | ChartKind::Line => series.values.len(),
+        };
+        if count == 0 {
+            return Err(format!("Series '{}' has no data", series.name));
+        }
+        if count > MAX_POINTS {
+            return Err(format!("Series '{}' has more than {} points", series.name, MAX_POINTS));
+        }
+        if !spec.labels.is_empty() && spec.kind != ChartKind::Scatter && series.values.len() != spec.labels.len() {
+            return Err(format!(
+                "Series '{}' has {} values but there are {} labels",
+                series.name,
+                series.values.len(),
+                spec.labels.len()
+            ));
+        }
+        let finite = series.values.iter().all(|v| v.is_finite())
+            && series.points.iter().all(|(x, y)| x.is_finite() && y.is_finite());
+        if !finite {
+            return Err(format!("Series '{}' contains a value that is not a number", series.name));
+        }
+    }
+    Ok(())
+}
+
+/// `values` padded by 5% on each side; widened when all values are equal
+fn padded_range(values: impl Iterator<Item = f64>, include_zero: bool) -> Range<f64> {
+    let (mut min, mut max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if include_zero {
+        min = min.min(0.0);
+        max = max.max(0.0);
+    }
+    if max - min < f64::EPSILON {
+        min -= 1.0;
+        max += 1.0;
+    }
+    let pad = (max - min) * 0.05;
+    let lower = if include_zero && min >= 0.0 { min } else { min - pad };
+    lower..max + pad
+}
+
+fn category_count(spec: &ChartSpec) -> usize {
+    spec.series.iter().map(|series| series.values.len()).max().unwrap_or(0)
+}
+
+fn draw(spec: &ChartSpec, path: &Path, width: u32, height: u32) -> Result<(), String> {
+    let draw_error = |e: &dyn std::fmt::Display| format!("Failed to draw chart: {}", e);
+
+    let root = BitMapBackend::new(path, (width, height)).into_drawing_area();
+    root.fill(&WHITE).map_err(|e| draw_error(&e))?;
+
+    let (x_range, y_range) = match spec.kind {
+        ChartKind::Scatter => (
+            padded_range(spec.series.iter().flat_map(|s| s.points.iter().map(|p| p.0)), false),
+            padded_range(spec.series.iter().flat_map(|s| s.points.iter().map(|p| p.1)), false),
+        ),
+        ChartKind::Bar | ChartKind::Line => (
+            -0.5..category_count(spec) as f64 - 0.5,
+            padded_range(spec.series.iter().flat_map(|s| s.values.iter().copied()), spec.kind == ChartKind::Bar),
+        ),
+    };
+
+    let mut builder = ChartBuilder::on(&root);
+    builder.margin(20).x_label_area_size(50).y_label_area_size(70);
+    if let Some(title) = spec.title.as_deref().filter(|t| !t.is_empty()) {
+        builder.caption(title, ("sans-serif", 28));
+    }
+    let mut chart = builder.build_cartesian_2d(x_range, y_range).map_err(|e| draw_error(&e))?;
+
+    // Categories sit on whole numbers; other ticks get no label
+    let categories = category_count(spec);
+    let label_for = |x: &f64| {
+        let index = x.round();
+        if (x - index).abs() > 1e-6 || index < 0.0 || index as usize >= categories {
+            String::new()
+        } else {
+            spec.labels.get(index as usize).cloned().unwrap_or_else(|| (index as usize + 1).to_string())
+        }
+    };
+    let mut mesh = chart.configure_mesh();
+    mesh.x_desc(spec.x_label.clone().unwrap_or_default())
+        .y_desc(spec.y_label.clone().unwrap_or_default());
+    if spec.kind != ChartKind::Scatter {
+        mesh.x_label_formatter(&label_for).x_labels(categories + 1).disable_x_mesh();
+    }
+    mesh.draw().map_err(|e| draw_error(&e))?;
+
+    let bar_width = 0.8 / spec.series.len() as f64;
+    for (index, series) in spec.series.iter().enumerate() {
+        let color = Palette99::pick(index).mix(0.9);
+        let drawn = match spec.kind {
+            ChartKind::Line => chart.draw_series(LineSeries::new(
+                series.values.iter().enumerate().map(|(i, v)| (i as f64, *v)),
+                color.stroke_width(2),
+            )),
+            ChartKind::Bar => chart.draw_series(series.values.iter().enumerate().map(|(i, v)| {
+                let left = i as f64 - 0.4 + index as f64 * bar_width;
+                Rectangle::new([(left, 0.0), (left + bar_width, *v)], color.filled())
+            })),
+            ChartKind::Scatter => chart.draw_series(
+                series.points.iter().map(|(x, y)| Circle::new((*x, *y), 4, color.filled()))
+            ),
+        }.map_err(|e| draw_error(&e))?;
+
+        if !series.name.is_empty() {
+            drawn.label(series.name.clone())
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 12, y + 5)], color.filled()));
+        }
+    }
+
+    if spec.series.iter().any(|series| !series.name.is_empty()) {
+        chart.configure_series_labels()
+            .background_style(WHITE.mix(0.85))
+            .border_style(BLACK)
+            .draw()
+            .map_err(|e| draw_error(&e))?;
+    }
+
+    root.present().map_err(|e| draw_error(&e))
+}
+
+/// Render `spec` into the assets folder of `session_id`. Blocks; call from
+/// a blocking thread.
+pub fn render(spec: &ChartSpec, session_id: Option<&str>) -> Result<RenderedChart, String> {
+    validate(spec)?;
+    let width = spec.width.unwrap_or(DEFAULT_WIDTH).clamp(200, MAX_SIDE);
+    let height = spec.height.unwrap_or(DEFAULT_HEIGHT).clamp(150, MAX_SIDE);
+
+    let dir = paths::get_session_assets_dir(session_id).map_err(|e| e.to_string())?;
+    let path: PathBuf = dir.join(format!("chart-{}.png", uuid::Uuid::new_v4()));
+    draw(spec, &path, width, height)?;
+
+    tracing::debug!(path = %path.display(), kind = ?spec.kind, "Rendered chart");
+    Ok(RenderedChart { path: path.to_string_lossy().to_string(), width, height })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(value: serde_json::Value) -> ChartSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validate_checks_series_against_labels() {
+        let ok = spec(serde_json::json!({
+            "type": "bar",
+            "labels": ["Q1", "Q2"],
+            "series": [{ "name": "Revenue", "values": [3.0, 4.5] }]
+        }));
+        assert!(validate(&ok).is_ok());
+
+        let mismatched = spec(serde_json::json!({
+            "type": "line",
+            "labels": ["Q1", "Q2", "Q3"],
+            "series": [{ "name": "Revenue", "values": [3.0, 4.5] }]
+        }));
+        assert!(validate(&mismatched).is_err());
+
+        let empty_scatter = spec(serde_json::json!({
+            "type": "scatter",
+            "series": [{ "name": "Samples", "values": [1.0] }]
+        }));
+        assert!(validate(&empty_scatter).is_err());
+    }
+
+    #[test]
+    fn test_padded_range() {
+        let range = padded_range([2.0, 10.0].into_iter(), true);
+        assert_eq!(range.start, 0.0);
+        assert!(range.end > 10.0);
+
+        let flat = padded_range([5.0, 5.0].into_iter(), false);
+        assert!(flat.start < 5.0 && flat.end > 5.0);
+    }
+}
@@ -4,9 +4,9 @@ use serde_json;
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::{ Duration, Instant };
 use uuid::Uuid;
-use std::sync::{ Arc, Mutex };
-use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 use async_openai::{Client, config::OpenAIConfig};
 use async_openai::types::chat::{
     CreateChatCompletionRequestArgs,
@@ -21,17 +21,30 @@ use async_openai::types::chat::{
     ChatCompletionStreamOptions,
     ImageUrl,
     ImageDetail,
+    ChatCompletionTool,
+    ChatCompletionTools,
+    ChatCompletionToolChoiceOption,
+    ToolChoiceOptions,
+    ChatCompletionMessageToolCall,
+    ChatCompletionMessageToolCalls,
+    ChatCompletionMessageToolCallChunk,
+    ChatCompletionRequestToolMessageArgs,
+    CreateChatCompletionRequest,
+    FunctionCall,
 };
 use futures::StreamExt;
 use tauri::{ AppHandle, Emitter };
 use base64::Engine;
 
-use crate::{ mcp, paths, constants };
+use crate::{ mcp, paths, constants, storage, language, prompts, server_busy, session_titles, structured_output };
+use crate::cancellation::{ self, OperationKind };
+use crate::coalesce::TokenCoalescer;
+use crate::drafts::DraftWriter;
+use crate::structured_output::ChatResponseFormat;
+use crate::model_capabilities::ToolTransport;
 
-// Global state for managing streaming cancellation
-lazy_static::lazy_static! {
-    static ref ACTIVE_STREAMS: Arc<Mutex<HashMap<String, broadcast::Sender<()>>>> = Arc::new(Mutex::new(HashMap::new()));
-}
+/// Stream id of a chat without a saved session
+pub(crate) const TEMP_STREAM_ID: &str = "temp";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttachmentInfo {
@@ -54,6 +67,25 @@ pub struct ChatMessage {
     pub completion_tokens: Option<u32>,
     pub total_tokens: Option<u32>,
     pub attachments: Option<Vec<AttachmentInfo>>,
+    /// Always sent to the model, ahead of the conversation history
+    #[serde(default)]
+    pub pinned: bool,
+    /// How an assistant reply was produced; the frontend passes it on from `chat-metrics`
+    #[serde(default)]
+    pub generation: Option<GenerationMetrics>,
+    /// Sources of the `[n]` markers in a RAG reply; the frontend passes them on from `rag-citations`
+    #[serde(default)]
+    pub citations: Option<Vec<crate::rag::citations::Citation>>,
+}
+
+/// Model and timings of one generated reply
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationMetrics {
+    pub model: Option<String>,
+    /// From sending the request to the first streamed token
+    pub time_to_first_token_ms: Option<u64>,
+    /// From sending the request to the finished reply, tool rounds included
+    pub generation_time_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +96,125 @@ pub struct ChatSession {
     pub updated_at: i64,
     pub model_id: Option<String>,
     pub messages: Vec<ChatMessage>,
+    /// Overrides `language.preferred` from settings for this session
+    #[serde(default)]
+    pub preferred_language: Option<String>,
+    /// Tools the model may use in this session
+    #[serde(default)]
+    pub tool_policy: ToolPolicy,
+    /// Sampling options beyond temperature, top_p and seed
+    #[serde(default)]
+    pub sampling: SamplingOptions,
+    /// Id of the prompt preset used when a request brings no system prompt
+    #[serde(default)]
+    pub prompt_preset: Option<String>,
+    /// Bumped on every saved change; writers pass the revision they last saw
+    /// so a stale window cannot overwrite newer edits
+    #[serde(default)]
+    pub revision: u64,
+}
+
+/// Tools a session lets the model use. Entries are tool names as the model
+/// sees them (`builtin_render_chart`, `<server>_<tool>`); a trailing `*`
+/// matches a prefix, so `builtin_*` or `github_*` covers a whole server and
+/// `*` every tool.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolPolicy {
+    /// When not empty, only these tools are offered
+    pub allow: Vec<String>,
+    /// Never offered; wins over `allow`
+    pub deny: Vec<String>,
+}
+
+impl ToolPolicy {
+    fn matches(pattern: &str, tool_name: &str) -> bool {
+        match pattern.trim().strip_suffix('*') {
+            Some(prefix) => tool_name.starts_with(prefix),
+            None => pattern.trim() == tool_name,
+        }
+    }
+
+    pub fn permits(&self, tool_name: &str) -> bool {
+        let listed = |patterns: &[String]| patterns.iter().any(|pattern| Self::matches(pattern, tool_name));
+        !listed(&self.deny) && (self.allow.is_empty() || listed(&self.allow))
+    }
+}
+
+/// Sampling options a session keeps between requests; unset ones are left
+/// to the model's generation config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplingOptions {
+    pub top_k: Option<u32>,
+    pub repetition_penalty: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    /// Generation stops before any of these
+    pub stop: Vec<String>,
+}
+
+impl SamplingOptions {
+    /// Range-checked copy with empty stop sequences dropped
+    fn validated(self) -> Result<Self, String> {
+        if self.top_k == Some(0) {
+            return Err("top_k must be at least 1".to_string());
+        }
+        if let Some(penalty) = self.repetition_penalty.filter(|penalty| *penalty <= 0.0) {
+            return Err(format!("repetition_penalty must be above 0, got {}", penalty));
+        }
+        for (name, penalty) in [("frequency_penalty", self.frequency_penalty), ("presence_penalty", self.presence_penalty)] {
+            if let Some(penalty) = penalty.filter(|penalty| !(-2.0..=2.0).contains(penalty)) {
+                return Err(format!("{} must be between -2 and 2, got {}", name, penalty));
+            }
+        }
+        let stop: Vec<String> = self.stop.into_iter().filter(|stop| !stop.is_empty()).collect();
+        if stop.len() > constants::MAX_STOP_SEQUENCES {
+            return Err(format!("At most {} stop sequences are allowed", constants::MAX_STOP_SEQUENCES));
+        }
+        Ok(Self { stop, ..self })
+    }
+
+    /// `self` with the options set in `overrides` replacing its own
+    fn overridden_by(self, overrides: SamplingOptions) -> Self {
+        Self {
+            top_k: overrides.top_k.or(self.top_k),
+            repetition_penalty: overrides.repetition_penalty.or(self.repetition_penalty),
+            frequency_penalty: overrides.frequency_penalty.or(self.frequency_penalty),
+            presence_penalty: overrides.presence_penalty.or(self.presence_penalty),
+            stop: if overrides.stop.is_empty() { self.stop } else { overrides.stop },
+        }
+    }
+
+    fn apply(&self, request_builder: &mut CreateChatCompletionRequestArgs) {
+        if let Some(top_k) = self.top_k {
+            request_builder.top_k(top_k);
+        }
+        if let Some(repetition_penalty) = self.repetition_penalty {
+            request_builder.repetition_penalty(repetition_penalty);
+        }
+        if let Some(frequency_penalty) = self.frequency_penalty {
+            request_builder.frequency_penalty(frequency_penalty);
+        }
+        if let Some(presence_penalty) = self.presence_penalty {
+            request_builder.presence_penalty(presence_penalty);
+        }
+        if !self.stop.is_empty() {
+            request_builder.stop(self.stop.clone());
+        }
+    }
+}
+
+/// The session's prompt preset, rendered; `None` without one or when it was deleted
+fn preset_prompt(session: &ChatSession) -> Option<String> {
+    let id = session.prompt_preset.as_deref()?;
+    match prompts::find_preset(id) {
+        Some(preset) => Some(prompts::render(&preset)),
+        None => {
+            log_warning!("Session names a missing prompt preset", session_id = %session.id, preset = %id);
+            None
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -85,24 +236,21 @@ fn get_chat_sessions_path() -> Result<PathBuf, String> {
     paths::get_chat_sessions_path().map_err(|e| e.to_string())
 }
 
-fn load_chat_sessions() -> Result<ChatSessionsStorage, String> {
+pub(crate) async fn load_chat_sessions() -> Result<ChatSessionsStorage, String> {
     debug!("Loading chat sessions");
     let path = get_chat_sessions_path()?;
-    
-    info!(path = %path.display(), exists = path.exists(), "Checking chat sessions file");
-
-    if !path.exists() {
-        info!("Chat sessions file does not exist, returning empty storage");
-        return Ok(ChatSessionsStorage::default());
-    }
 
-    let contents = fs
-        ::read_to_string(&path)
+    let contents = storage::read_string(&path).await
         .map_err(|e| {
             error!(path = %path.display(), error = %e, "Failed to read chat sessions file");
             format!("Failed to read chat sessions file: {}", e)
         })?;
 
+    let Some(contents) = contents else {
+        info!("Chat sessions file does not exist, returning empty storage");
+        return Ok(ChatSessionsStorage::default());
+    };
+
     info!(path = %path.display(), size = contents.len(), "Chat sessions file read successfully");
 
     let result = serde_json
@@ -129,7 +277,73 @@ fn load_chat_sessions() -> Result<ChatSessionsStorage, String> {
     result
 }
 
-fn save_chat_sessions(storage: &ChatSessionsStorage) -> Result<(), String> {
+/// Serializes load-modify-save cycles on the sessions file, so concurrent
+/// commands (e.g. two windows) cannot drop each other's changes
+static SESSIONS_WRITE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Apply `f` to the stored sessions and save the result, holding the write lock
+pub(crate) async fn modify_sessions<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce(&mut ChatSessionsStorage) -> Result<T, String>,
+{
+    let _guard = SESSIONS_WRITE.lock().await;
+    let mut storage = load_chat_sessions().await?;
+    let result = f(&mut storage)?;
+    save_chat_sessions(&storage).await?;
+    Ok(result)
+}
+
+fn session_mut<'a>(storage: &'a mut ChatSessionsStorage, session_id: &str) -> Result<&'a mut ChatSession, String> {
+    storage.sessions
+        .get_mut(session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))
+}
+
+/// Reject a write based on an older revision than the stored one
+fn check_revision(session: &ChatSession, expected: Option<u64>) -> Result<(), String> {
+    match expected {
+        Some(expected) if expected != session.revision => {
+            tracing::warn!(session_id = %session.id, expected, current = session.revision, "Chat session revision conflict");
+            Err(format!(
+                "Conflict: chat session {} was changed elsewhere (revision {}, expected {}); reload it and retry",
+                session.id, session.revision, expected
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn touch(session: &mut ChatSession, now: i64) {
+    session.updated_at = now;
+    session.revision += 1;
+}
+
+/// Fold `incoming` into `existing`: messages are unioned by id in timestamp
+/// order, and the newer copy wins for title, model and language
+pub(crate) fn merge_sessions(existing: &mut ChatSession, incoming: ChatSession) {
+    let incoming_newer = incoming.updated_at > existing.updated_at;
+
+    for message in incoming.messages {
+        if !existing.messages.iter().any(|m| m.id == message.id) {
+            existing.messages.push(message);
+        }
+    }
+    existing.messages.sort_by_key(|m| m.timestamp);
+
+    if incoming_newer {
+        existing.title = incoming.title;
+        existing.model_id = incoming.model_id.or(existing.model_id.take());
+        existing.preferred_language = incoming.preferred_language;
+        existing.sampling = incoming.sampling;
+        existing.prompt_preset = incoming.prompt_preset;
+        existing.tool_policy = incoming.tool_policy;
+    }
+    existing.created_at = existing.created_at.min(incoming.created_at);
+    existing.updated_at = existing.updated_at.max(incoming.updated_at);
+    existing.revision = existing.revision.max(incoming.revision) + 1;
+}
+
+async fn save_chat_sessions(storage: &ChatSessionsStorage) -> Result<(), String> {
     debug!(session_count = storage.sessions.len(), "Saving chat sessions");
     let path = get_chat_sessions_path()?;
 
@@ -142,7 +356,7 @@ fn save_chat_sessions(storage: &ChatSessionsStorage) -> Result<(), String> {
             format!("Failed to serialize chat sessions: {}", e)
         })?;
 
-    fs::write(&path, &contents).map_err(|e| {
+    storage::write_string(&path, &contents).await.map_err(|e| {
         error!(path = %path.display(), error = %e, "Failed to write chat sessions file");
         format!("Failed to write chat sessions file: {}", e)
     })?;
@@ -197,7 +411,7 @@ fn generate_chat_title(content: &str) -> String {
 
 #[tauri::command]
 pub async fn get_chat_sessions() -> Result<ChatSessionsStorage, String> {
-    load_chat_sessions()
+    load_chat_sessions().await
 }
 
 #[tauri::command]
@@ -205,8 +419,6 @@ pub async fn create_chat_session(title: Option<String>) -> Result<ChatSession, S
     let session_title = title.clone().unwrap_or_else(|| constants::DEFAULT_CHAT_TITLE.to_string());
     log_operation_start!("Creating chat session", title = %session_title);
     
-    let mut storage = load_chat_sessions()?;
-
     let session_id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp_millis();
 
@@ -217,6 +429,11 @@ pub async fn create_chat_session(title: Option<String>) -> Result<ChatSession, S
         updated_at: now,
         model_id: None,
         messages: Vec::new(),
+        preferred_language: None,
+        tool_policy: ToolPolicy::default(),
+        sampling: SamplingOptions::default(),
+        prompt_preset: None,
+        revision: 0,
     };
 
     log_debug_details!(
@@ -225,10 +442,11 @@ pub async fn create_chat_session(title: Option<String>) -> Result<ChatSession, S
         "Chat session created"
     );
     
-    storage.sessions.insert(session_id.clone(), session.clone());
-    storage.active_session_id = Some(session_id.clone());
-
-    save_chat_sessions(&storage)?;
+    modify_sessions(|storage| {
+        storage.sessions.insert(session_id.clone(), session.clone());
+        storage.active_session_id = Some(session_id.clone());
+        Ok(())
+    }).await?;
     log_operation_success!("Chat session created", session_id = %session_id);
 
     Ok(session)
@@ -246,6 +464,11 @@ pub async fn create_temporary_chat_session(title: Option<String>) -> Result<Chat
         updated_at: now,
         model_id: None,
         messages: Vec::new(),
+        preferred_language: None,
+        tool_policy: ToolPolicy::default(),
+        sampling: SamplingOptions::default(),
+        prompt_preset: None,
+        revision: 0,
     };
 
     // Don't save to storage yet - this is a temporary session
@@ -256,66 +479,142 @@ pub async fn create_temporary_chat_session(title: Option<String>) -> Result<Chat
 pub async fn update_chat_session(
     session_id: String,
     title: Option<String>,
-    model_id: Option<String>
+    model_id: Option<String>,
+    expected_revision: Option<u64>
 ) -> Result<ChatSession, String> {
-    let mut storage = load_chat_sessions()?;
+    modify_sessions(|storage| {
+        let session = session_mut(storage, &session_id)?;
+        check_revision(session, expected_revision)?;
 
-    let session = storage.sessions
-        .get_mut(&session_id)
-        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+        if let Some(new_title) = title {
+            session.title = new_title;
+        }
 
-    if let Some(new_title) = title {
-        session.title = new_title;
-    }
+        if let Some(new_model_id) = model_id {
+            session.model_id = Some(new_model_id);
+        }
 
-    if let Some(new_model_id) = model_id {
-        session.model_id = Some(new_model_id);
-    }
+        touch(session, chrono::Utc::now().timestamp_millis());
+        Ok(session.clone())
+    }).await
+}
 
-    session.updated_at = chrono::Utc::now().timestamp_millis();
+/// Set or clear (with `None`) the reply language of a session
+#[tauri::command]
+pub async fn set_session_language(
+    session_id: String,
+    language: Option<String>,
+    expected_revision: Option<u64>
+) -> Result<ChatSession, String> {
+    modify_sessions(|storage| {
+        let session = session_mut(storage, &session_id)?;
+        check_revision(session, expected_revision)?;
+
+        session.preferred_language = language
+            .map(|language| language.trim().to_string())
+            .filter(|language| !language.is_empty());
+        touch(session, chrono::Utc::now().timestamp_millis());
+        Ok(session.clone())
+    }).await
+}
 
-    let updated_session = session.clone();
-    save_chat_sessions(&storage)?;
+/// Limit the tools the model may use in a session
+#[tauri::command]
+pub async fn set_session_tool_policy(
+    session_id: String,
+    policy: ToolPolicy,
+    expected_revision: Option<u64>
+) -> Result<ChatSession, String> {
+    modify_sessions(|storage| {
+        let session = session_mut(storage, &session_id)?;
+        check_revision(session, expected_revision)?;
 
-    Ok(updated_session)
+        session.tool_policy = ToolPolicy {
+            allow: policy.allow.into_iter().filter(|name| !name.trim().is_empty()).collect(),
+            deny: policy.deny.into_iter().filter(|name| !name.trim().is_empty()).collect(),
+        };
+        touch(session, chrono::Utc::now().timestamp_millis());
+        Ok(session.clone())
+    }).await
 }
 
+/// Set the sampling options a session's requests use
 #[tauri::command]
-pub async fn delete_chat_session(session_id: String) -> Result<String, String> {
-    let mut storage = load_chat_sessions()?;
+pub async fn set_session_sampling(
+    session_id: String,
+    sampling: SamplingOptions,
+    expected_revision: Option<u64>
+) -> Result<ChatSession, String> {
+    let sampling = sampling.validated()?;
+    modify_sessions(|storage| {
+        let session = session_mut(storage, &session_id)?;
+        check_revision(session, expected_revision)?;
+
+        session.sampling = sampling;
+        touch(session, chrono::Utc::now().timestamp_millis());
+        Ok(session.clone())
+    }).await
+}
 
-    if !storage.sessions.contains_key(&session_id) {
-        return Err(format!("Chat session not found: {}", session_id));
+/// Set or clear (with `None`) the prompt preset a session defaults to
+#[tauri::command]
+pub async fn set_session_prompt_preset(
+    session_id: String,
+    preset_id: Option<String>,
+    expected_revision: Option<u64>
+) -> Result<ChatSession, String> {
+    if let Some(id) = preset_id.as_deref() {
+        prompts::find_preset(id).ok_or_else(|| format!("Prompt preset not found: {}", id))?;
     }
+    modify_sessions(|storage| {
+        let session = session_mut(storage, &session_id)?;
+        check_revision(session, expected_revision)?;
+
+        session.prompt_preset = preset_id;
+        touch(session, chrono::Utc::now().timestamp_millis());
+        Ok(session.clone())
+    }).await
+}
+
+#[tauri::command]
+pub async fn delete_chat_session(session_id: String) -> Result<String, String> {
+    modify_sessions(|storage| {
+        if storage.sessions.remove(&session_id).is_none() {
+            return Err(format!("Chat session not found: {}", session_id));
+        }
 
-    storage.sessions.remove(&session_id);
+        // If this was the active session, clear it
+        if storage.active_session_id.as_ref() == Some(&session_id) {
+            storage.active_session_id = None;
+        }
+        Ok(())
+    }).await?;
+    crate::rag::sessions::forget_session(&session_id).await;
 
-    // If this was the active session, clear it
-    if storage.active_session_id.as_ref() == Some(&session_id) {
-        storage.active_session_id = None;
+    if let Ok(assets) = crate::paths::get_session_assets_dir(Some(&session_id)) {
+        let _ = tokio::fs::remove_dir_all(assets).await;
     }
 
-    save_chat_sessions(&storage)?;
-
     Ok(format!("Chat session deleted: {}", session_id))
 }
 
 #[tauri::command]
 pub async fn set_active_chat_session(session_id: String) -> Result<String, String> {
-    let mut storage = load_chat_sessions()?;
-
-    if !storage.sessions.contains_key(&session_id) {
-        return Err(format!("Chat session not found: {}", session_id));
-    }
+    modify_sessions(|storage| {
+        if !storage.sessions.contains_key(&session_id) {
+            return Err(format!("Chat session not found: {}", session_id));
+        }
 
-    storage.active_session_id = Some(session_id.clone());
-    save_chat_sessions(&storage)?;
+        storage.active_session_id = Some(session_id.clone());
+        Ok(())
+    }).await?;
 
     Ok(session_id)
 }
 
 #[tauri::command]
 pub async fn add_message_to_session(
+    app: AppHandle,
     session_id: String,
     role: String,
     content: String,
@@ -324,7 +623,9 @@ pub async fn add_message_to_session(
     prompt_tokens: Option<u32>,
     completion_tokens: Option<u32>,
     total_tokens: Option<u32>,
-    attachments: Option<Vec<AttachmentInfo>>
+    attachments: Option<Vec<AttachmentInfo>>,
+    generation: Option<GenerationMetrics>,
+    citations: Option<Vec<crate::rag::citations::Citation>>
 ) -> Result<ChatMessage, String> {
     tracing::debug!(
         session_id = %session_id,
@@ -335,15 +636,6 @@ pub async fn add_message_to_session(
         "Adding message to session"
     );
     
-    let mut storage = load_chat_sessions()?;
-
-    let session = storage.sessions
-        .get_mut(&session_id)
-        .ok_or_else(|| {
-            log_operation_error!("Add message to session", "Session not found", session_id = %session_id);
-            format!("Chat session not found: {}", session_id)
-        })?;
-
     let message_id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp_millis();
 
@@ -358,29 +650,61 @@ pub async fn add_message_to_session(
         completion_tokens,
         total_tokens,
         attachments,
+        pinned: false,
+        generation,
+        citations,
     };
 
-    session.messages.push(message.clone());
-    session.updated_at = now;
+    // Appends never conflict: the write lock orders them, and each re-reads the file
+    let (auto_generated_title, message_count, first_exchange) = modify_sessions(|storage| {
+        let session = storage.sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| {
+                log_operation_error!("Add message to session", "Session not found", session_id = %session_id);
+                format!("Chat session not found: {}", session_id)
+            })?;
+
+        session.messages.push(message.clone());
+        touch(session, now);
+
+        // Auto-generate title from first user message if still "New Chat"
+        let auto_generated_title = if session.title == "New Chat" && role == "user" {
+            let title = generate_chat_title(&content);
+            tracing::debug!(
+                session_id = %session_id,
+                old_title = "New Chat",
+                new_title = %title,
+                "Auto-generated session title"
+            );
+            session.title = title.clone();
+            Some(title)
+        } else {
+            None
+        };
 
-    // Auto-generate title from first user message if still "New Chat"
-    let auto_generated_title = if session.title == "New Chat" && role == "user" {
-        let title = generate_chat_title(&content);
-        tracing::debug!(
-            session_id = %session_id,
-            old_title = "New Chat",
-            new_title = %title,
-            "Auto-generated session title"
-        );
-        session.title = title.clone();
-        Some(title)
-    } else {
-        None
-    };
+        // The first reply completes the first exchange, which the model may title
+        let first_exchange = if role == "assistant" && session.messages.iter().filter(|m| m.role == "assistant").count() == 1 {
+            session.messages.iter().find(|m| m.role == "user").map(|user| session_titles::FirstExchange {
+                session_id: session_id.clone(),
+                auto_title: session.title.clone(),
+                user: user.content.clone(),
+                assistant: content.clone(),
+            })
+        } else {
+            None
+        };
 
-    let message_count = session.messages.len();
+        Ok((auto_generated_title, session.messages.len(), first_exchange))
+    }).await?;
+
+    if let Some(exchange) = first_exchange {
+        // Only sessions still named after their first message are retitled
+        let auto_named = exchange.auto_title == generate_chat_title(&exchange.user);
+        if auto_named && crate::settings::current().chat.llm_titles {
+            session_titles::spawn(app, exchange);
+        }
+    }
 
-    save_chat_sessions(&storage)?;
     info!(
         session_id = %session_id,
         message_id = %message_id,
@@ -393,16 +717,49 @@ pub async fn add_message_to_session(
     Ok(message)
 }
 
-#[tauri::command]
-pub async fn persist_temporary_session(session: ChatSession) -> Result<ChatSession, String> {
-    let mut storage = load_chat_sessions()?;
+/// Append an assistant message produced outside the frontend (e.g. a restored draft)
+pub(crate) async fn append_assistant_message(session_id: &str, content: String, timestamp: i64) -> Result<(), String> {
+    let message = ChatMessage {
+        id: Uuid::new_v4().to_string(),
+        role: "assistant".to_string(),
+        content,
+        timestamp,
+        tokens_per_second: None,
+        is_error: None,
+        prompt_tokens: None,
+        completion_tokens: None,
+        total_tokens: None,
+        attachments: None,
+        pinned: false,
+        generation: None,
+        citations: None,
+    };
 
-    storage.sessions.insert(session.id.clone(), session.clone());
-    storage.active_session_id = Some(session.id.clone());
+    modify_sessions(|storage| {
+        let session = session_mut(storage, session_id)?;
+        let now = timestamp.max(session.updated_at);
+        session.messages.push(message);
+        touch(session, now);
+        Ok(())
+    }).await
+}
 
-    save_chat_sessions(&storage)?;
+#[tauri::command]
+pub async fn persist_temporary_session(session: ChatSession) -> Result<ChatSession, String> {
+    modify_sessions(|storage| {
+        storage.active_session_id = Some(session.id.clone());
+
+        // Persisting the same session twice (e.g. from two windows) merges
+        // instead of replacing what the first call stored
+        if let Some(existing) = storage.sessions.get_mut(&session.id) {
+            tracing::info!(session_id = %session.id, "Session already persisted, merging");
+            merge_sessions(existing, session);
+            return Ok(existing.clone());
+        }
 
-    Ok(session)
+        storage.sessions.insert(session.id.clone(), session.clone());
+        Ok(session)
+    }).await
 }
 
 #[tauri::command]
@@ -415,7 +772,9 @@ pub async fn add_message_to_temporary_session(
     prompt_tokens: Option<u32>,
     completion_tokens: Option<u32>,
     total_tokens: Option<u32>,
-    attachments: Option<Vec<AttachmentInfo>>
+    attachments: Option<Vec<AttachmentInfo>>,
+    generation: Option<GenerationMetrics>,
+    citations: Option<Vec<crate::rag::citations::Citation>>
 ) -> Result<(ChatSession, ChatMessage), String> {
     let message_id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp_millis();
@@ -431,6 +790,9 @@ pub async fn add_message_to_temporary_session(
         completion_tokens,
         total_tokens,
         attachments,
+        pinned: false,
+        generation,
+        citations,
     };
 
     session.messages.push(message.clone());
@@ -447,7 +809,7 @@ pub async fn add_message_to_temporary_session(
 
 #[tauri::command]
 pub async fn get_session_messages(session_id: String) -> Result<Vec<ChatMessage>, String> {
-    let storage = load_chat_sessions()?;
+    let storage = load_chat_sessions().await?;
 
     let session = storage.sessions
         .get(&session_id)
@@ -456,15 +818,55 @@ pub async fn get_session_messages(session_id: String) -> Result<Vec<ChatMessage>
     Ok(session.messages.clone())
 }
 
+/// Pin or unpin a message; pinned messages stay in the prompt for the whole chat
+#[tauri::command]
+pub async fn pin_message(session_id: String, message_id: String, pinned: bool) -> Result<ChatMessage, String> {
+    modify_sessions(|storage| {
+        let session = session_mut(storage, &session_id)?;
+        let message = session.messages
+            .iter_mut()
+            .find(|m| m.id == message_id)
+            .ok_or_else(|| format!("Message not found: {}", message_id))?;
+        message.pinned = pinned;
+        let message = message.clone();
+
+        touch(session, chrono::Utc::now().timestamp_millis());
+        Ok(message)
+    }).await
+}
+
+#[tauri::command]
+pub async fn get_pinned_messages(session_id: String) -> Result<Vec<ChatMessage>, String> {
+    let storage = load_chat_sessions().await?;
+
+    let session = storage.sessions
+        .get(&session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+
+    Ok(pinned_messages(session))
+}
+
+fn pinned_messages(session: &ChatSession) -> Vec<ChatMessage> {
+    session.messages.iter().filter(|m| m.pinned).cloned().collect()
+}
+
+/// System prompt section quoting the pinned messages
+fn pinned_context(pinned: &[ChatMessage]) -> String {
+    if pinned.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("\n\nPinned messages from this conversation (keep them in mind throughout):");
+    for message in pinned {
+        section.push_str(&format!("\n[{}] {}", message.role, strip_tool_xml_tags(&message.content)));
+    }
+    section
+}
+
 #[tauri::command]
-pub async fn stop_chat_streaming(session_id: String) -> Result<String, String> {
+pub async fn stop_chat_streaming(app: AppHandle, session_id: String) -> Result<String, String> {
     info!(session_id = %session_id, "Attempting to stop chat streaming");
-    
-    let mut streams = ACTIVE_STREAMS.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
-    
-    if let Some(sender) = streams.remove(&session_id) {
-        // Send cancellation signal
-        let _ = sender.send(());
+
+    if cancellation::cancel(&app, &session_id).is_ok() {
         info!(session_id = %session_id, "Streaming cancellation signal sent");
         Ok(format!("Streaming stopped for session: {}", session_id))
     } else {
@@ -475,7 +877,7 @@ pub async fn stop_chat_streaming(session_id: String) -> Result<String, String> {
 
 #[tauri::command]
 pub async fn get_conversation_history(session_id: String) -> Result<Vec<ChatMessage>, String> {
-    let storage = load_chat_sessions()?;
+    let storage = load_chat_sessions().await?;
 
     let session = storage.sessions
         .get(&session_id)
@@ -491,11 +893,11 @@ pub async fn get_conversation_history(session_id: String) -> Result<Vec<ChatMess
     Ok(messages)
 }
 
-// Chat with the currently loaded model using streaming
+// Chat with a loaded model using streaming; the default model when `model_name` is unset
 #[tauri::command]
 pub async fn chat_with_loaded_model_streaming(
     app: AppHandle,
-    model_name: String,
+    model_name: Option<String>,
     message: String,
     session_id: Option<String>,
     include_history: Option<bool>,
@@ -505,79 +907,76 @@ pub async fn chat_with_loaded_model_streaming(
     seed: Option<i64>,
     max_tokens: Option<u32>,
     max_completion_tokens: Option<u32>,
-    attachments: Option<Vec<AttachmentInfo>>
+    sampling: Option<SamplingOptions>,
+    attachments: Option<Vec<AttachmentInfo>>,
+    response_format: Option<ChatResponseFormat>
 ) -> Result<String, String> {
+    // OVMS may have been deferred at launch; the first chat brings it up
+    crate::ensure_ovms_initialized(&app).await;
+    let model_name = crate::ovms::route_model(&app, model_name).await?;
+
+    // Unknown capabilities (e.g. no metadata) keep the old behavior
+    let capabilities = crate::model_capabilities::capabilities(&model_name).await.ok();
+    if let Some(capabilities) = &capabilities {
+        if !capabilities.supports_chat && capabilities.model_type.is_some() {
+            return Err(format!("Model '{}' is not a chat model", model_name));
+        }
+        let has_images = attachments.as_ref().is_some_and(|list| list.iter().any(|a| a.is_image));
+        if has_images && !capabilities.supports_vision {
+            return Err(format!("Model '{}' does not accept images; load a vision model to chat about them", model_name));
+        }
+    }
+    // Without capabilities, tools go in the prompt as they always have
+    let tool_transport = capabilities.as_ref().map_or(ToolTransport::Prompt, |c| c.tool_transport());
+
     let config = OpenAIConfig::new()
         .with_api_key("unused")
-        .with_api_base("http://localhost:1114/v3");
+        .with_api_base(crate::ovms::openai_api_base());
     let client = Client::with_config(config);
 
+    let stored_session = match &session_id {
+        Some(id) => load_chat_sessions().await
+            .ok()
+            .and_then(|mut storage| storage.sessions.remove(id)),
+        None => None,
+    };
+    let tool_policy = stored_session.as_ref().map(|s| s.tool_policy.clone()).unwrap_or_default();
+    // Options passed with the request win over the ones saved with the session
+    let sampling = stored_session.as_ref()
+        .map(|s| s.sampling.clone())
+        .unwrap_or_default()
+        .overridden_by(sampling.map(SamplingOptions::validated).transpose()?.unwrap_or_default());
+
     // Get MCP tools info for system message
-    let mcp_tools = match mcp::get_all_mcp_tools_for_chat(app.clone()).await {
-        Ok(tools) => {
-            tracing::debug!(count = tools.len(), "Loaded MCP tools for chat");
-            if tools.is_empty() {
-                log_warning!("No MCP tools available", note = "LLM will not have access to any tools");
-            } else {
-                tracing::debug!(tools = ?tools.iter().map(|t| &t.function.name).collect::<Vec<_>>(), "Available MCP tools");
+    let mut mcp_tools = if tool_transport == ToolTransport::None {
+        tracing::debug!(model = %model_name, "Model does not take tools, chatting without them");
+        Vec::new()
+    } else {
+        match mcp::get_all_mcp_tools_for_chat(app.clone()).await {
+            Ok(tools) => {
+                tracing::debug!(count = tools.len(), "Loaded MCP tools for chat");
+                if tools.is_empty() {
+                    log_warning!("No MCP tools available", note = "LLM will not have access to any tools");
+                } else {
+                    tracing::debug!(tools = ?tools.iter().map(|t| &t.function.name).collect::<Vec<_>>(), "Available MCP tools");
+                }
+                tools
+            }
+            Err(e) => {
+                log_warning!("Failed to load MCP tools", error = %e);
+                Vec::new()
             }
-            tools
-        }
-        Err(e) => {
-            log_warning!("Failed to load MCP tools", error = %e);
-            Vec::new()
         }
     };
 
-    let tools_info = if !mcp_tools.is_empty() {
-        tracing::debug!("Processing MCP tools for system message...");
-
-        // Generate tool descriptions in simple text format for the custom template
-        let tool_descs: Vec<String> = mcp_tools
-            .iter()
-            .enumerate()
-            .map(|(i, tool)| {
-                tracing::trace!(index = i, name = %tool.function.name, "Processing tool");
-                let params_str = match &tool.function.parameters {
-                    Some(params) => serde_json::to_string_pretty(params).unwrap_or_default(),
-                    None => "{}".to_string(),
-                };
-
-                format!(
-                    "{}({}) - {}",
-                    tool.function.name,
-                    params_str,
-                    tool.function.description.as_ref().unwrap_or(&"".to_string())
-                )
-            })
-            .collect();
-
-        let tool_descs_text = tool_descs.join("\n");
-        let formatted_tools =
-            format!(r#"
-
-# Tools
-
-You may call one or more functions to assist with the user query.
-
-You are provided with function signatures within <tools></tools> XML tags:
-<tools>
-{}
-</tools>
-
-For each function call, return a json object with function name and arguments within <tool_call></tool_call> XML tags:
-<tool_call>
-{{"name": <function-name>, "arguments": <args-json-object>}}
-</tool_call>"#, tool_descs_text);
+    // Tools this session turned off are never offered
+    mcp_tools.retain(|tool| tool_policy.permits(&tool.function.name));
 
-        tracing::debug!(length = formatted_tools.len(), "Generated custom tool template");
-        formatted_tools
-    } else {
-        tracing::trace!("No MCP tools available for system message");
-        "".to_string()
-    };
+    let native_tools = tool_transport == ToolTransport::Native && !mcp_tools.is_empty();
+    let tools_info = if native_tools { String::new() } else { format_tools_prompt(&mcp_tools) };
 
-    let base_system_message = system_prompt.unwrap_or_else(|| {
+    // A request's own system prompt wins over the session's preset
+    let base_system_message = system_prompt.or_else(|| stored_session.as_ref().and_then(preset_prompt)).unwrap_or_else(|| {
         "You are a helpful AI assistant with access to various functions/tools.
 
         Tool Usage Guidelines:
@@ -590,8 +989,20 @@ For each function call, return a json object with function name and arguments wi
         When a tool would be helpful, use it. Otherwise, respond conversationally.".to_string()
     });
 
-    // Always append tools info to system message (whether custom or default)
-    let system_message = format!("{}{}", base_system_message, tools_info);
+    let session_language = stored_session.as_ref().and_then(|s| s.preferred_language.clone());
+    let pinned = stored_session.as_ref().map(pinned_messages).unwrap_or_default();
+    let reply_language = language::preferred_language(session_language.as_deref());
+    let language_instruction = reply_language.as_deref().map(language::system_instruction).unwrap_or_default();
+
+    // Always append tools info to system message (whether custom or default).
+    // Pinned messages go here too, so they stay in scope however long the history
+    let system_message = format!(
+        "{}{}{}{}",
+        base_system_message,
+        language_instruction,
+        pinned_context(&pinned),
+        tools_info
+    );
 
     tracing::debug!(
         length = system_message.len(),
@@ -627,7 +1038,8 @@ For each function call, return a json object with function name and arguments wi
                     "Including conversation history"
                 );
 
-                for msg in history {
+                // Pinned messages are already in the system message
+                for msg in history.into_iter().filter(|msg| !msg.pinned) {
                     match msg.role.as_str() {
                         "user" => {
                             messages.push(
@@ -786,66 +1198,21 @@ For each function call, return a json object with function name and arguments wi
         request_builder.max_completion_tokens(max_completion_tokens);
     }
 
-    // Commented out: Add MCP tools using modern tools format
-    /*
-    match mcp::get_all_mcp_tools_for_chat(app.clone()).await {
-        Ok(mcp_tools) => {
-            if !mcp_tools.is_empty() {
-                let mcp_info = format!("Adding {} MCP tools to chat completion", mcp_tools.len());
-                debug!("{}", mcp_info);
-
-                // Log each tool for debugging
-                for (i, tool) in mcp_tools.iter().enumerate() {
-                    let tool_info = format!(
-                        "Tool {}: {} - {}",
-                        i,
-                        &tool.function.name,
-                        tool.function.description.as_ref().unwrap_or(&"No description".to_string())
-                    );
-                    debug!("{}", tool_info);
-                }
-
-                debug!("Using modern 'tools' format...");
-                request_builder.tools(mcp_tools.clone());
-
-                // Determine tool choice based on message content
-                let message_lower = message.to_lowercase();
-                let forced_tool = if message_lower.contains("time") || message_lower.contains("current") {
-                    mcp_tools.iter().find(|tool| tool.function.name.contains("time_get_current_time"))
-                } else if message_lower.contains("convert") && message_lower.contains("time") {
-                    mcp_tools.iter().find(|tool| tool.function.name.contains("time_convert_time"))
-                } else {
-                    None
-                };
+    sampling.apply(&mut request_builder);
 
-                if let Some(tool) = forced_tool {
-                    debug!("Forcing specific tool call: {}", tool.function.name);
-                    
-                    let specific_choice = ChatCompletionNamedToolChoice {
-                        r#type: ChatCompletionToolType::Function,
-                        function: FunctionName {
-                            name: tool.function.name.clone(),
-                        },
-                    };
-                    request_builder.tool_choice(ChatCompletionToolChoiceOption::Named(specific_choice));
-                } else {
-                    debug!("Using auto tool choice (no specific match found)");
-                    request_builder.tool_choice(ChatCompletionToolChoiceOption::Auto);
-                }
-            } else {
-                debug!("No MCP tools available");
-            }
-        }
-        Err(e) => {
-            let mcp_error = format!("Failed to get MCP tools: {}", e);
-            warn!("{}", mcp_error);
-            // Continue without tools
-        }
+    if let Some(format) = &response_format {
+        request_builder.response_format(format.to_request());
     }
-    */
 
-    // Tools info is now in system message instead
-    tracing::debug!("Tools info included in system message instead of request tools array");
+    // The graph's tool parser turns the model's calls into `tool_calls` deltas
+    if native_tools {
+        tracing::debug!(count = mcp_tools.len(), "Passing tools in the request");
+        request_builder
+            .tools(mcp_tools.iter().cloned().map(ChatCompletionTools::Function).collect::<Vec<_>>())
+            .tool_choice(ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::Auto));
+    } else if !tools_info.is_empty() {
+        tracing::debug!(count = mcp_tools.len(), "Tools described in the system message");
+    }
 
     let request = request_builder
         .build()
@@ -854,68 +1221,42 @@ For each function call, return a json object with function name and arguments wi
             format!("Failed to build chat request: {}", e)
         })?;
 
-    // Check system message for tools info (since tools are now in system message)
-    if let Ok(request_value) = serde_json::to_value(&request) {
-        if let Some(messages) = request_value.get("messages") {
-            if let Some(messages_array) = messages.as_array() {
-                if let Some(system_msg) = messages_array.get(0) {
-                    if let Some(content) = system_msg.get("content") {
-                        if let Some(content_str) = content.as_str() {
-                            if
-                                content_str.contains("<tools>") ||
-                                content_str.contains("Available functions:")
-                            {
-                                tracing::trace!("Tools info found in system message");
-                            } else {
-                                tracing::trace!("No tools info found in system message");
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Verify no tools array in request (should be commented out)
-        if request_value.get("tools").is_some() {
-            log_warning!("Tools array still present in request", note = "should be in system message");
-        } else {
-            tracing::trace!("Confirmed: No tools array in request (as expected)");
-        }
-    }
+    // Register this stream for cancellation; tool rounds stay cancellable too
+    let stream_id = stream_key(session_id.as_deref()).to_string();
+    let operation = cancellation::register(&app, &stream_id, OperationKind::Chat);
 
-    let mut stream = client
-        .chat()
-        .create_stream(request).await
+    // Timings include any wait while OVMS is busy, as the user sees it
+    let started = Instant::now();
+    let mut first_token_after: Option<Duration> = None;
+    let mut stream = server_busy::open_stream(&app, &client, request, &stream_id, OperationKind::Chat, &operation.token()).await
         .map_err(|e| {
             log_operation_error!("Create chat stream", &e);
             format!("Failed to create chat stream: {}", e)
         })?;
 
-    // Setup cancellation channel
-    let (cancel_tx, mut cancel_rx) = broadcast::channel::<()>(1);
-    let stream_id = session_id.clone().unwrap_or_else(|| "temp".to_string());
-    
-    // Register this stream for cancellation
-    {
-        let mut streams = ACTIVE_STREAMS.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
-        streams.insert(stream_id.clone(), cancel_tx);
-    }
-
     let mut full_response = String::new();
+    let mut first_round = RoundOutput::default();
     let mut executed_tools = std::collections::HashSet::new();
-    let mut needs_continuation = false;
     let mut usage_data: Option<(u32, u32, u32)> = None; // (prompt_tokens, completion_tokens, total_tokens)
     let mut was_cancelled = false;
+    let mut coalescer = TokenCoalescer::from_settings();
+    let mut draft = DraftWriter::new(session_id.as_deref(), &model_name);
 
     // Process streaming responses with function call support
     loop {
         tokio::select! {
             // Check for cancellation signal
-            _ = cancel_rx.recv() => {
+            _ = operation.cancelled() => {
                 info!(stream_id = %stream_id, "Stream cancelled by user");
                 was_cancelled = true;
                 break;
             }
+            // Release coalesced text the model is slow to finish
+            _ = tokio::time::sleep_until(coalescer.deadline()), if coalescer.has_pending() => {
+                if let Some(chunk) = coalescer.flush() {
+                    emit_chat_token(&app, &stream_id, &chunk);
+                }
+            }
             // Process next stream item
             result = stream.next() => {
                 match result {
@@ -951,19 +1292,20 @@ For each function call, return a json object with function name and arguments wi
 
                     // Handle content and look for <tool_call> XML tags
                     if let Some(content) = &chat_choice.delta.content {
+                        if first_token_after.is_none() && !content.is_empty() {
+                            first_token_after = Some(started.elapsed());
+                        }
                         full_response.push_str(content);
+                        first_round.text.push_str(content);
+                        draft.checkpoint(&full_response).await;
 
                         // Emit streaming content to frontend (including XML tags)
-                        let _ = app.emit(
-                            "chat-token",
-                            serde_json::json!({
-                                "token": content,
-                                "finished": false
-                            })
-                        );
+                        if let Some(chunk) = coalescer.push(content) {
+                            emit_chat_token(&app, &stream_id, &chunk);
+                        }
 
                         // Process any complete tool calls found in the response so far
-                        let tool_calls = extract_all_tool_calls_from_xml(&full_response);
+                        let tool_calls = extract_all_tool_calls_from_xml(&first_round.text);
 
                         for (fn_name, fn_args) in tool_calls {
                             // Skip if we already executed this exact tool call
@@ -974,80 +1316,25 @@ For each function call, return a json object with function name and arguments wi
 
                             executed_tools.insert(tool_signature);
 
+                            // Text before the tool call reaches the frontend first
+                            if let Some(chunk) = coalescer.flush() {
+                                emit_chat_token(&app, &stream_id, &chunk);
+                            }
+
                             tracing::debug!(name = %fn_name, args = %fn_args, "Found tool call");
 
-                            // Parse arguments as JSON for MCP tool call
-                            let args_map = match
-                                serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
-                                    &fn_args
-                                )
-                            {
-                                Ok(mut map) => {
-                                    // Remove null values as MCP tools don't handle them well
-                                    map.retain(|_k, v| !v.is_null());
-                                    Some(map)
-                                }
-                                Err(e) => {
-                                    log_warning!("Failed to parse tool arguments", error = %e, args = %fn_args);
-                                    None
-                                }
-                            };
-
-                            // Call the MCP tool
-                            match mcp::call_mcp_tool(app.clone(), fn_name.clone(), args_map).await {
-                                Ok(tool_result) => {
-                                    tracing::debug!(tool = %fn_name, result_length = tool_result.len(), "Tool execution completed");
-                                    tracing::trace!(result = %tool_result, "Tool result content");
-
-                                    // Emit function call result to frontend
-                                    let _ = app.emit(
-                                        "tool-call",
-                                        serde_json::json!({
-                                            "tool_name": fn_name,
-                                            "arguments": fn_args,
-                                            "result": tool_result
-                                        })
-                                    );
-
-                                    // Add tool response in Qwen-Agent format and emit to frontend
-                                    let tool_response_text =
-                                        format!("\n<tool_response>\n{}\n</tool_response>", tool_result);
-                                    full_response.push_str(&tool_response_text);
-
-                                    // Emit tool response as streaming content (including XML tags)
-                                    let _ = app.emit(
-                                        "chat-token",
-                                        serde_json::json!({
-                                            "token": tool_response_text,
-                                            "finished": false
-                                        })
-                                    );
-
-                                    // Mark that we need to continue the conversation after tool execution
-                                    needs_continuation = true;
-                                }
-                                Err(e) => {
-                                    log_operation_error!("Tool execution", &e, tool = %fn_name);
-                                    let error_response_text =
-                                        format!("\n<tool_response>\nError: {}\n</tool_response>", e);
-                                    full_response.push_str(&error_response_text);
-
-                                    // Emit error response as streaming content (including XML tags)
-                                    let _ = app.emit(
-                                        "chat-token",
-                                        serde_json::json!({
-                                            "token": error_response_text,
-                                            "finished": false
-                                        })
-                                    );
-
-                                    // Mark that we need to continue the conversation even after tool error
-                                    needs_continuation = true;
-                                }
-                            }
+                            // Run it right away; the results go back to the model once the reply ends
+                            let record = run_xml_tool_call(&app, session_id.as_deref(), &tool_policy, fn_name, fn_args, &mut full_response).await;
+                            first_round.xml_records.push(record);
                         }
                     }
 
+                    // Native tool calls arrive in pieces, keyed by index
+                    if let Some(chunks) = &chat_choice.delta.tool_calls {
+                        first_token_after.get_or_insert_with(|| started.elapsed());
+                        merge_tool_call_chunks(&mut first_round.native_calls, chunks);
+                    }
+
                     // Handle finish reason
                     if let Some(_finish_reason) = &chat_choice.finish_reason {
                         tracing::debug!(reason = ?_finish_reason, "Stream finished");
@@ -1064,6 +1351,7 @@ For each function call, return a json object with function name and arguments wi
                             let _ = app.emit(
                                 "chat-error",
                                 serde_json::json!({
+                                    "stream_id": stream_id,
                                     "error": format!("Stream error: {}", err)
                                 })
                             );
@@ -1075,69 +1363,149 @@ For each function call, return a json object with function name and arguments wi
         }
     }
 
-    // Cleanup: Remove this stream from active streams
-    {
-        let mut streams = ACTIVE_STREAMS.lock().map_err(|e| format!("Failed to acquire lock: {}", e))?;
-        streams.remove(&stream_id);
-    }
-
-    // Continue the conversation if we executed tools and got JSON responses
-    if needs_continuation {
-        tracing::trace!("Checking if continuation needed after tool execution...");
-
-        // Check if the tool responses contain JSON structures that need interpretation
-        let should_continue = check_if_continuation_needed(&full_response);
-
-        if should_continue {
-            tracing::debug!("Tool response contains JSON - continuing conversation...");
-
-            match
-                continue_conversation_after_tools(
-                    app.clone(),
-                    &client,
-                    &system_message,
-                    &messages,
-                    full_response.clone(),
-                    &model_name,
-                    temperature,
-                    top_p,
-                    seed,
-                    max_tokens,
-                    max_completion_tokens
-                ).await
-            {
-                Ok(continued_response) => {
-                    if !continued_response.trim().is_empty() {
-                        // Append the continued response (streaming is already handled by continue_conversation_after_tools)
-                        full_response.push_str(&continued_response);
+    if let Some(chunk) = coalescer.flush() {
+        emit_chat_token(&app, &stream_id, &chunk);
+    }
+
+    // Feed tool results back until the model answers without calling tools
+    first_round.native_calls.retain(|call| !call.name.is_empty());
+    if !was_cancelled && first_round.has_tool_calls() {
+        for call in &first_round.native_calls {
+            executed_tools.insert(format!("{}:{}", call.name, call.arguments));
+        }
+        let rounds = ToolRounds {
+            app: &app,
+            client: &client,
+            model_name: &model_name,
+            session_id: session_id.as_deref(),
+            tool_policy: &tool_policy,
+            params: SamplingParams { temperature, top_p, seed, max_tokens, max_completion_tokens, options: sampling },
+            response_format: response_format.as_ref(),
+            native_tools: if native_tools { mcp_tools.clone() } else { Vec::new() },
+            max_rounds: crate::settings::current().chat.max_tool_rounds.clamp(1, constants::MAX_CHAT_TOOL_ROUNDS),
+            cancel: operation.token(),
+        };
+        if let Err(e) = rounds.run(messages.clone(), first_round, &mut full_response).await {
+            error!("Failed to continue conversation: {}", e);
+            let error_msg = format!("\n\n[Continuation Error: {}]", e);
+            full_response.push_str(&error_msg);
+            emit_chat_token(&app, &stream_id, &error_msg);
+        }
+        was_cancelled = operation.is_cancelled();
+    }
+
+    // Small models drift back into English; ask again if the reply clearly did
+    let language_settings = crate::settings::current().language;
+    if let Some(reply_language) = reply_language.as_deref().filter(|_| language_settings.verify) {
+        if !was_cancelled && executed_tools.is_empty() {
+            for _ in 0..language_settings.max_reasks {
+                if language::matches_language(reply_language, &full_response) != Some(false) {
+                    break;
+                }
+                log_warning!("Reply is not in the preferred language, asking again", language = %reply_language);
+                match language::reask(&client, &model_name, &messages, &full_response, reply_language).await {
+                    Ok(corrected) => {
+                        full_response = corrected;
+                        // `reset` tells the frontend to replace what it has streamed so far
+                        let _ = app.emit(
+                            "chat-token",
+                            serde_json::json!({
+                                "stream_id": stream_id,
+                                "token": full_response,
+                                "finished": false,
+                                "reset": true
+                            })
+                        );
+                    }
+                    Err(e) => {
+                        log_operation_error!("Re-ask in preferred language", &e);
+                        break;
                     }
                 }
-                Err(e) => {
-                    error!("Failed to continue conversation: {}", e);
-                    let error_msg = format!("\n\n[Continuation Error: {}]", e);
-                    full_response.push_str(&error_msg);
+            }
+        }
+    }
 
+    // JSON mode: check the reply against the requested format and ask again while it fails
+    let mut format_error = None;
+    if let Some(format) = response_format.as_ref().filter(|_| !was_cancelled) {
+        let json_retries = crate::settings::current().chat.json_retries;
+        let answer_start = structured_output::answer_start(&full_response);
+        let mut attempt = 0;
+        while let Err(e) = format.validate(&full_response[answer_start..]) {
+            if attempt >= json_retries {
+                log_warning!("Reply does not match the response format", error = %e, retries = attempt);
+                format_error = Some(e);
+                break;
+            }
+            attempt += 1;
+            log_warning!("Reply does not match the response format, asking again", error = %e, attempt = attempt);
+            let reask = structured_output::reask(
+                &app,
+                &client,
+                &model_name,
+                &messages,
+                &full_response,
+                format,
+                &e,
+                &stream_id,
+                &operation.token()
+            ).await;
+            match reask {
+                Ok(corrected) => {
+                    // Tool exchanges before the answer stay as they were
+                    full_response.truncate(answer_start);
+                    full_response.push_str(&corrected);
                     let _ = app.emit(
                         "chat-token",
                         serde_json::json!({
-                            "token": error_msg,
-                            "finished": false
+                            "stream_id": stream_id,
+                            "token": full_response,
+                            "finished": false,
+                            "reset": true
                         })
                     );
                 }
+                Err(_) if operation.is_cancelled() => {
+                    was_cancelled = true;
+                    break;
+                }
+                Err(reask_error) => {
+                    log_operation_error!("Re-ask for valid JSON", &reask_error);
+                    format_error = Some(e);
+                    break;
+                }
             }
-        } else {
-            debug!("Tool response doesn't contain JSON - no continuation needed");
         }
     }
+    drop(operation);
+
+    draft.finish().await;
+
+    let generation = GenerationMetrics {
+        model: Some(model_name.clone()),
+        time_to_first_token_ms: first_token_after.map(|after| after.as_millis() as u64),
+        generation_time_ms: Some(started.elapsed().as_millis() as u64),
+    };
+    let metrics = ChatMetrics::new(&stream_id, session_id.clone(), generation, usage_data, was_cancelled);
+    debug!(
+        model = %model_name,
+        time_to_first_token_ms = ?metrics.generation.time_to_first_token_ms,
+        generation_time_ms = ?metrics.generation.generation_time_ms,
+        tokens_per_second = ?metrics.tokens_per_second,
+        "Chat metrics"
+    );
+    let _ = app.emit("chat-metrics", &metrics);
 
     // Emit completion signal with usage data and cancellation status
     let _ = app.emit(
         "chat-token",
         serde_json::json!({
+            "stream_id": stream_id,
             "token": "",
             "finished": true,
             "cancelled": was_cancelled,
+            "format_error": format_error,
             "usage": usage_data.map(|(prompt, completion, total)| {
                 serde_json::json!({
                     "prompt_tokens": prompt,
@@ -1153,6 +1521,7 @@ For each function call, return a json object with function name and arguments wi
         let _ = app.emit(
             "chat-usage",
             serde_json::json!({
+                "stream_id": stream_id,
                 "prompt_tokens": prompt_tokens,
                 "completion_tokens": completion_tokens,
                 "total_tokens": total_tokens
@@ -1185,125 +1554,477 @@ For each function call, return a json object with function name and arguments wi
     Ok(full_response)
 }
 
-async fn continue_conversation_after_tools(
-    app: AppHandle,
-    client: &Client<OpenAIConfig>,
-    _system_message: &str,
-    previous_messages: &[ChatCompletionRequestMessage],
-    assistant_response_with_tools: String,
-    model_name: &str,
+/// Id a chat stream is registered and reported under: the session id, or
+/// `TEMP_STREAM_ID` for a chat that is not saved
+pub(crate) fn stream_key(session_id: Option<&str>) -> &str {
+    session_id.unwrap_or(TEMP_STREAM_ID)
+}
+
+/// A piece of streamed reply text for the frontend
+fn emit_chat_token(app: &AppHandle, stream_id: &str, token: &str) {
+    let _ = app.emit("chat-token", serde_json::json!({ "stream_id": stream_id, "token": token, "finished": false }));
+}
+
+/// Payload of `chat-metrics`, emitted when a reply is finished
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMetrics {
+    pub stream_id: String,
+    pub session_id: Option<String>,
+    pub generation: GenerationMetrics,
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    /// Completion tokens over the time after the first token
+    pub tokens_per_second: Option<f64>,
+    pub cancelled: bool,
+}
+
+impl ChatMetrics {
+    fn new(
+        stream_id: &str,
+        session_id: Option<String>,
+        generation: GenerationMetrics,
+        usage: Option<(u32, u32, u32)>,
+        cancelled: bool
+    ) -> Self {
+        let completion_tokens = usage.map(|(_, completion, _)| completion);
+        let streaming_ms = generation.generation_time_ms
+            .zip(generation.time_to_first_token_ms)
+            .map(|(total, first)| total.saturating_sub(first))
+            .filter(|ms| *ms > 0);
+        let tokens_per_second = completion_tokens
+            .zip(streaming_ms)
+            .map(|(tokens, ms)| tokens as f64 * 1000.0 / ms as f64);
+        Self {
+            stream_id: stream_id.to_string(),
+            session_id,
+            generation,
+            prompt_tokens: usage.map(|(prompt, _, _)| prompt),
+            completion_tokens,
+            tokens_per_second,
+            cancelled,
+        }
+    }
+}
+
+/// Sampling options of a chat request, reused for its continuation
+#[derive(Debug, Clone)]
+struct SamplingParams {
     temperature: Option<f64>,
     top_p: Option<f64>,
     seed: Option<i64>,
     max_tokens: Option<u32>,
-    max_completion_tokens: Option<u32>
+    max_completion_tokens: Option<u32>,
+    options: SamplingOptions,
+}
+
+/// A native tool call assembled from streamed `tool_calls` deltas
+#[derive(Debug, Default, Clone, PartialEq)]
+struct PendingToolCall {
+    index: u32,
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Fold one delta's tool call pieces into the calls seen so far: the id and
+/// name come once, the arguments as a run of string fragments
+fn merge_tool_call_chunks(calls: &mut Vec<PendingToolCall>, chunks: &[ChatCompletionMessageToolCallChunk]) {
+    for chunk in chunks {
+        let position = match calls.iter().position(|call| call.index == chunk.index) {
+            Some(position) => position,
+            None => {
+                calls.push(PendingToolCall { index: chunk.index, ..Default::default() });
+                calls.len() - 1
+            }
+        };
+        let call = &mut calls[position];
+        if let Some(id) = &chunk.id {
+            call.id.push_str(id);
+        }
+        if let Some(function) = &chunk.function {
+            if let Some(name) = &function.name {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = &function.arguments {
+                call.arguments.push_str(arguments);
+            }
+        }
+    }
+}
+
+/// Run one tool call and report it with a `tool-call` event
+async fn execute_tool_call(
+    app: &AppHandle,
+    session_id: Option<&str>,
+    tool_policy: &ToolPolicy,
+    fn_name: &str,
+    fn_args: &str
 ) -> Result<String, String> {
-    debug!("Continuing conversation after tool execution");
+    // Parse arguments as JSON for MCP tool call
+    let mut args_map = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(fn_args) {
+        Ok(mut map) => {
+            // Remove null values as MCP tools don't handle them well
+            map.retain(|_k, v| !v.is_null());
+            Some(map)
+        }
+        Err(e) => {
+            log_warning!("Failed to parse tool arguments", error = %e, args = %fn_args);
+            None
+        }
+    };
 
-    // Create a clean system message for continuation - completely remove tool formatting
-    let continuation_system_message = 
-        "You are a helpful AI assistant. A tool has just been executed and returned results. \
-        Your task is to interpret the JSON results and provide a clear, natural language response to the user.\n\n\
-        CRITICAL: Respond ONLY with plain text. Do NOT use any XML tags like <tool_call> or <tool_response>. \
-        Do NOT wrap your response in any special formatting. Just provide a natural, conversational answer.".to_string();
+    // render_chart files its output under the session
+    if let (Some(map), Some(id)) = (args_map.as_mut(), session_id) {
+        if fn_name == mcp::builtin_tools::SESSION_TOOL {
+            map.insert(mcp::builtin_tools::SESSION_ARG.to_string(), serde_json::json!(id));
+        }
+    }
 
-    debug!("Continuation system message (length: {})", continuation_system_message.len());
+    // The model may still name a tool it was not offered
+    if !tool_policy.permits(fn_name) {
+        log_warning!("Tool call blocked by session policy", tool = %fn_name);
+        return Err(format!("The tool {} is disabled in this conversation", fn_name));
+    }
 
-    // Extract just the tool response content (the JSON data) without XML tags
-    // This prevents the LLM from seeing the XML format and copying it
-    let clean_tool_response = extract_tool_response_content(&assistant_response_with_tools);
-    
-    tracing::trace!(length = clean_tool_response.len(), "Clean tool response for continuation");
+    // Flagged tools wait here until the user approves them
+    crate::tool_approval::approve(app, session_id, fn_name, fn_args).await?;
 
-    // Build a minimal message list for continuation - ONLY system, user question, and tool result
-    // Do NOT include conversation history to avoid the LLM seeing XML patterns
-    let mut continuation_messages = vec![
-        ChatCompletionRequestSystemMessageArgs::default()
-            .content(continuation_system_message)
-            .build()
-            .map_err(|e| format!("Failed to build system message: {}", e))?
-            .into()
-    ];
+    match mcp::call_mcp_tool(app.clone(), fn_name.to_string(), args_map).await {
+        Ok(tool_result) => {
+            tracing::debug!(tool = %fn_name, result_length = tool_result.len(), "Tool execution completed");
+            tracing::trace!(result = %tool_result, "Tool result content");
 
-    // Find the last user message (the current question)
-    if let Some(last_user_msg) = previous_messages.iter().rev().find(|msg| {
-        // Check if this is a user message by inspecting the message structure
-        if let Ok(value) = serde_json::to_value(msg) {
-            if let Some(role) = value.get("role") {
-                return role.as_str() == Some("user");
-            }
+            // Emit function call result to frontend
+            let _ = app.emit(
+                "tool-call",
+                serde_json::json!({
+                    "stream_id": stream_key(session_id),
+                    "tool_name": fn_name,
+                    "arguments": fn_args,
+                    "result": tool_result
+                })
+            );
+            Ok(tool_result)
         }
-        false
-    }) {
-        // Add only the current user's question
-        continuation_messages.push(last_user_msg.clone());
-    } else {
-        log_warning!("Could not find user message in history", note = "continuation may lack context");
+        Err(e) => {
+            log_operation_error!("Tool execution", &e, tool = %fn_name);
+            Err(e)
+        }
+    }
+}
+
+/// One executed tool call, as reported in `agent-step` events
+#[derive(Debug, Clone, Serialize)]
+struct ToolCallRecord {
+    name: String,
+    arguments: String,
+    result: String,
+    success: bool,
+}
+
+/// Payload of `agent-step`, emitted after each round of tool calls
+#[derive(Debug, Clone, Serialize)]
+struct AgentStepEvent<'a> {
+    session_id: Option<&'a str>,
+    round: u32,
+    max_rounds: u32,
+    tool_calls: &'a [ToolCallRecord],
+    /// The model was asked to answer without calling more tools
+    final_round: bool,
+}
+
+/// What one streamed model turn produced
+#[derive(Debug, Default)]
+struct RoundOutput {
+    /// The model's own text, without tool results
+    text: String,
+    /// Calls OVMS parsed out of the turn (native tools)
+    native_calls: Vec<PendingToolCall>,
+    /// `<tool_call>` tags in the text, already executed (prompt scheme)
+    xml_records: Vec<ToolCallRecord>,
+}
+
+impl RoundOutput {
+    fn has_tool_calls(&self) -> bool {
+        !self.native_calls.is_empty() || !self.xml_records.is_empty()
     }
+}
+
+/// Run a `<tool_call>` from the reply and append its `<tool_response>` block
+async fn run_xml_tool_call(
+    app: &AppHandle,
+    session_id: Option<&str>,
+    tool_policy: &ToolPolicy,
+    name: String,
+    arguments: String,
+    full_response: &mut String
+) -> ToolCallRecord {
+    let (result, success) = match execute_tool_call(app, session_id, tool_policy, &name, &arguments).await {
+        Ok(tool_result) => (tool_result, true),
+        Err(e) => (format!("Error: {}", e), false),
+    };
+    // Add tool response in Qwen-Agent format and emit to frontend
+    let response_text = format!("\n<tool_response>\n{}\n</tool_response>", result);
+    full_response.push_str(&response_text);
+    emit_chat_token(app, stream_key(session_id), &response_text);
+    ToolCallRecord { name, arguments, result, success }
+}
 
-    // Add a simplified assistant message showing the tool result without XML formatting
-    continuation_messages.push(
+/// Messages reporting prompt-scheme results: the model's turn as written,
+/// then the results in a user turn, as the Qwen tool template expects
+fn xml_follow_up(
+    text: &str,
+    records: &[ToolCallRecord],
+    final_round: bool
+) -> Result<Vec<ChatCompletionRequestMessage>, String> {
+    let mut results = records.iter()
+        .map(|record| format!("<tool_response>\n{}\n</tool_response>", record.result))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if final_round {
+        results.push_str("\n\nAnswer the user now using these results, without calling more tools.");
+    }
+    Ok(vec![
         ChatCompletionRequestAssistantMessageArgs::default()
-            .content(format!("I called a tool and received this result:\n{}", clean_tool_response))
+            .content(text.to_string())
+            .build()
+            .map_err(|e| format!("Failed to build assistant message with tools: {}", e))?
+            .into(),
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(results)
+            .build()
+            .map_err(|e| format!("Failed to build tool response message: {}", e))?
+            .into(),
+    ])
+}
+
+/// Run the tool calls OVMS parsed out of a turn and return the assistant
+/// and tool messages that report them back to the model. The calls and
+/// results are also written into the reply as `<tool_call>`/`<tool_response>`
+/// blocks, the shape the prompt scheme produces, so the frontend and history
+/// handling treat both alike.
+async fn run_native_tool_calls(
+    app: &AppHandle,
+    session_id: Option<&str>,
+    tool_policy: &ToolPolicy,
+    text: &str,
+    calls: Vec<PendingToolCall>,
+    full_response: &mut String
+) -> Result<(Vec<ChatCompletionRequestMessage>, Vec<ToolCallRecord>), String> {
+    let mut assistant = ChatCompletionRequestAssistantMessageArgs::default();
+    if !text.trim().is_empty() {
+        assistant.content(text.to_string());
+    }
+    let mut tool_messages = Vec::with_capacity(calls.len());
+    let mut tool_calls = Vec::with_capacity(calls.len());
+    let mut records = Vec::with_capacity(calls.len());
+
+    for (position, call) in calls.into_iter().enumerate() {
+        let id = if call.id.is_empty() { format!("call_{}", position) } else { call.id };
+        let arguments = if call.arguments.trim().is_empty() { "{}".to_string() } else { call.arguments };
+        tracing::debug!(name = %call.name, args = %arguments, "Native tool call");
+
+        let call_text = format!(
+            "\n<tool_call>\n{}\n</tool_call>",
+            serde_json::json!({
+                "name": call.name,
+                "arguments": serde_json::from_str::<serde_json::Value>(&arguments).unwrap_or(serde_json::Value::String(arguments.clone()))
+            })
+        );
+        full_response.push_str(&call_text);
+        emit_chat_token(app, stream_key(session_id), &call_text);
+
+        let record = run_xml_tool_call(app, session_id, tool_policy, call.name.clone(), arguments.clone(), full_response).await;
+        tool_messages.push(
+            ChatCompletionRequestToolMessageArgs::default()
+                .tool_call_id(id.clone())
+                .content(record.result.clone())
+                .build()
+                .map_err(|e| format!("Failed to build tool message: {}", e))?
+                .into()
+        );
+        tool_calls.push(ChatCompletionMessageToolCalls::Function(ChatCompletionMessageToolCall {
+            id,
+            function: FunctionCall { name: call.name, arguments },
+        }));
+        records.push(record);
+    }
+
+    let mut follow_up: Vec<ChatCompletionRequestMessage> = vec![
+        assistant
+            .tool_calls(tool_calls)
             .build()
             .map_err(|e| format!("Failed to build assistant message with tools: {}", e))?
             .into()
-    );
+    ];
+    follow_up.extend(tool_messages);
+    Ok((follow_up, records))
+}
 
-    // Create a new streaming request to continue the conversation
+/// The bounded loop after a reply that called tools: results go back to the
+/// model, which may call further tools, for at most `max_rounds` rounds. The
+/// request after the last round asks for an answer without tools.
+struct ToolRounds<'a> {
+    app: &'a AppHandle,
+    client: &'a Client<OpenAIConfig>,
+    model_name: &'a str,
+    session_id: Option<&'a str>,
+    /// Policy of the session, loaded once for the whole reply
+    tool_policy: &'a ToolPolicy,
+    params: SamplingParams,
+    response_format: Option<&'a ChatResponseFormat>,
+    /// Tools for the request's `tools` field; empty with the prompt scheme
+    native_tools: Vec<ChatCompletionTool>,
+    max_rounds: u32,
+    /// Stops further rounds when the user stops the reply
+    cancel: CancellationToken,
+}
+
+impl ToolRounds<'_> {
+    async fn run(
+        &self,
+        mut conversation: Vec<ChatCompletionRequestMessage>,
+        first: RoundOutput,
+        full_response: &mut String
+    ) -> Result<(), String> {
+        let mut output = first;
+        for round in 1..=self.max_rounds {
+            if self.cancel.is_cancelled() {
+                return Ok(());
+            }
+            let final_round = round == self.max_rounds;
+            let (follow_up, records) = if !output.native_calls.is_empty() {
+                run_native_tool_calls(self.app, self.session_id, self.tool_policy, &output.text, output.native_calls, full_response).await?
+            } else if !output.xml_records.is_empty() {
+                (xml_follow_up(&output.text, &output.xml_records, final_round)?, output.xml_records)
+            } else {
+                return Ok(());
+            };
+
+            tracing::debug!(round, tools = records.len(), "Tool round finished");
+            let _ = self.app.emit("agent-step", AgentStepEvent {
+                session_id: self.session_id,
+                round,
+                max_rounds: self.max_rounds,
+                tool_calls: &records,
+                final_round,
+            });
+
+            conversation.extend(follow_up);
+            let tools = if final_round { &[][..] } else { &self.native_tools[..] };
+            let request = continuation_request(self.model_name, conversation.clone(), &self.params, self.response_format, tools)?;
+            output = stream_round(self.app, self.client, request, stream_key(self.session_id), &self.cancel).await?;
+            full_response.push_str(&output.text);
+
+            output.native_calls.retain(|call| !call.name.is_empty());
+            if final_round {
+                if !output.native_calls.is_empty() || !extract_all_tool_calls_from_xml(&output.text).is_empty() {
+                    log_warning!("Tool round limit reached", max_rounds = self.max_rounds, note = "further tool calls ignored");
+                }
+                return Ok(());
+            }
+            for (name, arguments) in extract_all_tool_calls_from_xml(&output.text) {
+                let record = run_xml_tool_call(self.app, self.session_id, self.tool_policy, name, arguments, full_response).await;
+                output.xml_records.push(record);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Streaming request continuing a conversation after tool results; `tools`
+/// lets the model call again
+fn continuation_request(
+    model_name: &str,
+    messages: Vec<ChatCompletionRequestMessage>,
+    params: &SamplingParams,
+    response_format: Option<&ChatResponseFormat>,
+    tools: &[ChatCompletionTool]
+) -> Result<CreateChatCompletionRequest, String> {
     let mut request_builder = CreateChatCompletionRequestArgs::default();
     request_builder
         .model(model_name.to_string())
-        .messages(continuation_messages)
+        .messages(messages)
         .stream(true)
-        .temperature(temperature.unwrap_or(0.7) as f32)
-        .top_p(top_p.unwrap_or(1.0) as f32);
+        .temperature(params.temperature.unwrap_or(0.7) as f32)
+        .top_p(params.top_p.unwrap_or(1.0) as f32);
 
-    if let Some(seed) = seed {
+    if let Some(seed) = params.seed {
         request_builder.seed(seed);
     }
 
-    let effective_max_tokens = max_tokens.unwrap_or(1000).max(100);
+    let effective_max_tokens = params.max_tokens.unwrap_or(1000).max(100);
     request_builder.max_tokens(effective_max_tokens);
 
-    if let Some(max_completion_tokens) = max_completion_tokens {
+    if let Some(max_completion_tokens) = params.max_completion_tokens {
         request_builder.max_completion_tokens(max_completion_tokens);
     }
 
-    let request = request_builder
+    params.options.apply(&mut request_builder);
+
+    if let Some(format) = response_format {
+        request_builder.response_format(format.to_request());
+    }
+
+    if !tools.is_empty() {
+        request_builder
+            .tools(tools.iter().cloned().map(ChatCompletionTools::Function).collect::<Vec<_>>())
+            .tool_choice(ChatCompletionToolChoiceOption::Mode(ToolChoiceOptions::Auto));
+    }
+
+    request_builder
         .build()
-        .map_err(|e| format!("Failed to build continuation request: {}", e))?;
+        .map_err(|e| format!("Failed to build continuation request: {}", e))
+}
 
+/// Stream one continuation turn to the frontend; a cancel ends it early
+async fn stream_round(
+    app: &AppHandle,
+    client: &Client<OpenAIConfig>,
+    request: CreateChatCompletionRequest,
+    stream_id: &str,
+    cancel: &CancellationToken
+) -> Result<RoundOutput, String> {
     debug!("Sending continuation request...");
 
-    let mut stream = client
-        .chat()
-        .create_stream(request).await
+    let mut stream = server_busy::open_stream(app, client, request, stream_id, OperationKind::Chat, cancel).await
         .map_err(|e| format!("Failed to create continuation stream: {}", e))?;
 
-    let mut continued_response = String::new();
+    let mut output = RoundOutput::default();
+    let mut coalescer = TokenCoalescer::from_settings();
 
-    // Process the continuation stream
-    while let Some(result) = stream.next().await {
+    loop {
+        let result = tokio::select! {
+            _ = cancel.cancelled() => break,
+            // Release coalesced text the model is slow to finish
+            _ = tokio::time::sleep_until(coalescer.deadline()), if coalescer.has_pending() => {
+                if let Some(chunk) = coalescer.flush() {
+                    emit_chat_token(app, stream_id, &chunk);
+                }
+                continue;
+            }
+            next = stream.next() => match next {
+                Some(result) => result,
+                None => break,
+            },
+        };
         match result {
             Ok(response) => {
                 for chat_choice in response.choices {
                     if let Some(content) = &chat_choice.delta.content {
-                        continued_response.push_str(content);
+                        output.text.push_str(content);
 
                         // Emit streaming content for continuation
-                        let _ = app.emit(
-                            "chat-token",
-                            serde_json::json!({
-                                "token": content,
-                                "finished": false
-                            })
-                        );
+                        if let Some(chunk) = coalescer.push(content) {
+                            emit_chat_token(app, stream_id, &chunk);
+                        }
+                    }
+                    if let Some(chunks) = &chat_choice.delta.tool_calls {
+                        merge_tool_call_chunks(&mut output.native_calls, chunks);
                     }
 
                     if let Some(finish_reason) = &chat_choice.finish_reason {
                         tracing::debug!(reason = ?finish_reason, "Continuation finished");
-                        break;
                     }
                 }
             }
@@ -1313,16 +2034,19 @@ async fn continue_conversation_after_tools(
             }
         }
     }
+    if let Some(chunk) = coalescer.flush() {
+        emit_chat_token(app, stream_id, &chunk);
+    }
 
-    tracing::debug!(length = continued_response.len(), "Continuation response completed");
-    Ok(continued_response)
+    tracing::debug!(length = output.text.len(), "Continuation response completed");
+    Ok(output)
 }
 
 // RAG-enhanced chat with streaming
 #[tauri::command]
 pub async fn chat_with_rag_streaming(
     app: AppHandle,
-    model_name: String,
+    model_name: Option<String>,
     message: String,
     session_id: Option<String>,
     include_history: Option<bool>,
@@ -1332,11 +2056,18 @@ pub async fn chat_with_rag_streaming(
     seed: Option<i64>,
     max_tokens: Option<u32>,
     max_completion_tokens: Option<u32>,
+    sampling: Option<SamplingOptions>,
     use_rag: Option<bool>,
     rag_limit: Option<usize>,
-    attachments: Option<Vec<AttachmentInfo>>
+    retrieval: Option<RetrievalOptions>,
+    attachments: Option<Vec<AttachmentInfo>>,
+    response_format: Option<ChatResponseFormat>
 ) -> Result<String, String> {
+    // Retrieval below needs the embedding model, so OVMS must be up first
+    crate::ensure_ovms_initialized(&app).await;
+
     let mut context_content = String::new();
+    let mut citations = Vec::new();
 
     // Separate images from documents
     let (_image_attachments, document_attachments): (Vec<AttachmentInfo>, Vec<AttachmentInfo>) = 
@@ -1364,18 +2095,24 @@ pub async fn chat_with_rag_streaming(
 
     // RAG retrieval if enabled OR if there are document attachments (not images)
     let should_use_rag = use_rag.unwrap_or(false) || doc_file_paths.is_some();
-    
+
     if should_use_rag {
+        // `rag_limit` predates the retrieval options and stands in for their top_n
+        let retrieval = retrieval.unwrap_or_default();
+        let retrieval = RetrievalOptions { top_n: retrieval.top_n.or(rag_limit), ..retrieval };
+        let limits = retrieval.resolve(&crate::settings::current().rag.retrieval, doc_file_paths.is_some())?;
+
         tracing::info!(
             has_attached_files = doc_file_paths.is_some(), 
             attached_count = doc_file_paths.as_ref().map(|f| f.len()),
             "RAG is enabled, performing document retrieval"
         );
-        match perform_rag_retrieval(&message, rag_limit, doc_file_paths.as_ref()).await {
+        match retrieve_rag_context(&message, limits, doc_file_paths.as_ref()).await {
             Ok(context) => {
-                if !context.is_empty() {
-                    tracing::info!(context_length = context.len(), "RAG context retrieved successfully");
-                    context_content = context;
+                if !context.text.is_empty() {
+                    tracing::info!(context_length = context.text.len(), "RAG context retrieved successfully");
+                    context_content = context.text;
+                    citations = context.citations;
                 } else {
                     tracing::warn!("RAG retrieval returned empty context - no relevant documents found");
                 }
@@ -1395,25 +2132,43 @@ pub async fn chat_with_rag_streaming(
             "You are a helpful AI assistant with access to document content. CRITICAL INSTRUCTIONS:\n\
             - You MUST use the document excerpts provided below to answer questions\n\
             - Quote specific details from the documents when relevant\n\
-            - If information is in the documents, cite it explicitly (e.g., \"According to Source 1...\")\n\
+            - Cite the sources you use with their number in square brackets right after the statement, \
+            e.g. \"The warranty lasts two years [2].\" or \"[1][3]\" for several\n\
+            - Only cite numbers of the sources listed below\n\
             - If the answer isn't in the provided excerpts, say so clearly\n\
             - DO NOT claim you cannot analyze documents - the content is right here\n\
             - Synthesize information across multiple sources when needed\n\n\
             DOCUMENT EXCERPTS:\n\
             {}\n\n\
-            Answer the user's question using the above document content. Be specific and cite your sources.",
+            Answer the user's question using the above document content. Be specific and cite your sources with [n] markers.",
             context_content
         );
         tracing::info!(prompt_length = prompt.len(), has_context = true, "Enhanced system prompt with RAG context");
         prompt
     } else {
-        let prompt = system_prompt.unwrap_or_else(||
+        let preset = match (&system_prompt, &session_id) {
+            (None, Some(id)) => load_chat_sessions().await
+                .ok()
+                .and_then(|storage| storage.sessions.get(id).and_then(preset_prompt)),
+            _ => None,
+        };
+        let prompt = system_prompt.or(preset).unwrap_or_else(||
             "You're an AI assistant that provides helpful responses.".to_string()
         );
         tracing::debug!(has_context = false, "Using standard system prompt without RAG");
         prompt
     };
 
+    // Sent before the answer streams so its [n] markers can link to sources
+    if !citations.is_empty() {
+        let payload = crate::rag::citations::RagCitations {
+            stream_id: stream_key(session_id.as_deref()).to_string(),
+            session_id: session_id.clone(),
+            citations,
+        };
+        let _ = app.emit("rag-citations", payload);
+    }
+
     // Use existing chat function with enhanced prompt
     // Pass the full attachments list (including images) to the base chat function
     chat_with_loaded_model_streaming(
@@ -1428,18 +2183,99 @@ pub async fn chat_with_rag_streaming(
         seed,
         max_tokens,
         max_completion_tokens,
-        attachments // Pass all attachments, images will be handled separately
+        sampling,
+        attachments, // Pass all attachments, images will be handled separately
+        response_format
     ).await
 }
 
-async fn perform_rag_retrieval(
-    query: &str, 
+/// Retrieval knobs of a RAG request; unset ones come from `rag.retrieval`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrievalOptions {
+    /// Chunks fetched by vector search before reranking
+    pub candidates: Option<usize>,
+    /// Chunks put into the prompt after reranking
+    pub top_n: Option<usize>,
+    /// Characters kept of each chunk (0 = the whole chunk)
+    pub max_chunk_chars: Option<usize>,
+    /// Lowest similarity a chunk needs to be considered
+    pub min_score: Option<f32>,
+}
+
+/// Retrieval options with the defaults filled in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RetrievalLimits {
+    pub candidates: usize,
+    pub top_n: usize,
+    pub max_chunk_chars: usize,
+    pub min_score: f32,
+}
+
+impl RetrievalOptions {
+    /// Checked limits, taking unset options from `defaults`; with attached
+    /// documents the attached-file defaults apply
+    pub(crate) fn resolve(&self, defaults: &crate::settings::RetrievalSettings, attached: bool) -> Result<RetrievalLimits, String> {
+        let (default_candidates, default_top_n) = if attached {
+            (defaults.attached_candidates, defaults.attached_top_n)
+        } else {
+            (defaults.candidates, defaults.top_n)
+        };
+        let top_n = self.top_n.unwrap_or(default_top_n);
+        if top_n == 0 {
+            return Err("top_n must be at least 1".to_string());
+        }
+        if self.candidates == Some(0) {
+            return Err("candidates must be at least 1".to_string());
+        }
+        let min_score = self.min_score.unwrap_or(defaults.min_score);
+        if !min_score.is_finite() {
+            return Err(format!("min_score must be a number, got {}", min_score));
+        }
+        Ok(RetrievalLimits {
+            // The reranker can only pick from what the search returned
+            candidates: self.candidates.unwrap_or(default_candidates).max(top_n),
+            top_n,
+            max_chunk_chars: self.max_chunk_chars.unwrap_or(defaults.max_chunk_chars),
+            min_score,
+        })
+    }
+}
+
+/// At most `max_chars` characters of a chunk; 0 keeps all of it
+fn clip_chunk(content: &str, max_chars: usize) -> String {
+    match content.char_indices().nth(max_chars).filter(|_| max_chars > 0) {
+        Some((end, _)) => format!("{}...", &content[..end]),
+        None => content.to_string(),
+    }
+}
+
+/// Retrieved context for a prompt and the sources its excerpts are numbered after
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RagContext {
+    pub text: String,
+    pub citations: Vec<crate::rag::citations::Citation>,
+}
+
+/// Context for `query` with `limit` chunks and the other limits from settings
+pub(crate) async fn perform_rag_retrieval(
+    query: &str,
     limit: Option<usize>,
     attached_file_paths: Option<&Vec<String>>
 ) -> Result<String, String> {
+    let limits = RetrievalOptions { top_n: limit, ..Default::default() }
+        .resolve(&crate::settings::current().rag.retrieval, attached_file_paths.is_some())?;
+    retrieve_rag_context(query, limits, attached_file_paths).await.map(|context| context.text)
+}
+
+pub(crate) async fn retrieve_rag_context(
+    query: &str, 
+    limits: RetrievalLimits,
+    attached_file_paths: Option<&Vec<String>>
+) -> Result<RagContext, String> {
     tracing::info!(
         query_length = query.len(), 
-        limit = ?limit,
+        limits = ?limits,
         has_attached_files = attached_file_paths.is_some(),
         attached_count = attached_file_paths.map(|f| f.len()),
         "Starting RAG retrieval"
@@ -1455,23 +2291,31 @@ async fn perform_rag_retrieval(
     tracing::debug!(embedding_dim = query_embedding.len(), "Query embedding created");
 
     // Search similar documents
-    let vector_store = crate::rag::vector_store::VectorStore::new()?;
+    let vector_store = crate::rag::backend::open_default()?;
     
     // If attached files are specified, search only in those files
     let search_results = if let Some(file_paths) = attached_file_paths {
         tracing::info!(file_count = file_paths.len(), "Searching only in attached files");
-        vector_store.search_similar_in_files(&query_embedding, file_paths, limit.unwrap_or(100))?
+        vector_store.search_similar_in_files(&query_embedding, file_paths, limits.candidates).await?
     } else {
-        // Otherwise, search all documents with the specified limit
-        let search_limit = limit.unwrap_or(5) * 2; // Get more for reranking
-        vector_store.search_similar(&query_embedding, search_limit)?
+        // Otherwise, search all documents, fetching more than needed for reranking
+        vector_store.search_similar(&query_embedding, limits.candidates).await?
     };
     
     tracing::info!(results_found = search_results.len(), "Vector search completed");
 
+    let found = search_results.len();
+    let search_results: Vec<_> = search_results
+        .into_iter()
+        .filter(|result| result.score >= limits.min_score)
+        .collect();
+    if search_results.len() < found {
+        tracing::debug!(dropped = found - search_results.len(), min_score = limits.min_score, "Dropped chunks below the similarity threshold");
+    }
+
     if search_results.is_empty() {
         tracing::warn!("No similar documents found in vector store");
-        return Ok(String::new());
+        return Ok(RagContext::default());
     }
 
     // Rerank results
@@ -1485,12 +2329,7 @@ async fn perform_rag_retrieval(
     tracing::info!(reranked_count = reranked_results.len(), "Results reranked");
 
     // Build context from top results
-    // Use a higher count if filtering by specific files
-    let default_top_results = if attached_file_paths.is_some() { 10 } else { 5 };
-    let top_results_count = std::cmp::min(
-        default_top_results, 
-        limit.unwrap_or(default_top_results)
-    );
+    let top_results_count = limits.top_n;
     let context_content = reranked_results
         .iter()
         .take(top_results_count)
@@ -1505,26 +2344,89 @@ async fn perform_rag_retrieval(
                 "Including document chunk in context"
             );
             format!(
-                "Source {}: {}\nContent: {}\nRelevance Score: {:.2}\n---",
+                "Source [{}]: {}\nContent: {}\nRelevance Score: {:.2}\n---",
                 i + 1,
                 result.document.title,
-                &result.document.content, // Use full content instead of truncating
+                clip_chunk(&result.document.content, limits.max_chunk_chars),
                 result.rerank_score.unwrap_or(result.score)
             )
         })
         .collect::<Vec<_>>()
         .join("\n");
+
+    let included = &reranked_results[..top_results_count.min(reranked_results.len())];
+    let graph_context = crate::rag::graph::context_for(query, included).await;
+    let context_content = if graph_context.is_empty() {
+        context_content
+    } else {
+        format!("{}\n{}", context_content, graph_context)
+    };
     
     tracing::info!(
         context_length = context_content.len(),
-        chunks_included = top_results_count,
+        chunks_included = included.len(),
         "RAG context built successfully"
     );
 
-    Ok(context_content)
+    Ok(RagContext {
+        text: context_content,
+        citations: crate::rag::citations::from_results(included),
+    })
 }
 
-fn extract_all_tool_calls_from_xml(text: &str) -> Vec<(String, String)> {
+/// Render the XML tool-calling section appended to the system prompt.
+/// Returns an empty string when no tools are available.
+pub(crate) fn format_tools_prompt(tools: &[ChatCompletionTool]) -> String {
+    if !tools.is_empty() {
+        tracing::debug!("Processing MCP tools for system message...");
+
+        // Generate tool descriptions in simple text format for the custom template
+        let tool_descs: Vec<String> = tools
+            .iter()
+            .enumerate()
+            .map(|(i, tool)| {
+                tracing::trace!(index = i, name = %tool.function.name, "Processing tool");
+                let params_str = match &tool.function.parameters {
+                    Some(params) => serde_json::to_string_pretty(params).unwrap_or_default(),
+                    None => "{}".to_string(),
+                };
+
+                format!(
+                    "{}({}) - {}",
+                    tool.function.name,
+                    params_str,
+                    tool.function.description.as_ref().unwrap_or(&"".to_string())
+                )
+            })
+            .collect();
+
+        let tool_descs_text = tool_descs.join("\n");
+        let formatted_tools =
+            format!(r#"
+
+# Tools
+
+You may call one or more functions to assist with the user query.
+
+You are provided with function signatures within <tools></tools> XML tags:
+<tools>
+{}
+</tools>
+
+For each function call, return a json object with function name and arguments within <tool_call></tool_call> XML tags:
+<tool_call>
+{{"name": <function-name>, "arguments": <args-json-object>}}
+</tool_call>"#, tool_descs_text);
+
+        tracing::debug!(length = formatted_tools.len(), "Generated custom tool template");
+        formatted_tools
+    } else {
+        tracing::trace!("No MCP tools available for system message");
+        "".to_string()
+    }
+}
+
+pub(crate) fn extract_all_tool_calls_from_xml(text: &str) -> Vec<(String, String)> {
     let mut tool_calls = Vec::new();
     let mut search_start = 0;
 
@@ -1554,28 +2456,6 @@ fn extract_all_tool_calls_from_xml(text: &str) -> Vec<(String, String)> {
     tool_calls
 }
 
-fn extract_tool_response_content(text: &str) -> String {
-    // Extract content from <tool_response> tags without the XML formatting
-    if let Some(start) = text.find("<tool_response>") {
-        if let Some(end) = text[start..].find("</tool_response>") {
-            let content_start = start + 15; // Length of "<tool_response>"
-            let content_end = start + end;
-            let raw_content = text[content_start..content_end].trim();
-            
-            // Try to parse as JSON and pretty-print it for better LLM readability
-            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(raw_content) {
-                // Successfully parsed - return pretty-printed JSON
-                return serde_json::to_string_pretty(&parsed).unwrap_or_else(|_| raw_content.to_string());
-            }
-            
-            // If not valid JSON, return as-is
-            return raw_content.to_string();
-        }
-    }
-    // If no tool_response found, return the whole text
-    text.to_string()
-}
-
 fn has_incomplete_tool_call(text: &str) -> bool {
     if let Some(start) = text.rfind("<tool_call>") {
         if let Some(_end) = text[start..].find("</tool_call>") {
@@ -1586,11 +2466,6 @@ fn has_incomplete_tool_call(text: &str) -> bool {
     false
 }
 
-fn check_if_continuation_needed(text: &str) -> bool {
-    // Always continue conversation when any tool_response is found, regardless of content
-    text.contains("<tool_response>")
-}
-
 #[allow(dead_code)]
 fn truncate_content(content: &str, max_length: usize) -> String {
     if content.len() <= max_length {
@@ -1606,7 +2481,7 @@ fn truncate_content(content: &str, max_length: usize) -> String {
     }
 }
 
-fn strip_tool_xml_tags(content: &str) -> String {
+pub(crate) fn strip_tool_xml_tags(content: &str) -> String {
     let mut result = String::new();
     let mut current_pos = 0;
     
@@ -1654,3 +2529,174 @@ fn strip_tool_xml_tags(content: &str) -> String {
     
     result.trim().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, timestamp: i64) -> ChatMessage {
+        ChatMessage {
+            id: id.to_string(),
+            role: "user".to_string(),
+            content: id.to_string(),
+            timestamp,
+            tokens_per_second: None,
+            is_error: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            total_tokens: None,
+            attachments: None,
+            pinned: false,
+            generation: None,
+            citations: None,
+        }
+    }
+
+    fn session(title: &str, updated_at: i64, revision: u64, messages: Vec<ChatMessage>) -> ChatSession {
+        ChatSession {
+            id: "s1".to_string(),
+            title: title.to_string(),
+            created_at: 0,
+            updated_at,
+            model_id: None,
+            messages,
+            preferred_language: None,
+            tool_policy: ToolPolicy::default(),
+            sampling: SamplingOptions::default(),
+            prompt_preset: None,
+            revision,
+        }
+    }
+
+    #[test]
+    fn test_merge_sessions_unions_messages() {
+        let mut existing = session("First", 10, 2, vec![message("a", 1), message("c", 3)]);
+        let mut incoming = session("Second", 20, 1, vec![message("a", 1), message("b", 2)]);
+        incoming.tool_policy.deny = vec!["builtin_*".to_string()];
+
+        merge_sessions(&mut existing, incoming);
+
+        let ids: Vec<&str> = existing.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(existing.title, "Second");
+        assert_eq!(existing.tool_policy.deny, vec!["builtin_*"]);
+        assert_eq!(existing.updated_at, 20);
+        assert_eq!(existing.revision, 3);
+    }
+
+    #[test]
+    fn test_pinned_context_lists_pinned_messages() {
+        let mut pinned = message("a", 1);
+        pinned.pinned = true;
+        pinned.content = "Always answer in metric units".to_string();
+        let stored = session("Chat", 0, 0, vec![pinned, message("b", 2)]);
+
+        let pinned = pinned_messages(&stored);
+        assert_eq!(pinned.len(), 1);
+        assert!(pinned_context(&pinned).ends_with("[user] Always answer in metric units"));
+        assert_eq!(pinned_context(&[]), "");
+    }
+
+    #[test]
+    fn test_merge_tool_call_chunks_joins_fragments() {
+        let chunk = |value: serde_json::Value| -> ChatCompletionMessageToolCallChunk {
+            serde_json::from_value(value).unwrap()
+        };
+        let mut calls = Vec::new();
+        merge_tool_call_chunks(&mut calls, &[
+            chunk(serde_json::json!({ "index": 0, "id": "call_a", "type": "function", "function": { "name": "get_time", "arguments": "{\"tz\": " } })),
+        ]);
+        merge_tool_call_chunks(&mut calls, &[
+            chunk(serde_json::json!({ "index": 0, "function": { "arguments": "\"UTC\"}" } })),
+            chunk(serde_json::json!({ "index": 1, "id": "call_b", "function": { "name": "list_files", "arguments": "{}" } })),
+        ]);
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].name, "get_time");
+        assert_eq!(calls[0].arguments, "{\"tz\": \"UTC\"}");
+        assert_eq!(calls[1].name, "list_files");
+    }
+
+    #[test]
+    fn test_xml_follow_up_reports_results_as_user_turn() {
+        let records = vec![ToolCallRecord {
+            name: "get_time".into(),
+            arguments: "{}".into(),
+            result: "12:00".into(),
+            success: true,
+        }];
+        let messages = xml_follow_up("<tool_call>{}</tool_call>", &records, true).unwrap();
+        let messages = serde_json::to_value(&messages).unwrap();
+
+        assert_eq!(messages[0]["role"], "assistant");
+        assert_eq!(messages[1]["role"], "user");
+        let results = messages[1]["content"].as_str().unwrap();
+        assert!(results.starts_with("<tool_response>\n12:00\n</tool_response>"));
+        assert!(results.contains("without calling more tools"));
+    }
+
+    #[test]
+    fn test_check_revision() {
+        let stored = session("Chat", 0, 4, Vec::new());
+        assert!(check_revision(&stored, None).is_ok());
+        assert!(check_revision(&stored, Some(4)).is_ok());
+        assert!(check_revision(&stored, Some(3)).unwrap_err().starts_with("Conflict"));
+    }
+
+    #[test]
+    fn test_tool_policy_permits() {
+        assert!(ToolPolicy::default().permits("builtin_render_chart"));
+
+        let policy = ToolPolicy { allow: Vec::new(), deny: vec!["builtin_*".to_string(), "github_create_issue".to_string()] };
+        assert!(!policy.permits("builtin_render_chart"));
+        assert!(!policy.permits("github_create_issue"));
+        assert!(policy.permits("github_search"));
+
+        let policy = ToolPolicy { allow: vec!["builtin_get_current_time".to_string()], deny: Vec::new() };
+        assert!(policy.permits("builtin_get_current_time"));
+        assert!(!policy.permits("builtin_render_chart"));
+
+        let policy = ToolPolicy { allow: vec!["*".to_string()], deny: vec!["*".to_string()] };
+        assert!(!policy.permits("builtin_get_current_time"));
+    }
+
+    #[test]
+    fn test_sampling_options_validation_and_overrides() {
+        let saved = SamplingOptions {
+            top_k: Some(40),
+            repetition_penalty: Some(1.1),
+            stop: vec!["\n\nUser:".to_string(), String::new()],
+            ..Default::default()
+        }.validated().unwrap();
+        assert_eq!(saved.stop, vec!["\n\nUser:".to_string()]);
+
+        assert!(SamplingOptions { top_k: Some(0), ..Default::default() }.validated().is_err());
+        assert!(SamplingOptions { repetition_penalty: Some(0.0), ..Default::default() }.validated().is_err());
+        assert!(SamplingOptions { presence_penalty: Some(2.5), ..Default::default() }.validated().is_err());
+        assert!(SamplingOptions { stop: vec!["a".to_string(); 5], ..Default::default() }.validated().is_err());
+
+        let merged = saved.clone().overridden_by(SamplingOptions { top_k: Some(20), frequency_penalty: Some(0.5), ..Default::default() });
+        assert_eq!(merged, SamplingOptions { top_k: Some(20), frequency_penalty: Some(0.5), ..saved });
+    }
+
+    #[test]
+    fn test_retrieval_options_resolve_against_defaults() {
+        let defaults = crate::settings::RetrievalSettings::default();
+        let limits = RetrievalOptions::default().resolve(&defaults, false).unwrap();
+        assert_eq!((limits.candidates, limits.top_n), (defaults.candidates, defaults.top_n));
+        let limits = RetrievalOptions::default().resolve(&defaults, true).unwrap();
+        assert_eq!((limits.candidates, limits.top_n), (defaults.attached_candidates, defaults.attached_top_n));
+
+        let options = RetrievalOptions { candidates: Some(4), top_n: Some(8), max_chunk_chars: Some(500), min_score: Some(0.3) };
+        let limits = options.resolve(&defaults, false).unwrap();
+        assert_eq!(limits, RetrievalLimits { candidates: 8, top_n: 8, max_chunk_chars: 500, min_score: 0.3 });
+
+        assert!(RetrievalOptions { top_n: Some(0), ..Default::default() }.resolve(&defaults, false).is_err());
+        assert!(RetrievalOptions { candidates: Some(0), ..Default::default() }.resolve(&defaults, false).is_err());
+        assert!(RetrievalOptions { min_score: Some(f32::NAN), ..Default::default() }.resolve(&defaults, false).is_err());
+
+        assert_eq!(clip_chunk("héllo world", 5), "héllo...");
+        assert_eq!(clip_chunk("héllo world", 0), "héllo world");
+    }
+}
@@ -0,0 +1,137 @@
+//! Aggregate generation stats of a chat session.
+//!
+//! Assistant messages carry the model and timings of their reply
+//! (`ChatMessage::generation`, filled from `chat-metrics`). This sums them
+//! up per session: latency distributions over all timed replies, token
+//! counts and throughput, and how many replies each model wrote. Messages
+//! saved before timings were recorded only count towards the totals.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::chat::{ self, ChatSession };
+use crate::rag::benchmark::LatencyStats;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionMetrics {
+    pub session_id: String,
+    pub replies: usize,
+    /// Replies with recorded timings
+    pub timed_replies: usize,
+    pub time_to_first_token: LatencyStats,
+    pub generation_time: LatencyStats,
+    pub total_generation_time_ms: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Mean over replies that report it
+    pub mean_tokens_per_second: Option<f64>,
+    /// Replies per model
+    pub models: BTreeMap<String, usize>,
+}
+
+fn session_metrics(session: &ChatSession) -> SessionMetrics {
+    let replies: Vec<_> = session.messages
+        .iter()
+        .filter(|message| message.role == "assistant" && !message.is_error.unwrap_or(false))
+        .collect();
+
+    let mut first_token_ms = Vec::new();
+    let mut generation_ms = Vec::new();
+    let mut models = BTreeMap::new();
+    for generation in replies.iter().filter_map(|message| message.generation.as_ref()) {
+        first_token_ms.extend(generation.time_to_first_token_ms.map(|ms| ms as f64));
+        generation_ms.extend(generation.generation_time_ms.map(|ms| ms as f64));
+        if let Some(model) = &generation.model {
+            *models.entry(model.clone()).or_insert(0) += 1;
+        }
+    }
+    let speeds: Vec<f64> = replies.iter().filter_map(|message| message.tokens_per_second).collect();
+
+    SessionMetrics {
+        session_id: session.id.clone(),
+        replies: replies.len(),
+        timed_replies: generation_ms.len(),
+        time_to_first_token: LatencyStats::from_samples(&first_token_ms),
+        generation_time: LatencyStats::from_samples(&generation_ms),
+        total_generation_time_ms: generation_ms.iter().sum::<f64>() as u64,
+        prompt_tokens: replies.iter().filter_map(|message| message.prompt_tokens).map(u64::from).sum(),
+        completion_tokens: replies.iter().filter_map(|message| message.completion_tokens).map(u64::from).sum(),
+        mean_tokens_per_second: (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64),
+        models,
+    }
+}
+
+#[tauri::command]
+pub async fn get_session_metrics(session_id: String) -> Result<SessionMetrics, String> {
+    let storage = chat::load_chat_sessions().await?;
+    let session = storage.sessions
+        .get(&session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+    Ok(session_metrics(session))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ ChatMessage, GenerationMetrics };
+
+    fn reply(model: Option<&str>, first_token_ms: u64, generation_ms: u64, completion_tokens: u32) -> ChatMessage {
+        ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            role: "assistant".to_string(),
+            content: "answer".to_string(),
+            timestamp: 0,
+            tokens_per_second: Some(20.0),
+            is_error: None,
+            prompt_tokens: Some(100),
+            completion_tokens: Some(completion_tokens),
+            total_tokens: None,
+            attachments: None,
+            pinned: false,
+            generation: model.map(|model| GenerationMetrics {
+                model: Some(model.to_string()),
+                time_to_first_token_ms: Some(first_token_ms),
+                generation_time_ms: Some(generation_ms),
+            }),
+            citations: None,
+        }
+    }
+
+    #[test]
+    fn test_session_metrics_aggregates_timed_replies() {
+        let mut question = reply(None, 0, 0, 0);
+        question.role = "user".to_string();
+        let session = ChatSession {
+            id: "s1".to_string(),
+            title: "Metrics".to_string(),
+            created_at: 0,
+            updated_at: 0,
+            model_id: None,
+            messages: vec![
+                question,
+                reply(Some("qwen"), 200, 2000, 40),
+                reply(Some("qwen"), 400, 3000, 60),
+                reply(Some("phi"), 300, 1000, 20),
+                // Saved before timings were recorded
+                reply(None, 0, 0, 10),
+            ],
+            preferred_language: None,
+            tool_policy: Default::default(),
+            sampling: Default::default(),
+            prompt_preset: None,
+            revision: 0,
+        };
+
+        let metrics = session_metrics(&session);
+        assert_eq!(metrics.replies, 4);
+        assert_eq!(metrics.timed_replies, 3);
+        assert_eq!(metrics.time_to_first_token.p50_ms, 300.0);
+        assert_eq!(metrics.generation_time.max_ms, 3000.0);
+        assert_eq!(metrics.total_generation_time_ms, 6000);
+        assert_eq!(metrics.prompt_tokens, 400);
+        assert_eq!(metrics.completion_tokens, 130);
+        assert_eq!(metrics.mean_tokens_per_second, Some(20.0));
+        assert_eq!(metrics.models, BTreeMap::from([("phi".to_string(), 1), ("qwen".to_string(), 2)]));
+    }
+}
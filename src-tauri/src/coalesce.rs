@@ -0,0 +1,150 @@
+//! Batch streamed tokens into larger chunks before they are emitted.
+//!
+//! Models stream a few characters per token, and every token becomes an
+//! event. Screen readers re-announce on each update and low-end machines spend
+//! their time re-rendering. With `streaming.coalesce` set, tokens are buffered
+//! and emitted per sentence (or line) or every `streaming.interval_ms`. The
+//! emitter also flushes on a timer, so a stalled model never holds text back
+//! longer than the interval.
+
+use std::time::Duration;
+
+use serde::{ Deserialize, Serialize };
+use tokio::time::Instant;
+
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoalesceMode {
+    /// Emit every token as it arrives
+    #[default]
+    Off,
+    /// Emit whole sentences and lines
+    Sentence,
+    /// Emit whatever arrived every `interval_ms`
+    Interval,
+}
+
+/// Characters that end a sentence when followed by whitespace
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', ':', ';', '。', '！', '？'];
+
+pub struct TokenCoalescer {
+    mode: CoalesceMode,
+    interval: Duration,
+    max_chars: usize,
+    buffer: String,
+    last_flush: Instant,
+}
+
+impl TokenCoalescer {
+    pub fn new(mode: CoalesceMode, interval: Duration, max_chars: usize) -> Self {
+        Self { mode, interval, max_chars, buffer: String::new(), last_flush: Instant::now() }
+    }
+
+    pub fn from_settings() -> Self {
+        let streaming = settings::current().streaming;
+        Self::new(streaming.coalesce, Duration::from_millis(streaming.interval_ms.max(1)), streaming.max_chars.max(1))
+    }
+
+    /// Add a token; returns the text to emit now, if any
+    pub fn push(&mut self, token: &str) -> Option<String> {
+        if self.mode == CoalesceMode::Off {
+            return Some(token.to_string()).filter(|t| !t.is_empty());
+        }
+        self.buffer.push_str(token);
+
+        if self.buffer.len() >= self.max_chars || self.last_flush.elapsed() >= self.interval {
+            return self.flush();
+        }
+        if self.mode == CoalesceMode::Sentence {
+            if let Some(end) = last_sentence_end(&self.buffer) {
+                let rest = self.buffer.split_off(end);
+                let chunk = std::mem::replace(&mut self.buffer, rest);
+                self.last_flush = Instant::now();
+                return Some(chunk);
+            }
+        }
+        None
+    }
+
+    /// Everything buffered so far
+    pub fn flush(&mut self) -> Option<String> {
+        self.last_flush = Instant::now();
+        if self.buffer.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.buffer))
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// When the buffered text is due, for the emitter's flush timer
+    pub fn deadline(&self) -> Instant {
+        self.last_flush + self.interval
+    }
+}
+
+/// Byte offset just past the last sentence end (terminator plus whitespace) or newline
+fn last_sentence_end(text: &str) -> Option<usize> {
+    let mut end = None;
+    let mut previous: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        let after = i + c.len_utf8();
+        if c == '\n' || (c.is_whitespace() && previous.is_some_and(|p| SENTENCE_ENDS.contains(&p))) {
+            end = Some(after);
+        } else if matches!(c, '。' | '！' | '？') {
+            // CJK text puts no space after the terminator
+            end = Some(after);
+        }
+        previous = Some(c);
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coalescer(mode: CoalesceMode) -> TokenCoalescer {
+        TokenCoalescer::new(mode, Duration::from_secs(60), 1000)
+    }
+
+    #[test]
+    fn test_off_passes_tokens_through() {
+        let mut c = coalescer(CoalesceMode::Off);
+        assert_eq!(c.push("Hel").as_deref(), Some("Hel"));
+        assert!(!c.has_pending());
+    }
+
+    #[test]
+    fn test_sentence_mode_emits_whole_sentences() {
+        let mut c = coalescer(CoalesceMode::Sentence);
+        assert_eq!(c.push("Pi is 3."), None);
+        // A period inside a number is not a sentence end
+        assert_eq!(c.push("14 roughly."), None);
+        assert_eq!(c.push(" Next"), Some("Pi is 3.14 roughly. ".to_string()));
+        assert_eq!(c.push(" line\nmore"), Some("Next line\n".to_string()));
+        assert_eq!(c.flush().as_deref(), Some("more"));
+        assert_eq!(c.flush(), None);
+    }
+
+    #[test]
+    fn test_max_chars_forces_a_flush() {
+        let mut c = TokenCoalescer::new(CoalesceMode::Sentence, Duration::from_secs(60), 8);
+        assert_eq!(c.push("abcd"), None);
+        assert_eq!(c.push("efgh").as_deref(), Some("abcdefgh"));
+    }
+
+    #[test]
+    fn test_interval_mode_waits_for_the_interval() {
+        let mut c = coalescer(CoalesceMode::Interval);
+        assert_eq!(c.push("One. "), None);
+        assert!(c.has_pending());
+
+        let mut c = TokenCoalescer::new(CoalesceMode::Interval, Duration::ZERO, 1000);
+        assert_eq!(c.push("now").as_deref(), Some("now"));
+    }
+}
@@ -0,0 +1,257 @@
+//! Run short Python snippets written by the assistant.
+//!
+//! Snippets run in an interpreter compiled to WASI (see `plugins::wasm`), so
+//! they can compute and reshape data but cannot reach the network or any file
+//! outside a scratch directory that is deleted afterwards. The interpreter
+//! lives in `~/.sparrow/runtimes` and is downloaded on first use.
+//!
+//! A runtime is only downloaded when its SHA-256 is pinned in the
+//! `code_sandbox` settings, and the file is checked against that digest on
+//! download and again whenever it is compiled. JavaScript is not supported:
+//! there is no QuickJS WASI build with a published digest to pin.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::Serialize;
+use sha2::{ Digest, Sha256 };
+use tokio::io::AsyncWriteExt;
+
+use crate::plugins::wasm;
+use crate::{ constants, paths, settings };
+
+/// Guest directory holding the script; also the program's working space
+const SCRATCH_MOUNT: &str = "/sandbox";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    Python,
+}
+
+impl Language {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_lowercase().as_str() {
+            "python" | "py" => Ok(Language::Python),
+            "javascript" | "js" => Err(
+                "JavaScript is not supported in the sandbox; write the program in Python instead".to_string()
+            ),
+            other => Err(format!("Unsupported language: {} (use python)", other)),
+        }
+    }
+
+    fn runtime_file(self) -> &'static str {
+        match self {
+            Language::Python => "python.wasm",
+        }
+    }
+
+    fn script_file(self) -> &'static str {
+        match self {
+            Language::Python => "main.py",
+        }
+    }
+
+    fn runtime_url(self, sandbox: &settings::CodeSandboxSettings) -> Option<String> {
+        match self {
+            Language::Python => Some(sandbox.python_runtime_url.clone()).filter(|url| !url.is_empty()),
+        }
+    }
+
+    fn runtime_sha256(self, sandbox: &settings::CodeSandboxSettings) -> Option<String> {
+        let sha256 = match self {
+            Language::Python => &sandbox.python_runtime_sha256,
+        };
+        sha256.as_deref().map(str::trim).filter(|sha256| !sha256.is_empty()).map(str::to_lowercase)
+    }
+
+    fn settings_prefix(self) -> &'static str {
+        match self {
+            Language::Python => "python",
+        }
+    }
+
+    /// Interpreter argv for running the script
+    fn args(self) -> Vec<String> {
+        let script = format!("{}/{}", SCRATCH_MOUNT, self.script_file());
+        match self {
+            Language::Python => vec!["python".to_string(), "-I".to_string(), script],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeOutput {
+    pub language: Language,
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    /// Output was cut to `constants::SANDBOX_OUTPUT_CHARS`
+    pub truncated: bool,
+}
+
+/// The first `max_chars` characters of `text`, and whether anything was cut
+fn truncate_chars(text: &str, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (text[..end].to_string(), true),
+        None => (text.to_string(), false),
+    }
+}
+
+/// Refuse runtime bytes whose SHA-256 is not `expected`
+fn check_sha256(bytes: &[u8], expected: &str) -> Result<(), String> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual != expected {
+        return Err(format!("The code runtime failed verification: SHA256 is {}, expected {}", actual, expected));
+    }
+    Ok(())
+}
+
+async fn download_runtime(url: &str, expected_sha256: &str, path: &PathBuf) -> Result<(), String> {
+    log_operation_start!("Download code runtime", url = %url);
+
+    let response = crate::http::client()?.get(url).send().await
+        .map_err(|e| format!("Failed to download runtime: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download runtime: HTTP {}", response.status()));
+    }
+
+    let partial = path.with_extension("wasm.part");
+    let written: Result<String, String> = async {
+        let mut file = tokio::fs::File::create(&partial).await
+            .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+        let mut hasher = Sha256::new();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to download runtime: {}", e))?;
+            file.write_all(&chunk).await
+                .map_err(|e| format!("Failed to write runtime: {}", e))?;
+            hasher.update(&chunk);
+        }
+        file.flush().await.map_err(|e| format!("Failed to write runtime: {}", e))?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }.await;
+
+    let actual = match written {
+        Ok(actual) => actual,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+    };
+    if actual != expected_sha256 {
+        let _ = tokio::fs::remove_file(&partial).await;
+        let e = format!("The downloaded runtime failed verification: SHA256 is {}, expected {}", actual, expected_sha256);
+        log_operation_error!("Download code runtime", &e, url = %url);
+        return Err(e);
+    }
+
+    tokio::fs::rename(&partial, path).await
+        .map_err(|e| format!("Failed to install runtime: {}", e))?;
+    log_operation_success!("Download code runtime", path = %path.display());
+    Ok(())
+}
+
+/// Path of the interpreter for `language`, downloading it if needed
+async fn ensure_runtime(language: Language, sandbox: &settings::CodeSandboxSettings) -> Result<PathBuf, String> {
+    // Two first runs at once would download the same file twice
+    static INSTALL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _guard = INSTALL.lock().await;
+
+    let dir = paths::get_runtimes_dir().map_err(|e| e.to_string())?;
+    let path = dir.join(language.runtime_file());
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    match (language.runtime_url(sandbox), language.runtime_sha256(sandbox)) {
+        (Some(url), Some(sha256)) => {
+            download_runtime(&url, &sha256, &path).await?;
+            Ok(path)
+        }
+        (Some(url), None) => Err(format!(
+            "No SHA-256 is pinned for the {:?} runtime. Set code_sandbox.{}_runtime_sha256 to the digest published for {}.",
+            language,
+            language.settings_prefix(),
+            url
+        )),
+        (None, _) => Err(format!(
+            "No {:?} runtime installed. Place a WASI build at {} or set code_sandbox.{}_runtime_url and code_sandbox.{}_runtime_sha256 in settings.",
+            language,
+            path.display(),
+            language.settings_prefix(),
+            language.settings_prefix()
+        )),
+    }
+}
+
+/// Run `code` with `stdin` as its standard input
+pub async fn run_code(language: Language, code: &str, stdin: Option<&str>) -> Result<CodeOutput, String> {
+    let limits = settings::current().code_sandbox;
+    let runtime = ensure_runtime(language, &limits).await?;
+    // A runtime placed by hand is trusted as it is unless a digest is pinned
+    let expected_sha256 = language.runtime_sha256(&limits);
+
+    let scratch = std::env::temp_dir().join(format!("sparrow-sandbox-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&scratch).await
+        .map_err(|e| format!("Failed to create sandbox directory: {}", e))?;
+    tokio::fs::write(scratch.join(language.script_file()), code).await
+        .map_err(|e| format!("Failed to write script: {}", e))?;
+
+    let sandbox = wasm::Sandbox {
+        args: language.args(),
+        mounts: vec![wasm::Mount { host: scratch.clone(), guest: SCRATCH_MOUNT.to_string(), writable: true }],
+        env: vec![("HOME".to_string(), SCRATCH_MOUNT.to_string())],
+        max_memory_bytes: (limits.max_memory_mb.max(16) * 1024 * 1024) as usize,
+        timeout: Duration::from_millis(limits.timeout_ms.max(100)),
+        fuel: Some(limits.fuel),
+        max_output_bytes: constants::PLUGIN_MAX_OUTPUT_BYTES,
+    };
+    let input = stdin.unwrap_or_default().as_bytes().to_vec();
+
+    let verify = move |bytes: &[u8]| match &expected_sha256 {
+        Some(expected) => check_sha256(bytes, expected),
+        None => Ok(()),
+    };
+    let result = tokio::task::spawn_blocking(move || wasm::run(&runtime, &input, &sandbox, &verify)).await
+        .map_err(|e| format!("Sandbox task failed: {}", e));
+    let _ = tokio::fs::remove_dir_all(&scratch).await;
+    let output = result??;
+
+    let (stdout, stdout_cut) = truncate_chars(&String::from_utf8_lossy(&output.stdout), constants::SANDBOX_OUTPUT_CHARS);
+    let (stderr, stderr_cut) = truncate_chars(&output.stderr_text(), constants::SANDBOX_OUTPUT_CHARS);
+
+    Ok(CodeOutput {
+        language,
+        exit_code: output.exit_code,
+        stdout,
+        stderr,
+        truncated: stdout_cut || stderr_cut,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_parse() {
+        assert_eq!(Language::parse("Python").unwrap(), Language::Python);
+        assert!(Language::parse("js").unwrap_err().contains("not supported"));
+        assert!(Language::parse("bash").is_err());
+    }
+
+    #[test]
+    fn test_check_sha256() {
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert!(check_sha256(b"", empty).is_ok());
+        assert!(check_sha256(b"changed", empty).is_err());
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("héllo", 10), ("héllo".to_string(), false));
+        assert_eq!(truncate_chars("héllo", 2), ("hé".to_string(), true));
+    }
+}
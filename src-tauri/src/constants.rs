@@ -6,6 +6,12 @@ pub const DEFAULT_CHUNK_SIZE: usize = 1500;
 /// Increased from 200 to 300 (20% overlap) for better continuity
 pub const DEFAULT_CHUNK_OVERLAP: usize = 300;
 
+/// Chunks embedded per request during document ingestion
+pub const EMBEDDING_BATCH_SIZE: usize = 32;
+
+/// Documents written per sled batch by `store_documents`
+pub const VECTOR_STORE_WRITE_BATCH_SIZE: usize = 512;
+
 /// Default search result limit
 #[allow(dead_code)]
 pub const DEFAULT_SEARCH_LIMIT: usize = 10;
@@ -29,22 +35,54 @@ pub const OVMS_EXE_NAME: &str = "ovms.exe";
 /// OVMS config file name
 pub const OVMS_CONFIG_FILE: &str = "models_config.json";
 
-/// Default OVMS port
-#[allow(dead_code)]
+/// Default OVMS REST port; the port in use comes from `ovms::api_base()`
 pub const OVMS_DEFAULT_PORT: u16 = 1114;
 
-/// OVMS API base URL
-pub const OVMS_API_BASE: &str = "http://localhost:1114";
+/// Lines of OVMS output kept in memory for the log viewer
+pub const OVMS_LOG_BUFFER_LINES: usize = 5000;
+
+/// Size at which `ovms.log` is rotated
+pub const OVMS_LOG_FILE_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Backups of `models_config.json` kept before the oldest is removed
+pub const OVMS_CONFIG_BACKUPS_KEPT: usize = 20;
 
 /// OVMS OpenAI-compatible API path
 pub const OVMS_OPENAI_PATH: &str = "/v3";
 
+/// How long to wait for OVMS to report a model AVAILABLE after a config reload (seconds)
+pub const MODEL_LOAD_TIMEOUT_SECS: u64 = 600;
+
+/// Interval between OVMS REST readiness probes at startup (milliseconds)
+pub const OVMS_STARTUP_POLL_MS: u64 = 500;
+
+/// Interval between model status polls while a model loads (milliseconds)
+pub const MODEL_STATUS_POLL_MS: u64 = 500;
+
+/// Default step budget for agent tasks (one step = one model call)
+pub const DEFAULT_AGENT_MAX_STEPS: u32 = 8;
+
+/// Hard upper bound on agent task steps
+pub const MAX_AGENT_STEPS: u32 = 32;
+
+/// Default wall-clock budget for agent tasks (seconds)
+pub const DEFAULT_AGENT_TIMEOUT_SECS: u64 = 300;
+
+/// Hard upper bound on tool-call rounds in one chat reply
+pub const MAX_CHAT_TOOL_ROUNDS: u32 = 16;
+
+/// Stop sequences one chat request may carry, as in the OpenAI API
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
 /// Default embedding model name
 pub const DEFAULT_EMBEDDING_MODEL: &str = "Qwen3-Embedding-0.6B-int8-ov";
 
 /// OpenVINO organization name on HuggingFace
 pub const OPENVINO_ORG: &str = "OpenVINO";
 
+/// Organization directory for models imported from a local folder
+pub const LOCAL_MODEL_ORG: &str = "local";
+
 /// Default chat session title
 pub const DEFAULT_CHAT_TITLE: &str = "New Chat";
 
@@ -54,15 +92,55 @@ pub const MAX_CHAT_TITLE_LENGTH: usize = 60;
 /// Minimum chat title length before truncation
 pub const MIN_CHAT_TITLE_LENGTH: usize = 40;
 
+/// Characters of a chat session embedded for related-session search
+pub const SESSION_SUMMARY_MAX_CHARS: usize = 2000;
+
+/// How often a streaming answer is checkpointed to its draft file (milliseconds)
+pub const DRAFT_CHECKPOINT_INTERVAL_MS: u64 = 2000;
+
+/// Flashcards generated per request when no count is given
+pub const DEFAULT_FLASHCARD_COUNT: usize = 20;
+
+/// Most flashcards one request may generate
+pub const MAX_FLASHCARD_COUNT: usize = 200;
+
 /// Download progress emit interval (milliseconds)
 pub const DOWNLOAD_PROGRESS_INTERVAL_MS: u128 = 100;
 
 /// Download timeout (seconds)
 pub const DOWNLOAD_TIMEOUT_SECS: u64 = 600;
 
+/// Files of one model downloaded at once by default
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+
+/// Upper bound on concurrent file downloads per model
+pub const MAX_DOWNLOAD_CONCURRENCY: usize = 8;
+
+/// Free space kept on the drive beyond the model's own size
+pub const DOWNLOAD_DISK_MARGIN_BYTES: u64 = 512 * 1024 * 1024;
+
 /// Maximum download retries
 pub const MAX_DOWNLOAD_RETRIES: u8 = 3;
 
+/// Releases of the app itself, checked for updates
+pub const APP_RELEASES_URL: &str = "https://api.github.com/repos/ozcode95/SparrowAI/releases";
+
+/// Memory a plugin instance may use unless its manifest asks for less
+pub const PLUGIN_MAX_MEMORY_MB: u64 = 256;
+
+/// Wall-clock limit for one plugin call unless its manifest asks for less
+pub const PLUGIN_MAX_TIMEOUT_MS: u64 = 30_000;
+
+/// Cap on what a plugin may write to stdout per call
+pub const PLUGIN_MAX_OUTPUT_BYTES: usize = 8 * 1024 * 1024;
+
+/// CPython compiled to WASI, with the standard library embedded
+pub const SANDBOX_PYTHON_RUNTIME_URL: &str =
+    "https://github.com/vmware-labs/webassembly-language-runtimes/releases/download/python%2F3.12.0%2B20231211-040d5a6/python-3.12.0.wasm";
+
+/// Characters of sandboxed program output returned to the model
+pub const SANDBOX_OUTPUT_CHARS: usize = 16_000;
+
 /// Log retention days
 pub const LOG_RETENTION_DAYS: i64 = 30;
 
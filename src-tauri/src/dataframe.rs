@@ -0,0 +1,306 @@
+//! Local CSV analysis for the `load_csv` and `query_dataframe` tools.
+//!
+//! A loaded file is kept as a Polars lazy scan under a short name, so each
+//! query reads only what it needs and no data leaves the machine. Queries are
+//! JSON (filters, selection, grouping, aggregation, sorting, limit) compiled
+//! to Polars expressions; the model cannot run arbitrary code this way.
+
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+
+use parking_lot::Mutex;
+use polars::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Rows returned when a query sets no limit
+const DEFAULT_ROW_LIMIT: usize = 50;
+
+/// Rows a query may return at most
+const MAX_ROW_LIMIT: usize = 500;
+
+/// Rows inferred before a column type is fixed
+const SCHEMA_INFERENCE_ROWS: usize = 1000;
+
+struct LoadedFrame {
+    path: PathBuf,
+    frame: LazyFrame,
+}
+
+static FRAMES: OnceLock<Mutex<HashMap<String, LoadedFrame>>> = OnceLock::new();
+
+fn frames() -> &'static Mutex<HashMap<String, LoadedFrame>> {
+    FRAMES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum FilterOp {
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = "contains")]
+    Contains,
+    #[serde(rename = "is_null")]
+    IsNull,
+    #[serde(rename = "is_not_null")]
+    IsNotNull,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    pub column: String,
+    pub op: FilterOp,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggFunction {
+    Sum,
+    Mean,
+    Median,
+    Min,
+    Max,
+    Count,
+    NUnique,
+    Std,
+    First,
+    Last,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Aggregation {
+    pub column: String,
+    pub function: AggFunction,
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SortKey {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// A query over one loaded frame; steps apply in field order
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataframeQuery {
+    /// All filters must match
+    pub filters: Vec<Filter>,
+    /// Columns to keep when not aggregating
+    pub select: Vec<String>,
+    pub group_by: Vec<String>,
+    pub aggregations: Vec<Aggregation>,
+    pub sort: Vec<SortKey>,
+    pub limit: Option<usize>,
+}
+
+fn literal(value: &Value) -> Result<Expr, String> {
+    match value {
+        Value::Bool(b) => Ok(lit(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(lit(i)),
+            None => Ok(lit(n.as_f64().unwrap_or(f64::NAN))),
+        },
+        Value::String(s) => Ok(lit(s.clone())),
+        _ => Err("Filter values must be a string, number or boolean".to_string()),
+    }
+}
+
+fn filter_expr(filter: &Filter) -> Result<Expr, String> {
+    let column = col(filter.column.as_str());
+    let value = || {
+        filter.value.as_ref()
+            .ok_or_else(|| format!("Filter on '{}' needs a value", filter.column))
+            .and_then(literal)
+    };
+    Ok(match filter.op {
+        FilterOp::Eq => column.eq(value()?),
+        FilterOp::Ne => column.neq(value()?),
+        FilterOp::Gt => column.gt(value()?),
+        FilterOp::Ge => column.gt_eq(value()?),
+        FilterOp::Lt => column.lt(value()?),
+        FilterOp::Le => column.lt_eq(value()?),
+        FilterOp::Contains => match &filter.value {
+            Some(Value::String(text)) => column.str().contains_literal(lit(text.clone())),
+            _ => return Err(format!("'contains' on '{}' needs a string value", filter.column)),
+        },
+        FilterOp::IsNull => column.is_null(),
+        FilterOp::IsNotNull => column.is_not_null(),
+    })
+}
+
+fn aggregation_expr(aggregation: &Aggregation) -> Expr {
+    let column = col(aggregation.column.as_str());
+    let (expr, suffix) = match aggregation.function {
+        AggFunction::Sum => (column.sum(), "sum"),
+        AggFunction::Mean => (column.mean(), "mean"),
+        AggFunction::Median => (column.median(), "median"),
+        AggFunction::Min => (column.min(), "min"),
+        AggFunction::Max => (column.max(), "max"),
+        AggFunction::Count => (column.count(), "count"),
+        AggFunction::NUnique => (column.n_unique(), "n_unique"),
+        AggFunction::Std => (column.std(1), "std"),
+        AggFunction::First => (column.first(), "first"),
+        AggFunction::Last => (column.last(), "last"),
+    };
+    let alias = aggregation.alias.clone().unwrap_or_else(|| format!("{}_{}", aggregation.column, suffix));
+    expr.alias(alias.as_str())
+}
+
+fn build_query(frame: LazyFrame, query: &DataframeQuery) -> Result<LazyFrame, String> {
+    let mut frame = frame;
+    for filter in &query.filters {
+        frame = frame.filter(filter_expr(filter)?);
+    }
+
+    let columns = |names: &[String]| names.iter().map(|name| col(name.as_str())).collect::<Vec<_>>();
+    if !query.aggregations.is_empty() || !query.group_by.is_empty() {
+        let aggregations: Vec<Expr> = query.aggregations.iter().map(aggregation_expr).collect();
+        frame = if query.group_by.is_empty() {
+            frame.select(aggregations)
+        } else {
+            frame.group_by(columns(&query.group_by)).agg(aggregations)
+        };
+    } else if !query.select.is_empty() {
+        frame = frame.select(columns(&query.select));
+    }
+
+    if !query.sort.is_empty() {
+        let by: Vec<Expr> = query.sort.iter().map(|key| col(key.column.as_str())).collect();
+        let descending: Vec<bool> = query.sort.iter().map(|key| key.descending).collect();
+        frame = frame.sort_by_exprs(by, SortMultipleOptions::default().with_order_descending_multi(descending));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_ROW_LIMIT).clamp(1, MAX_ROW_LIMIT);
+    Ok(frame.limit(limit as IdxSize))
+}
+
+fn to_csv(frame: &mut DataFrame) -> Result<String, String> {
+    let mut buffer = Vec::new();
+    CsvWriter::new(&mut buffer)
+        .finish(frame)
+        .map_err(|e| format!("Failed to render result: {}", e))?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+fn scan_csv(path: &Path) -> Result<LazyFrame, String> {
+    let separator = match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+        Some("csv") => b',',
+        Some("tsv") | Some("tab") => b'\t',
+        _ => return Err(format!("Not a CSV or TSV file: {}", path.display())),
+    };
+    LazyCsvReader::new(path)
+        .with_has_header(true)
+        .with_separator(separator)
+        .with_infer_schema_length(Some(SCHEMA_INFERENCE_ROWS))
+        .finish()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Load `path` under `name` (the file stem by default); returns a summary
+/// with the columns, their types, the row count and the first rows
+pub fn load_csv(path: &Path, name: Option<&str>) -> Result<String, String> {
+    if !path.is_file() {
+        return Err(format!("File does not exist: {}", path.display()));
+    }
+    let name = match name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => name.to_string(),
+        None => path.file_stem().and_then(|s| s.to_str()).unwrap_or("data").to_string(),
+    };
+
+    let frame = scan_csv(path)?;
+    let schema = frame.clone().collect_schema()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let rows = frame.clone().select([len()]).collect()
+        .map_err(|e| format!("Failed to count rows: {}", e))?
+        .get_columns().first()
+        .and_then(|column| column.get(0).ok())
+        .and_then(|value| value.extract::<u64>())
+        .unwrap_or(0);
+    let mut head = frame.clone().limit(5).collect()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let columns: Vec<String> = schema.iter().map(|(column, dtype)| format!("- {} ({})", column, dtype)).collect();
+    let summary = format!(
+        "Loaded '{}' from {}: {} rows, {} columns\n\nColumns:\n{}\n\nFirst rows:\n{}",
+        name,
+        path.display(),
+        rows,
+        columns.len(),
+        columns.join("\n"),
+        to_csv(&mut head)?
+    );
+
+    frames().lock().insert(name, LoadedFrame { path: path.to_path_buf(), frame });
+    Ok(summary)
+}
+
+/// Run `query` against the frame loaded as `name`; the result is CSV
+pub fn query(name: &str, query: &DataframeQuery) -> Result<String, String> {
+    let (path, frame) = {
+        let frames = frames().lock();
+        let loaded = frames.get(name).ok_or_else(|| {
+            let mut names: Vec<&String> = frames.keys().collect();
+            names.sort();
+            format!("No dataframe named '{}'. Loaded: {:?}. Call load_csv first.", name, names)
+        })?;
+        (loaded.path.clone(), loaded.frame.clone())
+    };
+
+    let mut result = build_query(frame, query)?
+        .collect()
+        .map_err(|e| format!("Query on {} failed: {}", path.display(), e))?;
+    Ok(format!("{} rows\n{}", result.height(), to_csv(&mut result)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sales() -> LazyFrame {
+        df!(
+            "city" => &["Oslo", "Bergen", "Oslo", "Trondheim", "Bergen"],
+            "amount" => &[10i64, 20, 30, 5, 40]
+        ).unwrap().lazy()
+    }
+
+    #[test]
+    fn test_query_groups_sorts_and_limits() {
+        let query: DataframeQuery = serde_json::from_value(serde_json::json!({
+            "filters": [{ "column": "amount", "op": ">", "value": 5 }],
+            "group_by": ["city"],
+            "aggregations": [{ "column": "amount", "function": "sum" }],
+            "sort": [{ "column": "amount_sum", "descending": true }],
+            "limit": 1
+        })).unwrap();
+
+        let result = build_query(sales(), &query).unwrap().collect().unwrap();
+        assert_eq!(result.height(), 1);
+        assert_eq!(result.column("city").unwrap().str().unwrap().get(0), Some("Bergen"));
+    }
+
+    #[test]
+    fn test_query_rejects_unknown_fields_and_bad_filters() {
+        assert!(serde_json::from_value::<DataframeQuery>(serde_json::json!({ "code": "drop()" })).is_err());
+
+        let missing_value = Filter { column: "amount".to_string(), op: FilterOp::Gt, value: None };
+        assert!(filter_expr(&missing_value).is_err());
+    }
+}
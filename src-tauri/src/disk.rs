@@ -0,0 +1,48 @@
+//! Free space on the drive a path lives on.
+
+use std::path::{ Path, PathBuf };
+
+use sysinfo::Disks;
+
+/// The mount point with the longest prefix of `path` wins, so a drive mounted
+/// under another (e.g. `/home` on `/`) is picked over its parent
+fn space_on(path: &Path, mounts: &[(PathBuf, u64)]) -> Option<u64> {
+    mounts
+        .iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, available)| *available)
+}
+
+/// Bytes available on the drive holding `path`. The path need not exist yet;
+/// its nearest existing ancestor decides the drive.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    let resolved = existing.canonicalize().unwrap_or_else(|_| existing.to_path_buf());
+
+    let disks = Disks::new_with_refreshed_list();
+    let mounts: Vec<(PathBuf, u64)> = disks
+        .list()
+        .iter()
+        .map(|disk| (disk.mount_point().to_path_buf(), disk.available_space()))
+        .collect();
+    space_on(&resolved, &mounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space_on_picks_the_deepest_mount() {
+        let mounts = vec![
+            (PathBuf::from("/"), 10),
+            (PathBuf::from("/home"), 20),
+            (PathBuf::from("/home/user/data"), 30),
+        ];
+        assert_eq!(space_on(Path::new("/home/user/.sparrow/models"), &mounts), Some(20));
+        assert_eq!(space_on(Path::new("/home/user/data/models"), &mounts), Some(30));
+        assert_eq!(space_on(Path::new("/opt"), &mounts), Some(10));
+        assert_eq!(space_on(Path::new("/opt"), &[]), None);
+    }
+}
@@ -0,0 +1,296 @@
+//! Warns when the drive holding `~/.sparrow` runs low on space.
+//!
+//! Every `disk.check_interval_secs` the watchdog reads the free space on the
+//! data drive and compares it with `disk.warn_free_mb` and
+//! `disk.critical_free_mb`. Below a threshold it gathers cleanup candidates
+//! (compiled caches of loaded models, downloaded models OVMS does not serve
+//! and nothing else depends on, staged uploads nobody is waiting for) and
+//! emits the result as `disk-space`.
+//! A notification is shown only when the level gets worse, so a drive that
+//! stays low does not warn on every check.
+
+use std::fs;
+use std::path::Path;
+use std::time::{ Duration, SystemTime };
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager, State };
+
+use crate::huggingface::{ self, ModelType };
+use crate::messages::Message;
+use crate::state::AppState;
+use crate::{ constants, disk, model_cache, notifications, ovms, paths, settings, temp_files };
+
+/// Staged uploads younger than this may still be waiting to be ingested
+const TEMP_CANDIDATE_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskLevel {
+    /// Above both thresholds, or the free space could not be read
+    #[default]
+    Ok,
+    Low,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateKind {
+    /// Compiled cache of a loaded model, rebuilt on its next load (`clear_model_cache`)
+    ModelCache,
+    /// Downloaded model OVMS does not serve and RAG or a paired model does not
+    /// use (`delete_downloaded_model`)
+    UnloadedModel,
+    /// All staged uploads older than an hour, as one entry (`purge_temp_files`)
+    TempFiles,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupCandidate {
+    pub kind: CandidateKind,
+    /// Model id, or "tmp" for staged uploads
+    pub id: String,
+    pub size_bytes: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Payload of `disk-space` events
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiskStatus {
+    pub level: DiskLevel,
+    pub available_bytes: Option<u64>,
+    pub warn_free_bytes: u64,
+    pub critical_free_bytes: u64,
+    pub checked_at: Option<DateTime<Utc>>,
+    /// Largest first; empty while the level is ok
+    pub candidates: Vec<CleanupCandidate>,
+    pub reclaimable_bytes: u64,
+}
+
+fn classify(available_bytes: Option<u64>, warn_free_bytes: u64, critical_free_bytes: u64) -> DiskLevel {
+    match available_bytes {
+        Some(available) if available < critical_free_bytes => DiskLevel::Critical,
+        Some(available) if available < warn_free_bytes => DiskLevel::Low,
+        _ => DiskLevel::Ok,
+    }
+}
+
+fn timestamp(modified: SystemTime) -> Option<DateTime<Utc>> {
+    (modified > SystemTime::UNIX_EPOCH).then(|| modified.into())
+}
+
+/// Whether `model_id` is named in `names`, with or without its organization
+fn is_listed(names: &[String], model_id: &str) -> bool {
+    let name = model_id.rsplit('/').next().unwrap_or(model_id);
+    names.iter().any(|listed| listed == name || listed == model_id)
+}
+
+/// Models that are needed even while OVMS does not serve them: the RAG models
+/// and the draft models paired with text models
+async fn referenced_models() -> Vec<String> {
+    let mut models = vec![constants::DEFAULT_EMBEDDING_MODEL.to_string()];
+    models.extend(settings::current().rag.knowledge_graph.model.filter(|model| !model.is_empty()));
+    for (model_id, metadata) in huggingface::get_all_model_metadata().await.unwrap_or_default() {
+        if matches!(metadata.model_type, ModelType::Embedding | ModelType::Reranker) {
+            models.push(model_id);
+        }
+        models.extend(metadata.draft_model_id);
+    }
+    models
+}
+
+/// Candidates under `models_dir` and `temp_dir`; `served` are the names OVMS
+/// serves, `referenced` models that must be kept anyway
+fn find_candidates(models_dir: &Path, temp_dir: &Path, served: &[String], referenced: &[String]) -> Vec<CleanupCandidate> {
+    let is_served = |model_id: &str| is_listed(served, model_id);
+
+    // The cache of an unloaded model goes with the model, so only loaded ones are listed
+    let mut candidates: Vec<CleanupCandidate> = model_cache::find_caches(models_dir)
+        .into_iter()
+        .filter(|cache| is_served(&cache.model_id))
+        .map(|cache| CleanupCandidate {
+            kind: CandidateKind::ModelCache,
+            id: cache.model_id,
+            size_bytes: cache.size_bytes,
+            last_modified: timestamp(cache.modified),
+        })
+        .collect();
+
+    for org in fs::read_dir(models_dir).into_iter().flatten().flatten().filter(|entry| entry.path().is_dir()) {
+        for model in fs::read_dir(org.path()).into_iter().flatten().flatten().filter(|entry| entry.path().is_dir()) {
+            let model_id = format!("{}/{}", org.file_name().to_string_lossy(), model.file_name().to_string_lossy());
+            if is_served(&model_id) || is_listed(referenced, &model_id) {
+                continue;
+            }
+            let modified = model.metadata().and_then(|m| m.modified()).ok();
+            candidates.push(CleanupCandidate {
+                kind: CandidateKind::UnloadedModel,
+                size_bytes: temp_files::size_of(&model.path()),
+                last_modified: modified.and_then(timestamp),
+                id: model_id,
+            });
+        }
+    }
+
+    let cutoff = SystemTime::now().checked_sub(TEMP_CANDIDATE_MIN_AGE).unwrap_or(SystemTime::UNIX_EPOCH);
+    let (temp_bytes, temp_newest) = fs::read_dir(temp_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| Some((entry.path(), entry.metadata().and_then(|m| m.modified()).ok()?)))
+        .filter(|(_, modified)| *modified < cutoff)
+        .fold((0, SystemTime::UNIX_EPOCH), |(size, newest), (path, modified)| {
+            (size + temp_files::size_of(&path), newest.max(modified))
+        });
+    if temp_bytes > 0 {
+        candidates.push(CleanupCandidate {
+            kind: CandidateKind::TempFiles,
+            id: "tmp".to_string(),
+            size_bytes: temp_bytes,
+            last_modified: timestamp(temp_newest),
+        });
+    }
+
+    candidates.retain(|candidate| candidate.size_bytes > 0);
+    candidates.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+    candidates
+}
+
+/// Free space now, with cleanup candidates when below a threshold (or when `always_candidates`)
+async fn check(app: &AppHandle, always_candidates: bool) -> Result<DiskStatus, String> {
+    let config = settings::current().disk;
+    let data_dir = paths::get_sparrow_dir().map_err(|e| e.to_string())?;
+    let warn_free_bytes = config.warn_free_mb.saturating_mul(1024 * 1024);
+    let critical_free_bytes = config.critical_free_mb.saturating_mul(1024 * 1024);
+
+    let available_bytes = tokio::task::spawn_blocking(move || disk::available_space(&data_dir))
+        .await
+        .map_err(|e| format!("Failed to read free disk space: {}", e))?;
+    let level = classify(available_bytes, warn_free_bytes, critical_free_bytes);
+
+    let mut candidates = Vec::new();
+    if always_candidates || level != DiskLevel::Ok {
+        let models_dir = paths::get_models_dir().map_err(|e| e.to_string())?;
+        let temp_dir = paths::get_temp_dir().map_err(|e| e.to_string())?;
+        let served = ovms::get_loaded_models(app.clone()).await.unwrap_or_default();
+        let referenced = referenced_models().await;
+        candidates = tokio::task::spawn_blocking(move || find_candidates(&models_dir, &temp_dir, &served, &referenced))
+            .await
+            .map_err(|e| format!("Failed to look for cleanup candidates: {}", e))?;
+    }
+
+    Ok(DiskStatus {
+        level,
+        available_bytes,
+        warn_free_bytes,
+        critical_free_bytes,
+        checked_at: Some(Utc::now()),
+        reclaimable_bytes: candidates.iter().map(|candidate| candidate.size_bytes).sum(),
+        candidates,
+    })
+}
+
+fn notify(app: &AppHandle, status: &DiskStatus) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let code = match status.level {
+        DiskLevel::Critical => "disk.critical",
+        _ => "disk.low",
+    };
+    let title = Message::new(&format!("{}.title", code)).text;
+    let body = Message::new(code)
+        .param("available_bytes", status.available_bytes.unwrap_or(0))
+        .param("reclaimable_bytes", status.reclaimable_bytes)
+        .text;
+
+    if let Err(e) = app.notification().builder().title(&title).body(&body).show() {
+        log_warning!("Failed to show disk space notification", error = %e);
+    }
+    notifications::record(app, &title, &body, "disk");
+}
+
+/// Runs for the life of the app
+pub async fn run(app: AppHandle) {
+    let mut last_level = DiskLevel::Ok;
+
+    loop {
+        let config = settings::current().disk;
+        tokio::time::sleep(Duration::from_secs(config.check_interval_secs.max(1))).await;
+        if !config.watchdog {
+            continue;
+        }
+
+        let status = match check(&app, false).await {
+            Ok(status) => status,
+            Err(e) => {
+                log_warning!("Disk space check failed", error = %e);
+                continue;
+            }
+        };
+
+        if status.level > last_level {
+            log_warning!(
+                "Data drive is running low on space",
+                level = ?status.level,
+                available_bytes = ?status.available_bytes,
+                reclaimable_bytes = status.reclaimable_bytes
+            );
+            notify(&app, &status);
+        }
+        last_level = status.level;
+
+        *app.state::<AppState>().disk_status.lock() = status.clone();
+        let _ = app.emit("disk-space", status);
+    }
+}
+
+/// Check the data drive now; candidates are listed whatever the level
+#[tauri::command]
+pub async fn get_disk_status(app: AppHandle, state: State<'_, AppState>) -> Result<DiskStatus, String> {
+    let status = check(&app, true).await?;
+    *state.disk_status.lock() = status.clone();
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_against_thresholds() {
+        assert_eq!(classify(Some(500), 200, 100), DiskLevel::Ok);
+        assert_eq!(classify(Some(150), 200, 100), DiskLevel::Low);
+        assert_eq!(classify(Some(50), 200, 100), DiskLevel::Critical);
+        assert_eq!(classify(None, 200, 100), DiskLevel::Ok);
+        assert!(DiskLevel::Critical > DiskLevel::Low);
+    }
+
+    #[test]
+    fn test_find_candidates_skips_served_and_referenced_models() {
+        let root = std::env::temp_dir().join(format!("sparrow-disk-{}", uuid::Uuid::new_v4()));
+        let models_dir = root.join("models");
+        let temp_dir = root.join("tmp");
+        fs::create_dir_all(models_dir.join("OpenVINO/served/.ovms_cache")).unwrap();
+        fs::create_dir_all(models_dir.join("OpenVINO/idle")).unwrap();
+        fs::create_dir_all(models_dir.join("OpenVINO/draft")).unwrap();
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(models_dir.join("OpenVINO/served/.ovms_cache/blob"), b"cache").unwrap();
+        fs::write(models_dir.join("OpenVINO/served/model.bin"), b"weights").unwrap();
+        fs::write(models_dir.join("OpenVINO/idle/model.bin"), b"idle weights").unwrap();
+        fs::write(models_dir.join("OpenVINO/draft/model.bin"), b"draft weights").unwrap();
+
+        let candidates = find_candidates(&models_dir, &temp_dir, &["served".to_string()], &["OpenVINO/draft".to_string()]);
+        let found: Vec<(CandidateKind, &str, u64)> = candidates
+            .iter()
+            .map(|c| (c.kind, c.id.as_str(), c.size_bytes))
+            .collect();
+        assert_eq!(found, vec![
+            (CandidateKind::UnloadedModel, "OpenVINO/idle", 12),
+            (CandidateKind::ModelCache, "OpenVINO/served", 5),
+        ]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+//! On-disk drafts of answers being streamed, for crash recovery.
+//!
+//! The frontend saves an assistant message only once streaming ends, so a
+//! crash mid-generation used to lose the answer. While a chat streams,
+//! `DraftWriter` checkpoints the text so far to `~/.sparrow/drafts/<session>.json`
+//! every `DRAFT_CHECKPOINT_INTERVAL_MS`, and removes the file when the stream
+//! ends. Drafts still present at launch belong to interrupted answers;
+//! `restore_interrupted` appends them to their sessions marked as partial.
+
+use std::path::PathBuf;
+use std::time::{ Duration, Instant };
+
+use serde::{ Deserialize, Serialize };
+use tauri::{ AppHandle, Emitter };
+
+use crate::{ chat, constants, paths, storage };
+
+/// Marker added to a restored answer
+const INTERRUPTED_NOTE: &str = "_(partial, interrupted)_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Draft {
+    session_id: String,
+    model: String,
+    content: String,
+    started_at: i64,
+    updated_at: i64,
+}
+
+fn drafts_dir() -> Result<PathBuf, String> {
+    paths::get_drafts_dir().map_err(|e| e.to_string())
+}
+
+/// Session ids become file names; anything else is not a session of ours
+fn draft_path(session_id: &str) -> Option<PathBuf> {
+    let valid = !session_id.is_empty() && session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return None;
+    }
+    drafts_dir().ok().map(|dir| dir.join(format!("{}.json", session_id)))
+}
+
+/// Checkpoints one streamed answer; does nothing for temporary sessions
+pub struct DraftWriter {
+    path: Option<PathBuf>,
+    draft: Option<Draft>,
+    last_write: Option<Instant>,
+    written_len: usize,
+}
+
+impl DraftWriter {
+    pub fn new(session_id: Option<&str>, model: &str) -> Self {
+        let path = session_id.and_then(draft_path);
+        let now = chrono::Utc::now().timestamp_millis();
+        let draft = session_id.filter(|_| path.is_some()).map(|id| Draft {
+            session_id: id.to_string(),
+            model: model.to_string(),
+            content: String::new(),
+            started_at: now,
+            updated_at: now,
+        });
+        Self { path, draft, last_write: None, written_len: 0 }
+    }
+
+    /// Save `content` if it grew and the checkpoint interval has passed
+    pub async fn checkpoint(&mut self, content: &str) {
+        let (Some(path), Some(draft)) = (&self.path, &mut self.draft) else {
+            return;
+        };
+        let interval = Duration::from_millis(constants::DRAFT_CHECKPOINT_INTERVAL_MS);
+        if content.len() == self.written_len || self.last_write.is_some_and(|at| at.elapsed() < interval) {
+            return;
+        }
+
+        draft.content = content.to_string();
+        draft.updated_at = chrono::Utc::now().timestamp_millis();
+        match serde_json::to_string(draft) {
+            Ok(json) => {
+                if let Err(e) = storage::write_string(path, &json).await {
+                    tracing::warn!(error = %e, "Failed to write response draft");
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to serialize response draft"),
+        }
+        self.last_write = Some(Instant::now());
+        self.written_len = content.len();
+    }
+
+    /// The answer reached the frontend; the draft is no longer needed
+    pub async fn finish(self) {
+        if let (Some(path), Some(_)) = (self.path, self.last_write) {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+    }
+}
+
+fn interrupted_content(content: &str) -> String {
+    format!("{}\n\n{}", content.trim_end(), INTERRUPTED_NOTE)
+}
+
+/// Append drafts left by a crash to their sessions, then delete them
+pub async fn restore_interrupted(app: &AppHandle) {
+    let Ok(dir) = drafts_dir() else {
+        return;
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return;
+    };
+
+    let mut restored = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let draft = storage::read_string(&path).await
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str::<Draft>(&json).ok());
+        if let Some(draft) = draft.filter(|d| !d.content.trim().is_empty()) {
+            match chat::append_assistant_message(&draft.session_id, interrupted_content(&draft.content), draft.updated_at).await {
+                Ok(()) => {
+                    tracing::info!(session_id = %draft.session_id, chars = draft.content.len(), "Restored interrupted response");
+                    restored.push(draft.session_id);
+                }
+                Err(e) => tracing::warn!(session_id = %draft.session_id, error = %e, "Could not restore response draft"),
+            }
+        }
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+
+    if !restored.is_empty() {
+        let _ = app.emit("chat-drafts-restored", &restored);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupted_content_is_marked() {
+        assert_eq!(interrupted_content("Half an answer \n"), "Half an answer\n\n_(partial, interrupted)_");
+    }
+
+    #[test]
+    fn test_temporary_sessions_are_not_checkpointed() {
+        let writer = DraftWriter::new(None, "model");
+        assert!(writer.path.is_none());
+        let writer = DraftWriter::new(Some("../escape"), "model");
+        assert!(writer.draft.is_none());
+    }
+}
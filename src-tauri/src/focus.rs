@@ -0,0 +1,280 @@
+//! Focus sessions (Pomodoro-style timers).
+//!
+//! A session schedules its end and break-over notifications as one-time
+//! tasks, so they survive the window being closed and show up in the task
+//! list. While a session asks for quiet, the scheduler holds back every other
+//! task until it ends. Sessions are kept in `~/.sparrow/focus_sessions.json`
+//! for the assistant to answer questions like "how much did I focus this week?".
+
+use std::collections::BTreeMap;
+
+use chrono::{ DateTime, Duration, Local, Utc };
+use serde::{ Deserialize, Serialize };
+
+use crate::tasks::{ self, ActionType, Task, TriggerTime };
+use crate::{ paths, storage };
+
+/// `action_params` key marking a task as belonging to a focus session
+const FOCUS_SESSION_PARAM: &str = "focus_session";
+
+const MAX_DURATION_MINUTES: u32 = 240;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSession {
+    pub id: String,
+    pub goal: Option<String>,
+    pub duration_minutes: u32,
+    pub break_minutes: u32,
+    pub started_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Set when the session was stopped before `ends_at`
+    #[serde(default)]
+    pub stopped_at: Option<DateTime<Utc>>,
+    pub silence_background: bool,
+    /// One-time tasks showing the end and break-over notifications
+    #[serde(default)]
+    pub task_ids: Vec<String>,
+}
+
+impl FocusSession {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.stopped_at.is_none() && self.ends_at > now
+    }
+
+    /// Ran for its full duration
+    pub fn is_completed(&self, now: DateTime<Utc>) -> bool {
+        self.stopped_at.is_none() && self.ends_at <= now
+    }
+
+    /// Minutes actually spent in the session so far
+    pub fn focused_minutes(&self, now: DateTime<Utc>) -> f64 {
+        let end = self.stopped_at.unwrap_or(self.ends_at).min(now);
+        ((end - self.started_at).num_seconds().max(0) as f64) / 60.0
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FocusStorage {
+    sessions: Vec<FocusSession>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusStats {
+    pub days: u32,
+    pub sessions: usize,
+    pub completed: usize,
+    pub total_minutes: f64,
+    /// Minutes per local date (`YYYY-MM-DD`), oldest first
+    pub minutes_by_day: BTreeMap<String, f64>,
+    pub active: Option<FocusSession>,
+    /// Goals of the sessions in the period, newest first
+    pub goals: Vec<String>,
+}
+
+/// Serializes read-modify-write of the sessions file
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+async fn load() -> Result<FocusStorage, String> {
+    let path = paths::get_focus_sessions_path().map_err(|e| e.to_string())?;
+    match storage::read_string(&path).await.map_err(|e| format!("Failed to read focus sessions: {}", e))? {
+        Some(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse focus sessions: {}", e)),
+        None => Ok(FocusStorage::default()),
+    }
+}
+
+async fn save(store: &FocusStorage) -> Result<(), String> {
+    let path = paths::get_focus_sessions_path().map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("Failed to serialize focus sessions: {}", e))?;
+    storage::write_string(&path, &content).await
+        .map_err(|e| format!("Failed to write focus sessions: {}", e))
+}
+
+pub fn is_focus_task(task: &Task) -> bool {
+    task.action_params.get(FOCUS_SESSION_PARAM).is_some()
+}
+
+async fn schedule_notification(session_id: &str, name: &str, title: &str, message: String, at: DateTime<Utc>) -> Result<String, String> {
+    let task = tasks::create_task(
+        name.to_string(),
+        ActionType::ShowNotification { title: title.to_string(), message },
+        serde_json::json!({ FOCUS_SESSION_PARAM: session_id }),
+        TriggerTime::DateTime { datetime: at },
+        None,
+        Some(true),
+        None,
+    ).await?;
+    Ok(task.id)
+}
+
+fn compute_stats(sessions: &[FocusSession], days: u32, now: DateTime<Utc>) -> FocusStats {
+    let since = now - Duration::days(days as i64);
+    let recent: Vec<&FocusSession> = sessions.iter().filter(|s| s.started_at >= since).collect();
+
+    let mut minutes_by_day = BTreeMap::new();
+    for session in &recent {
+        let day = session.started_at.with_timezone(&Local).format("%Y-%m-%d").to_string();
+        *minutes_by_day.entry(day).or_insert(0.0) += session.focused_minutes(now);
+    }
+
+    FocusStats {
+        days,
+        sessions: recent.len(),
+        completed: recent.iter().filter(|s| s.is_completed(now)).count(),
+        total_minutes: recent.iter().map(|s| s.focused_minutes(now)).sum(),
+        minutes_by_day,
+        active: sessions.iter().find(|s| s.is_active(now)).cloned(),
+        goals: recent.iter().rev().filter_map(|s| s.goal.clone()).collect(),
+    }
+}
+
+/// Start a session of `duration_minutes`, followed by a break of `break_minutes`
+pub async fn start(
+    duration_minutes: u32,
+    goal: Option<String>,
+    break_minutes: Option<u32>,
+    silence_background: bool
+) -> Result<FocusSession, String> {
+    if duration_minutes == 0 || duration_minutes > MAX_DURATION_MINUTES {
+        return Err(format!("Duration must be between 1 and {} minutes", MAX_DURATION_MINUTES));
+    }
+    let goal = goal.map(|g| g.trim().to_string()).filter(|g| !g.is_empty());
+    let break_minutes = break_minutes.unwrap_or(5).min(60);
+
+    let _guard = STORE_LOCK.lock().await;
+    let mut store = load().await?;
+    let now = Utc::now();
+    if let Some(active) = store.sessions.iter().find(|s| s.is_active(now)) {
+        return Err(format!("A focus session is already running until {}", active.ends_at.with_timezone(&Local).format("%H:%M")));
+    }
+
+    let mut session = FocusSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        goal,
+        duration_minutes,
+        break_minutes,
+        started_at: now,
+        ends_at: now + Duration::minutes(duration_minutes as i64),
+        stopped_at: None,
+        silence_background,
+        task_ids: Vec::new(),
+    };
+
+    let done = match &session.goal {
+        Some(goal) => format!("{} minutes on \"{}\" done. Time for a {} minute break.", duration_minutes, goal, break_minutes),
+        None => format!("{} minutes done. Time for a {} minute break.", duration_minutes, break_minutes),
+    };
+    session.task_ids.push(schedule_notification(&session.id, "Focus session end", "Focus session complete", done, session.ends_at).await?);
+    if break_minutes > 0 {
+        let break_over = session.ends_at + Duration::minutes(break_minutes as i64);
+        session.task_ids.push(
+            schedule_notification(&session.id, "Focus break end", "Break over", "Ready for the next session?".to_string(), break_over).await?
+        );
+    }
+
+    if silence_background {
+        tasks::silence_background_tasks(Some(session.ends_at));
+    }
+
+    store.sessions.push(session.clone());
+    save(&store).await?;
+    tracing::info!(session = %session.id, minutes = duration_minutes, "Started focus session");
+    Ok(session)
+}
+
+/// Stop the running session early; its pending notifications are cancelled
+pub async fn stop() -> Result<FocusSession, String> {
+    let _guard = STORE_LOCK.lock().await;
+    let mut store = load().await?;
+    let now = Utc::now();
+    let session = store.sessions.iter_mut()
+        .find(|s| s.is_active(now))
+        .ok_or("No focus session is running")?;
+
+    session.stopped_at = Some(now);
+    for task_id in &session.task_ids {
+        // Already fired and auto-deleted when missing
+        let _ = tasks::delete_task(task_id.clone()).await;
+    }
+    if session.silence_background {
+        tasks::silence_background_tasks(None);
+    }
+
+    let session = session.clone();
+    save(&store).await?;
+    tracing::info!(session = %session.id, "Stopped focus session");
+    Ok(session)
+}
+
+pub async fn stats(days: Option<u32>) -> Result<FocusStats, String> {
+    let store = load().await?;
+    Ok(compute_stats(&store.sessions, days.unwrap_or(7).clamp(1, 365), Utc::now()))
+}
+
+/// Restore the background-task hold of a session still running at startup
+pub async fn restore() {
+    if let Ok(store) = load().await {
+        let now = Utc::now();
+        if let Some(session) = store.sessions.iter().find(|s| s.is_active(now) && s.silence_background) {
+            tasks::silence_background_tasks(Some(session.ends_at));
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn start_focus_session(
+    duration_minutes: u32,
+    goal: Option<String>,
+    break_minutes: Option<u32>,
+    silence_background: Option<bool>
+) -> Result<FocusSession, String> {
+    start(duration_minutes, goal, break_minutes, silence_background.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn stop_focus_session() -> Result<FocusSession, String> {
+    stop().await
+}
+
+#[tauri::command]
+pub async fn get_focus_stats(days: Option<u32>) -> Result<FocusStats, String> {
+    stats(days).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(started_minutes_ago: i64, duration: u32, stopped_after: Option<i64>, now: DateTime<Utc>) -> FocusSession {
+        let started_at = now - Duration::minutes(started_minutes_ago);
+        FocusSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            goal: Some(format!("goal {}", started_minutes_ago)),
+            duration_minutes: duration,
+            break_minutes: 5,
+            started_at,
+            ends_at: started_at + Duration::minutes(duration as i64),
+            stopped_at: stopped_after.map(|m| started_at + Duration::minutes(m)),
+            silence_background: false,
+            task_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_stats_count_partial_and_running_sessions() {
+        let now = Utc::now();
+        let sessions = vec![
+            session(60 * 24 * 10, 25, None, now), // outside the week
+            session(120, 25, None, now),          // completed
+            session(60, 25, Some(10), now),       // stopped after 10 minutes
+            session(5, 25, None, now),            // running
+        ];
+
+        let stats = compute_stats(&sessions, 7, now);
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(stats.completed, 1);
+        assert!((stats.total_minutes - 40.0).abs() < 0.1);
+        assert_eq!(stats.active.map(|s| s.id), Some(sessions[3].id.clone()));
+        assert_eq!(stats.goals.first().map(String::as_str), Some("goal 5"));
+    }
+}
@@ -1,21 +1,18 @@
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, debug};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::time::Duration;
+use base64::Engine;
 use uuid::Uuid;
-use async_openai::{
-    Client,
-    config::OpenAIConfig,
-    types::images::{
-        CreateImageRequestArgs,
-        ImageSize,
-        ImageResponseFormat,
-        ImageModel
-    },
-};
-
-use crate::paths;
-use crate::constants;
+
+use crate::{ paths, storage };
+
+/// Diffusion models can take minutes per image on a CPU
+const IMAGE_REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Serializes read-modify-write cycles on the metadata file
+static METADATA_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -30,6 +27,33 @@ pub struct GeneratedImage {
     pub size: String,
     #[serde(skip_serializing_if = "Option::is_none", alias = "num_inference_steps")]
     pub num_inference_steps: Option<i32>,
+    /// Seed the image was sampled with; generating again with it and the
+    /// same prompt and parameters reproduces the image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guidance_scale: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+/// Generation parameters of `generate_image`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageParams {
+    pub model_id: String,
+    /// "WIDTHxHEIGHT", 512x512 when unset
+    pub size: Option<String>,
+    pub num_inference_steps: Option<i32>,
+    /// Random when unset; the seed used is saved either way
+    pub seed: Option<u64>,
+    pub negative_prompt: Option<String>,
+    pub guidance_scale: Option<f32>,
+    pub reference_images: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,16 +69,26 @@ impl Default for GeneratedImagesStorage {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct ImageResponse {
+    data: Vec<ImageData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageData {
+    b64_json: Option<String>,
+}
+
 fn get_images_dir() -> Result<PathBuf, String> {
     let dir = paths::get_sparrow_dir()
         .map_err(|e| e.to_string())?
         .join("images");
-    
+
     if !dir.exists() {
         fs::create_dir_all(&dir)
             .map_err(|e| format!("Failed to create images directory: {}", e))?;
     }
-    
+
     Ok(dir)
 }
 
@@ -63,168 +97,306 @@ fn get_images_metadata_path() -> Result<PathBuf, String> {
     Ok(dir.join("metadata.json"))
 }
 
-fn load_images_metadata() -> Result<GeneratedImagesStorage, String> {
+async fn load_images_metadata() -> Result<GeneratedImagesStorage, String> {
     let path = get_images_metadata_path()?;
-    
-    if !path.exists() {
+
+    let Some(contents) = storage::read_string(&path).await
+        .map_err(|e| format!("Failed to read images metadata: {}", e))? else {
         return Ok(GeneratedImagesStorage::default());
-    }
-    
-    let contents = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read images metadata: {}", e))?;
-    
+    };
+
     serde_json::from_str(&contents)
         .map_err(|e| format!("Failed to parse images metadata: {}", e))
 }
 
-fn save_images_metadata(storage: &GeneratedImagesStorage) -> Result<(), String> {
+async fn save_images_metadata(images: &GeneratedImagesStorage) -> Result<(), String> {
     let path = get_images_metadata_path()?;
-    
-    let json = serde_json::to_string_pretty(storage)
+
+    let json = serde_json::to_string_pretty(images)
         .map_err(|e| format!("Failed to serialize images metadata: {}", e))?;
-    
-    fs::write(&path, json)
+
+    storage::write_string(&path, &json).await
         .map_err(|e| format!("Failed to write images metadata: {}", e))?;
-    
+
     Ok(())
 }
 
+/// Load, change and save the metadata while holding the lock
+async fn modify_images_metadata<T>(
+    change: impl FnOnce(&mut GeneratedImagesStorage) -> Result<T, String>
+) -> Result<T, String> {
+    let _guard = METADATA_LOCK.lock().await;
+    let mut images = load_images_metadata().await?;
+    let result = change(&mut images)?;
+    save_images_metadata(&images).await?;
+    Ok(result)
+}
+
+/// Check a "WIDTHxHEIGHT" size and return it normalized
+fn parse_size(size: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid image size '{}', expected WIDTHxHEIGHT", size);
+    let (width, height) = size.trim().to_ascii_lowercase()
+        .split_once('x')
+        .map(|(w, h)| (w.trim().parse::<u32>(), h.trim().parse::<u32>()))
+        .ok_or_else(invalid)?;
+    match (width, height) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok(format!("{}x{}", width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Body of an OVMS images/generations request. Besides the OpenAI fields,
+/// OVMS reads the diffusion parameters, with the seed as `rng_seed`.
+fn image_request_body(model: &str, prompt: &str, size: &str, seed: u64, params: &ImageParams) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "model": model,
+        "prompt": prompt,
+        "size": size,
+        "n": 1,
+        "response_format": "b64_json",
+        "rng_seed": seed,
+    });
+    if let Some(steps) = params.num_inference_steps {
+        body["num_inference_steps"] = steps.into();
+    }
+    if let Some(negative_prompt) = params.negative_prompt.as_deref().filter(|p| !p.trim().is_empty()) {
+        body["negative_prompt"] = negative_prompt.into();
+    }
+    if let Some(guidance_scale) = params.guidance_scale {
+        body["guidance_scale"] = guidance_scale.into();
+    }
+    body
+}
+
+/// Only files in the images directory are removed with their entry
+fn remove_image_file(images_dir: &Path, image: &GeneratedImage) {
+    let path = PathBuf::from(&image.image_path);
+    if !path.starts_with(images_dir) {
+        info!("Keeping {} outside the images directory", path.display());
+        return;
+    }
+    if let Err(e) = fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            error!("Failed to delete image file {}: {}", path.display(), e);
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn generate_image(
-    model_id: String,
+    app: tauri::AppHandle,
     prompt: String,
-    size: String,
-    num_inference_steps: i32,
-    reference_images: Vec<String>,
-) -> Result<serde_json::Value, String> {
-    info!("Generating image with model: {}, size: {}, steps: {}", model_id, size, num_inference_steps);
+    params: ImageParams,
+) -> Result<GeneratedImage, String> {
+    if prompt.trim().is_empty() {
+        return Err("Please enter a prompt".to_string());
+    }
+    let size = parse_size(params.size.as_deref().unwrap_or("512x512"))?;
+    let seed = params.seed.unwrap_or_else(|| u64::from(Uuid::new_v4().as_u128() as u32));
+
+    info!("Generating image with model: {}, size: {}, steps: {:?}, seed: {}",
+        params.model_id, size, params.num_inference_steps, seed);
     debug!("Prompt: {}", prompt);
-    debug!("Reference images: {:?}", reference_images);
+    debug!("Reference images: {:?}", params.reference_images);
+
+    crate::ensure_ovms_initialized(&app).await;
 
-    let mut model_id = model_id;
-    if model_id.starts_with("OpenVINO/") {
-        // remove OpenVINO/ prefix
-        model_id = model_id.trim_start_matches("OpenVINO/").to_string();
-        info!("Using OVMS model ID: {}", model_id);
+    // OVMS serves models under their name without the organization
+    let model_id = match params.model_id.rsplit_once('/') {
+        Some((_, name)) => name.to_string(),
+        None => params.model_id.clone(),
+    };
+    if model_id.is_empty() {
+        return Err("Please select an image generation model".to_string());
     }
-    
+
     // Handle reference images if provided
-    if !reference_images.is_empty() {
+    if !params.reference_images.is_empty() {
         info!("Reference images provided but not yet supported in current implementation");
     }
 
-    // Parse the size string (e.g., "512x512") into ImageSize
-    let image_size = match size.as_str() {
-        "256x256" => ImageSize::S256x256,
-        "512x512" => ImageSize::S512x512,
-        "1024x1024" => ImageSize::S1024x1024,
-        _ => ImageSize::S512x512, // default
-    };
-
-    // Configure async_openai client to use OVMS endpoint
-    let config = OpenAIConfig::new()
-        .with_api_base(&format!("{}/v3", constants::OVMS_API_BASE))
-        .with_api_key(""); // OVMS doesn't require an API key
-
-    let client = Client::with_config(config);
-    
-    // Build the image generation request
-    let request = CreateImageRequestArgs::default()
-        .prompt(prompt.clone())
-        .model(ImageModel::Other(model_id.clone()))
-        .n(1)
-        .size(image_size)
-        .response_format(ImageResponseFormat::B64Json)
-        .build().map_err(|e| {
-            error!("Failed to generate image request: {}", e);
-            format!("Failed to generate image request: {}", e)
-        })?;
+    let body = image_request_body(&model_id, &prompt, &size, seed, &params);
+    let client = crate::http::client_with_timeout(IMAGE_REQUEST_TIMEOUT)?;
 
-    // Add num_inference_steps as extra parameter if needed
-    // Note: This might need to be handled differently depending on OVMS implementation
     info!("Making image generation request to OVMS");
-
-    // Call the async_openai API
     let response = client
-        .images()
-        .generate(request)
+        .post(format!("{}/images/generations", crate::ovms::openai_api_base()))
+        .json(&body)
+        .send()
         .await
         .map_err(|e| {
             error!("Failed to generate image: {}", e);
             format!("Failed to generate image: {}", e)
         })?;
 
-    // Get the base64 image data from the response
-    if response.data.is_empty() {
-        return Err("No image data received from server".to_string());
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        error!("Image generation failed with status {}: {}", status, detail);
+        return Err(format!("Image generation failed with status {}: {}", status, detail.trim()));
     }
 
-    // Generate metadata before saving
-    let image_id = Uuid::new_v4().to_string();
-    let timestamp = chrono::Utc::now().timestamp();
-    
-    let images_dir = get_images_dir()?;
-    
-    // Save images using the response's save method
-    let paths = response.save(images_dir.to_str().ok_or("Invalid images directory path")?)
+    let response: ImageResponse = response.json()
         .await
-        .map_err(|e| format!("Failed to save image: {}", e))?;
+        .map_err(|e| format!("Failed to parse image response: {}", e))?;
 
-    let image_path = paths.get(0)
-        .ok_or("No image path returned from save operation")?;
+    // Get the base64 image data from the response
+    let encoded = response.data
+        .into_iter()
+        .find_map(|data| data.b64_json)
+        .ok_or("No image data received from server")?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid image data: {}", e))?;
+
+    let image_id = Uuid::new_v4().to_string();
+    let image_path = get_images_dir()?.join(format!("{}.png", image_id));
+    fs::write(&image_path, bytes)
+        .map_err(|e| format!("Failed to save image: {}", e))?;
 
     info!("Image saved to: {}", image_path.display());
 
-    // Create metadata entry
     let generated_image = GeneratedImage {
-        id: image_id.clone(),
-        prompt: prompt.clone(),
+        id: image_id,
+        prompt,
         image_path: image_path.to_string_lossy().to_string(),
-        timestamp,
-        model_id: model_id.clone(),
-        size: size.clone(),
-        num_inference_steps: Some(num_inference_steps),
+        timestamp: chrono::Utc::now().timestamp(),
+        model_id,
+        size,
+        num_inference_steps: params.num_inference_steps,
+        seed: Some(seed),
+        negative_prompt: params.negative_prompt.filter(|p| !p.trim().is_empty()),
+        guidance_scale: params.guidance_scale,
+        title: None,
+        favorite: false,
     };
 
-    // Save to metadata
-    let mut storage = load_images_metadata()?;
-    storage.images.insert(0, generated_image);
-    save_images_metadata(&storage)?;
+    modify_images_metadata(|storage| {
+        storage.images.insert(0, generated_image.clone());
+        Ok(())
+    }).await?;
 
-    Ok(serde_json::json!({
-        "id": image_id,
-        "image_path": image_path.to_string_lossy().to_string(),
-    }))
+    Ok(generated_image)
 }
 
 #[tauri::command]
 pub async fn get_generated_images() -> Result<Vec<GeneratedImage>, String> {
-    let storage = load_images_metadata()?;
+    let storage = load_images_metadata().await?;
     Ok(storage.images)
 }
 
 #[tauri::command]
-pub async fn delete_generated_image(image_id: String, image_path: String) -> Result<(), String> {
-    info!("Deleting image: {} at path: {}", image_id, image_path);
+pub async fn get_generated_image(image_id: String) -> Result<GeneratedImage, String> {
+    load_images_metadata().await?
+        .images
+        .into_iter()
+        .find(|img| img.id == image_id)
+        .ok_or_else(|| format!("Image not found: {}", image_id))
+}
 
-    // Delete the file
-    let path = PathBuf::from(&image_path);
-    if path.exists() {
-        fs::remove_file(&path)
-            .map_err(|e| format!("Failed to delete image file: {}", e))?;
-    }
+/// Rename an image or mark it as favorite; an empty title clears it
+#[tauri::command]
+pub async fn update_generated_image(
+    image_id: String,
+    title: Option<String>,
+    favorite: Option<bool>,
+) -> Result<GeneratedImage, String> {
+    modify_images_metadata(|storage| {
+        let image = storage.images
+            .iter_mut()
+            .find(|img| img.id == image_id)
+            .ok_or_else(|| format!("Image not found: {}", image_id))?;
+        if let Some(title) = title {
+            let title = title.trim();
+            image.title = (!title.is_empty()).then(|| title.to_string());
+        }
+        if let Some(favorite) = favorite {
+            image.favorite = favorite;
+        }
+        Ok(image.clone())
+    }).await
+}
 
-    // Update metadata
-    let mut storage = load_images_metadata()?;
-    storage.images.retain(|img| img.id != image_id);
-    save_images_metadata(&storage)?;
+#[tauri::command]
+pub async fn delete_generated_image(image_id: String) -> Result<(), String> {
+    info!("Deleting image: {}", image_id);
+
+    let images_dir = get_images_dir()?;
+    let image = modify_images_metadata(|storage| {
+        let index = storage.images
+            .iter()
+            .position(|img| img.id == image_id)
+            .ok_or_else(|| format!("Image not found: {}", image_id))?;
+        Ok(storage.images.remove(index))
+    }).await?;
+    remove_image_file(&images_dir, &image);
 
     Ok(())
 }
 
+/// Delete every image except favorites unless `include_favorites` is set;
+/// returns how many were deleted
+#[tauri::command]
+pub async fn clear_gallery(include_favorites: Option<bool>) -> Result<usize, String> {
+    let include_favorites = include_favorites.unwrap_or(false);
+    let images_dir = get_images_dir()?;
+    let removed = modify_images_metadata(|storage| {
+        let (removed, kept) = std::mem::take(&mut storage.images)
+            .into_iter()
+            .partition::<Vec<_>, _>(|img| include_favorites || !img.favorite);
+        storage.images = kept;
+        Ok(removed)
+    }).await?;
+    for image in &removed {
+        remove_image_file(&images_dir, image);
+    }
+
+    info!("Cleared {} images from the gallery", removed.len());
+    Ok(removed.len())
+}
+
 #[tauri::command]
 pub async fn copy_file(source_path: String, dest_path: String) -> Result<(), String> {
     fs::copy(&source_path, &dest_path)
         .map_err(|e| format!("Failed to copy file: {}", e))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_normalizes_dimensions() {
+        assert_eq!(parse_size("512x512").unwrap(), "512x512");
+        assert_eq!(parse_size(" 768 X 1024 ").unwrap(), "768x1024");
+        assert!(parse_size("512").is_err());
+        assert!(parse_size("0x512").is_err());
+        assert!(parse_size("widexhigh").is_err());
+    }
+
+    #[test]
+    fn test_image_request_body_includes_seed_and_set_parameters() {
+        let params = ImageParams {
+            model_id: "OpenVINO/stable-diffusion".to_string(),
+            num_inference_steps: Some(20),
+            negative_prompt: Some("  ".to_string()),
+            ..Default::default()
+        };
+        let body = image_request_body("stable-diffusion", "a fox", "512x512", 42, &params);
+        assert_eq!(body["rng_seed"], 42);
+        assert_eq!(body["num_inference_steps"], 20);
+        assert_eq!(body["response_format"], "b64_json");
+        assert!(body.get("negative_prompt").is_none());
+        assert!(body.get("guidance_scale").is_none());
+    }
+
+    #[test]
+    fn test_metadata_without_new_fields_still_loads() {
+        let image: GeneratedImage = serde_json::from_str(
+            r#"{"id":"1","prompt":"a fox","image_path":"/tmp/1.png","timestamp":0,"model_id":"sd","size":"512x512"}"#
+        ).unwrap();
+        assert_eq!(image.seed, None);
+        assert!(!image.favorite);
+    }
+}
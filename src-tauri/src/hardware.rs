@@ -0,0 +1,288 @@
+//! Inference devices OpenVINO can use on this machine.
+//!
+//! OpenVINO's GPU plugin only drives Intel GPUs, and its NPU plugin only Intel
+//! NPUs, so the list below is built from the OS device inventory filtered to
+//! Intel hardware: PowerShell/CIM on Windows, sysfs on Linux. Device ids follow
+//! OpenVINO's numbering: the integrated GPU is `GPU.0` and discrete cards come
+//! after it. With a single GPU the id is plain `GPU`.
+//!
+//! Integrated GPUs and NPUs share system memory, so no memory is reported for
+//! them. For discrete GPUs Windows reports at most 4 GiB (`AdapterRAM` is a
+//! 32-bit value).
+
+use serde::Serialize;
+use sysinfo::System;
+
+const INTEL_VENDOR_ID: &str = "8086";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKind {
+    Cpu,
+    IntegratedGpu,
+    DiscreteGpu,
+    Npu,
+    Auto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HardwareDevice {
+    /// Value for `target_device` / `load_model(device)`: CPU, GPU, GPU.1, NPU, AUTO
+    pub id: String,
+    pub kind: DeviceKind,
+    pub name: String,
+    /// Dedicated memory, when the OS reports it
+    pub memory_bytes: Option<u64>,
+    /// Uses system RAM rather than memory of its own
+    pub shared_memory: bool,
+}
+
+/// A GPU found in the OS inventory, before ids are assigned
+#[derive(Debug, Clone)]
+struct GpuInfo {
+    name: String,
+    discrete: bool,
+    memory_bytes: Option<u64>,
+}
+
+static DEVICES: parking_lot::Mutex<Option<Vec<HardwareDevice>>> = parking_lot::Mutex::new(None);
+
+/// Discrete Intel cards are the Arc A/B series, Arc Pro and Data Center GPUs;
+/// "Arc(TM) Graphics" without a model number is the integrated GPU of Core Ultra
+fn is_discrete_gpu(name: &str) -> bool {
+    let name = name.to_lowercase();
+    if name.contains("data center gpu") || name.contains("xe max") {
+        return true;
+    }
+    let Some(after_arc) = name.split("arc").nth(1) else {
+        return false;
+    };
+    let model = after_arc.trim_start_matches("(tm)").trim_start();
+    model.starts_with("pro")
+        || (model.starts_with(['a', 'b']) && model[1..].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Integrated GPU first, then discrete ones, numbered the way OpenVINO does
+fn gpu_devices(mut gpus: Vec<GpuInfo>) -> Vec<HardwareDevice> {
+    gpus.sort_by_key(|gpu| gpu.discrete);
+    let single = gpus.len() == 1;
+    gpus.into_iter()
+        .enumerate()
+        .map(|(index, gpu)| HardwareDevice {
+            id: if single { "GPU".to_string() } else { format!("GPU.{}", index) },
+            kind: if gpu.discrete { DeviceKind::DiscreteGpu } else { DeviceKind::IntegratedGpu },
+            name: gpu.name,
+            memory_bytes: gpu.memory_bytes.filter(|_| gpu.discrete),
+            shared_memory: !gpu.discrete,
+        })
+        .collect()
+}
+
+fn cpu_device() -> HardwareDevice {
+    let mut system = System::new();
+    system.refresh_cpu_all();
+    system.refresh_memory();
+    let name = system.cpus()
+        .first()
+        .map(|cpu| cpu.brand().trim().to_string())
+        .filter(|brand| !brand.is_empty())
+        .unwrap_or_else(|| "CPU".to_string());
+    HardwareDevice {
+        id: "CPU".to_string(),
+        kind: DeviceKind::Cpu,
+        name,
+        memory_bytes: Some(system.total_memory()),
+        shared_memory: false,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn accelerators() -> (Vec<GpuInfo>, Vec<String>) {
+    use std::os::windows::process::CommandExt;
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct VideoController {
+        name: Option<String>,
+        #[serde(rename = "AdapterRAM")]
+        adapter_ram: Option<u64>,
+        #[serde(rename = "PNPDeviceID")]
+        pnp_device_id: Option<String>,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct PnpDevice {
+        friendly_name: Option<String>,
+        instance_id: Option<String>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Inventory {
+        #[serde(default)]
+        gpus: Vec<VideoController>,
+        #[serde(default)]
+        npus: Vec<PnpDevice>,
+    }
+
+    const SCRIPT: &str = "$g = @(Get-CimInstance Win32_VideoController | Select-Object Name, AdapterRAM, PNPDeviceID); \
+        $n = @(Get-PnpDevice -PresentOnly -Class ComputeAccelerator -ErrorAction SilentlyContinue | Select-Object FriendlyName, InstanceId); \
+        @{ gpus = $g; npus = $n } | ConvertTo-Json -Depth 3 -Compress";
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output();
+    let inventory: Inventory = match output {
+        Ok(output) if output.status.success() => match serde_json::from_slice(&output.stdout) {
+            Ok(inventory) => inventory,
+            Err(e) => {
+                log_warning!("Failed to parse device inventory", error = %e);
+                return (Vec::new(), Vec::new());
+            }
+        },
+        Ok(output) => {
+            log_warning!("Device inventory query failed", stderr = %String::from_utf8_lossy(&output.stderr));
+            return (Vec::new(), Vec::new());
+        }
+        Err(e) => {
+            log_warning!("Failed to run PowerShell for device inventory", error = %e);
+            return (Vec::new(), Vec::new());
+        }
+    };
+
+    let intel = |id: &Option<String>| {
+        id.as_deref().is_some_and(|id| id.to_uppercase().contains(&format!("VEN_{}", INTEL_VENDOR_ID)))
+    };
+    let gpus = inventory.gpus.into_iter()
+        .filter(|gpu| intel(&gpu.pnp_device_id))
+        .filter_map(|gpu| {
+            let name = gpu.name?;
+            Some(GpuInfo { discrete: is_discrete_gpu(&name), name, memory_bytes: gpu.adapter_ram })
+        })
+        .collect();
+    let npus = inventory.npus.into_iter()
+        .filter(|npu| intel(&npu.instance_id))
+        .filter_map(|npu| npu.friendly_name)
+        .collect();
+    (gpus, npus)
+}
+
+#[cfg(target_os = "linux")]
+fn accelerators() -> (Vec<GpuInfo>, Vec<String>) {
+    use std::fs;
+    use std::path::Path;
+
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let is_intel = |device: &Path| read(&device.join("vendor")).as_deref() == Some(&format!("0x{}", INTEL_VENDOR_ID));
+
+    let mut gpus = Vec::new();
+    if let Ok(entries) = fs::read_dir("/sys/class/drm") {
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // card0, card1, ... but not connectors such as card0-HDMI-A-1
+            if !name.strip_prefix("card").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            let device = entry.path().join("device");
+            if !is_intel(&device) {
+                continue;
+            }
+            // The integrated GPU always sits at PCI slot 00:02.0
+            let slot = fs::canonicalize(&device).ok()
+                .and_then(|path| path.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_default();
+            let pci_id = read(&device.join("device")).unwrap_or_default();
+            gpus.push(GpuInfo {
+                name: format!("Intel GPU {}", pci_id).trim().to_string(),
+                discrete: !slot.ends_with(":00:02.0"),
+                memory_bytes: None,
+            });
+        }
+    }
+
+    let npus = fs::read_dir("/sys/class/accel")
+        .map(|entries| entries.filter_map(Result::ok)
+            .filter(|entry| is_intel(&entry.path().join("device")))
+            .map(|_| "Intel NPU".to_string())
+            .collect())
+        .unwrap_or_default();
+    (gpus, npus)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn accelerators() -> (Vec<GpuInfo>, Vec<String>) {
+    (Vec::new(), Vec::new())
+}
+
+fn discover() -> Vec<HardwareDevice> {
+    let (gpus, npus) = accelerators();
+    let mut devices = vec![cpu_device()];
+    devices.extend(gpu_devices(gpus));
+    // OpenVINO exposes a single NPU device
+    if let Some(name) = npus.into_iter().next() {
+        devices.push(HardwareDevice {
+            id: "NPU".to_string(),
+            kind: DeviceKind::Npu,
+            name,
+            memory_bytes: None,
+            shared_memory: true,
+        });
+    }
+    devices.push(HardwareDevice {
+        id: "AUTO".to_string(),
+        kind: DeviceKind::Auto,
+        name: "Automatic (OpenVINO picks the device)".to_string(),
+        memory_bytes: None,
+        shared_memory: false,
+    });
+    devices
+}
+
+/// Devices found on this machine; enumerated once and cached unless `refresh`
+pub async fn available_devices(refresh: bool) -> Vec<HardwareDevice> {
+    if !refresh {
+        if let Some(devices) = DEVICES.lock().clone() {
+            return devices;
+        }
+    }
+    let devices = tokio::task::spawn_blocking(discover).await.unwrap_or_else(|_| vec![cpu_device()]);
+    tracing::info!(devices = ?devices.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), "Enumerated inference devices");
+    *DEVICES.lock() = Some(devices.clone());
+    devices
+}
+
+#[tauri::command]
+pub async fn get_available_devices(refresh: Option<bool>) -> Result<Vec<HardwareDevice>, String> {
+    Ok(available_devices(refresh.unwrap_or(false)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_discrete_gpu() {
+        assert!(is_discrete_gpu("Intel(R) Arc(TM) A770 Graphics"));
+        assert!(is_discrete_gpu("Intel(R) Arc(TM) B580 Graphics"));
+        assert!(is_discrete_gpu("Intel(R) Arc(TM) Pro A60"));
+        assert!(!is_discrete_gpu("Intel(R) Arc(TM) Graphics"));
+        assert!(!is_discrete_gpu("Intel(R) Iris(R) Xe Graphics"));
+        assert!(!is_discrete_gpu("Intel(R) UHD Graphics 770"));
+    }
+
+    #[test]
+    fn test_gpu_ids_put_integrated_first() {
+        let gpu = |name: &str, discrete| GpuInfo { name: name.to_string(), discrete, memory_bytes: Some(1 << 30) };
+
+        let devices = gpu_devices(vec![gpu("A770", true), gpu("UHD", false)]);
+        let ids: Vec<_> = devices.iter().map(|d| (d.id.as_str(), d.name.as_str())).collect();
+        assert_eq!(ids, vec![("GPU.0", "UHD"), ("GPU.1", "A770")]);
+        assert_eq!(devices[0].memory_bytes, None);
+        assert!(devices[0].shared_memory);
+
+        let single = gpu_devices(vec![gpu("A770", true)]);
+        assert_eq!(single[0].id, "GPU");
+        assert_eq!(single[0].kind, DeviceKind::DiscreteGpu);
+    }
+}
@@ -0,0 +1,149 @@
+//! Hugging Face access token, for gated mirrors and private conversions.
+//!
+//! The token itself lives in the OS credential store (Windows Credential
+//! Manager, macOS Keychain, Secret Service on Linux), never in
+//! `settings.json`. Settings only remember the account it belongs to and
+//! whether requests should carry it (`huggingface.use_token`).
+
+use parking_lot::Mutex;
+use serde::{ Deserialize, Serialize };
+use tracing::warn;
+
+use crate::{ constants, settings };
+
+const KEYRING_SERVICE: &str = "SparrowAI";
+const KEYRING_ENTRY: &str = "huggingface-token";
+
+/// Token read from the credential store: `None` until the first lookup,
+/// then `Some(None)` when no token is stored
+static TOKEN_CACHE: Mutex<Option<Option<String>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HfTokenStatus {
+    pub configured: bool,
+    pub enabled: bool,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoAmI {
+    name: String,
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_ENTRY)
+        .map_err(|e| format!("Failed to open the credential store: {}", e))
+}
+
+fn stored_token() -> Option<String> {
+    let mut cache = TOKEN_CACHE.lock();
+    if let Some(token) = cache.as_ref() {
+        return token.clone();
+    }
+
+    let token = match keyring_entry().map(|entry| entry.get_password()) {
+        Ok(Ok(token)) => Some(token),
+        Ok(Err(keyring::Error::NoEntry)) => None,
+        Ok(Err(e)) => {
+            warn!(error = %e, "Failed to read Hugging Face token from the credential store");
+            None
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to read Hugging Face token from the credential store");
+            None
+        }
+    };
+    *cache = Some(token.clone());
+    token
+}
+
+/// The token to send, if one is stored and enabled
+pub fn token() -> Option<String> {
+    if !settings::current().huggingface.use_token {
+        return None;
+    }
+    stored_token()
+}
+
+/// Attach the token to a request for huggingface.co
+pub fn authorize(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match token() {
+        // reqwest drops the header when a download redirects to another host
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+async fn whoami(token: &str) -> Result<String, String> {
+    let response = crate::http::client()?
+        .get(format!("{}/whoami-v2", constants::HUGGINGFACE_API_BASE))
+        .bearer_auth(token)
+        .send().await
+        .map_err(|e| format!("Failed to reach Hugging Face: {}", e))?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("Hugging Face rejected the token".to_string());
+    }
+    if !response.status().is_success() {
+        return Err(format!("Token check failed with status: {}", response.status()));
+    }
+
+    let account: WhoAmI = response.json().await
+        .map_err(|e| format!("Failed to parse token check response: {}", e))?;
+    Ok(account.name)
+}
+
+fn status() -> HfTokenStatus {
+    let settings = settings::current().huggingface;
+    HfTokenStatus {
+        configured: stored_token().is_some(),
+        enabled: settings.use_token,
+        username: settings.username,
+    }
+}
+
+#[tauri::command]
+pub async fn get_hf_token_status() -> Result<HfTokenStatus, String> {
+    Ok(status())
+}
+
+/// Check the token against Hugging Face, then store it
+#[tauri::command]
+pub async fn set_hf_token(token: String) -> Result<HfTokenStatus, String> {
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        return Err("The token is empty".to_string());
+    }
+
+    log_operation_start!("Set Hugging Face token");
+    let username = whoami(&token).await.map_err(|e| {
+        log_operation_error!("Set Hugging Face token", &e);
+        e
+    })?;
+
+    keyring_entry()?
+        .set_password(&token)
+        .map_err(|e| format!("Failed to store the token in the credential store: {}", e))?;
+    *TOKEN_CACHE.lock() = Some(Some(token));
+
+    settings::update(|settings| {
+        settings.huggingface.username = Some(username.clone());
+        settings.huggingface.use_token = true;
+    })?;
+
+    log_operation_success!("Set Hugging Face token", username = %username);
+    Ok(status())
+}
+
+#[tauri::command]
+pub async fn clear_hf_token() -> Result<HfTokenStatus, String> {
+    match keyring_entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("Failed to remove the token from the credential store: {}", e)),
+    }
+    *TOKEN_CACHE.lock() = Some(None);
+
+    settings::update(|settings| settings.huggingface.username = None)?;
+    tracing::info!("Removed Hugging Face token");
+    Ok(status())
+}
@@ -0,0 +1,90 @@
+//! Construction of the app's HTTP clients.
+//!
+//! Hugging Face, GitHub and OVMS requests all go through clients built here,
+//! so the user agent, extra headers and TLS options in `settings.http`
+//! apply everywhere instead of per call site.
+
+use std::time::Duration;
+
+use reqwest::header::{ HeaderMap, HeaderName, HeaderValue };
+
+use crate::settings::{ self, HttpSettings };
+
+/// Headers from the settings; invalid names or values are skipped with a warning
+fn default_headers(http: &HttpSettings) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &http.extra_headers {
+        match (HeaderName::from_bytes(name.trim().as_bytes()), HeaderValue::from_str(value.trim())) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!(header = %name, "Ignoring invalid extra HTTP header"),
+        }
+    }
+    headers
+}
+
+fn builder_for(http: &HttpSettings) -> Result<reqwest::ClientBuilder, String> {
+    let user_agent = if http.user_agent.trim().is_empty() {
+        crate::constants::USER_AGENT
+    } else {
+        http.user_agent.trim()
+    };
+    let mut builder = reqwest::Client::builder()
+        .user_agent(user_agent)
+        .default_headers(default_headers(http));
+
+    if let Some(path) = http.ca_certificate_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA certificate {}: {}", path, e))?;
+        let certificate = reqwest::Certificate::from_pem(&pem)
+            .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+    if http.accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+/// Client builder with the configured options, for callers adding their own
+pub fn builder() -> Result<reqwest::ClientBuilder, String> {
+    builder_for(&settings::current().http)
+}
+
+pub fn client() -> Result<reqwest::Client, String> {
+    builder()?.build().map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Client whose requests fail after `timeout`
+pub fn client_with_timeout(timeout: Duration) -> Result<reqwest::Client, String> {
+    builder()?
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_headers_are_skipped() {
+        let mut http = HttpSettings::default();
+        http.extra_headers.insert("X-Proxy-Team".to_string(), "ml".to_string());
+        http.extra_headers.insert("Bad Header".to_string(), "x".to_string());
+        http.extra_headers.insert("X-Newline".to_string(), "a\nb".to_string());
+
+        let headers = default_headers(&http);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-proxy-team"], "ml");
+    }
+
+    #[test]
+    fn test_missing_ca_certificate_is_an_error() {
+        let http = HttpSettings {
+            ca_certificate_path: Some("/nonexistent/ca.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(builder_for(&http).is_err());
+    }
+}
@@ -2,12 +2,19 @@ use serde::{ Deserialize, Serialize };
 use serde_json;
 use tracing::{ info, warn, error };
 use std::path::PathBuf;
-use tauri::Emitter;
-use tokio::io::AsyncWriteExt;
+use tauri::{ Emitter, Manager };
+use tokio::io::{ AsyncSeekExt, AsyncWriteExt };
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use sha2::{ Digest, Sha256 };
 use std::fs;
 use std::collections::HashMap;
 
-use crate::{ constants, paths };
+use crate::{ constants, hf_auth, model_integrity, models, path_policy, paths };
+use crate::path_policy::Access;
+use crate::cancellation::{ self, OperationGuard, OperationKind };
+use crate::messages::Message;
+use crate::state::AppState;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum ModelType {
@@ -47,6 +54,27 @@ pub struct ModelMetadata {
     pub model_type: ModelType,
     pub pipeline_tag: String,
     pub commit_sha: Option<String>,
+    /// Smaller model proposing tokens for speculative decoding
+    #[serde(default)]
+    pub draft_model_id: Option<String>,
+    /// Tokens the draft model proposes per step
+    #[serde(default)]
+    pub num_assistant_tokens: Option<u32>,
+    #[serde(default)]
+    pub tool_call_mode: ToolCallMode,
+}
+
+/// How chat requests give tools to a model
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCallMode {
+    /// `Native` when the graph has a tool parser
+    #[default]
+    Auto,
+    /// The request's `tools`/`tool_choice` fields, with calls parsed by OVMS
+    Native,
+    /// Tool list in the system prompt, with calls parsed from `<tool_call>` tags
+    Prompt,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -115,32 +143,404 @@ struct HfModelInfo {
     pub siblings: Option<Vec<HfModelSibling>>,
 }
 
-#[derive(Debug, Deserialize)]
-struct HfFileInfo {
+/// Large files are stored through Git LFS; the tree API reports their SHA256
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct HfLfsInfo {
+    pub oid: String,
+    pub size: u64,
+}
+
+/// Entry of the repository tree API (`/api/models/<id>/tree/main`)
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct HfFileInfo {
     #[serde(rename = "path")]
     pub path: String,
     #[serde(rename = "type")]
-    #[allow(dead_code)]
     pub file_type: String,
-    #[allow(dead_code)]
     pub size: Option<u64>,
+    /// Git object id; changes whenever the file does
+    #[serde(default)]
+    pub oid: Option<String>,
+    #[serde(default)]
+    pub lfs: Option<HfLfsInfo>,
+}
+
+impl HfFileInfo {
+    pub fn expected_size(&self) -> Option<u64> {
+        self.lfs.as_ref().map(|lfs| lfs.size).or(self.size)
+    }
+
+    pub fn expected_sha256(&self) -> Option<&str> {
+        self.lfs.as_ref().map(|lfs| lfs.oid.as_str())
+    }
+
+    /// Identifies this revision of the file, as recorded in the file manifest
+    pub fn version(&self) -> Option<&str> {
+        self.expected_sha256().or(self.oid.as_deref())
+    }
+}
+
+/// Per-model record of the version of each downloaded file, so updates can
+/// tell which files changed without hashing them
+const FILE_MANIFEST: &str = ".sparrow-files.json";
+
+fn read_file_manifest(model_dir: &std::path::Path) -> HashMap<String, String> {
+    fs::read_to_string(model_dir.join(FILE_MANIFEST))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_file_manifest(model_dir: &std::path::Path, manifest: &HashMap<String, String>) {
+    let result = serde_json::to_string_pretty(manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(model_dir.join(FILE_MANIFEST), content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!(error = %e, dir = %model_dir.display(), "Failed to write file manifest");
+    }
+}
+
+/// Filter a selective download was made with, so updates fetch the same subset
+const FILE_FILTER: &str = ".sparrow-filter.json";
+
+/// Which repository files to download: those matching any `include` pattern
+/// (all when empty) and no `exclude` pattern. Patterns are globs with `*`,
+/// `?` and `**`; a pattern without `/` is matched against the file name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let matching = |patterns: &[String]| patterns.iter().any(|pattern| glob_matches(pattern, path));
+        (self.include.is_empty() || matching(&self.include)) && !matching(&self.exclude)
+    }
+}
+
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("./");
+    let target = if pattern.contains('/') { path } else { path.rsplit('/').next().unwrap_or(path) };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    glob_match_chars(&pattern, &target)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Zero or more whole directories
+            glob_match_chars(rest, text)
+                || (0..text.len()).any(|i| text[i] == '/' && glob_match_chars(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob_match_chars(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_chars(rest, &text[i..])),
+        ['?', rest @ ..] => matches!(text.first(), Some(c) if *c != '/') && glob_match_chars(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_match_chars(rest, &text[1..]),
+    }
+}
+
+fn read_file_filter(model_dir: &std::path::Path) -> FileFilter {
+    fs::read_to_string(model_dir.join(FILE_FILTER))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_file_filter(model_dir: &std::path::Path, filter: &FileFilter) {
+    let path = model_dir.join(FILE_FILTER);
+    let result = if filter.is_empty() {
+        // A full download replaces an earlier selective one
+        fs::remove_file(&path).or_else(|e| if e.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(e) })
+            .map_err(|e| e.to_string())
+    } else {
+        serde_json::to_string_pretty(filter)
+            .map_err(|e| e.to_string())
+            .and_then(|content| fs::write(&path, content).map_err(|e| e.to_string()))
+    };
+    if let Err(e) = result {
+        warn!(error = %e, dir = %model_dir.display(), "Failed to write file filter");
+    }
+}
+
+/// Explanation appended to errors for gated or private repositories
+fn access_hint(status: reqwest::StatusCode) -> &'static str {
+    match status {
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            if hf_auth::token().is_some() {
+                ". The Hugging Face token has no access to this repository; for gated models, accept the terms on the model page first."
+            } else {
+                ". The repository is gated or private; add a Hugging Face access token in settings."
+            }
+        }
+        _ => "",
+    }
+}
+
+/// Every file in a model repository, with sizes and LFS checksums
+pub(crate) async fn fetch_file_tree(client: &reqwest::Client, model_id: &str) -> Result<Vec<HfFileInfo>, String> {
+    let url = format!("https://huggingface.co/api/models/{}/tree/main?recursive=true", model_id);
+
+    let request = client.get(&url);
+    let response = hf_auth::authorize(request)
+        .send().await
+        .map_err(|e| format!("Failed to fetch file list: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch file list: HTTP {}", response.status()));
+    }
+
+    let entries: Vec<HfFileInfo> = response
+        .json().await
+        .map_err(|e| format!("Failed to parse file list: {}", e))?;
+
+    Ok(entries.into_iter().filter(|entry| entry.file_type == "file").collect())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSizeEstimate {
+    pub model_id: String,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    /// What the download adds to the drive; files already in the target
+    /// directory are overwritten, so their current size is subtracted
+    pub required_bytes: u64,
+    pub target_dir: String,
+    pub available_bytes: Option<u64>,
+    /// `None` when the drive's free space is unknown
+    pub fits: Option<bool>,
+}
+
+/// Where a model is downloaded to: `<download_path or models dir>/<org>/<name>`
+fn model_target_dir(model_id: &str, download_path: Option<String>) -> Result<PathBuf, String> {
+    match download_path {
+        Some(path) => path_policy::check(&PathBuf::from(path).join(model_id), Access::Write),
+        None => Ok(paths::get_models_dir().map_err(|e| e.to_string())?.join(model_id)),
+    }
+}
+
+/// Bytes a download of `files` into `target_dir` adds to the drive
+fn required_bytes(files: &[HfFileInfo], target_dir: &std::path::Path) -> u64 {
+    files
+        .iter()
+        .map(|file| {
+            let existing = fs::metadata(target_dir.join(&file.path)).map_or(0, |m| m.len());
+            file.expected_size().unwrap_or(0).saturating_sub(existing)
+        })
+        .sum()
+}
+
+fn size_estimate(model_id: &str, files: &[HfFileInfo], target_dir: &std::path::Path) -> ModelSizeEstimate {
+    let required = required_bytes(files, target_dir);
+    let available_bytes = crate::disk::available_space(target_dir);
+    ModelSizeEstimate {
+        model_id: model_id.to_string(),
+        file_count: files.len(),
+        total_bytes: files.iter().filter_map(HfFileInfo::expected_size).sum(),
+        required_bytes: required,
+        target_dir: target_dir.display().to_string(),
+        available_bytes,
+        fits: available_bytes.map(|available| required.saturating_add(constants::DOWNLOAD_DISK_MARGIN_BYTES) <= available),
+    }
+}
+
+/// Total size of a model repository and whether it fits on the target drive
+#[tauri::command]
+pub async fn estimate_model_size(
+    model_id: String,
+    download_path: Option<String>,
+    file_filter: Option<FileFilter>
+) -> Result<ModelSizeEstimate, String> {
+    let model_id = models::normalize_model_id(&model_id);
+    models::check_allowed_org(&model_id)?;
+    let target_dir = model_target_dir(&model_id, download_path)?;
+
+    let mut files = fetch_file_tree(&crate::http::client()?, &model_id).await?;
+    if let Some(filter) = &file_filter {
+        files.retain(|file| filter.matches(&file.path));
+    }
+    Ok(size_estimate(&model_id, &files, &target_dir))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelFile {
+    pub path: String,
+    pub size: Option<u64>,
+    /// Stored with Git LFS; in practice the weight files
+    pub lfs: bool,
+}
+
+/// Files of a repository with their sizes, for choosing what to download
+#[tauri::command]
+pub async fn list_model_files(model_id: String) -> Result<Vec<ModelFile>, String> {
+    let model_id = models::normalize_model_id(&model_id);
+    models::check_allowed_org(&model_id)?;
+
+    let mut files: Vec<ModelFile> = fetch_file_tree(&crate::http::client()?, &model_id).await?
+        .into_iter()
+        .map(|file| ModelFile {
+            size: file.expected_size(),
+            lfs: file.lfs.is_some(),
+            path: file.path,
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Aggregated progress of a model download whose files are fetched concurrently
+struct DownloadTracker {
+    model_id: String,
+    total_files: usize,
+    state: parking_lot::Mutex<TrackerState>,
+    /// Pause/resume requests from `pause_model_download` and friends
+    control: watch::Receiver<DownloadControl>,
+    /// Cancelled by `cancel_model_download` or `cancel_operation`
+    cancel: CancellationToken,
+}
+
+struct TrackerState {
+    completed_files: usize,
+    completed_bytes: u64,
+    /// Files in flight: path -> (bytes so far, content length)
+    active: HashMap<String, (u64, u64)>,
+    last_emit: std::time::Instant,
+}
+
+impl DownloadTracker {
+    fn new(model_id: &str, total_files: usize, control: watch::Receiver<DownloadControl>, cancel: CancellationToken) -> Self {
+        Self {
+            model_id: model_id.to_string(),
+            total_files,
+            control,
+            cancel,
+            state: parking_lot::Mutex::new(TrackerState {
+                completed_files: 0,
+                completed_bytes: 0,
+                active: HashMap::new(),
+                last_emit: std::time::Instant::now(),
+            }),
+        }
+    }
+
+    fn update(&self, app: &tauri::AppHandle, file: &str, file_index: usize, downloaded: u64, content_length: u64) {
+        let mut state = self.state.lock();
+        state.active.insert(file.to_string(), (downloaded, content_length));
+
+        let finished_file = content_length > 0 && downloaded == content_length;
+        if state.last_emit.elapsed().as_millis() <= constants::DOWNLOAD_PROGRESS_INTERVAL_MS && !finished_file {
+            return;
+        }
+        state.last_emit = std::time::Instant::now();
+        self.emit(app, &state, file, file_index, downloaded, content_length);
+    }
+
+    fn finish(&self, file: &str, bytes: Option<u64>) {
+        let mut state = self.state.lock();
+        state.active.remove(file);
+        state.completed_files += 1;
+        state.completed_bytes += bytes.unwrap_or(0);
+    }
+
+    fn emit(&self, app: &tauri::AppHandle, state: &TrackerState, file: &str, file_index: usize, downloaded: u64, content_length: u64) {
+        // File sizes are unknown up front, so overall progress counts files,
+        // with in-flight files contributing their fraction
+        let in_flight: f64 = state.active
+            .values()
+            .filter(|(_, total)| *total > 0)
+            .map(|(done, total)| (*done as f64) / (*total as f64))
+            .sum();
+        let overall_progress = (((state.completed_files as f64) + in_flight) / (self.total_files as f64)) * 100.0;
+        let file_progress = if content_length > 0 {
+            (((downloaded as f64) / (content_length as f64)) * 100.0) as u32
+        } else {
+            0
+        };
+        let downloaded_bytes = state.completed_bytes + state.active.values().map(|(done, _)| done).sum::<u64>();
+
+        let _ = app.emit(
+            "download-progress",
+            serde_json::json!({
+                "modelId": self.model_id,
+                "progress": (overall_progress as u32).min(100),
+                "currentFile": file,
+                "fileIndex": file_index,
+                "totalFiles": self.total_files,
+                "fileProgress": file_progress,
+                "downloadedBytes": downloaded_bytes,
+                "totalBytes": 0,
+                "currentFileDownloaded": downloaded,
+                "currentFileTotal": content_length,
+                "completedFiles": state.completed_files,
+                "activeFiles": state.active.len()
+            })
+        );
+    }
+}
+
+/// Requested state of an in-flight model download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadControl {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+const DOWNLOAD_CANCELLED: &str = "Download cancelled";
+
+/// Wait until the download is not paused. Errors if it was cancelled.
+async fn wait_while_paused(control: &mut watch::Receiver<DownloadControl>, cancel: &CancellationToken) -> Result<(), String> {
+    loop {
+        if cancel.is_cancelled() {
+            return Err(DOWNLOAD_CANCELLED.to_string());
+        }
+        let state = *control.borrow_and_update();
+        match state {
+            DownloadControl::Running => return Ok(()),
+            DownloadControl::Cancelled => return Err(DOWNLOAD_CANCELLED.to_string()),
+            DownloadControl::Paused => {}
+        }
+        tokio::select! {
+            _ = cancel.cancelled() => return Err(DOWNLOAD_CANCELLED.to_string()),
+            changed = control.changed() => {
+                // The sender only goes away once the whole download is over
+                if changed.is_err() {
+                    return Err(DOWNLOAD_CANCELLED.to_string());
+                }
+            }
+        }
+    }
 }
 
-// Memory-efficient streaming file download
+// Memory-efficient streaming file download. A pause drops the connection;
+// resuming continues from the bytes already on disk with a range request.
 async fn download_single_file(
     client: &reqwest::Client,
     file_url: &str,
     target_dir: &PathBuf,
     file_info: &HfFileInfo,
-    model_id: &str,
     file_index: usize,
-    total_files: usize,
-    total_downloaded_so_far: u64,
-    total_estimated_size: u64,
+    tracker: &DownloadTracker,
     app: &tauri::AppHandle
 ) -> Result<u64, String> {
     use futures::StreamExt;
 
+    let model_id = tracker.model_id.as_str();
+    let mut control = tracker.control.clone();
+
+    // Files queued behind a cancel or pause never start
+    wait_while_paused(&mut control, &tracker.cancel).await?;
+
     // Create subdirectories if needed (async)
     let target_file = target_dir.join(&file_info.path);
     if let Some(parent) = target_file.parent() {
@@ -149,25 +549,6 @@ async fn download_single_file(
             .map_err(|e| format!("Failed to create directory for {}: {}", file_info.path, e))?;
     }
 
-    // Start the request
-    let response = client
-        .get(file_url)
-        .header("User-Agent", constants::USER_AGENT)
-        .send().await
-        .map_err(|e| {
-            log_operation_error!("File download", &e, file = %file_info.path, model_id = %model_id);
-            format!("Request failed: {}", e)
-        })?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        log_operation_error!("File download", &format!("HTTP {}", status), file = %file_info.path, model_id = %model_id);
-        return Err(format!("HTTP error {}", status));
-    }
-
-    // Get content length for progress tracking
-    let content_length = response.content_length().unwrap_or(0);
-
     // Create the file
     let mut file = tokio::fs::File
         ::create(&target_file).await
@@ -176,57 +557,97 @@ async fn download_single_file(
             format!("Failed to create file: {}", e)
         })?;
 
-    // Stream the response body in chunks to avoid loading entire file into memory
-    let mut stream = response.bytes_stream();
     let mut downloaded = 0u64;
-    let mut last_progress_emit = std::time::Instant::now();
+    // Hash while writing so verification needs no second pass over the file
+    let mut hasher = file_info.expected_sha256().map(|_| Sha256::new());
+
+    let transfer: Result<(), String> = async {
+        loop {
+            wait_while_paused(&mut control, &tracker.cancel).await?;
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
+            // Start the request, picking up where a pause left off
+            let mut request = hf_auth::authorize(client.get(file_url));
+            if downloaded > 0 {
+                request = request.header(reqwest::header::RANGE, format!("bytes={}-", downloaded));
+            }
+            let response = request
+                .send().await
+                .map_err(|e| {
+                    log_operation_error!("File download", &e, file = %file_info.path, model_id = %model_id);
+                    format!("Request failed: {}", e)
+                })?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                log_operation_error!("File download", &format!("HTTP {}", status), file = %file_info.path, model_id = %model_id);
+                return Err(format!("HTTP error {}{}", status, access_hint(status)));
+            }
 
-        // Write chunk to file
-        file.write_all(&chunk).await.map_err(|e| format!("Failed to write chunk: {}", e))?;
+            // A server that ignores the range sends the whole file again
+            if downloaded > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                downloaded = 0;
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.reset();
+                }
+                file.set_len(0).await.map_err(|e| format!("Failed to truncate file: {}", e))?;
+                file.seek(std::io::SeekFrom::Start(0)).await.map_err(|e| format!("Failed to rewind file: {}", e))?;
+            }
 
-        downloaded += chunk.len() as u64;
+            // Get content length for progress tracking
+            let content_length = response.content_length().map_or(0, |remaining| downloaded + remaining);
 
-        // Emit progress events, but not too frequently to avoid overwhelming the UI
-        if last_progress_emit.elapsed().as_millis() > constants::DOWNLOAD_PROGRESS_INTERVAL_MS || downloaded == content_length {
-            let file_progress = if content_length > 0 {
-                (((downloaded as f64) / (content_length as f64)) * 100.0) as u32
-            } else {
-                0
-            };
+            // Stream the response body in chunks to avoid loading entire file into memory
+            let mut stream = response.bytes_stream();
 
-            // Calculate overall progress based on total downloaded bytes across all files
-            let total_downloaded_bytes = total_downloaded_so_far + downloaded;
-            let overall_progress = if total_estimated_size > 0 {
-                (((total_downloaded_bytes as f64) / (total_estimated_size as f64)) * 100.0) as u32
-            } else {
-                // Fallback to file-based progress if no size info
-                (((file_index as f64) / (total_files as f64)) * 100.0) as u32
-            };
+            let paused = loop {
+                tokio::select! {
+                    chunk = stream.next() => {
+                        let Some(chunk) = chunk else {
+                            break false;
+                        };
+                        let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
 
-            let _ = app.emit(
-                "download-progress",
-                serde_json::json!({
-                "modelId": model_id,
-                "progress": overall_progress.min(100), // Cap at 100%
-                "currentFile": file_info.path,
-                "fileIndex": file_index,
-                "totalFiles": total_files,
-                "fileProgress": file_progress,
-                "downloadedBytes": total_downloaded_bytes,
-                "totalBytes": total_estimated_size,
-                "currentFileDownloaded": downloaded,
-                "currentFileTotal": content_length
-            })
-            );
+                        // Write chunk to file
+                        file.write_all(&chunk).await.map_err(|e| format!("Failed to write chunk: {}", e))?;
+                        if let Some(hasher) = hasher.as_mut() {
+                            hasher.update(&chunk);
+                        }
 
-            last_progress_emit = std::time::Instant::now();
-        }
+                        downloaded += chunk.len() as u64;
 
-        // Add a small yield to prevent blocking the async runtime
-        tokio::task::yield_now().await;
+                        // The tracker throttles events so the UI is not overwhelmed
+                        tracker.update(app, &file_info.path, file_index, downloaded, content_length);
+                    }
+                    _ = tracker.cancel.cancelled() => return Err(DOWNLOAD_CANCELLED.to_string()),
+                    changed = control.changed() => {
+                        if changed.is_err() {
+                            return Err(DOWNLOAD_CANCELLED.to_string());
+                        }
+                        let state = *control.borrow_and_update();
+                        match state {
+                            DownloadControl::Running => {}
+                            DownloadControl::Paused => break true,
+                            DownloadControl::Cancelled => return Err(DOWNLOAD_CANCELLED.to_string()),
+                        }
+                    }
+                }
+            };
+
+            if !paused {
+                break;
+            }
+            tracing::debug!(file = %file_info.path, bytes = downloaded, model_id = %model_id, "File download paused");
+        }
+        Ok(())
+    }.await;
+
+    if let Err(e) = transfer {
+        // A cancelled file is never worth keeping
+        if e == DOWNLOAD_CANCELLED {
+            drop(file);
+            let _ = tokio::fs::remove_file(&target_file).await;
+        }
+        return Err(e);
     }
 
     // Ensure all data is written to disk
@@ -234,6 +655,14 @@ async fn download_single_file(
         log_operation_error!("File flush", &e, file = %file_info.path, model_id = %model_id);
         format!("Failed to flush file: {}", e)
     })?;
+    drop(file);
+
+    let sha256 = hasher.map(|hasher| format!("{:x}", hasher.finalize()));
+    if let Err(problem) = model_integrity::check_file(file_info, downloaded, sha256.as_deref()) {
+        log_operation_error!("File verification", &problem, file = %file_info.path, model_id = %model_id);
+        let _ = tokio::fs::remove_file(&target_file).await;
+        return Err(format!("Downloaded file failed verification: {}", problem));
+    }
 
     tracing::debug!(
         file = %file_info.path,
@@ -245,64 +674,144 @@ async fn download_single_file(
     Ok(downloaded)
 }
 
+fn emit_download_finished(app: &tauri::AppHandle, model_id: &str, success: bool, message: &Message) {
+    let _ = app.emit("download-finished", serde_json::json!({
+        "modelId": model_id,
+        "success": success,
+        "message": message,
+    }));
+}
+
+fn set_download_control(app: &tauri::AppHandle, model_id: String, control: DownloadControl) -> Result<(), String> {
+    let model_id = models::normalize_model_id(&model_id);
+
+    let state = app.state::<AppState>();
+    let downloads = state.downloads.lock();
+    let sender = downloads
+        .get(&model_id)
+        .ok_or_else(|| format!("No download in progress for {}", model_id))?;
+
+    if control == DownloadControl::Cancelled {
+        cancellation::cancel(app, &model_id)?;
+    }
+
+    // A cancelled download stays cancelled
+    let changed = sender.send_if_modified(|current| {
+        if *current == DownloadControl::Cancelled || *current == control {
+            return false;
+        }
+        *current = control;
+        true
+    });
+    if changed {
+        info!(model_id = %model_id, state = ?control, "Model download state changed");
+        let message = Message::new(match control {
+            DownloadControl::Running => "download.running",
+            DownloadControl::Paused => "download.paused",
+            DownloadControl::Cancelled => "download.cancelled",
+        }).param("model_id", model_id.as_str());
+        let _ = app.emit("download-state", serde_json::json!({ "modelId": model_id, "state": control, "message": message }));
+    }
+    Ok(())
+}
+
+/// Make a download of `model_id` pausable and cancellable; fails if one is already running
+fn register_download(
+    app: &tauri::AppHandle,
+    model_id: &str
+) -> Result<(watch::Receiver<DownloadControl>, OperationGuard), String> {
+    let (control_tx, control_rx) = watch::channel(DownloadControl::Running);
+    let state = app.state::<AppState>();
+    let mut downloads = state.downloads.lock();
+    if downloads.contains_key(model_id) {
+        return Err(format!("{} is already being downloaded", model_id));
+    }
+    let operation = cancellation::try_register(app, model_id, OperationKind::Download)?;
+    downloads.insert(model_id.to_string(), control_tx);
+    Ok((control_rx, operation))
+}
+
+#[tauri::command]
+pub async fn pause_model_download(app: tauri::AppHandle, model_id: String) -> Result<(), String> {
+    set_download_control(&app, model_id, DownloadControl::Paused)
+}
+
+#[tauri::command]
+pub async fn resume_model_download(app: tauri::AppHandle, model_id: String) -> Result<(), String> {
+    set_download_control(&app, model_id, DownloadControl::Running)
+}
+
+/// Stop a download. `download_entire_model` then removes what it wrote.
+#[tauri::command]
+pub async fn cancel_model_download(app: tauri::AppHandle, model_id: String) -> Result<(), String> {
+    set_download_control(&app, model_id, DownloadControl::Cancelled)
+}
+
 #[tauri::command]
 pub async fn search_models(query: String, limit: Option<u32>) -> Result<SearchResult, String> {
     log_operation_start!("Model search");
     
-    let client = reqwest::Client::new();
+    let client = crate::http::client()?;
     let search_limit = limit.unwrap_or(constants::DEFAULT_MODEL_SEARCH_LIMIT).min(constants::MAX_MODEL_SEARCH_LIMIT);
 
-    // Search specifically under OpenVINO organization
-    let search_query = if query.trim().is_empty() {
-        constants::OPENVINO_ORG.to_string()
-    } else {
-        format!("{}/{}", constants::OPENVINO_ORG, query)
+    // A query of the form "org/name" searches that organization only
+    let (orgs, name_query) = match query.trim().split_once('/') {
+        Some((org, name)) => {
+            models::check_allowed_org(query.trim())?;
+            (vec![org.to_string()], name.to_string())
+        }
+        None => (models::allowed_orgs(), query.trim().to_string()),
     };
 
-    tracing::debug!(query = %search_query, limit = search_limit, org = constants::OPENVINO_ORG, "Searching HuggingFace models");
+    let mut model_ids: Vec<String> = Vec::new();
+    for org in &orgs {
+        tracing::debug!(query = %name_query, limit = search_limit, org = %org, "Searching HuggingFace models");
 
-    let url = format!(
-        "{}/models?search={}&limit={}&author={}",
-        constants::HUGGINGFACE_API_BASE,
-        urlencoding::encode(&search_query),
-        search_limit,
-        constants::OPENVINO_ORG
-    );
+        let mut url = format!(
+            "{}/models?limit={}&author={}",
+            constants::HUGGINGFACE_API_BASE,
+            search_limit,
+            urlencoding::encode(org)
+        );
+        if !name_query.is_empty() {
+            url.push_str(&format!("&search={}", urlencoding::encode(&name_query)));
+        }
 
-    let response = client
-        .get(&url)
-        .header("User-Agent", "SparrowAI/1.0")
-        .send().await
-        .map_err(|e| {
-            log_operation_error!("Model search", &e);
-            format!("Failed to send request: {}", e)
-        })?;
+        let request = client.get(&url);
+        let response = hf_auth::authorize(request)
+            .send().await
+            .map_err(|e| {
+                log_operation_error!("Model search", &e);
+                format!("Failed to send request: {}", e)
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            log_operation_error!("Model search", &format!("API returned status {}", status), org = %org);
+            return Err(format!("API request failed with status: {}", status));
+        }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        log_operation_error!("Model search", &format!("API returned status {}", status));
-        return Err(format!("API request failed with status: {}", status));
+        let hf_models: Vec<HfModelInfo> = response
+            .json().await
+            .map_err(|e| {
+                log_operation_error!("Model search", &format!("JSON parse failed: {}", e));
+                format!("Failed to parse JSON: {}", e)
+            })?;
+
+        // Keep models of the organization whose name matches the query
+        let org_prefix = format!("{}/", org.to_lowercase());
+        model_ids.extend(
+            hf_models
+                .into_iter()
+                .map(|hf_model| hf_model.id)
+                .filter(|id| {
+                    let id = id.to_lowercase();
+                    id.starts_with(&org_prefix) && id.contains(&name_query.to_lowercase())
+                })
+        );
     }
-
-    let hf_models: Vec<HfModelInfo> = response
-        .json().await
-        .map_err(|e| {
-            log_operation_error!("Model search", &format!("JSON parse failed: {}", e));
-            format!("Failed to parse JSON: {}", e)
-        })?;
-
-    // Filter to only include OpenVINO models and optionally filter by query
-    let model_ids: Vec<String> = hf_models
-        .into_iter()
-        .filter(|hf_model| {
-            // Ensure the model is from OpenVINO organization
-            hf_model.id.starts_with("OpenVINO/") &&
-                // If there's a specific query, check if the model name contains it
-                (query.trim().is_empty() ||
-                    hf_model.id.to_lowercase().contains(&query.to_lowercase()))
-        })
-        .map(|hf_model| hf_model.id)
-        .collect();
+    model_ids.dedup();
+    model_ids.truncate(search_limit as usize);
 
     let total_count = model_ids.len() as u64;
 
@@ -319,14 +828,10 @@ pub async fn search_models(query: String, limit: Option<u32>) -> Result<SearchRe
 pub async fn get_model_info(model_id: String) -> Result<ModelInfo, String> {
     log_operation_start!("Get model info");
     
-    let client = reqwest::Client::new();
+    let client = crate::http::client()?;
 
-    // Ensure we're getting info for an OpenVINO model
-    let normalized_model_id = if model_id.starts_with("OpenVINO/") {
-        model_id
-    } else {
-        format!("OpenVINO/{}", model_id)
-    };
+    let normalized_model_id = models::normalize_model_id(&model_id);
+    models::check_allowed_org(&normalized_model_id)?;
 
     tracing::debug!(model_id = %normalized_model_id, "Fetching model info from HuggingFace");
 
@@ -336,9 +841,8 @@ pub async fn get_model_info(model_id: String) -> Result<ModelInfo, String> {
         normalized_model_id
     );
 
-    let response = client
-        .get(&url)
-        .header("User-Agent", "SparrowAI/1.0")
+    let request = client.get(&url);
+    let response = hf_auth::authorize(request)
         .send().await
         .map_err(|e| {
             log_operation_error!("Get model info", &e, model_id = %normalized_model_id);
@@ -348,9 +852,12 @@ pub async fn get_model_info(model_id: String) -> Result<ModelInfo, String> {
     if !response.status().is_success() {
         let status = response.status();
         log_operation_error!("Get model info", &format!("API returned status {}", status), model_id = %normalized_model_id);
+        if !access_hint(status).is_empty() {
+            return Err(format!("API request failed with status: {}{}", status, access_hint(status)));
+        }
         return Err(
             format!(
-                "API request failed with status: {}. Make sure the model exists under OpenVINO organization.",
+                "API request failed with status: {}. Make sure the model exists under an allowed organization.",
                 status
             )
         );
@@ -363,10 +870,10 @@ pub async fn get_model_info(model_id: String) -> Result<ModelInfo, String> {
             format!("Failed to parse JSON: {}", e)
         })?;
 
-    // Verify this is actually an OpenVINO model
-    if !hf_model.id.starts_with("OpenVINO/") {
-        log_operation_error!("Get model info", "Model not from OpenVINO organization", model_id = %hf_model.id);
-        return Err(format!("Model {} is not from OpenVINO organization", hf_model.id));
+    // Hugging Face resolves renamed repos, so check the id it answers with
+    if let Err(e) = models::check_allowed_org(&hf_model.id) {
+        log_operation_error!("Get model info", &e, model_id = %hf_model.id);
+        return Err(e);
     }
 
     // Extract collections from cardData
@@ -424,7 +931,7 @@ pub struct ModelUpdateInfo {
     pub needs_update: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct GraphGenerationParams {
     // Task type (text_generation, embeddings_ov, rerank_ov, etc.)
     pub task_type: Option<String>,
@@ -440,6 +947,11 @@ pub struct GraphGenerationParams {
     pub max_num_batched_tokens: Option<u32>,
     pub dynamic_split_fuse: Option<bool>,
     pub pipeline_type: Option<String>,
+
+    // Speculative decoding: absolute path of the draft model folder, and
+    // tokens it proposes per step (a request option, kept in the metadata)
+    pub draft_model_path: Option<String>,
+    pub num_assistant_tokens: Option<u32>,
     
     // Embeddings specific
     pub normalize: Option<bool>,
@@ -510,6 +1022,9 @@ async fn save_model_type(model_id: String, model_type: ModelType, pipeline_tag:
         model_type,
         pipeline_tag,
         commit_sha,
+        draft_model_id: None,
+        num_assistant_tokens: None,
+        tool_call_mode: ToolCallMode::Auto,
     };
     
     store.models.insert(model_id, metadata);
@@ -524,6 +1039,24 @@ pub async fn get_model_type(model_id: &str) -> Result<Option<ModelType>, String>
     Ok(store.models.get(model_id).map(|m| m.model_type.clone()))
 }
 
+/// Full id of a model OVMS serves under its bare name; downloaded models
+/// of any organization are found through their metadata
+pub async fn resolve_model_id(served_name: &str) -> String {
+    if served_name.contains('/') {
+        return served_name.to_string();
+    }
+    let suffix = format!("/{}", served_name);
+    load_model_metadata().await
+        .ok()
+        .and_then(|store| {
+            let mut ids: Vec<String> = store.models.into_keys().filter(|id| id.ends_with(&suffix)).collect();
+            // Prefer the OpenVINO copy if two organizations publish the same name
+            ids.sort_by_key(|id| !id.starts_with(&format!("{}/", constants::OPENVINO_ORG)));
+            ids.into_iter().next()
+        })
+        .unwrap_or_else(|| models::normalize_model_id(served_name))
+}
+
 // Remove model from metadata
 pub async fn remove_model_metadata(model_id: &str) -> Result<(), String> {
     let mut store = load_model_metadata().await?;
@@ -566,20 +1099,122 @@ pub async fn get_all_model_metadata() -> Result<HashMap<String, ModelMetadata>,
 // Manually set model type for a model (useful for existing models or manual corrections)
 #[tauri::command]
 pub async fn set_model_type(model_id: String, model_type_str: String) -> Result<(), String> {
-    let model_type = match model_type_str.as_str() {
-        "text" => ModelType::Text,
-        "image-to-text" => ModelType::ImageToText,
-        "embedding" => ModelType::Embedding,
-        "reranker" => ModelType::Reranker,
-        "image-generation" => ModelType::ImageGeneration,
-        "speech-to-text" => ModelType::SpeechToText,
-        "text-to-speech" => ModelType::TextToSpeech,
-        _ => return Err(format!("Invalid model type: {}", model_type_str)),
-    };
-    
+    let model_type = parse_model_type(&model_type_str)?;
     save_model_type(model_id, model_type, String::new(), None).await
 }
 
+/// Metadata of one model, if it has any
+pub async fn get_model_metadata(model_id: &str) -> Result<Option<ModelMetadata>, String> {
+    let store = load_model_metadata().await?;
+    Ok(store.models.get(model_id).cloned())
+}
+
+/// Pair a text model with a smaller draft model for speculative decoding, or
+/// unpair it when `draft_model_id` is unset. Only the draft entry of the
+/// model's graph changes; the model must be reloaded for it to apply.
+#[tauri::command]
+pub async fn pair_draft_model(
+    model_id: String,
+    draft_model_id: Option<String>,
+    num_assistant_tokens: Option<u32>
+) -> Result<ModelMetadata, String> {
+    const MAX_ASSISTANT_TOKENS: u32 = 32;
+
+    let model_id = models::normalize_model_id(&model_id);
+    let draft_model_id = draft_model_id.map(|id| models::normalize_model_id(&id));
+    let models_dir = paths::get_models_dir().map_err(|e| e.to_string())?;
+    let model_dir = models_dir.join(&model_id);
+    if !model_dir.is_dir() {
+        return Err(format!("Model not found: {}", model_id));
+    }
+
+    let mut store = load_model_metadata().await?;
+    if !matches!(store.models.get(&model_id).map(|m| &m.model_type), Some(ModelType::Text)) {
+        return Err(format!("{} is not a text generation model", model_id));
+    }
+
+    let draft_model_path = match &draft_model_id {
+        Some(draft_id) => {
+            if draft_id == &model_id {
+                return Err("A model cannot be its own draft model".to_string());
+            }
+            let draft_dir = models_dir.join(draft_id);
+            if !draft_dir.is_dir() {
+                return Err(format!("Draft model not found: {}", draft_id));
+            }
+            if !matches!(store.models.get(draft_id).map(|m| &m.model_type), Some(ModelType::Text)) {
+                return Err(format!("{} is not a text generation model", draft_id));
+            }
+            Some(draft_dir.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+    let num_assistant_tokens = match (&draft_model_id, num_assistant_tokens) {
+        (None, _) => None,
+        (Some(_), Some(tokens)) if tokens == 0 || tokens > MAX_ASSISTANT_TOKENS => {
+            return Err(format!("num_assistant_tokens must be between 1 and {}", MAX_ASSISTANT_TOKENS));
+        }
+        (Some(_), tokens) => tokens,
+    };
+
+    let graph_path = model_dir.join("graph.pbtxt");
+    match fs::read_to_string(&graph_path) {
+        Ok(graph) => {
+            let graph = set_graph_draft_model(&graph, draft_model_path.as_deref())?;
+            crate::storage::write_string(&graph_path, &graph).await
+                .map_err(|e| format!("Failed to write {}: {}", graph_path.display(), e))?;
+        }
+        Err(_) => {
+            let params = GraphGenerationParams { draft_model_path, num_assistant_tokens, ..Default::default() };
+            generate_graph_for_task("text_generation", &model_dir, &model_id, Some(&params))?;
+        }
+    }
+
+    let metadata = store.models.get_mut(&model_id).ok_or_else(|| format!("Model not found: {}", model_id))?;
+    metadata.draft_model_id = draft_model_id;
+    metadata.num_assistant_tokens = num_assistant_tokens;
+    let metadata = metadata.clone();
+    save_model_metadata(&store).await?;
+
+    info!(
+        model_id = %model_id,
+        draft_model_id = ?metadata.draft_model_id,
+        num_assistant_tokens = ?metadata.num_assistant_tokens,
+        "Updated draft model pairing"
+    );
+    Ok(metadata)
+}
+
+/// Choose how chat passes tools to a text model (see `ToolCallMode`)
+#[tauri::command]
+pub async fn set_tool_call_mode(model_id: String, mode: ToolCallMode) -> Result<ModelMetadata, String> {
+    let model_id = models::normalize_model_id(&model_id);
+    let mut store = load_model_metadata().await?;
+    let metadata = store.models.get_mut(&model_id).ok_or_else(|| format!("Model not found: {}", model_id))?;
+    if !matches!(metadata.model_type, ModelType::Text | ModelType::ImageToText) {
+        return Err(format!("{} is not a chat model", model_id));
+    }
+    metadata.tool_call_mode = mode;
+    let metadata = metadata.clone();
+    save_model_metadata(&store).await?;
+
+    info!(model_id = %model_id, mode = ?mode, "Updated tool call mode");
+    Ok(metadata)
+}
+
+fn parse_model_type(model_type: &str) -> Result<ModelType, String> {
+    match model_type {
+        "text" => Ok(ModelType::Text),
+        "image-to-text" => Ok(ModelType::ImageToText),
+        "embedding" => Ok(ModelType::Embedding),
+        "reranker" => Ok(ModelType::Reranker),
+        "image-generation" => Ok(ModelType::ImageGeneration),
+        "speech-to-text" => Ok(ModelType::SpeechToText),
+        "text-to-speech" => Ok(ModelType::TextToSpeech),
+        _ => Err(format!("Invalid model type: {}", model_type)),
+    }
+}
+
 // Initialize metadata for all downloaded models by fetching from HuggingFace
 #[tauri::command]
 pub async fn initialize_model_metadata(models_dir: Option<String>) -> Result<String, String> {
@@ -596,36 +1231,29 @@ pub async fn initialize_model_metadata(models_dir: Option<String>) -> Result<Str
         return Err("Models directory does not exist".to_string());
     }
 
-    // Look for OpenVINO models
-    let openvino_dir = downloads_dir.join("OpenVINO");
-    if !openvino_dir.exists() {
-        return Ok("No OpenVINO models found".to_string());
+    // Models are stored as <org>/<name>
+    let mut model_ids = Vec::new();
+    for org in models::allowed_orgs() {
+        let Ok(entries) = fs::read_dir(downloads_dir.join(&org)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            if let Some(model_name) = entry.file_name().to_str() {
+                model_ids.push(format!("{}/{}", org, model_name));
+            }
+        }
+    }
+    if model_ids.is_empty() {
+        return Ok("No models found".to_string());
     }
 
     let mut initialized_count = 0;
     let mut failed_count = 0;
 
-    // Iterate through model directories
-    let entries = fs::read_dir(&openvino_dir)
-        .map_err(|e| format!("Failed to read OpenVINO directory: {}", e))?;
-
-    for entry in entries {
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
-        }
-
-        let model_name = match path.file_name().and_then(|n| n.to_str()) {
-            Some(name) => name,
-            None => continue,
-        };
-
-        let model_id = format!("OpenVINO/{}", model_name);
+    for model_id in model_ids {
 
         // Check if metadata already exists
         if let Ok(Some(_)) = get_model_type(&model_id).await {
@@ -704,6 +1332,19 @@ fn map_task_type_to_model_type(task_type: &str) -> Option<ModelType> {
     }
 }
 
+/// The graph template a model type was set up with
+fn task_type_for_model_type(model_type: &ModelType) -> &'static str {
+    match model_type {
+        ModelType::Text => "text_generation",
+        ModelType::ImageToText => "image_text",
+        ModelType::Embedding => "embeddings_ov",
+        ModelType::Reranker => "rerank_ov",
+        ModelType::ImageGeneration => "image_generation",
+        ModelType::SpeechToText => "speech2text",
+        ModelType::TextToSpeech => "text2speech",
+    }
+}
+
 // Get commit SHA from metadata
 async fn get_commit_sha_from_metadata(model_id: &str) -> Option<String> {
     if let Ok(store) = load_model_metadata().await {
@@ -718,12 +1359,8 @@ pub async fn check_model_update_status(
     model_id: String,
     models_dir: Option<String>
 ) -> Result<ModelUpdateInfo, String> {
-    // Ensure we're checking an OpenVINO model
-    let normalized_model_id = if model_id.starts_with("OpenVINO/") {
-        model_id
-    } else {
-        format!("OpenVINO/{}", model_id)
-    };
+    // Bare names are OpenVINO models
+    let normalized_model_id = models::normalize_model_id(&model_id);
 
     // Determine model directory
     let model_dir = if let Some(dir) = models_dir {
@@ -739,6 +1376,17 @@ pub async fn check_model_update_status(
         return Err(format!("Model directory not found: {}", model_dir.to_string_lossy()));
     }
 
+    // Imported models have no repository to compare against
+    if models::is_local_model(&normalized_model_id) {
+        return Ok(ModelUpdateInfo {
+            model_id: normalized_model_id,
+            is_latest: true,
+            local_commit: None,
+            remote_commit: None,
+            needs_update: false,
+        });
+    }
+
     // Read local commit SHA from metadata
     let local_commit = get_commit_sha_from_metadata(&normalized_model_id).await;
 
@@ -765,19 +1413,466 @@ pub async fn check_model_update_status(
     })
 }
 
+/// Name an imported model is stored under: `name`, or the folder's name.
+/// It becomes a directory and the served model name, so it must be one plain
+/// path segment.
+fn local_model_name(source: &std::path::Path, name: Option<&str>) -> Result<String, String> {
+    let name = match name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => name.to_string(),
+        None => source.file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("Cannot derive a model name from {}", source.display()))?
+            .to_string(),
+    };
+    if name.starts_with('.') || name.contains(['/', '\\', ':']) {
+        return Err(format!("Invalid model name: {}", name));
+    }
+    Ok(name)
+}
+
+/// Whether `dir` holds an OpenVINO IR (an `.xml` next to its `.bin`), at
+/// the top level or one directory down as image generation pipelines do
+fn has_openvino_ir(dir: &std::path::Path) -> bool {
+    walkdir::WalkDir::new(dir)
+        .max_depth(2)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("xml"))
+        .any(|entry| entry.path().with_extension("bin").is_file())
+}
+
+fn copy_dir_all(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    for entry in walkdir::WalkDir::new(source) {
+        let entry = entry.map_err(std::io::Error::other)?;
+        let relative = entry.path().strip_prefix(source).map_err(std::io::Error::other)?;
+        let destination = target.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&destination)?;
+        } else {
+            fs::copy(entry.path(), &destination)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn link_dir(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_dir(source, target)
+}
+
+#[cfg(unix)]
+fn link_dir(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+/// Register a model converted outside the app (e.g. with optimum-intel's
+/// `optimum-cli export openvino`). The folder is copied under the models
+/// directory as `local/<name>`, or symlinked with `link` (graph.pbtxt is
+/// then written into the original folder, so linking needs write access to
+/// it). Returns the new model id.
+#[tauri::command]
+pub async fn import_local_model(
+    path: String,
+    model_type: String,
+    task_type: Option<String>,
+    name: Option<String>,
+    link: Option<bool>,
+    graph_params: Option<GraphGenerationParams>
+) -> Result<String, String> {
+    let link = link.unwrap_or(false);
+    // graph.pbtxt is written through the link into the source folder
+    let source = path_policy::check_str(&path, if link { Access::Write } else { Access::Read })?;
+    log_operation_start!("Import local model", path = %source.display());
+
+    if !source.is_dir() {
+        return Err(format!("Model folder not found: {}", source.display()));
+    }
+    if !has_openvino_ir(&source) {
+        return Err(format!(
+            "No OpenVINO model (.xml and .bin) found in {}. Convert the model with `optimum-cli export openvino` first.",
+            source.display()
+        ));
+    }
+
+    let model_type = parse_model_type(&model_type)?;
+    let task_type = task_type.unwrap_or_else(|| task_type_for_model_type(&model_type).to_string());
+    if map_task_type_to_model_type(&task_type).is_none() {
+        return Err(format!("Invalid task type: {}", task_type));
+    }
+
+    let model_id = format!("{}/{}", constants::LOCAL_MODEL_ORG, local_model_name(&source, name.as_deref())?);
+    let target_dir = paths::get_models_dir().map_err(|e| e.to_string())?.join(&model_id);
+    if target_dir.exists() || get_model_type(&model_id).await?.is_some() {
+        return Err(format!("A model named {} already exists", model_id));
+    }
+
+    {
+        let source = source.clone();
+        let target_dir = target_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = target_dir.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if link {
+                link_dir(&source, &target_dir)
+            } else {
+                copy_dir_all(&source, &target_dir).inspect_err(|_| {
+                    let _ = fs::remove_dir_all(&target_dir);
+                })
+            }
+        }).await
+            .map_err(|e| format!("Import task failed: {}", e))?
+            .map_err(|e| {
+                log_operation_error!("Import local model", &e, model_id = %model_id);
+                format!("Failed to {} model folder: {}", if link { "link" } else { "copy" }, e)
+            })?;
+    }
+
+    let params = graph_params.unwrap_or_default();
+    let registered = match generate_graph_for_task(&task_type, &target_dir, &model_id, Some(&params)) {
+        Ok(()) => save_model_type(model_id.clone(), model_type, "local".to_string(), None).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = registered {
+        // A link is removed without touching the folder it points to
+        let _ = if link { fs::remove_file(&target_dir).or_else(|_| fs::remove_dir(&target_dir)) } else { fs::remove_dir_all(&target_dir) };
+        log_operation_error!("Import local model", &e, model_id = %model_id);
+        return Err(e);
+    }
+
+    log_operation_success!("Import local model", model_id = %model_id, linked = link);
+    Ok(model_id)
+}
+
+/// Staging directory inside the model directory, so swapping files in is a rename
+const UPDATE_STAGING_DIR: &str = ".sparrow-update";
+
+/// Which files of a downloaded model differ from the repository
+#[derive(Debug)]
+struct UpdatePlan {
+    changed: Vec<HfFileInfo>,
+    /// Files of the previous download the repository no longer has
+    removed: Vec<String>,
+    unchanged: usize,
+}
+
+/// Files in the manifest compare by version. Others compare by size, and LFS
+/// files of the right size by SHA256; small files without a recorded version
+/// are cheap enough to fetch again.
+fn plan_update(model_dir: &std::path::Path, remote: &[HfFileInfo], manifest: &HashMap<String, String>) -> Result<UpdatePlan, String> {
+    let mut changed = Vec::new();
+    let mut unchanged = 0;
+
+    for file in remote {
+        let local = model_dir.join(&file.path);
+        let Ok(metadata) = fs::metadata(&local) else {
+            changed.push(file.clone());
+            continue;
+        };
+        let size_matches = file.expected_size().unwrap_or(metadata.len()) == metadata.len();
+
+        let same = match (manifest.get(&file.path), file.version()) {
+            (Some(recorded), Some(version)) => recorded == version && size_matches,
+            _ => match file.expected_sha256() {
+                Some(sha) if size_matches => model_integrity::sha256_file(&local)
+                    .map_err(|e| format!("Failed to read {}: {}", local.display(), e))?
+                    .eq_ignore_ascii_case(sha),
+                _ => false,
+            },
+        };
+        if same {
+            unchanged += 1;
+        } else {
+            changed.push(file.clone());
+        }
+    }
+
+    let removed = manifest
+        .keys()
+        .filter(|path| !remote.iter().any(|file| &file.path == *path))
+        .cloned()
+        .collect();
+
+    Ok(UpdatePlan { changed, removed, unchanged })
+}
+
+/// Move staged files over the model's files and delete `removed`. Replaced
+/// files are set aside until everything is in place, so a failure midway
+/// restores the previous version.
+fn swap_in_staged(staging: &std::path::Path, model_dir: &std::path::Path, files: &[String], removed: &[String]) -> Result<(), String> {
+    let backup = staging.join(".previous");
+    let mut set_aside: Vec<String> = Vec::new();
+    let mut placed: Vec<String> = Vec::new();
+
+    let result = (|| -> std::io::Result<()> {
+        for path in files.iter().chain(removed) {
+            let current = model_dir.join(path);
+            if current.exists() {
+                let aside = backup.join(path);
+                if let Some(parent) = aside.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&current, &aside)?;
+                set_aside.push(path.clone());
+            }
+        }
+        for path in files {
+            let target = model_dir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(staging.join(path), &target)?;
+            placed.push(path.clone());
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        for path in placed {
+            let _ = fs::remove_file(model_dir.join(path));
+        }
+        for path in set_aside {
+            let _ = fs::rename(backup.join(path), model_dir.join(path));
+        }
+        return Err(format!("Failed to swap in updated files: {}", e));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUpdateResult {
+    pub model_id: String,
+    pub updated_files: Vec<String>,
+    pub removed_files: Vec<String>,
+    pub unchanged_files: usize,
+    pub downloaded_bytes: u64,
+    pub commit_sha: Option<String>,
+    pub graph_regenerated: bool,
+    pub message: Message,
+}
+
+/// Record the commit a model was updated to, keeping its type
+async fn set_commit_sha(model_id: &str, model_info: &ModelInfo, task_type: Option<&str>) -> Result<(), String> {
+    let mut store = load_model_metadata().await?;
+    match store.models.get_mut(model_id) {
+        Some(metadata) => metadata.commit_sha = model_info.sha.clone(),
+        None => {
+            let model_type = task_type.and_then(map_task_type_to_model_type).unwrap_or(ModelType::Text);
+            store.models.insert(model_id.to_string(), ModelMetadata {
+                model_id: model_id.to_string(),
+                model_type,
+                pipeline_tag: model_info.pipeline_tag.clone().unwrap_or_else(|| "unknown".to_string()),
+                commit_sha: model_info.sha.clone(),
+                draft_model_id: None,
+                num_assistant_tokens: None,
+                tool_call_mode: ToolCallMode::Auto,
+            });
+        }
+    }
+    save_model_metadata(&store).await
+}
+
+/// Bring a downloaded model up to date with its repository: only changed
+/// files are downloaded, into a staging directory, and swapped in once all
+/// of them arrived and verified. graph.pbtxt is then regenerated, keeping
+/// the device of the current one unless `graph_params` say otherwise.
+#[tauri::command]
+pub async fn update_model(
+    app: tauri::AppHandle,
+    model_id: String,
+    models_dir: Option<String>,
+    graph_params: Option<GraphGenerationParams>
+) -> Result<ModelUpdateResult, String> {
+    let model_id = models::normalize_model_id(&model_id);
+    log_operation_start!("Model update", model_id = %model_id);
+
+    if models::is_local_model(&model_id) {
+        return Err(format!("{} was imported from a local folder and cannot be updated from Hugging Face", model_id));
+    }
+
+    let model_dir = model_target_dir(&model_id, models_dir)?;
+    if !model_dir.exists() {
+        return Err(format!("Model directory not found: {}", model_dir.to_string_lossy()));
+    }
+
+    let served_name = model_id.rsplit('/').next().unwrap_or(&model_id);
+    let loaded = crate::ovms::get_loaded_models(app.clone()).await.unwrap_or_default();
+    if loaded.iter().any(|name| name == served_name) {
+        return Err(format!("{} is loaded; unload it before updating", model_id));
+    }
+
+    let model_info = get_model_info(model_id.clone()).await?;
+    let client = crate::http::client_with_timeout(std::time::Duration::from_secs(300))?;
+    let mut remote = fetch_file_tree(&client, &model_id).await?;
+    let filter = read_file_filter(&model_dir);
+    remote.retain(|file| filter.matches(&file.path));
+
+    let mut manifest = read_file_manifest(&model_dir);
+    let plan = {
+        let model_dir = model_dir.clone();
+        let remote = remote.clone();
+        let manifest = manifest.clone();
+        tokio::task::spawn_blocking(move || plan_update(&model_dir, &remote, &manifest)).await
+            .map_err(|e| format!("Update check failed: {}", e))??
+    };
+
+    let task_type = get_model_type(&model_id).await.ok().flatten()
+        .map(|model_type| task_type_for_model_type(&model_type).to_string())
+        .or_else(|| detect_task_type(&model_info));
+
+    if plan.changed.is_empty() && plan.removed.is_empty() {
+        set_commit_sha(&model_id, &model_info, task_type.as_deref()).await?;
+        log_operation_success!("Model update", model_id = %model_id, note = "already up to date");
+        return Ok(ModelUpdateResult {
+            model_id: model_id.clone(),
+            updated_files: Vec::new(),
+            removed_files: Vec::new(),
+            unchanged_files: plan.unchanged,
+            downloaded_bytes: 0,
+            commit_sha: model_info.sha.clone(),
+            graph_regenerated: false,
+            message: Message::new("model.up_to_date").param("model_id", model_id.as_str()),
+        });
+    }
+
+    let staging = model_dir.join(UPDATE_STAGING_DIR);
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    let estimate = size_estimate(&model_id, &plan.changed, &staging);
+    if estimate.fits == Some(false) {
+        return Err(format!(
+            "Not enough disk space to update {}: the changed files need {:.1} GB",
+            model_id,
+            (estimate.required_bytes + constants::DOWNLOAD_DISK_MARGIN_BYTES) as f64 / (1024.0 * 1024.0 * 1024.0)
+        ));
+    }
+    tokio::fs::create_dir_all(&staging).await
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    log_progress!("Downloading changed files", model_id = %model_id, changed = plan.changed.len(), removed = plan.removed.len());
+
+    use futures::StreamExt;
+
+    let concurrency = crate::settings::current().downloads.concurrency.clamp(1, constants::MAX_DOWNLOAD_CONCURRENCY);
+    let (control_rx, operation) = register_download(&app, &model_id)?;
+    let tracker = DownloadTracker::new(&model_id, plan.changed.len(), control_rx, operation.token());
+
+    let results: Vec<(String, Result<u64, String>)> = futures::stream
+        ::iter(plan.changed.iter().enumerate())
+        .map(|(index, file)| {
+            let client = &client;
+            let staging = &staging;
+            let tracker = &tracker;
+            let app = &app;
+            let model_id = &model_id;
+            async move {
+                let file_url = format!("https://huggingface.co/{}/resolve/main/{}", model_id, file.path);
+                let result = download_single_file(client, &file_url, staging, file, index + 1, tracker, app).await;
+                tracker.finish(&file.path, result.as_ref().ok().copied());
+                (file.path.clone(), result)
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    app.state::<AppState>().downloads.lock().remove(&model_id);
+
+    let cancelled = operation.is_cancelled();
+    drop(operation);
+    let errors: Vec<String> = results
+        .iter()
+        .filter_map(|(path, result)| result.as_ref().err().map(|e| format!("Failed to download {}: {}", path, e)))
+        .collect();
+    if cancelled || !errors.is_empty() {
+        // Nothing was swapped in yet, so the model is still the previous version
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+        let code = if cancelled { "download.cancelled" } else { "download.failed" };
+        let message = Message::new(code).param("model_id", model_id.as_str());
+        emit_download_finished(&app, &model_id, false, &message);
+        let e = if cancelled { message.text } else { format!("Model update failed:\n{}", errors.join("\n")) };
+        log_operation_error!("Model update", &e, model_id = %model_id);
+        return Err(e);
+    }
+
+    let updated_files: Vec<String> = plan.changed.iter().map(|file| file.path.clone()).collect();
+    {
+        let staging = staging.clone();
+        let model_dir = model_dir.clone();
+        let updated_files = updated_files.clone();
+        let removed = plan.removed.clone();
+        tokio::task::spawn_blocking(move || swap_in_staged(&staging, &model_dir, &updated_files, &removed)).await
+            .map_err(|e| format!("Update failed: {}", e))?
+            .map_err(|e| {
+                log_operation_error!("Model update", &e, model_id = %model_id);
+                e
+            })?;
+    }
+    if let Err(e) = tokio::fs::remove_dir_all(&staging).await {
+        warn!(error = %e, dir = %staging.display(), "Failed to remove update staging directory");
+    }
+
+    for file in &plan.changed {
+        if let Some(version) = file.version() {
+            manifest.insert(file.path.clone(), version.to_string());
+        }
+    }
+    for path in &plan.removed {
+        manifest.remove(path);
+    }
+    write_file_manifest(&model_dir, &manifest);
+
+    let graph_regenerated = match &task_type {
+        Some(task_type) => {
+            let params = graph_params.unwrap_or_else(|| GraphGenerationParams {
+                target_device: fs::read_to_string(model_dir.join("graph.pbtxt"))
+                    .ok()
+                    .and_then(|graph| crate::ovms::graph_device(&graph)),
+                ..Default::default()
+            });
+            match generate_graph_for_task(task_type, &model_dir, &model_id, Some(&params)) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!(error = %e, task_type = %task_type, "Failed to regenerate graph.pbtxt");
+                    false
+                }
+            }
+        }
+        None => false,
+    };
+
+    if let Err(e) = set_commit_sha(&model_id, &model_info, task_type.as_deref()).await {
+        error!(error = %e, model_id = %model_id, "Failed to save model metadata after update");
+    }
+
+    let downloaded_bytes = results.iter().filter_map(|(_, result)| result.as_ref().ok()).sum();
+    let message = Message::new("model.updated")
+        .param("model_id", model_id.as_str())
+        .param("files", updated_files.len());
+    emit_download_finished(&app, &model_id, true, &message);
+
+    log_operation_success!("Model update", model_id = %model_id, updated = updated_files.len(), removed = plan.removed.len());
+    Ok(ModelUpdateResult {
+        model_id,
+        updated_files,
+        removed_files: plan.removed,
+        unchanged_files: plan.unchanged,
+        downloaded_bytes,
+        commit_sha: model_info.sha,
+        graph_regenerated,
+        message,
+    })
+}
+
 #[tauri::command]
 pub async fn download_entire_model(
     model_id: String,
     download_path: Option<String>,
     graph_params: Option<GraphGenerationParams>,
+    file_filter: Option<FileFilter>,
     app: tauri::AppHandle
 ) -> Result<String, String> {
-    // Ensure we're downloading an OpenVINO model
-    let normalized_model_id = if model_id.starts_with("OpenVINO/") {
-        model_id
-    } else {
-        format!("OpenVINO/{}", model_id)
-    };
+    // Bare names are OpenVINO models
+    let normalized_model_id = models::normalize_model_id(&model_id);
 
     log_operation_start!("Model download", model_id = %normalized_model_id);
 
@@ -788,26 +1883,47 @@ pub async fn download_entire_model(
     })?;
 
     // Create a client with timeout to prevent hanging
-    let client = reqwest::Client
-        ::builder()
-        .timeout(std::time::Duration::from_secs(300)) // 5 minute timeout per request
-        .build()
+    let client = crate::http::client_with_timeout(std::time::Duration::from_secs(300)) // 5 minute timeout per request
         .map_err(|e| {
             log_operation_error!("HTTP client creation", &e);
-            format!("Failed to create HTTP client: {}", e)
+            e
         })?;
 
-    let target_dir = if let Some(path) = download_path {
-        PathBuf::from(path).join(&normalized_model_id)
-    } else {
-        // Use .sparrow/models as default
-        paths::get_models_dir()
-            .map_err(|e| {
-                log_operation_error!("Get models directory", &e);
-                e.to_string()
-            })?
-            .join(&normalized_model_id)
+    let target_dir = model_target_dir(&normalized_model_id, download_path).map_err(|e| {
+        log_operation_error!("Get models directory", &e);
+        e
+    })?;
+
+    let file_filter = file_filter.unwrap_or_default();
+
+    // Sizes and checksums to verify each file against
+    let mut file_list = match fetch_file_tree(&client, &normalized_model_id).await {
+        Ok(files) => files,
+        Err(e) => {
+            log_warning!("Downloading without size or checksum checks", error = %e, model_id = %normalized_model_id);
+            Vec::new()
+        }
     };
+    file_list.retain(|file| file_filter.matches(&file.path));
+
+    if !file_list.is_empty() {
+        let estimate = size_estimate(&normalized_model_id, &file_list, &target_dir);
+        if estimate.fits == Some(false) {
+            let gib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+            let e = format!(
+                "Not enough disk space to download {}: it needs {:.1} GB and {:.1} GB is free at {}",
+                normalized_model_id,
+                gib(estimate.required_bytes + constants::DOWNLOAD_DISK_MARGIN_BYTES),
+                gib(estimate.available_bytes.unwrap_or(0)),
+                target_dir.display()
+            );
+            log_operation_error!("Model download", &e, model_id = %normalized_model_id);
+            return Err(e);
+        }
+    }
+
+    // Only a directory this download created is removed again on cancel
+    let created_target_dir = !target_dir.exists();
 
     // Create target directory
     std::fs::create_dir_all(&target_dir).map_err(|e| {
@@ -819,13 +1935,20 @@ pub async fn download_entire_model(
     let downloadable_files: Vec<&ModelSibling> = model_info.siblings
         .iter()
         .filter(|sibling| !sibling.rfilename.is_empty())
+        .filter(|sibling| file_filter.matches(&sibling.rfilename))
         .collect();
 
     if downloadable_files.is_empty() {
-        log_operation_error!("Model download", "No files found in repository", 
-            model_id = %normalized_model_id
-        );
-        return Err("No files found in model repository".to_string());
+        let e = if file_filter.is_empty() {
+            "No files found in model repository"
+        } else {
+            "No files in the model repository match the file filter"
+        };
+        log_operation_error!("Model download", e, model_id = %normalized_model_id);
+        if created_target_dir {
+            let _ = fs::remove_dir(&target_dir);
+        }
+        return Err(e.to_string());
     }
 
     let total_files = downloadable_files.len();
@@ -835,75 +1958,112 @@ pub async fn download_entire_model(
         total_files = total_files
     );
 
-    let mut downloaded_files = Vec::new();
-    let mut errors = Vec::new();
-    let mut total_downloaded_size = 0u64;
+    use futures::StreamExt;
 
-    for (index, sibling) in downloadable_files.iter().enumerate() {
-        // Don't encode model ID or file path - they're part of the URL path
-        let file_url = format!(
-            "https://huggingface.co/{}/resolve/main/{}",
-            normalized_model_id,
-            sibling.rfilename
-        );
+    let file_tree: HashMap<String, HfFileInfo> = file_list.into_iter().map(|file| (file.path.clone(), file)).collect();
+
+    let concurrency = crate::settings::current().downloads.concurrency.clamp(1, constants::MAX_DOWNLOAD_CONCURRENCY);
+    let (control_rx, operation) = register_download(&app, &normalized_model_id)?;
+    let tracker = DownloadTracker::new(&normalized_model_id, total_files, control_rx, operation.token());
+
+    let _ = app.emit(
+        "download-progress",
+        serde_json::json!({
+            "modelId": normalized_model_id,
+            "progress": 0,
+            "currentFile": downloadable_files[0].rfilename,
+            "fileIndex": 1,
+            "totalFiles": total_files,
+            "fileProgress": 0,
+        })
+    );
 
-        // Create temporary HfFileInfo for compatibility with download_single_file
-        let file_info = HfFileInfo {
-            path: sibling.rfilename.clone(),
-            file_type: "file".to_string(),
-            size: None,  // We don't have size info from siblings
-        };
+    let results: Vec<(String, Result<u64, String>)> = futures::stream
+        ::iter(downloadable_files.iter().enumerate())
+        .map(|(index, sibling)| {
+            let client = &client;
+            let target_dir = &target_dir;
+            let tracker = &tracker;
+            let app = &app;
+            let model_id = &normalized_model_id;
+            let file_tree = &file_tree;
+            async move {
+                // Don't encode model ID or file path - they're part of the URL path
+                let file_url = format!(
+                    "https://huggingface.co/{}/resolve/main/{}",
+                    model_id,
+                    sibling.rfilename
+                );
 
-        // Emit progress update
-        let current_progress = ((index as f64 / total_files as f64) * 100.0) as u32;
-        let _ = app.emit(
-            "download-progress",
-            serde_json::json!({
-                "modelId": normalized_model_id,
-                "progress": current_progress,
-                "currentFile": sibling.rfilename,
-                "fileIndex": index + 1,
-                "totalFiles": total_files,
-                "fileProgress": 0,
-            })
-        );
+                // Files missing from the tree listing are downloaded unverified
+                let file_info = file_tree.get(&sibling.rfilename).cloned().unwrap_or_else(|| HfFileInfo {
+                    path: sibling.rfilename.clone(),
+                    file_type: "file".to_string(),
+                    size: None,
+                    oid: None,
+                    lfs: None,
+                });
+
+                let result = download_single_file(client, &file_url, target_dir, &file_info, index + 1, tracker, app).await;
+                tracker.finish(&sibling.rfilename, result.as_ref().ok().copied());
+                (sibling.rfilename.clone(), result)
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    app.state::<AppState>().downloads.lock().remove(&normalized_model_id);
+
+    let cancelled = operation.is_cancelled();
+    drop(operation);
+    if cancelled {
+        // Unfinished files are already gone; a model directory this download
+        // created only holds a fraction of the model
+        if created_target_dir {
+            if let Err(e) = tokio::fs::remove_dir_all(&target_dir).await {
+                warn!(error = %e, dir = %target_dir.display(), "Failed to remove cancelled download");
+            }
+        }
+        log_warning!("Model download cancelled", model_id = %normalized_model_id);
+        let message = Message::new("download.cancelled").param("model_id", normalized_model_id.as_str());
+        emit_download_finished(&app, &normalized_model_id, false, &message);
+        return Err(message.text);
+    }
 
-        // Download the file
-        let download_result = download_single_file(
-            &client,
-            &file_url,
-            &target_dir,
-            &file_info,
-            &normalized_model_id,
-            index + 1,
-            total_files,
-            total_downloaded_size,
-            0,  // No total size estimate available
-            &app
-        ).await;
+    let mut downloaded_files = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_downloaded_size = 0u64;
+    let mut manifest = read_file_manifest(&target_dir);
 
-        match download_result {
+    for (file_name, result) in results {
+        match result {
             Ok(file_size) => {
-                downloaded_files.push(sibling.rfilename.clone());
+                if let Some(version) = file_tree.get(&file_name).and_then(HfFileInfo::version) {
+                    manifest.insert(file_name.clone(), version.to_string());
+                }
+                downloaded_files.push(file_name);
                 total_downloaded_size += file_size;
             }
             Err(e) => {
-                let error_msg = format!("Failed to download {}: {}", sibling.rfilename, e);
+                // Other files keep downloading; failures are reported together below
+                let error_msg = format!("Failed to download {}: {}", file_name, e);
                 error!(error = %error_msg, "Model download failed");
                 errors.push(error_msg);
-
-                // Continue with other files instead of crashing
-                continue;
             }
         }
     }
 
+    write_file_manifest(&target_dir, &manifest);
+    write_file_filter(&target_dir, &file_filter);
+
     if downloaded_files.is_empty() {
         let error_details = if errors.is_empty() {
             "No files could be downloaded from the repository.".to_string()
         } else {
             format!("Download errors occurred:\n{}", errors.join("\n"))
         };
+        emit_download_finished(&app, &normalized_model_id, false, &Message::new("download.failed").param("model_id", normalized_model_id.as_str()));
         return Err(format!("Failed to download model files. {}", error_details));
     }
 
@@ -1055,6 +2215,16 @@ pub async fn download_entire_model(
         );
     }
 
+    let summary = if errors.is_empty() {
+        Message::new("download.completed").param("size_bytes", total_downloaded_size)
+    } else {
+        Message::new("download.partial").param("failed", errors.len())
+    };
+    let summary = summary
+        .param("model_id", normalized_model_id.as_str())
+        .param("files", downloaded_files.len());
+    emit_download_finished(&app, &normalized_model_id, true, &summary);
+
     if !errors.is_empty() {
         Ok(
             format!(
@@ -1310,6 +2480,32 @@ fn detect_task_type(model_info: &ModelInfo) -> Option<String> {
     None
 }
 
+/// Replace the `draft_models_path` entry of a text generation graph, keeping
+/// every other option as it is; `None` removes it
+fn set_graph_draft_model(graph: &str, draft_path: Option<&str>) -> Result<String, String> {
+    const DRAFT_KEY: &str = "draft_models_path:";
+
+    let mut lines: Vec<String> = graph.lines()
+        .filter(|line| !line.trim_start().starts_with(DRAFT_KEY))
+        .map(str::to_string)
+        .collect();
+    if let Some(draft_path) = draft_path {
+        // Next to the device, inside the LLM calculator options
+        let device_line = lines.iter()
+            .position(|line| line.trim_start().starts_with("device:"))
+            .ok_or("The model's graph has no text generation options to add a draft model to")?;
+        let indent = {
+            let line = &lines[device_line];
+            line[..line.len() - line.trim_start().len()].to_string()
+        };
+        lines.insert(device_line + 1, format!("{}{} \"{}\",", indent, DRAFT_KEY, draft_path.replace('\\', "/")));
+    }
+    if graph.ends_with('\n') {
+        lines.push(String::new());
+    }
+    Ok(lines.join("\n"))
+}
+
 // Helper function to generate graph.pbtxt for a given task type
 fn generate_graph_for_task(
     task_type: &str,
@@ -1400,7 +2596,15 @@ fn generate_graph_for_task(
                 template_params.insert("reasoning_parser".to_string(), "".to_string());
             }
             
-            template_params.insert("draft_models_path".to_string(), "".to_string());
+            match params.and_then(|p| p.draft_model_path.as_deref()) {
+                Some(draft_path) => {
+                    template_params.insert("draft_models_path".to_string(),
+                        format!("draft_models_path: \"{}\",\n          ", draft_path.replace('\\', "/")));
+                }
+                None => {
+                    template_params.insert("draft_models_path".to_string(), "".to_string());
+                }
+            }
             render_template(TEXT_GENERATION_GRAPH_TEMPLATE, &template_params)
         },
         "embeddings_ov" => {
@@ -1562,3 +2766,169 @@ fn generate_graph_for_task(
     info!(task_type = %task_type, "Generated graph.pbtxt for model");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sparrow-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn remote_file(path: &str, size: u64, oid: &str) -> HfFileInfo {
+        HfFileInfo {
+            path: path.to_string(),
+            file_type: "file".to_string(),
+            size: Some(size),
+            oid: Some(oid.to_string()),
+            lfs: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_update_compares_recorded_versions() {
+        let dir = temp_dir("plan");
+        fs::write(dir.join("config.json"), b"{}").unwrap();
+        fs::write(dir.join("tokenizer.json"), b"{}").unwrap();
+
+        let remote = vec![
+            remote_file("config.json", 2, "a1"),
+            remote_file("tokenizer.json", 2, "b2"),
+            remote_file("new.json", 2, "c1"),
+        ];
+        let manifest: HashMap<String, String> = [
+            ("config.json", "a1"),
+            ("tokenizer.json", "b1"),
+            ("dropped.json", "d1"),
+        ].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+        let plan = plan_update(&dir, &remote, &manifest).unwrap();
+        let changed: Vec<&str> = plan.changed.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(changed, vec!["tokenizer.json", "new.json"]);
+        assert_eq!(plan.removed, vec!["dropped.json".to_string()]);
+        assert_eq!(plan.unchanged, 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_swap_in_staged_replaces_and_removes() {
+        let dir = temp_dir("swap");
+        let staging = dir.join(UPDATE_STAGING_DIR);
+        fs::create_dir_all(staging.join("sub")).unwrap();
+        fs::write(dir.join("a.bin"), b"old").unwrap();
+        fs::write(dir.join("gone.txt"), b"old").unwrap();
+        fs::write(staging.join("a.bin"), b"new").unwrap();
+        fs::write(staging.join("sub/b.bin"), b"new").unwrap();
+
+        let files = vec!["a.bin".to_string(), "sub/b.bin".to_string()];
+        swap_in_staged(&staging, &dir, &files, &["gone.txt".to_string()]).unwrap();
+
+        assert_eq!(fs::read(dir.join("a.bin")).unwrap(), b"new");
+        assert_eq!(fs::read(dir.join("sub/b.bin")).unwrap(), b"new");
+        assert!(!dir.join("gone.txt").exists());
+
+        // A missing staged file rolls everything back
+        fs::write(staging.join("a.bin"), b"newer").unwrap();
+        let files = vec!["a.bin".to_string(), "missing.bin".to_string()];
+        assert!(swap_in_staged(&staging, &dir, &files, &[]).is_err());
+        assert_eq!(fs::read(dir.join("a.bin")).unwrap(), b"new");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_local_model_name() {
+        let source = std::path::Path::new("/models/qwen2.5-1.5b-int4-ov");
+        assert_eq!(local_model_name(source, None).unwrap(), "qwen2.5-1.5b-int4-ov");
+        assert_eq!(local_model_name(source, Some(" My-Model ")).unwrap(), "My-Model");
+        assert!(local_model_name(source, Some("../escape")).is_err());
+        assert!(local_model_name(source, Some(".hidden")).is_err());
+    }
+
+    #[test]
+    fn test_import_copies_only_openvino_folders() {
+        let source = temp_dir("import-src");
+        assert!(!has_openvino_ir(&source));
+
+        fs::create_dir_all(source.join("tokenizer")).unwrap();
+        fs::write(source.join("openvino_model.xml"), "<net/>").unwrap();
+        assert!(!has_openvino_ir(&source));
+        fs::write(source.join("openvino_model.bin"), b"weights").unwrap();
+        fs::write(source.join("tokenizer").join("vocab.json"), "{}").unwrap();
+        assert!(has_openvino_ir(&source));
+
+        let target = temp_dir("import-dst").join("local").join("model");
+        copy_dir_all(&source, &target).unwrap();
+        assert_eq!(fs::read(target.join("openvino_model.bin")).unwrap(), b"weights");
+        assert!(target.join("tokenizer").join("vocab.json").is_file());
+
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(target.parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn test_text_graph_includes_draft_model() {
+        let dir = temp_dir("draft-graph");
+        let params = GraphGenerationParams {
+            draft_model_path: Some("C:\\models\\OpenVINO\\Qwen3-0.6B-int4-ov".to_string()),
+            ..Default::default()
+        };
+        generate_graph_for_task("text_generation", &dir, "OpenVINO/Qwen3-8B-int4-ov", Some(&params)).unwrap();
+        let graph = fs::read_to_string(dir.join("graph.pbtxt")).unwrap();
+        assert!(graph.contains("draft_models_path: \"C:/models/OpenVINO/Qwen3-0.6B-int4-ov\","));
+
+        generate_graph_for_task("text_generation", &dir, "OpenVINO/Qwen3-8B-int4-ov", None).unwrap();
+        let graph = fs::read_to_string(dir.join("graph.pbtxt")).unwrap();
+        assert!(!graph.contains("draft_models_path"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_set_graph_draft_model_keeps_other_options() {
+        let dir = temp_dir("draft-patch");
+        let params = GraphGenerationParams {
+            target_device: Some("GPU".to_string()),
+            cache_size: Some(8),
+            kv_cache_precision: Some("u8".to_string()),
+            ..Default::default()
+        };
+        generate_graph_for_task("text_generation", &dir, "OpenVINO/Qwen3-8B-int4-ov", Some(&params)).unwrap();
+        let original = fs::read_to_string(dir.join("graph.pbtxt")).unwrap();
+
+        let paired = set_graph_draft_model(&original, Some("/models/OpenVINO/Qwen3-0.6B-int4-ov")).unwrap();
+        assert!(paired.contains("draft_models_path: \"/models/OpenVINO/Qwen3-0.6B-int4-ov\","));
+        assert!(paired.contains("cache_size: 8,"));
+        assert!(paired.contains("KV_CACHE_PRECISION"));
+        assert_eq!(crate::ovms::graph_device(&paired).as_deref(), Some("GPU"));
+
+        let repaired = set_graph_draft_model(&paired, Some("/models/other")).unwrap();
+        assert_eq!(repaired.matches("draft_models_path").count(), 1);
+        assert_eq!(set_graph_draft_model(&paired, None).unwrap(), original);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_filter_globs() {
+        let filter = FileFilter {
+            include: vec!["*.json".to_string(), "openvino_*".to_string(), "tokenizer/**".to_string()],
+            exclude: vec!["**/*fp16*".to_string()],
+        };
+        assert!(filter.matches("config.json"));
+        assert!(filter.matches("openvino_model.bin"));
+        assert!(filter.matches("tokenizer/vocab.txt"));
+        assert!(filter.matches("nested/generation_config.json"));
+        assert!(!filter.matches("README.md"));
+        assert!(!filter.matches("openvino_model_fp16.bin"));
+        assert!(!filter.matches("fp16/openvino_model_fp16.xml"));
+
+        assert!(FileFilter::default().matches("anything.bin"));
+        assert!(glob_matches("**/model.bin", "model.bin"));
+        assert!(!glob_matches("int?/*.bin", "int4/sub/model.bin"));
+        assert!(glob_matches("int?/*.bin", "int4/model.bin"));
+    }
+}
@@ -0,0 +1,361 @@
+//! OVMS initialization at startup (or on first use when deferred).
+//!
+//! Initialization runs as three ordered steps: make sure the OVMS binary is
+//! present, make sure a config exists, then start the server. Progress is
+//! kept in `AppState::init_status` and emitted as `ovms-init-status`. Every attempt is
+//! appended to `~/.sparrow/init_history.json` with the step that failed, so
+//! `retry_initialization` can resume from that step instead of starting over.
+
+use std::sync::atomic::{ AtomicBool, Ordering };
+
+use chrono::{ DateTime, Utc };
+use serde::{ Deserialize, Serialize };
+use tauri::{ AppHandle, Emitter, Manager, State };
+use tracing::{ error, info };
+
+use crate::{ ovms, paths, storage };
+use crate::messages::Message;
+use crate::state::AppState;
+
+/// Number of attempts kept in the history file
+const MAX_HISTORY_ENTRIES: usize = 20;
+
+const BGE_MODEL_NAME: &str = "bge-base-en-v1.5-int8-ov";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitStep {
+    Download,
+    Config,
+    StartServer,
+}
+
+impl InitStep {
+    const ALL: [InitStep; 3] = [InitStep::Download, InitStep::Config, InitStep::StartServer];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitAttempt {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// First step this attempt ran; earlier steps had already succeeded
+    pub from_step: InitStep,
+    pub success: bool,
+    pub failed_step: Option<InitStep>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct InitializationStatus {
+    step: String,
+    /// `message_code` rendered in the configured locale
+    message: String,
+    message_code: String,
+    progress: u8,
+    is_complete: bool,
+    has_error: bool,
+    error_message: Option<String>,
+    /// Message code of the failure, e.g. `init.failed.download`
+    error_code: Option<String>,
+    failed_step: Option<InitStep>,
+    /// Only filled in by `get_initialization_status`, not on emitted events
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    history: Vec<InitAttempt>,
+}
+
+impl InitializationStatus {
+    pub(crate) fn new(step: &str) -> Self {
+        let message = step_message(step);
+        Self {
+            step: step.to_string(),
+            message: message.text,
+            message_code: message.code,
+            progress: 0,
+            is_complete: false,
+            has_error: false,
+            error_message: None,
+            error_code: None,
+            failed_step: None,
+            history: Vec::new(),
+        }
+    }
+}
+
+// Set once the first caller has kicked off OVMS initialization
+static OVMS_INIT_STARTED: AtomicBool = AtomicBool::new(false);
+
+// Held while an attempt (first run or retry) is executing
+static INIT_RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn update_status<F>(app_handle: &AppHandle, f: F) where F: FnOnce(&mut InitializationStatus) {
+    let state = app_handle.state::<AppState>();
+    let mut status = state.init_status.lock();
+    f(&mut status);
+    app_handle
+        .emit("ovms-init-status", &*status)
+        .unwrap_or_else(|e| {
+            log_warning!("Failed to emit init status", error = %e);
+        });
+}
+
+/// Each step has a message code of its own, `init.<step>`
+fn step_message(step: &str) -> Message {
+    Message::new(&format!("init.{}", step))
+}
+
+fn set_step(app_handle: &AppHandle, step: &str, progress: u8) {
+    let message = step_message(step);
+    update_status(app_handle, |status| {
+        status.step = step.to_string();
+        status.message = message.text;
+        status.message_code = message.code;
+        status.progress = progress;
+    });
+}
+
+async fn load_history() -> Vec<InitAttempt> {
+    let Ok(path) = paths::get_init_history_path() else {
+        return Vec::new();
+    };
+
+    storage::read_string(&path).await
+        .ok()
+        .flatten()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+async fn record_attempt(attempt: InitAttempt) {
+    let mut history = load_history().await;
+    history.push(attempt);
+    if history.len() > MAX_HISTORY_ENTRIES {
+        history.drain(..history.len() - MAX_HISTORY_ENTRIES);
+    }
+
+    let result = async {
+        let path = paths::get_init_history_path().map_err(|e| e.to_string())?;
+        let content = serde_json::to_string_pretty(&history)
+            .map_err(|e| format!("Failed to serialize init history: {}", e))?;
+        storage::write_string(&path, &content).await
+            .map_err(|e| format!("Failed to write init history: {}", e))
+    }.await;
+
+    if let Err(e) = result {
+        log_warning!("Failed to save initialization history", error = %e);
+    }
+}
+
+/// Current status with the attempt history attached
+async fn status_report(state: &AppState) -> InitializationStatus {
+    let mut status = state.init_status.lock().clone();
+    status.history = load_history().await;
+    status
+}
+
+#[tauri::command]
+pub async fn get_initialization_status(state: State<'_, AppState>) -> Result<InitializationStatus, String> {
+    Ok(status_report(&state).await)
+}
+
+/// Start OVMS now if it was deferred at launch, and report the resulting status
+#[tauri::command]
+pub async fn ensure_ovms_started(app_handle: AppHandle) -> Result<InitializationStatus, String> {
+    ensure_ovms_initialized(&app_handle).await;
+    Ok(status_report(&app_handle.state::<AppState>()).await)
+}
+
+/// Retry initialization without restarting the app.
+///
+/// By default resumes from the step that failed last time; `from_step`
+/// forces a specific starting point (later steps always run after it).
+#[tauri::command]
+pub async fn retry_initialization(
+    app_handle: AppHandle,
+    from_step: Option<InitStep>
+) -> Result<InitializationStatus, String> {
+    let (is_complete, failed_step) = {
+        let state = app_handle.state::<AppState>();
+        let status = state.init_status.lock();
+        (status.is_complete, status.failed_step)
+    };
+
+    if is_complete && from_step.is_none() {
+        return Ok(status_report(&app_handle.state::<AppState>()).await);
+    }
+
+    let step = from_step.or(failed_step).unwrap_or(InitStep::Download);
+
+    if INIT_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Initialization is already running".to_string());
+    }
+    OVMS_INIT_STARTED.store(true, Ordering::SeqCst);
+
+    info!(from_step = ?step, "Retrying OVMS initialization");
+    run_initialization(&app_handle, step).await;
+    INIT_RUNNING.store(false, Ordering::SeqCst);
+
+    Ok(status_report(&app_handle.state::<AppState>()).await)
+}
+
+/// Record that OVMS startup was postponed, so the UI does not wait on it
+pub fn mark_ovms_deferred(app_handle: &AppHandle) {
+    set_step(app_handle, "deferred", 0);
+}
+
+/// Run OVMS initialization if nobody has yet, otherwise wait for the
+/// in-flight run to finish. Safe to call from any command that needs OVMS.
+pub(crate) async fn ensure_ovms_initialized(app_handle: &AppHandle) {
+    if !OVMS_INIT_STARTED.swap(true, Ordering::SeqCst) {
+        INIT_RUNNING.store(true, Ordering::SeqCst);
+        run_initialization(app_handle, InitStep::Download).await;
+        INIT_RUNNING.store(false, Ordering::SeqCst);
+        return;
+    }
+
+    // Another caller is initializing; wait up to 2 minutes for it
+    for _ in 0..240 {
+        let done = {
+            let state = app_handle.state::<AppState>();
+            let status = state.init_status.lock();
+            !INIT_RUNNING.load(Ordering::SeqCst) && (status.is_complete || status.has_error)
+        };
+        if done {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    log_warning!("Timed out waiting for OVMS initialization");
+}
+
+async fn run_initialization(app_handle: &AppHandle, from_step: InitStep) {
+    log_operation_start!("OVMS initialization", from_step = ?from_step);
+
+    let started_at = Utc::now();
+    update_status(app_handle, |status| {
+        status.is_complete = false;
+        status.has_error = false;
+        status.error_message = None;
+        status.error_code = None;
+        status.failed_step = None;
+    });
+
+    let mut failure = None;
+    for step in InitStep::ALL.into_iter().filter(|step| *step >= from_step) {
+        let result = match step {
+            InitStep::Download => ensure_ovms_present(app_handle).await,
+            InitStep::Config => ensure_ovms_config(app_handle).await,
+            InitStep::StartServer => start_server(app_handle).await,
+        };
+        if let Err(e) = result {
+            failure = Some((step, e));
+            break;
+        }
+    }
+
+    match &failure {
+        None => {
+            log_operation_success!("OVMS initialization");
+            let message = step_message("complete");
+            update_status(app_handle, |status| {
+                status.step = "complete".to_string();
+                status.message = message.text;
+                status.message_code = message.code;
+                status.progress = 100;
+                status.is_complete = true;
+            });
+        }
+        Some((step, e)) => {
+            log_operation_error!("OVMS initialization", e, failed_step = ?step);
+            let message = Message::new(match step {
+                InitStep::Download => "init.failed.download",
+                InitStep::Config => "init.failed.config",
+                InitStep::StartServer => "init.failed.start_server",
+            });
+            update_status(app_handle, |status| {
+                status.has_error = true;
+                status.error_message = Some(e.clone());
+                status.error_code = Some(message.code.clone());
+                status.failed_step = Some(*step);
+                status.message = message.text;
+                status.message_code = message.code;
+            });
+        }
+    }
+
+    record_attempt(InitAttempt {
+        started_at,
+        finished_at: Some(Utc::now()),
+        from_step,
+        success: failure.is_none(),
+        failed_step: failure.as_ref().map(|(step, _)| *step),
+        error: failure.map(|(_, e)| e),
+    }).await;
+}
+
+async fn ensure_ovms_present(app_handle: &AppHandle) -> Result<(), String> {
+    set_step(app_handle, "checking", 15);
+    tracing::debug!("Checking OVMS presence");
+
+    if ovms::is_ovms_present(Some(app_handle)) {
+        tracing::debug!("OVMS already present, skipping download");
+        set_step(app_handle, "present", 75);
+        return Ok(());
+    }
+
+    log_progress!("OVMS not found, downloading...");
+    set_step(app_handle, "downloading", 25);
+
+    let msg = ovms::download_ovms(app_handle.clone()).await
+        .map_err(|e| format!("Failed to download OVMS: {}", e))?;
+    tracing::debug!(message = %msg, "OVMS download completed");
+    set_step(app_handle, "downloaded", 75);
+    Ok(())
+}
+
+/// Create the initial config (registering the BGE embedding model) if none exists.
+/// Failures are logged but not fatal; the server step validates the config.
+async fn ensure_ovms_config(app_handle: &AppHandle) -> Result<(), String> {
+    let config_exists = paths::get_ovms_config_path(Some(app_handle))
+        .map(|path| path.exists())
+        .unwrap_or(false);
+
+    if config_exists {
+        tracing::debug!("OVMS config already exists, skipping config creation");
+        return Ok(());
+    }
+
+    log_progress!("Creating initial OVMS config...");
+    set_step(app_handle, "creating_config", 77);
+
+    let models_dir = match paths::get_models_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            log_warning!("Failed to get models directory for OVMS config", error = %e, note = "skipping config creation");
+            return Ok(());
+        }
+    };
+
+    let bge_model_path = models_dir
+        .join("OpenVINO")
+        .join(BGE_MODEL_NAME)
+        .to_string_lossy()
+        .to_string();
+
+    tracing::debug!(model = BGE_MODEL_NAME, path = %bge_model_path, "Creating OVMS config for BGE model");
+
+    match ovms::create_ovms_config(app_handle.clone(), BGE_MODEL_NAME.to_string(), bge_model_path).await {
+        Ok(_) => info!("OVMS config created successfully"),
+        Err(e) => error!(error = %e, "Failed to create OVMS config, continuing initialization"),
+    }
+    Ok(())
+}
+
+async fn start_server(app_handle: &AppHandle) -> Result<(), String> {
+    log_progress!("Starting OVMS server...");
+    set_step(app_handle, "starting_server", 85);
+
+    let msg = ovms::start_ovms_server(app_handle.clone()).await
+        .map_err(|e| format!("Failed to start OVMS server: {}", e))?;
+    tracing::debug!(message = %msg, "OVMS server started successfully");
+    Ok(())
+}
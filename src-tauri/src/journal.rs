@@ -0,0 +1,324 @@
+//! Daily notes: one Markdown file per day, written from a template.
+//!
+//! `create_daily_note` starts the day's note from `journal.template` (or a
+//! built-in one) in `journal.folder`. `append_to_daily_note` adds a timestamped
+//! entry: free text, a model-written summary of a chat session, or the latest
+//! outcome of a scheduled task. With `journal.log_task_runs` set, every task
+//! run is appended on its own.
+//!
+//! When `journal.auto_ingest` is on, a note is re-indexed into the "journal"
+//! collection after each change, and `search_journal` queries it. A configured
+//! `journal.folder` must be writable under the path policy.
+
+use std::path::PathBuf;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use chrono::{ Local, NaiveDate };
+use serde::{ Deserialize, Serialize };
+use tauri::{ AppHandle, Emitter };
+
+use crate::rag::search::SearchService;
+use crate::rag::{ backend, ingest, SearchResult };
+use crate::path_policy::{ self, Access };
+use crate::tasks::{ ExecutionStatus, Task, TaskExecutionLog };
+use crate::{ chat, paths, settings, storage };
+
+/// Collection the notes are indexed into
+pub const JOURNAL_COLLECTION: &str = "journal";
+
+const DEFAULT_TEMPLATE: &str = "# {{date}} ({{weekday}})\n\n## Plan\n\n## Notes\n\n## Log\n";
+
+const SUMMARY_PROMPT: &str = "You summarize a chat conversation for the user's daily journal. \
+Write two to four sentences in the first person plural (\"we\"), covering what was asked, \
+what was found or decided, and anything left open. Reply with the summary only.";
+
+/// Serializes read-modify-write of note files
+static NOTE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Serializes re-indexing of the journal folder, so two runs for one note
+/// cannot both delete its chunks and then both store new ones
+static INDEX_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEntry {
+    Text { text: String },
+    ChatSummary { session_id: String },
+    TaskOutcome { task_id: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalNote {
+    pub date: String,
+    pub path: String,
+    pub content: String,
+    /// The note did not exist before this call
+    pub created: bool,
+}
+
+fn journal_dir() -> Result<PathBuf, String> {
+    let dir = match settings::current().journal.folder.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => path_policy::check_str(&dir, Access::Write)?,
+        None => paths::get_journal_dir().map_err(|e| e.to_string())?,
+    };
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn parse_date(date: Option<&str>) -> Result<NaiveDate, String> {
+    match date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", date)),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+fn render_template(template: &str, date: NaiveDate) -> String {
+    template
+        .replace("{{date}}", &date.format("%Y-%m-%d").to_string())
+        .replace("{{weekday}}", &date.format("%A").to_string())
+        .replace("{{time}}", &Local::now().format("%H:%M").to_string())
+}
+
+/// Entry block appended to a note
+fn entry_block(heading: &str, body: &str) -> String {
+    format!("\n### {} {}\n\n{}\n", Local::now().format("%H:%M"), heading, body.trim())
+}
+
+fn task_outcome(task: &Task, log: &TaskExecutionLog) -> String {
+    let status = match log.status {
+        ExecutionStatus::Success => "succeeded",
+        ExecutionStatus::Failed => "failed",
+        ExecutionStatus::Skipped => "was skipped",
+    };
+    let detail = log.message.as_deref().or(log.error.as_deref()).unwrap_or("").trim();
+    if detail.is_empty() {
+        format!("Task \"{}\" {}.", task.name, status)
+    } else {
+        format!("Task \"{}\" {}: {}", task.name, status, detail)
+    }
+}
+
+/// Create the note for `date` from the template unless it exists; returns it and whether it was created
+async fn ensure_note(date: NaiveDate) -> Result<(PathBuf, String, bool), String> {
+    let path = journal_dir()?.join(format!("{}.md", date.format("%Y-%m-%d")));
+    if let Some(content) = storage::read_string(&path).await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))? {
+        return Ok((path, content, false));
+    }
+
+    let template = settings::current().journal.template
+        .filter(|template| !template.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+    let content = render_template(&template, date);
+    storage::write_string(&path, &content).await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok((path, content, true))
+}
+
+/// Re-index a changed note in the background; the note itself is already saved
+fn reindex(app: &AppHandle, path: &std::path::Path) {
+    if !settings::current().journal.auto_ingest {
+        return;
+    }
+    let app = app.clone();
+    let file_path = path.to_string_lossy().to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let _guard = INDEX_LOCK.lock().await;
+            let store = backend::open(JOURNAL_COLLECTION)?;
+            store.delete_file(&file_path).await?;
+            ingest::ingest_into(&app, file_path.clone(), JOURNAL_COLLECTION).await
+        }.await;
+        if let Err(e) = result {
+            log_warning!("Failed to index journal note", error = %e, file = %file_path);
+        }
+    });
+}
+
+fn note(date: NaiveDate, path: PathBuf, content: String, created: bool) -> JournalNote {
+    JournalNote {
+        date: date.format("%Y-%m-%d").to_string(),
+        path: path.to_string_lossy().to_string(),
+        content,
+        created,
+    }
+}
+
+async fn append(app: &AppHandle, date: NaiveDate, block: &str) -> Result<JournalNote, String> {
+    let (path, content, created) = {
+        let _guard = NOTE_LOCK.lock().await;
+        let (path, mut content, created) = ensure_note(date).await?;
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(block);
+        storage::write_string(&path, &content).await
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        (path, content, created)
+    };
+
+    reindex(app, &path);
+    let journal_note = note(date, path, content, created);
+    let _ = app.emit("journal-note-updated", &journal_note);
+    Ok(journal_note)
+}
+
+async fn summarize_session(app: &AppHandle, session_id: &str) -> Result<(String, String), String> {
+    let sessions = chat::load_chat_sessions().await?.sessions;
+    let session = sessions.get(session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+    if session.messages.is_empty() {
+        return Err("The chat session has no messages to summarize".to_string());
+    }
+
+    crate::ensure_ovms_initialized(app).await;
+    let model = crate::ovms::get_loaded_model(app.clone()).await?
+        .ok_or("No model is loaded; load a text model to summarize the chat")?;
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(vec![
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(SUMMARY_PROMPT)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(crate::rag::sessions::session_summary(session))
+                .build()
+                .map_err(|e| format!("Failed to build user message: {}", e))?
+                .into(),
+        ])
+        .temperature(0.3)
+        .max_tokens(400u32)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let client = Client::with_config(
+        OpenAIConfig::new()
+            .with_api_key("unused")
+            .with_api_base(crate::ovms::openai_api_base())
+    );
+    let response = client.chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Summary request failed: {}", e))?;
+    let summary = response.choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .filter(|content| !content.trim().is_empty())
+        .ok_or_else(|| "The model returned an empty summary".to_string())?;
+    Ok((session.title.clone(), summary))
+}
+
+/// Append a task run to today's note when `journal.log_task_runs` is set
+pub(crate) async fn record_task_run(app: &AppHandle, task: &Task, log: &TaskExecutionLog) {
+    if !settings::current().journal.log_task_runs {
+        return;
+    }
+    let block = entry_block("Task", &task_outcome(task, log));
+    if let Err(e) = append(app, Local::now().date_naive(), &block).await {
+        log_warning!("Failed to log task run to journal", error = %e, task_id = %task.id);
+    }
+}
+
+/// Open the note for `date` (today when unset), creating it from the template if needed
+#[tauri::command]
+pub async fn create_daily_note(app: AppHandle, date: Option<String>) -> Result<JournalNote, String> {
+    let date = parse_date(date.as_deref())?;
+    let (path, content, created) = {
+        let _guard = NOTE_LOCK.lock().await;
+        ensure_note(date).await?
+    };
+    if created {
+        tracing::info!(path = %path.display(), "Created daily note");
+        reindex(&app, &path);
+    }
+    Ok(note(date, path, content, created))
+}
+
+#[tauri::command]
+pub async fn append_to_daily_note(app: AppHandle, entry: JournalEntry, date: Option<String>) -> Result<JournalNote, String> {
+    let date = parse_date(date.as_deref())?;
+    let block = match entry {
+        JournalEntry::Text { text } => {
+            if text.trim().is_empty() {
+                return Err("Journal entry is empty".to_string());
+            }
+            entry_block("Note", &text)
+        }
+        JournalEntry::ChatSummary { session_id } => {
+            let (title, summary) = summarize_session(&app, &session_id).await?;
+            entry_block(&format!("Chat: {}", title), &summary)
+        }
+        JournalEntry::TaskOutcome { task_id } => {
+            let task = crate::tasks::get_task(task_id.clone()).await?;
+            let log = crate::tasks::get_task_logs(task_id).await?
+                .pop()
+                .ok_or_else(|| format!("Task \"{}\" has not run yet", task.name))?;
+            entry_block("Task", &task_outcome(&task, &log))
+        }
+    };
+    append(&app, date, &block).await
+}
+
+/// Dates with a note, newest first
+#[tauri::command]
+pub async fn list_daily_notes() -> Result<Vec<String>, String> {
+    let dir = journal_dir()?;
+    let mut dates: Vec<String> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".md").map(str::to_string))
+        .filter(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+        .collect();
+    dates.sort_by(|a, b| b.cmp(a));
+    Ok(dates)
+}
+
+#[tauri::command]
+pub async fn search_journal(query: String, limit: Option<usize>) -> Result<Vec<SearchResult>, String> {
+    SearchService::for_collection(JOURNAL_COLLECTION)?
+        .search(&query, limit.unwrap_or(10), true)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template_fills_placeholders() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        assert_eq!(render_template(DEFAULT_TEMPLATE, date).lines().next(), Some("# 2026-03-02 (Monday)"));
+        assert_eq!(render_template("no placeholders", date), "no placeholders");
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date(Some("2026-01-31")).unwrap(), NaiveDate::from_ymd_opt(2026, 1, 31).unwrap());
+        assert!(parse_date(Some("31.01.2026")).is_err());
+        assert!(parse_date(None).is_ok());
+    }
+
+    #[test]
+    fn test_task_outcome_prefers_message_then_error() {
+        let task = crate::tasks::digest::builtin_task();
+        let log = TaskExecutionLog {
+            task_id: task.id.clone(),
+            executed_at: chrono::Utc::now(),
+            status: ExecutionStatus::Failed,
+            message: None,
+            error: Some("No model is loaded".to_string()),
+            steps: Vec::new(),
+        };
+        assert_eq!(task_outcome(&task, &log), "Task \"Weekly memory review\" failed: No model is loaded");
+    }
+}
@@ -0,0 +1,265 @@
+//! Preferred response language: prompt instruction plus a cheap post-check.
+//!
+//! Small local models often drift back into English. The preferred language
+//! comes from the session (`ChatSession::preferred_language`) or, failing
+//! that, from `language.preferred` in settings. It is spelled out in the
+//! system prompt, and when `language.verify` is on the finished answer is
+//! checked with script and stop-word heuristics. An answer that is clearly in
+//! another language is asked for again.
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestAssistantMessageArgs,
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+}
+
+struct Language {
+    /// Lowercase English names and ISO 639-1 codes
+    names: &'static [&'static str],
+    script: Script,
+    /// Frequent short words, used to tell Latin-script languages apart
+    stopwords: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        names: &["english", "en"],
+        script: Script::Latin,
+        stopwords: &["the", "and", "is", "are", "of", "to", "in", "that", "it", "with", "for", "this", "you"],
+    },
+    Language {
+        names: &["spanish", "español", "es"],
+        script: Script::Latin,
+        stopwords: &["el", "la", "los", "las", "y", "es", "de", "que", "en", "un", "una", "por", "para", "con"],
+    },
+    Language {
+        names: &["french", "français", "fr"],
+        script: Script::Latin,
+        stopwords: &["le", "la", "les", "et", "est", "de", "des", "que", "un", "une", "pour", "avec", "vous", "dans"],
+    },
+    Language {
+        names: &["german", "deutsch", "de"],
+        script: Script::Latin,
+        stopwords: &["der", "die", "das", "und", "ist", "nicht", "ein", "eine", "zu", "mit", "sie", "ich", "auf", "für"],
+    },
+    Language {
+        names: &["portuguese", "português", "pt"],
+        script: Script::Latin,
+        stopwords: &["o", "os", "as", "e", "é", "de", "que", "um", "uma", "não", "para", "com", "em", "você"],
+    },
+    Language {
+        names: &["italian", "italiano", "it"],
+        script: Script::Latin,
+        stopwords: &["il", "lo", "gli", "e", "è", "di", "che", "un", "una", "non", "per", "con", "sono", "della"],
+    },
+    Language {
+        names: &["dutch", "nederlands", "nl"],
+        script: Script::Latin,
+        stopwords: &["de", "het", "een", "en", "is", "van", "niet", "dat", "je", "met", "voor", "zijn", "op"],
+    },
+    Language { names: &["russian", "русский", "ru"], script: Script::Cyrillic, stopwords: &[] },
+    Language { names: &["ukrainian", "українська", "uk"], script: Script::Cyrillic, stopwords: &[] },
+    Language { names: &["greek", "ελληνικά", "el"], script: Script::Greek, stopwords: &[] },
+    Language { names: &["arabic", "العربية", "ar"], script: Script::Arabic, stopwords: &[] },
+    Language { names: &["persian", "farsi", "fa"], script: Script::Arabic, stopwords: &[] },
+    Language { names: &["hebrew", "עברית", "he"], script: Script::Hebrew, stopwords: &[] },
+    Language { names: &["hindi", "हिन्दी", "hi"], script: Script::Devanagari, stopwords: &[] },
+    Language { names: &["thai", "ไทย", "th"], script: Script::Thai, stopwords: &[] },
+    Language { names: &["korean", "한국어", "ko"], script: Script::Hangul, stopwords: &[] },
+    Language { names: &["japanese", "日本語", "ja"], script: Script::Kana, stopwords: &[] },
+    Language {
+        names: &["chinese", "中文", "zh", "simplified chinese", "traditional chinese"],
+        script: Script::Han,
+        stopwords: &[],
+    },
+];
+
+/// Fewer letters than this is too little text to judge
+const MIN_LETTERS: usize = 20;
+
+fn lookup(language: &str) -> Option<&'static Language> {
+    let key = language.trim().to_lowercase();
+    LANGUAGES.iter().find(|l| l.names.contains(&key.as_str()))
+}
+
+fn script_of(c: char) -> Option<Script> {
+    match c as u32 {
+        0x41..=0x5a | 0x61..=0x7a | 0xc0..=0x24f => Some(Script::Latin),
+        0x370..=0x3ff => Some(Script::Greek),
+        0x400..=0x52f => Some(Script::Cyrillic),
+        0x590..=0x5ff => Some(Script::Hebrew),
+        0x600..=0x6ff | 0x750..=0x77f => Some(Script::Arabic),
+        0x900..=0x97f => Some(Script::Devanagari),
+        0xe00..=0xe7f => Some(Script::Thai),
+        0x3040..=0x30ff => Some(Script::Kana),
+        0x3400..=0x4dbf | 0x4e00..=0x9fff => Some(Script::Han),
+        0xac00..=0xd7af | 0x1100..=0x11ff => Some(Script::Hangul),
+        _ => None,
+    }
+}
+
+/// Drop fenced code blocks, whose keywords would read as English
+fn strip_code(text: &str) -> String {
+    text.split("```").step_by(2).collect::<Vec<_>>().join(" ")
+}
+
+/// Whether `text` is written in `language`; `None` when the language is not
+/// known here or there is too little text to tell
+pub fn matches_language(language: &str, text: &str) -> Option<bool> {
+    let expected = lookup(language)?;
+    let prose = strip_code(text);
+
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in prose.chars().filter_map(script_of) {
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    let letters: usize = counts.iter().map(|(_, n)| n).sum();
+    if letters < MIN_LETTERS {
+        return None;
+    }
+    let count = |script: Script| counts.iter().find(|(s, _)| *s == script).map_or(0, |(_, n)| *n);
+
+    let in_script = match expected.script {
+        // Japanese mixes kana with kanji; Chinese text has no kana at all
+        Script::Kana => count(Script::Kana) > 0 && count(Script::Kana) + count(Script::Han) >= letters / 2,
+        Script::Han => count(Script::Kana) == 0 && count(Script::Han) >= letters / 2,
+        script => count(script) >= letters / 2,
+    };
+    if !in_script {
+        return Some(false);
+    }
+    if expected.stopwords.is_empty() {
+        return Some(true);
+    }
+
+    let words: Vec<String> = prose
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let hits = |language: &Language| words.iter().filter(|w| language.stopwords.contains(&w.as_str())).count();
+
+    let own = hits(expected);
+    let best_other = LANGUAGES.iter()
+        .filter(|l| !std::ptr::eq(*l, expected) && !l.stopwords.is_empty())
+        .map(hits)
+        .max()
+        .unwrap_or(0);
+
+    Some(!(best_other >= 3 && best_other > own * 2))
+}
+
+/// The language a chat should answer in: the session's choice, else the profile's
+pub fn preferred_language(session_language: Option<&str>) -> Option<String> {
+    session_language
+        .map(str::to_string)
+        .or_else(|| settings::current().language.preferred)
+        .map(|language| language.trim().to_string())
+        .filter(|language| !language.is_empty())
+}
+
+/// Appended to the system prompt
+pub fn system_instruction(language: &str) -> String {
+    format!(
+        "\n\nAlways write your replies in {language}, whatever language the question, the documents or the tool results are in. Keep code, commands and proper names unchanged."
+    )
+}
+
+/// Ask the model once more, without streaming, to give `answer` in `language`
+pub(crate) async fn reask(
+    client: &Client<OpenAIConfig>,
+    model_name: &str,
+    messages: &[ChatCompletionRequestMessage],
+    answer: &str,
+    language: &str
+) -> Result<String, String> {
+    let mut messages = messages.to_vec();
+    messages.push(
+        ChatCompletionRequestAssistantMessageArgs::default()
+            .content(answer.to_string())
+            .build()
+            .map_err(|e| format!("Failed to build assistant message: {}", e))?
+            .into()
+    );
+    messages.push(
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(format!("Your reply was not in {language}. Give the same answer again, written entirely in {language}."))
+            .build()
+            .map_err(|e| format!("Failed to build user message: {}", e))?
+            .into()
+    );
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model_name.to_string())
+        .messages(messages)
+        .temperature(0.3)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let response = client.chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Failed to re-ask in {}: {}", language, e))?;
+
+    response.choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .filter(|content| !content.trim().is_empty())
+        .ok_or_else(|| "The model returned an empty answer".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_wrong_latin_language() {
+        let english = "The answer is that you need to restart the service, and it should work with the new config.";
+        let spanish = "La respuesta es que tienes que reiniciar el servicio y con la nueva configuración debería funcionar.";
+
+        assert_eq!(matches_language("English", english), Some(true));
+        assert_eq!(matches_language("es", spanish), Some(true));
+        assert_eq!(matches_language("Spanish", english), Some(false));
+    }
+
+    #[test]
+    fn test_script_languages() {
+        let russian = "Чтобы это исправить, перезапустите службу и проверьте настройки.";
+        assert_eq!(matches_language("Russian", russian), Some(true));
+        assert_eq!(matches_language("Japanese", russian), Some(false));
+
+        let chinese = "要解决这个问题，请重新启动服务并检查配置文件中的设置是否正确。";
+        assert_eq!(matches_language("Chinese", chinese), Some(true));
+        assert_eq!(matches_language("Japanese", chinese), Some(false));
+    }
+
+    #[test]
+    fn test_undecidable_cases() {
+        assert_eq!(matches_language("Klingon", "Some text that is long enough to judge"), None);
+        assert_eq!(matches_language("German", "ok"), None);
+        // Code alone says nothing about the prose language
+        assert_eq!(matches_language("German", "```\nfor item in items { print(item) }\n```"), None);
+    }
+}
@@ -1,6 +1,4 @@
-use std::sync::{ Arc, Mutex };
-use tauri::Emitter;
-use tracing::{ info, error };
+use tauri::Manager;
 
 #[macro_use]
 mod log_utils;
@@ -10,13 +8,65 @@ mod constants;
 mod models;
 mod huggingface;
 mod ovms;
+mod ovms_watchdog;
+mod ovms_logs;
+mod ovms_backups;
+mod ovms_metrics;
 mod chat;
+mod chat_metrics;
+mod cancellation;
+mod tool_approval;
+mod server_busy;
+mod structured_output;
 mod rag;
 mod mcp;
 mod logging;
 mod autostart;
 mod tasks;
 mod gallery;
+mod system_state;
+mod settings;
+mod tray;
+mod init;
+mod state;
+mod storage;
+mod quick_actions;
+mod selection;
+mod text_assist;
+mod language;
+mod model_switch;
+mod model_diagnostics;
+mod memory_estimate;
+mod hardware;
+mod model_integrity;
+mod notifications;
+mod snapshot;
+mod hf_auth;
+mod messages;
+mod coalesce;
+mod disk;
+mod disk_watchdog;
+mod app_update;
+mod plugins;
+mod code_sandbox;
+mod dataframe;
+mod charts;
+mod focus;
+mod permissions;
+mod path_policy;
+mod temp_files;
+mod model_capabilities;
+mod drafts;
+mod http;
+mod model_cache;
+mod session_export;
+mod session_titles;
+mod journal;
+mod prompts;
+mod web;
+mod research;
+
+pub(crate) use init::ensure_ovms_initialized;
 
 #[tauri::command]
 async fn get_default_download_path() -> Result<String, String> {
@@ -43,279 +93,6 @@ async fn get_home_dir() -> Result<String, String> {
         .map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-async fn get_initialization_status() -> Result<InitializationStatus, String> {
-    let status_mutex = INIT_STATUS.get_or_init(||
-        Arc::new(
-            Mutex::new(InitializationStatus {
-                step: "not_started".to_string(),
-                message: "Initialization not started".to_string(),
-                progress: 0,
-                is_complete: false,
-                has_error: false,
-                error_message: None,
-            })
-        )
-    );
-
-    let status = status_mutex.lock().unwrap();
-    Ok(status.clone())
-}
-
-#[derive(Clone, serde::Serialize)]
-struct InitializationStatus {
-    step: String,
-    message: String,
-    progress: u8,
-    is_complete: bool,
-    has_error: bool,
-    error_message: Option<String>,
-}
-
-// Global initialization status
-static INIT_STATUS: std::sync::OnceLock<Arc<Mutex<InitializationStatus>>> = std::sync::OnceLock::new();
-
-async fn initialize_ovms(app_handle: tauri::AppHandle) {
-    log_operation_start!("OVMS initialization");
-    
-    let status_mutex = INIT_STATUS.get_or_init(||
-        Arc::new(
-            Mutex::new(InitializationStatus {
-                step: "starting".to_string(),
-                message: "Initializing OVMS...".to_string(),
-                progress: 0,
-                is_complete: false,
-                has_error: false,
-                error_message: None,
-            })
-        )
-    );
-
-    // BGE models check removed - models will be downloaded on-demand when user accesses RAG features
-    // See DocumentsPage.tsx for the on-demand download implementation
-
-    // Update status: Starting OVMS check
-    {
-        let mut status = status_mutex.lock().unwrap();
-        status.step = "checking".to_string();
-        status.message = "Checking if OVMS is present...".to_string();
-        status.progress = 15;
-        app_handle
-            .emit("ovms-init-status", &*status)
-            .unwrap_or_else(|e| {
-                log_warning!("Failed to emit init status", error = %e);
-            });
-    }
-
-    tracing::debug!("Checking OVMS presence");
-    
-    // Check if OVMS is present
-    if !ovms::is_ovms_present(Some(&app_handle)) {
-        log_progress!("OVMS not found, downloading...");
-        
-        // Update status: Downloading
-        {
-            let mut status = status_mutex.lock().unwrap();
-            status.step = "downloading".to_string();
-            status.message = "OVMS not found, downloading...".to_string();
-            status.progress = 25;
-            app_handle
-                .emit("ovms-init-status", &*status)
-                .unwrap_or_else(|e| {
-                    log_warning!("Failed to emit init status", error = %e);
-                });
-        }
-
-        match ovms::download_ovms(app_handle.clone()).await {
-            Ok(msg) => {
-                tracing::debug!(message = %msg, "OVMS download completed");
-
-                // Update status: Downloaded
-                {
-                    let mut status = status_mutex.lock().unwrap();
-                    status.step = "downloaded".to_string();
-                    status.message = "OVMS downloaded successfully".to_string();
-                    status.progress = 75;
-                    app_handle
-                        .emit("ovms-init-status", &*status)
-                        .unwrap_or_else(|e| {
-                            log_warning!("Failed to emit init status", error = %e);
-                        });
-                }
-
-                // Update status: Creating config
-                {
-                    let mut status = status_mutex.lock().unwrap();
-                    status.step = "creating_config".to_string();
-                    status.message = "Creating OVMS configuration...".to_string();
-                    status.progress = 77;
-                    app_handle
-                        .emit("ovms-init-status", &*status)
-                        .unwrap_or_else(|e| {
-                            log_warning!("Failed to emit init status", error = %e);
-                        });
-                }
-
-                // Create initial OVMS config with BGE models
-                let models_dir = match paths::get_models_dir() {
-                    Ok(dir) => dir,
-                    Err(e) => {
-                        log_operation_error!("OVMS initialization", &e);
-                        return;
-                    }
-                };
-
-                let bge_model_path = models_dir
-                    .join("OpenVINO")
-                    .join("bge-base-en-v1.5-int8-ov")
-                    .to_string_lossy()
-                    .to_string();
-
-                match
-                    ovms::create_ovms_config(
-                        app_handle.clone(),
-                        "bge-base-en-v1.5-int8-ov".to_string(),
-                        bge_model_path
-                    ).await
-                {
-                    Ok(_) => {
-                        info!("OVMS config created successfully");
-                    }
-                    Err(e) => {
-                        error!(error = %e, "Failed to create OVMS config");
-                        // Continue with initialization even if config creation fails
-                    }
-                }
-            }
-            Err(e) => {
-                log_operation_error!("OVMS download", &e);
-                let mut status = status_mutex.lock().unwrap();
-                status.has_error = true;
-                status.error_message = Some(format!("Failed to download OVMS: {}", e));
-                status.message = "Download failed".to_string();
-                app_handle
-                    .emit("ovms-init-status", &*status)
-                    .unwrap_or_else(|e| {
-                        log_warning!("Failed to emit init status", error = %e);
-                    });
-                return;
-            }
-        }
-    } else {
-        tracing::debug!("OVMS already present, skipping download");
-
-        // Update status: Present
-        {
-            let mut status = status_mutex.lock().unwrap();
-            status.step = "present".to_string();
-            status.message = "OVMS already present".to_string();
-            status.progress = 75;
-            app_handle
-                .emit("ovms-init-status", &*status)
-                .unwrap_or_else(|e| {
-                    log_warning!("Failed to emit init status", error = %e);
-                });
-        }
-
-        // Check if OVMS config already exists
-        let config_exists = if let Ok(config_path) = paths::get_ovms_config_path(Some(&app_handle)) {
-            config_path.exists()
-        } else {
-            false
-        };
-        
-        if !config_exists {
-            log_progress!("Creating initial OVMS config...");
-
-            // Update status: Creating config
-            {
-                let mut status = status_mutex.lock().unwrap();
-                status.step = "creating_config".to_string();
-                status.message = "Creating OVMS configuration...".to_string();
-                status.progress = 77;
-                app_handle
-                    .emit("ovms-init-status", &*status)
-                    .unwrap_or_else(|e| {
-                        log_warning!("Failed to emit init status", error = %e);
-                    });
-            }
-
-            // Create initial OVMS config with BGE models
-            if let Ok(models_dir) = paths::get_models_dir() {
-                let bge_model_path = models_dir
-                    .join("OpenVINO")
-                    .join("bge-base-en-v1.5-int8-ov")
-                    .to_string_lossy()
-                    .to_string();
-
-                tracing::debug!(model = "bge-base-en-v1.5-int8-ov", path = %bge_model_path, "Creating OVMS config for BGE model");
-                
-                match
-                    ovms::create_ovms_config(
-                        app_handle.clone(),
-                        "bge-base-en-v1.5-int8-ov".to_string(),
-                        bge_model_path
-                    ).await
-                {
-                    Ok(_) => {
-                        tracing::debug!("OVMS config created successfully");
-                    }
-                    Err(e) => {
-                        log_warning!("Failed to create initial OVMS config", error = %e, note = "continuing initialization");
-                    }
-                }
-            } else {
-                log_warning!("Failed to get home directory for OVMS config", note = "skipping config creation");
-            }
-        } else {
-            tracing::debug!("OVMS config already exists, skipping config creation");
-        }
-    }
-
-    // Start OVMS server
-    log_progress!("Starting OVMS server...");
-    {
-        let mut status = status_mutex.lock().unwrap();
-        status.step = "starting_server".to_string();
-        status.message = "Starting OVMS server...".to_string();
-        status.progress = 85;
-        app_handle
-            .emit("ovms-init-status", &*status)
-            .unwrap_or_else(|e| {
-                log_warning!("Failed to emit init status", error = %e);
-            });
-    }
-
-    match ovms::start_ovms_server(app_handle.clone()).await {
-        Ok(msg) => {
-            log_operation_success!("OVMS initialization");
-            tracing::debug!(message = %msg, "OVMS server started successfully");
-            let mut status = status_mutex.lock().unwrap();
-            status.step = "complete".to_string();
-            status.message = "OVMS initialization complete".to_string();
-            status.progress = 100;
-            status.is_complete = true;
-            app_handle
-                .emit("ovms-init-status", &*status)
-                .unwrap_or_else(|e| {
-                    log_warning!("Failed to emit init status", error = %e);
-                });
-        }
-        Err(e) => {
-            log_operation_error!("OVMS server startup", &e);
-            let mut status = status_mutex.lock().unwrap();
-            status.has_error = true;
-            status.error_message = Some(format!("Failed to start OVMS server: {}", e));
-            status.message = "Server startup failed".to_string();
-            app_handle
-                .emit("ovms-init-status", &*status)
-                .unwrap_or_else(|e| {
-                    log_warning!("Failed to emit init status", error = %e);
-                });
-        }
-    }
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Build the Tauri log plugin with custom configuration
@@ -330,45 +107,99 @@ pub fn run() {
 
     tauri::Builder
         ::default()
+        // Must come first so a second launch is forwarded before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            selection::handle_second_instance(app, argv);
+        }))
         .plugin(log_plugin)
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_http::init())
-        .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(vec!["--minimized"])))
+        .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, Some(vec![autostart::AUTOSTART_ARG])))
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(state::AppState::default())
         .invoke_handler(
             tauri::generate_handler![
                 huggingface::search_models,
                 huggingface::get_model_info,
+                huggingface::estimate_model_size,
                 huggingface::download_entire_model,
+                huggingface::pause_model_download,
+                huggingface::resume_model_download,
+                huggingface::cancel_model_download,
+                model_integrity::verify_downloaded_model,
+                hf_auth::get_hf_token_status,
+                hf_auth::set_hf_token,
+                hf_auth::clear_hf_token,
                 huggingface::check_model_update_status,
+                huggingface::update_model,
+                huggingface::import_local_model,
+                huggingface::list_model_files,
                 huggingface::check_rag_models_exist,
                 huggingface::get_models_by_type,
                 huggingface::get_all_model_metadata,
                 huggingface::set_model_type,
+                huggingface::pair_draft_model,
+                huggingface::set_tool_call_mode,
                 huggingface::initialize_model_metadata,
                 models::check_downloaded_models,
                 models::delete_downloaded_model,
                 models::open_model_folder,
                 models::list_directory_names,
                 models::delete_directory,
+                memory_estimate::estimate_model_memory,
+                model_capabilities::get_model_capabilities,
+                hardware::get_available_devices,
+                permissions::request_command_confirmation,
+                permissions::elevate_permissions,
+                permissions::drop_elevated_permissions,
+                permissions::get_elevation_status,
                 get_default_download_path,
                 get_user_profile_dir,
                 get_home_dir,
-                get_initialization_status,
+                init::get_initialization_status,
+                snapshot::get_app_state_snapshot,
+                notifications::get_notifications,
+                notifications::mark_notifications_read,
+                init::ensure_ovms_started,
+                init::retry_initialization,
                 ovms::download_ovms,
                 ovms::check_ovms_present,
                 ovms::start_ovms_server,
+                ovms::get_ovms_runtime_params,
+                ovms::configure_ovms_runtime,
                 ovms::create_ovms_config,
                 ovms::update_ovms_config,
                 ovms::reload_ovms_config,
+                ovms_backups::list_ovms_config_backups,
+                ovms_backups::rollback_ovms_config,
                 ovms::load_model,
+                ovms::unload_model,
+                model_switch::switch_model,
                 ovms::get_loaded_model,
                 ovms::get_loaded_models,
+                ovms::get_default_model,
+                ovms::set_default_model,
                 chat::chat_with_loaded_model_streaming,
                 ovms::check_ovms_status,
+                ovms::get_ovms_endpoint,
+                ovms_watchdog::get_ovms_health,
+                ovms_metrics::get_ovms_metrics_snapshot,
+                model_cache::get_cache_usage,
+                model_cache::clear_model_cache,
+                disk_watchdog::get_disk_status,
+                session_export::export_session_bundle,
+                session_export::export_chat_session,
+                session_export::import_chat_sessions,
+                journal::create_daily_note,
+                journal::append_to_daily_note,
+                journal::list_daily_notes,
+                journal::search_journal,
+                research::research_query,
+                ovms_logs::get_ovms_logs,
                 ovms::get_ovms_model_metadata,
                 chat::get_chat_sessions,
                 chat::create_chat_session,
@@ -377,14 +208,33 @@ pub fn run() {
                 chat::add_message_to_temporary_session,
                 chat::update_chat_session,
                 chat::delete_chat_session,
+                chat::set_session_language,
+                chat::set_session_tool_policy,
+                chat::set_session_sampling,
+                chat::set_session_prompt_preset,
+                chat_metrics::get_session_metrics,
                 chat::set_active_chat_session,
                 chat::add_message_to_session,
                 chat::get_session_messages,
+                chat::pin_message,
+                chat::get_pinned_messages,
                 chat::get_conversation_history,
                 chat::stop_chat_streaming,
+                cancellation::cancel_operation,
+                cancellation::list_operations,
+                tool_approval::respond_to_tool_request,
                 chat::chat_with_rag_streaming,
                 rag::documents::process_document,
                 rag::documents::save_temp_file,
+                temp_files::purge_temp_files,
+                rag::ingest::ingest_document,
+                rag::folder_watch::watch_folder,
+                rag::folder_watch::unwatch_folder,
+                rag::folder_watch::get_watched_folders,
+                rag::ingest_queue::enqueue_document,
+                rag::ingest_queue::get_ingest_jobs,
+                rag::reindex::reindex_file,
+                rag::reindex::reindex_all,
                 rag::embeddings::create_document_embeddings,
                 rag::embeddings::create_query_embedding,
                 rag::vector_store::store_documents,
@@ -397,6 +247,32 @@ pub fn run() {
                 rag::vector_store::get_file_chunks,
                 rag::vector_store::delete_file_by_path,
                 rag::vector_store::clear_vector_store,
+                rag::vector_store::get_collection_config,
+                rag::vector_store::requantize_collection,
+                rag::qdrant::get_qdrant_api_key_status,
+                rag::qdrant::set_qdrant_api_key,
+                rag::interchange::export_embeddings,
+                rag::interchange::import_embeddings,
+                rag::sessions::find_related_sessions,
+                rag::benchmark::benchmark_rag,
+                rag::graph::build_knowledge_graph,
+                rag::graph::get_entity_neighbors,
+                rag::graph::clear_knowledge_graph,
+                rag::flashcards::generate_flashcards,
+                rag::flashcards::clear_flashcard_history,
+                quick_actions::get_quick_actions,
+                quick_actions::save_quick_action,
+                quick_actions::delete_quick_action,
+                quick_actions::execute_quick_action,
+                prompts::get_prompt_presets,
+                prompts::create_prompt_preset,
+                prompts::update_prompt_preset,
+                prompts::delete_prompt_preset,
+                prompts::render_prompt_preset,
+                selection::send_selection_to_chat,
+                selection::take_pending_selection,
+                text_assist::rewrite_text,
+                text_assist::fix_grammar,
                 rag::reranker::rerank_search_results,
                 rag::reranker::rerank_search_results_simple,
                 rag::search::search_documents_by_query,
@@ -422,6 +298,20 @@ pub fn run() {
                 autostart::disable_autostart,
                 autostart::is_autostart_enabled,
                 autostart::toggle_autostart,
+                autostart::get_autostart_config,
+                autostart::set_autostart_config,
+                settings::get_app_settings,
+                settings::update_app_settings,
+                settings::reset_app_settings,
+                app_update::check_for_app_update,
+                app_update::get_app_release_notes,
+                app_update::download_app_update,
+                app_update::skip_app_update_version,
+                app_update::defer_app_update,
+                plugins::list_plugins,
+                plugins::reload_plugins,
+                plugins::enable_plugin,
+                plugins::disable_plugin,
                 tasks::create_task,
                 tasks::get_tasks,
                 tasks::get_task,
@@ -430,9 +320,20 @@ pub fn run() {
                 tasks::toggle_task,
                 tasks::execute_task_manually,
                 tasks::get_task_logs,
+                tasks::pause_all_tasks,
+                tasks::resume_all_tasks,
+                tasks::get_scheduler_paused,
+                tasks::snooze_task,
+                tasks::validate_task_definition,
+                focus::start_focus_session,
+                focus::stop_focus_session,
+                focus::get_focus_stats,
                 gallery::generate_image,
                 gallery::get_generated_images,
+                gallery::get_generated_image,
+                gallery::update_generated_image,
                 gallery::delete_generated_image,
+                gallery::clear_gallery,
                 gallery::copy_file
             ]
         )
@@ -441,12 +342,82 @@ pub fn run() {
             tracing::info!("🚀 SparrowAI starting...");
             tracing::debug!("Tauri application setup initiated");
             
+            if let Err(e) = tray::setup_tray(app.handle()) {
+                log_warning!("Failed to create tray icon", error = %e);
+            }
+
+            // Launch options only apply when the OS started us at login
+            let autostart_launch = autostart::launched_by_autostart();
+            let app_settings = settings::current();
+            let startup = app_settings.autostart;
+            let startup_delay = if autostart_launch { startup.delay_secs } else { 0 };
+            let defer_ovms = app_settings.ovms.lazy_init || (autostart_launch && startup.defer_ovms);
+
+            if autostart_launch {
+                tracing::info!(
+                    delay_secs = startup.delay_secs,
+                    start_minimized = startup.start_minimized,
+                    defer_ovms = startup.defer_ovms,
+                    "Launched by autostart"
+                );
+                if startup.start_minimized {
+                    tray::hide_main_window(app.handle());
+                }
+            }
+
+            let launch_args: Vec<String> = std::env::args().collect();
+            selection::handle_launch_args(app.handle(), &launch_args);
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if defer_ovms {
+                    init::mark_ovms_deferred(&handle);
+                    return;
+                }
+                if startup_delay > 0 {
+                    tokio::time::sleep(std::time::Duration::from_secs(startup_delay)).await;
+                }
+                ensure_ovms_initialized(&handle).await;
+            });
+
+            // Save answers a crash cut off as partial messages
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                drafts::restore_interrupted(&handle).await;
+            });
+
+            // Restart OVMS if it crashes mid-session
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                initialize_ovms(handle).await;
+                ovms_watchdog::run(handle).await;
             });
 
-            // Start periodic log cleanup task
+            // Scrape OVMS metrics for the dashboard when enabled
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                ovms_metrics::run(handle).await;
+            });
+
+            // Warn before the data drive fills up
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                disk_watchdog::run(handle).await;
+            });
+
+            // Build the search index of a large RAG collection ahead of the first query
+            tauri::async_runtime::spawn_blocking(|| {
+                if let Err(e) = rag::vector_store::warm_ann_index() {
+                    log_warning!("Could not prepare the RAG search index", error = %e);
+                }
+            });
+
+            // Keep watched folders in sync with their collections
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                rag::folder_watch::start(handle).await;
+            });
+
+            // Start periodic log and temp file cleanup task
             tauri::async_runtime::spawn(async move {
                 logging::periodic_cleanup_task().await;
             });
@@ -454,16 +425,20 @@ pub fn run() {
             // Start task scheduler
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
+                if startup_delay > 0 {
+                    tokio::time::sleep(std::time::Duration::from_secs(startup_delay)).await;
+                }
+                focus::restore().await;
                 tasks::start_task_scheduler(handle).await;
             });
 
             Ok(())
         })
 
-        .on_window_event(|_window, event| {
+        .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event {
                 // Stop OVMS server when app is closing
-                if let Err(e) = ovms::stop_ovms_server() {
+                if let Err(e) = ovms::stop_ovms_server(window.app_handle()) {
                     log_operation_error!("OVMS server shutdown", &e);
                 } else {
                     log_operation_success!("OVMS server shutdown");
@@ -222,18 +222,27 @@ pub fn cleanup_old_archives() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// Periodically clean up old archived logs (should be called periodically, e.g., daily)
+/// Periodically clean up old archived logs and expired temp files
 /// This is a public function that can be called from the main application
 pub async fn periodic_cleanup_task() {
     loop {
-        // Wait 24 hours before next cleanup
-        tokio::time::sleep(tokio::time::Duration::from_secs(24 * 60 * 60)).await;
+        // Staged uploads are cleaned hourly; logs and model caches once a day
+        for _ in 0..24 {
+            if let Err(e) = crate::temp_files::cleanup_expired() {
+                tracing::warn!("Periodic temp file cleanup failed: {}", e);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(60 * 60)).await;
+        }
         
         if let Err(e) = cleanup_old_archives() {
             tracing::warn!("Periodic log cleanup failed: {}", e);
         } else {
             tracing::debug!("Periodic log cleanup completed successfully");
         }
+
+        if let Err(e) = crate::model_cache::enforce_size_cap().await {
+            tracing::warn!("Periodic model cache cleanup failed: {}", e);
+        }
     }
 }
 
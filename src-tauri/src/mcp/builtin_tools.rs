@@ -7,6 +7,14 @@ use std::fs;
 use std::path::Path;
 use async_openai::types::chat::{ChatCompletionTool, FunctionObjectArgs};
 
+use crate::path_policy::{self, Access};
+
+/// Argument the chat adds to `SESSION_TOOL` calls with the current session id
+pub const SESSION_ARG: &str = "_session_id";
+
+/// The one built-in tool that takes `SESSION_ARG`; others reject unknown arguments
+pub const SESSION_TOOL: &str = "builtin_render_chart";
+
 /// Represents a built-in MCP tool with its metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuiltinTool {
@@ -58,7 +66,7 @@ impl BuiltinToolRegistry {
     /// Convert built-in tools to OpenAI ChatCompletionTool format
     pub fn to_openai_tools(&self) -> Result<Vec<ChatCompletionTool>, String> {
         
-        self.tools.values().map(|tool| {
+        self.list_tools().iter().map(|tool| {
             let tool_name = format!("builtin_{}", tool.name);
             tracing::debug!("Registering builtin tool for chat: {} (hidden_from_task_creation: {})", 
                 tool_name, tool.hidden_from_task_creation);
@@ -140,7 +148,7 @@ impl BuiltinToolRegistry {
             "create_task".to_string(),
             BuiltinTool {
                 name: "create_task".to_string(),
-                description: "Create a scheduled task with structured parameters. Supports notifications and MCP function execution on various schedules.".to_string(),
+                description: "Create a scheduled task with structured parameters. Supports notifications and MCP function execution on various schedules. The first call validates and returns a schedule preview (or machine-readable errors); call again with confirm=true to create it.".to_string(),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
@@ -150,7 +158,7 @@ impl BuiltinToolRegistry {
                         },
                         "action_type": {
                             "type": "object",
-                            "description": "Action to perform. For ShowNotification: must include 'type', 'title', 'message'. For RunMcpFunction: must include 'type', 'server_name', 'tool_name', 'arguments'",
+                            "description": "Action to perform. For ShowNotification: must include 'type', 'title', 'message'. For RunMcpFunction: must include 'type', 'server_name', 'tool_name', 'arguments'. For AgentTask: must include 'type', 'goal'. For RunQuickAction: must include 'type', 'action_id', 'text'",
                             "oneOf": [
                                 {
                                     "type": "object",
@@ -191,6 +199,46 @@ impl BuiltinToolRegistry {
                                         }
                                     },
                                     "required": ["type", "server_name", "tool_name"]
+                                },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "type": {
+                                            "type": "string",
+                                            "const": "AgentTask"
+                                        },
+                                        "goal": {
+                                            "type": "string",
+                                            "description": "What the agent should accomplish, written as an instruction"
+                                        },
+                                        "max_steps": {
+                                            "type": "integer",
+                                            "description": "Maximum model calls before giving up (default: 8)"
+                                        },
+                                        "use_rag": {
+                                            "type": "boolean",
+                                            "description": "Retrieve relevant excerpts from the user's documents first"
+                                        }
+                                    },
+                                    "required": ["type", "goal"]
+                                },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "type": {
+                                            "type": "string",
+                                            "const": "RunQuickAction"
+                                        },
+                                        "action_id": {
+                                            "type": "string",
+                                            "description": "Quick action id (e.g., 'summarize', 'translate')"
+                                        },
+                                        "text": {
+                                            "type": "string",
+                                            "description": "Text to run the action on"
+                                        }
+                                    },
+                                    "required": ["type", "action_id", "text"]
                                 }
                             ]
                         },
@@ -295,12 +343,77 @@ impl BuiltinToolRegistry {
                                         }
                                     },
                                     "required": ["type", "hours"]
+                                },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "type": {
+                                            "type": "string",
+                                            "const": "FileEvent"
+                                        },
+                                        "path": {
+                                            "type": "string",
+                                            "description": "Folder to watch, '~' expands to the home directory (e.g., '~/Scans')"
+                                        },
+                                        "pattern": {
+                                            "type": "string",
+                                            "description": "File name wildcard such as '*.pdf' (default: all files)"
+                                        },
+                                        "event": {
+                                            "type": "string",
+                                            "enum": ["Created", "Modified", "Removed", "Any"],
+                                            "description": "Which file event fires the task (default: Created). Use {{file_path}} or {{file_name}} in the action to refer to the file"
+                                        }
+                                    },
+                                    "required": ["type", "path"]
+                                },
+                                {
+                                    "type": "object",
+                                    "properties": {
+                                        "type": {
+                                            "type": "string",
+                                            "const": "SystemEvent"
+                                        },
+                                        "event": {
+                                            "type": "string",
+                                            "enum": ["AppStartup", "SystemResume", "NetworkOnline", "NetworkOffline"],
+                                            "description": "System event that fires the task"
+                                        }
+                                    },
+                                    "required": ["type", "event"]
                                 }
                             ]
                         },
                         "auto_delete": {
                             "type": "boolean",
                             "description": "Auto-delete task after one-time execution (default: false)"
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Set to true only after the user has approved the schedule preview returned by a previous call (default: false, which validates and previews without creating)"
+                        },
+                        "conditions": {
+                            "type": "object",
+                            "description": "Optional run conditions checked before each scheduled run; the task waits until they are met",
+                            "properties": {
+                                "idle_minutes": {
+                                    "type": "integer",
+                                    "description": "Only run after the machine has been idle this many minutes",
+                                    "minimum": 1
+                                },
+                                "require_ac_power": {
+                                    "type": "boolean",
+                                    "description": "Only run while on AC power"
+                                },
+                                "require_model_loaded": {
+                                    "type": "boolean",
+                                    "description": "Only run while an OVMS model is loaded"
+                                },
+                                "model_name": {
+                                    "type": "string",
+                                    "description": "Specific model that must be loaded"
+                                }
+                            }
                         }
                     },
                     "required": ["name", "action_type", "trigger_time"]
@@ -308,10 +421,251 @@ impl BuiltinToolRegistry {
                 hidden_from_task_creation: true,
             },
         );
+
+        // Tool 5: Run a code snippet in the WASI sandbox
+        self.tools.insert(
+            "run_sandboxed_code".to_string(),
+            BuiltinTool {
+                name: "run_sandboxed_code".to_string(),
+                description: "Run a short Python program in an isolated sandbox and return its output. Use it to calculate, transform data or print tables. The program has no network access, no access to the user's files, and strict time and memory limits; print results to stdout".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "language": {
+                            "type": "string",
+                            "enum": ["python"],
+                            "description": "Language of the program"
+                        },
+                        "code": {
+                            "type": "string",
+                            "description": "Complete program source"
+                        },
+                        "stdin": {
+                            "type": "string",
+                            "description": "Text passed to the program on standard input (optional)"
+                        }
+                    },
+                    "required": ["language", "code"]
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
+        // Tool 6: Load a CSV file for analysis
+        self.tools.insert(
+            "load_csv".to_string(),
+            BuiltinTool {
+                name: "load_csv".to_string(),
+                description: "Load a local CSV or TSV file for analysis with query_dataframe. Returns its columns with types, the row count and the first rows. The data stays on this machine".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Path of the .csv or .tsv file"
+                        },
+                        "name": {
+                            "type": "string",
+                            "description": "Name to refer to the data by (default: the file name without extension)"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
+
+        // Tool 7: Query a loaded dataframe
+        self.tools.insert(
+            "query_dataframe".to_string(),
+            BuiltinTool {
+                name: "query_dataframe".to_string(),
+                description: "Query data loaded with load_csv. Filters apply first, then either grouping with aggregations or a column selection, then sorting and the row limit. Returns the result as CSV".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Name the data was loaded under"
+                        },
+                        "filters": {
+                            "type": "array",
+                            "description": "Conditions that must all hold",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "column": { "type": "string" },
+                                    "op": {
+                                        "type": "string",
+                                        "enum": ["==", "!=", ">", ">=", "<", "<=", "contains", "is_null", "is_not_null"]
+                                    },
+                                    "value": {
+                                        "description": "String, number or boolean; not needed for is_null/is_not_null"
+                                    }
+                                },
+                                "required": ["column", "op"]
+                            }
+                        },
+                        "select": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Columns to return when not aggregating"
+                        },
+                        "group_by": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Columns to group by"
+                        },
+                        "aggregations": {
+                            "type": "array",
+                            "description": "Aggregations per group (or over all rows without group_by). Result columns are named <column>_<function> unless an alias is given",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "column": { "type": "string" },
+                                    "function": {
+                                        "type": "string",
+                                        "enum": ["sum", "mean", "median", "min", "max", "count", "n_unique", "std", "first", "last"]
+                                    },
+                                    "alias": { "type": "string" }
+                                },
+                                "required": ["column", "function"]
+                            }
+                        },
+                        "sort": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "column": { "type": "string" },
+                                    "descending": { "type": "boolean" }
+                                },
+                                "required": ["column"]
+                            }
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum rows to return (default: 50, at most 500)"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
+
+        // Tool 8: Render a chart image
+        self.tools.insert(
+            "render_chart".to_string(),
+            BuiltinTool {
+                name: "render_chart".to_string(),
+                description: "Draw a bar, line or scatter chart as a PNG image and return its path. The image is shown to the user in the chat".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "type": {
+                            "type": "string",
+                            "enum": ["bar", "line", "scatter"],
+                            "description": "Kind of chart"
+                        },
+                        "title": { "type": "string" },
+                        "x_label": { "type": "string", "description": "Caption of the x axis" },
+                        "y_label": { "type": "string", "description": "Caption of the y axis" },
+                        "labels": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Category names along the x axis (bar and line charts)"
+                        },
+                        "series": {
+                            "type": "array",
+                            "description": "Data series; bar and line charts use 'values' (one per label), scatter charts use 'points'",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "values": { "type": "array", "items": { "type": "number" } },
+                                    "points": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "array",
+                                            "items": { "type": "number" },
+                                            "minItems": 2,
+                                            "maxItems": 2
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "width": { "type": "integer", "description": "Image width in pixels (default: 900)" },
+                        "height": { "type": "integer", "description": "Image height in pixels (default: 540)" }
+                    },
+                    "required": ["type", "series"]
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
+
+        // Tool 9: Start a focus session
+        self.tools.insert(
+            "start_focus_session".to_string(),
+            BuiltinTool {
+                name: "start_focus_session".to_string(),
+                description: "Start a focus (Pomodoro) session. The user is notified when it ends and again when the break is over".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "duration_minutes": { "type": "integer", "description": "Length of the session in minutes (default: 25)" },
+                        "goal": { "type": "string", "description": "What the user wants to get done" },
+                        "break_minutes": { "type": "integer", "description": "Break after the session in minutes, 0 for none (default: 5)" },
+                        "silence_background": { "type": "boolean", "description": "Hold back other scheduled tasks until the session ends (default: false)" }
+                    }
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
+
+        // Tool 10: Focus statistics
+        self.tools.insert(
+            "get_focus_stats".to_string(),
+            BuiltinTool {
+                name: "get_focus_stats".to_string(),
+                description: "Summarize the user's focus sessions: how many, how many were completed and the minutes focused per day".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "days": { "type": "integer", "description": "How many days back to look (default: 7)" }
+                    }
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
+
+        // Tool 11: Web search
+        self.tools.insert(
+            "web_search".to_string(),
+            BuiltinTool {
+                name: "web_search".to_string(),
+                description: "Search the web. Returns the title, URL and a short snippet of each result".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string", "description": "What to search for" },
+                        "max_results": { "type": "integer", "description": "Number of results to return (default: 5, max: 20)" }
+                    },
+                    "required": ["query"]
+                }),
+                hidden_from_task_creation: false,
+            },
+        );
     }
 
+    /// Built-in tools followed by those of enabled plugins
     pub fn list_tools(&self) -> Vec<BuiltinTool> {
-        self.tools.values().cloned().collect()
+        let mut tools: Vec<BuiltinTool> = self.tools.values().cloned().collect();
+        tools.extend(
+            crate::plugins::builtin_tools().into_iter()
+                .filter(|tool| !self.tools.contains_key(&tool.name))
+        );
+        tools
     }
 
     #[allow(dead_code)]
@@ -325,7 +679,15 @@ impl BuiltinToolRegistry {
             "get_current_time" => execute_get_current_time(arguments).await,
             "list_directory" => execute_list_directory(arguments).await,
             "create_task" => execute_create_task(arguments).await,
-            _ => Err(format!("Unknown tool: {}", name)),
+            "run_sandboxed_code" => execute_run_sandboxed_code(arguments).await,
+            "load_csv" => execute_load_csv(arguments).await,
+            "query_dataframe" => execute_query_dataframe(arguments).await,
+            "render_chart" => execute_render_chart(arguments).await,
+            "start_focus_session" => execute_start_focus_session(arguments).await,
+            "get_focus_stats" => execute_get_focus_stats(arguments).await,
+            "web_search" => execute_web_search(arguments).await,
+            _ => crate::plugins::execute_tool(name, arguments).await
+                .unwrap_or_else(|| Err(format!("Unknown tool: {}", name))),
         }
     }
 }
@@ -451,6 +813,90 @@ async fn execute_get_current_time(arguments: Value) -> Result<ToolResult, String
     Ok(ToolResult::text(serde_json::to_string_pretty(&time_info).unwrap()))
 }
 
+async fn execute_run_sandboxed_code(arguments: Value) -> Result<ToolResult, String> {
+    let language = arguments.get("language")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'language' parameter")?;
+    let code = arguments.get("code")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'code' parameter")?;
+    let stdin = arguments.get("stdin").and_then(|v| v.as_str());
+
+    let language = crate::code_sandbox::Language::parse(language)?;
+    let output = crate::code_sandbox::run_code(language, code, stdin).await?;
+
+    Ok(ToolResult::text(serde_json::to_string_pretty(&output).unwrap()))
+}
+
+async fn execute_load_csv(arguments: Value) -> Result<ToolResult, String> {
+    let path = arguments.get("path")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'path' parameter")?
+        .to_string();
+    let name = arguments.get("name").and_then(|v| v.as_str()).map(str::to_string);
+
+    let path = path_policy::check_str(&path, Access::Read)?;
+
+    let summary = tokio::task::spawn_blocking(move || crate::dataframe::load_csv(&path, name.as_deref()))
+        .await
+        .map_err(|e| format!("CSV loading failed: {}", e))??;
+    Ok(ToolResult::text(summary))
+}
+
+async fn execute_query_dataframe(arguments: Value) -> Result<ToolResult, String> {
+    let mut arguments = arguments;
+    let name = arguments.as_object_mut()
+        .and_then(|args| args.remove("name"))
+        .and_then(|v| v.as_str().map(str::to_string))
+        .ok_or("Missing 'name' parameter")?;
+    let query: crate::dataframe::DataframeQuery = serde_json::from_value(arguments)
+        .map_err(|e| format!("Invalid query: {}", e))?;
+
+    let result = tokio::task::spawn_blocking(move || crate::dataframe::query(&name, &query))
+        .await
+        .map_err(|e| format!("Query failed: {}", e))??;
+    Ok(ToolResult::text(result))
+}
+
+async fn execute_render_chart(arguments: Value) -> Result<ToolResult, String> {
+    let mut arguments = arguments;
+    let session_id = arguments.as_object_mut()
+        .and_then(|args| args.remove(SESSION_ARG))
+        .and_then(|v| v.as_str().map(str::to_string));
+    let spec: crate::charts::ChartSpec = serde_json::from_value(arguments)
+        .map_err(|e| format!("Invalid chart spec: {}", e))?;
+
+    let chart = tokio::task::spawn_blocking(move || crate::charts::render(&spec, session_id.as_deref()))
+        .await
+        .map_err(|e| format!("Chart rendering failed: {}", e))??;
+    Ok(ToolResult::text(serde_json::to_string_pretty(&chart).unwrap()))
+}
+
+async fn execute_start_focus_session(arguments: Value) -> Result<ToolResult, String> {
+    let minutes = |key: &str| arguments.get(key).and_then(|v| v.as_u64()).map(|v| v.min(u32::MAX as u64) as u32);
+    let goal = arguments.get("goal").and_then(|v| v.as_str()).map(str::to_string);
+    let silence = arguments.get("silence_background").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let session = crate::focus::start(minutes("duration_minutes").unwrap_or(25), goal, minutes("break_minutes"), silence).await?;
+    Ok(ToolResult::text(serde_json::to_string_pretty(&session).unwrap()))
+}
+
+async fn execute_get_focus_stats(arguments: Value) -> Result<ToolResult, String> {
+    let days = arguments.get("days").and_then(|v| v.as_u64()).map(|v| v.min(365) as u32);
+    let stats = crate::focus::stats(days).await?;
+    Ok(ToolResult::text(serde_json::to_string_pretty(&stats).unwrap()))
+}
+
+async fn execute_web_search(arguments: Value) -> Result<ToolResult, String> {
+    let query = arguments.get("query")
+        .and_then(|v| v.as_str())
+        .ok_or("Missing 'query' parameter")?;
+    let max_results = arguments.get("max_results").and_then(|v| v.as_u64()).unwrap_or(5).clamp(1, 20) as usize;
+
+    let results = crate::web::search(query, max_results).await?;
+    Ok(ToolResult::text(serde_json::to_string_pretty(&results).unwrap()))
+}
+
 async fn execute_list_directory(arguments: Value) -> Result<ToolResult, String> {
     let path_str = arguments.get("path")
         .and_then(|v| v.as_str())
@@ -460,7 +906,8 @@ async fn execute_list_directory(arguments: Value) -> Result<ToolResult, String>
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
-    let path = Path::new(path_str);
+    let resolved = path_policy::check_str(path_str, Access::Read)?;
+    let path = resolved.as_path();
     
     if !path.exists() {
         return Err(format!("Path does not exist: {}", path_str));
@@ -561,136 +1008,43 @@ fn list_directory_recursive(path: &Path, prefix: &str, entries: &mut Vec<Value>)
 }
 
 async fn execute_create_task(arguments: Value) -> Result<ToolResult, String> {
-    use crate::tasks::{ActionType, TriggerTime};
-    
-    // Extract name
-    let name = arguments.get("name")
-        .and_then(|v| v.as_str())
-        .ok_or("Missing 'name' parameter")?
-        .to_string();
-    
-    // Extract and parse action_type
-    let action_obj = arguments.get("action_type")
-        .ok_or("Missing 'action_type' parameter")?;
-    
-    let action_type_str = action_obj.get("type")
-        .and_then(|v| v.as_str())
-        .ok_or("Missing 'action_type.type' field")?;
-    
-    let action_type = match action_type_str {
-        "ShowNotification" => {
-            let title = action_obj.get("title")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'action_type.title' for ShowNotification")?
-                .to_string();
-            let message = action_obj.get("message")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'action_type.message' for ShowNotification")?
-                .to_string();
-            ActionType::ShowNotification { title, message }
-        },
-        "RunMcpFunction" => {
-            let server_name = action_obj.get("server_name")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'action_type.server_name' for RunMcpFunction")?
-                .to_string();
-            let tool_name = action_obj.get("tool_name")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'action_type.tool_name' for RunMcpFunction")?
-                .to_string();
-            let arguments = action_obj.get("arguments")
-                .cloned()
-                .unwrap_or(json!({}));
-            ActionType::RunMcpFunction { server_name, tool_name, arguments }
-        },
-        _ => return Err(format!("Invalid action_type: {}", action_type_str)),
-    };
-    
-    // Extract and parse trigger_time
-    let trigger_obj = arguments.get("trigger_time")
-        .ok_or("Missing 'trigger_time' parameter")?;
-    
-    let trigger_type_str = trigger_obj.get("type")
-        .and_then(|v| v.as_str())
-        .ok_or("Missing 'trigger_time.type' field")?;
-    
-    let trigger_time = match trigger_type_str {
-        "DateTime" => {
-            let datetime_str = trigger_obj.get("datetime")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'trigger_time.datetime' for DateTime trigger")?;
-            
-            // Try parsing as RFC3339 first (with timezone)
-            let datetime = if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(datetime_str) {
-                dt.with_timezone(&chrono::Utc)
-            } else {
-                // Try parsing as naive datetime (without timezone), assume local timezone
-                use chrono::{NaiveDateTime, TimeZone};
-                let naive_dt = NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%dT%H:%M:%S")
-                    .or_else(|_| NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%d %H:%M:%S"))
-                    .map_err(|e| format!("Invalid datetime format (expected ISO 8601 with or without timezone): {}", e))?;
-                chrono::Local.from_local_datetime(&naive_dt)
-                    .single()
-                    .ok_or("Ambiguous or invalid local datetime")?
-                    .with_timezone(&chrono::Utc)
-            };
-            
-            TriggerTime::DateTime { datetime }
-        },
-        "Daily" => {
-            let time = trigger_obj.get("time")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'trigger_time.time' for Daily trigger")?
-                .to_string();
-            TriggerTime::Daily { time }
-        },
-        "Weekly" => {
-            let day_of_week = trigger_obj.get("day_of_week")
-                .and_then(|v| v.as_u64())
-                .ok_or("Missing 'trigger_time.day_of_week' for Weekly trigger")? as u8;
-            let time = trigger_obj.get("time")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'trigger_time.time' for Weekly trigger")?
-                .to_string();
-            TriggerTime::Weekly { day_of_week, time }
-        },
-        "Monthly" => {
-            let day_of_month = trigger_obj.get("day_of_month")
-                .and_then(|v| v.as_u64())
-                .ok_or("Missing 'trigger_time.day_of_month' for Monthly trigger")? as u8;
-            let time = trigger_obj.get("time")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing 'trigger_time.time' for Monthly trigger")?
-                .to_string();
-            TriggerTime::Monthly { day_of_month, time }
-        },
-        "EveryNMinutes" => {
-            let minutes = trigger_obj.get("minutes")
-                .and_then(|v| v.as_u64())
-                .ok_or("Missing 'trigger_time.minutes' for EveryNMinutes trigger")? as u32;
-            TriggerTime::EveryNMinutes { minutes }
-        },
-        "EveryNHours" => {
-            let hours = trigger_obj.get("hours")
-                .and_then(|v| v.as_u64())
-                .ok_or("Missing 'trigger_time.hours' for EveryNHours trigger")? as u32;
-            TriggerTime::EveryNHours { hours }
-        },
-        _ => return Err(format!("Invalid trigger_time.type: {}", trigger_type_str)),
+    use crate::tasks::validation;
+
+    let report = validation::build_report(&arguments, 3);
+
+    let draft = match (report.valid, report.task.clone()) {
+        (true, Some(draft)) => draft,
+        _ => {
+            let result = json!({
+                "success": false,
+                "errors": report.errors,
+                "message": "Task definition is invalid. Fix the listed fields and call create_task again.",
+            });
+            return Ok(ToolResult::text(serde_json::to_string_pretty(&result).unwrap()));
+        }
     };
-    
-    // Extract auto_delete (optional)
-    let auto_delete = arguments.get("auto_delete")
-        .and_then(|v| v.as_bool());
-    
-    // Create the task using the tasks module
+
+    // Nothing is committed until the schedule has been confirmed
+    let confirmed = arguments.get("confirm").and_then(|v| v.as_bool()).unwrap_or(false);
+    if !confirmed {
+        let result = json!({
+            "success": true,
+            "confirmation_required": true,
+            "task": draft,
+            "preview": report.preview,
+            "message": "Show this schedule to the user. Once they agree, call create_task again with the same arguments and \"confirm\": true.",
+        });
+        return Ok(ToolResult::text(serde_json::to_string_pretty(&result).unwrap()));
+    }
+
     let task = crate::tasks::create_task(
-        name,
-        action_type,
+        draft.name,
+        draft.action_type,
         json!({}),
-        trigger_time,
-        None,
-        auto_delete,
+        draft.trigger_time,
+        draft.repeat_interval,
+        Some(draft.auto_delete),
+        Some(draft.conditions),
     ).await?;
 
     let result = json!({
@@ -699,6 +1053,7 @@ async fn execute_create_task(arguments: Value) -> Result<ToolResult, String> {
         "task_name": task.name,
         "message": format!("Task '{}' created successfully", task.name),
         "next_run": task.next_run,
+        "preview": report.preview,
     });
 
     Ok(ToolResult::text(serde_json::to_string_pretty(&result).unwrap()))
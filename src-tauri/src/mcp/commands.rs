@@ -4,9 +4,12 @@ use super::builtin_tools::{BuiltinToolRegistry, BuiltinTool, ToolResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tauri::AppHandle;
+use tauri::{AppHandle, State, Window};
 use serde_json::Value;
 
+use crate::permissions;
+use crate::state::AppState;
+
 // Global MCP manager instance
 lazy_static::lazy_static! {
     static ref MCP_MANAGER: Arc<Mutex<Option<McpManager>>> = Arc::new(Mutex::new(None));
@@ -585,15 +588,24 @@ pub async fn get_builtin_tools() -> Result<Vec<BuiltinTool>, String> {
     Ok(BUILTIN_TOOLS.list_tools())
 }
 
-/// Execute a built-in tool
+/// Execute a built-in tool on behalf of the backend (scheduled tasks, agents)
+pub async fn run_builtin_tool(tool_name: String, arguments: Value) -> Result<ToolResult, String> {
+    tracing::debug!(tool = %tool_name, args = ?arguments, "Executing built-in tool");
+    
+    BUILTIN_TOOLS.execute_tool(&tool_name, arguments).await
+}
+
+/// Execute a built-in tool from the frontend; needs a confirmation token or an elevated window
 #[tauri::command]
 pub async fn execute_builtin_tool(
+    state: State<'_, AppState>,
+    window: Window,
     tool_name: String,
     arguments: Value,
+    confirmation_token: Option<String>,
 ) -> Result<ToolResult, String> {
-    tracing::debug!(tool = %tool_name, args = ?arguments, "Executing built-in tool");
-    
-    BUILTIN_TOOLS.execute_tool(&tool_name, arguments).await
+    permissions::authorize(&state, window.label(), "execute_builtin_tool", confirmation_token.as_deref())?;
+    run_builtin_tool(tool_name, arguments).await
 }
 
 /// Get all available tools (both built-in and external MCP servers)
@@ -0,0 +1,213 @@
+//! Rough memory footprint of a downloaded model, checked before loading it.
+//!
+//! The estimate adds up the IR weight files, a compile/runtime overhead that
+//! depends on the device, and the KV cache OVMS reserves up front
+//! (`cache_size` in graph.pbtxt, in GB). It is compared with the memory the
+//! OS reports as available. Intel GPUs and NPUs share system memory, so they
+//! are measured against RAM as well; discrete GPU memory cannot be queried
+//! here and is reported as unknown.
+
+use std::path::Path;
+
+use serde::Serialize;
+use sysinfo::System;
+
+use crate::{ ovms, paths };
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Share of available memory a model may use before we warn
+const HEADROOM: f64 = 0.9;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryEstimate {
+    pub model_id: String,
+    pub device: String,
+    /// Weight precision guessed from the model name (int4, int8, fp16, ...)
+    pub quantization: Option<String>,
+    pub weights_bytes: u64,
+    pub runtime_overhead_bytes: u64,
+    pub kv_cache_bytes: u64,
+    /// KV cache per token of context, when the model config allows computing it
+    pub kv_bytes_per_token: Option<u64>,
+    pub total_bytes: u64,
+    pub available_bytes: Option<u64>,
+    /// `None` when the available memory is unknown
+    pub fits: Option<bool>,
+    pub warning: Option<String>,
+}
+
+fn quantization_from_name(model_id: &str) -> Option<String> {
+    let name = model_id.to_lowercase();
+    ["int4", "int8", "fp16", "bf16", "fp32", "nf4"]
+        .into_iter()
+        .find(|q| name.contains(q))
+        .map(str::to_string)
+}
+
+/// Total size of the `.bin` weight files under `dir`
+fn weights_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("bin"))
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// `key: value` from a graph.pbtxt, as a number
+fn graph_number(graph: &str, key: &str) -> Option<f64> {
+    graph.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(key))
+        .and_then(|rest| rest.trim_start_matches(':').trim().trim_end_matches(',').parse().ok())
+}
+
+/// Bytes of K and V per token: 2 x layers x kv_heads x head_dim x element size.
+/// `None` for configs without a layer count, which describe no decoder.
+fn kv_bytes_per_token(config: &serde_json::Value, kv_precision: Option<&str>) -> Result<Option<u64>, String> {
+    let number = |key: &str| config.get(key).and_then(|v| v.as_u64());
+    let Some(layers) = number("num_hidden_layers") else {
+        return Ok(None);
+    };
+    let heads = number("num_attention_heads")
+        .filter(|heads| *heads > 0)
+        .ok_or("config.json has no valid num_attention_heads")?;
+    let kv_heads = number("num_key_value_heads").unwrap_or(heads);
+    let Some(head_dim) = number("head_dim").or_else(|| Some(number("hidden_size")? / heads)) else {
+        return Ok(None);
+    };
+    let element_size = match kv_precision.map(str::to_lowercase).as_deref() {
+        Some("u8") | Some("i8") => 1,
+        Some("f32") => 4,
+        _ => 2,
+    };
+    Ok(Some(2 * layers * kv_heads * head_dim * element_size))
+}
+
+/// Extra memory for compiling and running the model, as a share of the weights
+fn overhead_factor(device: &str) -> f64 {
+    match device.to_uppercase().as_str() {
+        // GPU compilation keeps a host copy of the weights for a while
+        "GPU" => 0.35,
+        "NPU" => 0.3,
+        _ => 0.15,
+    }
+}
+
+fn available_memory() -> Option<u64> {
+    let mut system = System::new();
+    system.refresh_memory();
+    Some(system.available_memory()).filter(|bytes| *bytes > 0)
+}
+
+fn estimate(model_id: &str, model_dir: &Path, device: Option<String>, available: Option<u64>) -> Result<MemoryEstimate, String> {
+    let graph = std::fs::read_to_string(model_dir.join("graph.pbtxt")).unwrap_or_default();
+    let device = device
+        .or_else(|| ovms::graph_device(&graph))
+        .unwrap_or_else(|| "CPU".to_string())
+        .to_uppercase();
+
+    let weights_bytes = weights_size(model_dir);
+    let runtime_overhead_bytes = ((weights_bytes as f64) * overhead_factor(&device)) as u64;
+    let kv_cache_bytes = graph_number(&graph, "cache_size").map_or(0, |gb| (gb * GIB as f64) as u64);
+
+    let kv_precision = graph.lines()
+        .find_map(|line| line.split("KV_CACHE_PRECISION").nth(1))
+        .and_then(|rest| rest.split('"').nth(2).map(str::to_string));
+    let kv_bytes_per_token = match std::fs::read_to_string(model_dir.join("config.json"))
+        .ok()
+        .and_then(|config| serde_json::from_str::<serde_json::Value>(&config).ok())
+    {
+        Some(config) => kv_bytes_per_token(&config, kv_precision.as_deref())?,
+        None => None,
+    };
+
+    let total_bytes = weights_bytes + runtime_overhead_bytes + kv_cache_bytes;
+    let measurable = matches!(device.as_str(), "CPU" | "AUTO" | "NPU" | "GPU");
+    let available_bytes = available.filter(|_| measurable);
+    let fits = available_bytes.map(|available| (total_bytes as f64) <= (available as f64) * HEADROOM);
+
+    let warning = match fits {
+        Some(false) => Some(format!(
+            "This model likely won't fit: it needs about {:.1} GB and {:.1} GB is available",
+            total_bytes as f64 / GIB as f64,
+            available_bytes.unwrap_or(0) as f64 / GIB as f64
+        )),
+        _ if device == "GPU" => Some(
+            "Measured against system memory; a discrete GPU's own memory is not checked".to_string()
+        ),
+        _ => None,
+    };
+
+    Ok(MemoryEstimate {
+        model_id: model_id.to_string(),
+        device,
+        quantization: quantization_from_name(model_id),
+        weights_bytes,
+        runtime_overhead_bytes,
+        kv_cache_bytes,
+        kv_bytes_per_token,
+        total_bytes,
+        available_bytes,
+        fits,
+        warning,
+    })
+}
+
+/// Estimate whether a downloaded model fits in memory on `device` (the
+/// device in its graph.pbtxt when not given)
+#[tauri::command]
+pub async fn estimate_model_memory(model_id: String, device: Option<String>) -> Result<MemoryEstimate, String> {
+    let model_id = crate::models::normalize_model_id(&model_id);
+    let model_dir = paths::get_models_dir().map_err(|e| e.to_string())?.join(&model_id);
+    if !model_dir.exists() {
+        return Err(format!("Model not found at: {}. Please download the model first.", model_dir.display()));
+    }
+
+    tokio::task::spawn_blocking(move || estimate(&model_id, &model_dir, device, available_memory()))
+        .await
+        .map_err(|e| format!("Memory estimation failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kv_bytes_per_token() {
+        let config = serde_json::json!({
+            "num_hidden_layers": 28,
+            "num_attention_heads": 16,
+            "num_key_value_heads": 8,
+            "hidden_size": 2048
+        });
+        // 2 (K and V) x 28 layers x 8 heads x 128 dims x 2 bytes
+        assert_eq!(kv_bytes_per_token(&config, None), Ok(Some(2 * 28 * 8 * 128 * 2)));
+        assert_eq!(kv_bytes_per_token(&config, Some("u8")), Ok(Some(2 * 28 * 8 * 128)));
+
+        let zero_heads = serde_json::json!({ "num_hidden_layers": 28, "num_attention_heads": 0, "hidden_size": 2048 });
+        assert!(kv_bytes_per_token(&zero_heads, None).is_err());
+        let no_heads = serde_json::json!({ "num_hidden_layers": 28, "hidden_size": 2048 });
+        assert!(kv_bytes_per_token(&no_heads, None).is_err());
+        assert_eq!(kv_bytes_per_token(&serde_json::json!({}), None), Ok(None));
+    }
+
+    #[test]
+    fn test_estimate_flags_models_that_do_not_fit() {
+        let dir = std::env::temp_dir().join(format!("sparrow-memory-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("openvino_model.bin"), vec![0u8; 1000]).unwrap();
+        std::fs::write(dir.join("graph.pbtxt"), "cache_size: 2,\ndevice: \"GPU\",\n").unwrap();
+
+        let estimate = estimate("OpenVINO/Test-int4-ov", &dir, None, Some(GIB)).unwrap();
+        assert_eq!(estimate.device, "GPU");
+        assert_eq!(estimate.quantization.as_deref(), Some("int4"));
+        assert_eq!(estimate.kv_cache_bytes, 2 * GIB);
+        assert_eq!(estimate.weights_bytes, 1000);
+        assert_eq!(estimate.fits, Some(false));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
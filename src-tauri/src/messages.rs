@@ -0,0 +1,234 @@
+//! Message codes for user-facing status and error text.
+//!
+//! Events and results that show text to the user carry a `Message`: a stable
+//! `code`, the raw `params`, and `text` rendered from the catalog below in the
+//! `locale` from settings (English when unset or not translated). A UI with
+//! its own translations keys off `code` and `params` and ignores `text`.
+//!
+//! Templates name their params as `{name}`. Params ending in `_bytes` render
+//! as sizes, other numbers with the locale's digit grouping.
+
+use serde::Serialize;
+use serde_json::{ Map, Value };
+
+use crate::settings;
+
+const DEFAULT_LOCALE: &str = "en";
+
+/// code -> (locale, template)
+const CATALOG: &[(&str, &[(&str, &str)])] = &[
+    ("init.not_started", &[("en", "Initialization not started"), ("de", "Initialisierung nicht gestartet")]),
+    ("init.deferred", &[
+        ("en", "OVMS will start when it is first needed"),
+        ("de", "OVMS wird beim ersten Bedarf gestartet"),
+    ]),
+    ("init.checking", &[("en", "Checking if OVMS is present..."), ("de", "Prüfe, ob OVMS vorhanden ist...")]),
+    ("init.present", &[("en", "OVMS already present"), ("de", "OVMS ist bereits vorhanden")]),
+    ("init.downloading", &[
+        ("en", "OVMS not found, downloading..."),
+        ("de", "OVMS nicht gefunden, wird heruntergeladen..."),
+    ]),
+    ("init.downloaded", &[("en", "OVMS downloaded successfully"), ("de", "OVMS erfolgreich heruntergeladen")]),
+    ("init.creating_config", &[("en", "Creating OVMS configuration..."), ("de", "OVMS-Konfiguration wird erstellt...")]),
+    ("init.starting_server", &[("en", "Starting OVMS server..."), ("de", "OVMS-Server wird gestartet...")]),
+    ("init.complete", &[("en", "OVMS initialization complete"), ("de", "OVMS-Initialisierung abgeschlossen")]),
+    ("init.failed.download", &[("en", "Download failed"), ("de", "Download fehlgeschlagen")]),
+    ("init.failed.config", &[("en", "Configuration failed"), ("de", "Konfiguration fehlgeschlagen")]),
+    ("init.failed.start_server", &[("en", "Server startup failed"), ("de", "Serverstart fehlgeschlagen")]),
+    ("download.completed", &[
+        ("en", "Downloaded {files} files ({size_bytes}) of {model_id}"),
+        ("de", "{files} Dateien ({size_bytes}) von {model_id} heruntergeladen"),
+    ]),
+    ("download.partial", &[
+        ("en", "Downloaded {files} files of {model_id}; {failed} failed"),
+        ("de", "{files} Dateien von {model_id} heruntergeladen; {failed} fehlgeschlagen"),
+    ]),
+    ("download.failed", &[
+        ("en", "Failed to download {model_id}"),
+        ("de", "Download von {model_id} fehlgeschlagen"),
+    ]),
+    ("download.cancelled", &[
+        ("en", "Download of {model_id} was cancelled"),
+        ("de", "Download von {model_id} wurde abgebrochen"),
+    ]),
+    ("download.paused", &[("en", "Download of {model_id} paused"), ("de", "Download von {model_id} pausiert")]),
+    ("download.running", &[("en", "Downloading {model_id}"), ("de", "{model_id} wird heruntergeladen")]),
+    ("model.updated", &[
+        ("en", "Updated {files} files of {model_id}"),
+        ("de", "{files} Dateien von {model_id} aktualisiert"),
+    ]),
+    ("model.up_to_date", &[
+        ("en", "{model_id} is up to date"),
+        ("de", "{model_id} ist auf dem neuesten Stand"),
+    ]),
+    ("model.load_failed.insufficient_memory", &[
+        ("en", "Not enough memory to load {model_id}"),
+        ("de", "Nicht genug Speicher, um {model_id} zu laden"),
+    ]),
+    ("model.load_failed.unsupported_device", &[
+        ("en", "The device {model_id} targets is not available"),
+        ("de", "Das Zielgerät von {model_id} ist nicht verfügbar"),
+    ]),
+    ("model.load_failed.missing_tokenizer", &[
+        ("en", "The tokenizer of {model_id} could not be loaded"),
+        ("de", "Der Tokenizer von {model_id} konnte nicht geladen werden"),
+    ]),
+    ("model.load_failed.invalid_graph", &[
+        ("en", "OVMS rejected the serving graph of {model_id}"),
+        ("de", "OVMS hat den Serving-Graphen von {model_id} abgelehnt"),
+    ]),
+    ("model.load_failed.missing_model_files", &[
+        ("en", "Files of {model_id} are missing"),
+        ("de", "Dateien von {model_id} fehlen"),
+    ]),
+    ("model.load_failed.timeout", &[
+        ("en", "{model_id} did not finish loading in time"),
+        ("de", "{model_id} wurde nicht rechtzeitig geladen"),
+    ]),
+    ("model.load_failed.unknown", &[
+        ("en", "OVMS failed to load {model_id}"),
+        ("de", "OVMS konnte {model_id} nicht laden"),
+    ]),
+    ("disk.low.title", &[("en", "Low disk space"), ("de", "Wenig Speicherplatz")]),
+    ("disk.critical.title", &[("en", "Disk almost full"), ("de", "Festplatte fast voll")]),
+    ("disk.low", &[
+        ("en", "Only {available_bytes} free on the drive holding Sparrow's data; {reclaimable_bytes} can be reclaimed"),
+        ("de", "Nur noch {available_bytes} frei auf dem Laufwerk mit Sparrows Daten; {reclaimable_bytes} können freigegeben werden"),
+    ]),
+    ("disk.critical", &[
+        ("en", "The drive holding Sparrow's data is almost full ({available_bytes} free); downloads and indexing may fail"),
+        ("de", "Das Laufwerk mit Sparrows Daten ist fast voll ({available_bytes} frei); Downloads und Indizierung können fehlschlagen"),
+    ]),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Message {
+    pub code: String,
+    pub params: Map<String, Value>,
+    /// `code` rendered in the configured locale
+    pub text: String,
+}
+
+impl Message {
+    pub fn new(code: &str) -> Self {
+        let mut message = Self { code: code.to_string(), params: Map::new(), text: String::new() };
+        message.text = message.render(&locale());
+        message
+    }
+
+    pub fn param(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self.text = self.render(&locale());
+        self
+    }
+
+    /// The text in `locale`, falling back to English, then to the code itself
+    pub fn render(&self, locale: &str) -> String {
+        let Some(template) = template(&self.code, locale) else {
+            return self.code.clone();
+        };
+
+        let mut text = template.to_string();
+        for (name, value) in &self.params {
+            let rendered = match value {
+                Value::Number(n) if name.ends_with("_bytes") => format_bytes(n.as_u64().unwrap_or(0), locale),
+                Value::Number(n) => match n.as_u64() {
+                    Some(n) => format_count(n, locale),
+                    None => n.to_string(),
+                },
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            text = text.replace(&format!("{{{}}}", name), &rendered);
+        }
+        text
+    }
+}
+
+fn template(code: &str, locale: &str) -> Option<&'static str> {
+    let (_, translations) = CATALOG.iter().find(|(c, _)| *c == code)?;
+    let language = locale.split(['-', '_']).next().unwrap_or(locale).to_lowercase();
+    translations
+        .iter()
+        .find(|(l, _)| *l == language)
+        .or_else(|| translations.iter().find(|(l, _)| *l == DEFAULT_LOCALE))
+        .map(|(_, template)| *template)
+}
+
+/// The configured locale, e.g. "de-DE"
+pub fn locale() -> String {
+    settings::current().locale.unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// (decimal separator, group separator)
+fn separators(locale: &str) -> (char, char) {
+    match locale.split(['-', '_']).next().unwrap_or(locale).to_lowercase().as_str() {
+        "de" | "es" | "it" | "nl" | "pt" | "tr" | "id" => (',', '.'),
+        "fr" | "ru" | "uk" | "pl" | "cs" | "sv" | "fi" | "nb" => (',', '\u{a0}'),
+        _ => ('.', ','),
+    }
+}
+
+/// Whole number with the locale's digit grouping
+pub fn format_count(value: u64, locale: &str) -> String {
+    let (_, group) = separators(locale);
+    let digits = value.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(group);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Size in binary units with one decimal, using the locale's decimal separator
+pub fn format_bytes(bytes: u64, locale: &str) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let (decimal, _) = separators(locale);
+    format!("{:.1} {}", value, UNITS[unit]).replace('.', &decimal.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_with_locale_fallback() {
+        let mut message = Message { code: "download.cancelled".to_string(), params: Map::new(), text: String::new() };
+        message.params.insert("model_id".to_string(), Value::from("OpenVINO/Phi-4"));
+
+        assert_eq!(message.render("de-AT"), "Download von OpenVINO/Phi-4 wurde abgebrochen");
+        assert_eq!(message.render("ja"), "Download of OpenVINO/Phi-4 was cancelled");
+
+        message.code = "no.such.code".to_string();
+        assert_eq!(message.render("en"), "no.such.code");
+    }
+
+    #[test]
+    fn test_locale_aware_numbers() {
+        assert_eq!(format_count(1234567, "en-US"), "1,234,567");
+        assert_eq!(format_count(1234567, "de"), "1.234.567");
+        assert_eq!(format_count(999, "de"), "999");
+        assert_eq!(format_bytes(1536 * 1024 * 1024, "de"), "1,5 GB");
+        assert_eq!(format_bytes(1536 * 1024 * 1024, "en"), "1.5 GB");
+        assert_eq!(format_bytes(12, "en"), "12 B");
+    }
+
+    #[test]
+    fn test_every_code_has_english() {
+        for (code, translations) in CATALOG {
+            assert!(translations.iter().any(|(l, _)| *l == DEFAULT_LOCALE), "{} has no English text", code);
+        }
+    }
+}
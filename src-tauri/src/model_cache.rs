@@ -0,0 +1,261 @@
+//! Compiled-model caches OVMS writes to each model's `.ovms_cache`.
+//!
+//! The caches make later loads faster but are never pruned by OVMS, and a
+//! new cache is added for every device and OpenVINO version. Besides the
+//! manual commands, the maintenance task keeps the total under
+//! `model_cache.max_size_mb` (0 = no limit) by removing the least recently
+//! written caches first. The cap also covers `ovms.runtime.cache_dir` when
+//! one is set, each entry there counting as a cache. A cache removed from a
+//! loaded model is rebuilt on its next load.
+
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::time::SystemTime;
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+
+use crate::temp_files::PurgeReport;
+use crate::{ models, paths, settings };
+
+const CACHE_DIR_NAME: &str = ".ovms_cache";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCacheUsage {
+    pub model_id: String,
+    pub size_bytes: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheUsage {
+    pub total_bytes: u64,
+    /// Largest first
+    pub models: Vec<ModelCacheUsage>,
+}
+
+pub(crate) struct CacheDir {
+    pub model_id: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub modified: SystemTime,
+}
+
+/// Size and newest write time of everything under `path`
+fn measure(path: &Path) -> (u64, SystemTime) {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .fold((0, SystemTime::UNIX_EPOCH), |(size, newest), metadata| {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            (size + metadata.len(), newest.max(modified))
+        })
+}
+
+/// Caches of the models under `models_dir`, laid out as `<org>/<name>`
+pub(crate) fn find_caches(models_dir: &Path) -> Vec<CacheDir> {
+    let mut caches = Vec::new();
+    let Ok(orgs) = fs::read_dir(models_dir) else {
+        return caches;
+    };
+
+    for org in orgs.flatten().filter(|entry| entry.path().is_dir()) {
+        let Ok(models) = fs::read_dir(org.path()) else {
+            continue;
+        };
+        for model in models.flatten() {
+            let path = model.path().join(CACHE_DIR_NAME);
+            if !path.is_dir() {
+                continue;
+            }
+            let (size_bytes, modified) = measure(&path);
+            caches.push(CacheDir {
+                model_id: format!("{}/{}", org.file_name().to_string_lossy(), model.file_name().to_string_lossy()),
+                path,
+                size_bytes,
+                modified,
+            });
+        }
+    }
+    caches
+}
+
+/// Entries of the shared `ovms.runtime.cache_dir`, used by models whose graph
+/// sets no cache of its own
+fn find_shared_caches(dir: &Path) -> Vec<CacheDir> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            let (size_bytes, modified) = measure(&path);
+            CacheDir { model_id: entry.file_name().to_string_lossy().to_string(), path, size_bytes, modified }
+        })
+        .collect()
+}
+
+fn remove(caches: Vec<CacheDir>) -> PurgeReport {
+    let mut report = PurgeReport::default();
+    for cache in caches {
+        let removed = if cache.path.is_dir() { fs::remove_dir_all(&cache.path) } else { fs::remove_file(&cache.path) };
+        match removed {
+            Ok(()) => {
+                report.removed_entries += 1;
+                report.reclaimed_bytes += cache.size_bytes;
+            }
+            // OVMS keeps the cache of a loaded model open on Windows
+            Err(e) => tracing::warn!(model_id = %cache.model_id, error = %e, "Failed to remove model cache"),
+        }
+    }
+    report
+}
+
+/// Caches to remove, oldest first, to bring the total down to `max_bytes`
+fn over_cap(mut caches: Vec<CacheDir>, max_bytes: u64) -> Vec<CacheDir> {
+    let mut total: u64 = caches.iter().map(|cache| cache.size_bytes).sum();
+    caches.sort_by_key(|cache| cache.modified);
+    caches
+        .into_iter()
+        .take_while(|cache| {
+            let evict = total > max_bytes;
+            total = total.saturating_sub(cache.size_bytes);
+            evict
+        })
+        .collect()
+}
+
+/// Remove the oldest caches while the total is over the configured cap
+pub async fn enforce_size_cap() -> Result<PurgeReport, String> {
+    let config = settings::current();
+    let max_size_mb = config.model_cache.max_size_mb;
+    if max_size_mb == 0 {
+        return Ok(PurgeReport::default());
+    }
+    let models_dir = paths::get_models_dir().map_err(|e| e.to_string())?;
+    let shared_dir = config.ovms.runtime.cache_dir.filter(|dir| !dir.trim().is_empty()).map(PathBuf::from);
+
+    let report = tokio::task::spawn_blocking(move || {
+        let mut caches = find_caches(&models_dir);
+        if let Some(dir) = &shared_dir {
+            caches.extend(find_shared_caches(dir));
+        }
+        remove(over_cap(caches, max_size_mb * 1024 * 1024))
+    }).await
+        .map_err(|e| format!("Failed to enforce the model cache cap: {}", e))?;
+    if report.removed_entries > 0 {
+        tracing::info!(
+            removed = report.removed_entries,
+            reclaimed_bytes = report.reclaimed_bytes,
+            max_size_mb = max_size_mb,
+            "Removed model caches over the size cap"
+        );
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_cache_usage() -> Result<CacheUsage, String> {
+    let models_dir = paths::get_models_dir().map_err(|e| e.to_string())?;
+    let caches = tokio::task::spawn_blocking(move || find_caches(&models_dir))
+        .await
+        .map_err(|e| format!("Failed to measure model caches: {}", e))?;
+
+    let mut models: Vec<ModelCacheUsage> = caches
+        .into_iter()
+        .map(|cache| ModelCacheUsage {
+            model_id: cache.model_id,
+            size_bytes: cache.size_bytes,
+            last_modified: (cache.modified > SystemTime::UNIX_EPOCH).then(|| cache.modified.into()),
+        })
+        .collect();
+    models.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+    Ok(CacheUsage { total_bytes: models.iter().map(|m| m.size_bytes).sum(), models })
+}
+
+/// Remove the cache of one model, or of every model when `model_id` is unset
+#[tauri::command]
+pub async fn clear_model_cache(model_id: Option<String>) -> Result<PurgeReport, String> {
+    let models_dir = paths::get_models_dir().map_err(|e| e.to_string())?;
+    let model_id = model_id.map(|id| models::normalize_model_id(&id));
+
+    let report = tokio::task::spawn_blocking(move || {
+        let mut caches = find_caches(&models_dir);
+        if let Some(model_id) = &model_id {
+            caches.retain(|cache| &cache.model_id == model_id);
+            if caches.is_empty() {
+                return Err(format!("No compiled cache found for {}", model_id));
+            }
+        }
+        Ok(remove(caches))
+    }).await
+        .map_err(|e| format!("Failed to clear model caches: {}", e))??;
+
+    tracing::info!(
+        removed = report.removed_entries,
+        reclaimed_bytes = report.reclaimed_bytes,
+        "Cleared model caches"
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn cache(model_id: &str, size_bytes: u64, age_secs: u64) -> CacheDir {
+        CacheDir {
+            model_id: model_id.to_string(),
+            path: PathBuf::new(),
+            size_bytes,
+            modified: SystemTime::now() - Duration::from_secs(age_secs),
+        }
+    }
+
+    #[test]
+    fn test_over_cap_evicts_oldest_first() {
+        let caches = vec![cache("new", 40, 10), cache("old", 50, 1000), cache("mid", 30, 100)];
+        let evicted: Vec<String> = over_cap(caches, 60).into_iter().map(|c| c.model_id).collect();
+        assert_eq!(evicted, vec!["old", "mid"]);
+
+        let caches = vec![cache("a", 10, 10), cache("b", 10, 20)];
+        assert!(over_cap(caches, 100).is_empty());
+    }
+
+    #[test]
+    fn test_find_caches_measures_model_folders() {
+        let dir = std::env::temp_dir().join(format!("sparrow-cache-{}", uuid::Uuid::new_v4()));
+        let cache_dir = dir.join("OpenVINO").join("Qwen3-8B-int4-ov").join(CACHE_DIR_NAME);
+        fs::create_dir_all(cache_dir.join("GPU")).unwrap();
+        fs::write(cache_dir.join("GPU").join("blob.cl_cache"), vec![0u8; 128]).unwrap();
+        fs::create_dir_all(dir.join("OpenVINO").join("no-cache")).unwrap();
+
+        let caches = find_caches(&dir);
+        assert_eq!(caches.len(), 1);
+        assert_eq!(caches[0].model_id, "OpenVINO/Qwen3-8B-int4-ov");
+        assert_eq!(caches[0].size_bytes, 128);
+
+        assert_eq!(remove(caches).reclaimed_bytes, 128);
+        assert!(!cache_dir.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shared_cache_entries_are_files_and_folders() {
+        let dir = std::env::temp_dir().join(format!("sparrow-shared-cache-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("gpu")).unwrap();
+        fs::write(dir.join("gpu").join("kernels.cl_cache"), vec![0u8; 64]).unwrap();
+        fs::write(dir.join("1234.blob"), vec![0u8; 32]).unwrap();
+
+        let caches = find_shared_caches(&dir);
+        assert_eq!(caches.iter().map(|c| c.size_bytes).sum::<u64>(), 96);
+        assert_eq!(remove(caches).removed_entries, 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
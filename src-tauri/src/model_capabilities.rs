@@ -0,0 +1,177 @@
+//! What a downloaded model can do, gathered from the places that know.
+//!
+//! The model type comes from the metadata store, the tool/reasoning parsers
+//! and draft model from its graph.pbtxt, and the context length and vision
+//! tower from its config.json. The chat pipeline checks these before sending
+//! a request: tools go in the request's `tools` field only when OVMS can parse
+//! the model's calls (see `tool_transport`), and images sent to a text-only
+//! model fail with a clear message rather than being dropped by OVMS.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::huggingface::{ self, ModelType, ToolCallMode };
+use crate::{ models, paths };
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelCapabilities {
+    pub model_id: String,
+    pub model_type: Option<ModelType>,
+    /// Longest context the model was trained for, in tokens
+    pub context_length: Option<u64>,
+    pub tool_parser: Option<String>,
+    pub reasoning_parser: Option<String>,
+    /// Draft model used for speculative decoding, when the graph has one
+    pub draft_model: Option<String>,
+    /// Tokens the draft model proposes per step, as set by `pair_draft_model`
+    pub num_assistant_tokens: Option<u32>,
+    /// Per-model choice set with `set_tool_call_mode`
+    pub tool_call_mode: ToolCallMode,
+    pub supports_chat: bool,
+    /// OVMS parses tool calls out of the model's output (the graph has a tool parser)
+    pub supports_tools: bool,
+    pub supports_vision: bool,
+    pub supports_speculative_decoding: bool,
+}
+
+/// How tools reach the model in a chat request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolTransport {
+    None,
+    /// `tools`/`tool_choice` request fields; calls come back as `tool_calls` deltas
+    Native,
+    /// Tool list in the system prompt; calls come back as `<tool_call>` text
+    Prompt,
+}
+
+impl ModelCapabilities {
+    pub fn tool_transport(&self) -> ToolTransport {
+        if !self.supports_chat {
+            return ToolTransport::None;
+        }
+        match self.tool_call_mode {
+            ToolCallMode::Native => ToolTransport::Native,
+            ToolCallMode::Prompt => ToolTransport::Prompt,
+            ToolCallMode::Auto if self.supports_tools => ToolTransport::Native,
+            ToolCallMode::Auto => ToolTransport::Prompt,
+        }
+    }
+}
+
+/// Quoted `key: "value"` from a graph.pbtxt
+fn graph_string(graph: &str, key: &str) -> Option<String> {
+    graph.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(|value| value.trim().trim_end_matches(',').trim_matches('"').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Context length from a Hugging Face config.json; multimodal configs keep
+/// it under `text_config`
+fn context_length(config: &serde_json::Value) -> Option<u64> {
+    const KEYS: &[&str] = &["max_position_embeddings", "max_sequence_length", "seq_length", "n_positions"];
+    [config, &config["text_config"]]
+        .into_iter()
+        .find_map(|section| KEYS.iter().find_map(|key| section.get(*key).and_then(|v| v.as_u64())))
+}
+
+pub(crate) fn from_files(model_id: &str, model_type: Option<ModelType>, model_dir: &Path) -> ModelCapabilities {
+    let graph = std::fs::read_to_string(model_dir.join("graph.pbtxt")).unwrap_or_default();
+    let config: serde_json::Value = std::fs::read_to_string(model_dir.join("config.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+
+    let tool_parser = graph_string(&graph, "tool_parser");
+    let draft_model = graph_string(&graph, "draft_models_path");
+    let supports_chat = matches!(model_type, Some(ModelType::Text | ModelType::ImageToText));
+
+    ModelCapabilities {
+        model_id: model_id.to_string(),
+        context_length: context_length(&config),
+        reasoning_parser: graph_string(&graph, "reasoning_parser"),
+        supports_chat,
+        supports_tools: supports_chat && tool_parser.is_some(),
+        supports_vision: matches!(model_type, Some(ModelType::ImageToText)) || config.get("vision_config").is_some(),
+        supports_speculative_decoding: draft_model.is_some(),
+        tool_parser,
+        draft_model,
+        model_type,
+        ..Default::default()
+    }
+}
+
+/// Capabilities of a downloaded model, by id or by the name OVMS serves it under
+pub async fn capabilities(model: &str) -> Result<ModelCapabilities, String> {
+    let model_id = huggingface::resolve_model_id(model).await;
+    let metadata = huggingface::get_model_metadata(&model_id).await.ok().flatten();
+    let model_type = metadata.as_ref().map(|m| m.model_type.clone());
+    let model_dir = paths::get_models_dir()
+        .map_err(|e| e.to_string())?
+        .join(models::normalize_model_id(&model_id));
+    if !model_dir.exists() {
+        return Err(format!("Model not found: {}", model_id));
+    }
+
+    let mut caps = tokio::task::spawn_blocking(move || from_files(&model_id, model_type, &model_dir))
+        .await
+        .map_err(|e| format!("Failed to read model capabilities: {}", e))?;
+    if let Some(metadata) = metadata {
+        caps.tool_call_mode = metadata.tool_call_mode;
+        if caps.supports_speculative_decoding {
+            caps.num_assistant_tokens = metadata.num_assistant_tokens;
+        }
+    }
+    Ok(caps)
+}
+
+#[tauri::command]
+pub async fn get_model_capabilities(model_id: String) -> Result<ModelCapabilities, String> {
+    capabilities(&model_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_files_reads_graph_and_config() {
+        let dir = std::env::temp_dir().join(format!("sparrow-caps-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("graph.pbtxt"),
+            "models_path: \"./\",\n          tool_parser: \"hermes3\",\n          draft_models_path: \"./draft\",\n"
+        ).unwrap();
+        std::fs::write(dir.join("config.json"), r#"{"text_config": {"max_position_embeddings": 32768}, "vision_config": {}}"#).unwrap();
+
+        let caps = from_files("OpenVINO/test", Some(ModelType::Text), &dir);
+        assert_eq!(caps.tool_parser.as_deref(), Some("hermes3"));
+        assert_eq!(caps.draft_model.as_deref(), Some("./draft"));
+        assert_eq!(caps.context_length, Some(32768));
+        assert!(caps.supports_tools);
+        assert!(caps.supports_vision);
+        assert!(caps.supports_speculative_decoding);
+
+        let caps = from_files("OpenVINO/embed", Some(ModelType::Embedding), &dir);
+        assert!(!caps.supports_chat);
+        assert!(!caps.supports_tools);
+        assert_eq!(caps.tool_transport(), ToolTransport::None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tool_transport_follows_mode_and_parser() {
+        let mut caps = ModelCapabilities { supports_chat: true, supports_tools: true, ..Default::default() };
+        assert_eq!(caps.tool_transport(), ToolTransport::Native);
+        caps.tool_call_mode = ToolCallMode::Prompt;
+        assert_eq!(caps.tool_transport(), ToolTransport::Prompt);
+
+        let mut caps = ModelCapabilities { supports_chat: true, ..Default::default() };
+        assert_eq!(caps.tool_transport(), ToolTransport::Prompt);
+        caps.tool_call_mode = ToolCallMode::Native;
+        assert_eq!(caps.tool_transport(), ToolTransport::Native);
+    }
+}
@@ -0,0 +1,276 @@
+//! Turn OVMS model load failures into something a user can act on.
+//!
+//! OVMS reports failures as a status code plus a free-form message, with the
+//! actual reason usually only in its own output. `diagnose` matches the
+//! message and, when that says too little, the error lines OVMS printed
+//! during the load against known failure signatures, looks at the model
+//! directory for missing pieces, and names the likely cause with concrete
+//! next steps.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::messages::Message;
+use crate::ovms;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadFailureCause {
+    InsufficientMemory,
+    UnsupportedDevice,
+    MissingTokenizer,
+    InvalidGraph,
+    MissingModelFiles,
+    Timeout,
+    Unknown,
+}
+
+impl LoadFailureCause {
+    fn code(self) -> &'static str {
+        match self {
+            LoadFailureCause::InsufficientMemory => "insufficient_memory",
+            LoadFailureCause::UnsupportedDevice => "unsupported_device",
+            LoadFailureCause::MissingTokenizer => "missing_tokenizer",
+            LoadFailureCause::InvalidGraph => "invalid_graph",
+            LoadFailureCause::MissingModelFiles => "missing_model_files",
+            LoadFailureCause::Timeout => "timeout",
+            LoadFailureCause::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadDiagnostics {
+    pub model_id: String,
+    pub cause: LoadFailureCause,
+    /// One-line explanation of the cause
+    pub summary: String,
+    pub suggestions: Vec<String>,
+    /// Device named in the model's graph.pbtxt, if any
+    pub target_device: Option<String>,
+    /// The raw error this was derived from
+    pub error: String,
+    /// The OVMS output line the cause was recognized in, when the error alone
+    /// did not tell
+    pub log_line: Option<String>,
+    /// The cause as a message code (`model.load_failed.<cause>`), for localized UIs
+    pub message: Message,
+}
+
+impl LoadDiagnostics {
+    /// Error text for commands that return `Result<_, String>`
+    pub fn to_message(&self) -> String {
+        let mut message = format!("Failed to load '{}': {}", self.model_id, self.summary);
+        for suggestion in &self.suggestions {
+            message.push_str("\n- ");
+            message.push_str(suggestion);
+        }
+        message.push_str(&format!("\n\nOVMS said: {}", self.error));
+        if let Some(line) = &self.log_line {
+            message.push_str(&format!("\nOVMS log: {}", line));
+        }
+        message
+    }
+}
+
+/// Lowercase substrings that identify a cause, checked in order
+const SIGNATURES: &[(LoadFailureCause, &[&str])] = &[
+    (LoadFailureCause::Timeout, &["timed out after"]),
+    (LoadFailureCause::InsufficientMemory, &[
+        "out of memory",
+        "bad_alloc",
+        "cl_out_of_resources",
+        "cl_mem_object_allocation_failure",
+        "not enough memory",
+        "insufficient memory",
+        "failed to allocate",
+        "unable to allocate",
+    ]),
+    (LoadFailureCause::UnsupportedDevice, &[
+        "is not registered in the openvino runtime",
+        "device with",
+        "failed to create plugin",
+        "unsupported device",
+        "device not found",
+        "no devices found",
+    ]),
+    (LoadFailureCause::MissingTokenizer, &["tokenizer"]),
+    (LoadFailureCause::InvalidGraph, &[
+        "graph.pbtxt",
+        "calculator",
+        "mediapipe",
+        "failed to parse",
+        "graph definition",
+    ]),
+    (LoadFailureCause::MissingModelFiles, &[
+        "no such file",
+        "cannot find",
+        "not found",
+        "openvino_model.xml",
+    ]),
+];
+
+/// Lowercase substrings that mark an OVMS output line as reporting a failure;
+/// other lines are not matched, since the signatures are loose enough to hit
+/// ordinary progress output
+const LOG_ERROR_MARKERS: &[&str] = &["[error]", "[critical]", "error", "exception", "failed", "unable"];
+
+const TOKENIZER_FILES: &[&str] = &["openvino_tokenizer.xml", "openvino_detokenizer.xml"];
+
+fn match_signature(error: &str) -> LoadFailureCause {
+    let error = error.to_lowercase();
+    SIGNATURES
+        .iter()
+        .find(|(_, patterns)| patterns.iter().any(|p| error.contains(p)))
+        .map_or(LoadFailureCause::Unknown, |(cause, _)| *cause)
+}
+
+/// The newest failure line in `log_tail` that names a specific cause
+fn match_log(log_tail: &[String]) -> Option<(LoadFailureCause, &str)> {
+    log_tail
+        .iter()
+        .rev()
+        .filter(|line| {
+            let lower = line.to_lowercase();
+            LOG_ERROR_MARKERS.iter().any(|marker| lower.contains(marker))
+        })
+        .map(|line| (match_signature(line), line.as_str()))
+        .find(|(cause, _)| !matches!(cause, LoadFailureCause::Unknown | LoadFailureCause::Timeout))
+}
+
+fn suggestions_for(cause: LoadFailureCause, target_device: Option<&str>) -> (String, Vec<String>) {
+    let on_device = target_device.map(|d| format!(" on {}", d)).unwrap_or_default();
+    match cause {
+        LoadFailureCause::InsufficientMemory => (
+            format!("there is not enough memory to load the model{}", on_device),
+            vec![
+                "Close other applications or unload other models, then try again".to_string(),
+                "Pick a smaller or more heavily quantized variant (e.g. int4 instead of int8/fp16)".to_string(),
+                "If the model targets GPU, try loading it on CPU, which can use system memory".to_string(),
+            ],
+        ),
+        LoadFailureCause::UnsupportedDevice => (
+            format!("the device the model targets{} is not available", on_device),
+            vec![
+                "Check that the GPU/NPU driver is installed and up to date".to_string(),
+                "Load the model on CPU instead".to_string(),
+            ],
+        ),
+        LoadFailureCause::MissingTokenizer => (
+            "the model's tokenizer could not be loaded".to_string(),
+            vec![
+                "Delete the model and download it again; the tokenizer files may be missing or incomplete".to_string(),
+                "Use a model from the OpenVINO organization, which ships converted tokenizers".to_string(),
+            ],
+        ),
+        LoadFailureCause::InvalidGraph => (
+            "OVMS rejected the model's serving graph (graph.pbtxt)".to_string(),
+            vec![
+                "Delete the model's graph.pbtxt and load it again to regenerate it".to_string(),
+                "Make sure OVMS is up to date, since older versions lack newer graph calculators".to_string(),
+            ],
+        ),
+        LoadFailureCause::MissingModelFiles => (
+            "some model files are missing".to_string(),
+            vec!["Delete the model and download it again".to_string()],
+        ),
+        LoadFailureCause::Timeout => (
+            "the model did not finish loading in time".to_string(),
+            vec![
+                "Large models can take several minutes on first load; try again".to_string(),
+                "Check the OVMS output for errors".to_string(),
+            ],
+        ),
+        LoadFailureCause::Unknown => (
+            "OVMS reported an error".to_string(),
+            vec!["Check the OVMS output for details".to_string()],
+        ),
+    }
+}
+
+/// Classify a load failure of the model stored in `model_dir`; `log_tail` is
+/// the OVMS output written while the load was attempted
+pub fn diagnose(model_id: &str, model_dir: &Path, error: &str, log_tail: &[String]) -> LoadDiagnostics {
+    let target_device = std::fs::read_to_string(model_dir.join("graph.pbtxt"))
+        .ok()
+        .and_then(|graph| ovms::graph_device(&graph));
+
+    let mut cause = match_signature(error);
+    let mut log_line = None;
+    // A timeout or a generic "not found" only says the model never became
+    // available; the reason is in what OVMS printed meanwhile
+    if matches!(cause, LoadFailureCause::Unknown | LoadFailureCause::Timeout | LoadFailureCause::MissingModelFiles) {
+        if let Some((logged, line)) = match_log(log_tail) {
+            cause = logged;
+            log_line = Some(line.to_string());
+        }
+    }
+    // Files missing on disk are a more certain explanation than an unrecognized message
+    if matches!(cause, LoadFailureCause::Unknown | LoadFailureCause::MissingModelFiles) {
+        let has_tokenizer_files = TOKENIZER_FILES.iter().all(|f| model_dir.join(f).exists());
+        if target_device.is_some() && !has_tokenizer_files {
+            cause = LoadFailureCause::MissingTokenizer;
+        } else if !model_dir.join("graph.pbtxt").exists() {
+            cause = LoadFailureCause::InvalidGraph;
+        }
+    }
+
+    let (summary, suggestions) = suggestions_for(cause, target_device.as_deref());
+    let mut message = Message::new(&format!("model.load_failed.{}", cause.code())).param("model_id", model_id);
+    if let Some(device) = &target_device {
+        message = message.param("device", device.as_str());
+    }
+    LoadDiagnostics {
+        model_id: model_id.to_string(),
+        cause,
+        summary,
+        suggestions,
+        target_device,
+        error: error.to_string(),
+        log_line,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_signature() {
+        assert_eq!(match_signature("Exception from src/inference: std::bad_alloc"), LoadFailureCause::InsufficientMemory);
+        assert_eq!(
+            match_signature("Device with \"NPU\" name is not registered in the OpenVINO Runtime"),
+            LoadFailureCause::UnsupportedDevice
+        );
+        assert_eq!(match_signature("Failed to load openvino_tokenizer.xml"), LoadFailureCause::MissingTokenizer);
+        assert_eq!(match_signature("something odd"), LoadFailureCause::Unknown);
+    }
+
+    #[test]
+    fn test_diagnose_from_log_tail() {
+        // Nothing on disk, so only the error and the log decide
+        let dir = std::env::temp_dir().join(format!("sparrow-diagnose-{}", uuid::Uuid::new_v4()));
+        let log_tail: Vec<String> = [
+            "[2024-05-01 10:00:00.000][1][serving][info][modelmanager.cpp:123] Loading model: llm",
+            "[2024-05-01 10:00:01.000][1][serving][error][servable_initializer.cpp:88] Error during llm node initialization: Exception from src/inference/src/core.cpp:116: std::bad_alloc",
+            "[2024-05-01 10:00:01.500][1][serving][info][modelmanager.cpp:140] Model llm not found",
+        ].iter().map(|line| line.to_string()).collect();
+
+        let diagnostics = diagnose("OpenVINO/llm", &dir, "Timed out after 300s waiting for 'llm' to load", &log_tail);
+        assert_eq!(diagnostics.cause, LoadFailureCause::InsufficientMemory);
+        assert!(diagnostics.log_line.as_deref().unwrap().contains("bad_alloc"));
+
+        // A specific error message wins over the log
+        let diagnostics = diagnose("OpenVINO/llm", &dir, "Failed to load openvino_tokenizer.xml", &log_tail);
+        assert_eq!(diagnostics.cause, LoadFailureCause::MissingTokenizer);
+        assert!(diagnostics.log_line.is_none());
+
+        // Informational lines are not classified
+        let quiet = vec![log_tail[0].clone(), log_tail[2].clone()];
+        let diagnostics = diagnose("OpenVINO/llm", &dir, "Timed out after 300s waiting for 'llm' to load", &quiet);
+        assert_eq!(diagnostics.cause, LoadFailureCause::Timeout);
+        assert!(diagnostics.log_line.is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! Verify model files against the sizes and SHA256 checksums Hugging Face
+//! publishes for them.
+//!
+//! Downloads are checked file by file as they finish (see
+//! `huggingface::download_single_file`). `verify_downloaded_model` re-checks
+//! a model that is already on disk, e.g. after a crash or when a load fails in
+//! a way that hints at a damaged file. Only LFS files have a published
+//! SHA256; small files are checked by size.
+
+use std::fmt;
+use std::io::Read;
+use std::path::{ Path, PathBuf };
+
+use serde::Serialize;
+use sha2::{ Digest, Sha256 };
+
+use crate::huggingface::{ self, HfFileInfo };
+use crate::paths;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum FileProblem {
+    Missing,
+    SizeMismatch { expected: u64, actual: u64 },
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl fmt::Display for FileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileProblem::Missing => write!(f, "file is missing"),
+            FileProblem::SizeMismatch { expected, actual } => {
+                write!(f, "expected {} bytes, found {}", expected, actual)
+            }
+            FileProblem::ChecksumMismatch { expected, actual } => {
+                write!(f, "SHA256 is {}, expected {}", actual, expected)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorruptedFile {
+    pub path: String,
+    #[serde(flatten)]
+    pub problem: FileProblem,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelVerification {
+    pub model_id: String,
+    pub checked_files: usize,
+    /// How many of the checked files had a checksum to compare against
+    pub checksummed_files: usize,
+    pub corrupted: Vec<CorruptedFile>,
+    pub ok: bool,
+}
+
+/// Compare a file's size and, when known, its SHA256 with the published values
+pub fn check_file(expected: &HfFileInfo, size: u64, sha256: Option<&str>) -> Result<(), FileProblem> {
+    if let Some(expected_size) = expected.expected_size() {
+        if expected_size != size {
+            return Err(FileProblem::SizeMismatch { expected: expected_size, actual: size });
+        }
+    }
+    if let (Some(expected_sha), Some(actual)) = (expected.expected_sha256(), sha256) {
+        if !expected_sha.eq_ignore_ascii_case(actual) {
+            return Err(FileProblem::ChecksumMismatch {
+                expected: expected_sha.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn verify_local_files(model_dir: &Path, files: &[HfFileInfo]) -> Result<(usize, Vec<CorruptedFile>), String> {
+    let mut checksummed = 0;
+    let mut corrupted = Vec::new();
+
+    for expected in files {
+        let path = model_dir.join(&expected.path);
+        let size = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => {
+                corrupted.push(CorruptedFile { path: expected.path.clone(), problem: FileProblem::Missing });
+                continue;
+            }
+        };
+
+        // Hashing a file whose size is already wrong is wasted time
+        let sha256 = match expected.expected_sha256() {
+            Some(_) if expected.expected_size() == Some(size) => {
+                checksummed += 1;
+                Some(sha256_file(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?)
+            }
+            _ => None,
+        };
+
+        if let Err(problem) = check_file(expected, size, sha256.as_deref()) {
+            corrupted.push(CorruptedFile { path: expected.path.clone(), problem });
+        }
+    }
+
+    Ok((checksummed, corrupted))
+}
+
+/// Re-check every file of a downloaded model against the repository
+#[tauri::command]
+pub async fn verify_downloaded_model(model_id: String, download_path: Option<String>) -> Result<ModelVerification, String> {
+    let model_id = crate::models::normalize_model_id(&model_id);
+    log_operation_start!("Verify model", model_id = %model_id);
+
+    let model_dir = match download_path {
+        Some(path) => PathBuf::from(path).join(&model_id),
+        None => paths::get_models_dir().map_err(|e| e.to_string())?.join(&model_id),
+    };
+    if !model_dir.exists() {
+        return Err(format!("Model not found at: {}. Please download the model first.", model_dir.display()));
+    }
+
+    let client = crate::http::client()?;
+    let files = huggingface::fetch_file_tree(&client, &model_id).await.map_err(|e| {
+        log_operation_error!("Verify model", &e, model_id = %model_id);
+        e
+    })?;
+
+    let checked_files = files.len();
+    let (checksummed_files, corrupted) = tokio::task
+        ::spawn_blocking(move || verify_local_files(&model_dir, &files)).await
+        .map_err(|e| format!("Verification task failed: {}", e))??;
+
+    if corrupted.is_empty() {
+        log_operation_success!("Verify model", model_id = %model_id, files = checked_files);
+    } else {
+        log_warning!("Model has corrupted files", model_id = %model_id, corrupted = corrupted.len());
+    }
+
+    Ok(ModelVerification {
+        model_id,
+        checked_files,
+        checksummed_files,
+        ok: corrupted.is_empty(),
+        corrupted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huggingface::HfLfsInfo;
+
+    fn lfs_file(size: u64, oid: &str) -> HfFileInfo {
+        HfFileInfo {
+            path: "openvino_model.bin".to_string(),
+            file_type: "file".to_string(),
+            size: Some(size),
+            oid: None,
+            lfs: Some(HfLfsInfo { oid: oid.to_string(), size }),
+        }
+    }
+
+    #[test]
+    fn test_check_file() {
+        // SHA256 of "abc"
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let expected = lfs_file(3, abc);
+
+        assert_eq!(check_file(&expected, 3, Some(abc)), Ok(()));
+        assert_eq!(check_file(&expected, 2, Some(abc)), Err(FileProblem::SizeMismatch { expected: 3, actual: 2 }));
+        assert!(matches!(check_file(&expected, 3, Some("00")), Err(FileProblem::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn test_verify_local_files_reports_missing_and_corrupted() {
+        let dir = std::env::temp_dir().join(format!("sparrow-verify-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("openvino_model.bin"), b"abd").unwrap();
+
+        let mut missing = lfs_file(3, "00");
+        missing.path = "openvino_tokenizer.bin".to_string();
+        let files = vec![
+            lfs_file(3, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            missing,
+        ];
+
+        let (checksummed, corrupted) = verify_local_files(&dir, &files).unwrap();
+        assert_eq!(checksummed, 1);
+        assert_eq!(corrupted.len(), 2);
+        assert!(matches!(corrupted[0].problem, FileProblem::ChecksumMismatch { .. }));
+        assert_eq!(corrupted[1].problem, FileProblem::Missing);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
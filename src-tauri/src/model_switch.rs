@@ -0,0 +1,214 @@
+//! Swap the model behind a chat session without losing the conversation.
+//!
+//! `switch_model` loads the new model (it becomes the default text model;
+//! others stay loaded), waits until OVMS reports it AVAILABLE, records
+//! it on the session, and replays the system prompt and history through it
+//! once so its prompt cache is warm for the next turn. Every stage is reported
+//! as a `model-switch-progress` event.
+
+use std::time::Instant;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestAssistantMessageArgs,
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager };
+
+use crate::chat::{ self, ChatMessage };
+use crate::ovms;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchStage {
+    Loading,
+    WarmingUp,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SwitchProgress<'a> {
+    session_id: &'a str,
+    model_id: &'a str,
+    stage: SwitchStage,
+    elapsed_ms: u64,
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwitchModelResult {
+    pub session_id: String,
+    pub model_id: String,
+    /// Name the model is served under, for chat requests
+    pub model_name: String,
+    pub elapsed_ms: u64,
+    /// Whether the conversation was replayed through the new model
+    pub warmed_up: bool,
+}
+
+/// OVMS serves `OpenVINO/<name>` models under `<name>`
+fn served_name(model_id: &str) -> &str {
+    model_id.rsplit('/').next().unwrap_or(model_id)
+}
+
+fn emit_progress(app: &AppHandle, session_id: &str, model_id: &str, stage: SwitchStage, started: Instant, message: Option<String>) {
+    let _ = app.emit("model-switch-progress", SwitchProgress {
+        session_id,
+        model_id,
+        stage,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        message,
+    });
+}
+
+fn replay_messages(system_prompt: Option<String>, history: &[ChatMessage]) -> Result<Vec<ChatCompletionRequestMessage>, String> {
+    let mut messages: Vec<ChatCompletionRequestMessage> = Vec::new();
+    if let Some(system_prompt) = system_prompt.filter(|p| !p.trim().is_empty()) {
+        messages.push(
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(system_prompt)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into()
+        );
+    }
+
+    for message in history.iter().filter(|m| m.is_error != Some(true)) {
+        match message.role.as_str() {
+            "user" => messages.push(
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content(message.content.clone())
+                    .build()
+                    .map_err(|e| format!("Failed to build user message: {}", e))?
+                    .into()
+            ),
+            "assistant" => {
+                let content = chat::strip_tool_xml_tags(&message.content);
+                if !content.is_empty() {
+                    messages.push(
+                        ChatCompletionRequestAssistantMessageArgs::default()
+                            .content(content)
+                            .build()
+                            .map_err(|e| format!("Failed to build assistant message: {}", e))?
+                            .into()
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(messages)
+}
+
+/// Run the conversation through the model once, generating a single token
+async fn warm_up(model_name: &str, messages: Vec<ChatCompletionRequestMessage>) -> Result<(), String> {
+    let config = OpenAIConfig::new()
+        .with_api_key("unused")
+        .with_api_base(ovms::openai_api_base());
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model_name.to_string())
+        .messages(messages)
+        .max_tokens(1u32)
+        .build()
+        .map_err(|e| format!("Failed to build warm-up request: {}", e))?;
+
+    Client::with_config(config)
+        .chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Warm-up request failed: {}", e))?;
+    Ok(())
+}
+
+async fn switch(
+    app: &AppHandle,
+    session_id: &str,
+    model_id: &str,
+    system_prompt: Option<String>,
+    started: Instant
+) -> Result<SwitchModelResult, String> {
+    if app.state::<AppState>().operations.lock().contains_key(session_id) {
+        return Err("A reply is still streaming in this session; stop it before switching models".to_string());
+    }
+
+    let storage = chat::load_chat_sessions().await?;
+    let history = storage.sessions
+        .get(session_id)
+        .map(|session| session.messages.clone())
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+
+    // `load_model` returns once OVMS reports the model AVAILABLE and emits
+    // `model-load-progress` meanwhile
+    emit_progress(app, session_id, model_id, SwitchStage::Loading, started, None);
+    ovms::load_model(app.clone(), model_id.to_string(), None).await?;
+
+    let model_name = served_name(model_id);
+    chat::update_chat_session(session_id.to_string(), None, Some(model_id.to_string())).await?;
+
+    let messages = replay_messages(system_prompt, &history)?;
+    let warmed_up = if messages.is_empty() {
+        false
+    } else {
+        emit_progress(app, session_id, model_id, SwitchStage::WarmingUp, started, None);
+        // A cold cache only costs time on the next turn, so this never fails the switch
+        match warm_up(model_name, messages).await {
+            Ok(()) => true,
+            Err(e) => {
+                log_warning!("Skipped conversation replay after model switch", error = %e, model_id = %model_id);
+                false
+            }
+        }
+    };
+
+    Ok(SwitchModelResult {
+        session_id: session_id.to_string(),
+        model_id: model_id.to_string(),
+        model_name: model_name.to_string(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        warmed_up,
+    })
+}
+
+/// Load `model_id` for a session mid-conversation. `system_prompt` is the
+/// prompt the chat uses, replayed with the history to warm the new model.
+#[tauri::command]
+pub async fn switch_model(
+    app: AppHandle,
+    session_id: String,
+    model_id: String,
+    system_prompt: Option<String>
+) -> Result<SwitchModelResult, String> {
+    log_operation_start!("Switch model", session_id = %session_id, model_id = %model_id);
+    let started = Instant::now();
+
+    match switch(&app, &session_id, &model_id, system_prompt, started).await {
+        Ok(result) => {
+            emit_progress(&app, &session_id, &model_id, SwitchStage::Ready, started, None);
+            log_operation_success!("Switch model", model_id = %model_id, elapsed_ms = result.elapsed_ms);
+            Ok(result)
+        }
+        Err(e) => {
+            emit_progress(&app, &session_id, &model_id, SwitchStage::Failed, started, Some(e.clone()));
+            log_operation_error!("Switch model", &e, model_id = %model_id);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_served_name_strips_organization() {
+        assert_eq!(served_name("OpenVINO/Qwen3-8B-int4-ov"), "Qwen3-8B-int4-ov");
+        assert_eq!(served_name("Qwen3-8B-int4-ov"), "Qwen3-8B-int4-ov");
+    }
+}
@@ -1,6 +1,9 @@
-use crate::paths;
+use crate::{ constants, path_policy, paths, permissions, settings };
+use crate::path_policy::Access;
+use crate::state::AppState;
 use std::fs;
 use std::path::PathBuf;
+use tauri::{ State, Window };
 
 /// Check if model files exist in a directory
 #[allow(dead_code)]
@@ -35,15 +38,52 @@ pub fn get_models_dir_from_option(download_path: Option<String>) -> Result<PathB
     }
 }
 
-/// Normalize model ID to include OpenVINO organization prefix
+/// Normalize model ID to `org/name`; bare names belong to the OpenVINO organization
 pub fn normalize_model_id(model_id: &str) -> String {
-    if model_id.starts_with("OpenVINO/") {
+    if model_id.contains('/') {
         model_id.to_string()
     } else {
-        format!("OpenVINO/{}", model_id)
+        format!("{}/{}", constants::OPENVINO_ORG, model_id)
     }
 }
 
+/// Imported from a local folder rather than downloaded from Hugging Face
+pub fn is_local_model(model_id: &str) -> bool {
+    model_id.split('/').next().is_some_and(|org| org == constants::LOCAL_MODEL_ORG)
+}
+
+/// Organizations models may be searched and downloaded from (`huggingface.allowed_orgs`)
+pub fn allowed_orgs() -> Vec<String> {
+    let orgs: Vec<String> = settings::current().huggingface.allowed_orgs
+        .into_iter()
+        .map(|org| org.trim().to_string())
+        .filter(|org| !org.is_empty())
+        .collect();
+    if orgs.is_empty() {
+        vec![constants::OPENVINO_ORG.to_string()]
+    } else {
+        orgs
+    }
+}
+
+fn org_in(model_id: &str, orgs: &[String]) -> bool {
+    let org = normalize_model_id(model_id).split('/').next().unwrap_or_default().to_string();
+    orgs.iter().any(|allowed| allowed.eq_ignore_ascii_case(&org))
+}
+
+/// Fail unless the model's organization is allowed
+pub fn check_allowed_org(model_id: &str) -> Result<(), String> {
+    let orgs = allowed_orgs();
+    if org_in(model_id, &orgs) {
+        return Ok(());
+    }
+    Err(format!(
+        "Model {} is not from an allowed organization ({}). Add its organization to huggingface.allowed_orgs in settings.",
+        model_id,
+        orgs.join(", ")
+    ))
+}
+
 #[tauri::command]
 pub async fn check_downloaded_models(_download_path: Option<String>) -> Result<Vec<String>, String> {
     use crate::huggingface::get_all_model_metadata;
@@ -136,26 +176,27 @@ pub async fn open_model_folder(
 
 #[tauri::command]
 pub async fn list_directory_names(path: String) -> Result<Vec<String>, String> {
-    let dir_path = PathBuf::from(&path);
+    let dir_path = path_policy::check_str(&path, Access::Read)?;
 
     if !dir_path.exists() {
         return Ok(Vec::new());
     }
 
     let mut names = Vec::new();
+    let mut orgs = allowed_orgs();
+    orgs.push(constants::LOCAL_MODEL_ORG.to_string());
 
     for entry in fs::read_dir(&dir_path).map_err(|e| format!("Failed to read directory: {}", e))?.flatten() {
         let entry_path = entry.path();
         if entry_path.is_dir() {
-            // Check for nested OpenVINO organization structure
+            // Models are stored as <org>/<name>
             if let Some(dir_name) = entry.file_name().to_str() {
-                if dir_name == "OpenVINO" {
-                    // List models inside OpenVINO directory
+                if orgs.iter().any(|org| org.eq_ignore_ascii_case(dir_name)) {
                     if let Ok(org_entries) = fs::read_dir(&entry_path) {
                         for org_entry in org_entries.flatten() {
                             if org_entry.path().is_dir() {
                                 if let Some(model_name) = org_entry.file_name().to_str() {
-                                    names.push(format!("OpenVINO/{}", model_name));
+                                    names.push(format!("{}/{}", dir_name, model_name));
                                 }
                             }
                         }
@@ -169,8 +210,14 @@ pub async fn list_directory_names(path: String) -> Result<Vec<String>, String> {
 }
 
 #[tauri::command]
-pub async fn delete_directory(path: String) -> Result<String, String> {
-    let dir_path = PathBuf::from(&path);
+pub async fn delete_directory(
+    state: State<'_, AppState>,
+    window: Window,
+    path: String,
+    confirmation_token: Option<String>
+) -> Result<String, String> {
+    permissions::authorize(&state, window.label(), "delete_directory", confirmation_token.as_deref())?;
+    let dir_path = path_policy::check_str(&path, Access::Delete)?;
 
     if !dir_path.exists() {
         return Err(format!("Directory does not exist: {}", path));
@@ -190,5 +237,14 @@ mod tests {
     fn test_normalize_model_id() {
         assert_eq!(normalize_model_id("model-name"), "OpenVINO/model-name");
         assert_eq!(normalize_model_id("OpenVINO/model-name"), "OpenVINO/model-name");
+        assert_eq!(normalize_model_id("my-org/model-name"), "my-org/model-name");
+    }
+
+    #[test]
+    fn test_org_allow_list() {
+        let orgs = vec!["OpenVINO".to_string(), "my-org".to_string()];
+        assert!(org_in("model-name", &orgs));
+        assert!(org_in("My-Org/model-name", &orgs));
+        assert!(!org_in("someone/model-name", &orgs));
     }
 }
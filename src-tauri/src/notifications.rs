@@ -0,0 +1,73 @@
+//! Notifications the backend has shown since launch.
+//!
+//! Native notifications vanish once dismissed and a window opened later never
+//! sees them. Each one is also recorded here (in memory, capped) with a read
+//! flag, for the UI to list and for `get_app_state_snapshot`.
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager, State };
+
+use crate::state::AppState;
+
+const MAX_NOTIFICATIONS: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppNotification {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    /// What raised it, e.g. "task"
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    pub read: bool,
+}
+
+/// Record a notification and tell open windows about it
+pub fn record(app: &AppHandle, title: &str, body: &str, source: &str) {
+    let notification = AppNotification {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: title.to_string(),
+        body: body.to_string(),
+        source: source.to_string(),
+        created_at: Utc::now(),
+        read: false,
+    };
+
+    {
+        let state = app.state::<AppState>();
+        let mut notifications = state.notifications.lock();
+        notifications.push(notification.clone());
+        let overflow = notifications.len().saturating_sub(MAX_NOTIFICATIONS);
+        notifications.drain(..overflow);
+    }
+
+    let _ = app.emit("notification-added", notification);
+}
+
+pub fn unread(state: &AppState) -> Vec<AppNotification> {
+    state.notifications.lock().iter().filter(|n| !n.read).cloned().collect()
+}
+
+#[tauri::command]
+pub async fn get_notifications(state: State<'_, AppState>, unread_only: Option<bool>) -> Result<Vec<AppNotification>, String> {
+    if unread_only.unwrap_or(false) {
+        return Ok(unread(&state));
+    }
+    Ok(state.notifications.lock().clone())
+}
+
+/// Mark the given notifications read, or all of them when `ids` is absent
+#[tauri::command]
+pub async fn mark_notifications_read(state: State<'_, AppState>, ids: Option<Vec<String>>) -> Result<(), String> {
+    for notification in state.notifications.lock().iter_mut() {
+        let selected = match &ids {
+            Some(ids) => ids.contains(&notification.id),
+            None => true,
+        };
+        if selected {
+            notification.read = true;
+        }
+    }
+    Ok(())
+}
@@ -1,15 +1,82 @@
 use std::fs;
-use std::io::{ Write, Read };
 use std::path::PathBuf;
-use std::process::{ Command, Stdio, Child };
-use std::sync::{ Arc, Mutex };
+use std::process::{ Command, Stdio };
+use std::sync::atomic::{ AtomicU16, Ordering };
 use zip::ZipArchive;
 use serde_json::{ json, Value };
 use serde::{ Deserialize, Serialize };
-use tauri::AppHandle;
+use tauri::{ AppHandle, Emitter, Manager };
 use tracing::{ info, warn, error, debug };
 
-use crate::{ paths, constants };
+use crate::{ paths, constants, storage, model_diagnostics, ovms_backups, ovms_logs };
+use crate::state::AppState;
+
+/// REST port of the OVMS started by this app; 0 until it is started
+static ACTIVE_REST_PORT: AtomicU16 = AtomicU16::new(0);
+static ACTIVE_GRPC_PORT: AtomicU16 = AtomicU16::new(0);
+
+/// REST port OVMS is reached on: the one it was started with, else the configured one
+pub fn rest_port() -> u16 {
+    match ACTIVE_REST_PORT.load(Ordering::Relaxed) {
+        0 => crate::settings::current().ovms.rest_port,
+        port => port,
+    }
+}
+
+/// Base URL of the OVMS REST API, e.g. `http://localhost:1114`
+pub fn api_base() -> String {
+    format!("http://localhost:{}", rest_port())
+}
+
+/// Base URL of the OpenAI-compatible endpoints
+pub fn openai_api_base() -> String {
+    format!("{}{}", api_base(), constants::OVMS_OPENAI_PATH)
+}
+
+fn port_is_free(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// `preferred` if nothing listens on it; otherwise, with `auto_port`, a port
+/// the OS reports free. `taken` are ports already handed out for this launch.
+fn choose_port(preferred: u16, auto_port: bool, taken: &[u16]) -> Result<u16, String> {
+    if preferred != 0 && !taken.contains(&preferred) && port_is_free(preferred) {
+        return Ok(preferred);
+    }
+    if !auto_port {
+        return Err(format!(
+            "Port {} is already in use. Free it or choose another OVMS port in settings.",
+            preferred
+        ));
+    }
+    for _ in 0..10 {
+        let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| format!("Failed to find a free port: {}", e))?
+            .port();
+        if !taken.contains(&port) {
+            return Ok(port);
+        }
+    }
+    Err("Failed to find a free port".to_string())
+}
+
+/// Current OVMS endpoints, for display
+#[derive(Debug, Clone, Serialize)]
+pub struct OvmsEndpoint {
+    pub rest_port: u16,
+    pub grpc_port: Option<u16>,
+    pub api_base: String,
+}
+
+#[tauri::command]
+pub async fn get_ovms_endpoint() -> Result<OvmsEndpoint, String> {
+    let grpc_port = match ACTIVE_GRPC_PORT.load(Ordering::Relaxed) {
+        0 => crate::settings::current().ovms.grpc_port,
+        port => Some(port),
+    };
+    Ok(OvmsEndpoint { rest_port: rest_port(), grpc_port, api_base: api_base() })
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OvmsStatus {
@@ -38,8 +105,166 @@ struct ModelInfo {
     model_version_status: Vec<ModelVersionStatus>,
 }
 
-// Global OVMS process management
-static OVMS_PROCESS: std::sync::OnceLock<Arc<Mutex<Option<Child>>>> = std::sync::OnceLock::new();
+/// Latest version state of one model, as OVMS reports it in `/v1/models/{name}`
+/// and `/v1/config`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelLoadState {
+    /// START, LOADING, AVAILABLE, UNLOADING or END
+    pub state: String,
+    pub error_code: String,
+    pub error_message: String,
+}
+
+impl ModelLoadState {
+    pub fn is_available(&self) -> bool {
+        self.state == "AVAILABLE"
+    }
+
+    /// OVMS keeps a model in END with an error code when loading it failed
+    pub fn is_failed(&self) -> bool {
+        self.state == "END" && !self.error_code.eq_ignore_ascii_case("OK")
+    }
+}
+
+fn parse_model_state(config: &Value, model_name: &str) -> Option<ModelLoadState> {
+    parse_model_info(config.get(model_name)?)
+}
+
+fn parse_model_info(info: &Value) -> Option<ModelLoadState> {
+    let info: ModelInfo = serde_json::from_value(info.clone()).ok()?;
+    let latest = info.model_version_status
+        .into_iter()
+        .max_by_key(|status| status.version.parse::<u64>().unwrap_or(0))?;
+    Some(ModelLoadState {
+        state: latest.state,
+        error_code: latest.status.error_code,
+        error_message: latest.status.error_message,
+    })
+}
+
+/// Current state of `model_name`; `None` if OVMS does not know the model.
+/// Asks for the model alone, falling back to the full config for graphs the
+/// per-model status endpoint does not report.
+pub(crate) async fn get_model_state(model_name: &str) -> Result<Option<ModelLoadState>, String> {
+    let client = crate::http::client()?;
+    let response = client
+        .get(format!("{}/v1/models/{}", api_base(), model_name))
+        .send().await
+        .map_err(|e| format!("Failed to connect to OVMS server: {}", e))?;
+
+    if response.status().is_success() {
+        let info: Value = response.json().await
+            .map_err(|e| format!("Failed to parse OVMS response JSON: {}", e))?;
+        if let Some(state) = parse_model_info(&info) {
+            return Ok(Some(state));
+        }
+    }
+
+    let response = client
+        .get(format!("{}/v1/config", api_base()))
+        .send().await
+        .map_err(|e| format!("Failed to connect to OVMS server: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("OVMS status check failed with status: {}", response.status()));
+    }
+
+    let config: Value = response.json().await
+        .map_err(|e| format!("Failed to parse OVMS response JSON: {}", e))?;
+    Ok(parse_model_state(&config, model_name))
+}
+
+/// Payload of `model-load-progress` events
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelLoadProgress {
+    pub model_name: String,
+    /// LOADING until the model is AVAILABLE or FAILED (error, or timed out)
+    pub phase: &'static str,
+    /// OVMS state, or `UNKNOWN` while OVMS does not list the model yet
+    pub state: String,
+    pub elapsed_ms: u64,
+    /// Last error reported by OVMS or by the status poll itself
+    pub last_error: Option<String>,
+    pub finished: bool,
+}
+
+/// Poll OVMS until `model_name` is AVAILABLE, failing early if its load failed.
+/// `on_progress` is called on every state change and about every two seconds.
+pub(crate) async fn wait_for_model(
+    model_name: &str,
+    timeout: std::time::Duration,
+    mut on_progress: impl FnMut(ModelLoadProgress)
+) -> Result<(), String> {
+    const HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(2);
+
+    let started = std::time::Instant::now();
+    let mut last_state = String::new();
+    let mut last_error: Option<String> = None;
+    let mut last_emit: Option<std::time::Instant> = None;
+
+    loop {
+        let poll = get_model_state(model_name).await;
+        let state = match &poll {
+            Ok(Some(state)) => state.state.clone(),
+            Ok(None) | Err(_) => "UNKNOWN".to_string(),
+        };
+        match &poll {
+            Ok(Some(state)) if !state.error_code.eq_ignore_ascii_case("OK") && !state.error_code.is_empty() => {
+                last_error = Some(format!("{}: {}", state.error_code, state.error_message));
+            }
+            Err(e) => last_error = Some(e.clone()),
+            _ => {}
+        }
+
+        let available = matches!(&poll, Ok(Some(state)) if state.is_available());
+        let failed = matches!(&poll, Ok(Some(state)) if state.is_failed());
+        let finished = available || failed;
+
+        let timed_out = !finished && started.elapsed() >= timeout;
+        if timed_out {
+            last_error = Some(format!("Timed out after {}s waiting for '{}' to load", timeout.as_secs(), model_name));
+        }
+        let phase = if available {
+            "AVAILABLE"
+        } else if failed || timed_out {
+            "FAILED"
+        } else {
+            "LOADING"
+        };
+
+        if finished || timed_out || state != last_state || !last_emit.is_some_and(|at| at.elapsed() < HEARTBEAT) {
+            on_progress(ModelLoadProgress {
+                model_name: model_name.to_string(),
+                phase,
+                state: state.clone(),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                last_error: last_error.clone(),
+                finished: finished || timed_out,
+            });
+            last_emit = Some(std::time::Instant::now());
+        }
+        last_state = state;
+
+        if available {
+            return Ok(());
+        }
+        if failed {
+            return Err(format!(
+                "OVMS failed to load '{}': {}",
+                model_name,
+                last_error.unwrap_or_else(|| "unknown error".to_string())
+            ));
+        }
+        if let Err(e) = &poll {
+            debug!(error = %e, "Model status poll failed");
+        }
+
+        if timed_out {
+            return Err(last_error.unwrap_or_default());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(constants::MODEL_STATUS_POLL_MS)).await;
+    }
+}
 
 // Get loaded models from models_config.json
 #[tauri::command]
@@ -47,12 +272,10 @@ pub async fn get_loaded_models(app_handle: AppHandle) -> Result<Vec<String>, Str
     let config_path = paths::get_ovms_config_path(Some(&app_handle))
         .map_err(|e| e.to_string())?;
     
-    if !config_path.exists() {
+    let Some(config_str) = storage::read_string(&config_path).await
+        .map_err(|e| format!("Failed to read config file: {}", e))? else {
         return Ok(Vec::new());
-    }
-    
-    let config_str = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    };
     
     let config: Value = serde_json::from_str(&config_str)
         .map_err(|e| format!("Failed to parse config file: {}", e))?;
@@ -95,7 +318,7 @@ pub fn create_minimal_test_config(config_path: &PathBuf) -> Result<(), String> {
     Ok(())
 }
 
-pub fn validate_ovms_config(config_path: &PathBuf) -> Result<(), String> {
+pub async fn validate_ovms_config(config_path: &PathBuf) -> Result<(), String> {
     tracing::debug!(config_path = %config_path.display(), "Validating OVMS configuration");
     
     if !config_path.exists() {
@@ -107,8 +330,8 @@ pub fn validate_ovms_config(config_path: &PathBuf) -> Result<(), String> {
     }
 
     // Read and validate JSON structure
-    let config_str = fs
-        ::read_to_string(config_path)
+    let config_str = tokio::fs
+        ::read_to_string(config_path).await
         .map_err(|e| format!("Failed to read config file: {}", e))?;
 
     let config: Value = serde_json
@@ -184,14 +407,10 @@ pub async fn download_ovms(app_handle: AppHandle) -> Result<String, String> {
     }
 
     // Download the file with retry logic and better error handling
-    let client = reqwest::Client
-        ::builder()
-        .user_agent(constants::USER_AGENT)
-        .timeout(std::time::Duration::from_secs(constants::DOWNLOAD_TIMEOUT_SECS))
-        .build()
+    let client = crate::http::client_with_timeout(std::time::Duration::from_secs(constants::DOWNLOAD_TIMEOUT_SECS))
         .map_err(|e| {
             log_operation_error!("OVMS download setup", &e);
-            format!("Failed to create HTTP client: {}", e)
+            e
         })?;
 
     log_progress!("Starting OVMS download", url = %constants::OVMS_DOWNLOAD_URL);
@@ -199,7 +418,7 @@ pub async fn download_ovms(app_handle: AppHandle) -> Result<String, String> {
     let mut retries = constants::MAX_DOWNLOAD_RETRIES;
 
     while retries > 0 {
-        match download_and_validate(&client, &zip_path).await {
+        match download_and_validate(&app_handle, &client, &zip_path).await {
             Ok(_bytes) => {
                 break;
             }
@@ -228,8 +447,23 @@ pub async fn download_ovms(app_handle: AppHandle) -> Result<String, String> {
 
     log_progress!("OVMS download completed, extracting...");
 
-    // Extract the zip file to ovms directory
-    extract_ovms(&zip_path, &ovms_dir)?;
+    // Extract the zip file to ovms directory, off the async runtime
+    let extract_handle = app_handle.clone();
+    let (extract_zip, extract_dir) = (zip_path.clone(), ovms_dir.clone());
+    tokio::task::spawn_blocking(move || {
+        let mut last_emit = std::time::Instant::now();
+        extract_ovms(&extract_zip, &extract_dir, |bytes, total_bytes| {
+            if last_emit.elapsed().as_millis() > constants::DOWNLOAD_PROGRESS_INTERVAL_MS || bytes == total_bytes {
+                last_emit = std::time::Instant::now();
+                let _ = extract_handle.emit("ovms-download-progress", OvmsDownloadProgress {
+                    stage: "extracting",
+                    bytes,
+                    total_bytes: Some(total_bytes),
+                });
+            }
+        })
+    }).await
+        .map_err(|e| format!("OVMS extraction task failed: {}", e))??;
 
     // Clean up the zip file after successful extraction
     if zip_path.exists() {
@@ -247,10 +481,25 @@ pub async fn download_ovms(app_handle: AppHandle) -> Result<String, String> {
     Ok("OVMS downloaded and extracted successfully".to_string())
 }
 
+/// Payload of `ovms-download-progress` events
+#[derive(Debug, Clone, Serialize)]
+pub struct OvmsDownloadProgress {
+    /// `downloading` or `extracting`
+    pub stage: &'static str,
+    /// Archive bytes received, or uncompressed bytes written while extracting
+    pub bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+/// Stream the archive to `zip_path`, emitting progress, then check it is a usable zip
 async fn download_and_validate(
+    app_handle: &AppHandle,
     client: &reqwest::Client,
     zip_path: &PathBuf
-) -> Result<Vec<u8>, String> {
+) -> Result<u64, String> {
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
     let response = client
         .get(constants::OVMS_DOWNLOAD_URL)
         .send().await
@@ -268,54 +517,61 @@ async fn download_and_validate(
         tracing::debug!(size_bytes = length, size_mb = size_mb, "Download size");
     }
 
-    let bytes = response
-        .bytes().await
-        .map_err(|e| format!("Failed to read response bytes: {}", e))?;
+    let mut file = tokio::fs::File::create(zip_path).await
+        .map_err(|e| format!("Failed to create zip file: {}", e))?;
+    let mut downloaded = 0u64;
+    let mut last_emit = std::time::Instant::now();
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to read response bytes: {}", e))?;
+        file.write_all(&chunk).await.map_err(|e| format!("Failed to write zip file: {}", e))?;
+        downloaded += chunk.len() as u64;
+
+        if last_emit.elapsed().as_millis() > constants::DOWNLOAD_PROGRESS_INTERVAL_MS || Some(downloaded) == expected_length {
+            last_emit = std::time::Instant::now();
+            let _ = app_handle.emit("ovms-download-progress", OvmsDownloadProgress {
+                stage: "downloading",
+                bytes: downloaded,
+                total_bytes: expected_length,
+            });
+        }
+    }
+    file.flush().await.map_err(|e| format!("Failed to write zip file: {}", e))?;
+    drop(file);
 
     // Validate content length if provided
     if let Some(expected) = expected_length {
-        if (bytes.len() as u64) != expected {
+        if downloaded != expected {
             return Err(
                 format!(
                     "Downloaded size mismatch: expected {} bytes, got {} bytes",
                     expected,
-                    bytes.len()
+                    downloaded
                 )
             );
         }
     }
 
-    // Validate that it's a valid ZIP file before writing
-    validate_zip_bytes(&bytes)?;
-
-    info!("Download validation passed, writing to file...");
-
-    // Write to file
-    let mut file = fs::File
-        ::create(zip_path)
-        .map_err(|e| format!("Failed to create zip file: {}", e))?;
-
-    file.write_all(&bytes).map_err(|e| format!("Failed to write zip file: {}", e))?;
-
-    Ok(bytes.into())
+    validate_zip_file(zip_path)?;
+    info!(size_bytes = downloaded, "Download validation passed");
+    Ok(downloaded)
 }
 
-fn validate_zip_bytes(bytes: &[u8]) -> Result<(), String> {
-    use std::io::Cursor;
+fn validate_zip_file(zip_path: &PathBuf) -> Result<(), String> {
+    use std::io::Read;
 
-    // Check if it starts with ZIP magic number
-    if bytes.len() < 4 {
-        return Err("File too small to be a valid ZIP".to_string());
-    }
+    let mut file = fs::File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
 
     // ZIP files start with "PK" (0x504B)
-    if &bytes[0..2] != b"PK" {
+    let mut signature = [0u8; 4];
+    file.read_exact(&mut signature).map_err(|_| "File too small to be a valid ZIP".to_string())?;
+    if &signature[0..2] != b"PK" {
         return Err("Invalid ZIP file signature".to_string());
     }
 
-    // Try to open as ZIP archive to validate structure
-    let cursor = Cursor::new(bytes);
-    match zip::ZipArchive::new(cursor) {
+    // Opening the archive reads only its central directory, not the entries
+    match ZipArchive::new(file) {
         Ok(archive) => {
             if archive.len() == 0 {
                 return Err("ZIP file is empty".to_string());
@@ -327,15 +583,28 @@ fn validate_zip_bytes(bytes: &[u8]) -> Result<(), String> {
     }
 }
 
-pub fn extract_ovms(zip_path: &PathBuf, extract_to: &PathBuf) -> Result<(), String> {
+/// Extract the archive entry by entry straight from disk; `on_progress`
+/// gets the uncompressed bytes written so far and the total
+pub fn extract_ovms(
+    zip_path: &PathBuf,
+    extract_to: &PathBuf,
+    mut on_progress: impl FnMut(u64, u64)
+) -> Result<(), String> {
     let file = fs::File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
 
-    let mut archive = ZipArchive::new(file).map_err(|e|
+    let mut archive = ZipArchive::new(std::io::BufReader::new(file)).map_err(|e|
         format!("Failed to read zip archive: {}", e)
     )?;
 
-    info!(file_count = archive.len(), "Extracting files from archive");
+    let mut total_bytes = 0u64;
+    for i in 0..archive.len() {
+        if let Ok(file) = archive.by_index_raw(i) {
+            total_bytes += file.size();
+        }
+    }
+    info!(file_count = archive.len(), total_bytes = total_bytes, "Extracting files from archive");
 
+    let mut extracted_bytes = 0u64;
     for i in 0..archive.len() {
         let mut file = archive
             .by_index(i)
@@ -379,9 +648,10 @@ pub fn extract_ovms(zip_path: &PathBuf, extract_to: &PathBuf) -> Result<(), Stri
             ::create(&outpath)
             .map_err(|e| format!("Failed to create output file {}: {}", outpath.display(), e))?;
 
-        std::io
+        extracted_bytes += std::io
             ::copy(&mut file, &mut outfile)
             .map_err(|e| format!("Failed to extract file {}: {}", outpath.display(), e))?;
+        on_progress(extracted_bytes, total_bytes);
 
         debug!(output_path = %outpath.display(), "File extracted");
     }
@@ -408,7 +678,8 @@ pub async fn create_ovms_config(
 
     let config_path = paths::get_ovms_config_path(Some(&app_handle))
         .map_err(|e| e.to_string())?;
-    fs::write(&config_path, config_str).map_err(|e| format!("Failed to write config file: {}", e))?;
+    storage::write_string(&config_path, &config_str).await
+        .map_err(|e| format!("Failed to write config file: {}", e))?;
 
     Ok("OVMS configuration file created successfully".to_string())
 }
@@ -425,29 +696,23 @@ pub async fn update_ovms_config(
         .map_err(|e| e.to_string())?;
 
     // Read existing config or create new one
-    let mut config: Value = if config_path.exists() {
-        let config_str = fs
-            ::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read config file: {}", e))?;
-        serde_json
+    let existing = storage::read_string(&config_path).await
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    let mut config: Value = match existing {
+        Some(config_str) => serde_json
             ::from_str(&config_str)
-            .map_err(|e| format!("Failed to parse config file: {}", e))?
-    } else {
-        json!({
+            .map_err(|e| format!("Failed to parse config file: {}", e))?,
+        None => json!({
             "mediapipe_config_list": [],
             "model_config_list": []
-        })
+        }),
     };
 
     // Normalize the model_path to use forward slashes for OVMS
     let normalized_model_path = model_path.replace('\\', "/");
     
     // Extract model ID from model_name (e.g., "Qwen2.5-VL-7B-Instruct-int4-ov" from full path)
-    let model_id = if model_name.starts_with("OpenVINO/") {
-        model_name.clone()
-    } else {
-        format!("OpenVINO/{}", model_name)
-    };
+    let model_id = crate::huggingface::resolve_model_id(&model_name).await;
     
     // Get the model type from metadata
     let model_type = get_model_type(&model_id).await.ok().flatten();
@@ -461,15 +726,11 @@ pub async fn update_ovms_config(
     if let Some(model_list) = config["mediapipe_config_list"].as_array_mut() {
         // Build a map of existing models by type
         let mut models_by_type: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-        let mut rag_models: Vec<usize> = Vec::new();
+        let mut rag_models: std::collections::HashSet<String> = std::collections::HashSet::new();
         
         for (index, model) in model_list.iter().enumerate() {
             if let Some(name) = model["name"].as_str() {
-                let full_model_id = if name.starts_with("OpenVINO/") {
-                    name.to_string()
-                } else {
-                    format!("OpenVINO/{}", name)
-                };
+                let full_model_id = crate::huggingface::resolve_model_id(name).await;
                 
                 // Get model type for this model
                 if let Ok(Some(mtype)) = get_model_type(&full_model_id).await {
@@ -477,7 +738,7 @@ pub async fn update_ovms_config(
                     
                     // Track RAG models separately (embedding and reranker)
                     if matches!(mtype, ModelType::Embedding | ModelType::Reranker) {
-                        rag_models.push(index);
+                        rag_models.insert(name.to_string());
                     } else {
                         // For non-RAG models, track by type
                         models_by_type.insert(type_str, index);
@@ -490,9 +751,10 @@ pub async fn update_ovms_config(
         if let Some(new_model_type) = model_type {
             let type_str = new_model_type.as_str().to_string();
             
-            // Check if this is a RAG model
-            if matches!(new_model_type, ModelType::Embedding | ModelType::Reranker) {
-                // For RAG models, just update or add them without replacing
+            // RAG models and text models can be loaded side by side; chat
+            // requests pick a text model by name (see `route_model`)
+            if matches!(new_model_type, ModelType::Embedding | ModelType::Reranker | ModelType::Text) {
+                // Just update or add them without replacing
                 let mut found = false;
                 for i in 0..model_list.len() {
                     if let Some(name) = model_list[i]["name"].as_str() {
@@ -566,31 +828,48 @@ pub async fn update_ovms_config(
                 "No type metadata found for model, added without type enforcement"
             );
         }
+
+        if matches!(model_type, Some(ModelType::Embedding | ModelType::Reranker)) {
+            rag_models.insert(model_name.clone());
+        }
+        if crate::settings::current().ovms.rag_models.load_first {
+            order_rag_models_first(model_list, &rag_models);
+        }
     }
 
     let config_str = serde_json
         ::to_string_pretty(&config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
-    fs::write(&config_path, config_str).map_err(|e| format!("Failed to write config file: {}", e))?;
+    ovms_backups::backup_current(&config_path).await?;
+    storage::write_string(&config_path, &config_str).await
+        .map_err(|e| format!("Failed to write config file: {}", e))?;
 
     Ok("OVMS configuration updated successfully".to_string())
 }
 
+/// Move the RAG models ahead of the others, keeping the order within each
+/// group, so OVMS loads them before a large LLM claims the memory
+fn order_rag_models_first(model_list: &mut [Value], rag_models: &std::collections::HashSet<String>) {
+    model_list.sort_by_key(|entry| !entry["name"].as_str().is_some_and(|name| rag_models.contains(name)));
+}
+
 #[tauri::command]
 pub async fn reload_ovms_config() -> Result<String, String> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client()?;
 
     let response = client
-        .post("http://localhost:1114/v1/config/reload")
+        .post(format!("{}/v1/config/reload", api_base()))
         .send().await
         .map_err(|e| format!("Failed to send reload request: {}", e))?;
 
-    if response.status().is_success() {
-        let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+    if status.is_success() {
         Ok(format!("Config reloaded successfully: {}", body))
     } else {
-        Err(format!("Config reload failed with status: {}", response.status()))
+        // The body carries OVMS's reason, which load diagnostics classify
+        Err(format!("Config reload failed with status {}: {}", status, body.trim()))
     }
 }
 
@@ -717,25 +996,44 @@ pub async fn start_ovms_server(app_handle: AppHandle) -> Result<String, String>
         .map_err(|e| e.to_string())?;
 
     // Validate config
-    validate_ovms_config(&config_path)?;
+    validate_ovms_config(&config_path).await?;
 
     log_progress!("Launching OVMS process", 
         exe = %ovms_exe.display(),
         config = %config_path.display()
     );
 
+    // Another program may hold the configured ports
+    let settings = crate::settings::current().ovms;
+    let rest_port = choose_port(settings.rest_port, settings.auto_port, &[])?;
+    let grpc_port = match settings.grpc_port {
+        Some(port) => Some(choose_port(port, settings.auto_port, &[rest_port])?),
+        None => None,
+    };
+    if rest_port != settings.rest_port {
+        log_warning!("Configured OVMS port is in use, using another",
+            configured = settings.rest_port,
+            port = rest_port
+        );
+    }
+
     // Start OVMS process
     let mut cmd = Command::new(&ovms_exe);
     cmd.args([
         "--config_path",
         &config_path.to_string_lossy(),
         "--rest_port",
-        "1114",
-        "--log_level",
-        "INFO",
+        &rest_port.to_string(),
     ])
+        .args(runtime_args(&settings.runtime))
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
+    if let Some(port) = grpc_port {
+        cmd.args(["--port", &port.to_string()]);
+    }
+    if settings.metrics {
+        cmd.arg("--metrics_enable");
+    }
 
     // Hide console window on Windows
     #[cfg(target_os = "windows")]
@@ -748,70 +1046,154 @@ pub async fn start_ovms_server(app_handle: AppHandle) -> Result<String, String>
         log_operation_error!("OVMS process spawn", &e);
         format!("Failed to start OVMS: {}", e)
     })?;
+    let output = ovms_logs::capture(&app_handle, &mut child);
+
+    // Wait until the REST API answers; big models on a GPU can take minutes
+    let timeout = std::time::Duration::from_secs(settings.startup_timeout_secs.max(1));
+    let probe_url = format!("http://localhost:{}/v1/config", rest_port);
+    let probe = crate::http::client_with_timeout(std::time::Duration::from_secs(2))?;
+    let started = std::time::Instant::now();
+    tracing::debug!(timeout_secs = timeout.as_secs(), "Waiting for OVMS to initialize...");
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // Process exited
+                let (stdout_output, stderr_output) = output.finish();
+
+                let error_msg = format!(
+                    "OVMS exited with status: {}\nSTDOUT: {}\nSTDERR: {}\nConfig: {}\nExecutable: {}",
+                    status,
+                    stdout_output.trim(),
+                    stderr_output.trim(),
+                    config_path.display(),
+                    ovms_exe.display()
+                );
 
-    // Wait a moment for server to start
-    tracing::debug!("Waiting for OVMS to initialize...");
-    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
-
-    // Check if process is still running before storing it
-    match child.try_wait() {
-        Ok(Some(status)) => {
-            // Process exited
-            let mut stderr_output = String::new();
-            let mut stdout_output = String::new();
-
-            if let Some(mut stderr) = child.stderr.take() {
-                stderr.read_to_string(&mut stderr_output).unwrap_or_default();
+                log_operation_error!("OVMS startup", &error_msg,
+                    exit_status = %status,
+                    config = %config_path.display(),
+                    executable = %ovms_exe.display()
+                );
+                return Err(error_msg);
             }
-
-            if let Some(mut stdout) = child.stdout.take() {
-                stdout.read_to_string(&mut stdout_output).unwrap_or_default();
+            Ok(None) => {}
+            Err(e) => {
+                log_operation_error!("OVMS status check", &e);
+                return Err(format!("Failed to check OVMS status: {}", e));
             }
+        }
 
-            let error_msg = format!(
-                "OVMS exited with status: {}\nSTDOUT: {}\nSTDERR: {}\nConfig: {}\nExecutable: {}",
-                status,
-                stdout_output.trim(),
-                stderr_output.trim(),
-                config_path.display(),
-                ovms_exe.display()
-            );
+        let ready = probe.get(&probe_url).send().await.is_ok_and(|response| response.status().is_success());
+        let _ = app_handle.emit("ovms-starting", OvmsStartupProgress {
+            rest_port,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            timeout_ms: timeout.as_millis() as u64,
+            ready,
+        });
+        if ready {
+            break;
+        }
 
-            log_operation_error!("OVMS startup", &error_msg,
-                exit_status = %status,
-                config = %config_path.display(),
-                executable = %ovms_exe.display()
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            let (_, stderr_output) = output.finish();
+            let error_msg = format!(
+                "OVMS did not answer on port {} within {}s (raise ovms.startup_timeout_secs for large models)\nSTDERR: {}",
+                rest_port,
+                timeout.as_secs(),
+                stderr_output.trim()
             );
-            Err(error_msg)
+            log_operation_error!("OVMS startup", &error_msg, timeout_secs = timeout.as_secs());
+            return Err(error_msg);
         }
-        Ok(None) => {
-            // Process is still running, store it globally
-            // Scope the mutex guard properly to avoid Send issues
-            {
-                let process_mutex = OVMS_PROCESS.get_or_init(|| Arc::new(Mutex::new(None)));
-                let mut process_guard = process_mutex.lock().unwrap();
-                *process_guard = Some(child);
-            } // Guard is dropped here
-
-            log_operation_success!("OVMS server started on port 1114");
-
-            Ok("OVMS server started successfully.".to_string())
+        tokio::time::sleep(std::time::Duration::from_millis(constants::OVMS_STARTUP_POLL_MS)).await;
+    }
+
+    // Keep the process so it can be stopped on exit
+    *app_handle.state::<AppState>().ovms_process.lock() = Some(child);
+    ACTIVE_REST_PORT.store(rest_port, Ordering::Relaxed);
+    ACTIVE_GRPC_PORT.store(grpc_port.unwrap_or(0), Ordering::Relaxed);
+
+    log_operation_success!("OVMS server started",
+        rest_port = rest_port,
+        grpc_port = ?grpc_port,
+        startup_ms = started.elapsed().as_millis() as u64
+    );
+
+    Ok("OVMS server started successfully.".to_string())
+}
+
+/// Payload of `ovms-starting` events, sent while waiting for OVMS to answer
+#[derive(Debug, Clone, Serialize)]
+pub struct OvmsStartupProgress {
+    pub rest_port: u16,
+    pub elapsed_ms: u64,
+    pub timeout_ms: u64,
+    pub ready: bool,
+}
+
+const OVMS_LOG_LEVELS: &[&str] = &["TRACE", "DEBUG", "INFO", "WARNING", "ERROR"];
+
+/// OVMS flags for `params`, after the config path and ports
+fn runtime_args(params: &crate::settings::OvmsRuntimeParams) -> Vec<String> {
+    let mut args = vec!["--log_level".to_string(), params.log_level.clone()];
+    let optional = [
+        ("--grpc_workers", params.grpc_workers.map(|v| v.to_string())),
+        ("--rest_workers", params.rest_workers.map(|v| v.to_string())),
+        ("--cache_dir", params.cache_dir.clone().filter(|dir| !dir.trim().is_empty())),
+        ("--file_system_poll_wait_seconds", params.file_system_poll_wait_seconds.map(|v| v.to_string())),
+    ];
+    for (flag, value) in optional {
+        if let Some(value) = value {
+            args.push(flag.to_string());
+            args.push(value);
         }
-        Err(e) => { 
-            log_operation_error!("OVMS status check", &e);
-            Err(format!("Failed to check OVMS status: {}", e)) 
+    }
+    args
+}
+
+fn validate_runtime_params(params: &mut crate::settings::OvmsRuntimeParams) -> Result<(), String> {
+    params.log_level = params.log_level.trim().to_uppercase();
+    if !OVMS_LOG_LEVELS.contains(&params.log_level.as_str()) {
+        return Err(format!("Unknown OVMS log level '{}'; use one of {}", params.log_level, OVMS_LOG_LEVELS.join(", ")));
+    }
+    for (name, workers) in [("grpc_workers", params.grpc_workers), ("rest_workers", params.rest_workers)] {
+        if workers == Some(0) {
+            return Err(format!("{} must be at least 1", name));
         }
     }
+    if let Some(dir) = params.cache_dir.as_deref().filter(|dir| !dir.trim().is_empty()) {
+        let resolved = crate::path_policy::check_str(dir, crate::path_policy::Access::Write)?;
+        params.cache_dir = Some(resolved.to_string_lossy().replace('\\', "/"));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_ovms_runtime_params() -> Result<crate::settings::OvmsRuntimeParams, String> {
+    Ok(crate::settings::current().ovms.runtime)
+}
+
+/// Save OVMS launch flags; they take effect when OVMS is next started
+#[tauri::command]
+pub async fn configure_ovms_runtime(
+    mut params: crate::settings::OvmsRuntimeParams
+) -> Result<crate::settings::OvmsRuntimeParams, String> {
+    validate_runtime_params(&mut params)?;
+    crate::settings::update(|settings| settings.ovms.runtime = params.clone())?;
+    info!(params = ?params, "OVMS runtime parameters updated");
+    Ok(params)
 }
 
 // Stop OVMS server
-pub fn stop_ovms_server() -> Result<(), String> {
+pub fn stop_ovms_server(app_handle: &AppHandle) -> Result<(), String> {
     log_operation_start!("Stopping OVMS server");
     
-    let process_mutex = OVMS_PROCESS.get_or_init(|| Arc::new(Mutex::new(None)));
-    let mut process_guard = process_mutex.lock().unwrap();
+    let child = app_handle.state::<AppState>().ovms_process.lock().take();
 
-    if let Some(mut child) = process_guard.take() {
+    if let Some(mut child) = child {
         tracing::debug!("Terminating OVMS process...");
 
         // Try to terminate gracefully first
@@ -847,31 +1229,30 @@ pub fn stop_ovms_server() -> Result<(), String> {
         let _ = Command::new("pkill").args(["-f", "ovms"]).output();
     }
 
+    // The next start picks its ports again
+    ACTIVE_REST_PORT.store(0, Ordering::Relaxed);
+    ACTIVE_GRPC_PORT.store(0, Ordering::Relaxed);
+
     Ok(())
 }
 
-// Load a model into OVMS
+// Load a model into OVMS; `device` (CPU, GPU, NPU or AUTO) overrides the one in its graph.pbtxt
 #[tauri::command]
-pub async fn load_model(app_handle: AppHandle, model_id: String) -> Result<String, String> {
-    log_operation_start!("Loading model", model_id = %model_id);
+pub async fn load_model(app_handle: AppHandle, model_id: String, device: Option<String>) -> Result<String, String> {
+    log_operation_start!("Loading model", model_id = %model_id, device = ?device);
+    let device = device.as_deref().map(normalize_device).transpose()?;
+
+    crate::ensure_ovms_initialized(&app_handle).await;
     
     // Ensure we're working with an OpenVINO model
-    let normalized_model_id = if model_id.starts_with("OpenVINO/") {
-        model_id.clone()
-    } else {
-        format!("OpenVINO/{}", model_id)
-    };
+    let normalized_model_id = crate::models::normalize_model_id(&model_id);
 
     // Get the model path
     let models_dir = paths::get_models_dir()
         .map_err(|e| e.to_string())?;
 
     // Build the path using the original model_id structure
-    let original_model_id = if model_id.starts_with("OpenVINO") {
-        model_id.clone()
-    } else {
-        format!("OpenVINO/{}", model_id)
-    };
+    let original_model_id = crate::models::normalize_model_id(&model_id);
 
     let model_path = models_dir.join(&original_model_id);
 
@@ -887,6 +1268,15 @@ pub async fn load_model(app_handle: AppHandle, model_id: String) -> Result<Strin
     // Extract model name from the full ID
     let model_name = normalized_model_id.split('/').next_back().unwrap_or(&normalized_model_id);
 
+    // A device passed in still wins over the RAG model options
+    let model_type = crate::huggingface::get_model_type(&normalized_model_id).await.ok().flatten();
+    if matches!(model_type, Some(crate::huggingface::ModelType::Embedding | crate::huggingface::ModelType::Reranker)) {
+        apply_rag_model_options(&model_path).await?;
+    }
+    if let Some(device) = &device {
+        retarget_graph(&model_path, device).await?;
+    }
+
     log_progress!("Updating OVMS configuration", model_name = %model_name);
     
     // Update OVMS config with the model
@@ -898,29 +1288,391 @@ pub async fn load_model(app_handle: AppHandle, model_id: String) -> Result<Strin
 
     log_progress!("Reloading OVMS configuration");
     
-    // Reload OVMS config to apply changes
-    reload_ovms_config().await?;
+    let reload_started = chrono::Utc::now();
+    if let Err(e) = reload_and_wait(&app_handle, model_name).await {
+        let log_tail = ovms_logs::lines_since(reload_started);
+        let diagnostics = model_diagnostics::diagnose(&normalized_model_id, &model_path, &e, &log_tail);
+        log_operation_error!("Loading model", &e, model_id = %normalized_model_id, cause = ?diagnostics.cause);
+
+        let retried = match fallback_device_for(&diagnostics) {
+            Some(device) => retry_on_device(&app_handle, &normalized_model_id, &model_path, &diagnostics, &device).await,
+            None => Err(diagnostics),
+        };
+        if let Err(diagnostics) = retried {
+            let _ = app_handle.emit("model-load-failed", &diagnostics);
+            return Err(diagnostics.to_message());
+        }
+    }
+
+    // The text model loaded last answers requests that don't name one
+    if matches!(model_type, Some(crate::huggingface::ModelType::Text)) {
+        crate::settings::update(|settings| settings.ovms.default_model = Some(model_name.to_string()))?;
+        let _ = app_handle.emit("default-model-changed", model_name);
+    }
 
     log_operation_success!("Model loaded", model_id = %normalized_model_id);
     Ok(format!("Model '{}' loaded successfully", normalized_model_id))
 }
 
 
+/// Remove `model_name` from an OVMS config; `false` when it was not in it
+fn remove_model_entry(config: &mut Value, model_name: &str) -> bool {
+    let mut removed = false;
+    if let Some(model_list) = config["mediapipe_config_list"].as_array_mut() {
+        let before = model_list.len();
+        model_list.retain(|model| model["name"].as_str() != Some(model_name));
+        removed |= model_list.len() != before;
+    }
+    if let Some(model_list) = config["model_config_list"].as_array_mut() {
+        let before = model_list.len();
+        model_list.retain(|model| model["config"]["name"].as_str() != Some(model_name));
+        removed |= model_list.len() != before;
+    }
+    removed
+}
+
+// Unload a model: drop it from models_config.json and have OVMS release it
+#[tauri::command]
+pub async fn unload_model(app_handle: AppHandle, model_id: String) -> Result<String, String> {
+    log_operation_start!("Unloading model", model_id = %model_id);
+
+    let normalized_model_id = crate::models::normalize_model_id(&model_id);
+    let model_name = normalized_model_id.split('/').next_back().unwrap_or(&normalized_model_id).to_string();
+
+    let config_path = paths::get_ovms_config_path(Some(&app_handle))
+        .map_err(|e| e.to_string())?;
+    let Some(config_str) = storage::read_string(&config_path).await
+        .map_err(|e| format!("Failed to read config file: {}", e))? else {
+        return Err(format!("Model '{}' is not loaded", model_name));
+    };
+    let mut config: Value = serde_json::from_str(&config_str)
+        .map_err(|e| format!("Failed to parse config file: {}", e))?;
+
+    if !remove_model_entry(&mut config, &model_name) {
+        return Err(format!("Model '{}' is not loaded", model_name));
+    }
+
+    let config_str = serde_json
+        ::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    ovms_backups::backup_current(&config_path).await?;
+    storage::write_string(&config_path, &config_str).await
+        .map_err(|e| format!("Failed to write config file: {}", e))?;
+
+    // A stopped OVMS reads the updated config when it next starts
+    if check_ovms_status().await.is_ok() {
+        if let Err(e) = reload_ovms_config().await {
+            log_operation_error!("Unloading model", &e, model_id = %normalized_model_id);
+            return Err(e);
+        }
+    }
+
+    app_handle.state::<AppState>().ovms_health.lock().loaded_models.retain(|name| name != &model_name);
+    if crate::settings::current().ovms.default_model.as_deref() == Some(model_name.as_str()) {
+        crate::settings::update(|settings| settings.ovms.default_model = None)?;
+    }
+    let _ = app_handle.emit("model-unloaded", json!({
+        "model_id": normalized_model_id,
+        "model_name": model_name,
+    }));
+
+    log_operation_success!("Model unloaded", model_id = %normalized_model_id);
+    Ok(format!("Model '{}' unloaded successfully", normalized_model_id))
+}
+
+
+/// Reload the OVMS config and wait for `model_name`: the reload only
+/// schedules the load, and large models take minutes to become AVAILABLE
+async fn reload_and_wait(app_handle: &AppHandle, model_name: &str) -> Result<(), String> {
+    reload_ovms_config().await?;
+    wait_for_model(
+        model_name,
+        std::time::Duration::from_secs(constants::MODEL_LOAD_TIMEOUT_SECS),
+        |progress| {
+            let _ = app_handle.emit("model-load-progress", progress);
+        }
+    ).await
+}
+
+/// Device to retry a failed load on, if the failure looks device-related
+fn fallback_device_for(diagnostics: &model_diagnostics::LoadDiagnostics) -> Option<String> {
+    use model_diagnostics::LoadFailureCause;
+
+    let settings = crate::settings::current().ovms;
+    let current = diagnostics.target_device.as_deref()?;
+    let device_related = matches!(
+        diagnostics.cause,
+        LoadFailureCause::UnsupportedDevice | LoadFailureCause::InsufficientMemory
+    );
+    (settings.device_fallback && device_related && !current.eq_ignore_ascii_case(&settings.fallback_device))
+        .then_some(settings.fallback_device)
+}
+
+/// Point the model's graph at `device` and load it again. The new device is
+/// kept on success; on failure the original graph is restored.
+async fn retry_on_device(
+    app_handle: &AppHandle,
+    model_id: &str,
+    model_path: &std::path::Path,
+    diagnostics: &model_diagnostics::LoadDiagnostics,
+    device: &str
+) -> Result<(), model_diagnostics::LoadDiagnostics> {
+    let graph_path = model_path.join("graph.pbtxt");
+    let model_name = model_id.split('/').next_back().unwrap_or(model_id);
+    let original = match storage::read_string(&graph_path).await {
+        Ok(Some(graph)) => graph,
+        _ => return Err(diagnostics.clone()),
+    };
+
+    let from = diagnostics.target_device.clone().unwrap_or_default();
+    log_warning!("Retrying model load on fallback device", model_id = %model_id, from = %from, to = %device);
+    let _ = app_handle.emit("model-device-fallback", json!({
+        "model_id": model_id,
+        "from_device": from,
+        "to_device": device,
+        "reason": diagnostics.summary,
+    }));
+
+    if let Err(e) = storage::write_string(&graph_path, &set_graph_device(&original, device)).await {
+        log_warning!("Failed to rewrite graph for fallback device", error = %e);
+        return Err(diagnostics.clone());
+    }
+
+    let reload_started = chrono::Utc::now();
+    match reload_and_wait(app_handle, model_name).await {
+        Ok(()) => {
+            info!(model_id = %model_id, device = %device, "Model loaded on fallback device");
+            Ok(())
+        }
+        Err(e) => {
+            if let Err(restore_error) = storage::write_string(&graph_path, &original).await {
+                log_warning!("Failed to restore graph after fallback", error = %restore_error);
+            }
+            let mut retry = model_diagnostics::diagnose(model_id, model_path, &e, &ovms_logs::lines_since(reload_started));
+            retry.summary = format!("{} (and retrying on {} failed too)", diagnostics.summary, device);
+            Err(retry)
+        }
+    }
+}
+
+/// Upper-case a device name and check that OVMS knows it. `GPU.1` picks one
+/// of several GPUs; `AUTO` lets OpenVINO choose.
+fn normalize_device(device: &str) -> Result<String, String> {
+    let device = device.trim().to_ascii_uppercase();
+    let valid = match device.split_once('.') {
+        Some((base, index)) => base == "GPU" && !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()),
+        None => matches!(device.as_str(), "CPU" | "GPU" | "NPU" | "AUTO"),
+    };
+    if valid {
+        Ok(device)
+    } else {
+        Err(format!("Unsupported device '{}'; expected CPU, GPU, NPU or AUTO", device))
+    }
+}
+
+/// Point the model's graph.pbtxt at `device` unless it already runs there.
+/// The change is kept, so later loads without a device use it too.
+async fn retarget_graph(model_path: &std::path::Path, device: &str) -> Result<(), String> {
+    let graph_path = model_path.join("graph.pbtxt");
+    let graph = storage::read_string(&graph_path).await
+        .map_err(|e| format!("Failed to read {}: {}", graph_path.display(), e))?
+        .ok_or_else(|| format!("Model has no graph.pbtxt at {}; its device cannot be changed", model_path.display()))?;
+
+    let current = graph_device(&graph)
+        .ok_or("The model's graph.pbtxt does not name a device; its device cannot be changed")?;
+    if current.eq_ignore_ascii_case(device) {
+        return Ok(());
+    }
+
+    storage::write_string(&graph_path, &set_graph_device(&graph, device)).await
+        .map_err(|e| format!("Failed to update {}: {}", graph_path.display(), e))?;
+    info!(from = %current, to = %device, graph = %graph_path.display(), "Retargeted model graph");
+    Ok(())
+}
+
+/// Keys that name the inference device in the graph templates
+const GRAPH_DEVICE_KEYS: &[&str] = &["target_device:", "device:"];
+
+/// Device a graph.pbtxt runs on
+pub(crate) fn graph_device(graph: &str) -> Option<String> {
+    graph.lines()
+        .map(str::trim)
+        .find_map(|line| GRAPH_DEVICE_KEYS.iter().find_map(|key| line.strip_prefix(key)))
+        .map(|value| value.trim().trim_end_matches(',').trim_matches('"').to_string())
+        .filter(|device| !device.is_empty())
+}
+
+/// Point every device entry of a graph.pbtxt at `device`
+pub(crate) fn set_graph_device(graph: &str, device: &str) -> String {
+    let mut patched: Vec<String> = graph.lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            match GRAPH_DEVICE_KEYS.iter().find(|key| trimmed.starts_with(*key)) {
+                Some(key) => {
+                    let indent = &line[..line.len() - trimmed.len()];
+                    let comma = if trimmed.trim_end().ends_with(',') { "," } else { "" };
+                    format!("{}{} \"{}\"{}", indent, key, device, comma)
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    if graph.ends_with('\n') {
+        patched.push(String::new());
+    }
+    patched.join("\n")
+}
+
+/// Set `key` in the `plugin_config` of a graph.pbtxt; a graph without one
+/// gets it after its device line, and is returned unchanged if it has neither
+pub(crate) fn set_graph_plugin_option(graph: &str, key: &str, value: &str) -> String {
+    let plugin_config = |options: serde_json::Map<String, Value>, indent: &str, comma: &str| {
+        format!("{}plugin_config: '{}'{}", indent, Value::Object(options), comma)
+    };
+    let mut lines: Vec<String> = graph.lines().map(str::to_string).collect();
+
+    let existing = lines.iter().position(|line| line.trim_start().starts_with("plugin_config:"));
+    let device = lines.iter().position(|line| {
+        let trimmed = line.trim_start();
+        GRAPH_DEVICE_KEYS.iter().any(|key| trimmed.starts_with(*key))
+    });
+    match (existing, device) {
+        (Some(index), _) => {
+            let line = &lines[index];
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            let comma = if trimmed.trim_end().ends_with(',') { "," } else { "" };
+            let mut options = trimmed
+                .split_once('\'')
+                .and_then(|(_, rest)| rest.rsplit_once('\''))
+                .and_then(|(json, _)| serde_json::from_str::<serde_json::Map<String, Value>>(json).ok())
+                .unwrap_or_default();
+            options.insert(key.to_string(), json!(value));
+            lines[index] = plugin_config(options, indent, comma);
+        }
+        (None, Some(index)) => {
+            let line = &lines[index];
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            let comma = if trimmed.trim_end().ends_with(',') { "," } else { "" };
+            let mut options = serde_json::Map::new();
+            options.insert(key.to_string(), json!(value));
+            let entry = plugin_config(options, indent, comma);
+            lines.insert(index + 1, entry);
+        }
+        (None, None) => return graph.to_string(),
+    }
+
+    if graph.ends_with('\n') {
+        lines.push(String::new());
+    }
+    lines.join("\n")
+}
+
+/// Apply the `ovms.rag_models` device and stream options to an embedding or reranker graph
+pub(crate) fn apply_rag_graph_options(graph: &str, options: &crate::settings::RagModelSettings) -> String {
+    let mut graph = graph.to_string();
+    if let Some(device) = options.device.as_deref() {
+        match normalize_device(device) {
+            Ok(device) => graph = set_graph_device(&graph, &device),
+            Err(e) => log_warning!("Ignoring configured RAG model device", error = %e),
+        }
+    }
+    if let Some(num_streams) = options.num_streams.filter(|n| *n > 0) {
+        graph = set_graph_plugin_option(&graph, "NUM_STREAMS", &num_streams.to_string());
+    }
+    graph
+}
+
+/// Rewrite an embedding or reranker model's graph.pbtxt with the `ovms.rag_models` options
+async fn apply_rag_model_options(model_path: &std::path::Path) -> Result<(), String> {
+    let options = crate::settings::current().ovms.rag_models;
+    if options.device.is_none() && options.num_streams.is_none() {
+        return Ok(());
+    }
+    let graph_path = model_path.join("graph.pbtxt");
+    let Some(graph) = storage::read_string(&graph_path).await
+        .map_err(|e| format!("Failed to read {}: {}", graph_path.display(), e))? else {
+        return Ok(());
+    };
+
+    let patched = apply_rag_graph_options(&graph, &options);
+    if patched != graph {
+        storage::write_string(&graph_path, &patched).await
+            .map_err(|e| format!("Failed to update {}: {}", graph_path.display(), e))?;
+        info!(graph = %graph_path.display(), device = ?options.device, num_streams = ?options.num_streams, "Applied RAG model options");
+    }
+    Ok(())
+}
 
-// Get the currently loaded model from config file
+// Get the model requests go to when they don't name one: the default model
+// if it is loaded, else the first loaded text model, else the first entry
 #[tauri::command]
 pub async fn get_loaded_model(app_handle: AppHandle) -> Result<Option<String>, String> {
+    use crate::huggingface::{ get_model_type, resolve_model_id, ModelType };
+
     let loaded_models = get_loaded_models(app_handle).await?;
-    // Return the first loaded model, or None if no models are loaded
+    if let Some(default) = crate::settings::current().ovms.default_model {
+        if loaded_models.contains(&default) {
+            return Ok(Some(default));
+        }
+    }
+
+    for name in &loaded_models {
+        let model_id = resolve_model_id(name).await;
+        if matches!(get_model_type(&model_id).await, Ok(Some(ModelType::Text))) {
+            return Ok(Some(name.clone()));
+        }
+    }
     Ok(loaded_models.into_iter().next())
 }
 
+/// Model a chat request runs on: the one it names, which must be loaded,
+/// or the default model
+pub(crate) async fn route_model(app_handle: &AppHandle, requested: Option<String>) -> Result<String, String> {
+    match requested.filter(|name| !name.trim().is_empty()) {
+        Some(name) => {
+            let loaded = get_loaded_models(app_handle.clone()).await?;
+            if !loaded.is_empty() && !loaded.contains(&name) {
+                return Err(format!("Model '{}' is not loaded", name));
+            }
+            Ok(name)
+        }
+        None => get_loaded_model(app_handle.clone()).await?
+            .ok_or_else(|| "No model is loaded".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_default_model(app_handle: AppHandle) -> Result<Option<String>, String> {
+    get_loaded_model(app_handle).await
+}
+
+/// Make a loaded model the one used when a request does not name a model
+#[tauri::command]
+pub async fn set_default_model(app_handle: AppHandle, model_name: String) -> Result<(), String> {
+    let model_name = crate::models::normalize_model_id(&model_name)
+        .split('/')
+        .next_back()
+        .unwrap_or(&model_name)
+        .to_string();
+    let loaded = get_loaded_models(app_handle.clone()).await?;
+    if !loaded.contains(&model_name) {
+        return Err(format!("Model '{}' is not loaded", model_name));
+    }
+
+    crate::settings::update(|settings| settings.ovms.default_model = Some(model_name.clone()))?;
+    info!(model = %model_name, "Default model changed");
+    let _ = app_handle.emit("default-model-changed", &model_name);
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn check_ovms_status() -> Result<OvmsStatus, String> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client()?;
 
     let response = client
-        .get("http://localhost:1114/v1/config")
+        .get(format!("{}/v1/config", api_base()))
         .send().await
         .map_err(|e| format!("Failed to connect to OVMS server: {}", e))?;
 
@@ -981,10 +1733,10 @@ pub async fn check_ovms_status() -> Result<OvmsStatus, String> {
 
 #[tauri::command]
 pub async fn get_ovms_model_metadata(model_name: String) -> Result<String, String> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client()?;
 
     // Try to get model metadata for more detailed error information
-    let metadata_url = format!("http://localhost:1114/v1/models/{}/metadata", model_name);
+    let metadata_url = format!("{}/v1/models/{}/metadata", api_base(), model_name);
     let response = client
         .get(&metadata_url)
         .send().await
@@ -997,7 +1749,7 @@ pub async fn get_ovms_model_metadata(model_name: String) -> Result<String, Strin
         Ok(body)
     } else {
         // If metadata fails, try the model status endpoint
-        let status_url = format!("http://localhost:1114/v1/models/{}", model_name);
+        let status_url = format!("{}/v1/models/{}", api_base(), model_name);
         let status_response = client
             .get(&status_url)
             .send().await
@@ -1201,6 +1953,12 @@ node {{
         )
     };
 
+    let graph_content = if matches!(model_name, "Qwen3-Reranker-0.6B-fp16-ov" | "Qwen3-Embedding-0.6B-int8-ov") {
+        apply_rag_graph_options(&graph_content, &crate::settings::current().ovms.rag_models)
+    } else {
+        graph_content
+    };
+
     let graph_path = model_dir.join("graph.pbtxt");
     std::fs
         ::write(&graph_path, graph_content)
@@ -1211,3 +1969,181 @@ node {{
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_args_skip_unset_flags() {
+        let mut params = crate::settings::OvmsRuntimeParams::default();
+        assert_eq!(runtime_args(&params), vec!["--log_level", "INFO"]);
+
+        params.rest_workers = Some(4);
+        params.file_system_poll_wait_seconds = Some(0);
+        assert_eq!(
+            runtime_args(&params),
+            vec!["--log_level", "INFO", "--rest_workers", "4", "--file_system_poll_wait_seconds", "0"]
+        );
+    }
+
+    #[test]
+    fn test_validate_runtime_params() {
+        let mut params = crate::settings::OvmsRuntimeParams {
+            log_level: "debug".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_runtime_params(&mut params).is_ok());
+        assert_eq!(params.log_level, "DEBUG");
+
+        params.log_level = "LOUD".to_string();
+        assert!(validate_runtime_params(&mut params).is_err());
+
+        params.log_level = "INFO".to_string();
+        params.grpc_workers = Some(0);
+        assert!(validate_runtime_params(&mut params).is_err());
+    }
+
+    #[test]
+    fn test_remove_model_entry() {
+        let mut config = json!({
+            "mediapipe_config_list": [
+                { "name": "chat-model", "base_path": "a" },
+                { "name": "embed-model", "base_path": "b" }
+            ],
+            "model_config_list": []
+        });
+
+        assert!(remove_model_entry(&mut config, "chat-model"));
+        assert_eq!(config["mediapipe_config_list"].as_array().unwrap().len(), 1);
+        assert_eq!(config["mediapipe_config_list"][0]["name"], "embed-model");
+        assert!(!remove_model_entry(&mut config, "chat-model"));
+    }
+
+    #[test]
+    fn test_choose_port_skips_busy_and_taken_ports() {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let busy = listener.local_addr().unwrap().port();
+
+        assert!(choose_port(busy, false, &[]).is_err());
+        let port = choose_port(busy, true, &[]).unwrap();
+        assert_ne!(port, busy);
+        assert_ne!(choose_port(port, true, &[port]).unwrap(), port);
+    }
+
+    #[test]
+    fn test_normalize_device() {
+        assert_eq!(normalize_device(" gpu ").unwrap(), "GPU");
+        assert_eq!(normalize_device("GPU.1").unwrap(), "GPU.1");
+        assert_eq!(normalize_device("auto").unwrap(), "AUTO");
+        assert!(normalize_device("NPU.0").is_err());
+        assert!(normalize_device("TPU").is_err());
+        assert!(normalize_device("GPU.x").is_err());
+        assert!(normalize_device("GPU.").is_err());
+    }
+
+    #[test]
+    fn test_set_graph_device_keeps_layout() {
+        let graph = "node_options: {\n    models_path: \"./\",\n    device: \"GPU\",\n}\n";
+        let patched = set_graph_device(graph, "CPU");
+
+        assert_eq!(patched, "node_options: {\n    models_path: \"./\",\n    device: \"CPU\",\n}\n");
+        assert_eq!(graph_device(graph).as_deref(), Some("GPU"));
+        assert_eq!(graph_device("target_device: \"NPU\"").as_deref(), Some("NPU"));
+    }
+
+    #[test]
+    fn test_apply_rag_graph_options_sets_device_and_streams() {
+        let options = crate::settings::RagModelSettings {
+            device: Some("cpu".to_string()),
+            num_streams: Some(2),
+            load_first: true,
+        };
+        let graph = "options: {\n  normalize_embeddings: true,\n  target_device: \"GPU\"\n}\n";
+        assert_eq!(
+            apply_rag_graph_options(graph, &options),
+            "options: {\n  normalize_embeddings: true,\n  target_device: \"CPU\"\n  plugin_config: '{\"NUM_STREAMS\":\"2\"}'\n}\n"
+        );
+
+        // An existing plugin_config keeps its other options
+        let graph = "  plugin_config: '{\"CACHE_DIR\": \"/cache\"}',\n  device: \"GPU\",\n";
+        assert_eq!(
+            set_graph_plugin_option(graph, "NUM_STREAMS", "1"),
+            "  plugin_config: '{\"CACHE_DIR\":\"/cache\",\"NUM_STREAMS\":\"1\"}',\n  device: \"GPU\",\n"
+        );
+        assert_eq!(set_graph_plugin_option("node {}\n", "NUM_STREAMS", "1"), "node {}\n");
+    }
+
+    #[test]
+    fn test_order_rag_models_first_keeps_group_order() {
+        let mut models = vec![
+            json!({ "name": "llm-a" }),
+            json!({ "name": "embed" }),
+            json!({ "name": "llm-b" }),
+            json!({ "name": "rerank" }),
+        ];
+        let rag_models = ["rerank".to_string(), "embed".to_string()].into_iter().collect();
+        order_rag_models_first(&mut models, &rag_models);
+
+        let names: Vec<&str> = models.iter().map(|m| m["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["embed", "rerank", "llm-a", "llm-b"]);
+    }
+
+    #[test]
+    fn test_parse_model_state_uses_latest_version() {
+        let config = json!({
+            "Phi-3.5-mini-instruct-int4-ov": {
+                "model_version_status": [
+                    { "version": "1", "state": "END", "status": { "error_code": "OK", "error_message": "OK" } },
+                    { "version": "2", "state": "LOADING", "status": { "error_code": "OK", "error_message": "OK" } }
+                ]
+            }
+        });
+
+        let state = parse_model_state(&config, "Phi-3.5-mini-instruct-int4-ov").unwrap();
+        assert_eq!(state.state, "LOADING");
+        assert!(!state.is_available() && !state.is_failed());
+        assert!(parse_model_state(&config, "missing").is_none());
+    }
+
+    #[test]
+    fn test_extract_ovms_strips_root_and_reports_progress() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("sparrow-ovms-zip-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let zip_path = dir.join("ovms.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        writer.add_directory("ovms/", zip::write::FileOptions::default()).unwrap();
+        writer.start_file("ovms/ovms.exe", zip::write::FileOptions::default()).unwrap();
+        writer.write_all(b"binary").unwrap();
+        writer.start_file("ovms/lib/plugin.dll", zip::write::FileOptions::default()).unwrap();
+        writer.write_all(b"lib").unwrap();
+        writer.finish().unwrap();
+
+        assert!(validate_zip_file(&zip_path).is_ok());
+
+        let mut progress = Vec::new();
+        let out = dir.join("out");
+        extract_ovms(&zip_path, &out, |bytes, total| progress.push((bytes, total))).unwrap();
+        assert_eq!(fs::read(out.join("ovms.exe")).unwrap(), b"binary");
+        assert!(out.join("lib").join("plugin.dll").exists());
+        assert_eq!(progress.last(), Some(&(9, 9)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_model_info_reports_load_failure() {
+        let info = json!({
+            "model_version_status": [
+                { "version": "1", "state": "END", "status": { "error_code": "UNKNOWN", "error_message": "Out of memory" } }
+            ]
+        });
+
+        let state = parse_model_info(&info).unwrap();
+        assert!(state.is_failed());
+        assert_eq!(state.error_message, "Out of memory");
+        assert!(parse_model_info(&json!({ "error": "Model not found" })).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Backups of the OVMS model config.
+//!
+//! A bad entry in `models_config.json` can keep OVMS from starting. Every
+//! change to the config first copies the current file to
+//! `~/.sparrow/ovms/config_backups/models_config.<timestamp>.json` (the
+//! newest `OVMS_CONFIG_BACKUPS_KEPT` are kept). `rollback_ovms_config`
+//! restores one of them, by default the newest, i.e. the config as it was
+//! before the last change.
+
+use std::path::{ Path, PathBuf };
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{ AppHandle, Emitter };
+
+use crate::{ constants, paths, path_policy, storage };
+
+const BACKUP_PREFIX: &str = "models_config.";
+const BACKUP_SUFFIX: &str = ".json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigBackup {
+    /// File name of the backup, passed back to `rollback_ovms_config`
+    pub id: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub size_bytes: u64,
+    /// Models the backed-up config serves
+    pub models: Vec<String>,
+}
+
+fn backups_dir() -> Result<PathBuf, String> {
+    let dir = paths::get_ovms_dir(None).map_err(|e| e.to_string())?.join("config_backups");
+    paths::ensure_dir_exists(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// Timestamp of a backup file name; sorts chronologically as a string
+fn backup_timestamp(id: &str) -> Option<&str> {
+    id.strip_prefix(BACKUP_PREFIX)?.strip_suffix(BACKUP_SUFFIX)
+}
+
+fn model_names(config: &Value) -> Vec<String> {
+    config["mediapipe_config_list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| model["name"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// Copy the current config into the backup folder; no-op when there is none yet
+pub async fn backup_current(config_path: &Path) -> Result<Option<String>, String> {
+    let Some(contents) = storage::read_string(config_path).await
+        .map_err(|e| format!("Failed to read config file: {}", e))? else {
+        return Ok(None);
+    };
+
+    let dir = backups_dir()?;
+    let id = format!("{}{}{}", BACKUP_PREFIX, Utc::now().format("%Y%m%dT%H%M%S%.3fZ"), BACKUP_SUFFIX);
+    storage::write_string(&dir.join(&id), &contents).await
+        .map_err(|e| format!("Failed to write config backup: {}", e))?;
+    tracing::debug!(backup = %id, "Backed up OVMS config");
+
+    prune(&dir).await;
+    Ok(Some(id))
+}
+
+async fn backup_ids(dir: &Path) -> Vec<String> {
+    let mut ids = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Some(name) = entry.file_name().to_str() {
+                if backup_timestamp(name).is_some() {
+                    ids.push(name.to_string());
+                }
+            }
+        }
+    }
+    // Newest first
+    ids.sort_by(|a, b| b.cmp(a));
+    ids
+}
+
+async fn prune(dir: &Path) {
+    for id in backup_ids(dir).await.into_iter().skip(constants::OVMS_CONFIG_BACKUPS_KEPT) {
+        if let Err(e) = tokio::fs::remove_file(dir.join(&id)).await {
+            tracing::warn!(backup = %id, error = %e, "Failed to remove old config backup");
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_ovms_config_backups() -> Result<Vec<ConfigBackup>, String> {
+    let dir = backups_dir()?;
+    let mut backups = Vec::new();
+
+    for id in backup_ids(&dir).await {
+        let path = dir.join(&id);
+        let contents = storage::read_string(&path).await.ok().flatten().unwrap_or_default();
+        let config: Value = serde_json::from_str(&contents).unwrap_or_default();
+        let created_at = backup_timestamp(&id)
+            .and_then(|ts| chrono::NaiveDateTime::parse_from_str(ts, "%Y%m%dT%H%M%S%.3fZ").ok())
+            .map(|ts| ts.and_utc());
+
+        backups.push(ConfigBackup {
+            models: model_names(&config),
+            size_bytes: contents.len() as u64,
+            created_at,
+            id,
+        });
+    }
+    Ok(backups)
+}
+
+/// Restore a backup (the newest when `backup_id` is unset) and reload OVMS.
+/// The config being replaced is backed up too, so a rollback can be undone.
+#[tauri::command]
+pub async fn rollback_ovms_config(app_handle: AppHandle, backup_id: Option<String>) -> Result<ConfigBackup, String> {
+    let dir = backups_dir()?;
+    let id = match backup_id {
+        Some(id) => path_policy::safe_file_name(&id)?.to_string(),
+        None => backup_ids(&dir).await
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No OVMS config backups to roll back to".to_string())?,
+    };
+    if backup_timestamp(&id).is_none() {
+        return Err(format!("Not an OVMS config backup: {}", id));
+    }
+
+    let contents = storage::read_string(&dir.join(&id)).await
+        .map_err(|e| format!("Failed to read config backup: {}", e))?
+        .ok_or_else(|| format!("Config backup not found: {}", id))?;
+    let config: Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("Config backup {} is not valid JSON: {}", id, e))?;
+
+    let config_path = paths::get_ovms_config_path(Some(&app_handle)).map_err(|e| e.to_string())?;
+    backup_current(&config_path).await?;
+    storage::write_string(&config_path, &contents).await
+        .map_err(|e| format!("Failed to write config file: {}", e))?;
+    tracing::info!(backup = %id, "Rolled back OVMS config");
+
+    // A stopped OVMS picks the restored config up when it starts
+    if crate::ovms::check_ovms_status().await.is_ok() {
+        crate::ovms::reload_ovms_config().await?;
+    }
+
+    let restored = ConfigBackup {
+        models: model_names(&config),
+        size_bytes: contents.len() as u64,
+        created_at: None,
+        id,
+    };
+    let _ = app_handle.emit("ovms-config-rolled-back", &restored);
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_backup_timestamp() {
+        assert_eq!(backup_timestamp("models_config.20260101T120000.000Z.json"), Some("20260101T120000.000Z"));
+        assert_eq!(backup_timestamp("models_config.json.tmp"), None);
+        assert_eq!(backup_timestamp("other.json"), None);
+    }
+
+    #[test]
+    fn test_model_names() {
+        let config = json!({ "mediapipe_config_list": [{ "name": "a" }, { "name": "b" }] });
+        assert_eq!(model_names(&config), vec!["a", "b"]);
+        assert!(model_names(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! Captured OVMS output.
+//!
+//! The stdout and stderr pipes of the OVMS child are read line by line on two
+//! threads for as long as the process runs. Each line is kept in an in-memory
+//! ring (for `get_ovms_logs`), appended to `~/.sparrow/logs/ovms.log` (rotated
+//! to `ovms.log.1` past `OVMS_LOG_FILE_MAX_BYTES`) and emitted as `ovms-log`.
+
+use std::collections::VecDeque;
+use std::fs::{ self, File, OpenOptions };
+use std::io::{ BufRead, BufReader, Read, Write };
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::thread::JoinHandle;
+
+use chrono::{ DateTime, Utc };
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+
+use crate::{ constants, paths };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of OVMS output; also the payload of `ovms-log` events
+#[derive(Debug, Clone, Serialize)]
+pub struct OvmsLogLine {
+    /// Which start of the server produced the line, counting from 1
+    pub run: u64,
+    pub stream: LogStream,
+    pub line: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+static RUN: AtomicU64 = AtomicU64::new(0);
+static LINES: Mutex<VecDeque<OvmsLogLine>> = Mutex::new(VecDeque::new());
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+fn log_file_path() -> Option<PathBuf> {
+    let dir = paths::get_logs_dir().ok()?;
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("ovms.log"))
+}
+
+fn write_to_file(entry: &OvmsLogLine) {
+    let mut file = LOG_FILE.lock();
+    let Some(path) = log_file_path() else {
+        return;
+    };
+
+    let too_big = fs::metadata(&path).map(|m| m.len() > constants::OVMS_LOG_FILE_MAX_BYTES).unwrap_or(false);
+    if too_big {
+        *file = None;
+        let _ = fs::rename(&path, path.with_extension("log.1"));
+    }
+    if file.is_none() {
+        *file = OpenOptions::new().create(true).append(true).open(&path).ok();
+    }
+
+    if let Some(handle) = file.as_mut() {
+        let stream = match entry.stream {
+            LogStream::Stdout => "out",
+            LogStream::Stderr => "err",
+        };
+        let _ = writeln!(handle, "{} [{}] {}", entry.timestamp.to_rfc3339(), stream, entry.line);
+    }
+}
+
+fn push(entry: OvmsLogLine) {
+    let mut lines = LINES.lock();
+    lines.push_back(entry);
+    let overflow = lines.len().saturating_sub(constants::OVMS_LOG_BUFFER_LINES);
+    lines.drain(..overflow);
+}
+
+fn pump(app: AppHandle, run: u64, stream: LogStream, pipe: impl Read + Send + 'static) -> Option<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name(format!("ovms-{:?}", stream).to_lowercase())
+        .spawn(move || {
+            // OVMS output is not guaranteed to be UTF-8; read bytes
+            let mut reader = BufReader::new(pipe);
+            let mut buffer = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut buffer), Ok(n) if n > 0) {
+                let line = String::from_utf8_lossy(&buffer).trim_end().to_string();
+                buffer.clear();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let entry = OvmsLogLine { run, stream, line, timestamp: Utc::now() };
+                write_to_file(&entry);
+                let _ = app.emit("ovms-log", &entry);
+                push(entry);
+            }
+        })
+        .ok()
+}
+
+/// Start reading the output of a freshly spawned OVMS; the threads end when
+/// the process closes its pipes
+pub fn capture(app: &AppHandle, child: &mut Child) -> Capture {
+    let run = RUN.fetch_add(1, Ordering::Relaxed) + 1;
+    let mut threads = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        threads.extend(pump(app.clone(), run, LogStream::Stdout, stdout));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        threads.extend(pump(app.clone(), run, LogStream::Stderr, stderr));
+    }
+    Capture { run, threads }
+}
+
+/// Output capture of one OVMS run
+pub struct Capture {
+    run: u64,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Capture {
+    /// After the process exited: wait for the readers to drain its pipes and
+    /// return what the run wrote to stdout and stderr
+    pub fn finish(self) -> (String, String) {
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+        let lines = LINES.lock();
+        let text = |stream| select(&lines, Some(self.run), Some(stream), usize::MAX)
+            .into_iter()
+            .map(|entry| entry.line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        (text(LogStream::Stdout), text(LogStream::Stderr))
+    }
+}
+
+/// The last `limit` lines matching `run` and `stream`, oldest first
+fn select(lines: &VecDeque<OvmsLogLine>, run: Option<u64>, stream: Option<LogStream>, limit: usize) -> Vec<OvmsLogLine> {
+    let mut selected: Vec<OvmsLogLine> = lines.iter()
+        .rev()
+        .filter(|entry| run.map_or(true, |run| entry.run == run))
+        .filter(|entry| stream.map_or(true, |stream| entry.stream == stream))
+        .take(limit)
+        .cloned()
+        .collect();
+    selected.reverse();
+    selected
+}
+
+/// Output of the current run written at or after `since`, oldest first
+pub fn lines_since(since: DateTime<Utc>) -> Vec<String> {
+    let run = RUN.load(Ordering::Relaxed);
+    select(&LINES.lock(), Some(run), None, usize::MAX)
+        .into_iter()
+        .filter(|entry| entry.timestamp >= since)
+        .map(|entry| entry.line)
+        .collect()
+}
+
+/// Recent OVMS output, oldest first; `current_run_only` skips output of earlier starts
+#[tauri::command]
+pub async fn get_ovms_logs(lines: Option<usize>, current_run_only: Option<bool>) -> Result<Vec<OvmsLogLine>, String> {
+    let run = current_run_only.unwrap_or(false).then(|| RUN.load(Ordering::Relaxed));
+    Ok(select(&LINES.lock(), run, None, lines.unwrap_or(200)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(run: u64, stream: LogStream, text: &str) -> OvmsLogLine {
+        OvmsLogLine { run, stream, line: text.to_string(), timestamp: Utc::now() }
+    }
+
+    #[test]
+    fn test_select_filters_and_keeps_order() {
+        let lines: VecDeque<OvmsLogLine> = vec![
+            line(1, LogStream::Stdout, "old"),
+            line(2, LogStream::Stdout, "a"),
+            line(2, LogStream::Stderr, "b"),
+            line(2, LogStream::Stdout, "c"),
+        ].into();
+
+        let texts = |selected: Vec<OvmsLogLine>| selected.into_iter().map(|l| l.line).collect::<Vec<_>>();
+        assert_eq!(texts(select(&lines, Some(2), Some(LogStream::Stdout), 10)), vec!["a", "c"]);
+        assert_eq!(texts(select(&lines, None, None, 2)), vec!["b", "c"]);
+    }
+}
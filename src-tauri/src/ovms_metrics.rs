@@ -0,0 +1,342 @@
+//! Scraping of the OVMS Prometheus endpoint for the performance dashboard.
+//!
+//! With `ovms.metrics` on, OVMS is started with `--metrics_enable` and this
+//! task reads `/metrics` every `ovms.metrics_interval_secs`. Counters are
+//! turned into rates and latency histograms into percentiles over the last
+//! interval, then emitted as `ovms-metrics`. `get_ovms_metrics_snapshot`
+//! returns the latest result.
+
+use std::collections::BTreeMap;
+use std::time::{ Duration, Instant };
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager, State };
+
+use crate::state::AppState;
+use crate::{ ovms, settings };
+
+/// Counters of served requests: model requests end in success or fail,
+/// MediaPipe graph (LLM) requests are counted when accepted
+const REQUEST_COUNTERS: &[&str] = &["ovms_requests_success", "ovms_requests_fail", "ovms_requests_accepted"];
+const QUEUE_GAUGES: &[&str] = &["ovms_infer_req_queue_size"];
+const ACTIVE_GAUGES: &[&str] = &["ovms_infer_req_active", "ovms_current_graphs"];
+const LATENCY_HISTOGRAM: &str = "ovms_request_time_us_bucket";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyPercentiles {
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+}
+
+/// Payload of `ovms-metrics` events
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OvmsMetrics {
+    pub scraped_at: Option<DateTime<Utc>>,
+    pub requests_total: u64,
+    /// Over the last interval; unset for the first scrape
+    pub requests_per_second: Option<f64>,
+    pub queue_size: u64,
+    pub active_requests: u64,
+    /// Of requests finished during the last interval
+    pub latency: LatencyPercentiles,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: f64,
+}
+
+/// Parse the Prometheus text format; comments and malformed lines are skipped
+fn parse_samples(text: &str) -> Vec<Sample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(parse_line)
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<Sample> {
+    let (name, labels, rest) = match line.find('{') {
+        Some(open) => {
+            let close = line.rfind('}')?;
+            (&line[..open], parse_labels(&line[open + 1..close]), &line[close + 1..])
+        }
+        None => {
+            let (name, rest) = line.split_once(char::is_whitespace)?;
+            (name, Vec::new(), rest)
+        }
+    };
+    let value = rest.split_whitespace().next()?;
+    let value = match value {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        other => other.parse().ok()?,
+    };
+    Some(Sample { name: name.trim().to_string(), labels, value })
+}
+
+fn parse_labels(text: &str) -> Vec<(String, String)> {
+    let mut labels = Vec::new();
+    let mut rest = text;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().trim_start_matches(',').trim().to_string();
+        let Some(after) = rest[eq + 1..].trim_start().strip_prefix('"') else {
+            break;
+        };
+
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(if escaped == 'n' { '\n' } else { escaped });
+                    }
+                }
+                '"' => {
+                    end = Some(i);
+                    break;
+                }
+                c => value.push(c),
+            }
+        }
+        let Some(end) = end else {
+            break;
+        };
+        labels.push((key, value));
+        rest = &after[end + 1..];
+    }
+    labels
+}
+
+fn label<'a>(sample: &'a Sample, key: &str) -> Option<&'a str> {
+    sample.labels.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn sum(samples: &[Sample], names: &[&str]) -> f64 {
+    samples.iter()
+        .filter(|s| names.contains(&s.name.as_str()) && s.value.is_finite())
+        .map(|s| s.value)
+        .sum()
+}
+
+/// Cumulative latency histogram summed over all models, keyed by bucket bound
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Histogram(BTreeMap<u64, f64>);
+
+impl Histogram {
+    fn from_samples(samples: &[Sample]) -> Self {
+        let mut buckets = BTreeMap::new();
+        for sample in samples.iter().filter(|s| s.name == LATENCY_HISTOGRAM) {
+            let Some(le) = label(sample, "le") else {
+                continue;
+            };
+            let bound = if le == "+Inf" { f64::INFINITY } else { le.parse().unwrap_or(f64::NAN) };
+            if bound.is_nan() {
+                continue;
+            }
+            *buckets.entry(bound.to_bits()).or_insert(0.0) += sample.value;
+        }
+        Self(buckets)
+    }
+
+    /// Buckets as (upper bound, cumulative count), ascending
+    fn buckets(&self) -> Vec<(f64, f64)> {
+        let mut buckets: Vec<(f64, f64)> = self.0.iter().map(|(bits, count)| (f64::from_bits(*bits), *count)).collect();
+        buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+        buckets
+    }
+
+    /// Observations since `earlier`
+    fn since(&self, earlier: &Histogram) -> Histogram {
+        Histogram(
+            self.0.iter()
+                .map(|(bound, count)| (*bound, (count - earlier.0.get(bound).copied().unwrap_or(0.0)).max(0.0)))
+                .collect()
+        )
+    }
+
+    /// Quantile `q` by linear interpolation within its bucket
+    fn quantile(&self, q: f64) -> Option<f64> {
+        let buckets = self.buckets();
+        let total = buckets.last()?.1;
+        if total <= 0.0 {
+            return None;
+        }
+        let target = q * total;
+        let mut lower = (0.0, 0.0);
+        for (bound, count) in buckets {
+            if count >= target {
+                if bound.is_infinite() {
+                    return Some(lower.0);
+                }
+                let in_bucket = count - lower.1;
+                let fraction = if in_bucket > 0.0 { (target - lower.1) / in_bucket } else { 1.0 };
+                return Some(lower.0 + (bound - lower.0) * fraction);
+            }
+            lower = (bound, count);
+        }
+        None
+    }
+}
+
+/// One scrape, kept to compute rates against the next
+#[derive(Debug, Clone)]
+struct Scrape {
+    at: Instant,
+    requests: f64,
+    latency: Histogram,
+}
+
+async fn scrape() -> Result<(Scrape, Vec<Sample>), String> {
+    let response = crate::http::client_with_timeout(Duration::from_secs(5))?
+        .get(format!("{}/metrics", ovms::api_base()))
+        .send().await
+        .map_err(|e| format!("Failed to reach OVMS metrics: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("OVMS metrics request failed with status: {}", response.status()));
+    }
+    let text = response.text().await.map_err(|e| format!("Failed to read OVMS metrics: {}", e))?;
+
+    let samples = parse_samples(&text);
+    let scrape = Scrape {
+        at: Instant::now(),
+        requests: sum(&samples, REQUEST_COUNTERS),
+        latency: Histogram::from_samples(&samples),
+    };
+    Ok((scrape, samples))
+}
+
+fn summarize(current: &Scrape, samples: &[Sample], previous: Option<&Scrape>) -> OvmsMetrics {
+    // Counters restart with OVMS; a drop means there is nothing to compare
+    let previous = previous.filter(|p| p.requests <= current.requests);
+    let window = previous.map(|p| current.latency.since(&p.latency)).unwrap_or_else(|| current.latency.clone());
+    let to_ms = |us: Option<f64>| us.map(|us| us / 1000.0);
+
+    OvmsMetrics {
+        scraped_at: Some(Utc::now()),
+        requests_total: current.requests as u64,
+        requests_per_second: previous.and_then(|p| {
+            let secs = current.at.duration_since(p.at).as_secs_f64();
+            (secs > 0.0).then(|| (current.requests - p.requests) / secs)
+        }),
+        queue_size: sum(samples, QUEUE_GAUGES) as u64,
+        active_requests: sum(samples, ACTIVE_GAUGES) as u64,
+        latency: LatencyPercentiles {
+            p50_ms: to_ms(window.quantile(0.5)),
+            p90_ms: to_ms(window.quantile(0.9)),
+            p99_ms: to_ms(window.quantile(0.99)),
+        },
+    }
+}
+
+fn store(app: &AppHandle, metrics: &OvmsMetrics) {
+    *app.state::<AppState>().ovms_metrics.lock() = metrics.clone();
+}
+
+/// Runs for the life of the app
+pub async fn run(app: AppHandle) {
+    let mut previous: Option<Scrape> = None;
+
+    loop {
+        let config = settings::current().ovms;
+        tokio::time::sleep(Duration::from_secs(config.metrics_interval_secs.max(1))).await;
+        if !config.metrics {
+            previous = None;
+            continue;
+        }
+
+        match scrape().await {
+            Ok((current, samples)) => {
+                let metrics = summarize(&current, &samples, previous.as_ref());
+                store(&app, &metrics);
+                let _ = app.emit("ovms-metrics", &metrics);
+                previous = Some(current);
+            }
+            Err(e) => {
+                // Usual while OVMS starts, or when it runs without --metrics_enable
+                tracing::debug!(error = %e, "OVMS metrics scrape failed");
+                previous = None;
+            }
+        }
+    }
+}
+
+/// Latest metrics; scrapes once if the background task has none yet
+#[tauri::command]
+pub async fn get_ovms_metrics_snapshot(app: AppHandle, state: State<'_, AppState>) -> Result<OvmsMetrics, String> {
+    if !settings::current().ovms.metrics {
+        return Err("OVMS metrics are disabled; enable ovms.metrics and restart OVMS".to_string());
+    }
+    let latest = state.ovms_metrics.lock().clone();
+    if latest.scraped_at.is_some() {
+        return Ok(latest);
+    }
+
+    let (current, samples) = scrape().await?;
+    let metrics = summarize(&current, &samples, None);
+    store(&app, &metrics);
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METRICS: &str = r#"# HELP ovms_requests_success Number of successful requests to a model or a DAG.
+# TYPE ovms_requests_success counter
+ovms_requests_success{api="KServe",interface="REST",method="ModelInfer",name="embed",version="1"} 10
+ovms_requests_accepted{method="OpenAI",name="llm"} 30
+ovms_infer_req_queue_size{name="embed",version="1"} 2
+ovms_current_graphs{name="llm"} 1
+ovms_request_time_us_bucket{interface="REST",name="embed",version="1",le="1000"} 4
+ovms_request_time_us_bucket{interface="REST",name="embed",version="1",le="10000"} 8
+ovms_request_time_us_bucket{interface="REST",name="embed",version="1",le="+Inf"} 10
+"#;
+
+    #[test]
+    fn test_parse_samples() {
+        let samples = parse_samples(METRICS);
+        assert_eq!(samples.len(), 7);
+        assert_eq!(samples[0].name, "ovms_requests_success");
+        assert_eq!(label(&samples[0], "api"), Some("KServe"));
+        assert_eq!(sum(&samples, REQUEST_COUNTERS), 40.0);
+
+        let escaped = parse_line(r#"m{a="x\"y",b="z"} 1.5"#).unwrap();
+        assert_eq!(escaped.labels, vec![("a".to_string(), "x\"y".to_string()), ("b".to_string(), "z".to_string())]);
+        assert_eq!(parse_line("plain 3").unwrap().value, 3.0);
+        assert!(parse_line("broken").is_none());
+    }
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let histogram = Histogram::from_samples(&parse_samples(METRICS));
+        // 5th of 10 observations: one into the (1000, 10000] bucket of four
+        assert_eq!(histogram.quantile(0.5), Some(3250.0));
+        // Beyond the last finite bucket the bound is the best estimate
+        assert_eq!(histogram.quantile(0.99), Some(10000.0));
+        assert_eq!(Histogram::default().quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_summarize_rates_over_interval() {
+        let samples = parse_samples(METRICS);
+        let now = Instant::now();
+        let current = Scrape { at: now, requests: 40.0, latency: Histogram::from_samples(&samples) };
+        let previous = Scrape { at: now - Duration::from_secs(10), requests: 20.0, latency: current.latency.clone() };
+
+        let metrics = summarize(&current, &samples, Some(&previous));
+        assert_eq!(metrics.requests_per_second, Some(2.0));
+        assert_eq!(metrics.queue_size, 2);
+        assert_eq!(metrics.active_requests, 1);
+        // Nothing finished in between
+        assert_eq!(metrics.latency.p50_ms, None);
+
+        assert_eq!(summarize(&current, &samples, None).requests_per_second, None);
+    }
+}
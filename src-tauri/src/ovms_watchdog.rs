@@ -0,0 +1,222 @@
+//! Keeps the OVMS server this app started alive.
+//!
+//! Every `ovms.watchdog_interval_secs` the watchdog checks that the child
+//! process is still running and that `/v1/config` answers, and emits the result
+//! as `ovms-health`. When the process has exited, or the API stops answering
+//! several times in a row, the server is restarted with exponential backoff.
+//! The restart reuses `models_config.json` as it is on disk, so the models that
+//! were served before come back without being loaded again by hand. A failed
+//! restart leaves no process behind; it stays pending and is retried after the
+//! backoff rather than being mistaken for a server that was never started.
+
+use std::time::Duration;
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager, State };
+
+use crate::state::AppState;
+use crate::{ notifications, ovms, settings };
+
+/// Failed API checks in a row before a running process is treated as hung
+const UNRESPONSIVE_CHECKS: u32 = 3;
+
+/// First restart delay; doubled after every failed restart
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(2);
+
+/// Longest wait between restart attempts
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Not watched: OVMS was not started by this app (yet)
+    Idle,
+    Healthy,
+    /// The API did not answer, but not often enough to restart yet
+    Degraded,
+    Restarting,
+    /// The last restart failed; the next attempt waits for the backoff
+    Down,
+}
+
+/// Payload of `ovms-health` events
+#[derive(Debug, Clone, Serialize)]
+pub struct OvmsHealth {
+    pub status: HealthStatus,
+    pub checked_at: Option<DateTime<Utc>>,
+    pub loaded_models: Vec<String>,
+    pub consecutive_failures: u32,
+    /// Restarts done by the watchdog since launch
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+impl Default for OvmsHealth {
+    fn default() -> Self {
+        Self {
+            status: HealthStatus::Idle,
+            checked_at: None,
+            loaded_models: Vec::new(),
+            consecutive_failures: 0,
+            restarts: 0,
+            last_error: None,
+        }
+    }
+}
+
+/// Wait before restart attempt `attempt` (0-based)
+fn backoff(attempt: u32) -> Duration {
+    RESTART_BACKOFF_BASE
+        .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+        .min(RESTART_BACKOFF_MAX)
+}
+
+fn publish(app: &AppHandle, f: impl FnOnce(&mut OvmsHealth)) {
+    let health = {
+        let state = app.state::<AppState>();
+        let mut health = state.ovms_health.lock();
+        f(&mut health);
+        health.checked_at = Some(Utc::now());
+        health.clone()
+    };
+    let _ = app.emit("ovms-health", health);
+}
+
+/// `Some(reason)` when the child process has exited; `None` while it runs or
+/// when this app has no child (nothing to watch)
+fn exited_child(app: &AppHandle) -> Option<Option<String>> {
+    let state = app.state::<AppState>();
+    let mut process = state.ovms_process.lock();
+    let child = process.as_mut()?;
+    match child.try_wait() {
+        Ok(Some(status)) => Some(Some(format!("OVMS exited with status: {}", status))),
+        Ok(None) => Some(None),
+        Err(e) => Some(Some(format!("Failed to check OVMS process: {}", e))),
+    }
+}
+
+async fn restart(app: &AppHandle, reason: &str) -> Result<(), String> {
+    log_warning!("Restarting OVMS", reason = %reason);
+    publish(app, |health| {
+        health.status = HealthStatus::Restarting;
+        health.last_error = Some(reason.to_string());
+    });
+
+    // Reaps the dead child and frees its port before starting again
+    ovms::stop_ovms_server(app)?;
+    ovms::start_ovms_server(app.clone()).await?;
+    Ok(())
+}
+
+/// Runs for the life of the app
+pub async fn run(app: AppHandle) {
+    let mut restart_attempts: u32 = 0;
+    let mut next_restart = tokio::time::Instant::now();
+    // Why the server is down after a failed restart, until it runs again
+    let mut pending_restart: Option<String> = None;
+
+    loop {
+        let config = settings::current().ovms;
+        tokio::time::sleep(Duration::from_secs(config.watchdog_interval_secs.max(1))).await;
+        if !config.watchdog {
+            continue;
+        }
+
+        let failure = match exited_child(&app) {
+            None if pending_restart.is_some() => pending_restart.clone().map(|reason| (reason, true)),
+            // Not started by us, or stopped on purpose
+            None => {
+                publish(&app, |health| health.status = HealthStatus::Idle);
+                continue;
+            }
+            Some(Some(reason)) => Some((reason, true)),
+            Some(None) => match ovms::check_ovms_status().await {
+                Ok(status) => {
+                    if restart_attempts > 0 {
+                        log_operation_success!("OVMS recovered", restarts = restart_attempts);
+                    }
+                    restart_attempts = 0;
+                    pending_restart = None;
+                    publish(&app, |health| {
+                        health.status = HealthStatus::Healthy;
+                        health.loaded_models = status.loaded_models;
+                        health.consecutive_failures = 0;
+                        health.last_error = None;
+                    });
+                    None
+                }
+                Err(e) => Some((e, false)),
+            },
+        };
+
+        let Some((reason, exited)) = failure else {
+            continue;
+        };
+
+        let failures = {
+            let state = app.state::<AppState>();
+            let mut health = state.ovms_health.lock();
+            health.consecutive_failures += 1;
+            health.consecutive_failures
+        };
+        if !exited && failures < UNRESPONSIVE_CHECKS {
+            publish(&app, |health| {
+                health.status = HealthStatus::Degraded;
+                health.last_error = Some(reason);
+            });
+            continue;
+        }
+
+        if tokio::time::Instant::now() < next_restart {
+            continue;
+        }
+
+        match restart(&app, &reason).await {
+            Ok(()) => {
+                publish(&app, |health| {
+                    health.status = HealthStatus::Healthy;
+                    health.consecutive_failures = 0;
+                    health.restarts += 1;
+                });
+                notifications::record(
+                    &app,
+                    "OVMS restarted",
+                    &format!("The model server stopped responding and was restarted. Reason: {}", reason),
+                    "ovms"
+                );
+                restart_attempts = 0;
+                pending_restart = None;
+            }
+            Err(e) => {
+                let delay = backoff(restart_attempts);
+                restart_attempts += 1;
+                next_restart = tokio::time::Instant::now() + delay;
+                pending_restart = Some(reason);
+                log_operation_error!("OVMS restart", &e, attempt = restart_attempts, retry_in_secs = delay.as_secs());
+                publish(&app, |health| {
+                    health.status = HealthStatus::Down;
+                    health.last_error = Some(e);
+                });
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_ovms_health(state: State<'_, AppState>) -> Result<OvmsHealth, String> {
+    Ok(state.ovms_health.lock().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        assert_eq!(backoff(0), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(16));
+        assert_eq!(backoff(10), RESTART_BACKOFF_MAX);
+        assert_eq!(backoff(u32::MAX), RESTART_BACKOFF_MAX);
+    }
+}
@@ -0,0 +1,170 @@
+//! Where filesystem-touching commands and tools may go.
+//!
+//! Paths from the webview or from a model are untrusted. Before using one,
+//! commands call [`check`], which resolves it (following symlinks, so a link
+//! cannot point out of scope) and requires it to sit under an allowed root:
+//!
+//! - reads: the home directory plus `filesystem.read_roots`, and everything
+//!   writable;
+//! - writes and deletes: `~/.sparrow` plus `filesystem.write_roots`
+//!   (e.g. a model folder on another drive).
+//!
+//! Deleting a root itself, or a symlink in place of a directory, is refused.
+
+use std::path::{ Component, Path, PathBuf };
+
+use crate::{ paths, settings };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Delete,
+}
+
+fn roots(access: Access) -> Vec<PathBuf> {
+    let filesystem = settings::current().filesystem;
+
+    // Everything writable is readable too
+    let mut roots: Vec<PathBuf> = paths::get_sparrow_dir().into_iter().collect();
+    let mut extra = filesystem.write_roots;
+    if access == Access::Read {
+        roots.extend(paths::get_home_dir());
+        extra.extend(filesystem.read_roots);
+    }
+    roots.extend(extra.into_iter().filter(|root| !root.trim().is_empty()).map(PathBuf::from));
+    roots
+}
+
+/// Resolve `path` and make sure it is within scope for `access`
+pub fn check(path: &Path, access: Access) -> Result<PathBuf, String> {
+    check_within(path, &roots(access), access)
+}
+
+/// Same as [`check`] for a path given as a string
+pub fn check_str(path: &str, access: Access) -> Result<PathBuf, String> {
+    check(Path::new(path), access)
+}
+
+pub(crate) fn check_within(path: &Path, roots: &[PathBuf], access: Access) -> Result<PathBuf, String> {
+    if path.as_os_str().is_empty() {
+        return Err("Path is empty".to_string());
+    }
+    if !path.is_absolute() {
+        return Err(format!("Path must be absolute: {}", path.display()));
+    }
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(format!("Path must not contain '..': {}", path.display()));
+    }
+
+    if access == Access::Delete {
+        let is_link = std::fs::symlink_metadata(path).map(|m| m.file_type().is_symlink()).unwrap_or(false);
+        if is_link {
+            return Err(format!("Refusing to delete through a symbolic link: {}", path.display()));
+        }
+    }
+
+    let resolved = resolve(path)?;
+    let roots: Vec<PathBuf> = roots.iter().filter_map(|root| resolve(root).ok()).collect();
+
+    let Some(root) = roots.iter().find(|root| resolved.starts_with(root)) else {
+        tracing::warn!(path = %path.display(), ?access, "Path outside allowed roots");
+        return Err(format!("Access denied: {} is outside the allowed folders", path.display()));
+    };
+
+    if access == Access::Delete && &resolved == root {
+        return Err(format!("Refusing to delete an allowed root folder: {}", path.display()));
+    }
+
+    Ok(resolved)
+}
+
+/// Canonicalize a path that may not exist yet: the longest existing prefix
+/// is resolved (symlinks included) and the rest appended as is
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(canonical) => {
+                return Ok(rest.iter().rev().fold(canonical, |acc, part| acc.join(part)));
+            }
+            Err(_) => {
+                let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                    return Err(format!("Cannot resolve path: {}", path.display()));
+                };
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+        }
+    }
+}
+
+/// Validate a bare file name from the frontend (no separators or `..`)
+pub fn safe_file_name(name: &str) -> Result<&str, String> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(part)), None) if part == name && !name.contains(['/', '\\']) => Ok(name),
+        _ => Err(format!("Invalid file name: {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sparrow-policy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("root").join("inner")).unwrap();
+        std::fs::create_dir_all(dir.join("outside")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_paths_inside_root_allowed() {
+        let dir = scratch();
+        let roots = vec![dir.join("root")];
+
+        assert!(check_within(&dir.join("root").join("inner"), &roots, Access::Read).is_ok());
+        assert!(check_within(&dir.join("root").join("new").join("file.txt"), &roots, Access::Write).is_ok());
+        assert!(check_within(&dir.join("root").join("inner"), &roots, Access::Delete).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_traversal_and_outside_paths_rejected() {
+        let dir = scratch();
+        let roots = vec![dir.join("root")];
+
+        assert!(check_within(&dir.join("outside"), &roots, Access::Read).is_err());
+        assert!(check_within(&dir.join("root").join("..").join("outside"), &roots, Access::Read).is_err());
+        assert!(check_within(Path::new("relative/path"), &roots, Access::Read).is_err());
+        assert!(check_within(&dir.join("root"), &roots, Access::Delete).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_rejected() {
+        let dir = scratch();
+        let roots = vec![dir.join("root")];
+        let link = dir.join("root").join("link");
+        std::os::unix::fs::symlink(dir.join("outside"), &link).unwrap();
+
+        assert!(check_within(&link, &roots, Access::Read).is_err());
+        assert!(check_within(&link, &roots, Access::Delete).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_safe_file_name() {
+        assert!(safe_file_name("report.pdf").is_ok());
+        assert!(safe_file_name("../report.pdf").is_err());
+        assert!(safe_file_name("dir/report.pdf").is_err());
+        assert!(safe_file_name("..").is_err());
+        assert!(safe_file_name("").is_err());
+    }
+}
@@ -69,6 +69,20 @@ pub fn get_vector_store_path() -> Result<PathBuf> {
     Ok(db_dir)
 }
 
+/// Get the knowledge graph database path
+pub fn get_knowledge_graph_path() -> Result<PathBuf> {
+    let db_dir = get_sparrow_dir()?.join("knowledge_graph");
+    ensure_dir_exists(&db_dir)?;
+    Ok(db_dir)
+}
+
+/// Get the directory holding the memory-mapped embedding matrices
+pub fn get_embedding_matrix_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("vector_index");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
 /// Get the MCP config file path
 pub fn get_mcp_config_path(_app_handle: &AppHandle) -> Result<PathBuf> {
     let config_dir = get_sparrow_dir()?.join("mcp");
@@ -87,6 +101,36 @@ pub fn get_tasks_path() -> Result<PathBuf> {
     Ok(get_sparrow_dir()?.join("tasks.json"))
 }
 
+/// Get the focus sessions file path
+pub fn get_focus_sessions_path() -> Result<PathBuf> {
+    Ok(get_sparrow_dir()?.join("focus_sessions.json"))
+}
+
+/// Get the file folders watched for RAG ingestion are saved in
+pub fn get_watched_folders_path() -> Result<PathBuf> {
+    Ok(get_sparrow_dir()?.join("watched_folders.json"))
+}
+
+/// Get the backend settings file path
+pub fn get_settings_path() -> Result<PathBuf> {
+    Ok(get_sparrow_dir()?.join("settings.json"))
+}
+
+/// Get the saved quick actions file path
+pub fn get_quick_actions_path() -> Result<PathBuf> {
+    Ok(get_sparrow_dir()?.join("quick_actions.json"))
+}
+
+/// Get the saved system-prompt presets file path
+pub fn get_prompts_path() -> Result<PathBuf> {
+    Ok(get_sparrow_dir()?.join("prompts.json"))
+}
+
+/// Get the OVMS initialization history file path
+pub fn get_init_history_path() -> Result<PathBuf> {
+    Ok(get_sparrow_dir()?.join("init_history.json"))
+}
+
 /// Get the images directory path
 pub fn get_images_dir() -> Result<PathBuf> {
     let dir = get_sparrow_dir()?.join("images");
@@ -94,6 +138,73 @@ pub fn get_images_dir() -> Result<PathBuf> {
     Ok(dir)
 }
 
+/// Get the folder for files a chat session produces (e.g. charts); files
+/// from outside a session go to `unsorted`
+pub fn get_session_assets_dir(session_id: Option<&str>) -> Result<PathBuf> {
+    let name = session_id
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or("unsorted");
+    let dir = get_sparrow_dir()?.join("assets").join(name);
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the directory app update installers are downloaded to
+pub fn get_updates_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("updates");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the .sparrow/plugins directory, one subdirectory per plugin
+pub fn get_plugins_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("plugins");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the directory holding WASI interpreters for sandboxed code
+pub fn get_runtimes_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("runtimes");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the .sparrow/tmp directory uploads are staged in before processing
+pub fn get_temp_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("tmp");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the default folder session bundles are exported to
+pub fn get_exports_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("exports");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the directory in-progress chat answers are checkpointed to
+pub fn get_drafts_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("drafts");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the directory generated flashcards and their history are kept in
+pub fn get_flashcards_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("flashcards");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// Get the default folder daily notes are kept in
+pub fn get_journal_dir() -> Result<PathBuf> {
+    let dir = get_sparrow_dir()?.join("journal");
+    ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
 /// Get the OpenVINO model path for a specific model
 #[allow(dead_code)]
 pub fn get_openvino_model_path(model_name: &str) -> Result<PathBuf> {
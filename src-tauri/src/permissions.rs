@@ -0,0 +1,247 @@
+//! Confirmation gate for destructive commands.
+//!
+//! Any script running in the webview can `invoke` any registered command, so
+//! a compromised or buggy page could wipe a folder or the document store.
+//! Sensitive commands therefore need either a confirmation token or an
+//! elevated window. Both are only handed out after the user accepts a native
+//! dialog, which the webview cannot answer on its own:
+//!
+//! - `request_command_confirmation` returns a single-use token for one
+//!   command, valid for a short time and only from the window that asked;
+//! - `elevate_permissions` lets that window run sensitive commands without
+//!   tokens for a few minutes (e.g. running several built-in tools in a row).
+
+use std::time::{ Duration, Instant };
+
+use serde::Serialize;
+use tauri::{ AppHandle, Manager, State, Window };
+use tauri_plugin_dialog::{ DialogExt, MessageDialogButtons, MessageDialogKind };
+use tokio::sync::oneshot;
+
+use crate::state::AppState;
+
+/// Commands that refuse to run without confirmation
+pub const SENSITIVE_COMMANDS: &[&str] = &[
+    "delete_directory",
+    "execute_builtin_tool",
+    "clear_all_documents",
+    "clear_vector_store",
+];
+
+const TOKEN_TTL: Duration = Duration::from_secs(60);
+const MAX_ELEVATION: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_ELEVATION_SECS: u64 = 5 * 60;
+
+/// A token issued for one command on one window
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    pub command: String,
+    pub window: String,
+    pub expires_at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmationToken {
+    pub token: String,
+    pub command: String,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ElevationStatus {
+    pub elevated: bool,
+    pub remaining_secs: u64,
+}
+
+pub fn is_sensitive(command: &str) -> bool {
+    SENSITIVE_COMMANDS.contains(&command)
+}
+
+/// Check that `window` may run `command` now, consuming `token` if one is given
+pub fn authorize(state: &AppState, window: &str, command: &str, token: Option<&str>) -> Result<(), String> {
+    let now = Instant::now();
+
+    if let Some(token) = token {
+        let mut confirmations = state.confirmations.lock();
+        confirmations.retain(|_, c| c.expires_at > now);
+        // Single use: a matching token is spent even if it was for another window
+        if let Some(confirmation) = confirmations.remove(token) {
+            if confirmation.command == command && confirmation.window == window {
+                return Ok(());
+            }
+            tracing::warn!(
+                command,
+                window,
+                issued_for = %confirmation.command,
+                issued_to = %confirmation.window,
+                "Confirmation token used for a different command or window"
+            );
+        }
+    }
+
+    if let Some(until) = state.elevated_windows.lock().get(window) {
+        if *until > now {
+            return Ok(());
+        }
+    }
+
+    tracing::warn!(command, window, "Blocked sensitive command without confirmation");
+    Err(format!("'{}' requires confirmation; call request_command_confirmation first", command))
+}
+
+/// Ask the user through a native dialog; `false` when declined or closed
+async fn ask_user(app: &AppHandle, title: &str, message: String) -> bool {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancel)
+        .show(move |accepted| {
+            let _ = tx.send(accepted);
+        });
+    rx.await.unwrap_or(false)
+}
+
+fn describe(command: &str, detail: Option<&str>) -> String {
+    let action = match command {
+        "delete_directory" => "Delete a folder and everything in it",
+        "execute_builtin_tool" => "Run a built-in tool",
+        "clear_all_documents" => "Remove all documents from the knowledge base",
+        "clear_vector_store" => "Delete the whole vector store, including every document collection",
+        _ => command,
+    };
+    match detail {
+        Some(detail) if !detail.is_empty() => format!("{}:\n{}\n\nAllow this?", action, detail),
+        _ => format!("{}.\n\nAllow this?", action),
+    }
+}
+
+/// Ask the user to confirm one sensitive command and return a token for it
+#[tauri::command]
+pub async fn request_command_confirmation(
+    app: AppHandle,
+    window: Window,
+    command: String,
+    detail: Option<String>
+) -> Result<ConfirmationToken, String> {
+    if !is_sensitive(&command) {
+        return Err(format!("'{}' does not need confirmation", command));
+    }
+
+    if !ask_user(&app, "Confirm action", describe(&command, detail.as_deref())).await {
+        return Err("Action was not confirmed".to_string());
+    }
+
+    let token = uuid::Uuid::new_v4().to_string();
+    app.state::<AppState>().confirmations.lock().insert(token.clone(), Confirmation {
+        command: command.clone(),
+        window: window.label().to_string(),
+        expires_at: Instant::now() + TOKEN_TTL,
+    });
+    tracing::info!(command = %command, window = %window.label(), "Issued confirmation token");
+
+    Ok(ConfirmationToken {
+        token,
+        command,
+        expires_in_secs: TOKEN_TTL.as_secs(),
+    })
+}
+
+/// Let the calling window run sensitive commands without tokens for a while
+#[tauri::command]
+pub async fn elevate_permissions(
+    app: AppHandle,
+    window: Window,
+    duration_secs: Option<u64>
+) -> Result<ElevationStatus, String> {
+    let duration = Duration::from_secs(duration_secs.unwrap_or(DEFAULT_ELEVATION_SECS)).min(MAX_ELEVATION);
+    let message = format!(
+        "Allow destructive actions (deleting folders, clearing documents, running built-in tools) without asking for the next {} minutes?",
+        duration.as_secs().div_ceil(60)
+    );
+    if !ask_user(&app, "Allow destructive actions", message).await {
+        return Err("Elevation was not confirmed".to_string());
+    }
+
+    app.state::<AppState>().elevated_windows.lock().insert(window.label().to_string(), Instant::now() + duration);
+    tracing::info!(window = %window.label(), secs = duration.as_secs(), "Elevated permissions");
+
+    Ok(ElevationStatus {
+        elevated: true,
+        remaining_secs: duration.as_secs(),
+    })
+}
+
+/// End elevation early for the calling window
+#[tauri::command]
+pub async fn drop_elevated_permissions(state: State<'_, AppState>, window: Window) -> Result<(), String> {
+    state.elevated_windows.lock().remove(window.label());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_elevation_status(state: State<'_, AppState>, window: Window) -> Result<ElevationStatus, String> {
+    let remaining = state.elevated_windows
+        .lock()
+        .get(window.label())
+        .map(|until| until.saturating_duration_since(Instant::now()))
+        .unwrap_or_default();
+    Ok(ElevationStatus {
+        elevated: !remaining.is_zero(),
+        remaining_secs: remaining.as_secs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(state: &AppState, token: &str, command: &str, window: &str, ttl: Duration) {
+        state.confirmations.lock().insert(token.to_string(), Confirmation {
+            command: command.to_string(),
+            window: window.to_string(),
+            expires_at: Instant::now() + ttl,
+        });
+    }
+
+    #[test]
+    fn test_token_is_single_use() {
+        let state = AppState::default();
+        issue(&state, "t1", "delete_directory", "main", TOKEN_TTL);
+
+        assert!(authorize(&state, "main", "delete_directory", Some("t1")).is_ok());
+        assert!(authorize(&state, "main", "delete_directory", Some("t1")).is_err());
+    }
+
+    #[test]
+    fn test_token_bound_to_command_and_window() {
+        let state = AppState::default();
+        issue(&state, "t1", "delete_directory", "main", TOKEN_TTL);
+        issue(&state, "t2", "delete_directory", "main", TOKEN_TTL);
+
+        assert!(authorize(&state, "main", "clear_all_documents", Some("t1")).is_err());
+        assert!(authorize(&state, "other", "delete_directory", Some("t2")).is_err());
+        // Misused tokens are spent
+        assert!(authorize(&state, "main", "delete_directory", Some("t1")).is_err());
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let state = AppState::default();
+        issue(&state, "t1", "clear_all_documents", "main", Duration::ZERO);
+
+        assert!(authorize(&state, "main", "clear_all_documents", Some("t1")).is_err());
+        assert!(state.confirmations.lock().is_empty());
+    }
+
+    #[test]
+    fn test_elevated_window_needs_no_token() {
+        let state = AppState::default();
+        assert!(authorize(&state, "main", "execute_builtin_tool", None).is_err());
+
+        state.elevated_windows.lock().insert("main".to_string(), Instant::now() + Duration::from_secs(60));
+        assert!(authorize(&state, "main", "execute_builtin_tool", None).is_ok());
+        assert!(authorize(&state, "other", "execute_builtin_tool", None).is_err());
+    }
+}
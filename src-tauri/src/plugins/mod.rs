@@ -0,0 +1,525 @@
+//! Third-party plugins discovered in `~/.sparrow/plugins`.
+//!
+//! Each plugin is a directory with a `plugin.json` manifest and a WASI module.
+//! A plugin can add tools (offered to chat and tasks next to the built-in
+//! ones) and document parsers (for file types RAG does not read itself).
+//!
+//! Calls use a JSON protocol over stdio. The module gets one request on stdin:
+//! `{"type": "tool", "tool": "<name>", "arguments": {...}}` or
+//! `{"type": "parse", "path": "/input/<file>", "extension": "<ext>"}`, and
+//! answers on stdout with `{"text": "..."}`, `{"sections": [{"title", "text"}]}`
+//! (parsers only) or `{"error": "..."}`.
+//!
+//! Plugins are off until enabled. Enabling records a fingerprint of the module
+//! and its declared capabilities; if either changes, the plugin is treated as
+//! disabled again until the user re-approves it. The module is checked again
+//! every time it is compiled, so one replaced while the app runs is refused.
+
+pub mod wasm;
+
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+use sha2::{ Digest, Sha256 };
+
+use crate::mcp::builtin_tools::{ BuiltinTool, ToolResult };
+use crate::{ constants, paths, settings };
+
+const MANIFEST_FILE: &str = "plugin.json";
+
+/// Guest path of the plugin's own writable directory
+const DATA_MOUNT: &str = "/data";
+
+/// Guest path the file being parsed is mounted under
+const INPUT_MOUNT: &str = "/input";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// WASI module, relative to the plugin directory
+    pub module: String,
+    #[serde(default)]
+    pub tools: Vec<PluginTool>,
+    #[serde(default)]
+    pub parsers: Vec<PluginParser>,
+    #[serde(default)]
+    pub capabilities: PluginCapabilities,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginTool {
+    pub name: String,
+    pub description: String,
+    #[serde(default = "empty_schema")]
+    pub input_schema: Value,
+}
+
+fn empty_schema() -> Value {
+    json!({ "type": "object", "properties": {}, "required": [] })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginParser {
+    /// File extensions without the dot, e.g. `["epub"]`
+    pub extensions: Vec<String>,
+}
+
+/// What a plugin asks to access; nothing by default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginCapabilities {
+    /// Host directories mounted read-only
+    pub read_dirs: Vec<DirGrant>,
+    /// A private writable directory, mounted at `/data`
+    pub data_dir: bool,
+    /// Host environment variables passed through
+    pub env: Vec<String>,
+    /// Capped at `constants::PLUGIN_MAX_MEMORY_MB`
+    pub max_memory_mb: Option<u64>,
+    /// Capped at `constants::PLUGIN_MAX_TIMEOUT_MS`
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirGrant {
+    /// Host path; a leading `~` is the home directory
+    pub host: String,
+    pub guest: String,
+}
+
+#[derive(Debug, Clone)]
+struct Plugin {
+    dir: PathBuf,
+    manifest: PluginManifest,
+    fingerprint: String,
+}
+
+/// A plugin as shown in the plugin list
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub id: String,
+    pub dir: String,
+    pub manifest: Option<PluginManifest>,
+    pub enabled: bool,
+    /// Enabled before, but its module or capabilities changed since
+    pub needs_approval: bool,
+    /// Why the plugin could not be loaded
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct Registry {
+    plugins: Vec<Plugin>,
+    /// Directories that failed to load, with the reason
+    broken: Vec<(PathBuf, String)>,
+}
+
+static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Registry> {
+    REGISTRY.get_or_init(|| RwLock::new(discover()))
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Name a plugin tool is offered under: `<plugin id>_<tool>`, with dashes
+/// mapped to underscores to stay within function-name rules
+fn qualified_tool_name(plugin_id: &str, tool: &str) -> String {
+    format!("{}_{}", plugin_id, tool).replace('-', "_")
+}
+
+/// SHA256 over the module bytes and the declared capabilities
+pub fn fingerprint(module: &[u8], capabilities: &PluginCapabilities) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(module);
+    hasher.update(serde_json::to_vec(capabilities).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
+fn validate(manifest: &PluginManifest, dir: &Path) -> Result<(), String> {
+    if !valid_name(&manifest.id) {
+        return Err(format!("Invalid plugin id '{}': use lowercase letters, digits, '-' and '_'", manifest.id));
+    }
+    let module = dir.join(&manifest.module);
+    if !module.starts_with(dir) || manifest.module.contains("..") {
+        return Err("Plugin module must be inside the plugin directory".to_string());
+    }
+    if !module.is_file() {
+        return Err(format!("Plugin module not found: {}", module.display()));
+    }
+    if let Some(tool) = manifest.tools.iter().find(|tool| !valid_name(&tool.name)) {
+        return Err(format!("Invalid tool name '{}'", tool.name));
+    }
+    for grant in &manifest.capabilities.read_dirs {
+        if !grant.guest.starts_with('/') || grant.guest == DATA_MOUNT || grant.guest == INPUT_MOUNT {
+            return Err(format!("Invalid mount point '{}'", grant.guest));
+        }
+    }
+    Ok(())
+}
+
+fn load_plugin(dir: &Path) -> Result<Plugin, String> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Failed to read {}: {}", MANIFEST_FILE, e))?;
+    let manifest: PluginManifest = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+    validate(&manifest, dir)?;
+
+    let module = std::fs::read(dir.join(&manifest.module))
+        .map_err(|e| format!("Failed to read plugin module: {}", e))?;
+    let fingerprint = fingerprint(&module, &manifest.capabilities);
+
+    Ok(Plugin { dir: dir.to_path_buf(), manifest, fingerprint })
+}
+
+fn discover() -> Registry {
+    let mut registry = Registry::default();
+    let plugins_dir = match paths::get_plugins_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            log_warning!("Failed to resolve plugins directory", error = %e);
+            return registry;
+        }
+    };
+    let entries = match std::fs::read_dir(&plugins_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log_warning!("Failed to read plugins directory", error = %e);
+            return registry;
+        }
+    };
+
+    for dir in entries.filter_map(Result::ok).map(|entry| entry.path()).filter(|path| path.is_dir()) {
+        match load_plugin(&dir) {
+            Ok(plugin) if registry.plugins.iter().any(|p| p.manifest.id == plugin.manifest.id) => {
+                registry.broken.push((dir, format!("Duplicate plugin id '{}'", plugin.manifest.id)));
+            }
+            Ok(plugin) => registry.plugins.push(plugin),
+            Err(e) => {
+                log_warning!("Failed to load plugin", dir = %dir.display(), error = %e);
+                registry.broken.push((dir, e));
+            }
+        }
+    }
+
+    tracing::info!(plugins = registry.plugins.len(), failed = registry.broken.len(), "Discovered plugins");
+    registry
+}
+
+fn is_enabled(plugin: &Plugin, enabled: &HashMap<String, String>) -> bool {
+    enabled.get(&plugin.manifest.id) == Some(&plugin.fingerprint)
+}
+
+/// Enabled plugins, cloned so no lock is held while they run
+fn enabled_plugins() -> Vec<Plugin> {
+    let enabled = settings::current().plugins.enabled;
+    registry().read().plugins.iter()
+        .filter(|plugin| is_enabled(plugin, &enabled))
+        .cloned()
+        .collect()
+}
+
+fn expand_home(path: &str) -> Result<PathBuf, String> {
+    match path.strip_prefix('~') {
+        Some(rest) => {
+            let home = paths::get_home_dir().map_err(|e| e.to_string())?;
+            Ok(home.join(rest.trim_start_matches(['/', '\\'])))
+        }
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+fn sandbox_for(plugin: &Plugin, extra_mounts: Vec<wasm::Mount>) -> Result<wasm::Sandbox, String> {
+    let capabilities = &plugin.manifest.capabilities;
+
+    let mut mounts = extra_mounts;
+    for grant in &capabilities.read_dirs {
+        mounts.push(wasm::Mount { host: expand_home(&grant.host)?, guest: grant.guest.clone(), writable: false });
+    }
+    if capabilities.data_dir {
+        let data = plugin.dir.join("data");
+        std::fs::create_dir_all(&data).map_err(|e| format!("Failed to create plugin data directory: {}", e))?;
+        mounts.push(wasm::Mount { host: data, guest: DATA_MOUNT.to_string(), writable: true });
+    }
+
+    let env = capabilities.env.iter()
+        .filter_map(|key| std::env::var(key).ok().map(|value| (key.clone(), value)))
+        .collect();
+
+    let memory_mb = capabilities.max_memory_mb.unwrap_or(constants::PLUGIN_MAX_MEMORY_MB).min(constants::PLUGIN_MAX_MEMORY_MB);
+    let timeout_ms = capabilities.timeout_ms.unwrap_or(constants::PLUGIN_MAX_TIMEOUT_MS).min(constants::PLUGIN_MAX_TIMEOUT_MS);
+
+    Ok(wasm::Sandbox {
+        args: vec![plugin.manifest.id.clone()],
+        mounts,
+        env,
+        max_memory_bytes: (memory_mb * 1024 * 1024) as usize,
+        timeout: Duration::from_millis(timeout_ms),
+        fuel: None,
+        max_output_bytes: constants::PLUGIN_MAX_OUTPUT_BYTES,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginResponse {
+    text: Option<String>,
+    sections: Option<Vec<ParsedSection>>,
+    error: Option<String>,
+}
+
+/// A titled part of a parsed document
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParsedSection {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+/// Send one request to the plugin and decode its answer
+fn call(plugin: &Plugin, request: &Value, extra_mounts: Vec<wasm::Mount>) -> Result<PluginResponse, String> {
+    let sandbox = sandbox_for(plugin, extra_mounts)?;
+    let input = serde_json::to_vec(request).map_err(|e| format!("Failed to encode plugin request: {}", e))?;
+    let module = plugin.dir.join(&plugin.manifest.module);
+
+    // The module may have been replaced since it was approved
+    let approved = settings::current().plugins.enabled.get(&plugin.manifest.id).cloned();
+    let verify = |bytes: &[u8]| match approved.as_deref() {
+        Some(approved) if fingerprint(bytes, &plugin.manifest.capabilities) == approved => Ok(()),
+        _ => Err("module changed since it was enabled; enable it again to approve the new version".to_string()),
+    };
+
+    let output = wasm::run(&module, &input, &sandbox, &verify)
+        .map_err(|e| format!("Plugin '{}' failed: {}", plugin.manifest.id, e))?;
+    if output.exit_code != 0 {
+        return Err(format!(
+            "Plugin '{}' exited with code {}: {}",
+            plugin.manifest.id,
+            output.exit_code,
+            output.stderr_text()
+        ));
+    }
+
+    let response: PluginResponse = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Plugin '{}' returned invalid JSON: {}", plugin.manifest.id, e))?;
+    match response.error {
+        Some(error) => Err(format!("Plugin '{}': {}", plugin.manifest.id, error)),
+        None => Ok(response),
+    }
+}
+
+/// Tools of all enabled plugins, under their qualified names
+pub fn builtin_tools() -> Vec<BuiltinTool> {
+    enabled_plugins().iter()
+        .flat_map(|plugin| plugin.manifest.tools.iter().map(move |tool| BuiltinTool {
+            name: qualified_tool_name(&plugin.manifest.id, &tool.name),
+            description: format!("{} (plugin: {})", tool.description, plugin.manifest.name),
+            input_schema: tool.input_schema.clone(),
+            hidden_from_task_creation: false,
+        }))
+        .collect()
+}
+
+/// Run a plugin tool by its qualified name; `None` when no enabled plugin has it
+pub async fn execute_tool(name: &str, arguments: Value) -> Option<Result<ToolResult, String>> {
+    let (plugin, tool) = enabled_plugins().into_iter().find_map(|plugin| {
+        let tool = plugin.manifest.tools.iter()
+            .find(|tool| qualified_tool_name(&plugin.manifest.id, &tool.name) == name)?
+            .name.clone();
+        Some((plugin, tool))
+    })?;
+
+    log_operation_start!("Plugin tool", plugin = %plugin.manifest.id, tool = %tool);
+    let request = json!({ "type": "tool", "tool": tool, "arguments": arguments });
+    let result = tokio::task::spawn_blocking(move || call(&plugin, &request, Vec::new())).await
+        .map_err(|e| format!("Plugin task failed: {}", e))
+        .and_then(|result| result)
+        .map(|response| ToolResult::text(response.text.unwrap_or_default()));
+
+    match &result {
+        Ok(_) => { log_operation_success!("Plugin tool", tool = %name); }
+        Err(e) => { log_operation_error!("Plugin tool", e, tool = %name); }
+    }
+    Some(result)
+}
+
+/// A plugin that parses files of one extension
+pub struct ParserHandle {
+    plugin: Plugin,
+    extension: String,
+}
+
+impl ParserHandle {
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin.manifest.id
+    }
+
+    /// Parse `file_path`. Blocks; the file is copied into a scratch directory
+    /// so the plugin sees only that one file.
+    pub fn parse(&self, file_path: &Path) -> Result<Vec<ParsedSection>, String> {
+        let file_name = file_path.file_name()
+            .ok_or_else(|| format!("Invalid file path: {}", file_path.display()))?;
+        let scratch = std::env::temp_dir().join(format!("sparrow-plugin-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&scratch).map_err(|e| format!("Failed to create scratch directory: {}", e))?;
+
+        let result = std::fs::copy(file_path, scratch.join(file_name))
+            .map_err(|e| format!("Failed to stage file for plugin: {}", e))
+            .and_then(|_| {
+                let request = json!({
+                    "type": "parse",
+                    "path": format!("{}/{}", INPUT_MOUNT, file_name.to_string_lossy()),
+                    "extension": self.extension,
+                });
+                let input = wasm::Mount { host: scratch.clone(), guest: INPUT_MOUNT.to_string(), writable: false };
+                call(&self.plugin, &request, vec![input])
+            });
+        let _ = std::fs::remove_dir_all(&scratch);
+
+        let response = result?;
+        Ok(match response.sections {
+            Some(sections) => sections,
+            None => vec![ParsedSection { title: None, text: response.text.unwrap_or_default() }],
+        })
+    }
+}
+
+/// The enabled plugin parsing `extension`, if any
+pub fn parser_for(extension: &str) -> Option<ParserHandle> {
+    let extension = extension.to_lowercase();
+    enabled_plugins().into_iter()
+        .find(|plugin| plugin.manifest.parsers.iter()
+            .any(|parser| parser.extensions.iter().any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(&extension))))
+        .map(|plugin| ParserHandle { plugin, extension })
+}
+
+fn plugin_infos() -> Vec<PluginInfo> {
+    let enabled = settings::current().plugins.enabled;
+    let registry = registry().read();
+
+    let mut infos: Vec<PluginInfo> = registry.plugins.iter()
+        .map(|plugin| PluginInfo {
+            id: plugin.manifest.id.clone(),
+            dir: plugin.dir.display().to_string(),
+            enabled: is_enabled(plugin, &enabled),
+            needs_approval: enabled.contains_key(&plugin.manifest.id) && !is_enabled(plugin, &enabled),
+            manifest: Some(plugin.manifest.clone()),
+            error: None,
+        })
+        .collect();
+    infos.extend(registry.broken.iter().map(|(dir, error)| PluginInfo {
+        id: dir.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        dir: dir.display().to_string(),
+        manifest: None,
+        enabled: false,
+        needs_approval: false,
+        error: Some(error.clone()),
+    }));
+    infos.sort_by(|a, b| a.id.cmp(&b.id));
+    infos
+}
+
+#[tauri::command]
+pub async fn list_plugins() -> Result<Vec<PluginInfo>, String> {
+    Ok(plugin_infos())
+}
+
+/// Rescan the plugins directory, e.g. after installing or updating a plugin
+#[tauri::command]
+pub async fn reload_plugins() -> Result<Vec<PluginInfo>, String> {
+    let discovered = tokio::task::spawn_blocking(discover).await
+        .map_err(|e| format!("Plugin discovery failed: {}", e))?;
+    *registry().write() = discovered;
+    Ok(plugin_infos())
+}
+
+/// Enable a plugin, approving its current module and capabilities
+#[tauri::command]
+pub async fn enable_plugin(plugin_id: String) -> Result<Vec<PluginInfo>, String> {
+    let fingerprint = registry().read().plugins.iter()
+        .find(|plugin| plugin.manifest.id == plugin_id)
+        .map(|plugin| plugin.fingerprint.clone())
+        .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+
+    settings::update(|s| {
+        s.plugins.enabled.insert(plugin_id.clone(), fingerprint);
+    })?;
+    tracing::info!(plugin = %plugin_id, "Plugin enabled");
+    Ok(plugin_infos())
+}
+
+#[tauri::command]
+pub async fn disable_plugin(plugin_id: String) -> Result<Vec<PluginInfo>, String> {
+    settings::update(|s| {
+        s.plugins.enabled.remove(&plugin_id);
+    })?;
+    tracing::info!(plugin = %plugin_id, "Plugin disabled");
+    Ok(plugin_infos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_plugin(manifest: Value) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sparrow-plugin-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        std::fs::write(dir.join("plugin.wasm"), b"\0asm").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_qualified_tool_name() {
+        assert_eq!(qualified_tool_name("csv-tools", "summarize"), "csv_tools_summarize");
+    }
+
+    #[test]
+    fn test_load_plugin_validates_manifest() {
+        let dir = write_plugin(json!({
+            "id": "epub",
+            "name": "EPUB reader",
+            "version": "1.0.0",
+            "module": "plugin.wasm",
+            "parsers": [{ "extensions": ["epub"] }]
+        }));
+        let plugin = load_plugin(&dir).unwrap();
+        assert_eq!(plugin.manifest.parsers[0].extensions, vec!["epub"]);
+        assert!(!plugin.manifest.capabilities.data_dir);
+
+        let escaping = write_plugin(json!({
+            "id": "bad", "name": "Bad", "version": "1", "module": "../plugin.wasm"
+        }));
+        assert!(load_plugin(&escaping).is_err());
+
+        let bad_id = write_plugin(json!({
+            "id": "Bad Id", "name": "Bad", "version": "1", "module": "plugin.wasm"
+        }));
+        assert!(load_plugin(&bad_id).is_err());
+
+        for dir in [dir, escaping, bad_id] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_capabilities() {
+        let none = PluginCapabilities::default();
+        let data = PluginCapabilities { data_dir: true, ..Default::default() };
+        assert_eq!(fingerprint(b"module", &none), fingerprint(b"module", &none));
+        assert_ne!(fingerprint(b"module", &none), fingerprint(b"module", &data));
+        assert_ne!(fingerprint(b"module", &none), fingerprint(b"other", &none));
+    }
+}
@@ -0,0 +1,210 @@
+//! Runs a WASI command module in a sandbox: input on stdin, output captured
+//! from stdout/stderr, and nothing else unless granted.
+//!
+//! The guest sees only the directories in `Sandbox::mounts` and the variables
+//! in `Sandbox::env`; WASI preview 1 gives it no sockets. Memory is capped per
+//! store, an optional fuel budget bounds the instructions executed, and a
+//! shared ticker thread advances the engine epoch so a call that runs past its
+//! timeout is interrupted instead of hanging the caller.
+
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+use std::time::{ Duration, SystemTime };
+
+use parking_lot::Mutex;
+use wasmtime::{ Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap };
+use wasmtime_wasi::pipe::{ MemoryInputPipe, MemoryOutputPipe };
+use wasmtime_wasi::preview1::{ self, WasiP1Ctx };
+use wasmtime_wasi::{ DirPerms, FilePerms, I32Exit, WasiCtxBuilder };
+
+/// How often the engine epoch advances; timeouts are rounded up to this
+const EPOCH_TICK: Duration = Duration::from_millis(50);
+
+/// stderr is only kept for error messages
+const MAX_STDERR_BYTES: usize = 64 * 1024;
+
+/// A host directory visible to the guest at `guest`
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub host: PathBuf,
+    pub guest: String,
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    pub args: Vec<String>,
+    pub mounts: Vec<Mount>,
+    pub env: Vec<(String, String)>,
+    pub max_memory_bytes: usize,
+    pub timeout: Duration,
+    /// Instruction budget (wasmtime fuel); unlimited when `None`
+    pub fuel: Option<u64>,
+    pub max_output_bytes: usize,
+}
+
+#[derive(Debug)]
+pub struct RunOutput {
+    pub exit_code: i32,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl RunOutput {
+    /// stderr as text, for error messages
+    pub fn stderr_text(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).trim().to_string()
+    }
+}
+
+struct RunState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+fn engine() -> Result<&'static Engine, String> {
+    static ENGINE: OnceLock<Result<Engine, String>> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.epoch_interruption(true);
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| format!("Failed to create WASM engine: {}", e))?;
+
+        let ticker = engine.clone();
+        std::thread::Builder::new()
+            .name("wasm-epoch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(EPOCH_TICK);
+                ticker.increment_epoch();
+            })
+            .map_err(|e| format!("Failed to start WASM timer: {}", e))?;
+
+        Ok(engine)
+    }).as_ref().map_err(Clone::clone)
+}
+
+/// Compiled modules, recompiled when the file changes. `verify` sees the
+/// bytes before every compile and can refuse them.
+fn load_module(
+    engine: &Engine,
+    path: &Path,
+    verify: &dyn Fn(&[u8]) -> Result<(), String>
+) -> Result<Module, String> {
+    static MODULES: OnceLock<Mutex<HashMap<PathBuf, (SystemTime, Module)>>> = OnceLock::new();
+
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let cache = MODULES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((cached_at, module)) = cache.lock().get(path) {
+        if *cached_at == modified {
+            return Ok(module.clone());
+        }
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    verify(&bytes)?;
+    let module = Module::new(engine, &bytes)
+        .map_err(|e| format!("Failed to compile {}: {}", path.display(), e))?;
+    cache.lock().insert(path.to_path_buf(), (modified, module.clone()));
+    Ok(module)
+}
+
+/// Epoch ticks until the deadline, at least one
+fn deadline_ticks(timeout: Duration) -> u64 {
+    (timeout.as_millis() as u64).div_ceil(EPOCH_TICK.as_millis() as u64).max(1)
+}
+
+/// Run the module's `_start` with `input` on stdin; `verify` checks the module
+/// bytes whenever they are (re)compiled. Blocks; call from a blocking thread.
+pub fn run(
+    module_path: &Path,
+    input: &[u8],
+    sandbox: &Sandbox,
+    verify: &dyn Fn(&[u8]) -> Result<(), String>
+) -> Result<RunOutput, String> {
+    let engine = engine()?;
+    let module = load_module(engine, module_path, verify)?;
+
+    let stdout = MemoryOutputPipe::new(sandbox.max_output_bytes);
+    let stderr = MemoryOutputPipe::new(MAX_STDERR_BYTES);
+
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .stdin(MemoryInputPipe::new(input.to_vec()))
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .args(&sandbox.args);
+    for (key, value) in &sandbox.env {
+        builder.env(key, value);
+    }
+    for mount in &sandbox.mounts {
+        let (dir_perms, file_perms) = if mount.writable {
+            (DirPerms::all(), FilePerms::all())
+        } else {
+            (DirPerms::READ, FilePerms::READ)
+        };
+        builder
+            .preopened_dir(&mount.host, &mount.guest, dir_perms, file_perms)
+            .map_err(|e| format!("Failed to mount {}: {}", mount.host.display(), e))?;
+    }
+
+    let state = RunState {
+        wasi: builder.build_p1(),
+        limits: StoreLimitsBuilder::new()
+            .memory_size(sandbox.max_memory_bytes)
+            .instances(1)
+            .build(),
+    };
+    let mut store = Store::new(engine, state);
+    store.limiter(|state| &mut state.limits);
+    store.set_epoch_deadline(deadline_ticks(sandbox.timeout));
+    store.set_fuel(sandbox.fuel.unwrap_or(u64::MAX))
+        .map_err(|e| format!("Failed to set fuel: {}", e))?;
+
+    let mut linker: Linker<RunState> = Linker::new(engine);
+    preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi)
+        .map_err(|e| format!("Failed to set up WASI: {}", e))?;
+
+    let instance = linker.instantiate(&mut store, &module)
+        .map_err(|e| format!("Failed to instantiate module: {}", e))?;
+    let start = instance.get_typed_func::<(), ()>(&mut store, "_start")
+        .map_err(|e| format!("Module is not a WASI command (no _start): {}", e))?;
+
+    let exit_code = match start.call(&mut store, ()) {
+        Ok(()) => 0,
+        Err(e) => {
+            if let Some(exit) = e.downcast_ref::<I32Exit>() {
+                exit.0
+            } else if matches!(e.downcast_ref::<Trap>(), Some(Trap::Interrupt)) {
+                return Err(format!("Timed out after {} ms", sandbox.timeout.as_millis()));
+            } else if matches!(e.downcast_ref::<Trap>(), Some(Trap::OutOfFuel)) {
+                return Err("Exceeded its instruction budget".to_string());
+            } else {
+                return Err(format!("Module trapped: {}", e));
+            }
+        }
+    };
+    drop(store);
+
+    Ok(RunOutput {
+        exit_code,
+        stdout: stdout.contents().to_vec(),
+        stderr: stderr.contents().to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_ticks_round_up() {
+        assert_eq!(deadline_ticks(Duration::ZERO), 1);
+        assert_eq!(deadline_ticks(Duration::from_millis(50)), 1);
+        assert_eq!(deadline_ticks(Duration::from_millis(51)), 2);
+        assert_eq!(deadline_ticks(Duration::from_secs(10)), 200);
+    }
+}
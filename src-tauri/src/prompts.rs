@@ -0,0 +1,243 @@
+//! Library of named system-prompt presets.
+//!
+//! Presets are saved in `~/.sparrow/prompts.json`. Their text may use
+//! `{{date}}`, `{{time}}`, `{{weekday}}` and `{{user_name}}`, filled in when
+//! the preset is used; other `{{...}}` placeholders are left as they are. A
+//! chat session can name a preset (`ChatSession::prompt_preset`) that stands
+//! in as its system prompt when a request does not bring its own.
+
+use std::sync::{ Arc, OnceLock };
+
+use chrono::Local;
+use parking_lot::Mutex;
+use serde::{ Deserialize, Serialize };
+use tracing::{ debug, warn };
+
+use crate::{ paths, settings, storage };
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptPreset {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub content: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PromptPresetStorage {
+    presets: Vec<PromptPreset>,
+}
+
+static PRESETS: OnceLock<Arc<Mutex<Vec<PromptPreset>>>> = OnceLock::new();
+/// Held from reading the presets to saving the changed list
+static SAVE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn presets() -> &'static Arc<Mutex<Vec<PromptPreset>>> {
+    PRESETS.get_or_init(|| Arc::new(Mutex::new(load_presets())))
+}
+
+fn load_presets() -> Vec<PromptPreset> {
+    let path = match paths::get_prompts_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!(error = %e, "Failed to resolve prompt presets path");
+            return Vec::new();
+        }
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<PromptPresetStorage>(&content) {
+            Ok(storage) => storage.presets,
+            Err(e) => {
+                warn!(error = %e, "Failed to parse prompt presets file, ignoring saved presets");
+                Vec::new()
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            warn!(error = %e, "Failed to read prompt presets file, ignoring saved presets");
+            Vec::new()
+        }
+    }
+}
+
+/// Write `presets` and make them the current list
+async fn save_presets(updated: Vec<PromptPreset>) -> Result<(), String> {
+    let path = paths::get_prompts_path().map_err(|e| e.to_string())?;
+    let saved = PromptPresetStorage { presets: updated };
+    let content = serde_json::to_string_pretty(&saved)
+        .map_err(|e| format!("Failed to serialize prompt presets: {}", e))?;
+
+    storage::write_string(&path, &content).await
+        .map_err(|e| format!("Failed to write prompt presets file: {}", e))?;
+
+    debug!(count = saved.presets.len(), "Saved prompt presets");
+    *presets().lock() = saved.presets;
+    Ok(())
+}
+
+pub fn find_preset(id: &str) -> Option<PromptPreset> {
+    presets().lock().iter().find(|preset| preset.id == id).cloned()
+}
+
+/// `chat.user_name` from settings, else the account name
+fn user_name() -> String {
+    settings::current().chat.user_name
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+/// Replace `{{name}}` placeholders in one pass, so substituted values are
+/// never expanded again
+fn substitute(content: &str, variables: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let name = after[..end].trim();
+            variables.iter().find(|(key, _)| *key == name).map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                rendered.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// A preset's text with its variables filled in for now
+pub fn render(preset: &PromptPreset) -> String {
+    let now = Local::now();
+    substitute(&preset.content, &[
+        ("date", now.format("%Y-%m-%d").to_string()),
+        ("time", now.format("%H:%M").to_string()),
+        ("weekday", now.format("%A").to_string()),
+        ("user_name", user_name()),
+    ])
+}
+
+fn validate(name: &str, content: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("A prompt preset needs a name".to_string());
+    }
+    if content.trim().is_empty() {
+        return Err("A prompt preset needs some text".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_prompt_presets() -> Result<Vec<PromptPreset>, String> {
+    Ok(presets().lock().clone())
+}
+
+#[tauri::command]
+pub async fn create_prompt_preset(
+    name: String,
+    content: String,
+    description: Option<String>
+) -> Result<PromptPreset, String> {
+    validate(&name, &content)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let preset = PromptPreset {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        description: description.filter(|description| !description.trim().is_empty()),
+        content,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let _save = SAVE_LOCK.lock().await;
+    let mut updated = presets().lock().clone();
+    updated.push(preset.clone());
+    save_presets(updated).await?;
+
+    tracing::info!(preset = %preset.id, name = %preset.name, "Created prompt preset");
+    Ok(preset)
+}
+
+#[tauri::command]
+pub async fn update_prompt_preset(
+    id: String,
+    name: String,
+    content: String,
+    description: Option<String>
+) -> Result<PromptPreset, String> {
+    validate(&name, &content)?;
+
+    let _save = SAVE_LOCK.lock().await;
+    let mut updated = presets().lock().clone();
+    let preset = updated.iter_mut()
+        .find(|preset| preset.id == id)
+        .ok_or_else(|| format!("Prompt preset not found: {}", id))?;
+    preset.name = name.trim().to_string();
+    preset.description = description.filter(|description| !description.trim().is_empty());
+    preset.content = content;
+    preset.updated_at = chrono::Utc::now().timestamp_millis();
+    let preset = preset.clone();
+    save_presets(updated).await?;
+
+    tracing::info!(preset = %preset.id, "Updated prompt preset");
+    Ok(preset)
+}
+
+/// Delete a preset; sessions that named it go back to the default prompt
+#[tauri::command]
+pub async fn delete_prompt_preset(id: String) -> Result<(), String> {
+    let _save = SAVE_LOCK.lock().await;
+    let mut updated = presets().lock().clone();
+    if !updated.iter().any(|preset| preset.id == id) {
+        return Err(format!("Prompt preset not found: {}", id));
+    }
+
+    updated.retain(|preset| preset.id != id);
+    save_presets(updated).await?;
+
+    tracing::info!(preset = %id, "Deleted prompt preset");
+    Ok(())
+}
+
+/// Preview a preset with its variables filled in
+#[tauri::command]
+pub async fn render_prompt_preset(id: String) -> Result<String, String> {
+    find_preset(&id)
+        .map(|preset| render(&preset))
+        .ok_or_else(|| format!("Prompt preset not found: {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute_fills_known_variables_once() {
+        let variables = [("date", "2025-03-01".to_string()), ("user_name", "{{date}}".to_string())];
+        assert_eq!(
+            substitute("Today is {{date}}. Hi {{ user_name }}, {{unknown}} stays.", &variables),
+            "Today is 2025-03-01. Hi {{date}}, {{unknown}} stays."
+        );
+        assert_eq!(substitute("Unclosed {{date", &variables), "Unclosed {{date");
+    }
+
+    #[test]
+    fn test_validate_requires_name_and_content() {
+        assert!(validate("Coder", "You write Rust.").is_ok());
+        assert!(validate("  ", "You write Rust.").is_err());
+        assert!(validate("Coder", "\n").is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! Registry of quick actions: named prompt templates applied to a piece of text.
+//!
+//! Templates use `{text}` for the input and `{language}` for the configured
+//! translation language (`quick_actions.translate_language` in settings).
+//!
+//! Besides the built-in actions, users can save their own in
+//! `~/.sparrow/quick_actions.json`. A saved action may pin a model, pull in
+//! RAG context, and deliver its result to the chat, the clipboard or a file.
+//! A saved action with the id of a built-in one replaces it.
+
+use std::sync::{ Arc, OnceLock };
+
+use parking_lot::Mutex;
+use serde::{ Deserialize, Serialize };
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tracing::{ debug, warn };
+
+use crate::{ paths, storage };
+use crate::path_policy::{ self, Access };
+use crate::selection::{ self, SelectionPrompt };
+use crate::settings;
+use crate::text_assist;
+
+/// Where the result of an action goes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuickActionOutput {
+    /// Send the rendered prompt to a chat and let the conversation answer it
+    #[default]
+    Chat,
+    /// Generate the answer in the background and copy it to the clipboard
+    Clipboard,
+    /// Generate the answer in the background and write it to `path`
+    File { path: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAction {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub prompt_template: String,
+    /// Model to run with; the loaded model when absent
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Prepend excerpts from the document store that match the text
+    #[serde(default)]
+    pub use_rag: bool,
+    #[serde(default)]
+    pub output: QuickActionOutput,
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct QuickActionStorage {
+    actions: Vec<QuickAction>,
+}
+
+/// What executing an action produced
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuickActionResult {
+    /// The prompt was handed to the chat
+    Chat { prompt: SelectionPrompt },
+    Clipboard { text: String },
+    File { path: String, text: String },
+}
+
+fn builtin(id: &str, name: &str, description: &str, prompt_template: &str) -> QuickAction {
+    QuickAction {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        prompt_template: prompt_template.to_string(),
+        model: None,
+        use_rag: false,
+        output: QuickActionOutput::Chat,
+        builtin: true,
+    }
+}
+
+pub fn builtin_actions() -> Vec<QuickAction> {
+    vec![
+        builtin(
+            "explain",
+            "Explain",
+            "Explain what the text means",
+            "Explain the following text in simple terms:\n\n{text}"
+        ),
+        builtin(
+            "translate",
+            "Translate",
+            "Translate the text into the configured language",
+            "Translate the following text into {language}. Reply with the translation only.\n\n{text}"
+        ),
+        builtin(
+            "rewrite",
+            "Rewrite",
+            "Rewrite the text more clearly",
+            "Rewrite the following text so it is clearer and reads well, keeping its meaning. Reply with the rewritten text only.\n\n{text}"
+        ),
+        builtin(
+            "summarize",
+            "Summarize",
+            "Summarize the key points",
+            "Summarize the key points of the following text:\n\n{text}"
+        )
+    ]
+}
+
+static USER_ACTIONS: OnceLock<Arc<Mutex<Vec<QuickAction>>>> = OnceLock::new();
+/// Held from reading the saved actions to saving the changed list
+static SAVE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn user_actions() -> &'static Arc<Mutex<Vec<QuickAction>>> {
+    USER_ACTIONS.get_or_init(|| Arc::new(Mutex::new(load_user_actions())))
+}
+
+fn load_user_actions() -> Vec<QuickAction> {
+    let path = match paths::get_quick_actions_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!(error = %e, "Failed to resolve quick actions path");
+            return Vec::new();
+        }
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str::<QuickActionStorage>(&content) {
+            Ok(storage) => storage.actions,
+            Err(e) => {
+                warn!(error = %e, "Failed to parse quick actions file, ignoring saved actions");
+                Vec::new()
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            warn!(error = %e, "Failed to read quick actions file, ignoring saved actions");
+            Vec::new()
+        }
+    }
+}
+
+/// Write `updated` and make it the current list of saved actions
+async fn save_user_actions(updated: Vec<QuickAction>) -> Result<(), String> {
+    let path = paths::get_quick_actions_path().map_err(|e| e.to_string())?;
+
+    let saved = QuickActionStorage { actions: updated };
+    let content = serde_json::to_string_pretty(&saved)
+        .map_err(|e| format!("Failed to serialize quick actions: {}", e))?;
+
+    storage::write_string(&path, &content).await
+        .map_err(|e| format!("Failed to write quick actions file: {}", e))?;
+
+    debug!(count = saved.actions.len(), "Saved quick actions");
+    *user_actions().lock() = saved.actions;
+    Ok(())
+}
+
+/// Built-in actions, with saved overrides applied, followed by saved actions
+fn merge_actions(builtins: Vec<QuickAction>, saved: &[QuickAction]) -> Vec<QuickAction> {
+    let mut actions: Vec<QuickAction> = builtins
+        .into_iter()
+        .map(|action| saved.iter().find(|s| s.id == action.id).cloned().unwrap_or(action))
+        .collect();
+    for action in saved {
+        if !actions.iter().any(|a| a.id == action.id) {
+            actions.push(action.clone());
+        }
+    }
+    actions
+}
+
+pub fn all_actions() -> Vec<QuickAction> {
+    let saved = user_actions().lock();
+    merge_actions(builtin_actions(), &saved)
+}
+
+pub fn find_action(id: &str) -> Option<QuickAction> {
+    all_actions().into_iter().find(|action| action.id == id)
+}
+
+fn validate(action: &QuickAction) -> Result<(), String> {
+    if action.id.trim().is_empty() || action.name.trim().is_empty() {
+        return Err("A quick action needs an id and a name".to_string());
+    }
+    if !action.prompt_template.contains("{text}") {
+        return Err("The prompt template must contain {text}".to_string());
+    }
+    if let QuickActionOutput::File { path } = &action.output {
+        if !std::path::Path::new(path).is_absolute() {
+            return Err(format!("Output file must be an absolute path: {}", path));
+        }
+    }
+    Ok(())
+}
+
+/// Fill in an action's template
+pub fn render_prompt(action: &QuickAction, text: &str) -> String {
+    let language = settings::current().quick_actions.translate_language;
+    action.prompt_template
+        .replace("{language}", &language)
+        .replace("{text}", text)
+}
+
+/// Run an action on `text` and deliver the result to its output target.
+/// `session_id` only matters for chat output.
+pub async fn execute(
+    app: &AppHandle,
+    action_id: &str,
+    text: &str,
+    session_id: Option<String>
+) -> Result<QuickActionResult, String> {
+    let action = find_action(action_id).ok_or_else(|| format!("Unknown quick action: '{}'", action_id))?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("No text given".to_string());
+    }
+
+    let mut prompt = render_prompt(&action, text);
+    if action.use_rag {
+        crate::ensure_ovms_initialized(app).await;
+        match crate::chat::perform_rag_retrieval(text, Some(5), None).await {
+            Ok(context) if !context.is_empty() => {
+                prompt = format!("Relevant excerpts from the user's documents:\n{}\n\n{}", context, prompt);
+            }
+            Ok(_) => {}
+            Err(e) => log_warning!("Quick action runs without document context", error = %e, action = %action.id),
+        }
+    }
+
+    match &action.output {
+        QuickActionOutput::Chat => {
+            let prompt = SelectionPrompt {
+                action_id: action.id.clone(),
+                action_name: action.name.clone(),
+                text: text.to_string(),
+                prompt,
+                session_id,
+                model: action.model.clone(),
+            };
+            selection::deliver(app, prompt.clone());
+            Ok(QuickActionResult::Chat { prompt })
+        }
+        QuickActionOutput::Clipboard => {
+            let result = text_assist::complete(app, text_assist::request_id_or_new(None), None, &prompt, action.model.clone()).await?;
+            app.clipboard()
+                .write_text(result.text.clone())
+                .map_err(|e| format!("Failed to copy to the clipboard: {}", e))?;
+            Ok(QuickActionResult::Clipboard { text: result.text })
+        }
+        QuickActionOutput::File { path } => {
+            let target = path_policy::check_str(path, Access::Write)?;
+            let result = text_assist::complete(app, text_assist::request_id_or_new(None), None, &prompt, action.model.clone()).await?;
+            storage::write_string(&target, &result.text).await
+                .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            Ok(QuickActionResult::File { path: path.clone(), text: result.text })
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_quick_actions() -> Result<Vec<QuickAction>, String> {
+    Ok(all_actions())
+}
+
+/// Create or replace a saved action
+#[tauri::command]
+pub async fn save_quick_action(mut action: QuickAction) -> Result<QuickAction, String> {
+    validate(&action)?;
+    action.builtin = false;
+
+    let _save = SAVE_LOCK.lock().await;
+    let mut updated = user_actions().lock().clone();
+    match updated.iter_mut().find(|a| a.id == action.id) {
+        Some(existing) => *existing = action.clone(),
+        None => updated.push(action.clone()),
+    }
+    save_user_actions(updated).await?;
+
+    tracing::info!(action = %action.id, "Saved quick action");
+    Ok(action)
+}
+
+/// Delete a saved action. Deleting an override restores the built-in action.
+#[tauri::command]
+pub async fn delete_quick_action(action_id: String) -> Result<(), String> {
+    let _save = SAVE_LOCK.lock().await;
+    let mut updated = user_actions().lock().clone();
+    if !updated.iter().any(|a| a.id == action_id) {
+        return Err(if builtin_actions().iter().any(|a| a.id == action_id) {
+            format!("'{}' is a built-in quick action and cannot be deleted", action_id)
+        } else {
+            format!("Quick action not found: {}", action_id)
+        });
+    }
+
+    updated.retain(|a| a.id != action_id);
+    save_user_actions(updated).await?;
+
+    tracing::info!(action = %action_id, "Deleted quick action");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn execute_quick_action(
+    app: AppHandle,
+    action_id: String,
+    text: String,
+    session_id: Option<String>
+) -> Result<QuickActionResult, String> {
+    log_operation_start!("Execute quick action", action = %action_id);
+
+    let result = execute(&app, &action_id, &text, session_id).await.map_err(|e| {
+        log_operation_error!("Execute quick action", &e, action = %action_id);
+        e
+    })?;
+
+    log_operation_success!("Execute quick action", action = %action_id);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_templates_include_text() {
+        for action in builtin_actions() {
+            assert!(action.prompt_template.contains("{text}"), "{} has no {{text}}", action.id);
+        }
+    }
+
+    #[test]
+    fn test_text_is_substituted_last() {
+        // A selection containing a placeholder must not be expanded again
+        let action = builtin_actions().into_iter().find(|a| a.id == "explain").unwrap();
+        let prompt = render_prompt(&action, "what is {language}?");
+        assert!(prompt.ends_with("what is {language}?"));
+    }
+
+    #[test]
+    fn test_saved_action_overrides_builtin() {
+        let mut custom = builtin("explain", "Explain like I'm five", "", "ELI5: {text}");
+        custom.builtin = false;
+        let extra = builtin("todo", "To-do list", "", "Extract tasks from: {text}");
+
+        let actions = merge_actions(builtin_actions(), &[custom, extra]);
+
+        assert_eq!(actions.len(), builtin_actions().len() + 1);
+        assert_eq!(actions[0].name, "Explain like I'm five");
+        assert!(!actions[0].builtin);
+        assert_eq!(actions.last().unwrap().id, "todo");
+    }
+
+    #[test]
+    fn test_output_target_serialization() {
+        let output: QuickActionOutput = serde_json::from_str(r#"{"type":"file","path":"/tmp/out.md"}"#).unwrap();
+        assert_eq!(output, QuickActionOutput::File { path: "/tmp/out.md".to_string() });
+
+        let mut action = builtin("notes", "Notes", "", "no placeholder");
+        assert!(validate(&action).is_err());
+        action.prompt_template = "{text}".to_string();
+        action.output = QuickActionOutput::File { path: "relative.md".to_string() };
+        assert!(validate(&action).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Storage backends for RAG documents.
+//!
+//! Commands talk to `dyn VectorBackend` from `open()`, which picks the
+//! backend configured in `rag.backend`: the embedded sled `VectorStore`
+//! (default) or an external Qdrant server. Collection configuration,
+//! quantization, the embedding matrix and embedding import/export are
+//! specific to the sled store and keep using `VectorStore` directly.
+
+use futures::future::BoxFuture;
+
+use super::{ Document, FileInfo, SearchResult };
+use super::filter::SearchFilter;
+use super::qdrant::QdrantStore;
+use super::vector_store::{ self, VectorStore };
+use crate::settings::{ self, VectorBackendKind };
+
+pub trait VectorBackend: Send + Sync {
+    /// Insert or replace documents, with their embeddings
+    fn store_documents<'a>(&'a self, documents: &'a [Document]) -> BoxFuture<'a, Result<(), String>>;
+
+    fn search_similar<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>>;
+
+    /// Like `search_similar`, restricted to chunks of the given files
+    fn search_similar_in_files<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        file_paths: &'a [String],
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>>;
+
+    /// Like `search_similar`, restricted to chunks the filter accepts
+    fn search_similar_filtered<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        filter: &'a SearchFilter,
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>>;
+
+    /// Returns whether the document existed
+    fn delete_document<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, String>>;
+
+    /// Delete every chunk of a file, returning how many were removed
+    fn delete_file<'a>(&'a self, file_path: &'a str) -> BoxFuture<'a, Result<usize, String>>;
+
+    /// All documents, newest first
+    fn list_documents(&self) -> BoxFuture<'_, Result<Vec<Document>, String>>;
+
+    fn count_documents(&self) -> BoxFuture<'_, Result<usize, String>>;
+
+    fn clear_all(&self) -> BoxFuture<'_, Result<(), String>>;
+
+    /// Remove the collection itself, not just its documents
+    fn drop_collection(&self) -> BoxFuture<'_, Result<(), String>>;
+
+    /// Make earlier writes durable
+    fn flush(&self) -> BoxFuture<'_, Result<(), String>>;
+
+    fn list_files(&self) -> BoxFuture<'_, Result<Vec<FileInfo>, String>> {
+        Box::pin(async move { Ok(vector_store::group_by_file(self.list_documents().await?)) })
+    }
+
+    /// Chunks of one file, in chunk order
+    fn file_chunks<'a>(&'a self, file_path: &'a str) -> BoxFuture<'a, Result<Vec<Document>, String>> {
+        Box::pin(async move {
+            let mut chunks: Vec<Document> = self.list_documents()
+                .await?
+                .into_iter()
+                .filter(|document| document.file_path == file_path)
+                .collect();
+            sort_chunks(&mut chunks);
+            Ok(chunks)
+        })
+    }
+}
+
+/// Sort by chunk index; chunks without one go last, oldest first
+pub fn sort_chunks(chunks: &mut [Document]) {
+    chunks.sort_by(|a, b| {
+        match (a.chunk_index, b.chunk_index) {
+            (Some(a_idx), Some(b_idx)) => a_idx.cmp(&b_idx),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.created_at.cmp(&b.created_at),
+        }
+    });
+}
+
+/// Open a collection in the configured backend
+pub fn open(collection: &str) -> Result<Box<dyn VectorBackend>, String> {
+    let rag = settings::current().rag;
+    match rag.backend {
+        VectorBackendKind::Sled => Ok(Box::new(VectorStore::open_collection(collection)?)),
+        VectorBackendKind::Qdrant => Ok(Box::new(QdrantStore::new(&rag.qdrant, collection)?)),
+    }
+}
+
+/// Open the collection the UI uses
+pub fn open_default() -> Result<Box<dyn VectorBackend>, String> {
+    open(vector_store::DEFAULT_COLLECTION)
+}
+
+/// Refuse sled-only features (see the module doc) while Qdrant is configured
+pub(crate) fn require_sled(feature: &str) -> Result<(), String> {
+    match settings::current().rag.backend {
+        VectorBackendKind::Sled => Ok(()),
+        VectorBackendKind::Qdrant => Err(format!(
+            "{} only applies to the built-in vector store; the Qdrant backend is configured",
+            feature
+        )),
+    }
+}
+
+/// Run sled work on a blocking thread; sled calls block on disk I/O
+fn blocking<T, F>(store: &VectorStore, work: F) -> BoxFuture<'static, Result<T, String>>
+where
+    T: Send + 'static,
+    F: FnOnce(&VectorStore) -> Result<T, String> + Send + 'static,
+{
+    let store = store.clone();
+    Box::pin(async move {
+        tokio::task::spawn_blocking(move || work(&store)).await
+            .map_err(|e| format!("Vector store task failed: {}", e))?
+    })
+}
+
+impl VectorBackend for VectorStore {
+    fn store_documents<'a>(&'a self, documents: &'a [Document]) -> BoxFuture<'a, Result<(), String>> {
+        let documents = documents.to_vec();
+        blocking(self, move |store| store.store_documents_batch(&documents))
+    }
+
+    fn search_similar<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>> {
+        let query_embedding = query_embedding.to_vec();
+        blocking(self, move |store| store.search_similar(&query_embedding, limit))
+    }
+
+    fn search_similar_in_files<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        file_paths: &'a [String],
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>> {
+        let (query_embedding, file_paths) = (query_embedding.to_vec(), file_paths.to_vec());
+        blocking(self, move |store| store.search_similar_in_files(&query_embedding, &file_paths, limit))
+    }
+
+    fn search_similar_filtered<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        filter: &'a SearchFilter,
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>> {
+        let (query_embedding, filter) = (query_embedding.to_vec(), filter.clone());
+        blocking(self, move |store| store.search_similar_filtered(&query_embedding, &filter, limit))
+    }
+
+    fn delete_document<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        let id = id.to_string();
+        blocking(self, move |store| store.delete_document(&id))
+    }
+
+    fn delete_file<'a>(&'a self, file_path: &'a str) -> BoxFuture<'a, Result<usize, String>> {
+        let file_path = file_path.to_string();
+        blocking(self, move |store| store.delete_file(&file_path))
+    }
+
+    fn list_documents(&self) -> BoxFuture<'_, Result<Vec<Document>, String>> {
+        blocking(self, VectorStore::list_all_documents)
+    }
+
+    fn count_documents(&self) -> BoxFuture<'_, Result<usize, String>> {
+        blocking(self, VectorStore::count_documents)
+    }
+
+    fn clear_all(&self) -> BoxFuture<'_, Result<(), String>> {
+        blocking(self, VectorStore::clear_all)
+    }
+
+    fn drop_collection(&self) -> BoxFuture<'_, Result<(), String>> {
+        blocking(self, VectorStore::drop_collection)
+    }
+
+    fn flush(&self) -> BoxFuture<'_, Result<(), String>> {
+        blocking(self, VectorStore::flush)
+    }
+
+    fn list_files(&self) -> BoxFuture<'_, Result<Vec<FileInfo>, String>> {
+        blocking(self, VectorStore::list_files)
+    }
+}
@@ -0,0 +1,417 @@
+//! End-to-end RAG latency benchmark, to compare hardware and settings.
+//!
+//! `benchmark_rag` indexes a generated corpus of `corpus_size` chunks into a
+//! scratch collection of the configured backend, then runs a fixed query set
+//! through the same stages as a RAG chat: query embedding, vector search,
+//! reranking and, when a text model is loaded, a short answer. Every stage is
+//! timed per query and summarized as min/mean/p50/p95/max. The corpus and
+//! queries are the same on every run, so reports from different machines or
+//! configurations can be compared directly. Each run indexes into a scratch
+//! collection of its own, dropped afterwards, so the user's collections are
+//! never touched.
+
+use std::time::Instant;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+
+use super::{ Document, SearchResult };
+use super::backend::{ self, VectorBackend };
+use super::embeddings::EmbeddingService;
+use super::reranker::RerankerService;
+use crate::{ constants, settings };
+
+/// Scratch collections are `__benchmark-<uuid>`, a new one per run
+const BENCHMARK_COLLECTION_PREFIX: &str = "__benchmark-";
+const TOPIC_KEY: &str = "benchmark_topic";
+
+const DEFAULT_CORPUS_SIZE: usize = 200;
+const MAX_CORPUS_SIZE: usize = 5000;
+const DEFAULT_LIMIT: usize = 5;
+const ANSWER_MAX_TOKENS: u32 = 64;
+
+/// (topic, sentences) the corpus is generated from
+const TOPICS: &[(&str, &[&str])] = &[
+    ("astronomy", &[
+        "The telescope tracked the comet as it passed the outer planets.",
+        "Neutron stars spin hundreds of times per second after a supernova.",
+        "Light from distant galaxies is shifted toward red as the universe expands.",
+        "The observatory measured the orbital period of the binary star system.",
+    ]),
+    ("cooking", &[
+        "Knead the dough for ten minutes until it becomes smooth and elastic.",
+        "Simmer the tomato sauce slowly so the garlic does not burn.",
+        "Rest the roast for fifteen minutes before carving to keep it juicy.",
+        "Toast the spices in a dry pan to release their aroma.",
+    ]),
+    ("networking", &[
+        "The router forwards packets based on the longest matching prefix.",
+        "TCP retransmits segments when acknowledgements do not arrive in time.",
+        "DNS resolvers cache records until their time to live expires.",
+        "The firewall drops inbound connections that do not match a rule.",
+    ]),
+    ("gardening", &[
+        "Tomato seedlings need full sun and regular watering at the roots.",
+        "Prune the roses in late winter before new shoots appear.",
+        "Compost improves soil structure and feeds beneficial microbes.",
+        "Mulch keeps the beds moist and suppresses weeds through summer.",
+    ]),
+    ("finance", &[
+        "The central bank raised interest rates to slow inflation.",
+        "Diversifying a portfolio lowers the risk of any single investment.",
+        "Bond prices fall when market interest rates rise.",
+        "The quarterly report showed revenue growth but shrinking margins.",
+    ]),
+    ("medicine", &[
+        "Vaccines train the immune system to recognize a pathogen.",
+        "The patient's blood pressure stabilized after the new medication.",
+        "Antibiotics do not work against viral infections such as the flu.",
+        "Regular exercise lowers the risk of heart disease.",
+    ]),
+    ("history", &[
+        "The printing press spread books across Europe in the fifteenth century.",
+        "The empire built roads that connected its distant provinces.",
+        "Steam engines powered the factories of the industrial revolution.",
+        "The treaty ended the war and redrew the borders of the region.",
+    ]),
+    ("music", &[
+        "The orchestra tuned to the oboe's A before the concert began.",
+        "A major chord combines the root, the major third and the fifth.",
+        "The drummer kept a steady tempo through the long improvisation.",
+        "Vinyl records store sound as grooves cut into the disc.",
+    ]),
+];
+
+/// (topic, query) run against the corpus
+const QUERIES: &[(&str, &str)] = &[
+    ("astronomy", "How fast do neutron stars rotate?"),
+    ("cooking", "How long should I knead bread dough?"),
+    ("networking", "When does TCP retransmit a segment?"),
+    ("gardening", "When is the best time to prune roses?"),
+    ("finance", "Why do bond prices drop when rates go up?"),
+    ("medicine", "Do antibiotics help against the flu?"),
+    ("history", "What powered factories during the industrial revolution?"),
+    ("music", "Which notes make up a major chord?"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    pub fn from_samples(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            samples: sorted.len(),
+            min_ms: sorted[0],
+            mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50_ms: percentile(&sorted, 0.5),
+            p95_ms: percentile(&sorted, 0.95),
+            max_ms: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RagBenchmarkReport {
+    pub backend: settings::VectorBackendKind,
+    pub corpus_size: usize,
+    pub query_count: usize,
+    pub limit: usize,
+    pub embedding_dim: usize,
+    pub reranking: bool,
+    /// Model that produced the answers; `None` when the answer stage was skipped
+    pub answer_model: Option<String>,
+    pub indexing_ms: f64,
+    pub indexing_chunks_per_sec: f64,
+    pub embedding: LatencyStats,
+    pub retrieval: LatencyStats,
+    pub rerank: Option<LatencyStats>,
+    pub answer: Option<LatencyStats>,
+    /// Embedding through answer, per query
+    pub end_to_end: LatencyStats,
+    /// Share of queries whose top result came from the query's topic
+    pub top1_accuracy: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BenchmarkProgress {
+    stage: &'static str,
+    completed: usize,
+    total: usize,
+}
+
+fn emit_progress(app: &AppHandle, stage: &'static str, completed: usize, total: usize) {
+    let _ = app.emit("rag-benchmark-progress", BenchmarkProgress { stage, completed, total });
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+/// The fixed corpus: chunk `i` belongs to topic `i % TOPICS.len()` and
+/// rotates through that topic's sentences, so any size keeps topics balanced
+fn generate_corpus(size: usize) -> Vec<Document> {
+    (0..size)
+        .map(|i| {
+            let (topic, sentences) = TOPICS[i % TOPICS.len()];
+            let variant = i / TOPICS.len();
+            let content = (0..3)
+                .map(|j| sentences[(variant + j) % sentences.len()])
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut document = Document::new(
+                format!("{} #{}", topic, variant + 1),
+                format!("{} (passage {})", content, variant + 1),
+                "txt".to_string(),
+                format!("benchmark/{}.txt", topic),
+                Some(variant)
+            );
+            document.metadata.insert(TOPIC_KEY.to_string(), topic.to_string());
+            document
+        })
+        .collect()
+}
+
+fn build_context(results: &[SearchResult], limit: usize) -> String {
+    results
+        .iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, result)| format!("Source {}: {}\nContent: {}\n---", i + 1, result.document.title, result.document.content))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn answer(client: &Client<OpenAIConfig>, model: &str, context: &str, query: &str) -> Result<(), String> {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model.to_string())
+        .messages(vec![
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(format!("Answer briefly using these excerpts:\n{}", context))
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(query.to_string())
+                .build()
+                .map_err(|e| format!("Failed to build user message: {}", e))?
+                .into(),
+        ])
+        .max_tokens(ANSWER_MAX_TOKENS)
+        .temperature(0.0)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    client.chat().create(request).await.map_err(|e| format!("Answer request failed: {}", e))?;
+    Ok(())
+}
+
+async fn index_corpus(
+    app: &AppHandle,
+    embedding_service: &EmbeddingService,
+    store: &dyn VectorBackend,
+    corpus: Vec<Document>
+) -> Result<(f64, usize), String> {
+    let total = corpus.len();
+    let started = Instant::now();
+    let mut embedding_dim = 0;
+    let mut indexed = 0;
+
+    for batch in corpus.chunks(constants::EMBEDDING_BATCH_SIZE) {
+        let texts = batch.iter().map(|document| document.content.clone()).collect();
+        let embeddings = embedding_service.create_embeddings(texts).await?;
+        let documents: Vec<Document> = batch
+            .iter()
+            .cloned()
+            .zip(embeddings)
+            .map(|(mut document, embedding)| {
+                embedding_dim = embedding.len();
+                document.embedding = Some(embedding);
+                document
+            })
+            .collect();
+        store.store_documents(&documents).await?;
+        indexed += documents.len();
+        emit_progress(app, "indexing", indexed, total);
+    }
+    store.flush().await?;
+
+    Ok((elapsed_ms(started), embedding_dim))
+}
+
+/// Measure RAG latency per stage over the fixed corpus and query set. The
+/// answer stage runs when `include_answer` is not false and a text model is
+/// given or loaded.
+#[tauri::command]
+pub async fn benchmark_rag(
+    app: AppHandle,
+    corpus_size: Option<usize>,
+    limit: Option<usize>,
+    use_reranking: Option<bool>,
+    include_answer: Option<bool>,
+    model: Option<String>
+) -> Result<RagBenchmarkReport, String> {
+    let corpus_size = corpus_size.unwrap_or(DEFAULT_CORPUS_SIZE).clamp(TOPICS.len(), MAX_CORPUS_SIZE);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    let reranking = use_reranking.unwrap_or(true);
+    log_operation_start!("RAG benchmark", corpus_size = corpus_size, limit = limit);
+
+    crate::ensure_ovms_initialized(&app).await;
+
+    let answer_model = match (include_answer.unwrap_or(true), model) {
+        (false, _) => None,
+        (true, Some(model)) => Some(model),
+        (true, None) => crate::ovms::get_loaded_model(app.clone()).await.ok().flatten(),
+    };
+
+    let collection = format!("{}{}", BENCHMARK_COLLECTION_PREFIX, uuid::Uuid::new_v4().simple());
+    let store = backend::open(&collection)?;
+
+    let result = run(&app, store.as_ref(), corpus_size, limit, reranking, answer_model).await;
+    if let Err(e) = store.drop_collection().await {
+        log_warning!("Failed to drop the benchmark collection", collection = %collection, error = %e);
+    }
+
+    match &result {
+        Ok(report) => {
+            log_operation_success!(
+                "RAG benchmark",
+                end_to_end_p50_ms = report.end_to_end.p50_ms,
+                top1_accuracy = report.top1_accuracy
+            );
+        }
+        Err(e) => {
+            log_operation_error!("RAG benchmark", e);
+        }
+    }
+    result
+}
+
+async fn run(
+    app: &AppHandle,
+    store: &dyn VectorBackend,
+    corpus_size: usize,
+    limit: usize,
+    reranking: bool,
+    answer_model: Option<String>
+) -> Result<RagBenchmarkReport, String> {
+    let embedding_service = EmbeddingService::new();
+    let reranker = RerankerService::new();
+    let client = Client::with_config(
+        OpenAIConfig::new()
+            .with_api_key("unused")
+            .with_api_base(crate::ovms::openai_api_base())
+    );
+
+    let (indexing_ms, embedding_dim) = index_corpus(app, &embedding_service, store, generate_corpus(corpus_size)).await?;
+
+    let mut embedding = Vec::new();
+    let mut retrieval = Vec::new();
+    let mut rerank = Vec::new();
+    let mut answers = Vec::new();
+    let mut end_to_end = Vec::new();
+    let mut hits = 0;
+
+    for (i, (topic, query)) in QUERIES.iter().enumerate() {
+        let started = Instant::now();
+        let query_embedding = embedding_service.create_single_embedding(query.to_string()).await?;
+        embedding.push(elapsed_ms(started));
+
+        // Same over-fetch as chat retrieval, so the reranker sees as many candidates
+        let stage = Instant::now();
+        let mut results = store.search_similar(&query_embedding, limit * 2).await?;
+        retrieval.push(elapsed_ms(stage));
+
+        if reranking {
+            let stage = Instant::now();
+            results = reranker.rerank(query, results).await?;
+            rerank.push(elapsed_ms(stage));
+        }
+
+        if results.first().and_then(|r| r.document.metadata.get(TOPIC_KEY)).map(String::as_str) == Some(*topic) {
+            hits += 1;
+        }
+
+        if let Some(model) = &answer_model {
+            let stage = Instant::now();
+            answer(&client, model, &build_context(&results, limit), query).await?;
+            answers.push(elapsed_ms(stage));
+        }
+
+        end_to_end.push(elapsed_ms(started));
+        emit_progress(app, "querying", i + 1, QUERIES.len());
+    }
+
+    Ok(RagBenchmarkReport {
+        backend: settings::current().rag.backend,
+        corpus_size,
+        query_count: QUERIES.len(),
+        limit,
+        embedding_dim,
+        reranking,
+        indexing_ms,
+        indexing_chunks_per_sec: corpus_size as f64 / (indexing_ms / 1000.0).max(f64::EPSILON),
+        embedding: LatencyStats::from_samples(&embedding),
+        retrieval: LatencyStats::from_samples(&retrieval),
+        rerank: reranking.then(|| LatencyStats::from_samples(&rerank)),
+        answer: answer_model.is_some().then(|| LatencyStats::from_samples(&answers)),
+        answer_model,
+        end_to_end: LatencyStats::from_samples(&end_to_end),
+        top1_accuracy: hits as f64 / QUERIES.len() as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        let stats = LatencyStats::from_samples(&[40.0, 10.0, 20.0, 30.0]);
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.min_ms, 10.0);
+        assert_eq!(stats.mean_ms, 25.0);
+        assert_eq!(stats.p50_ms, 20.0);
+        assert_eq!(stats.p95_ms, 40.0);
+        assert_eq!(stats.max_ms, 40.0);
+
+        assert_eq!(LatencyStats::from_samples(&[]), LatencyStats::default());
+    }
+
+    #[test]
+    fn test_corpus_is_deterministic_and_balanced() {
+        let corpus = generate_corpus(TOPICS.len() * 3);
+        let again = generate_corpus(TOPICS.len() * 3);
+        assert!(corpus.iter().zip(&again).all(|(a, b)| a.content == b.content));
+
+        for (topic, _) in TOPICS {
+            let count = corpus.iter().filter(|d| d.metadata.get(TOPIC_KEY).map(String::as_str) == Some(*topic)).count();
+            assert_eq!(count, 3);
+        }
+        // Every query has a topic in the corpus
+        assert!(QUERIES.iter().all(|(topic, _)| TOPICS.iter().any(|(t, _)| t == topic)));
+    }
+}
@@ -0,0 +1,722 @@
+//! Splitting extracted text into chunks for embedding.
+//!
+//! `process_document` and `ingest_document` take `ChunkingOptions`, choosing
+//! one of these strategies along with the chunk size and overlap (in
+//! characters):
+//!
+//! - `fixed`: windows of `chunk_size` with `overlap`, ending early at a
+//!   nearby paragraph or sentence break (the original behavior)
+//! - `sentence`: whole sentences packed up to `chunk_size`, overlapping by
+//!   trailing sentences
+//! - `markdown`: a new chunk at every heading outside code fences, long
+//!   sections split between paragraphs
+//! - `code`: whole lines, split preferably at blank lines or top-level
+//!   definitions
+//!
+//! Every strategy is incremental, so files are still read in blocks, and a
+//! sentence or line longer than a chunk is split as it arrives rather than
+//! buffered whole. The options used are recorded in each chunk's metadata, so
+//! a file can later be re-chunked the same way.
+
+use std::collections::HashMap;
+
+use serde::{ Deserialize, Serialize };
+
+use super::Document;
+use crate::constants;
+
+/// Metadata entries recording how a chunk was made
+pub const STRATEGY_KEY: &str = "chunking_strategy";
+pub const CHUNK_SIZE_KEY: &str = "chunk_size";
+pub const OVERLAP_KEY: &str = "chunk_overlap";
+
+/// How far back the fixed strategy looks for a paragraph or sentence break
+const BREAK_LOOKBACK: usize = 150;
+
+/// Characters after the indentation kept of a line too long to buffer; enough
+/// for the line rules to recognize a fence or a blank line
+const LINE_START_CHARS: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkingStrategy {
+    #[default]
+    Fixed,
+    Sentence,
+    Markdown,
+    Code,
+}
+
+impl ChunkingStrategy {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Fixed => "fixed",
+            Self::Sentence => "sentence",
+            Self::Markdown => "markdown",
+            Self::Code => "code",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [Self::Fixed, Self::Sentence, Self::Markdown, Self::Code]
+            .into_iter()
+            .find(|strategy| strategy.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingOptions {
+    pub strategy: ChunkingStrategy,
+    /// Longest chunk, in characters
+    pub chunk_size: usize,
+    /// Characters repeated from the end of one chunk at the start of the next
+    pub overlap: usize,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            strategy: ChunkingStrategy::default(),
+            chunk_size: constants::DEFAULT_CHUNK_SIZE,
+            overlap: constants::DEFAULT_CHUNK_OVERLAP,
+        }
+    }
+}
+
+impl ChunkingOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chunk_size == 0 {
+            return Err("chunk_size must be at least 1".to_string());
+        }
+        if self.overlap >= self.chunk_size {
+            return Err(format!("overlap ({}) must be smaller than chunk_size ({})", self.overlap, self.chunk_size));
+        }
+        Ok(())
+    }
+
+    /// The metadata entries recording these options on a chunk
+    pub fn record(&self, metadata: &mut HashMap<String, String>) {
+        metadata.insert(STRATEGY_KEY.to_string(), self.strategy.as_str().to_string());
+        metadata.insert(CHUNK_SIZE_KEY.to_string(), self.chunk_size.to_string());
+        metadata.insert(OVERLAP_KEY.to_string(), self.overlap.to_string());
+    }
+
+    /// The options a chunk was made with; `None` for chunks indexed before they were recorded
+    pub fn of_document(document: &Document) -> Option<Self> {
+        let metadata = &document.metadata;
+        Some(Self {
+            strategy: ChunkingStrategy::parse(metadata.get(STRATEGY_KEY)?)?,
+            chunk_size: metadata.get(CHUNK_SIZE_KEY)?.parse().ok()?,
+            overlap: metadata.get(OVERLAP_KEY)?.parse().ok()?,
+        })
+    }
+}
+
+/// Chunker for any strategy. Text can be pushed in pieces of any size.
+pub(crate) enum Chunker {
+    Fixed(TextChunker),
+    Sentence(SentenceSplitter),
+    Markdown(LineSplitter<MarkdownRules>),
+    Code(LineSplitter<CodeRules>),
+}
+
+impl Chunker {
+    pub fn new(options: &ChunkingOptions) -> Self {
+        let packer = || UnitPacker::new(options.chunk_size, options.overlap);
+        match options.strategy {
+            ChunkingStrategy::Fixed => Self::Fixed(TextChunker::new(options.chunk_size, options.overlap)),
+            ChunkingStrategy::Sentence => Self::Sentence(SentenceSplitter {
+                packer: packer(),
+                pending: String::new(),
+                scanned: 0,
+                paragraph_start: false,
+            }),
+            ChunkingStrategy::Markdown => Self::Markdown(LineSplitter::new(packer(), MarkdownRules::default())),
+            ChunkingStrategy::Code => Self::Code(LineSplitter::new(packer(), CodeRules::default())),
+        }
+    }
+
+    /// Append text and return every chunk that is now complete
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        match self {
+            Self::Fixed(chunker) => chunker.push(text),
+            Self::Sentence(splitter) => splitter.push(text),
+            Self::Markdown(splitter) => splitter.push(text),
+            Self::Code(splitter) => splitter.push(text),
+        }
+    }
+
+    /// Flush the remaining text
+    pub fn finish(self) -> Vec<String> {
+        match self {
+            Self::Fixed(chunker) => chunker.finish().into_iter().collect(),
+            Self::Sentence(splitter) => splitter.finish(),
+            Self::Markdown(splitter) => splitter.finish(),
+            Self::Code(splitter) => splitter.finish(),
+        }
+    }
+}
+
+/// Split a whole text at once
+pub(crate) fn chunk_text(text: &str, options: &ChunkingOptions) -> Vec<String> {
+    let mut chunker = Chunker::new(options);
+    let mut chunks = chunker.push(text);
+    chunks.extend(chunker.finish());
+    chunks
+}
+
+/// Incremental text chunker. Text can be pushed in pieces of any size; only
+/// the unconsumed tail (at most one chunk plus the latest piece) is buffered.
+/// Produces the same chunks as splitting the concatenated text at once.
+pub(crate) struct TextChunker {
+    buf: Vec<char>,
+    chunk_size: usize,
+    overlap: usize,
+}
+
+impl TextChunker {
+    pub fn new(chunk_size: usize, overlap: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        Self {
+            buf: Vec::new(),
+            chunk_size,
+            // Each chunk must advance by at least one character
+            overlap: overlap.min(chunk_size - 1),
+        }
+    }
+
+    /// Append text and return every chunk that is now complete
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.buf.extend(text.chars());
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        // A chunk is only final once text beyond its end is known
+        while self.buf.len() - start > self.chunk_size {
+            let end = start + chunk_end(&self.buf[start..], self.chunk_size);
+            let chunk: String = self.buf[start..end].iter().collect();
+            if !chunk.trim().is_empty() {
+                chunks.push(chunk);
+            }
+            start += self.chunk_size - self.overlap;
+        }
+        self.buf.drain(..start);
+        chunks
+    }
+
+    /// Flush the remaining text as the last chunk
+    pub fn finish(self) -> Option<String> {
+        let chunk: String = self.buf.iter().collect();
+        if chunk.trim().is_empty() { None } else { Some(chunk) }
+    }
+}
+
+/// End of the chunk starting at 0, preferring a paragraph or sentence break
+/// shortly before `chunk_size`. Requires `chars.len() > chunk_size`.
+fn chunk_end(chars: &[char], chunk_size: usize) -> usize {
+    let end = chunk_size;
+
+    // Look back for a paragraph break
+    let search_start = end.saturating_sub(BREAK_LOOKBACK);
+    if let Some(para_pos) = chars[search_start..end]
+        .windows(2)
+        .rposition(|w| w[0] == '\n' && w[1] == '\n')
+    {
+        search_start + para_pos + 2 // Include both newlines
+    }
+    // If no paragraph break, try sentence boundary
+    else if let Some(sent_pos) = chars[search_start..end]
+        .iter()
+        .rposition(|&c| c == '.' || c == '!' || c == '?')
+    {
+        search_start + sent_pos + 1
+    } else {
+        end
+    }
+}
+
+/// Where a chunk may end before a unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Break {
+    /// Only when the chunk is full
+    None,
+    /// Preferred once the chunk is half full
+    Soft,
+    /// Always; the next chunk does not overlap the previous one
+    Hard,
+}
+
+/// Packs units (sentences, lines) into chunks without splitting them, unless
+/// a single unit is longer than a chunk
+struct UnitPacker {
+    chunk_size: usize,
+    overlap: usize,
+    /// Units of the chunk being filled, with their length in characters
+    units: Vec<(String, usize, Break)>,
+    len: usize,
+    /// A unit longer than a chunk, being split as it arrives (see `push_long`)
+    long: Option<TextChunker>,
+}
+
+impl UnitPacker {
+    fn new(chunk_size: usize, overlap: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        Self { chunk_size, overlap: overlap.min(chunk_size - 1), units: Vec::new(), len: 0, long: None }
+    }
+
+    fn push(&mut self, text: String, brk: Break) -> Vec<String> {
+        let mut chunks: Vec<String> = self.end_long().into_iter().collect();
+        let len = text.chars().count();
+
+        if brk == Break::Hard || len > self.chunk_size {
+            chunks.extend(self.take(self.units.len(), false));
+        }
+        if len > self.chunk_size {
+            let mut chunker = TextChunker::new(self.chunk_size, self.overlap);
+            chunks.extend(chunker.push(&text));
+            chunks.extend(chunker.finish());
+            return chunks;
+        }
+
+        if self.len + len > self.chunk_size {
+            // End at the last preferred break in the second half of the chunk, if any
+            let mut prefix = 0;
+            let mut cut = self.units.len();
+            for (i, (_, unit_len, unit_break)) in self.units.iter().enumerate() {
+                if i > 0 && *unit_break != Break::None && prefix * 2 >= self.chunk_size {
+                    cut = i;
+                }
+                prefix += unit_len;
+            }
+            chunks.extend(self.take(cut, true));
+            // Drop overlap that would leave no room for the new unit
+            while self.len + len > self.chunk_size && !self.units.is_empty() {
+                let (_, unit_len, _) = self.units.remove(0);
+                self.len -= unit_len;
+            }
+        }
+
+        self.len += len;
+        self.units.push((text, len, brk));
+        chunks
+    }
+
+    /// Emit the first `count` units as a chunk; with `overlap`, its trailing
+    /// units up to `overlap` characters stay to start the next one
+    fn take(&mut self, count: usize, overlap: bool) -> Option<String> {
+        if count == 0 {
+            return None;
+        }
+        let rest = self.units.split_off(count);
+        let emitted = std::mem::replace(&mut self.units, rest);
+        let chunk: String = emitted.iter().map(|(text, _, _)| text.as_str()).collect();
+
+        let mut carried = Vec::new();
+        if overlap {
+            let mut carried_len = 0;
+            // The first unit is never carried, so every chunk advances
+            for unit in emitted.into_iter().skip(1).rev() {
+                if carried_len + unit.1 > self.overlap {
+                    break;
+                }
+                carried_len += unit.1;
+                carried.push(unit);
+            }
+            carried.reverse();
+        }
+        carried.append(&mut self.units);
+        self.units = carried;
+        self.len = self.units.iter().map(|(_, len, _)| len).sum();
+
+        (!chunk.trim().is_empty()).then_some(chunk)
+    }
+
+    /// Add a part of a unit known to be longer than a chunk. The result is the
+    /// same as pushing the whole unit at once; `end_long` or the next `push`
+    /// ends it.
+    fn push_long(&mut self, text: &str) -> Vec<String> {
+        let mut chunks = Vec::new();
+        if self.long.is_none() {
+            chunks.extend(self.take(self.units.len(), false));
+        }
+        let (chunk_size, overlap) = (self.chunk_size, self.overlap);
+        let long = self.long.get_or_insert_with(|| TextChunker::new(chunk_size, overlap));
+        chunks.extend(long.push(text));
+        chunks
+    }
+
+    fn in_long(&self) -> bool {
+        self.long.is_some()
+    }
+
+    fn end_long(&mut self) -> Option<String> {
+        self.long.take().and_then(TextChunker::finish)
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        let mut chunks: Vec<String> = self.end_long().into_iter().collect();
+        let count = self.units.len();
+        chunks.extend(self.take(count, false));
+        chunks
+    }
+}
+
+pub(crate) struct SentenceSplitter {
+    packer: UnitPacker,
+    /// Text after the last complete sentence
+    pending: String,
+    /// Where to resume looking for a sentence end in `pending`
+    scanned: usize,
+    /// Whether the next sentence starts a paragraph
+    paragraph_start: bool,
+}
+
+impl SentenceSplitter {
+    fn push(&mut self, text: &str) -> Vec<String> {
+        self.pending.push_str(text);
+        let mut chunks = Vec::new();
+        loop {
+            match sentence_end(&self.pending, self.scanned) {
+                Ok((end, paragraph_end)) => {
+                    let rest = self.pending.split_off(end);
+                    let sentence = std::mem::replace(&mut self.pending, rest);
+                    self.scanned = 0;
+                    if self.packer.in_long() {
+                        chunks.extend(self.packer.push_long(&sentence));
+                        chunks.extend(self.packer.end_long());
+                    } else {
+                        let brk = if self.paragraph_start { Break::Soft } else { Break::None };
+                        chunks.extend(self.packer.push(sentence, brk));
+                    }
+                    self.paragraph_start = paragraph_end;
+                }
+                Err(resume) => {
+                    self.scanned = resume;
+                    break;
+                }
+            }
+        }
+
+        // The sentence so far is already longer than a chunk: pass on what is
+        // scanned instead of holding the whole sentence
+        if self.packer.in_long() || self.pending.chars().count() > self.packer.chunk_size {
+            let rest = self.pending.split_off(self.scanned);
+            let part = std::mem::replace(&mut self.pending, rest);
+            self.scanned = 0;
+            chunks.extend(self.packer.push_long(&part));
+        }
+        chunks
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let rest = std::mem::take(&mut self.pending);
+        if self.packer.in_long() {
+            chunks.extend(self.packer.push_long(&rest));
+        } else if !rest.is_empty() {
+            chunks.extend(self.packer.push(rest, Break::None));
+        }
+        chunks.extend(self.packer.finish());
+        chunks
+    }
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。' | '！' | '？')
+}
+
+fn is_closer(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '”' | '’')
+}
+
+/// End of the first complete sentence in `text` at or after `from`, including
+/// the whitespace after it, and whether a paragraph ends there. A sentence is
+/// complete once the character after its whitespace is known. Without one,
+/// the offset to resume from once more text arrives: the start of the
+/// trailing terminators, closers and whitespace, which more text may still
+/// turn into a sentence end.
+fn sentence_end(text: &str, from: usize) -> Result<(usize, bool), usize> {
+    let mut chars = text[from..].char_indices().map(|(i, c)| (from + i, c)).peekable();
+    while let Some((i, c)) = chars.next() {
+        let terminator = is_terminator(c);
+        let paragraph = c == '\n' && chars.peek().is_some_and(|&(_, next)| next == '\n');
+        if !terminator && !paragraph {
+            continue;
+        }
+        let after = i + c.len_utf8();
+        // Closing quotes and brackets stay with the sentence
+        let after = after + text[after..]
+            .chars()
+            .take_while(|&c| is_closer(c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        let whitespace: usize = text[after..].chars().take_while(|c| c.is_whitespace()).map(char::len_utf8).sum();
+        if whitespace == 0 && !paragraph {
+            // "3.14" or "e.g." inside a sentence
+            continue;
+        }
+        let end = after + whitespace;
+        if end >= text.len() {
+            // More whitespace may follow in the next piece
+            break;
+        }
+        return Ok((end, text[after..end].contains("\n\n")));
+    }
+
+    let undecided: usize = text[from..]
+        .chars()
+        .rev()
+        .take_while(|&c| c.is_whitespace() || is_terminator(c) || is_closer(c))
+        .map(char::len_utf8)
+        .sum();
+    Err(text.len() - undecided)
+}
+
+/// What decides where a line-based chunk may end
+pub(crate) trait LineRules {
+    /// The break before `line`, updating any state that depends on it. For a
+    /// line too long to keep, `line` is only its first characters after the
+    /// indentation, and the break is not used.
+    fn line_break(&mut self, line: &str) -> Break;
+}
+
+/// Packs whole lines into chunks according to `R`. Only the current line is
+/// buffered, and only until it is longer than a chunk.
+pub(crate) struct LineSplitter<R> {
+    packer: UnitPacker,
+    rules: R,
+    /// The current line, newline excluded
+    pending: String,
+    pending_chars: usize,
+    /// Start of a line that is being passed on as it arrives
+    long_start: Option<String>,
+}
+
+impl<R: LineRules> LineSplitter<R> {
+    fn new(packer: UnitPacker, rules: R) -> Self {
+        Self { packer, rules, pending: String::new(), pending_chars: 0, long_start: None }
+    }
+
+    fn push(&mut self, text: &str) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut rest = text;
+        while let Some(newline) = rest.find('\n') {
+            let (line, after) = rest.split_at(newline + 1);
+            self.append(line, &mut chunks);
+            self.end_line(&mut chunks);
+            rest = after;
+        }
+        self.append(rest, &mut chunks);
+        chunks
+    }
+
+    fn append(&mut self, text: &str, chunks: &mut Vec<String>) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(start) = &mut self.long_start {
+            record_line_start(start, text);
+            chunks.extend(self.packer.push_long(text));
+            return;
+        }
+
+        self.pending.push_str(text);
+        self.pending_chars += text.chars().count();
+        if self.pending_chars > self.packer.chunk_size {
+            let line = std::mem::take(&mut self.pending);
+            self.pending_chars = 0;
+            let mut start = String::new();
+            record_line_start(&mut start, &line);
+            self.long_start = Some(start);
+            chunks.extend(self.packer.push_long(&line));
+        }
+    }
+
+    fn end_line(&mut self, chunks: &mut Vec<String>) {
+        match self.long_start.take() {
+            Some(start) => {
+                self.rules.line_break(&start);
+                chunks.extend(self.packer.end_long());
+            }
+            None => {
+                let line = std::mem::take(&mut self.pending);
+                self.pending_chars = 0;
+                let brk = self.rules.line_break(&line);
+                chunks.extend(self.packer.push(line, brk));
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        let mut chunks = Vec::new();
+        if !self.pending.is_empty() || self.long_start.is_some() {
+            self.end_line(&mut chunks);
+        }
+        chunks.extend(self.packer.finish());
+        chunks
+    }
+}
+
+/// Add to `start` the first of `text`'s characters after the indentation,
+/// up to `LINE_START_CHARS`
+fn record_line_start(start: &mut String, text: &str) {
+    let text = if start.is_empty() { text.trim_start() } else { text };
+    let needed = LINE_START_CHARS.saturating_sub(start.chars().count());
+    start.extend(text.chars().take(needed));
+}
+
+#[derive(Default)]
+pub(crate) struct MarkdownRules {
+    in_fence: bool,
+    after_blank: bool,
+}
+
+impl LineRules for MarkdownRules {
+    fn line_break(&mut self, line: &str) -> Break {
+        let trimmed = line.trim_start();
+        let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        let heading = !self.in_fence && is_heading(trimmed);
+
+        let brk = if heading {
+            Break::Hard
+        } else if self.after_blank && !self.in_fence {
+            Break::Soft
+        } else {
+            Break::None
+        };
+        if fence {
+            self.in_fence = !self.in_fence;
+        }
+        self.after_blank = line.trim().is_empty();
+        brk
+    }
+}
+
+/// `#` to `######` followed by a space
+fn is_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    (1..=6).contains(&level) && line[level..].starts_with([' ', '\t'])
+}
+
+#[derive(Default)]
+pub(crate) struct CodeRules {
+    after_blank: bool,
+}
+
+impl LineRules for CodeRules {
+    fn line_break(&mut self, line: &str) -> Break {
+        let blank = line.trim().is_empty();
+        // Top-level items start in the first column; closing brackets end the previous one
+        let top_level = !blank
+            && !line.starts_with([' ', '\t'])
+            && !line.trim_start().starts_with(['}', ')', ']']);
+        let brk = if self.after_blank || top_level { Break::Soft } else { Break::None };
+        self.after_blank = blank;
+        brk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(strategy: ChunkingStrategy, chunk_size: usize, overlap: usize) -> ChunkingOptions {
+        ChunkingOptions { strategy, chunk_size, overlap }
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        let text = "This is a test text that should be chunked properly.";
+        let chunks = chunk_text(text, &options(ChunkingStrategy::Fixed, 20, 5));
+        assert!(!chunks.is_empty());
+        assert!(chunks[0].len() <= 20);
+    }
+
+    #[test]
+    fn test_every_strategy_matches_whole_text_when_fed_in_pieces() {
+        let text = "# Title\n\nFirst sentence here. Second one follows!\n\nfn main() {\n    println!(\"3.14\");\n}\n\n## Part\nNew paragraph with more words. ".repeat(20);
+        for strategy in [ChunkingStrategy::Fixed, ChunkingStrategy::Sentence, ChunkingStrategy::Markdown, ChunkingStrategy::Code] {
+            let options = options(strategy, 120, 30);
+            let whole = chunk_text(&text, &options);
+
+            let mut chunker = Chunker::new(&options);
+            let mut pieces = Vec::new();
+            let chars: Vec<char> = text.chars().collect();
+            for piece in chars.chunks(7) {
+                pieces.extend(chunker.push(&piece.iter().collect::<String>()));
+            }
+            pieces.extend(chunker.finish());
+
+            assert_eq!(whole, pieces, "{:?}", strategy);
+            assert!(whole.len() > 1);
+            assert!(whole.iter().all(|chunk| chunk.chars().count() <= 120), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_overlong_sentences_and_lines_are_not_buffered_whole() {
+        let text = format!("Intro. {}\n\nOutro here. ", "word ".repeat(400));
+        for strategy in [ChunkingStrategy::Sentence, ChunkingStrategy::Markdown, ChunkingStrategy::Code] {
+            let options = options(strategy, 100, 20);
+            let whole = chunk_text(&text, &options);
+
+            let mut chunker = Chunker::new(&options);
+            let mut pieces = Vec::new();
+            for piece in text.as_bytes().chunks(9) {
+                pieces.extend(chunker.push(std::str::from_utf8(piece).unwrap()));
+                let pending = match &chunker {
+                    Chunker::Sentence(splitter) => splitter.pending.len(),
+                    Chunker::Markdown(splitter) => splitter.pending.len(),
+                    Chunker::Code(splitter) => splitter.pending.len(),
+                    Chunker::Fixed(_) => unreachable!(),
+                };
+                assert!(pending <= 100 + 9, "{:?}", strategy);
+            }
+            pieces.extend(chunker.finish());
+
+            assert_eq!(whole, pieces, "{:?}", strategy);
+            assert!(whole.iter().all(|chunk| chunk.chars().count() <= 100), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_sentence_strategy_keeps_sentences_whole_and_overlaps() {
+        let text = "Alpha is first. Beta has the value 3.14 in it. Gamma comes third! Delta ends it?";
+        let chunks = chunk_text(text, &options(ChunkingStrategy::Sentence, 50, 35));
+        assert_eq!(chunks, vec![
+            "Alpha is first. Beta has the value 3.14 in it. ",
+            "Beta has the value 3.14 in it. Gamma comes third! ",
+            "Gamma comes third! Delta ends it?",
+        ]);
+    }
+
+    #[test]
+    fn test_markdown_strategy_starts_chunks_at_headings() {
+        let text = "# One\nShort intro.\n\n## Two\nMore text.\n```\n# not a heading\n```\n";
+        let chunks = chunk_text(text, &options(ChunkingStrategy::Markdown, 500, 50));
+        assert_eq!(chunks, vec![
+            "# One\nShort intro.\n\n",
+            "## Two\nMore text.\n```\n# not a heading\n```\n",
+        ]);
+    }
+
+    #[test]
+    fn test_code_strategy_splits_between_definitions() {
+        let text = "fn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n";
+        let chunks = chunk_text(text, &options(ChunkingStrategy::Code, 30, 0));
+        assert_eq!(chunks, vec!["fn a() {\n    one();\n}\n\n", "fn b() {\n    two();\n}\n"]);
+    }
+
+    #[test]
+    fn test_options_round_trip_through_metadata() {
+        let options = options(ChunkingStrategy::Markdown, 800, 100);
+        let mut document = Document::new("t".to_string(), "c".to_string(), "md".to_string(), "a.md".to_string(), Some(0));
+        assert_eq!(ChunkingOptions::of_document(&document), None);
+        options.record(&mut document.metadata);
+        assert_eq!(ChunkingOptions::of_document(&document), Some(options));
+
+        assert!(ChunkingOptions { overlap: 800, ..ChunkingOptions::default() }.validate().is_ok());
+        assert!(ChunkingOptions { chunk_size: 100, overlap: 100, ..ChunkingOptions::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Sources behind the numbered excerpts of a RAG answer.
+//!
+//! Retrieved chunks are put into the prompt as `Source [n]` and the model is
+//! asked to cite them with `[n]` markers. Before the answer streams,
+//! `rag-citations` tells the UI which file, chunk and score each marker
+//! stands for, so the markers can link to their sources.
+
+use serde::{ Deserialize, Serialize };
+
+use super::SearchResult;
+
+/// Characters of the chunk shown when a citation is previewed
+const EXCERPT_CHARS: usize = 240;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// The `n` of `[n]` in the answer
+    pub marker: usize,
+    pub file_path: String,
+    pub title: String,
+    pub chunk_index: Option<usize>,
+    pub score: f32,
+    pub rerank_score: Option<f32>,
+    pub excerpt: String,
+}
+
+/// Payload of `rag-citations`
+#[derive(Debug, Clone, Serialize)]
+pub struct RagCitations {
+    pub stream_id: String,
+    pub session_id: Option<String>,
+    pub citations: Vec<Citation>,
+}
+
+fn excerpt(content: &str) -> String {
+    let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match content.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}...", &content[..end]),
+        None => content,
+    }
+}
+
+/// Citations for results in the order they are numbered in the prompt
+pub(crate) fn from_results(results: &[SearchResult]) -> Vec<Citation> {
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| Citation {
+            marker: i + 1,
+            file_path: result.document.file_path.clone(),
+            title: result.document.title.clone(),
+            chunk_index: result.document.chunk_index,
+            score: result.score,
+            rerank_score: result.rerank_score,
+            excerpt: excerpt(&result.document.content),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::Document;
+
+    #[test]
+    fn test_from_results_numbers_sources_from_one() {
+        let long = "word\n".repeat(100);
+        let results: Vec<SearchResult> = [("a.md", Some(0), "Short   text"), ("b.pdf", Some(3), long.as_str())]
+            .into_iter()
+            .map(|(path, chunk_index, content)| SearchResult {
+                document: Document::new(path.to_string(), content.to_string(), "md".to_string(), path.to_string(), chunk_index),
+                score: 0.5,
+                rerank_score: Some(0.9),
+            })
+            .collect();
+
+        let citations = from_results(&results);
+        assert_eq!(citations.iter().map(|c| c.marker).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(citations[0].excerpt, "Short text");
+        assert_eq!(citations[1].file_path, "b.pdf");
+        assert_eq!(citations[1].chunk_index, Some(3));
+        assert!(citations[1].excerpt.ends_with("...") && !citations[1].excerpt.contains('\n'));
+    }
+}
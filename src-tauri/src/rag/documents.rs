@@ -3,29 +3,40 @@ use pdf_extract::extract_text;
 use calamine::{Reader, Xlsx, open_workbook};
 use std::path::Path;
 use std::fs;
-use crate::constants;
+use std::io::Read;
+use tokio::sync::mpsc;
+use super::chunking::{ chunk_text, Chunker, ChunkingOptions };
+use super::reindex::FileVersion;
+use crate::{ path_policy, plugins, settings };
+use crate::path_policy::Access;
+
+/// Extensions `process_document` / `ingest_document` read natively; enabled
+/// plugins can add more (see `plugins::parser_for`)
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["pdf", "docx", "xlsx", "xls", "txt", "md", "log", "csv"];
+
+/// Chunks buffered between the reader thread and the consumer
+const CHUNK_CHANNEL_CAPACITY: usize = 64;
+
+/// Read size for plain-text files
+const READ_BLOCK_SIZE: usize = 64 * 1024;
 
 #[tauri::command]
-pub async fn process_document(file_path: String) -> Result<Vec<Document>, String> {
+pub async fn process_document(
+    file_path: String,
+    chunking: Option<ChunkingOptions>
+) -> Result<Vec<Document>, String> {
     log_operation_start!("Process document");
-    
-    let path = Path::new(&file_path);
-    let extension = path.extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    path_policy::check_str(&file_path, Access::Read)?;
 
-    tracing::debug!(file = %file_path, extension = %extension, "Processing document");
+    let mut chunks = stream_document_chunks(file_path.clone(), chunking.unwrap_or_default()).map_err(|e| {
+        log_operation_error!("Process document", &e, file = %file_path);
+        e
+    })?;
 
-    let result = match extension.as_str() {
-        "pdf" => process_pdf(&file_path).await,
-        "docx" => process_docx(&file_path).await,
-        "xlsx" | "xls" => process_excel(&file_path).await,
-        _ => {
-            log_operation_error!("Process document", "Unsupported file type", extension = %extension);
-            Err("Unsupported file type".to_string())
-        }
-    }?;
+    let mut result = Vec::new();
+    while let Some(chunk) = chunks.recv().await {
+        result.push(chunk?);
+    }
     
     log_operation_success!("Process document");
     tracing::debug!(file = %file_path, chunks = result.len(), "Document processed into chunks");
@@ -35,52 +46,184 @@ pub async fn process_document(file_path: String) -> Result<Vec<Document>, String
 
 #[tauri::command]
 pub async fn save_temp_file(file_name: String, file_data: Vec<u8>) -> Result<String, String> {
-    let temp_dir = std::env::temp_dir();
-    let file_path = temp_dir.join(&file_name);
-    
-    fs::write(&file_path, file_data)
-        .map_err(|e| format!("Failed to save temp file: {}", e))?;
+    let file_path = tokio::task::spawn_blocking(move || crate::temp_files::stage(&file_name, &file_data))
+        .await
+        .map_err(|e| format!("Failed to save temp file: {}", e))??;
     
     Ok(file_path.to_string_lossy().to_string())
 }
 
-async fn process_pdf(file_path: &str) -> Result<Vec<Document>, String> {
-    let text = extract_text(file_path)
-        .map_err(|e| {
-            log_operation_error!("PDF extraction", &e, file = %file_path);
-            format!("Failed to extract PDF text: {}", e)
-        })?;
-    
-    tracing::debug!(file = %file_path, text_length = text.len(), "Extracted PDF text");
-    
-    let chunks = chunk_text(&text, constants::DEFAULT_CHUNK_SIZE, constants::DEFAULT_CHUNK_OVERLAP);
-    
-    let mut documents = Vec::new();
-    let file_name = Path::new(file_path)
-        .file_stem()
-        .unwrap_or_default()
-        .to_str()
-        .unwrap_or("Unknown")
-        .to_string();
-    
-    for (i, chunk) in chunks.iter().enumerate() {
-        if chunk.trim().is_empty() {
-            continue; // Skip empty chunks
+/// Parse `file_path` on a blocking thread, sending chunks over a bounded
+/// channel as they are produced. A slow consumer (e.g. embedding) throttles
+/// the reader, so memory stays bounded by the channel rather than the file.
+pub(crate) fn stream_document_chunks(
+    file_path: String,
+    chunking: ChunkingOptions
+) -> Result<mpsc::Receiver<Result<Document, String>>, String> {
+    chunking.validate()?;
+
+    let path = Path::new(&file_path);
+    let extension = path.extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Plugins only fill in formats the app does not read itself
+    let plugin_parser = if SUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
+        None
+    } else {
+        plugins::parser_for(&extension)
+    };
+
+    if !SUPPORTED_EXTENSIONS.contains(&extension.as_str()) && plugin_parser.is_none() {
+        tracing::debug!(extension = %extension, "Unsupported file type");
+        return Err("Unsupported file type".to_string());
+    }
+
+    check_file_size(path)?;
+
+    tracing::debug!(file = %file_path, extension = %extension, "Processing document");
+
+    let (tx, rx) = mpsc::channel(CHUNK_CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        // Recorded on every chunk so `reindex_file` can tell when the file changed
+        let version = match FileVersion::read(Path::new(&file_path)) {
+            Ok(version) => Some(version),
+            Err(e) => {
+                log_warning!("Could not record file version", error = %e, file = %file_path);
+                None
+            }
+        };
+        let mut emitter = ChunkEmitter::new(tx, &file_path, &extension, chunking, version);
+        let result = match (plugin_parser, extension.as_str()) {
+            (Some(parser), _) => read_with_plugin(&parser, &file_path, &mut emitter),
+            (None, "pdf") => read_pdf(&file_path, &mut emitter),
+            (None, "docx") => read_docx(&file_path, &mut emitter),
+            (None, "xlsx" | "xls") => read_excel(&file_path, &mut emitter),
+            (None, _) => read_text_file(&file_path, &mut emitter),
+        };
+        if let Err(e) = result {
+            if !emitter.is_closed() {
+                log_operation_error!("Document extraction", &e, file = %file_path);
+            }
+            let _ = emitter.tx.blocking_send(Err(e));
         }
-        
-        documents.push(Document::new(
-            format!("{} - Part {}", file_name, i + 1),
-            chunk.clone(),
-            "pdf".to_string(),
-            file_path.to_string(),
-            Some(i),
+    });
+
+    Ok(rx)
+}
+
+fn check_file_size(path: &Path) -> Result<(), String> {
+    let limit_mb = settings::current().rag.max_file_size_mb;
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    if limit_mb > 0 && size > limit_mb * 1024 * 1024 {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        return Err(format!(
+            "{} is {:.1} MB, which exceeds the {} MB limit for document indexing. \
+            Raise rag.max_file_size_mb in settings to index larger files.",
+            file_name,
+            size as f64 / (1024.0 * 1024.0),
+            limit_mb
         ));
     }
+    Ok(())
+}
+
+/// Turns chunker output into `Document`s and hands them to the consumer
+struct ChunkEmitter {
+    tx: mpsc::Sender<Result<Document, String>>,
+    file_path: String,
+    file_name: String,
+    file_type: String,
+    chunking: ChunkingOptions,
+    version: Option<FileVersion>,
+}
+
+impl ChunkEmitter {
+    fn new(
+        tx: mpsc::Sender<Result<Document, String>>,
+        file_path: &str,
+        extension: &str,
+        chunking: ChunkingOptions,
+        version: Option<FileVersion>
+    ) -> Self {
+        let file_name = Path::new(file_path)
+            .file_stem()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or("Unknown")
+            .to_string();
+
+        Self {
+            tx,
+            file_path: file_path.to_string(),
+            file_name,
+            // Legacy Excel files have always been indexed as "xlsx"
+            file_type: if extension == "xls" { "xlsx".to_string() } else { extension.to_string() },
+            chunking,
+            version,
+        }
+    }
+
+    /// Send one chunk; `section` (a sheet name) becomes part of the title
+    fn emit(&mut self, section: Option<&str>, index: usize, content: String) -> Result<(), String> {
+        let title = match section {
+            Some(section) => format!("{} - {} - Part {}", self.file_name, section, index + 1),
+            None => format!("{} - Part {}", self.file_name, index + 1),
+        };
+
+        let mut document = Document::new(
+            title,
+            content,
+            self.file_type.clone(),
+            self.file_path.clone(),
+            Some(index),
+        );
+        // Kept so the file can be re-chunked the same way
+        self.chunking.record(&mut document.metadata);
+        if let Some(version) = &self.version {
+            version.record(&mut document.metadata);
+        }
+
+        self.tx
+            .blocking_send(Ok(document))
+            .map_err(|_| "Document processing cancelled".to_string())
+    }
+
+    fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// Feeds chunks to `emitter`, numbering chunks from `next_index`
+fn emit_chunks(
+    emitter: &mut ChunkEmitter,
+    section: Option<&str>,
+    next_index: &mut usize,
+    chunks: impl IntoIterator<Item = String>
+) -> Result<(), String> {
+    for chunk in chunks {
+        emitter.emit(section, *next_index, chunk)?;
+        *next_index += 1;
+    }
+    Ok(())
+}
+
+fn read_pdf(file_path: &str, emitter: &mut ChunkEmitter) -> Result<(), String> {
+    // pdf-extract has no incremental API; the file size limit bounds this
+    let text = extract_text(file_path)
+        .map_err(|e| format!("Failed to extract PDF text: {}", e))?;
     
-    Ok(documents)
+    tracing::debug!(file = %file_path, text_length = text.len(), "Extracted PDF text");
+
+    let chunks = chunk_text(&text, &emitter.chunking);
+    emit_chunks(emitter, None, &mut 0, chunks)
 }
 
-async fn process_docx(file_path: &str) -> Result<Vec<Document>, String> {
+fn read_docx(file_path: &str, emitter: &mut ChunkEmitter) -> Result<(), String> {
     // For now, we'll use a simple text extraction approach
     // You may want to use a more sophisticated DOCX parser
     let _file = fs::File::open(file_path)
@@ -88,134 +231,86 @@ async fn process_docx(file_path: &str) -> Result<Vec<Document>, String> {
     
     // Simple DOCX processing - you might want to use docx-rs properly
     let text = format!("DOCX content from: {}", file_path);
-    
-    let chunks = chunk_text(&text, constants::DEFAULT_CHUNK_SIZE, constants::DEFAULT_CHUNK_OVERLAP);
-    
-    let mut documents = Vec::new();
-    let file_name = Path::new(file_path)
-        .file_stem()
-        .unwrap_or_default()
-        .to_str()
-        .unwrap_or("Unknown")
-        .to_string();
-    
-    for (i, chunk) in chunks.iter().enumerate() {
-        if chunk.trim().is_empty() {
-            continue;
-        }
-        
-        documents.push(Document::new(
-            format!("{} - Part {}", file_name, i + 1),
-            chunk.clone(),
-            "docx".to_string(),
-            file_path.to_string(),
-            Some(i),
-        ));
-    }
-    
-    Ok(documents)
+
+    let chunks = chunk_text(&text, &emitter.chunking);
+    emit_chunks(emitter, None, &mut 0, chunks)
 }
 
-async fn process_excel(file_path: &str) -> Result<Vec<Document>, String> {
+fn read_excel(file_path: &str, emitter: &mut ChunkEmitter) -> Result<(), String> {
     let mut workbook: Xlsx<_> = open_workbook(file_path)
         .map_err(|e| format!("Failed to open Excel: {}", e))?;
     
-    let mut documents = Vec::new();
-    let file_name = Path::new(file_path)
-        .file_stem()
-        .unwrap_or_default()
-        .to_str()
-        .unwrap_or("Unknown")
-        .to_string();
-    
     for sheet_name in workbook.sheet_names().to_vec() {
         if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-            let mut text = String::new();
-            text.push_str(&format!("Sheet: {}\n", sheet_name));
-            
+            // Rows are chunked as they are rendered instead of building the whole sheet as one string
+            let mut chunker = Chunker::new(&emitter.chunking);
+            let mut index = 0;
+            let section = Some(sheet_name.as_str());
+
+            emit_chunks(emitter, section, &mut index, chunker.push(&format!("Sheet: {}\n", sheet_name)))?;
+
+            let mut line = String::new();
             for row in range.rows() {
+                line.clear();
                 for cell in row {
-                    text.push_str(&format!("{}\t", cell));
+                    line.push_str(&format!("{}\t", cell));
                 }
-                text.push('\n');
-            }
-            
-            let chunks = chunk_text(&text, constants::DEFAULT_CHUNK_SIZE, constants::DEFAULT_CHUNK_OVERLAP);
-            
-            for (i, chunk) in chunks.iter().enumerate() {
-                if chunk.trim().is_empty() {
-                    continue;
-                }
-                
-                documents.push(Document::new(
-                    format!("{} - {} - Part {}", file_name, sheet_name, i + 1),
-                    chunk.clone(),
-                    "xlsx".to_string(),
-                    file_path.to_string(),
-                    Some(i),
-                ));
+                line.push('\n');
+                emit_chunks(emitter, section, &mut index, chunker.push(&line))?;
             }
+
+            emit_chunks(emitter, section, &mut index, chunker.finish())?;
         }
     }
     
-    Ok(documents)
+    Ok(())
 }
 
-fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
-    let mut chunks = Vec::new();
-    let chars: Vec<char> = text.chars().collect();
-    
-    if chars.is_empty() {
-        return chunks;
+fn read_with_plugin(parser: &plugins::ParserHandle, file_path: &str, emitter: &mut ChunkEmitter) -> Result<(), String> {
+    tracing::debug!(file = %file_path, plugin = %parser.plugin_id(), "Parsing with plugin");
+    let sections = parser.parse(Path::new(file_path))?;
+
+    for section in sections {
+        let chunks = chunk_text(&section.text, &emitter.chunking);
+        emit_chunks(emitter, section.title.as_deref(), &mut 0, chunks)?;
     }
-    
-    let mut start = 0;
-    while start < chars.len() {
-        let mut end = std::cmp::min(start + chunk_size, chars.len());
-        
-        // Try to break at paragraph boundary (double newline) for better semantic coherence
-        if end < chars.len() {
-            // Look back up to 150 chars for a paragraph break
-            let search_start = std::cmp::max(end.saturating_sub(150), start);
-            if let Some(para_pos) = chars[search_start..end]
-                .windows(2)
-                .rposition(|w| w[0] == '\n' && w[1] == '\n')
-            {
-                end = search_start + para_pos + 2; // Include both newlines
-            } 
-            // If no paragraph break, try sentence boundary
-            else if let Some(sent_pos) = chars[search_start..end]
-                .iter()
-                .rposition(|&c| c == '.' || c == '!' || c == '?')
-            {
-                end = search_start + sent_pos + 1;
-            }
-        }
-        
-        let chunk: String = chars[start..end].iter().collect();
-        
-        if !chunk.trim().is_empty() {
-            chunks.push(chunk);
-        }
-        
-        if end == chars.len() {
+    Ok(())
+}
+
+/// Plain-text formats are read in fixed-size blocks, so arbitrarily large
+/// files (or files with no newlines at all) use constant memory.
+fn read_text_file(file_path: &str, emitter: &mut ChunkEmitter) -> Result<(), String> {
+    let mut file = fs::File::open(file_path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+
+    let mut chunker = Chunker::new(&emitter.chunking);
+    let mut index = 0;
+    let mut block = vec![0u8; READ_BLOCK_SIZE];
+    let mut pending: Vec<u8> = Vec::new();
+
+    loop {
+        let n = file.read(&mut block)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 {
             break;
         }
-        start += chunk_size - overlap;
-    }
-    
-    chunks
-}
+        pending.extend_from_slice(&block[..n]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // Hold back a multi-byte character split across two blocks
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(_) => pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => pending.len(),
+        };
+        let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+        pending.drain(..valid);
 
-    #[test]
-    fn test_chunk_text() {
-        let text = "This is a test text that should be chunked properly.";
-        let chunks = chunk_text(text, 20, 5);
-        assert!(!chunks.is_empty());
-        assert!(chunks[0].len() <= 20);
+        emit_chunks(emitter, None, &mut index, chunker.push(&text))?;
     }
-}
\ No newline at end of file
+
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        emit_chunks(emitter, None, &mut index, chunker.push(&text))?;
+    }
+    emit_chunks(emitter, None, &mut index, chunker.finish())
+}
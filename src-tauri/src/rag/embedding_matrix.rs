@@ -0,0 +1,362 @@
+//! Contiguous, memory-mapped copy of a collection's embeddings.
+//!
+//! sled keeps each embedding inside its bincode-encoded `Document`, so a
+//! brute-force search had to deserialize every document just to score it.
+//! This module maintains a `{collection}.matrix` file of unit-normalized f32
+//! rows, plus a `{collection}.ids` file naming the document of each row, that
+//! searches map into memory and scan with plain dot products.
+//!
+//! The matrix is a cache. Its header records the collection revision it was
+//! built for: inserts append rows while it is current, any other change
+//! leaves it stale, and the next search rebuilds it from sled.
+
+use std::collections::HashMap;
+use std::fs::{ self, File, OpenOptions };
+use std::io::{ BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write };
+use std::path::PathBuf;
+use std::sync::{ Arc, OnceLock };
+
+use memmap2::Mmap;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+
+use super::top_k::TopK;
+use crate::paths;
+
+const MAGIC: &[u8; 4] = b"SPVM";
+const FORMAT_VERSION: u32 = 1;
+
+/// magic, version, dim, reserved, revision; a multiple of 8 so rows stay aligned
+const HEADER_LEN: usize = 24;
+
+pub struct EmbeddingMatrix {
+    mmap: Mmap,
+    dim: usize,
+    ids: Vec<String>,
+}
+
+struct Header {
+    dim: u32,
+    revision: u64,
+}
+
+/// Loaded matrices by collection, with the revision they match
+type LoadedMatrices = HashMap<String, (u64, Arc<EmbeddingMatrix>)>;
+
+/// The lock also serializes writers of the matrix files
+static MATRICES: OnceLock<Mutex<LoadedMatrices>> = OnceLock::new();
+
+fn matrices() -> &'static Mutex<LoadedMatrices> {
+    MATRICES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+impl EmbeddingMatrix {
+    pub fn rows(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn id(&self, row: usize) -> &str {
+        &self.ids[row]
+    }
+
+    /// All rows as one contiguous slice
+    pub fn data(&self) -> &[f32] {
+        bytemuck::cast_slice(&self.mmap[HEADER_LEN..HEADER_LEN + self.rows() * self.dim * 4])
+    }
+
+    /// The `k` rows most similar to a unit-normalized query, as
+    /// `(row, cosine)` pairs, best first. Rows are scored in parallel.
+    pub fn top_k(&self, query_unit: &[f32], k: usize) -> Vec<(usize, f32)> {
+        if self.dim == 0 || k == 0 {
+            return Vec::new();
+        }
+        self.data()
+            .par_chunks_exact(self.dim)
+            .enumerate()
+            .fold(|| TopK::new(k), |mut top, (row, values)| {
+                top.push(dot(values, query_unit), row);
+                top
+            })
+            .reduce(|| TopK::new(k), TopK::merge)
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(score, row)| (row, score))
+            .collect()
+    }
+}
+
+/// Dot product with independent accumulators so the loop vectorizes
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let mut acc = [0.0f32; 8];
+    let a_chunks = a.chunks_exact(8);
+    let b_chunks = b.chunks_exact(8);
+    let tail: f32 = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(x, y)| x * y).sum();
+
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for ((lane, x), y) in acc.iter_mut().zip(x).zip(y) {
+            *lane += x * y;
+        }
+    }
+    acc.iter().sum::<f32>() + tail
+}
+
+/// Scale to unit length; `None` for a zero vector
+pub fn normalize(v: &[f32]) -> Option<Vec<f32>> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return None;
+    }
+    Some(v.iter().map(|x| x / norm).collect())
+}
+
+fn file_paths(collection: &str) -> Result<(PathBuf, PathBuf), String> {
+    let dir = paths::get_embedding_matrix_dir().map_err(|e| e.to_string())?;
+    Ok((dir.join(format!("{}.matrix", collection)), dir.join(format!("{}.ids", collection))))
+}
+
+fn encode_header(header: &Header) -> [u8; HEADER_LEN] {
+    let mut bytes = [0u8; HEADER_LEN];
+    bytes[0..4].copy_from_slice(MAGIC);
+    bytes[4..8].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes[8..12].copy_from_slice(&header.dim.to_le_bytes());
+    bytes[16..24].copy_from_slice(&header.revision.to_le_bytes());
+    bytes
+}
+
+fn decode_header(bytes: &[u8]) -> Option<Header> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+        return None;
+    }
+    if u32::from_le_bytes(bytes[4..8].try_into().ok()?) != FORMAT_VERSION {
+        return None;
+    }
+    Some(Header {
+        dim: u32::from_le_bytes(bytes[8..12].try_into().ok()?),
+        revision: u64::from_le_bytes(bytes[16..24].try_into().ok()?),
+    })
+}
+
+fn read_header(path: &PathBuf) -> Option<Header> {
+    let mut bytes = [0u8; HEADER_LEN];
+    File::open(path).ok()?.read_exact(&mut bytes).ok()?;
+    decode_header(&bytes)
+}
+
+/// The matrix for `collection` if it is current for `revision`
+pub fn load(collection: &str, revision: u64) -> Option<Arc<EmbeddingMatrix>> {
+    let mut loaded = matrices().lock();
+    if let Some((cached_revision, matrix)) = loaded.get(collection) {
+        if *cached_revision == revision {
+            return Some(Arc::clone(matrix));
+        }
+    }
+
+    let (matrix_path, ids_path) = file_paths(collection).ok()?;
+    let file = File::open(&matrix_path).ok()?;
+    // SAFETY: a mapping must not change while it is read. Within Sparrow,
+    // writers hold the same lock, `rebuild` replaces the file by rename and
+    // `append` only adds rows past the end and rewrites the header, so rows a
+    // live mapping reads stay as they were. Nothing stops another process
+    // from writing the file, though: there is no mandatory locking on Windows
+    // or Unix, so this relies on the matrix folder being left to Sparrow.
+    let mmap = unsafe { Mmap::map(&file) }.ok()?;
+
+    let header = decode_header(&mmap)?;
+    if header.revision != revision {
+        return None;
+    }
+
+    let ids: Vec<String> = BufReader::new(File::open(&ids_path).ok()?)
+        .lines()
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    let dim = header.dim as usize;
+    let expected_len = HEADER_LEN + ids.len() * dim * 4;
+    if mmap.len() != expected_len || (dim == 0 && !ids.is_empty()) {
+        tracing::debug!(collection = %collection, "Embedding matrix does not match its id list");
+        return None;
+    }
+
+    let matrix = Arc::new(EmbeddingMatrix { mmap, dim, ids });
+    loaded.insert(collection.to_string(), (revision, Arc::clone(&matrix)));
+    Some(matrix)
+}
+
+/// Write the matrix from scratch. Fails if the embeddings do not all share
+/// one dimension, in which case callers fall back to scanning sled.
+pub fn rebuild<I>(collection: &str, revision: u64, rows: I) -> Result<(), String>
+where
+    I: IntoIterator<Item = (String, Vec<f32>)>,
+{
+    let mut loaded = matrices().lock();
+    loaded.remove(collection);
+
+    let (matrix_path, ids_path) = file_paths(collection)?;
+    let tmp_matrix = matrix_path.with_extension("matrix.tmp");
+    let tmp_ids = ids_path.with_extension("ids.tmp");
+
+    let result = (|| -> Result<usize, String> {
+        let mut matrix_out = BufWriter::new(
+            File::create(&tmp_matrix).map_err(|e| format!("Failed to create embedding matrix: {}", e))?
+        );
+        let mut ids_out = BufWriter::new(
+            File::create(&tmp_ids).map_err(|e| format!("Failed to create embedding ids: {}", e))?
+        );
+
+        // Placeholder until the dimension is known
+        matrix_out.write_all(&[0u8; HEADER_LEN]).map_err(|e| e.to_string())?;
+
+        let mut dim = 0usize;
+        let mut count = 0usize;
+        for (id, embedding) in rows {
+            let Some(unit) = normalize(&embedding) else { continue };
+            if dim == 0 {
+                dim = unit.len();
+            } else if unit.len() != dim {
+                return Err(format!("Mixed embedding dimensions ({} and {})", dim, unit.len()));
+            }
+            matrix_out.write_all(bytemuck::cast_slice(&unit)).map_err(|e| e.to_string())?;
+            writeln!(ids_out, "{}", id).map_err(|e| e.to_string())?;
+            count += 1;
+        }
+
+        ids_out.flush().map_err(|e| e.to_string())?;
+        let mut matrix_file = matrix_out.into_inner().map_err(|e| e.to_string())?;
+        matrix_file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+        matrix_file
+            .write_all(&encode_header(&Header { dim: dim as u32, revision }))
+            .map_err(|e| e.to_string())?;
+        matrix_file.sync_all().map_err(|e| e.to_string())?;
+        Ok(count)
+    })();
+
+    let count = match result {
+        Ok(count) => count,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_matrix);
+            let _ = fs::remove_file(&tmp_ids);
+            return Err(e);
+        }
+    };
+
+    // Ids first: a matrix whose header matches but whose ids do not is rejected on load
+    fs::rename(&tmp_ids, &ids_path).map_err(|e| format!("Failed to replace embedding ids: {}", e))?;
+    fs::rename(&tmp_matrix, &matrix_path).map_err(|e| format!("Failed to replace embedding matrix: {}", e))?;
+
+    tracing::debug!(collection = %collection, rows = count, "Rebuilt embedding matrix");
+    Ok(())
+}
+
+/// Append rows inserted by the write that moved the collection from
+/// `previous` to `revision`. Returns `false` (leaving the matrix stale) if
+/// the matrix was not current for `previous`.
+pub fn append(collection: &str, previous: u64, revision: u64, rows: &[(&str, &[f32])]) -> Result<bool, String> {
+    let mut loaded = matrices().lock();
+
+    let (matrix_path, ids_path) = file_paths(collection)?;
+    let Some(header) = read_header(&matrix_path) else {
+        return Ok(false);
+    };
+    if header.revision != previous {
+        return Ok(false);
+    }
+
+    let mut dim = header.dim as usize;
+    let mut units = Vec::with_capacity(rows.len());
+    for (id, embedding) in rows {
+        let Some(unit) = normalize(embedding) else { continue };
+        if dim == 0 {
+            dim = unit.len();
+        }
+        if unit.len() != dim {
+            return Ok(false);
+        }
+        units.push((*id, unit));
+    }
+
+    // Drop our mapping before the file changes underneath it
+    loaded.remove(collection);
+
+    let mut ids_file = OpenOptions::new()
+        .append(true)
+        .open(&ids_path)
+        .map_err(|e| format!("Failed to open embedding ids: {}", e))?;
+    let mut matrix_file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&matrix_path)
+        .map_err(|e| format!("Failed to open embedding matrix: {}", e))?;
+
+    let mut id_lines = String::new();
+    for (id, _) in &units {
+        id_lines.push_str(id);
+        id_lines.push('\n');
+    }
+    ids_file.write_all(id_lines.as_bytes()).map_err(|e| e.to_string())?;
+
+    matrix_file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+    for (_, unit) in &units {
+        matrix_file.write_all(bytemuck::cast_slice(unit)).map_err(|e| e.to_string())?;
+    }
+
+    // The header is written last, so a crash before this point leaves the matrix stale
+    matrix_file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    matrix_file
+        .write_all(&encode_header(&Header { dim: dim as u32, revision }))
+        .map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+/// Remove the matrix files of one collection
+pub fn forget(collection: &str) {
+    let mut loaded = matrices().lock();
+    loaded.remove(collection);
+    if let Ok((matrix_path, ids_path)) = file_paths(collection) {
+        let _ = fs::remove_file(matrix_path);
+        let _ = fs::remove_file(ids_path);
+    }
+}
+
+/// Remove the matrix files of every collection
+pub fn remove_all() {
+    let mut loaded = matrices().lock();
+    loaded.clear();
+    if let Ok(dir) = paths::get_embedding_matrix_dir() {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_matches_naive() {
+        let a: Vec<f32> = (0..19).map(|i| i as f32 * 0.1).collect();
+        let b: Vec<f32> = (0..19).map(|i| 1.0 - i as f32 * 0.05).collect();
+        let naive: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        assert!((dot(&a, &b) - naive).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let bytes = encode_header(&Header { dim: 768, revision: 42 });
+        let header = decode_header(&bytes).unwrap();
+        assert_eq!(header.dim, 768);
+        assert_eq!(header.revision, 42);
+        assert!(decode_header(&[0u8; HEADER_LEN]).is_none());
+    }
+
+    #[test]
+    fn test_normalize_rejects_zero_vector() {
+        assert!(normalize(&[0.0, 0.0]).is_none());
+        let unit = normalize(&[3.0, 4.0]).unwrap();
+        assert!((unit[0] - 0.6).abs() < 1e-6 && (unit[1] - 0.8).abs() < 1e-6);
+    }
+}
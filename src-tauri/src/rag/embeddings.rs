@@ -1,6 +1,7 @@
 use super::Document;
 use async_openai::{ Client, config::OpenAIConfig };
 use async_openai::types::embeddings::CreateEmbeddingRequestArgs;
+use crate::cancellation::{ self, OperationKind };
 use crate::constants;
 
 pub struct EmbeddingService {
@@ -9,7 +10,7 @@ pub struct EmbeddingService {
 
 impl EmbeddingService {
     pub fn new() -> Self {
-        let api_base = format!("{}{}", constants::OVMS_API_BASE, constants::OVMS_OPENAI_PATH);
+        let api_base = crate::ovms::openai_api_base();
         let config = OpenAIConfig::new()
             .with_api_key("unused")
             .with_api_base(api_base);
@@ -52,13 +53,20 @@ impl EmbeddingService {
     }
 }
 
+/// Embed `documents`; cancellable under `job_id` with `cancel_operation`
 #[tauri::command]
-pub async fn create_document_embeddings(documents: Vec<Document>) -> Result<Vec<Document>, String> {
+pub async fn create_document_embeddings(
+    app: tauri::AppHandle,
+    documents: Vec<Document>,
+    job_id: Option<String>,
+) -> Result<Vec<Document>, String> {
     if documents.is_empty() {
         tracing::trace!("No documents to create embeddings for");
         return Ok(documents);
     }
 
+    crate::ensure_ovms_initialized(&app).await;
+
     log_operation_start!("Create embeddings");
     tracing::debug!(count = documents.len(), "Creating embeddings");
 
@@ -69,7 +77,14 @@ pub async fn create_document_embeddings(documents: Vec<Document>) -> Result<Vec<
         .map(|doc| doc.content.clone())
         .collect();
 
-    let embeddings = embedding_service.create_embeddings(texts).await
+    let job_id = job_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let operation = cancellation::register(&app, &job_id, OperationKind::Embeddings);
+    let embeddings = tokio::select! {
+        _ = operation.cancelled() => Err("Embedding cancelled".to_string()),
+        embeddings = embedding_service.create_embeddings(texts) => embeddings,
+    };
+    drop(operation);
+    let embeddings = embeddings
         .map_err(|e| {
             log_operation_error!("Create embeddings", &e, count = documents.len());
             e
@@ -89,7 +104,8 @@ pub async fn create_document_embeddings(documents: Vec<Document>) -> Result<Vec<
 }
 
 #[tauri::command]
-pub async fn create_query_embedding(query: String) -> Result<Vec<f32>, String> {
+pub async fn create_query_embedding(app: tauri::AppHandle, query: String) -> Result<Vec<f32>, String> {
+    crate::ensure_ovms_initialized(&app).await;
     let embedding_service = EmbeddingService::new();
     embedding_service.create_single_embedding(query).await
 }
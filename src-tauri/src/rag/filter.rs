@@ -0,0 +1,181 @@
+//! Metadata filters for vector search.
+//!
+//! A `SearchFilter` restricts a search to chunks of certain file types, under
+//! a folder, created in a time range or carrying certain tags. The sled store
+//! checks it before scoring a chunk; Qdrant gets it as a payload filter over
+//! the chunk's fields plus the `folders` and `tags` lists stored with it.
+//!
+//! Tags are read from the `tags` metadata entry of a chunk, either comma
+//! separated or as a JSON array of strings (the form an import with a
+//! `tags` list leaves).
+
+use serde::{ Deserialize, Serialize };
+
+use super::Document;
+
+/// Metadata entry holding a chunk's tags
+pub const TAGS_KEY: &str = "tags";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilter {
+    /// Any of these file types
+    pub file_types: Vec<String>,
+    /// Files in this folder or below it
+    pub path_prefix: Option<String>,
+    /// Chunks created at or after this time, in Unix milliseconds
+    pub created_after: Option<i64>,
+    /// Chunks created before this time, in Unix milliseconds
+    pub created_before: Option<i64>,
+    /// Chunks carrying at least one of these tags
+    pub tags: Vec<String>,
+}
+
+/// Forward slashes, and no case on Windows where paths ignore it
+fn normalize_path(path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    if cfg!(windows) { path.to_lowercase() } else { path }
+}
+
+/// Whether `path` is `folder` or lies below it; `/docs` does not match `/docs2/a.md`
+fn is_under(path: &str, folder: &str) -> bool {
+    let folder = folder.trim_end_matches('/');
+    match path.strip_prefix(folder) {
+        Some(rest) => folder.is_empty() || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// A folder condition in the form `folders` lists; `None` when it matches everything
+pub fn folder_key(prefix: &str) -> Option<String> {
+    let folder = normalize_path(prefix);
+    let folder = folder.trim_end_matches('/');
+    (!folder.is_empty()).then(|| folder.to_string())
+}
+
+/// The file's path and every folder above it, so that `is_under(path, folder)`
+/// holds exactly when `folder_key(folder)` is in the list
+pub fn folders(file_path: &str) -> Vec<String> {
+    let path = normalize_path(file_path);
+    let path = path.trim_end_matches('/');
+    let mut folders: Vec<String> = path
+        .match_indices('/')
+        .map(|(i, _)| &path[..i])
+        .filter(|folder| !folder.is_empty())
+        .map(str::to_string)
+        .collect();
+    if !path.is_empty() {
+        folders.push(path.to_string());
+    }
+    folders
+}
+
+/// The chunk's tags, trimmed and lowercased
+pub fn document_tags(document: &Document) -> Vec<String> {
+    let Some(value) = document.metadata.get(TAGS_KEY) else {
+        return Vec::new();
+    };
+    let tags: Vec<String> = match serde_json::from_str::<Vec<String>>(value) {
+        Ok(list) => list,
+        Err(_) => value.split(',').map(str::to_string).collect(),
+    };
+    tags.into_iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+impl SearchFilter {
+    pub fn is_empty(&self) -> bool {
+        self.file_types.is_empty()
+            && self.path_prefix.as_deref().map_or(true, |prefix| prefix.trim().is_empty())
+            && self.created_after.is_none()
+            && self.created_before.is_none()
+            && self.tags.is_empty()
+    }
+
+    pub fn matches(&self, document: &Document) -> bool {
+        if !self.file_types.is_empty()
+            && !self.file_types.iter().any(|file_type| file_type.eq_ignore_ascii_case(&document.file_type))
+        {
+            return false;
+        }
+        if let Some(prefix) = self.path_prefix.as_deref().filter(|prefix| !prefix.trim().is_empty()) {
+            if !is_under(&normalize_path(&document.file_path), &normalize_path(prefix)) {
+                return false;
+            }
+        }
+        if self.created_after.is_some_and(|after| document.created_at < after) {
+            return false;
+        }
+        if self.created_before.is_some_and(|before| document.created_at >= before) {
+            return false;
+        }
+        if !self.tags.is_empty() {
+            let tags = document_tags(document);
+            if !self.tags.iter().any(|wanted| tags.contains(&wanted.trim().to_lowercase())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(file_path: &str, file_type: &str, created_at: i64, tags: Option<&str>) -> Document {
+        let mut document = Document::new("t".to_string(), "c".to_string(), file_type.to_string(), file_path.to_string(), Some(0));
+        document.created_at = created_at;
+        if let Some(tags) = tags {
+            document.metadata.insert(TAGS_KEY.to_string(), tags.to_string());
+        }
+        document
+    }
+
+    #[test]
+    fn test_filter_conditions() {
+        let doc = document("/home/me/docs/report.pdf", "pdf", 1_000, Some("Work, q3"));
+
+        assert!(SearchFilter::default().is_empty());
+        assert!(SearchFilter::default().matches(&doc));
+
+        let by_type = SearchFilter { file_types: vec!["PDF".to_string()], ..Default::default() };
+        assert!(by_type.matches(&doc));
+        assert!(!SearchFilter { file_types: vec!["md".to_string()], ..Default::default() }.matches(&doc));
+
+        let in_folder = |prefix: &str| SearchFilter { path_prefix: Some(prefix.to_string()), ..Default::default() };
+        assert!(in_folder("/home/me/docs").matches(&doc));
+        assert!(in_folder("/home/me/docs/").matches(&doc));
+        assert!(!in_folder("/home/me/doc").matches(&doc));
+
+        let in_range = SearchFilter { created_after: Some(1_000), created_before: Some(2_000), ..Default::default() };
+        assert!(in_range.matches(&doc));
+        assert!(!SearchFilter { created_before: Some(1_000), ..Default::default() }.matches(&doc));
+
+        assert!(SearchFilter { tags: vec!["work".to_string(), "home".to_string()], ..Default::default() }.matches(&doc));
+        assert!(!SearchFilter { tags: vec!["home".to_string()], ..Default::default() }.matches(&doc));
+    }
+
+    #[test]
+    fn test_folders_agree_with_path_prefix() {
+        assert_eq!(folders("/home/me/docs/report.pdf"), vec!["/home", "/home/me", "/home/me/docs", "/home/me/docs/report.pdf"]);
+        assert_eq!(folder_key("/home/me/docs/"), Some("/home/me/docs".to_string()));
+        assert_eq!(folder_key(" / "), None);
+
+        let doc = document("/home/me/docs/report.pdf", "pdf", 0, None);
+        for prefix in ["/home", "/home/me/docs/", "/home/me/doc", "/home/me/docs/report.pdf", "/other"] {
+            let filter = SearchFilter { path_prefix: Some(prefix.to_string()), ..Default::default() };
+            let listed = folder_key(prefix).is_some_and(|key| folders(&doc.file_path).contains(&key));
+            assert_eq!(listed, filter.matches(&doc), "prefix {}", prefix);
+        }
+    }
+
+    #[test]
+    fn test_document_tags_reads_json_arrays_and_lists() {
+        assert_eq!(document_tags(&document("a", "md", 0, Some(r#"["Alpha", " beta "]"#))), vec!["alpha", "beta"]);
+        assert_eq!(document_tags(&document("a", "md", 0, Some("one,,Two"))), vec!["one", "two"]);
+        assert!(document_tags(&document("a", "md", 0, None)).is_empty());
+    }
+}
@@ -0,0 +1,364 @@
+//! Question-answer flashcards generated from ingested documents.
+//!
+//! `generate_flashcards` sends chunks of one indexed file, or of a whole
+//! collection, to the local model and asks for question/answer pairs. Cards
+//! are written as an Anki text export (tab-separated, with the `#separator`
+//! and `#tags column` headers Anki reads on import). Anki's `.apkg` format is
+//! a SQLite archive, which this module does not produce.
+//!
+//! Every generated question is remembered in
+//! `~/.sparrow/flashcards/history.json`, keyed by its normalized text, so
+//! running the command again over the same material yields new cards rather
+//! than repeating old ones.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use serde::{ Deserialize, Serialize };
+use tauri::{ AppHandle, Emitter };
+
+use super::{ backend, Document };
+use crate::{ constants, path_policy, paths, storage };
+
+const HISTORY_FILE: &str = "history.json";
+
+/// Cards asked of the model per chunk
+const CARDS_PER_CHUNK: usize = 3;
+
+/// Longest accepted question or answer, in characters
+const MAX_CARD_CHARS: usize = 600;
+
+const FLASHCARD_PROMPT: &str = "You write study flashcards from text. Reply with JSON only, in this form:\n\
+{\"cards\": [{\"question\": \"...\", \"answer\": \"...\"}]}\n\
+Each question must be answerable from the text alone and ask about one fact or idea. \
+Answers are short, at most two sentences. Do not repeat questions listed as already asked.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Flashcard {
+    pub question: String,
+    pub answer: String,
+    /// File the card was generated from
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlashcardDeck {
+    pub cards: Vec<Flashcard>,
+    /// Anki text export the cards were written to
+    pub output_path: String,
+    /// Cards dropped because the question was generated before
+    pub duplicates: usize,
+    /// Chunks the model gave no usable answer for
+    pub failed_chunks: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlashcardProgress {
+    pub generated: usize,
+    pub target: usize,
+    pub processed_chunks: usize,
+    pub total_chunks: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CardReply {
+    #[serde(default)]
+    cards: Vec<ReplyCard>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyCard {
+    question: String,
+    answer: String,
+}
+
+/// Lowercase alphanumerics with single spaces, so rewording punctuation or
+/// case does not make a question new
+fn normalize(question: &str) -> String {
+    question
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_cards(reply: &str) -> Result<Vec<(String, String)>, String> {
+    let start = reply.find('{').ok_or("Reply contains no JSON object")?;
+    let end = reply.rfind('}').filter(|end| *end > start).ok_or("Reply contains no JSON object")?;
+    let parsed: CardReply = serde_json::from_str(&reply[start..=end])
+        .map_err(|e| format!("Invalid flashcard JSON: {}", e))?;
+
+    let valid = |text: &str| !text.is_empty() && text.chars().count() <= MAX_CARD_CHARS;
+    Ok(parsed.cards
+        .into_iter()
+        .map(|card| (card.question.trim().to_string(), card.answer.trim().to_string()))
+        .filter(|(question, answer)| valid(question) && valid(answer))
+        .collect())
+}
+
+/// One field of the Anki text export; tabs and newlines would break the row
+fn tsv_field(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Anki tags are space separated, so a file name becomes one tag
+fn source_tag(source: &str) -> String {
+    let name = std::path::Path::new(source)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(source);
+    name.chars().map(|c| if c.is_whitespace() { '_' } else { c }).collect()
+}
+
+fn to_anki_text(cards: &[Flashcard]) -> String {
+    let mut text = String::from("#separator:tab\n#html:false\n#tags column:3\n");
+    for card in cards {
+        text.push_str(&format!(
+            "{}\t{}\tsparrow {}\n",
+            tsv_field(&card.question),
+            tsv_field(&card.answer),
+            source_tag(&card.source)
+        ));
+    }
+    text
+}
+
+/// Spread `wanted` picks evenly over `total` chunks, so a long document is
+/// not covered only from its start
+fn chunk_order(total: usize, wanted: usize) -> Vec<usize> {
+    if total == 0 {
+        return Vec::new();
+    }
+    let step = (total / wanted.max(1)).max(1);
+    let mut order: Vec<usize> = (0..total).step_by(step).collect();
+    let picked: HashSet<usize> = order.iter().copied().collect();
+    order.extend((0..total).filter(|index| !picked.contains(index)));
+    order
+}
+
+fn flashcards_dir() -> Result<PathBuf, String> {
+    paths::get_flashcards_dir().map_err(|e| e.to_string())
+}
+
+async fn load_history() -> Result<HashSet<String>, String> {
+    let path = flashcards_dir()?.join(HISTORY_FILE);
+    let Some(json) = storage::read_string(&path).await
+        .map_err(|e| format!("Failed to read flashcard history: {}", e))? else {
+        return Ok(HashSet::new());
+    };
+    serde_json::from_str(&json).map_err(|e| format!("Invalid flashcard history: {}", e))
+}
+
+async fn save_history(history: &HashSet<String>) -> Result<(), String> {
+    let mut questions: Vec<&String> = history.iter().collect();
+    questions.sort();
+    let json = serde_json::to_string(&questions)
+        .map_err(|e| format!("Failed to serialize flashcard history: {}", e))?;
+    storage::write_string(&flashcards_dir()?.join(HISTORY_FILE), &json).await
+        .map_err(|e| format!("Failed to write flashcard history: {}", e))
+}
+
+async fn ask(
+    client: &Client<OpenAIConfig>,
+    model: &str,
+    chunk: &Document,
+    recent: &[String]
+) -> Result<Vec<(String, String)>, String> {
+    let mut prompt = format!("Write up to {} flashcards from this text:\n\n{}", CARDS_PER_CHUNK, chunk.content);
+    if !recent.is_empty() {
+        prompt.push_str("\n\nAlready asked:\n");
+        for question in recent {
+            prompt.push_str(&format!("- {}\n", question));
+        }
+    }
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model.to_string())
+        .messages(vec![
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(FLASHCARD_PROMPT)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(prompt)
+                .build()
+                .map_err(|e| format!("Failed to build user message: {}", e))?
+                .into(),
+        ])
+        .temperature(0.3)
+        .max_tokens(1024u32)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let response = client.chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Flashcard request failed: {}", e))?;
+    let reply = response.choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .unwrap_or_default();
+    parse_cards(&reply)
+}
+
+/// Chunks of one file from the UI's collection, or every chunk of `collection`
+async fn source_chunks(file_path: Option<&str>, collection: Option<&str>) -> Result<Vec<Document>, String> {
+    match (file_path, collection) {
+        (Some(file_path), _) => {
+            let chunks = backend::open_default()?.file_chunks(file_path).await?;
+            if chunks.is_empty() {
+                return Err(format!("File is not indexed: {}", file_path));
+            }
+            Ok(chunks)
+        }
+        (None, Some(collection)) => {
+            let mut chunks = backend::open(collection)?.list_documents().await?;
+            if chunks.is_empty() {
+                return Err(format!("Collection is empty: {}", collection));
+            }
+            chunks.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.chunk_index.cmp(&b.chunk_index)));
+            Ok(chunks)
+        }
+        (None, None) => Err("Pass a file path or a collection to generate flashcards from".to_string()),
+    }
+}
+
+/// Generate up to `count` new cards and write them as an Anki text export,
+/// to `output_path` or a timestamped file in `~/.sparrow/flashcards`
+#[tauri::command]
+pub async fn generate_flashcards(
+    app: AppHandle,
+    file_path: Option<String>,
+    collection: Option<String>,
+    count: Option<usize>,
+    output_path: Option<String>
+) -> Result<FlashcardDeck, String> {
+    let target = count.unwrap_or(constants::DEFAULT_FLASHCARD_COUNT).clamp(1, constants::MAX_FLASHCARD_COUNT);
+    let output_path = match output_path {
+        Some(path) => path_policy::check_str(&path, path_policy::Access::Write)?,
+        None => flashcards_dir()?.join(format!("flashcards-{}.txt", chrono::Local::now().format("%Y%m%d-%H%M%S"))),
+    };
+    log_operation_start!("Generate flashcards", target = target);
+
+    let chunks = source_chunks(file_path.as_deref(), collection.as_deref()).await?;
+
+    crate::ensure_ovms_initialized(&app).await;
+    let model = crate::ovms::get_loaded_model(app.clone()).await?
+        .ok_or("No model is loaded; load a text model to generate flashcards")?;
+    let client = Client::with_config(
+        OpenAIConfig::new()
+            .with_api_key("unused")
+            .with_api_base(crate::ovms::openai_api_base())
+    );
+
+    let mut history = load_history().await?;
+    let mut cards: Vec<Flashcard> = Vec::new();
+    let mut duplicates = 0;
+    let mut failed_chunks = 0;
+    let order = chunk_order(chunks.len(), target.div_ceil(CARDS_PER_CHUNK));
+
+    for (processed, index) in order.iter().enumerate() {
+        if cards.len() >= target {
+            break;
+        }
+        let chunk = &chunks[*index];
+        let recent: Vec<String> = cards.iter().rev().take(10).map(|card| card.question.clone()).collect();
+
+        match ask(&client, &model, chunk, &recent).await {
+            Ok(pairs) => {
+                for (question, answer) in pairs {
+                    if cards.len() >= target {
+                        break;
+                    }
+                    if !history.insert(normalize(&question)) {
+                        duplicates += 1;
+                        continue;
+                    }
+                    cards.push(Flashcard { question, answer, source: chunk.file_path.clone() });
+                }
+            }
+            Err(e) => {
+                failed_chunks += 1;
+                log_warning!("Skipped chunk in flashcard generation", error = %e, chunk = %chunk.id);
+            }
+        }
+        let _ = app.emit("flashcards-progress", FlashcardProgress {
+            generated: cards.len(),
+            target,
+            processed_chunks: processed + 1,
+            total_chunks: order.len(),
+        });
+    }
+
+    if cards.is_empty() {
+        let e = "The model produced no new flashcards for this material".to_string();
+        log_operation_error!("Generate flashcards", &e);
+        return Err(e);
+    }
+
+    storage::write_string(&output_path, &to_anki_text(&cards)).await
+        .map_err(|e| format!("Failed to write flashcards: {}", e))?;
+    save_history(&history).await?;
+
+    log_operation_success!("Generate flashcards", cards = cards.len(), duplicates = duplicates, failed = failed_chunks);
+    Ok(FlashcardDeck {
+        cards,
+        output_path: output_path.to_string_lossy().to_string(),
+        duplicates,
+        failed_chunks,
+    })
+}
+
+/// Forget previously generated questions, so they may be generated again
+#[tauri::command]
+pub async fn clear_flashcard_history() -> Result<String, String> {
+    save_history(&HashSet::new()).await?;
+    Ok("Flashcard history cleared".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_ignores_case_and_punctuation() {
+        assert_eq!(normalize("What is  RAG?"), "what is rag");
+        assert_eq!(normalize("what is rag"), normalize("What is RAG ?!"));
+    }
+
+    #[test]
+    fn test_parse_cards_drops_empty_entries() {
+        let reply = "Sure:\n```json\n{\"cards\": [{\"question\": \" Q1 \", \"answer\": \"A1\"}, {\"question\": \"\", \"answer\": \"A2\"}]}\n```";
+        assert_eq!(parse_cards(reply).unwrap(), vec![("Q1".to_string(), "A1".to_string())]);
+        assert!(parse_cards("no json here").is_err());
+    }
+
+    #[test]
+    fn test_anki_text_escapes_fields() {
+        let cards = vec![Flashcard {
+            question: "Line one\nline\ttwo".into(),
+            answer: "Answer".into(),
+            source: "/docs/My Notes.pdf".into(),
+        }];
+        let text = to_anki_text(&cards);
+        assert!(text.starts_with("#separator:tab\n"));
+        assert!(text.ends_with("Line one line two\tAnswer\tsparrow My_Notes\n"));
+    }
+
+    #[test]
+    fn test_chunk_order_spreads_then_fills() {
+        assert_eq!(chunk_order(6, 2), vec![0, 3, 1, 2, 4, 5]);
+        assert_eq!(chunk_order(2, 5), vec![0, 1]);
+        assert!(chunk_order(0, 3).is_empty());
+    }
+}
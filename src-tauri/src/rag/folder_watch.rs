@@ -0,0 +1,449 @@
+//! Folders whose documents are kept in a collection automatically.
+//!
+//! `watch_folder` saves the folder and attaches a `notify` watcher. Raw
+//! events are collected per file until the file has been quiet for
+//! `SETTLE_DELAY`, so a file that is still being written is read once, when
+//! it is complete. A worker then handles the settled files one at a time:
+//!
+//! - a new or changed file is ingested again and its previous chunks are
+//!   dropped once the new ones are stored; a file whose content is what was
+//!   indexed (only touched, or its permissions changed) is left alone
+//! - a deleted file (or folder) has its chunks removed
+//!
+//! A file is processed under `ingest::lock_file`, so an `ingest_document` or
+//! re-index of the same file waits for it instead of interleaving.
+//!
+//! Each outcome is emitted as `rag-ingest`. Watched folders are restored at
+//! startup; files that were added while the app was closed are picked up by
+//! the same scan that indexes a folder when it is first watched.
+
+use std::collections::{ HashMap, HashSet };
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+use std::time::{ Duration, Instant };
+
+use notify::{ Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher };
+use parking_lot::Mutex;
+use serde::{ Deserialize, Serialize };
+use tauri::{ AppHandle, Emitter };
+use tokio::sync::mpsc;
+
+use super::backend;
+use super::chunking::ChunkingOptions;
+use super::documents::SUPPORTED_EXTENSIONS;
+use super::ingest;
+use super::reindex;
+use super::vector_store::DEFAULT_COLLECTION;
+use crate::cancellation::{ self, OperationKind };
+use crate::path_policy::{ self, Access };
+use crate::{ paths, plugins, storage };
+
+/// Quiet time after the last event for a file before it is processed
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// How often pending files are checked for having settled
+const SETTLE_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchOptions {
+    /// Include subfolders
+    pub recursive: bool,
+    /// Index files already in the folder that are not in the collection yet
+    pub ingest_existing: bool,
+    pub chunking: ChunkingOptions,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self { recursive: true, ingest_existing: true, chunking: ChunkingOptions::default() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchedFolder {
+    pub path: String,
+    pub collection: String,
+    #[serde(default)]
+    pub options: WatchOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestAction {
+    Ingested,
+    Removed,
+    Failed,
+}
+
+/// Payload of `rag-ingest`
+#[derive(Debug, Clone, Serialize)]
+pub struct RagIngestEvent {
+    pub folder: String,
+    pub collection: String,
+    pub file_path: String,
+    pub action: IngestAction,
+    /// Chunks stored for an ingested file, or removed for a deleted one
+    pub chunk_count: usize,
+    pub error: Option<String>,
+}
+
+static FOLDERS: OnceLock<Mutex<Vec<WatchedFolder>>> = OnceLock::new();
+static WATCHERS: OnceLock<Mutex<HashMap<String, RecommendedWatcher>>> = OnceLock::new();
+static EVENT_TX: OnceLock<mpsc::UnboundedSender<(String, Event)>> = OnceLock::new();
+static WORK_TX: OnceLock<mpsc::UnboundedSender<(WatchedFolder, PathBuf)>> = OnceLock::new();
+
+fn folders() -> &'static Mutex<Vec<WatchedFolder>> {
+    FOLDERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn watchers() -> &'static Mutex<HashMap<String, RecommendedWatcher>> {
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Files the document readers (or an enabled plugin) can index. Hidden files
+/// and Office lock files (`~$report.docx`) are left alone.
+fn is_indexable(path: &Path) -> bool {
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    if file_name.starts_with('.') || file_name.starts_with("~$") {
+        return false;
+    }
+    let extension = path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    SUPPORTED_EXTENSIONS.contains(&extension.as_str()) || plugins::parser_for(&extension).is_some()
+}
+
+fn is_relevant(kind: &EventKind) -> bool {
+    matches!(kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+}
+
+/// Whether `file_path` is `folder` itself or lies below it
+fn is_within(file_path: &str, folder: &str) -> bool {
+    Path::new(file_path).starts_with(folder)
+}
+
+async fn load() -> Result<Vec<WatchedFolder>, String> {
+    let path = paths::get_watched_folders_path().map_err(|e| e.to_string())?;
+    match storage::read_string(&path).await.map_err(|e| format!("Failed to read watched folders: {}", e))? {
+        Some(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse watched folders: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+async fn save(folders: &[WatchedFolder]) -> Result<(), String> {
+    let path = paths::get_watched_folders_path().map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(folders)
+        .map_err(|e| format!("Failed to serialize watched folders: {}", e))?;
+    storage::write_string(&path, &content).await
+        .map_err(|e| format!("Failed to write watched folders: {}", e))
+}
+
+fn attach(folder: &WatchedFolder) -> Result<(), String> {
+    let tx = EVENT_TX.get().ok_or("Folder watching is not running")?.clone();
+    let path = Path::new(&folder.path);
+    if !path.is_dir() {
+        return Err(format!("Folder does not exist: {}", folder.path));
+    }
+
+    let key = folder.path.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            let _ = tx.send((key.clone(), event));
+        }
+        Err(e) => tracing::warn!(error = %e, "Folder watcher error"),
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let mode = if folder.options.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+    watcher
+        .watch(path, mode)
+        .map_err(|e| format!("Failed to watch {}: {}", folder.path, e))?;
+
+    watchers().lock().insert(folder.path.clone(), watcher);
+    tracing::info!(path = %folder.path, collection = %folder.collection, "Watching folder for documents");
+    Ok(())
+}
+
+/// Queue the folder's indexable files that have no chunks in its collection yet
+async fn scan(folder: WatchedFolder) -> Result<(), String> {
+    let Some(work) = WORK_TX.get() else {
+        return Ok(());
+    };
+    let indexed: HashSet<PathBuf> = backend::open(&folder.collection)?
+        .list_files()
+        .await?
+        .into_iter()
+        .map(|file| PathBuf::from(file.file_path))
+        .collect();
+
+    let root = PathBuf::from(&folder.path);
+    let recursive = folder.options.recursive;
+    let files = tokio::task::spawn_blocking(move || list_files(&root, recursive))
+        .await
+        .map_err(|e| format!("Failed to list folder: {}", e))?;
+
+    let new_files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| is_indexable(file) && !indexed.contains(file))
+        .collect();
+    tracing::debug!(path = %folder.path, new_files = new_files.len(), "Scanned watched folder");
+    for file in new_files {
+        let _ = work.send((folder.clone(), file));
+    }
+    Ok(())
+}
+
+fn list_files(root: &Path, recursive: bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() && recursive => pending.push(path),
+                Ok(file_type) if file_type.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Bring the collection in line with `path` as it is now
+async fn process(app: &AppHandle, folder: &WatchedFolder, path: &Path) -> Option<RagIngestEvent> {
+    let file_path = path.to_string_lossy().to_string();
+    let event = |action, chunk_count, error| RagIngestEvent {
+        folder: folder.path.clone(),
+        collection: folder.collection.clone(),
+        file_path: file_path.clone(),
+        action,
+        chunk_count,
+        error,
+    };
+
+    let vector_store = match backend::open(&folder.collection) {
+        Ok(vector_store) => vector_store,
+        Err(e) => return Some(event(IngestAction::Failed, 0, Some(e))),
+    };
+    let _file = ingest::lock_file(&file_path).await;
+
+    if !path.exists() {
+        // A deleted folder only reports itself, so drop everything indexed below it
+        let files = match vector_store.list_files().await {
+            Ok(files) => files,
+            Err(e) => return Some(event(IngestAction::Failed, 0, Some(e))),
+        };
+        let mut removed = 0;
+        for file in files.iter().filter(|file| is_within(&file.file_path, &file_path)) {
+            match vector_store.delete_file(&file.file_path).await {
+                Ok(count) => removed += count,
+                Err(e) => return Some(event(IngestAction::Failed, removed, Some(e))),
+            }
+            if folder.collection == DEFAULT_COLLECTION {
+                if let Err(e) = super::graph::KnowledgeGraph::open().and_then(|graph| graph.remove_file(&file.file_path)) {
+                    log_warning!("Failed to remove file from knowledge graph", error = %e, file = %file.file_path);
+                }
+            }
+        }
+        let _ = vector_store.flush().await;
+        return (removed > 0).then(|| event(IngestAction::Removed, removed, None));
+    }
+
+    if !path.is_file() || !is_indexable(path) {
+        return None;
+    }
+
+    let existing = match vector_store.file_chunks(&file_path).await {
+        Ok(chunks) => chunks,
+        Err(e) => return Some(event(IngestAction::Failed, 0, Some(e))),
+    };
+    match reindex::is_current(vector_store.as_ref(), path, &existing).await {
+        Ok(true) => return None,
+        Ok(false) => {}
+        Err(e) => return Some(event(IngestAction::Failed, 0, Some(e))),
+    }
+
+    // The old chunks go only once the new version is stored, so a failed read keeps them
+    let previous: Vec<String> = existing.into_iter().map(|chunk| chunk.id).collect();
+    let operation = cancellation::register(app, &file_path, OperationKind::Ingestion);
+    let ingested = ingest::ingest(
+        app,
+        &operation,
+        file_path.clone(),
+        &folder.collection,
+        folder.options.chunking.clone(),
+        &|_| {}
+    ).await;
+    let summary = match ingested {
+        Ok(summary) => summary,
+        Err(e) => return Some(event(IngestAction::Failed, 0, Some(e))),
+    };
+    for id in &previous {
+        let _ = vector_store.delete_document(id).await;
+    }
+    let _ = vector_store.flush().await;
+
+    // Extract only now, so the old chunks' facts are not read again; the graph
+    // covers the UI's collection only
+    if folder.collection == DEFAULT_COLLECTION {
+        super::graph::extract_file_in_background(app, file_path.clone());
+    }
+    Some(event(IngestAction::Ingested, summary.chunk_count, None))
+}
+
+/// Start the event loop and worker and re-attach saved folders
+pub async fn start(app: AppHandle) {
+    let (event_tx, mut events) = mpsc::unbounded_channel::<(String, Event)>();
+    let (work_tx, mut work) = mpsc::unbounded_channel::<(WatchedFolder, PathBuf)>();
+    if EVENT_TX.set(event_tx).is_err() || WORK_TX.set(work_tx.clone()).is_err() {
+        log_warning!("Folder watching already started");
+        return;
+    }
+
+    // Collect events per file and hand files on once they have settled
+    tauri::async_runtime::spawn(async move {
+        let mut pending: HashMap<PathBuf, (String, Instant)> = HashMap::new();
+        let mut poll = tokio::time::interval(SETTLE_POLL);
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    let Some((folder, event)) = event else { break };
+                    if !is_relevant(&event.kind) {
+                        continue;
+                    }
+                    for path in event.paths {
+                        pending.insert(path, (folder.clone(), Instant::now()));
+                    }
+                }
+                _ = poll.tick() => {
+                    let settled: Vec<PathBuf> = pending
+                        .iter()
+                        .filter(|(_, (_, last))| last.elapsed() >= SETTLE_DELAY)
+                        .map(|(path, _)| path.clone())
+                        .collect();
+                    for path in settled {
+                        let Some((key, _)) = pending.remove(&path) else { continue };
+                        // The folder may have been unwatched in the meantime
+                        let folder = folders().lock().iter().find(|folder| folder.path == key).cloned();
+                        if let Some(folder) = folder {
+                            let _ = work_tx.send((folder, path));
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some((folder, path)) = work.recv().await {
+            if let Some(event) = process(&handle, &folder, &path).await {
+                if let Some(error) = &event.error {
+                    log_warning!("Automatic ingestion failed", error = %error, file = %event.file_path);
+                }
+                let _ = handle.emit("rag-ingest", &event);
+            }
+        }
+    });
+
+    let saved = match load().await {
+        Ok(saved) => saved,
+        Err(e) => {
+            log_warning!("Could not load watched folders", error = %e);
+            return;
+        }
+    };
+    *folders().lock() = saved.clone();
+    for folder in saved {
+        if let Err(e) = attach(&folder) {
+            log_warning!("Could not watch folder", error = %e, path = %folder.path);
+            continue;
+        }
+        if folder.options.ingest_existing {
+            if let Err(e) = scan(folder.clone()).await {
+                log_warning!("Could not scan watched folder", error = %e, path = %folder.path);
+            }
+        }
+    }
+}
+
+/// Watch a folder and keep its documents in `collection` (the document
+/// collection when unset). Watching a folder again replaces its options.
+#[tauri::command]
+pub async fn watch_folder(
+    path: String,
+    collection: Option<String>,
+    options: Option<WatchOptions>
+) -> Result<WatchedFolder, String> {
+    path_policy::check_str(&path, Access::Read)?;
+    let options = options.unwrap_or_default();
+    options.chunking.validate()?;
+
+    let folder = WatchedFolder {
+        path,
+        collection: collection.unwrap_or_else(|| DEFAULT_COLLECTION.to_string()),
+        options,
+    };
+    attach(&folder)?;
+
+    let saved = {
+        let mut folders = folders().lock();
+        folders.retain(|existing| existing.path != folder.path);
+        folders.push(folder.clone());
+        folders.clone()
+    };
+    save(&saved).await?;
+
+    if folder.options.ingest_existing {
+        scan(folder.clone()).await?;
+    }
+    Ok(folder)
+}
+
+/// Stop watching a folder; its documents stay in the collection
+#[tauri::command]
+pub async fn unwatch_folder(path: String) -> Result<bool, String> {
+    watchers().lock().remove(&path);
+    let (removed, saved) = {
+        let mut folders = folders().lock();
+        let before = folders.len();
+        folders.retain(|folder| folder.path != path);
+        (folders.len() != before, folders.clone())
+    };
+    if removed {
+        save(&saved).await?;
+        tracing::info!(path = %path, "Stopped watching folder");
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn get_watched_folders() -> Result<Vec<WatchedFolder>, String> {
+    Ok(folders().lock().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_indexable_skips_hidden_and_lock_files() {
+        assert!(is_indexable(Path::new("/docs/report.PDF")));
+        assert!(is_indexable(Path::new("/docs/notes.md")));
+        assert!(!is_indexable(Path::new("/docs/~$report.docx")));
+        assert!(!is_indexable(Path::new("/docs/.notes.md.swp")));
+    }
+
+    #[test]
+    fn test_is_within_matches_whole_path_components() {
+        assert!(is_within("/docs/a/b.md", "/docs/a"));
+        assert!(is_within("/docs/a", "/docs/a"));
+        assert!(!is_within("/docs/ab/c.md", "/docs/a"));
+    }
+}
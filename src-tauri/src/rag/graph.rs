@@ -0,0 +1,698 @@
+//! Knowledge graph of entities and relations found in ingested documents.
+//!
+//! When `rag.knowledge_graph.enabled` is set, each chunk of a newly ingested
+//! file is sent to the local model, which lists the entities it mentions and
+//! the relations between them. They are kept in a separate sled database:
+//!
+//! - `entities`: normalized name → `Entity`
+//! - `edges`: `source \0 target \0 relation` → `Edge`
+//! - `adjacency`: `entity \0 edge key` for both ends of every edge
+//! - `chunks`: chunk id → what the chunk contributed, so a file can be removed
+//!
+//! A chunk is added or removed in one transaction over all four trees. RAG
+//! retrieval adds the relations around entities named in the question or
+//! in the retrieved chunks as extra context.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use parking_lot::Mutex;
+use serde::{ Deserialize, Serialize };
+use sled::transaction::{ ConflictableTransactionError, TransactionError, TransactionalTree, Transactional };
+use sled::{ Db, Tree };
+use tauri::{ AppHandle, Emitter };
+
+use super::backend;
+use super::{ Document, SearchResult };
+use crate::{ paths, settings };
+
+/// Entities kept per chunk; the rest of a long list is usually noise
+const MAX_ENTITIES_PER_CHUNK: usize = 24;
+
+/// Longest accepted entity name, in characters
+const MAX_ENTITY_NAME_CHARS: usize = 80;
+
+/// Entity names shorter than this are not matched inside questions
+const MIN_MATCH_CHARS: usize = 3;
+
+const EXTRACTION_PROMPT: &str = "You extract a knowledge graph from text. Reply with JSON only, in this form:\n\
+{\"entities\": [{\"name\": \"...\", \"type\": \"person|organization|place|product|concept|event|other\"}],\n \
+\"relations\": [{\"source\": \"...\", \"relation\": \"...\", \"target\": \"...\"}]}\n\
+Use the names as written in the text. Relations are short verb phrases such as \"works for\" or \"is part of\", \
+and both ends must be listed entities. Leave out anything the text does not state.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    pub name: String,
+    pub kind: String,
+    /// Chunks mentioning the entity
+    pub chunk_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Edge {
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+    /// Chunks stating the relation
+    pub chunk_ids: Vec<String>,
+}
+
+/// Keys a chunk added to, for removing it again
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChunkRecord {
+    file_path: String,
+    entities: Vec<String>,
+    edges: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Neighbor {
+    pub name: String,
+    pub kind: String,
+    pub relation: String,
+    pub direction: Direction,
+    /// Chunks stating the relation
+    pub mentions: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityNeighbors {
+    pub entity: Entity,
+    pub neighbors: Vec<Neighbor>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphBuildSummary {
+    pub file_path: String,
+    pub chunks: usize,
+    /// Chunks the model gave no usable answer for
+    pub failed_chunks: usize,
+    /// Distinct entities and relations found in the file
+    pub entities: usize,
+    pub relations: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphProgress {
+    pub file_path: String,
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Extraction {
+    #[serde(default)]
+    entities: Vec<ExtractedEntity>,
+    #[serde(default)]
+    relations: Vec<ExtractedRelation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedEntity {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractedRelation {
+    source: String,
+    relation: String,
+    target: String,
+}
+
+/// Lowercase with single spaces, used as the entity key
+fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn edge_key(source: &str, target: &str, relation: &str) -> String {
+    format!("{}\0{}\0{}", source, target, relation)
+}
+
+fn adjacency_key(entity: &str, edge: &str) -> String {
+    format!("{}\0{}", entity, edge)
+}
+
+/// The JSON object in a model reply, which may be wrapped in prose or a code fence
+fn parse_extraction(reply: &str) -> Result<Extraction, String> {
+    let start = reply.find('{').ok_or("Reply contains no JSON object")?;
+    let end = reply.rfind('}').filter(|end| *end > start).ok_or("Reply contains no JSON object")?;
+    let mut extraction: Extraction = serde_json::from_str(&reply[start..=end])
+        .map_err(|e| format!("Invalid extraction JSON: {}", e))?;
+
+    let valid = |name: &str| !name.trim().is_empty() && name.chars().count() <= MAX_ENTITY_NAME_CHARS;
+    extraction.entities.retain(|entity| valid(&entity.name));
+    extraction.entities.truncate(MAX_ENTITIES_PER_CHUNK);
+    extraction.relations.retain(|relation| {
+        valid(&relation.source) && valid(&relation.target) && !relation.relation.trim().is_empty()
+            && normalize(&relation.source) != normalize(&relation.target)
+    });
+    Ok(extraction)
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Option<T> {
+    serde_json::from_slice(bytes).ok()
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(value).map_err(|e| format!("Failed to encode graph entry: {}", e))
+}
+
+/// The graph's trees inside a transaction: entities, edges, adjacency, chunks
+type GraphTx<'a> = (&'a TransactionalTree, &'a TransactionalTree, &'a TransactionalTree, &'a TransactionalTree);
+
+fn encode_tx<T: Serialize>(value: &T) -> Result<Vec<u8>, ConflictableTransactionError<String>> {
+    encode(value).map_err(ConflictableTransactionError::Abort)
+}
+
+fn transaction_error(action: &str, e: TransactionError<String>) -> String {
+    match e {
+        TransactionError::Abort(e) => e,
+        TransactionError::Storage(e) => format!("Failed to {}: {}", action, e),
+    }
+}
+
+fn push_unique(ids: &mut Vec<String>, id: &str) {
+    if !ids.iter().any(|existing| existing == id) {
+        ids.push(id.to_string());
+    }
+}
+
+pub struct KnowledgeGraph {
+    db: Db,
+    entities: Tree,
+    edges: Tree,
+    adjacency: Tree,
+    chunks: Tree,
+}
+
+impl KnowledgeGraph {
+    /// The database stays open for the life of the app; sled allows one handle per process
+    pub fn open() -> Result<Self, String> {
+        static DB: OnceLock<Db> = OnceLock::new();
+        static OPENING: Mutex<()> = Mutex::new(());
+
+        let db = match DB.get() {
+            Some(db) => db.clone(),
+            None => {
+                let _guard = OPENING.lock();
+                match DB.get() {
+                    Some(db) => db.clone(),
+                    None => {
+                        let path = paths::get_knowledge_graph_path().map_err(|e| e.to_string())?;
+                        let db = sled::open(&path)
+                            .map_err(|e| format!("Failed to open knowledge graph: {}", e))?;
+                        DB.get_or_init(|| db).clone()
+                    }
+                }
+            }
+        };
+        Self::with_db(db)
+    }
+
+    fn with_db(db: Db) -> Result<Self, String> {
+        let tree = |name: &str| db.open_tree(name)
+            .map_err(|e| format!("Failed to open knowledge graph tree '{}': {}", name, e));
+        Ok(Self {
+            entities: tree("entities")?,
+            edges: tree("edges")?,
+            adjacency: tree("adjacency")?,
+            chunks: tree("chunks")?,
+            db,
+        })
+    }
+
+    pub fn entity(&self, name: &str) -> Result<Option<Entity>, String> {
+        let value = self.entities.get(normalize(name))
+            .map_err(|e| format!("Failed to read entity: {}", e))?;
+        Ok(value.and_then(|bytes| decode(&bytes)))
+    }
+
+    /// Apply `change` to all four trees atomically
+    fn transaction<T>(
+        &self,
+        action: &str,
+        change: impl Fn(GraphTx) -> Result<T, ConflictableTransactionError<String>>
+    ) -> Result<T, String> {
+        (&self.entities, &self.edges, &self.adjacency, &self.chunks)
+            .transaction(|(entities, edges, adjacency, chunks)| change((entities, edges, adjacency, chunks)))
+            .map_err(|e| transaction_error(action, e))
+    }
+
+    /// Store what the model found in `chunk`, replacing what it found before.
+    /// Returns the entity and relation keys the chunk contributes.
+    fn add_chunk(&self, chunk: &Document, extraction: &Extraction) -> Result<ChunkRecord, String> {
+        self.transaction("add chunk to knowledge graph", |tx| {
+            remove_chunk_in(tx, &chunk.id)?;
+            add_chunk_in(tx, chunk, extraction)
+        })
+    }
+
+    /// Drop what `chunk_id` contributed; entities and relations no other chunk mentions go too
+    fn remove_chunk(&self, chunk_id: &str) -> Result<(), String> {
+        self.transaction("remove chunk from knowledge graph", |tx| remove_chunk_in(tx, chunk_id))
+    }
+
+    /// Remove everything extracted from `file_path`, returning the number of chunks dropped
+    pub fn remove_file(&self, file_path: &str) -> Result<usize, String> {
+        let chunk_ids: Vec<String> = self.chunks.iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|(_, value)| decode::<ChunkRecord>(value).is_some_and(|record| record.file_path == file_path))
+            .map(|(key, _)| String::from_utf8_lossy(&key).to_string())
+            .collect();
+        for id in &chunk_ids {
+            self.remove_chunk(id)?;
+        }
+        Ok(chunk_ids.len())
+    }
+
+    fn edges_of(&self, entity_key: &str) -> Vec<Edge> {
+        let prefix = format!("{}\0", entity_key);
+        self.adjacency.scan_prefix(prefix.as_bytes())
+            .filter_map(|entry| entry.ok())
+            .filter_map(|(key, _)| {
+                let edge = key.get(prefix.len()..)?.to_vec();
+                self.edges.get(edge).ok().flatten()
+            })
+            .filter_map(|bytes| decode::<Edge>(&bytes))
+            .collect()
+    }
+
+    pub fn neighbors(&self, name: &str, limit: usize) -> Result<Option<EntityNeighbors>, String> {
+        let key = normalize(name);
+        let Some(entity) = self.entity(&key)? else {
+            return Ok(None);
+        };
+
+        let mut neighbors: Vec<Neighbor> = self.edges_of(&key)
+            .into_iter()
+            .map(|edge| {
+                let (other, direction) = if edge.source == key {
+                    (edge.target.clone(), Direction::Outgoing)
+                } else {
+                    (edge.source.clone(), Direction::Incoming)
+                };
+                let other_entity = self.entity(&other).ok().flatten();
+                Neighbor {
+                    name: other_entity.as_ref().map(|e| e.name.clone()).unwrap_or(other),
+                    kind: other_entity.map(|e| e.kind).unwrap_or_default(),
+                    relation: edge.relation,
+                    direction,
+                    mentions: edge.chunk_ids.len(),
+                }
+            })
+            .collect();
+        neighbors.sort_by(|a, b| b.mentions.cmp(&a.mentions).then_with(|| a.name.cmp(&b.name)));
+        neighbors.truncate(limit);
+
+        Ok(Some(EntityNeighbors { entity, neighbors }))
+    }
+
+    /// Keys of known entities whose name occurs in `text` as whole words
+    fn entities_in_text(&self, text: &str) -> Vec<String> {
+        let haystack = format!(" {} ", normalize(&text.replace(|c: char| !c.is_alphanumeric(), " ")));
+        self.entities.iter()
+            .keys()
+            .filter_map(|key| key.ok())
+            .map(|key| String::from_utf8_lossy(&key).to_string())
+            .filter(|key| {
+                let words = normalize(&key.replace(|c: char| !c.is_alphanumeric(), " "));
+                words.chars().count() >= MIN_MATCH_CHARS && haystack.contains(&format!(" {} ", words))
+            })
+            .collect()
+    }
+
+    /// Relations around entities named in `query` or found in the chunks
+    /// `chunk_ids`, as sentences
+    pub fn related_facts(&self, query: &str, chunk_ids: &[String], limit: usize) -> Vec<String> {
+        let mut seeds = self.entities_in_text(query);
+        for chunk_id in chunk_ids {
+            if let Some(record) = self.chunks.get(chunk_id.as_str()).ok().flatten()
+                .and_then(|bytes| decode::<ChunkRecord>(&bytes))
+            {
+                for key in record.entities {
+                    if !seeds.contains(&key) {
+                        seeds.push(key);
+                    }
+                }
+            }
+        }
+
+        let display = |key: &str| self.entity(key).ok().flatten().map(|e| e.name).unwrap_or_else(|| key.to_string());
+        let mut seen = HashSet::new();
+        let mut facts = Vec::new();
+        for seed in &seeds {
+            let mut edges = self.edges_of(seed);
+            edges.sort_by(|a, b| b.chunk_ids.len().cmp(&a.chunk_ids.len()));
+            for edge in edges {
+                if facts.len() >= limit {
+                    return facts;
+                }
+                if seen.insert(edge_key(&edge.source, &edge.target, &edge.relation)) {
+                    facts.push(format!("{} {} {}", display(&edge.source), edge.relation, display(&edge.target)));
+                }
+            }
+        }
+        facts
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        for tree in [&self.entities, &self.edges, &self.adjacency, &self.chunks] {
+            tree.clear().map_err(|e| format!("Failed to clear knowledge graph: {}", e))?;
+        }
+        self.flush()
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        self.db.flush().map_err(|e| format!("Failed to flush knowledge graph: {}", e))?;
+        Ok(())
+    }
+}
+
+fn upsert_entity_in(
+    (entities, ..): GraphTx,
+    key: &str,
+    name: &str,
+    kind: &str,
+    chunk_id: &str
+) -> Result<(), ConflictableTransactionError<String>> {
+    let mut entity = entities.get(key)?
+        .and_then(|bytes| decode::<Entity>(&bytes))
+        .unwrap_or_else(|| Entity { name: name.trim().to_string(), kind: String::new(), chunk_ids: Vec::new() });
+    if entity.kind.is_empty() || entity.kind == "other" {
+        entity.kind = normalize(kind);
+    }
+    push_unique(&mut entity.chunk_ids, chunk_id);
+    entities.insert(key, encode_tx(&entity)?)?;
+    Ok(())
+}
+
+fn add_chunk_in(tx: GraphTx, chunk: &Document, extraction: &Extraction) -> Result<ChunkRecord, ConflictableTransactionError<String>> {
+    let (_, edges, adjacency, chunks) = tx;
+    let mut record = ChunkRecord { file_path: chunk.file_path.clone(), ..Default::default() };
+
+    for entity in &extraction.entities {
+        let key = normalize(&entity.name);
+        upsert_entity_in(tx, &key, &entity.name, &entity.kind, &chunk.id)?;
+        push_unique(&mut record.entities, &key);
+    }
+
+    for relation in &extraction.relations {
+        let (source, target) = (normalize(&relation.source), normalize(&relation.target));
+        // Relations may name an entity the model did not list
+        for (key, name) in [(&source, &relation.source), (&target, &relation.target)] {
+            if !record.entities.contains(key) {
+                upsert_entity_in(tx, key, name, "", &chunk.id)?;
+                record.entities.push(key.clone());
+            }
+        }
+
+        let relation_name = normalize(&relation.relation);
+        let key = edge_key(&source, &target, &relation_name);
+        let mut edge = edges.get(key.as_str())?
+            .and_then(|bytes| decode::<Edge>(&bytes))
+            .unwrap_or_else(|| Edge {
+                source: source.clone(),
+                relation: relation_name.clone(),
+                target: target.clone(),
+                chunk_ids: Vec::new(),
+            });
+        push_unique(&mut edge.chunk_ids, &chunk.id);
+        edges.insert(key.as_str(), encode_tx(&edge)?)?;
+        for end in [&source, &target] {
+            adjacency.insert(adjacency_key(end, &key).as_str(), Vec::<u8>::new())?;
+        }
+        push_unique(&mut record.edges, &key);
+    }
+
+    chunks.insert(chunk.id.as_str(), encode_tx(&record)?)?;
+    Ok(record)
+}
+
+fn remove_chunk_in((entities, edges, adjacency, chunks): GraphTx, chunk_id: &str) -> Result<(), ConflictableTransactionError<String>> {
+    let Some(record) = chunks.remove(chunk_id)?.and_then(|bytes| decode::<ChunkRecord>(&bytes)) else {
+        return Ok(());
+    };
+
+    for key in &record.edges {
+        let Some(mut edge) = edges.get(key.as_str())?.and_then(|bytes| decode::<Edge>(&bytes)) else {
+            continue;
+        };
+        edge.chunk_ids.retain(|id| id != chunk_id);
+        if edge.chunk_ids.is_empty() {
+            adjacency.remove(adjacency_key(&edge.source, key).as_str())?;
+            adjacency.remove(adjacency_key(&edge.target, key).as_str())?;
+            edges.remove(key.as_str())?;
+        } else {
+            edges.insert(key.as_str(), encode_tx(&edge)?)?;
+        }
+    }
+
+    for key in &record.entities {
+        let Some(mut entity) = entities.get(key.as_str())?.and_then(|bytes| decode::<Entity>(&bytes)) else {
+            continue;
+        };
+        entity.chunk_ids.retain(|id| id != chunk_id);
+        if entity.chunk_ids.is_empty() {
+            entities.remove(key.as_str())?;
+        } else {
+            entities.insert(key.as_str(), encode_tx(&entity)?)?;
+        }
+    }
+    Ok(())
+}
+
+async fn extract(client: &Client<OpenAIConfig>, model: &str, chunk: &Document) -> Result<Extraction, String> {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model.to_string())
+        .messages(vec![
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(EXTRACTION_PROMPT)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(chunk.content.clone())
+                .build()
+                .map_err(|e| format!("Failed to build user message: {}", e))?
+                .into(),
+        ])
+        .temperature(0.0)
+        .max_tokens(1024u32)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let response = client.chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Extraction request failed: {}", e))?;
+    let reply = response.choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .unwrap_or_default();
+    parse_extraction(&reply)
+}
+
+/// Rebuild the graph entries of one indexed file, emitting `knowledge-graph-progress` per chunk
+pub async fn extract_file(app: &AppHandle, file_path: &str) -> Result<GraphBuildSummary, String> {
+    log_operation_start!("Extract knowledge graph", file = %file_path);
+
+    let chunks = backend::open_default()?.file_chunks(file_path).await?;
+    if chunks.is_empty() {
+        return Err(format!("File is not indexed: {}", file_path));
+    }
+
+    crate::ensure_ovms_initialized(app).await;
+    let model = match settings::current().rag.knowledge_graph.model.filter(|model| !model.is_empty()) {
+        Some(model) => model,
+        None => crate::ovms::get_loaded_model(app.clone()).await?
+            .ok_or("No model is loaded; load a text model to build the knowledge graph")?,
+    };
+    let client = Client::with_config(
+        OpenAIConfig::new()
+            .with_api_key("unused")
+            .with_api_base(crate::ovms::openai_api_base())
+    );
+
+    let graph = KnowledgeGraph::open()?;
+    graph.remove_file(file_path)?;
+
+    let mut failed_chunks = 0;
+    let mut entities = HashSet::new();
+    let mut relations = HashSet::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        match extract(&client, &model, chunk).await {
+            Ok(extraction) => {
+                let record = graph.add_chunk(chunk, &extraction)?;
+                entities.extend(record.entities);
+                relations.extend(record.edges);
+            }
+            Err(e) => {
+                failed_chunks += 1;
+                log_warning!("Skipped chunk in knowledge graph extraction", error = %e, chunk = %chunk.id);
+            }
+        }
+        let _ = app.emit("knowledge-graph-progress", GraphProgress {
+            file_path: file_path.to_string(),
+            processed: index + 1,
+            total: chunks.len(),
+        });
+    }
+    graph.flush()?;
+
+    let summary = GraphBuildSummary {
+        file_path: file_path.to_string(),
+        chunks: chunks.len(),
+        failed_chunks,
+        entities: entities.len(),
+        relations: relations.len(),
+    };
+    log_operation_success!("Extract knowledge graph", chunks = summary.chunks, failed = failed_chunks);
+    Ok(summary)
+}
+
+/// `extract_file` on a background task when the graph is enabled. Extraction
+/// takes a model call per chunk, so callers run it once the file is searchable.
+pub(crate) fn extract_file_in_background(app: &AppHandle, file_path: String) {
+    if !settings::current().rag.knowledge_graph.enabled {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = extract_file(&app, &file_path).await {
+            log_warning!("Knowledge graph extraction failed", error = %e, file = %file_path);
+        }
+    });
+}
+
+/// Related facts for RAG context; empty when the graph is disabled or unavailable.
+/// Matching entity names scans every entity, so it runs on a blocking thread.
+pub(crate) async fn context_for(query: &str, results: &[SearchResult]) -> String {
+    let config = settings::current().rag.knowledge_graph;
+    if !config.enabled || config.max_facts == 0 {
+        return String::new();
+    }
+
+    let query = query.to_string();
+    let chunk_ids: Vec<String> = results.iter().map(|result| result.document.id.clone()).collect();
+    let facts = tokio::task::spawn_blocking(move || {
+        KnowledgeGraph::open().map(|graph| graph.related_facts(&query, &chunk_ids, config.max_facts))
+    }).await;
+    let facts = match facts {
+        Ok(Ok(facts)) => facts,
+        Ok(Err(e)) => {
+            log_warning!("Knowledge graph unavailable for retrieval", error = %e);
+            return String::new();
+        }
+        Err(e) => {
+            log_warning!("Knowledge graph lookup failed", error = %e);
+            return String::new();
+        }
+    };
+    if facts.is_empty() {
+        return String::new();
+    }
+    format!(
+        "Related facts from the knowledge graph:\n{}",
+        facts.iter().map(|fact| format!("- {}", fact)).collect::<Vec<_>>().join("\n")
+    )
+}
+
+/// Extract the graph for one file, or for every indexed file when `file_path` is omitted
+#[tauri::command]
+pub async fn build_knowledge_graph(app: AppHandle, file_path: Option<String>) -> Result<Vec<GraphBuildSummary>, String> {
+    let files = match file_path {
+        Some(path) => vec![path],
+        None => backend::open_default()?.list_files().await?
+            .into_iter()
+            .map(|file| file.file_path)
+            .collect(),
+    };
+
+    let mut summaries = Vec::with_capacity(files.len());
+    for file in files {
+        summaries.push(extract_file(&app, &file).await?);
+    }
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub async fn get_entity_neighbors(entity: String, limit: Option<usize>) -> Result<EntityNeighbors, String> {
+    KnowledgeGraph::open()?
+        .neighbors(&entity, limit.unwrap_or(50))?
+        .ok_or_else(|| format!("Entity not found in knowledge graph: {}", entity))
+}
+
+#[tauri::command]
+pub async fn clear_knowledge_graph() -> Result<String, String> {
+    KnowledgeGraph::open()?.clear()?;
+    Ok("Knowledge graph cleared".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_graph() -> KnowledgeGraph {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        KnowledgeGraph::with_db(db).unwrap()
+    }
+
+    fn chunk(id: &str, file_path: &str) -> Document {
+        let mut document = Document::new("doc".into(), String::new(), "txt".into(), file_path.into(), Some(0));
+        document.id = id.to_string();
+        document
+    }
+
+    #[test]
+    fn test_parse_extraction_from_fenced_reply() {
+        let reply = "Here you go:\n```json\n{\"entities\": [{\"name\": \"Ada Lovelace\", \"type\": \"person\"}, {\"name\": \"  \"}],\n\"relations\": [{\"source\": \"Ada Lovelace\", \"relation\": \"worked with\", \"target\": \"Charles Babbage\"}, {\"source\": \"Ada\", \"relation\": \"is\", \"target\": \"ada\"}]}\n```";
+        let extraction = parse_extraction(reply).unwrap();
+        assert_eq!(extraction.entities.len(), 1);
+        assert_eq!(extraction.relations.len(), 1);
+        assert!(parse_extraction("no graph here").is_err());
+    }
+
+    #[test]
+    fn test_neighbors_and_file_removal() {
+        let graph = temp_graph();
+        let extraction = parse_extraction(r#"{
+            "entities": [{"name": "Ada Lovelace", "type": "person"}, {"name": "Analytical Engine", "type": "product"}],
+            "relations": [
+                {"source": "Ada Lovelace", "relation": "wrote about", "target": "Analytical Engine"},
+                {"source": "Charles Babbage", "relation": "designed", "target": "Analytical Engine"}
+            ]
+        }"#).unwrap();
+        let record = graph.add_chunk(&chunk("c1", "notes.md"), &extraction).unwrap();
+        assert_eq!((record.entities.len(), record.edges.len()), (3, 2));
+
+        let engine = graph.neighbors("analytical  ENGINE", 10).unwrap().unwrap();
+        assert_eq!(engine.entity.kind, "product");
+        assert_eq!(engine.neighbors.len(), 2);
+        assert!(engine.neighbors.iter().all(|n| matches!(n.direction, Direction::Incoming)));
+
+        let facts = graph.related_facts("What did Ada Lovelace write?", &[], 10);
+        assert_eq!(facts, vec!["Ada Lovelace wrote about Analytical Engine".to_string()]);
+
+        assert_eq!(graph.remove_file("notes.md").unwrap(), 1);
+        assert!(graph.neighbors("Analytical Engine", 10).unwrap().is_none());
+        assert!(graph.entities.is_empty() && graph.edges.is_empty());
+        assert!(graph.adjacency.is_empty());
+    }
+}
@@ -0,0 +1,524 @@
+//! Approximate nearest-neighbor index over a collection's embeddings.
+//!
+//! Scoring every row of the embedding matrix costs one dot product per chunk
+//! and query, which adds up past 100k chunks. Collections with at least
+//! `rag.hnsw.min_documents` chunks are searched through a hierarchical
+//! navigable small world graph (HNSW) instead, held in memory per
+//! collection.
+//!
+//! sled stays the durable store and the embedding matrix the source of the
+//! graph: it is built from the matrix on a background thread, at startup and
+//! whenever a search finds it missing. Inserts add nodes and deletes leave
+//! tombstones. Like the matrix, the graph records the collection revision it
+//! matches; after any change it did not see it is dropped, and searches scan
+//! the matrix until the next build is done.
+
+use std::cmp::{ Ordering, Reverse };
+use std::collections::{ BinaryHeap, HashMap, HashSet };
+use std::sync::{ Arc, OnceLock };
+use std::time::Instant;
+
+use parking_lot::{ Mutex, RwLock };
+
+use super::embedding_matrix::{ dot, normalize, EmbeddingMatrix };
+use crate::settings::{ self, HnswSettings };
+
+/// Highest layer a node can be drawn for
+const MAX_LEVEL: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    similarity: f32,
+    node: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity.total_cmp(&other.similarity).then(self.node.cmp(&other.node))
+    }
+}
+
+/// HNSW graph of unit vectors, scored by dot product
+pub struct Hnsw {
+    dim: usize,
+    /// Links per node on the upper layers; layer 0 allows twice as many
+    m: usize,
+    ef_construction: usize,
+    level_factor: f64,
+    /// Node vectors, `dim` values each
+    vectors: Vec<f32>,
+    ids: Vec<String>,
+    /// Neighbors of each node, one list per layer from 0 to the node's level
+    links: Vec<Vec<Vec<u32>>>,
+    /// Live node of each document id
+    nodes: HashMap<String, u32>,
+    deleted: Vec<bool>,
+    deleted_count: usize,
+    entry: Option<u32>,
+    rng: u64,
+}
+
+impl Hnsw {
+    pub fn new(dim: usize, m: usize, ef_construction: usize) -> Self {
+        let m = m.max(2);
+        Self {
+            dim,
+            m,
+            ef_construction: ef_construction.max(m),
+            level_factor: 1.0 / (m as f64).ln(),
+            vectors: Vec::new(),
+            ids: Vec::new(),
+            links: Vec::new(),
+            nodes: HashMap::new(),
+            deleted: Vec::new(),
+            deleted_count: 0,
+            entry: None,
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Index every row of the matrix
+    pub fn from_matrix(matrix: &EmbeddingMatrix, config: &HnswSettings) -> Self {
+        let dim = matrix.dim();
+        let mut index = Self::new(dim, config.m, config.ef_construction);
+        for (row, unit) in matrix.data().chunks_exact(dim.max(1)).enumerate().take(matrix.rows()) {
+            index.insert(matrix.id(row), unit);
+        }
+        index
+    }
+
+    /// Live nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Whether enough nodes are deleted that a fresh build would be leaner
+    pub fn needs_rebuild(&self) -> bool {
+        self.deleted_count * 4 > self.ids.len()
+    }
+
+    fn vector(&self, node: u32) -> &[f32] {
+        let start = node as usize * self.dim;
+        &self.vectors[start..start + self.dim]
+    }
+
+    fn level_of(&self, node: u32) -> usize {
+        self.links[node as usize].len() - 1
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 { self.m * 2 } else { self.m }
+    }
+
+    /// Exponentially distributed level, from a xorshift generator so builds are repeatable
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        let uniform = (bits as f64 + 1.0) / (1u64 << 53) as f64;
+        ((-uniform.ln() * self.level_factor) as usize).min(MAX_LEVEL)
+    }
+
+    /// Add a unit vector; a node already indexed under `id` is replaced
+    pub fn insert(&mut self, id: &str, unit: &[f32]) {
+        if unit.len() != self.dim {
+            return;
+        }
+        self.remove(id);
+
+        let node = self.ids.len() as u32;
+        let level = self.random_level();
+        self.vectors.extend_from_slice(unit);
+        self.ids.push(id.to_string());
+        self.links.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+        self.nodes.insert(id.to_string(), node);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(node);
+            return;
+        };
+
+        let top = self.level_of(entry);
+        let mut nearest = Candidate { similarity: dot(unit, self.vector(entry)), node: entry };
+        for layer in (level + 1..=top).rev() {
+            nearest = self.greedy(unit, nearest, layer);
+        }
+
+        let mut entry_points = vec![nearest];
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(unit, &entry_points, self.ef_construction, layer);
+            let neighbors = self.select_neighbors(&found, self.m);
+            for &neighbor in &neighbors {
+                self.connect(neighbor, node, layer);
+            }
+            self.links[node as usize][layer] = neighbors;
+            entry_points = found;
+        }
+
+        if level > top {
+            self.entry = Some(node);
+        }
+    }
+
+    /// Tombstone the node of `id`; it keeps guiding searches but is no longer returned
+    pub fn remove(&mut self, id: &str) -> bool {
+        match self.nodes.remove(id) {
+            Some(node) => {
+                self.deleted[node as usize] = true;
+                self.deleted_count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The `k` live nodes most similar to a unit-normalized query, as
+    /// `(document id, cosine)` pairs, best first. `ef` trades speed for recall.
+    pub fn search(&self, query_unit: &[f32], k: usize, ef: usize) -> Vec<(String, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if k == 0 || query_unit.len() != self.dim {
+            return Vec::new();
+        }
+
+        let mut nearest = Candidate { similarity: dot(query_unit, self.vector(entry)), node: entry };
+        for layer in (1..=self.level_of(entry)).rev() {
+            nearest = self.greedy(query_unit, nearest, layer);
+        }
+
+        self.search_layer(query_unit, &[nearest], ef.max(k), 0)
+            .into_iter()
+            .filter(|candidate| !self.deleted[candidate.node as usize])
+            .take(k)
+            .map(|candidate| (self.ids[candidate.node as usize].clone(), candidate.similarity))
+            .collect()
+    }
+
+    /// Follow the best link on `layer` until no neighbor is closer
+    fn greedy(&self, query: &[f32], start: Candidate, layer: usize) -> Candidate {
+        let mut current = start;
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.links[current.node as usize][layer] {
+                let similarity = dot(query, self.vector(neighbor));
+                if similarity > current.similarity {
+                    current = Candidate { similarity, node: neighbor };
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Best-first search of one layer keeping the `ef` closest nodes, best first
+    fn search_layer(&self, query: &[f32], entry_points: &[Candidate], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry_points.iter().map(|candidate| candidate.node).collect();
+        let mut candidates: BinaryHeap<Candidate> = entry_points.iter().copied().collect();
+        let mut found: BinaryHeap<Reverse<Candidate>> = entry_points.iter().copied().map(Reverse).collect();
+
+        while let Some(current) = candidates.pop() {
+            let worst = found.peek().map_or(f32::NEG_INFINITY, |Reverse(worst)| worst.similarity);
+            if found.len() >= ef && current.similarity < worst {
+                break;
+            }
+            for &neighbor in &self.links[current.node as usize][layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let similarity = dot(query, self.vector(neighbor));
+                let worst = found.peek().map_or(f32::NEG_INFINITY, |Reverse(worst)| worst.similarity);
+                if found.len() < ef || similarity > worst {
+                    let candidate = Candidate { similarity, node: neighbor };
+                    candidates.push(candidate);
+                    found.push(Reverse(candidate));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut found: Vec<Candidate> = found.into_iter().map(|Reverse(candidate)| candidate).collect();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+
+    /// Up to `m` of the candidates (best first), preferring ones that are not
+    /// closer to an already selected neighbor than to the base node, so links
+    /// spread in different directions. Skipped candidates fill any remaining
+    /// slots, which keeps sparse regions connected.
+    fn select_neighbors(&self, candidates: &[Candidate], m: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut skipped = Vec::new();
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = self.vector(candidate.node);
+            let diverse = selected
+                .iter()
+                .all(|&chosen| dot(vector, self.vector(chosen)) < candidate.similarity);
+            if diverse {
+                selected.push(candidate.node);
+            } else {
+                skipped.push(candidate.node);
+            }
+        }
+        for node in skipped {
+            if selected.len() >= m {
+                break;
+            }
+            selected.push(node);
+        }
+        selected
+    }
+
+    /// Link `from` to `to` on `layer`, pruning `from`'s links if it has too many
+    fn connect(&mut self, from: u32, to: u32, layer: usize) {
+        let max_links = self.max_links(layer);
+        self.links[from as usize][layer].push(to);
+        if self.links[from as usize][layer].len() <= max_links {
+            return;
+        }
+
+        let base = self.vector(from);
+        let mut candidates: Vec<Candidate> = self.links[from as usize][layer]
+            .iter()
+            .map(|&node| Candidate { similarity: dot(base, self.vector(node)), node })
+            .collect();
+        candidates.sort_by(|a, b| b.cmp(a));
+        let pruned = self.select_neighbors(&candidates, max_links);
+        self.links[from as usize][layer] = pruned;
+    }
+}
+
+/// An index with the collection revision it matches
+struct LoadedIndex {
+    revision: u64,
+    index: Hnsw,
+}
+
+static INDEXES: OnceLock<RwLock<HashMap<String, LoadedIndex>>> = OnceLock::new();
+
+/// Collections whose index is being built
+static BUILDING: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn indexes() -> &'static RwLock<HashMap<String, LoadedIndex>> {
+    INDEXES.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn building() -> &'static Mutex<HashSet<String>> {
+    BUILDING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Search the collection's index; `None` when it is disabled, missing or stale
+pub fn search(collection: &str, revision: u64, query_unit: &[f32], k: usize) -> Option<Vec<(String, f32)>> {
+    let config = settings::current().rag.hnsw;
+    if !config.enabled {
+        return None;
+    }
+    let indexes = indexes().read();
+    let loaded = indexes.get(collection).filter(|loaded| loaded.revision == revision)?;
+    if loaded.index.dim() != query_unit.len() {
+        return None;
+    }
+    Some(loaded.index.search(query_unit, k, config.ef_search))
+}
+
+/// Build the index from `matrix` on a background thread, unless the
+/// collection is too small, its index is current or a build is under way
+pub fn build_in_background(collection: &str, revision: u64, matrix: Arc<EmbeddingMatrix>) {
+    let config = settings::current().rag.hnsw;
+    if !config.enabled || matrix.rows() < config.min_documents.max(1) {
+        return;
+    }
+    if indexes().read().get(collection).is_some_and(|loaded| loaded.revision == revision) {
+        return;
+    }
+    if !building().lock().insert(collection.to_string()) {
+        return;
+    }
+
+    let name = collection.to_string();
+    let spawned = std::thread::Builder::new()
+        .name(format!("hnsw-{}", collection))
+        .spawn(move || {
+            let started = Instant::now();
+            let index = Hnsw::from_matrix(&matrix, &config);
+            tracing::info!(
+                collection = %name,
+                nodes = index.len(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "Built HNSW index"
+            );
+            indexes().write().insert(name.clone(), LoadedIndex { revision, index });
+            building().lock().remove(&name);
+        });
+    if let Err(e) = spawned {
+        building().lock().remove(collection);
+        log_warning!("Failed to start HNSW index build", collection = %collection, error = %e);
+    }
+}
+
+/// Add documents inserted by the write that moved the collection from
+/// `previous` to `revision`; an index that missed a change is dropped
+pub fn insert(collection: &str, previous: u64, revision: u64, rows: &[(&str, &[f32])]) {
+    let mut indexes = indexes().write();
+    let Some(loaded) = indexes.get_mut(collection) else {
+        return;
+    };
+    if loaded.revision != previous {
+        indexes.remove(collection);
+        return;
+    }
+    for (id, embedding) in rows {
+        let Some(unit) = normalize(embedding) else { continue };
+        if unit.len() != loaded.index.dim() {
+            tracing::debug!(collection = %collection, "Embedding dimension changed; dropping HNSW index");
+            indexes.remove(collection);
+            return;
+        }
+        loaded.index.insert(id, &unit);
+    }
+    loaded.revision = revision;
+}
+
+/// Tombstone documents deleted by the change from `previous` to `revision`.
+/// Once a quarter of the nodes are tombstones the index is dropped, to be
+/// rebuilt without them.
+pub fn remove(collection: &str, previous: u64, revision: u64, ids: &[String]) {
+    let mut indexes = indexes().write();
+    let Some(loaded) = indexes.get_mut(collection) else {
+        return;
+    };
+    if loaded.revision != previous {
+        indexes.remove(collection);
+        return;
+    }
+    for id in ids {
+        loaded.index.remove(id);
+    }
+    loaded.revision = revision;
+    if loaded.index.needs_rebuild() {
+        tracing::debug!(collection = %collection, "Many deleted nodes; dropping HNSW index for a rebuild");
+        indexes.remove(collection);
+    }
+}
+
+/// Drop the index of one collection
+pub fn forget(collection: &str) {
+    indexes().write().remove(collection);
+}
+
+/// Drop every index
+pub fn remove_all() {
+    indexes().write().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random unit vectors
+    fn unit_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                let raw: Vec<f32> = (0..dim)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect();
+                normalize(&raw).unwrap()
+            })
+            .collect()
+    }
+
+    fn exact_top(vectors: &[Vec<f32>], query: &[f32], k: usize) -> Vec<String> {
+        let mut scored: Vec<(f32, usize)> = vectors.iter().enumerate().map(|(i, v)| (dot(v, query), i)).collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(k).map(|(_, i)| format!("doc-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_search_recall_against_exact_scan() {
+        let vectors = unit_vectors(2000, 24, 7);
+        let mut index = Hnsw::new(24, 16, 100);
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(&format!("doc-{}", i), vector);
+        }
+        assert_eq!(index.len(), 2000);
+
+        let queries = unit_vectors(50, 24, 99);
+        let mut hits = 0;
+        for query in &queries {
+            let expected = exact_top(&vectors, query, 10);
+            let found = index.search(query, 10, 64);
+            assert_eq!(found.len(), 10);
+            assert!(found.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+            hits += found.iter().filter(|(id, _)| expected.contains(id)).count();
+        }
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall > 0.9, "recall {}", recall);
+    }
+
+    #[test]
+    fn test_removed_and_replaced_nodes_are_not_returned() {
+        let vectors = unit_vectors(200, 8, 3);
+        let mut index = Hnsw::new(8, 8, 50);
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(&format!("doc-{}", i), vector);
+        }
+
+        // doc-0 is its own best match until it is removed
+        assert_eq!(index.search(&vectors[0], 1, 32)[0].0, "doc-0");
+        assert!(index.remove("doc-0"));
+        assert!(!index.remove("doc-0"));
+        assert!(index.search(&vectors[0], 5, 32).iter().all(|(id, _)| id != "doc-0"));
+
+        // Re-inserting an id under a new vector leaves one live node for it
+        index.insert("doc-1", &vectors[0]);
+        let found = index.search(&vectors[0], 5, 32);
+        assert_eq!(found[0].0, "doc-1");
+        assert_eq!(found.iter().filter(|(id, _)| id == "doc-1").count(), 1);
+        assert_eq!(index.len(), 199);
+    }
+
+    #[test]
+    fn test_changes_from_another_revision_drop_the_index() {
+        let collection = "hnsw-test-revisions";
+        let mut index = Hnsw::new(2, 4, 8);
+        index.insert("a", &[1.0, 0.0]);
+        indexes().write().insert(collection.to_string(), LoadedIndex { revision: 1, index });
+
+        insert(collection, 1, 2, &[("b", &[0.0, 2.0][..])]);
+        assert_eq!(indexes().read().get(collection).map(|loaded| (loaded.revision, loaded.index.len())), Some((2, 2)));
+
+        remove(collection, 5, 6, &["a".to_string()]);
+        assert!(indexes().read().get(collection).is_none());
+    }
+}
@@ -0,0 +1,185 @@
+//! One-call document ingestion: parse, embed and store a file as it is read.
+//!
+//! Unlike `process_document` → `create_document_embeddings` → `store_documents`,
+//! which passes every chunk of a file through the frontend at once, chunks
+//! here are embedded in small batches and written to the vector store as the
+//! reader produces them, so memory use does not grow with file size.
+//! A run is registered for `cancel_operation` under its `job_id` (the file
+//! path when unset); a cancelled run is rolled back like a failed one. Work
+//! that changes a file's chunks holds `lock_file`, so ingestion, re-indexing
+//! and folder watching never interleave on one file.
+
+use std::collections::HashMap;
+use std::sync::{ Arc, OnceLock };
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+
+use super::Document;
+use super::chunking::ChunkingOptions;
+use super::documents::stream_document_chunks;
+use super::embeddings::EmbeddingService;
+use super::backend::{ self, VectorBackend };
+use super::vector_store::DEFAULT_COLLECTION;
+use crate::cancellation::{ self, OperationGuard, OperationKind };
+use crate::constants;
+use crate::path_policy::{ self, Access };
+
+pub(crate) const INGESTION_CANCELLED: &str = "Ingestion cancelled";
+
+static FILE_LOCKS: OnceLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+
+/// Wait until no other task works on `file_path`'s chunks, and hold that
+/// until the guard is dropped
+pub(crate) async fn lock_file(file_path: &str) -> tokio::sync::OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = FILE_LOCKS.get_or_init(|| Mutex::new(HashMap::new())).lock();
+        // Locks nobody holds or waits for
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(file_path.to_string()).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionProgress {
+    pub file_path: String,
+    pub chunks_stored: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestionSummary {
+    pub file_path: String,
+    pub chunk_count: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Index a file into the vector store, emitting `ingestion-progress` per batch.
+/// On failure, chunks already stored for this run are removed again.
+#[tauri::command]
+pub async fn ingest_document(
+    app: AppHandle,
+    file_path: String,
+    job_id: Option<String>,
+    chunking: Option<ChunkingOptions>
+) -> Result<IngestionSummary, String> {
+    path_policy::check_str(&file_path, Access::Read)?;
+    let job_id = job_id.unwrap_or_else(|| file_path.clone());
+    let _file = lock_file(&file_path).await;
+    let operation = cancellation::register(&app, &job_id, OperationKind::Ingestion);
+    let summary = ingest(&app, &operation, file_path, DEFAULT_COLLECTION, chunking.unwrap_or_default(), &|_| {}).await?;
+    super::graph::extract_file_in_background(&app, summary.file_path.clone());
+    Ok(summary)
+}
+
+/// `ingest_document` into another collection
+pub(crate) async fn ingest_into(app: &AppHandle, file_path: String, collection: &str) -> Result<IngestionSummary, String> {
+    let operation = cancellation::register(app, &file_path, OperationKind::Ingestion);
+    ingest(app, &operation, file_path, collection, ChunkingOptions::default(), &|_| {}).await
+}
+
+/// Shared by the commands, folder watching and the ingestion queue, each of
+/// which registers the run's `operation` for `cancel_operation` first;
+/// `on_progress` gets the number of chunks stored after each batch. Callers
+/// hold `lock_file` where other work may touch the same file, and start
+/// graph extraction once the file's chunks are final.
+pub(crate) async fn ingest(
+    app: &AppHandle,
+    operation: &OperationGuard,
+    file_path: String,
+    collection: &str,
+    chunking: ChunkingOptions,
+    on_progress: &(dyn Fn(usize) + Send + Sync)
+) -> Result<IngestionSummary, String> {
+    log_operation_start!("Ingest document", file = %file_path, collection = %collection);
+    let started = Instant::now();
+
+    let mut chunks = stream_document_chunks(file_path.clone(), chunking).map_err(|e| {
+        log_operation_error!("Ingest document", &e, file = %file_path);
+        e
+    })?;
+
+    crate::ensure_ovms_initialized(app).await;
+
+    let embedding_service = EmbeddingService::new();
+    let vector_store = backend::open(collection)?;
+    let mut stored_ids: Vec<String> = Vec::new();
+
+    let result: Result<(), String> = async {
+        let mut batch = Vec::with_capacity(constants::EMBEDDING_BATCH_SIZE);
+        loop {
+            let next = tokio::select! {
+                _ = operation.cancelled() => return Err(INGESTION_CANCELLED.to_string()),
+                next = chunks.recv() => next,
+            };
+            let at_end = next.is_none();
+            if let Some(chunk) = next {
+                batch.push(chunk?);
+            }
+
+            if batch.len() >= constants::EMBEDDING_BATCH_SIZE || (at_end && !batch.is_empty()) {
+                // A batch is not interrupted halfway, so its ids can be rolled back
+                let ids = embed_and_store(&embedding_service, vector_store.as_ref(), std::mem::take(&mut batch)).await?;
+                stored_ids.extend(ids);
+                if operation.is_cancelled() {
+                    return Err(INGESTION_CANCELLED.to_string());
+                }
+
+                let _ = app.emit("ingestion-progress", IngestionProgress {
+                    file_path: file_path.clone(),
+                    chunks_stored: stored_ids.len(),
+                });
+                on_progress(stored_ids.len());
+            }
+
+            if at_end {
+                return vector_store.flush().await;
+            }
+        }
+    }.await;
+
+    if let Err(e) = result {
+        // Dropping the receiver stops the reader thread
+        drop(chunks);
+        for id in &stored_ids {
+            let _ = vector_store.delete_document(id).await;
+        }
+        let _ = vector_store.flush().await;
+        log_operation_error!("Ingest document", &e, file = %file_path, rolled_back = stored_ids.len());
+        return Err(e);
+    }
+
+    let summary = IngestionSummary {
+        file_path,
+        chunk_count: stored_ids.len(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    log_operation_success!("Ingest document", chunks = summary.chunk_count, elapsed_ms = summary.elapsed_ms);
+    Ok(summary)
+}
+
+pub(crate) async fn embed_and_store(
+    embedding_service: &EmbeddingService,
+    vector_store: &dyn VectorBackend,
+    mut batch: Vec<Document>
+) -> Result<Vec<String>, String> {
+    let texts: Vec<String> = batch.iter().map(|doc| doc.content.clone()).collect();
+    let embeddings = embedding_service.create_embeddings(texts).await?;
+
+    if embeddings.len() != batch.len() {
+        return Err(format!(
+            "Embedding service returned {} vectors for {} chunks",
+            embeddings.len(),
+            batch.len()
+        ));
+    }
+
+    for (doc, embedding) in batch.iter_mut().zip(embeddings) {
+        doc.embedding = Some(embedding);
+    }
+    vector_store.store_documents(&batch).await?;
+
+    Ok(batch.into_iter().map(|doc| doc.id).collect())
+}
@@ -0,0 +1,275 @@
+//! Background ingestion queue.
+//!
+//! `enqueue_document` records a job and returns its id right away; a single
+//! worker task takes jobs in order and runs each through `ingest` (parse,
+//! chunk, embed, store), so a large PDF no longer holds up the command that
+//! added it. Every change of a job is emitted as `ingest-progress` with the
+//! whole job, and `get_ingest_jobs` lists queued, running and recent jobs.
+//!
+//! Jobs run one at a time: they share the embedding model, so running them
+//! side by side would not finish any sooner. `cancel_operation` with a job id
+//! drops a queued job or stops a running one like a cancelled ingestion.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use chrono::{ DateTime, Utc };
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+use tokio::sync::Notify;
+
+use super::chunking::ChunkingOptions;
+use super::ingest::{ self, INGESTION_CANCELLED };
+use super::vector_store::DEFAULT_COLLECTION;
+use crate::cancellation::{ self, OperationKind };
+use crate::path_policy::{ self, Access };
+
+/// Finished jobs kept for `get_ingest_jobs`, oldest dropped first
+const FINISHED_JOBS_KEPT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl IngestJobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Payload of `ingest-progress` and entry of `get_ingest_jobs`
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestJob {
+    pub id: String,
+    pub file_path: String,
+    pub status: IngestJobStatus,
+    pub chunks_stored: usize,
+    pub error: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    chunking: ChunkingOptions,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// All jobs in the order they were enqueued
+    jobs: VecDeque<IngestJob>,
+}
+
+impl Queue {
+    fn get_mut(&mut self, id: &str) -> Option<&mut IngestJob> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    /// The oldest queued job, marked running
+    fn start_next(&mut self) -> Option<IngestJob> {
+        let job = self.jobs.iter_mut().find(|job| job.status == IngestJobStatus::Queued)?;
+        job.status = IngestJobStatus::Running;
+        job.started_at = Some(Utc::now());
+        Some(job.clone())
+    }
+
+    fn prune(&mut self) {
+        let mut finished = self.jobs.iter().filter(|job| job.status.is_finished()).count();
+        self.jobs.retain(|job| {
+            if finished > FINISHED_JOBS_KEPT && job.status.is_finished() {
+                finished -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
+
+static QUEUE: OnceLock<Mutex<Queue>> = OnceLock::new();
+static WORKER: OnceLock<Notify> = OnceLock::new();
+
+fn queue() -> &'static Mutex<Queue> {
+    QUEUE.get_or_init(|| Mutex::new(Queue::default()))
+}
+
+/// Wakes the worker, starting it on first use
+fn wake_worker(app: &AppHandle) {
+    let mut started = false;
+    let notify = WORKER.get_or_init(|| {
+        started = true;
+        Notify::new()
+    });
+    if started {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { run_worker(app, notify).await });
+    }
+    notify.notify_one();
+}
+
+/// Apply `change` to a job and emit the result
+fn update(app: &AppHandle, id: &str, change: impl FnOnce(&mut IngestJob)) {
+    let job = {
+        let mut queue = queue().lock();
+        let Some(job) = queue.get_mut(id) else {
+            return;
+        };
+        change(job);
+        let job = job.clone();
+        if job.status.is_finished() {
+            queue.prune();
+        }
+        job
+    };
+    let _ = app.emit("ingest-progress", &job);
+}
+
+async fn run_worker(app: AppHandle, notify: &'static Notify) {
+    loop {
+        let next = {
+            let mut queue = queue().lock();
+            // Registered before the lock is released, so a cancel that no
+            // longer finds the job queued finds it running
+            queue.start_next().map(|job| {
+                let operation = cancellation::register(&app, &job.id, OperationKind::Ingestion);
+                (job, operation)
+            })
+        };
+        let Some((job, operation)) = next else {
+            notify.notified().await;
+            continue;
+        };
+        let _ = app.emit("ingest-progress", &job);
+
+        let on_progress = |chunks_stored: usize| update(&app, &job.id, |job| job.chunks_stored = chunks_stored);
+        let file = ingest::lock_file(&job.file_path).await;
+        let result = ingest::ingest(
+            &app,
+            &operation,
+            job.file_path.clone(),
+            DEFAULT_COLLECTION,
+            job.chunking.clone(),
+            &on_progress
+        ).await;
+        drop(operation);
+        drop(file);
+        if result.is_ok() {
+            super::graph::extract_file_in_background(&app, job.file_path.clone());
+        }
+
+        update(&app, &job.id, |job| {
+            job.finished_at = Some(Utc::now());
+            match result {
+                Ok(summary) => {
+                    job.status = IngestJobStatus::Completed;
+                    job.chunks_stored = summary.chunk_count;
+                }
+                Err(e) if e == INGESTION_CANCELLED => job.status = IngestJobStatus::Cancelled,
+                Err(e) => {
+                    job.status = IngestJobStatus::Failed;
+                    job.error = Some(e);
+                }
+            }
+        });
+    }
+}
+
+/// Drop a job that has not started; `false` if there is no such queued job
+pub(crate) fn cancel_queued(app: &AppHandle, id: &str) -> bool {
+    let job = {
+        let mut queue = queue().lock();
+        let Some(job) = queue.get_mut(id).filter(|job| job.status == IngestJobStatus::Queued) else {
+            return false;
+        };
+        job.status = IngestJobStatus::Cancelled;
+        job.finished_at = Some(Utc::now());
+        let job = job.clone();
+        queue.prune();
+        job
+    };
+    tracing::info!(job_id = %id, "Queued ingestion cancelled");
+    let _ = app.emit("ingest-progress", &job);
+    true
+}
+
+/// Queue a file for ingestion into the document collection and return the job id
+#[tauri::command]
+pub async fn enqueue_document(
+    app: AppHandle,
+    file_path: String,
+    chunking: Option<ChunkingOptions>
+) -> Result<String, String> {
+    path_policy::check_str(&file_path, Access::Read)?;
+    let chunking = chunking.unwrap_or_default();
+    chunking.validate()?;
+
+    let job = IngestJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_path,
+        status: IngestJobStatus::Queued,
+        chunks_stored: 0,
+        error: None,
+        queued_at: Utc::now(),
+        started_at: None,
+        finished_at: None,
+        chunking,
+    };
+    tracing::info!(job_id = %job.id, file = %job.file_path, "Ingestion queued");
+
+    let id = job.id.clone();
+    queue().lock().jobs.push_back(job.clone());
+    let _ = app.emit("ingest-progress", &job);
+    wake_worker(&app);
+    Ok(id)
+}
+
+/// Queued and running jobs plus the most recent finished ones, oldest first
+#[tauri::command]
+pub async fn get_ingest_jobs() -> Result<Vec<IngestJob>, String> {
+    Ok(queue().lock().jobs.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, status: IngestJobStatus) -> IngestJob {
+        IngestJob {
+            id: id.to_string(),
+            file_path: format!("{}.pdf", id),
+            status,
+            chunks_stored: 0,
+            error: None,
+            queued_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            chunking: ChunkingOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_queue_starts_oldest_queued_job_and_prunes_finished() {
+        let mut queue = Queue::default();
+        queue.jobs.push_back(job("done", IngestJobStatus::Completed));
+        queue.jobs.push_back(job("first", IngestJobStatus::Queued));
+        queue.jobs.push_back(job("second", IngestJobStatus::Queued));
+
+        let started = queue.start_next().unwrap();
+        assert_eq!(started.id, "first");
+        assert_eq!(queue.get_mut("first").unwrap().status, IngestJobStatus::Running);
+        assert_eq!(queue.start_next().unwrap().id, "second");
+        assert!(queue.start_next().is_none());
+
+        for i in 0..FINISHED_JOBS_KEPT {
+            queue.jobs.push_back(job(&format!("old{}", i), IngestJobStatus::Failed));
+        }
+        queue.prune();
+        assert_eq!(queue.jobs.len(), FINISHED_JOBS_KEPT + 2);
+        assert!(queue.get_mut("done").is_none());
+        assert!(queue.get_mut("first").is_some());
+    }
+}
@@ -0,0 +1,643 @@
+//! Moving embeddings in and out of the vector store in open formats.
+//!
+//! - `parquet`: one row per document, with the embedding as a list of
+//!   `float32` and the metadata as a JSON object string.
+//! - `jsonl`: one JSON object per document, embedding included.
+//! - `npy`: a NumPy `float32` matrix with one row per document, plus a
+//!   sibling `<name>.rows.jsonl` file describing each row in the same order.
+//!
+//! Parquet and jsonl imports also accept the field names other vector
+//! databases commonly export (`text`, `vector`, `source`).
+//!
+//! Quantized collections export dequantized embeddings.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write };
+use std::path::{ Path, PathBuf };
+
+use polars::prelude::{ DataFrame, DataType, NamedFrom, ParquetReader, ParquetWriter, SerReader, Series };
+use serde::{ Deserialize, Serialize };
+
+use super::Document;
+use super::vector_store::{ VectorStore, DEFAULT_COLLECTION };
+use crate::constants;
+use crate::path_policy::{ self, Access };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingFormat {
+    Parquet,
+    Jsonl,
+    Npy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportReport {
+    pub collection: String,
+    pub format: EmbeddingFormat,
+    pub path: String,
+    /// Row descriptions written next to an `npy` matrix
+    pub metadata_path: Option<String>,
+    pub count: usize,
+    pub dim: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub collection: String,
+    pub imported: usize,
+    /// Records without an embedding, or whose dimension differs from the first one
+    pub skipped: usize,
+}
+
+/// A document as read from an import file
+#[derive(Debug, Deserialize)]
+struct ImportRecord {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(alias = "text")]
+    content: String,
+    #[serde(default)]
+    file_type: Option<String>,
+    #[serde(default, alias = "source")]
+    file_path: Option<String>,
+    #[serde(default)]
+    chunk_index: Option<usize>,
+    #[serde(default)]
+    metadata: HashMap<String, serde_json::Value>,
+    #[serde(default, alias = "vector")]
+    embedding: Option<Vec<f32>>,
+    #[serde(default)]
+    created_at: Option<i64>,
+}
+
+impl ImportRecord {
+    fn into_document(self) -> Document {
+        let file_path = self.file_path.unwrap_or_default();
+        let title = self.title.unwrap_or_else(|| {
+            Path::new(&file_path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("Imported document")
+                .to_string()
+        });
+        let file_type = self.file_type.unwrap_or_else(|| {
+            Path::new(&file_path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("txt")
+                .to_lowercase()
+        });
+
+        let mut document = Document::new(title, self.content, file_type, file_path, self.chunk_index);
+        if let Some(id) = self.id.filter(|id| !id.is_empty()) {
+            document.id = id;
+        }
+        if let Some(created_at) = self.created_at {
+            document.created_at = created_at;
+        }
+        document.metadata = self.metadata
+            .into_iter()
+            .map(|(key, value)| {
+                let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+                (key, value)
+            })
+            .collect();
+        document.embedding = self.embedding;
+        document
+    }
+}
+
+/// `<stem>.rows.jsonl`, which never equals `path` even when it ends in `.jsonl`
+fn npy_metadata_path(path: &Path) -> PathBuf {
+    path.with_extension("rows.jsonl")
+}
+
+// NumPy format: magic, version, header length, then a Python dict literal
+// padded with spaces to a multiple of 64 bytes and ending in a newline.
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// Space reserved for the header, so rows can be written before the row count is known
+const NPY_EXPORT_HEADER_LEN: usize = 128;
+
+fn npy_header(rows: usize, dim: usize) -> Vec<u8> {
+    let dict = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}", rows, dim);
+    let mut header = Vec::with_capacity(NPY_EXPORT_HEADER_LEN);
+    header.extend_from_slice(NPY_MAGIC);
+    header.extend_from_slice(&[1, 0]);
+    header.extend_from_slice(&((NPY_EXPORT_HEADER_LEN - 10) as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(NPY_EXPORT_HEADER_LEN - 1, b' ');
+    header.push(b'\n');
+    header
+}
+
+#[derive(Debug, PartialEq)]
+struct NpyLayout {
+    /// Bytes per value: 4 for `<f4`, 8 for `<f8`
+    value_size: usize,
+    rows: usize,
+    dim: usize,
+}
+
+/// Read the header of a 2-D little-endian float matrix, leaving `reader` at the data.
+/// `file_len` bounds the header and the matrix the header declares.
+fn read_npy_header(reader: &mut impl Read, file_len: u64) -> Result<NpyLayout, String> {
+    let mut preamble = [0u8; 8];
+    reader.read_exact(&mut preamble).map_err(|e| format!("Failed to read npy header: {}", e))?;
+    if &preamble[..6] != NPY_MAGIC {
+        return Err("Not an npy file".to_string());
+    }
+
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len).map_err(|e| e.to_string())?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len).map_err(|e| e.to_string())?;
+            u32::from_le_bytes(len) as usize
+        }
+        version => return Err(format!("Unsupported npy version {}", version)),
+    };
+    let len_field = if preamble[6] == 1 { 2 } else { 4 };
+    let data_offset = (preamble.len() + len_field + header_len) as u64;
+    if data_offset > file_len {
+        return Err("npy header is longer than the file".to_string());
+    }
+
+    let mut dict = vec![0u8; header_len];
+    reader.read_exact(&mut dict).map_err(|e| format!("Failed to read npy header: {}", e))?;
+    let dict = String::from_utf8_lossy(&dict);
+
+    let value_size = if dict.contains("'<f4'") {
+        4
+    } else if dict.contains("'<f8'") {
+        8
+    } else {
+        return Err("Only little-endian float32/float64 npy matrices are supported".to_string());
+    };
+    if dict.contains("'fortran_order': True") {
+        return Err("Fortran-ordered npy matrices are not supported".to_string());
+    }
+
+    let shape = dict
+        .split("'shape':")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .map(|shape| shape.trim().trim_start_matches('('))
+        .ok_or("npy header has no shape")?;
+    let dims: Vec<usize> = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse::<usize>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Invalid npy shape '{}': {}", shape, e))?;
+
+    let [rows, dim] = dims[..] else {
+        return Err(format!("Expected a 2-D npy matrix, found shape ({})", shape));
+    };
+    match rows.checked_mul(dim).and_then(|values| values.checked_mul(value_size)) {
+        Some(len) if len as u64 <= file_len - data_offset => Ok(NpyLayout { value_size, rows, dim }),
+        _ => Err(format!("npy shape ({}, {}) does not fit in the file", rows, dim)),
+    }
+}
+
+/// Write `documents` as a Parquet table with one column per field
+fn write_parquet(documents: &[Document], path: &Path) -> Result<(), String> {
+    let text = |field: fn(&Document) -> &str| documents.iter().map(field).collect::<Vec<_>>();
+    let metadata = documents
+        .iter()
+        .map(|document| serde_json::to_string(&document.metadata))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    let embeddings: Vec<Series> = documents
+        .iter()
+        .map(|document| Series::new("", document.embedding.as_deref().unwrap_or_default()))
+        .collect();
+
+    let mut frame = DataFrame::new(vec![
+        Series::new("id", text(|document| document.id.as_str())),
+        Series::new("title", text(|document| document.title.as_str())),
+        Series::new("content", text(|document| document.content.as_str())),
+        Series::new("file_type", text(|document| document.file_type.as_str())),
+        Series::new("file_path", text(|document| document.file_path.as_str())),
+        Series::new(
+            "chunk_index",
+            documents.iter().map(|document| document.chunk_index.map(|index| index as u64)).collect::<Vec<_>>()
+        ),
+        Series::new("metadata", metadata),
+        Series::new("embedding", embeddings),
+        Series::new("created_at", documents.iter().map(|document| document.created_at).collect::<Vec<_>>()),
+    ])
+    .map_err(|e| format!("Failed to build table: {}", e))?;
+
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    ParquetWriter::new(file)
+        .finish(&mut frame)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Import records from a Parquet table; columns are found by the names
+/// `write_parquet` uses or their common aliases, and all but the content
+/// may be missing
+fn read_parquet(path: &Path) -> Result<Vec<ImportRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let frame = ParquetReader::new(file)
+        .finish()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let rows = frame.height();
+
+    let column = |names: &[&str]| names.iter().find_map(|name| frame.column(name).ok());
+    let strings = |names: &[&str]| -> Result<Vec<Option<String>>, String> {
+        let Some(series) = column(names) else {
+            return Ok(vec![None; rows]);
+        };
+        let values = series.str().map_err(|e| format!("Column {} is not text: {}", series.name(), e))?;
+        Ok(values.into_iter().map(|value| value.map(str::to_string)).collect())
+    };
+    let integers = |name: &str| -> Result<Vec<Option<i64>>, String> {
+        let Some(series) = column(&[name]) else {
+            return Ok(vec![None; rows]);
+        };
+        let values = series.cast(&DataType::Int64).map_err(|e| format!("Column {} is not a number: {}", name, e))?;
+        Ok(values.i64().map_err(|e| e.to_string())?.into_iter().collect())
+    };
+
+    if column(&["content", "text"]).is_none() {
+        return Err(format!("{} has no content or text column", path.display()));
+    }
+    let mut ids = strings(&["id"])?;
+    let mut titles = strings(&["title"])?;
+    let mut contents = strings(&["content", "text"])?;
+    let mut file_types = strings(&["file_type"])?;
+    let mut file_paths = strings(&["file_path", "source"])?;
+    let mut metadata = strings(&["metadata"])?;
+    let chunk_indexes = integers("chunk_index")?;
+    let created_ats = integers("created_at")?;
+
+    let mut embeddings: Vec<Option<Vec<f32>>> = match column(&["embedding", "vector"]) {
+        Some(series) => series
+            .list()
+            .map_err(|e| format!("Column {} is not a list of numbers: {}", series.name(), e))?
+            .into_iter()
+            .map(|row| match row {
+                // A null inside a row leaves the record without an embedding
+                Some(row) => {
+                    let row = row.cast(&DataType::Float32).map_err(|e| format!("Invalid embedding: {}", e))?;
+                    Ok(row.f32().map_err(|e| e.to_string())?.into_iter().collect::<Option<Vec<f32>>>())
+                }
+                None => Ok(None),
+            })
+            .collect::<Result<_, String>>()?,
+        None => vec![None; rows],
+    };
+
+    Ok((0..rows)
+        .map(|row| ImportRecord {
+            id: ids[row].take(),
+            title: titles[row].take(),
+            content: contents[row].take().unwrap_or_default(),
+            file_type: file_types[row].take(),
+            file_path: file_paths[row].take(),
+            chunk_index: chunk_indexes[row].and_then(|index| usize::try_from(index).ok()),
+            metadata: metadata[row]
+                .take()
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            embedding: embeddings[row].take(),
+            created_at: created_ats[row],
+        })
+        .collect())
+}
+
+fn export_collection(store: &VectorStore, format: EmbeddingFormat, path: &Path) -> Result<ExportReport, String> {
+    let create = |path: &Path| {
+        File::create(path)
+            .map(BufWriter::new)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))
+    };
+
+    let mut count = 0;
+    let mut dim = 0;
+    let mut metadata_path = None;
+
+    match format {
+        EmbeddingFormat::Parquet => {
+            let mut documents = Vec::new();
+            for mut document in store.documents() {
+                let Some(embedding) = store.embedding_of(&document) else { continue };
+                dim = embedding.len();
+                document.embedding = Some(embedding);
+                documents.push(document);
+            }
+            count = documents.len();
+            write_parquet(&documents, path)?;
+        }
+        EmbeddingFormat::Jsonl => {
+            let mut out = create(path)?;
+            for mut document in store.documents() {
+                let Some(embedding) = store.embedding_of(&document) else { continue };
+                dim = embedding.len();
+                document.embedding = Some(embedding);
+                serde_json::to_writer(&mut out, &document)
+                    .map_err(|e| format!("Failed to write document: {}", e))?;
+                out.write_all(b"\n").map_err(|e| e.to_string())?;
+                count += 1;
+            }
+            out.flush().map_err(|e| e.to_string())?;
+        }
+        EmbeddingFormat::Npy => {
+            let meta_path = npy_metadata_path(path);
+            let mut matrix = create(path)?;
+            let mut meta = create(&meta_path)?;
+
+            matrix.write_all(&[0u8; NPY_EXPORT_HEADER_LEN]).map_err(|e| e.to_string())?;
+            for mut document in store.documents() {
+                let Some(embedding) = store.embedding_of(&document) else { continue };
+                if count == 0 {
+                    dim = embedding.len();
+                } else if embedding.len() != dim {
+                    return Err(format!(
+                        "Document {} has {} dimensions, expected {}; export as jsonl instead",
+                        document.id,
+                        embedding.len(),
+                        dim
+                    ));
+                }
+                for value in &embedding {
+                    matrix.write_all(&value.to_le_bytes()).map_err(|e| e.to_string())?;
+                }
+
+                document.embedding = None;
+                serde_json::to_writer(&mut meta, &document)
+                    .map_err(|e| format!("Failed to write document: {}", e))?;
+                meta.write_all(b"\n").map_err(|e| e.to_string())?;
+                count += 1;
+            }
+
+            meta.flush().map_err(|e| e.to_string())?;
+            let mut matrix = matrix.into_inner().map_err(|e| e.to_string())?;
+            matrix.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+            matrix.write_all(&npy_header(count, dim)).map_err(|e| e.to_string())?;
+            metadata_path = Some(meta_path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(ExportReport {
+        collection: store.collection().to_string(),
+        format,
+        path: path.to_string_lossy().to_string(),
+        metadata_path,
+        count,
+        dim,
+    })
+}
+
+/// Read import records from a file, in order
+fn read_records(format: EmbeddingFormat, path: &Path) -> Result<Box<dyn Iterator<Item = Result<ImportRecord, String>>>, String> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+    };
+    let parse_lines = |reader: BufReader<File>| {
+        reader
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|(index, line)| {
+                let line = line.map_err(|e| format!("Failed to read line {}: {}", index + 1, e))?;
+                serde_json::from_str::<ImportRecord>(&line)
+                    .map_err(|e| format!("Invalid record on line {}: {}", index + 1, e))
+            })
+    };
+
+    match format {
+        EmbeddingFormat::Parquet => Ok(Box::new(read_parquet(path)?.into_iter().map(Ok))),
+        EmbeddingFormat::Jsonl => Ok(Box::new(parse_lines(open(path)?))),
+        EmbeddingFormat::Npy => {
+            let meta_path = npy_metadata_path(path);
+            if !meta_path.exists() {
+                return Err(format!("Missing row descriptions: expected {}", meta_path.display()));
+            }
+
+            let file_len = std::fs::metadata(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .len();
+            let mut matrix = open(path)?;
+            let layout = read_npy_header(&mut matrix, file_len)?;
+            let mut row_bytes = vec![0u8; layout.dim * layout.value_size];
+            let mut remaining = layout.rows;
+
+            let records = parse_lines(open(&meta_path)?).map(move |record| {
+                let mut record = record?;
+                if remaining == 0 {
+                    return Err("The metadata file has more rows than the npy matrix".to_string());
+                }
+                remaining -= 1;
+
+                matrix.read_exact(&mut row_bytes).map_err(|e| format!("Failed to read npy row: {}", e))?;
+                record.embedding = Some(match layout.value_size {
+                    4 => row_bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                    _ => row_bytes
+                        .chunks_exact(8)
+                        .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+                        .collect(),
+                });
+                Ok(record)
+            });
+            Ok(Box::new(records))
+        }
+    }
+}
+
+fn import_records(store: &VectorStore, format: EmbeddingFormat, path: &Path) -> Result<ImportReport, String> {
+    let mut imported = 0;
+    let mut skipped = 0;
+    let mut dim = None;
+    let mut batch = Vec::with_capacity(constants::VECTOR_STORE_WRITE_BATCH_SIZE);
+
+    for record in read_records(format, path)? {
+        let document = record?.into_document();
+        let Some(embedding_dim) = document.embedding.as_ref().map(Vec::len) else {
+            skipped += 1;
+            continue;
+        };
+        if document.content.is_empty() || *dim.get_or_insert(embedding_dim) != embedding_dim {
+            skipped += 1;
+            continue;
+        }
+
+        batch.push(document);
+        if batch.len() >= constants::VECTOR_STORE_WRITE_BATCH_SIZE {
+            store.store_documents_batch(&batch)?;
+            imported += batch.len();
+            batch.clear();
+        }
+    }
+
+    if !batch.is_empty() {
+        store.store_documents_batch(&batch)?;
+        imported += batch.len();
+    }
+    store.flush()?;
+
+    Ok(ImportReport { collection: store.collection().to_string(), imported, skipped })
+}
+
+/// Write every embedding of a collection to `path`
+#[tauri::command]
+pub async fn export_embeddings(
+    collection: Option<String>,
+    format: EmbeddingFormat,
+    path: String
+) -> Result<ExportReport, String> {
+    let name = collection.unwrap_or_else(|| DEFAULT_COLLECTION.to_string());
+    log_operation_start!("Export embeddings", collection = %name, format = ?format, path = %path);
+    let path = path_policy::check_str(&path, Access::Write)?;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let store = VectorStore::open_collection(&name)?;
+        export_collection(&store, format, &path)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))
+    .and_then(|result| result);
+
+    match result {
+        Ok(report) => {
+            log_operation_success!("Export embeddings", count = report.count, dim = report.dim);
+            Ok(report)
+        }
+        Err(e) => {
+            log_operation_error!("Export embeddings", &e);
+            Err(e)
+        }
+    }
+}
+
+/// Add documents with precomputed embeddings from a file written by
+/// `export_embeddings` or another vector database
+#[tauri::command]
+pub async fn import_embeddings(
+    collection: Option<String>,
+    format: EmbeddingFormat,
+    path: String
+) -> Result<ImportReport, String> {
+    let name = collection.unwrap_or_else(|| DEFAULT_COLLECTION.to_string());
+    log_operation_start!("Import embeddings", collection = %name, format = ?format, path = %path);
+    let path = path_policy::check_str(&path, Access::Read)?;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let store = VectorStore::open_collection(&name)?;
+        import_records(&store, format, &path)
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))
+    .and_then(|result| result);
+
+    match result {
+        Ok(report) => {
+            log_operation_success!("Import embeddings", imported = report.imported, skipped = report.skipped);
+            Ok(report)
+        }
+        Err(e) => {
+            log_operation_error!("Import embeddings", &e);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npy_header_round_trip() {
+        let header = npy_header(1234, 768);
+        assert_eq!(header.len() % 64, 0);
+        assert_eq!(*header.last().unwrap(), b'\n');
+
+        let file_len = (header.len() + 1234 * 768 * 4) as u64;
+        let layout = read_npy_header(&mut header.as_slice(), file_len).unwrap();
+        assert_eq!(layout, NpyLayout { value_size: 4, rows: 1234, dim: 768 });
+    }
+
+    #[test]
+    fn test_npy_header_rejects_shape_larger_than_file() {
+        let header = npy_header(1_000_000, 1_000_000);
+        assert!(read_npy_header(&mut header.as_slice(), header.len() as u64 + 4096).is_err());
+
+        let header = npy_header(usize::MAX, 2);
+        assert!(read_npy_header(&mut header.as_slice(), u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_npy_metadata_path_differs_from_matrix_path() {
+        assert_eq!(npy_metadata_path(Path::new("out/vectors.npy")), Path::new("out/vectors.rows.jsonl"));
+        let jsonl = Path::new("out/vectors.jsonl");
+        assert_ne!(npy_metadata_path(jsonl), jsonl);
+    }
+
+    #[test]
+    fn test_npy_header_rejects_one_dimensional_shape() {
+        let mut header = npy_header(3, 4);
+        let dict = "{'descr': '<f4', 'fortran_order': False, 'shape': (12,), }";
+        header.truncate(10);
+        header.extend_from_slice(dict.as_bytes());
+        header[8..10].copy_from_slice(&(dict.len() as u16).to_le_bytes());
+
+        assert!(read_npy_header(&mut header.as_slice(), 1024).is_err());
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let path = std::env::temp_dir().join(format!("sparrow-interchange-{}.parquet", uuid::Uuid::new_v4()));
+        let mut document = Document::new(
+            "notes.md".to_string(),
+            "hello".to_string(),
+            "md".to_string(),
+            "notes/notes.md".to_string(),
+            Some(2)
+        );
+        document.metadata.insert("page".to_string(), "3".to_string());
+        document.embedding = Some(vec![0.5, 0.25]);
+
+        write_parquet(std::slice::from_ref(&document), &path).unwrap();
+        let records = read_parquet(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(records.len(), 1);
+        let imported = records.into_iter().next().unwrap().into_document();
+        assert_eq!(imported.id, document.id);
+        assert_eq!(imported.content, "hello");
+        assert_eq!(imported.chunk_index, Some(2));
+        assert_eq!(imported.created_at, document.created_at);
+        assert_eq!(imported.embedding, Some(vec![0.5, 0.25]));
+        assert_eq!(imported.metadata.get("page").map(String::as_str), Some("3"));
+    }
+
+    #[test]
+    fn test_import_record_accepts_common_field_names() {
+        let line = r#"{"id":"a1","text":"hello","vector":[0.5,0.25],"source":"notes/today.md","metadata":{"page":3}}"#;
+        let document = serde_json::from_str::<ImportRecord>(line).unwrap().into_document();
+
+        assert_eq!(document.id, "a1");
+        assert_eq!(document.content, "hello");
+        assert_eq!(document.title, "today.md");
+        assert_eq!(document.file_type, "md");
+        assert_eq!(document.embedding, Some(vec![0.5, 0.25]));
+        assert_eq!(document.metadata.get("page").map(String::as_str), Some("3"));
+    }
+}
@@ -1,8 +1,26 @@
 pub mod documents;
+pub mod chunking;
 pub mod embeddings; 
 pub mod vector_store;
 pub mod reranker;
 pub mod search;
+pub mod ingest;
+pub mod folder_watch;
+pub mod ingest_queue;
+pub mod quantization;
+pub mod embedding_matrix;
+pub mod hnsw;
+pub mod top_k;
+pub mod interchange;
+pub mod backend;
+pub mod qdrant;
+pub mod sessions;
+pub mod benchmark;
+pub mod graph;
+pub mod flashcards;
+pub mod citations;
+pub mod filter;
+pub mod reindex;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -0,0 +1,554 @@
+//! `VectorBackend` for an external Qdrant server, over its REST API.
+//!
+//! Each Sparrow collection maps to a Qdrant collection named
+//! `{collection_prefix}{collection}`, created with cosine distance on the
+//! first write (Qdrant needs the vector size up front). Documents are stored
+//! as point payloads without their embedding, plus `folders` and `tags` lists
+//! that search filters match on; point ids are the document ids when they
+//! are UUIDs, and a stable hash of the id otherwise. Chunks stored before
+//! those lists existed only match folder and tag filters once re-indexed.
+//!
+//! The API key lives in the OS credential store, like the Hugging Face
+//! token, and is set with `set_qdrant_api_key`. A key older versions saved
+//! in `settings.json` is moved there on first use.
+
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use reqwest::{ Client, Method, StatusCode };
+use serde_json::{ json, Value };
+use tokio::sync::OnceCell;
+
+use super::backend::{ self, VectorBackend };
+use super::{ Document, SearchResult };
+use super::filter::{ self as search_filter, SearchFilter };
+use crate::settings::{ self, QdrantSettings };
+
+/// Points per scroll request when listing documents
+const SCROLL_PAGE_SIZE: usize = 256;
+
+const KEYRING_SERVICE: &str = "SparrowAI";
+const KEYRING_ENTRY: &str = "qdrant-api-key";
+
+/// Key read from the credential store: `None` until the first lookup,
+/// then `Some(None)` when no key is stored
+static API_KEY_CACHE: Mutex<Option<Option<String>>> = Mutex::new(None);
+
+/// Payload lists that folder and tag filters match on
+const FOLDERS_FIELD: &str = "folders";
+const TAGS_FIELD: &str = "tags";
+
+pub struct QdrantStore {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    collection: String,
+    /// Set once the collection is known to exist
+    ready: OnceCell<()>,
+}
+
+impl QdrantStore {
+    pub fn new(settings: &QdrantSettings, collection: &str) -> Result<Self, String> {
+        let base_url = settings.url.trim_end_matches('/').to_string();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(format!("Invalid Qdrant URL: '{}'", settings.url));
+        }
+
+        Ok(Self {
+            client: crate::http::client()?,
+            base_url,
+            api_key: api_key(settings),
+            collection: format!("{}{}", settings.collection_prefix, collection),
+            ready: OnceCell::new(),
+        })
+    }
+
+    /// Send a request to `/collections/{collection}{path}` and return the `result` field
+    async fn request(&self, method: Method, path: &str, body: Option<Value>) -> Result<Value, String> {
+        self.send(method, &format!("/collections/{}{}", self.collection, path), body).await
+    }
+
+    /// Send a request to `path` on the server and return the `result` field
+    async fn send(&self, method: Method, path: &str, body: Option<Value>) -> Result<Value, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut request = self.client.request(method, &url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("api-key", api_key);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send()
+            .await
+            .map_err(|e| format!("Failed to reach Qdrant at {}: {}", self.base_url, e))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+
+        if !status.is_success() {
+            return Err(format!("Qdrant request failed with {}: {}", status, text));
+        }
+        let mut value: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid Qdrant response: {}", e))?;
+        Ok(value.get_mut("result").map(Value::take).unwrap_or(Value::Null))
+    }
+
+    async fn collection_exists(&self) -> Result<bool, String> {
+        let url = format!("{}/collections/{}", self.base_url, self.collection);
+        let mut request = self.client.get(&url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("api-key", api_key);
+        }
+        let response = request.send()
+            .await
+            .map_err(|e| format!("Failed to reach Qdrant at {}: {}", self.base_url, e))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(format!("Qdrant request failed with {}", status)),
+        }
+    }
+
+    async fn ensure_collection(&self, dim: usize) -> Result<(), String> {
+        self.ready
+            .get_or_try_init(|| async {
+                if !self.collection_exists().await? {
+                    tracing::info!(collection = %self.collection, dim, "Creating Qdrant collection");
+                    self.request(Method::PUT, "", Some(json!({
+                        "vectors": { "size": dim, "distance": "Cosine" }
+                    }))).await?;
+                }
+                Ok::<(), String>(())
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Reads against a collection that was never written to find nothing
+    async fn has_collection(&self) -> Result<bool, String> {
+        if self.ready.initialized() {
+            return Ok(true);
+        }
+        self.collection_exists().await
+    }
+
+    async fn search(&self, query_embedding: &[f32], filter: Option<Value>, limit: usize) -> Result<Vec<SearchResult>, String> {
+        if !self.has_collection().await? {
+            return Ok(Vec::new());
+        }
+
+        let mut body = json!({ "vector": query_embedding, "limit": limit, "with_payload": true });
+        if let Some(filter) = filter {
+            body["filter"] = filter;
+        }
+
+        let result = self.request(Method::POST, "/points/search", Some(body)).await?;
+        Ok(result
+            .as_array()
+            .map(|hits| {
+                hits.iter()
+                    .filter_map(|hit| {
+                        let document = document_from_payload(hit.get("payload")?)?;
+                        let score = hit.get("score")?.as_f64()? as f32;
+                        Some(SearchResult { document, score, rerank_score: None })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn count(&self, filter: Option<Value>) -> Result<usize, String> {
+        if !self.has_collection().await? {
+            return Ok(0);
+        }
+        let mut body = json!({ "exact": true });
+        if let Some(filter) = filter {
+            body["filter"] = filter;
+        }
+        let result = self.request(Method::POST, "/points/count", Some(body)).await?;
+        Ok(result.get("count").and_then(Value::as_u64).unwrap_or(0) as usize)
+    }
+
+    async fn scroll(&self, filter: Option<Value>) -> Result<Vec<Document>, String> {
+        if !self.has_collection().await? {
+            return Ok(Vec::new());
+        }
+
+        let mut documents = Vec::new();
+        let mut offset = Value::Null;
+        loop {
+            let mut body = json!({ "limit": SCROLL_PAGE_SIZE, "with_payload": true, "with_vector": false });
+            if let Some(filter) = &filter {
+                body["filter"] = filter.clone();
+            }
+            if !offset.is_null() {
+                body["offset"] = offset;
+            }
+
+            let mut result = self.request(Method::POST, "/points/scroll", Some(body)).await?;
+            if let Some(points) = result.get("points").and_then(Value::as_array) {
+                documents.extend(points.iter().filter_map(|point| document_from_payload(point.get("payload")?)));
+            }
+
+            offset = result.get_mut("next_page_offset").map(Value::take).unwrap_or(Value::Null);
+            if offset.is_null() {
+                return Ok(documents);
+            }
+        }
+    }
+}
+
+/// Delete every Qdrant collection named with `collection_prefix`, returning
+/// how many there were. With an empty prefix only the Sparrow collections
+/// known here are deleted, not everything on the server.
+pub async fn drop_all(settings: &QdrantSettings, known: &[&str]) -> Result<usize, String> {
+    let server = QdrantStore::new(settings, "")?;
+    let names: Vec<String> = if settings.collection_prefix.is_empty() {
+        known.iter().map(|name| name.to_string()).collect()
+    } else {
+        let result = server.send(Method::GET, "/collections", None).await?;
+        result["collections"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|collection| collection["name"].as_str())
+            .filter(|name| name.starts_with(&settings.collection_prefix))
+            .map(str::to_string)
+            .collect()
+    };
+
+    let mut dropped = 0;
+    for name in &names {
+        let store = QdrantStore::new(settings, name.strip_prefix(&settings.collection_prefix).unwrap_or(name))?;
+        if store.collection_exists().await? {
+            store.request(Method::DELETE, "", None).await?;
+            dropped += 1;
+        }
+    }
+    Ok(dropped)
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_ENTRY)
+        .map_err(|e| format!("Failed to open the credential store: {}", e))
+}
+
+fn stored_api_key() -> Option<String> {
+    let mut cache = API_KEY_CACHE.lock();
+    if let Some(key) = cache.as_ref() {
+        return key.clone();
+    }
+
+    let key = match keyring_entry().map(|entry| entry.get_password()) {
+        Ok(Ok(key)) => Some(key),
+        Ok(Err(keyring::Error::NoEntry)) => None,
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "Failed to read Qdrant API key from the credential store");
+            None
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read Qdrant API key from the credential store");
+            None
+        }
+    };
+    *cache = Some(key.clone());
+    key
+}
+
+fn store_api_key(api_key: Option<&str>) -> Result<(), String> {
+    let entry = keyring_entry()?;
+    match api_key {
+        Some(key) => entry
+            .set_password(key)
+            .map_err(|e| format!("Failed to store the API key in the credential store: {}", e))?,
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(format!("Failed to remove the API key from the credential store: {}", e)),
+        },
+    }
+    *API_KEY_CACHE.lock() = Some(api_key.map(str::to_string));
+    Ok(())
+}
+
+/// The stored key, after moving one left in `settings.json` into the credential store
+fn api_key(settings: &QdrantSettings) -> Option<String> {
+    if let Some(legacy) = settings.api_key.as_deref().filter(|key| !key.is_empty()) {
+        if let Err(e) = store_api_key(Some(legacy)) {
+            log_warning!("Qdrant API key stays in settings.json", error = %e);
+            return Some(legacy.to_string());
+        }
+        if let Err(e) = settings::update(|settings| settings.rag.qdrant.api_key = None) {
+            log_warning!("Failed to remove the Qdrant API key from settings.json", error = %e);
+        }
+        tracing::info!("Moved Qdrant API key to the credential store");
+    }
+    stored_api_key()
+}
+
+/// Whether a Qdrant API key is stored
+#[tauri::command]
+pub async fn get_qdrant_api_key_status() -> Result<bool, String> {
+    Ok(api_key(&settings::current().rag.qdrant).is_some())
+}
+
+/// Store the key sent to Qdrant; an empty key removes it
+#[tauri::command]
+pub async fn set_qdrant_api_key(api_key: String) -> Result<(), String> {
+    let api_key = api_key.trim();
+    store_api_key((!api_key.is_empty()).then_some(api_key))?;
+    if settings::current().rag.qdrant.api_key.is_some() {
+        settings::update(|settings| settings.rag.qdrant.api_key = None)?;
+    }
+    tracing::info!(configured = !api_key.is_empty(), "Updated Qdrant API key");
+    Ok(())
+}
+
+fn file_filter(file_paths: &[String]) -> Value {
+    json!({ "must": [{ "key": "file_path", "match": { "any": file_paths } }] })
+}
+
+/// `filter` as a Qdrant filter; folder and tag conditions match the
+/// `folders` and `tags` lists `payload_for` stores
+fn payload_filter(filter: &SearchFilter) -> Option<Value> {
+    let mut must = Vec::new();
+    if !filter.file_types.is_empty() {
+        let mut file_types: Vec<String> = filter.file_types
+            .iter()
+            .flat_map(|file_type| [file_type.clone(), file_type.to_lowercase()])
+            .collect();
+        file_types.sort();
+        file_types.dedup();
+        must.push(json!({ "key": "file_type", "match": { "any": file_types } }));
+    }
+    if filter.created_after.is_some() || filter.created_before.is_some() {
+        let mut range = serde_json::Map::new();
+        if let Some(after) = filter.created_after {
+            range.insert("gte".to_string(), json!(after));
+        }
+        if let Some(before) = filter.created_before {
+            range.insert("lt".to_string(), json!(before));
+        }
+        must.push(json!({ "key": "created_at", "range": range }));
+    }
+    if let Some(folder) = filter.path_prefix.as_deref().and_then(search_filter::folder_key) {
+        must.push(json!({ "key": FOLDERS_FIELD, "match": { "value": folder } }));
+    }
+    if !filter.tags.is_empty() {
+        let tags: Vec<String> = filter.tags.iter().map(|tag| tag.trim().to_lowercase()).collect();
+        must.push(json!({ "key": TAGS_FIELD, "match": { "any": tags } }));
+    }
+    (!must.is_empty()).then(|| json!({ "must": must }))
+}
+
+/// Qdrant point ids must be UUIDs or unsigned integers
+fn point_id(document_id: &str) -> Value {
+    match uuid::Uuid::parse_str(document_id) {
+        Ok(uuid) => json!(uuid.to_string()),
+        Err(_) => {
+            // FNV-1a: stable across runs and platforms, unlike `DefaultHasher`
+            let hash = document_id.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+            json!(hash)
+        }
+    }
+}
+
+fn payload_for(document: &Document) -> Result<Value, String> {
+    let mut payload = serde_json::to_value(document)
+        .map_err(|e| format!("Failed to serialize document: {}", e))?;
+    if let Some(object) = payload.as_object_mut() {
+        object.remove("embedding");
+        object.insert(FOLDERS_FIELD.to_string(), json!(search_filter::folders(&document.file_path)));
+        object.insert(TAGS_FIELD.to_string(), json!(search_filter::document_tags(document)));
+    }
+    Ok(payload)
+}
+
+fn document_from_payload(payload: &Value) -> Option<Document> {
+    let mut payload = payload.clone();
+    let object = payload.as_object_mut()?;
+    object.remove(FOLDERS_FIELD);
+    object.remove(TAGS_FIELD);
+    object.insert("embedding".to_string(), Value::Null);
+    serde_json::from_value(payload).ok()
+}
+
+impl VectorBackend for QdrantStore {
+    fn store_documents<'a>(&'a self, documents: &'a [Document]) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let mut points = Vec::with_capacity(documents.len());
+            for document in documents {
+                let Some(embedding) = &document.embedding else { continue };
+                points.push(json!({
+                    "id": point_id(&document.id),
+                    "vector": embedding,
+                    "payload": payload_for(document)?,
+                }));
+            }
+            let Some(dim) = documents.iter().find_map(|doc| doc.embedding.as_ref().map(Vec::len)) else {
+                return Ok(());
+            };
+
+            self.ensure_collection(dim).await?;
+            self.request(Method::PUT, "/points?wait=true", Some(json!({ "points": points }))).await?;
+            Ok(())
+        })
+    }
+
+    fn search_similar<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>> {
+        Box::pin(self.search(query_embedding, None, limit))
+    }
+
+    fn search_similar_in_files<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        file_paths: &'a [String],
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>> {
+        Box::pin(self.search(query_embedding, Some(file_filter(file_paths)), limit))
+    }
+
+    fn search_similar_filtered<'a>(
+        &'a self,
+        query_embedding: &'a [f32],
+        filter: &'a SearchFilter,
+        limit: usize
+    ) -> BoxFuture<'a, Result<Vec<SearchResult>, String>> {
+        Box::pin(self.search(query_embedding, payload_filter(filter), limit))
+    }
+
+    fn delete_document<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, String>> {
+        Box::pin(async move {
+            if !self.has_collection().await? {
+                return Ok(false);
+            }
+            let existing = self.request(Method::POST, "/points", Some(json!({
+                "ids": [point_id(id)], "with_payload": false
+            }))).await?;
+            let existed = existing.as_array().is_some_and(|points| !points.is_empty());
+
+            if existed {
+                self.request(Method::POST, "/points/delete?wait=true", Some(json!({
+                    "points": [point_id(id)]
+                }))).await?;
+            }
+            Ok(existed)
+        })
+    }
+
+    fn delete_file<'a>(&'a self, file_path: &'a str) -> BoxFuture<'a, Result<usize, String>> {
+        Box::pin(async move {
+            let filter = file_filter(&[file_path.to_string()]);
+            let count = self.count(Some(filter.clone())).await?;
+            if count > 0 {
+                self.request(Method::POST, "/points/delete?wait=true", Some(json!({ "filter": filter }))).await?;
+            }
+            Ok(count)
+        })
+    }
+
+    fn list_documents(&self) -> BoxFuture<'_, Result<Vec<Document>, String>> {
+        Box::pin(async move {
+            let mut documents = self.scroll(None).await?;
+            documents.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            Ok(documents)
+        })
+    }
+
+    fn count_documents(&self) -> BoxFuture<'_, Result<usize, String>> {
+        Box::pin(self.count(None))
+    }
+
+    fn clear_all(&self) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            // Delete every point but keep the collection and its vector size
+            if self.has_collection().await? {
+                self.request(Method::POST, "/points/delete?wait=true", Some(json!({ "filter": {} }))).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn drop_collection(&self) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            if self.has_collection().await? {
+                self.request(Method::DELETE, "", None).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn flush(&self) -> BoxFuture<'_, Result<(), String>> {
+        // Writes use `wait=true`, so they are applied before the request returns
+        Box::pin(async { Ok(()) })
+    }
+
+    fn file_chunks<'a>(&'a self, file_path: &'a str) -> BoxFuture<'a, Result<Vec<Document>, String>> {
+        Box::pin(async move {
+            let mut chunks = self.scroll(Some(file_filter(&[file_path.to_string()]))).await?;
+            backend::sort_chunks(&mut chunks);
+            Ok(chunks)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_id_keeps_uuids_and_hashes_other_ids() {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        assert_eq!(point_id(&uuid), json!(uuid));
+
+        let hashed = point_id("chunk-42");
+        assert!(hashed.is_u64());
+        assert_eq!(hashed, point_id("chunk-42"));
+        assert_ne!(hashed, point_id("chunk-43"));
+    }
+
+    #[test]
+    fn test_payload_filter_covers_every_condition() {
+        assert_eq!(payload_filter(&SearchFilter { path_prefix: Some("/".to_string()), ..Default::default() }), None);
+
+        let filter = SearchFilter {
+            file_types: vec!["PDF".to_string()],
+            created_after: Some(10),
+            path_prefix: Some("/home/me/docs/".to_string()),
+            tags: vec![" Work ".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(payload_filter(&filter), Some(json!({ "must": [
+            { "key": "file_type", "match": { "any": ["PDF", "pdf"] } },
+            { "key": "created_at", "range": { "gte": 10 } },
+            { "key": "folders", "match": { "value": "/home/me/docs" } },
+            { "key": "tags", "match": { "any": ["work"] } },
+        ] })));
+    }
+
+    #[test]
+    fn test_payload_round_trip_drops_embedding() {
+        let mut document = Document::new(
+            "notes.md - Part 1".to_string(),
+            "hello".to_string(),
+            "md".to_string(),
+            "/tmp/notes.md".to_string(),
+            Some(0)
+        );
+        document.embedding = Some(vec![0.1, 0.2]);
+
+        let payload = payload_for(&document).unwrap();
+        assert!(payload.get("embedding").is_none());
+        assert_eq!(payload["folders"], json!(["/tmp", "/tmp/notes.md"]));
+
+        let restored = document_from_payload(&payload).unwrap();
+        assert_eq!(restored.id, document.id);
+        assert_eq!(restored.chunk_index, Some(0));
+        assert!(restored.embedding.is_none());
+    }
+}
@@ -0,0 +1,156 @@
+//! Compact storage formats for document embeddings.
+//!
+//! Quantization is asymmetric: stored vectors are compressed, but queries
+//! stay full precision and are scored directly against the compressed form.
+//! `Int8` keeps one signed byte per dimension plus a scale (~4x smaller);
+//! `Binary` keeps only the sign of each dimension (~32x smaller) and is best
+//! used for large corpora where reranking recovers the lost precision.
+
+use serde::{ Deserialize, Serialize };
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// Full f32 embeddings stored on the document
+    #[default]
+    None,
+    Int8,
+    Binary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum QuantizedVector {
+    Int8 {
+        scale: f32,
+        /// L2 norm of the original vector
+        norm: f32,
+        values: Vec<i8>,
+    },
+    Binary {
+        dim: u32,
+        /// Bit `i` is set when dimension `i` was positive
+        bits: Vec<u8>,
+    },
+}
+
+impl QuantizedVector {
+    /// Compress `embedding`; `None` when `mode` is `Quantization::None`
+    pub fn quantize(embedding: &[f32], mode: Quantization) -> Option<Self> {
+        match mode {
+            Quantization::None => None,
+            Quantization::Int8 => {
+                let max_abs = embedding.iter().fold(0.0f32, |acc, x| acc.max(x.abs()));
+                let scale = if max_abs > 0.0 { max_abs / 127.0 } else { 1.0 };
+                let values = embedding
+                    .iter()
+                    .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8)
+                    .collect();
+                Some(QuantizedVector::Int8 { scale, norm: l2_norm(embedding), values })
+            }
+            Quantization::Binary => {
+                let mut bits = vec![0u8; embedding.len().div_ceil(8)];
+                for (i, x) in embedding.iter().enumerate() {
+                    if *x > 0.0 {
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                Some(QuantizedVector::Binary { dim: embedding.len() as u32, bits })
+            }
+        }
+    }
+
+    pub fn kind(&self) -> Quantization {
+        match self {
+            QuantizedVector::Int8 { .. } => Quantization::Int8,
+            QuantizedVector::Binary { .. } => Quantization::Binary,
+        }
+    }
+
+    /// Approximate the original vector. Exact up to rounding for `Int8`;
+    /// `Binary` only keeps signs, so its result is a ±1 vector.
+    pub fn dequantize(&self) -> Vec<f32> {
+        match self {
+            QuantizedVector::Int8 { scale, values, .. } => {
+                values.iter().map(|v| *v as f32 * scale).collect()
+            }
+            QuantizedVector::Binary { dim, bits } => {
+                (0..*dim as usize)
+                    .map(|i| if bits[i / 8] & (1 << (i % 8)) != 0 { 1.0 } else { -1.0 })
+                    .collect()
+            }
+        }
+    }
+
+    /// Cosine similarity between a full-precision query and this vector.
+    /// `query_norm` is passed in so it is computed once per search.
+    pub fn cosine(&self, query: &[f32], query_norm: f32) -> f32 {
+        if query_norm == 0.0 {
+            return 0.0;
+        }
+
+        match self {
+            QuantizedVector::Int8 { scale, norm, values } => {
+                if values.len() != query.len() || *norm == 0.0 {
+                    return 0.0;
+                }
+                let dot: f32 = query.iter().zip(values).map(|(q, v)| q * *v as f32).sum();
+                dot * scale / (query_norm * norm)
+            }
+            QuantizedVector::Binary { dim, bits } => {
+                if *dim as usize != query.len() || *dim == 0 {
+                    return 0.0;
+                }
+                let dot: f32 = query
+                    .iter()
+                    .enumerate()
+                    .map(|(i, q)| if bits[i / 8] & (1 << (i % 8)) != 0 { *q } else { -*q })
+                    .sum();
+                dot / (query_norm * (*dim as f32).sqrt())
+            }
+        }
+    }
+}
+
+pub fn l2_norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exact_cosine(a: &[f32], b: &[f32]) -> f32 {
+        let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+        dot / (l2_norm(a) * l2_norm(b))
+    }
+
+    #[test]
+    fn test_int8_scoring_close_to_exact() {
+        let doc = [0.12, -0.5, 0.33, 0.9, -0.07, 0.0, 0.41, -0.26];
+        let query = [0.1, -0.4, 0.3, 0.8, 0.0, 0.05, 0.35, -0.3];
+
+        let quantized = QuantizedVector::quantize(&doc, Quantization::Int8).unwrap();
+        let approx = quantized.cosine(&query, l2_norm(&query));
+
+        assert!((approx - exact_cosine(&query, &doc)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_binary_preserves_ranking() {
+        let query = [0.9, 0.8, -0.7, 0.6, -0.5, 0.4, 0.3, -0.2];
+        let near = [0.8, 0.7, -0.6, 0.5, -0.4, 0.5, 0.2, -0.1];
+        let far = [-0.8, -0.7, 0.6, -0.5, 0.4, -0.5, -0.2, 0.1];
+
+        let near_q = QuantizedVector::quantize(&near, Quantization::Binary).unwrap();
+        let far_q = QuantizedVector::quantize(&far, Quantization::Binary).unwrap();
+        let norm = l2_norm(&query);
+
+        assert!(near_q.cosine(&query, norm) > far_q.cosine(&query, norm));
+        assert_eq!(near_q.dequantize().len(), near.len());
+    }
+
+    #[test]
+    fn test_none_mode_keeps_full_precision() {
+        assert!(QuantizedVector::quantize(&[1.0, 2.0], Quantization::None).is_none());
+    }
+}
@@ -0,0 +1,371 @@
+//! Incremental re-indexing of files that changed on disk.
+//!
+//! Every chunk records the modification time and SHA-256 of the file it was
+//! read from. `reindex_file` compares them with the file as it is now:
+//!
+//! - same modification time: nothing to do
+//! - same hash: only the recorded time is updated
+//! - otherwise the file is chunked again the way it was before (see
+//!   `ChunkingOptions::of_document`), and only chunks whose text is new are
+//!   embedded; unchanged chunks keep their id and embedding, and chunks that
+//!   no longer occur are deleted
+//!
+//! A file whose chunks do not all record the same version is treated as
+//! changed. Chunks of a run that fails are restored as they were, and the
+//! knowledge graph is extracted again for files that were updated.
+//!
+//! A file that is gone keeps its chunks (uploads are indexed from temporary
+//! copies that are purged later); `delete_file_by_path` removes them.
+
+use std::collections::{ HashMap, HashSet };
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+
+use super::Document;
+use super::backend::{ self, VectorBackend };
+use super::chunking::ChunkingOptions;
+use super::documents::stream_document_chunks;
+use super::embeddings::EmbeddingService;
+use super::graph;
+use super::ingest::{ self, embed_and_store };
+use crate::path_policy::{ self, Access };
+use crate::{ constants, model_integrity };
+
+/// Metadata entries recording the version of the file a chunk was read from
+pub const MTIME_KEY: &str = "file_mtime";
+pub const HASH_KEY: &str = "file_sha256";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileVersion {
+    /// Modification time in Unix milliseconds
+    pub mtime: i64,
+    pub sha256: String,
+}
+
+impl FileVersion {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let mtime = modified_ms(path)?;
+        let sha256 = model_integrity::sha256_file(path)
+            .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?;
+        Ok(Self { mtime, sha256 })
+    }
+
+    pub fn record(&self, metadata: &mut HashMap<String, String>) {
+        metadata.insert(MTIME_KEY.to_string(), self.mtime.to_string());
+        metadata.insert(HASH_KEY.to_string(), self.sha256.clone());
+    }
+
+    /// The version a chunk was read from; `None` for chunks indexed before it was recorded
+    pub fn of_document(document: &Document) -> Option<Self> {
+        Some(Self {
+            mtime: document.metadata.get(MTIME_KEY)?.parse().ok()?,
+            sha256: document.metadata.get(HASH_KEY)?.clone(),
+        })
+    }
+}
+
+/// The version all of a file's chunks were read from; `None` if any chunk
+/// records none or a different one
+fn recorded_version(chunks: &[Document]) -> Option<FileVersion> {
+    let version = FileVersion::of_document(chunks.first()?)?;
+    chunks[1..]
+        .iter()
+        .all(|chunk| FileVersion::of_document(chunk).as_ref() == Some(&version))
+        .then_some(version)
+}
+
+/// Whether `existing`, a file's chunks, were read from the file as it is now.
+/// A file that was only touched gets its new time recorded on the chunks.
+pub(crate) async fn is_current(vector_store: &dyn VectorBackend, path: &Path, existing: &[Document]) -> Result<bool, String> {
+    let Some(recorded) = recorded_version(existing) else {
+        return Ok(false);
+    };
+    if recorded.mtime == modified_ms(path)? {
+        return Ok(true);
+    }
+
+    let hash_path = path.to_path_buf();
+    let current = tokio::task::spawn_blocking(move || FileVersion::read(&hash_path))
+        .await
+        .map_err(|e| format!("Failed to hash file: {}", e))??;
+    if recorded.sha256 != current.sha256 {
+        return Ok(false);
+    }
+
+    let mut chunks = existing.to_vec();
+    for chunk in &mut chunks {
+        current.record(&mut chunk.metadata);
+    }
+    vector_store.store_documents(&chunks).await?;
+    vector_store.flush().await?;
+    Ok(true)
+}
+
+fn modified_ms(path: &Path) -> Result<i64, String> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("Failed to read modification time of {}: {}", path.display(), e))?;
+    Ok(chrono::DateTime::<chrono::Utc>::from(modified).timestamp_millis())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexStatus {
+    Unchanged,
+    Updated,
+    /// The file no longer exists; its chunks were left in place
+    Missing,
+    Failed,
+}
+
+/// Result of `reindex_file`, and per file of `reindex_all` (emitted as `reindex-progress`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ReindexSummary {
+    pub file_path: String,
+    pub status: ReindexStatus,
+    /// Chunks of the file after re-indexing
+    pub chunk_count: usize,
+    pub chunks_embedded: usize,
+    pub chunks_reused: usize,
+    pub chunks_removed: usize,
+    pub error: Option<String>,
+}
+
+impl ReindexSummary {
+    fn new(file_path: &str, status: ReindexStatus, chunk_count: usize) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            status,
+            chunk_count,
+            chunks_embedded: 0,
+            chunks_reused: 0,
+            chunks_removed: 0,
+            error: None,
+        }
+    }
+}
+
+/// Old chunks by content, so unchanged text can keep its embedding
+struct ReusableChunks(HashMap<String, Vec<Document>>);
+
+impl ReusableChunks {
+    fn new(chunks: Vec<Document>) -> Self {
+        let mut by_content: HashMap<String, Vec<Document>> = HashMap::new();
+        for chunk in chunks.into_iter().filter(|chunk| chunk.embedding.is_some()) {
+            by_content.entry(chunk.content.clone()).or_default().push(chunk);
+        }
+        Self(by_content)
+    }
+
+    /// Give `chunk` the id and embedding of an old chunk with the same text,
+    /// returning the old chunk so a failed run can put it back
+    fn reuse(&mut self, chunk: &mut Document) -> Option<Document> {
+        let old = self.0.get_mut(&chunk.content).and_then(Vec::pop)?;
+        chunk.id = old.id.clone();
+        chunk.embedding = old.embedding.clone();
+        chunk.created_at = old.created_at;
+        Some(old)
+    }
+}
+
+async fn reindex(
+    vector_store: &dyn VectorBackend,
+    embedding_service: &EmbeddingService,
+    file_path: &str
+) -> Result<ReindexSummary, String> {
+    let existing = vector_store.file_chunks(file_path).await?;
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Ok(ReindexSummary::new(file_path, ReindexStatus::Missing, existing.len()));
+    }
+
+    if is_current(vector_store, path, &existing).await? {
+        return Ok(ReindexSummary::new(file_path, ReindexStatus::Unchanged, existing.len()));
+    }
+
+    // Chunk the way the file was chunked before, so unchanged text lines up again
+    let chunking = existing.iter().find_map(ChunkingOptions::of_document).unwrap_or_default();
+    let old_ids: Vec<String> = existing.iter().map(|chunk| chunk.id.clone()).collect();
+    let mut reusable = ReusableChunks::new(existing);
+
+    let mut summary = ReindexSummary::new(file_path, ReindexStatus::Updated, 0);
+    let mut kept_ids = HashSet::new();
+    let mut new_ids = Vec::new();
+    // Reused chunks as they were, with the version they were read from
+    let mut replaced = Vec::new();
+
+    let result: Result<(), String> = async {
+        let mut chunks = stream_document_chunks(file_path.to_string(), chunking)?;
+        let mut batch = Vec::with_capacity(constants::EMBEDDING_BATCH_SIZE);
+        let mut reused = Vec::with_capacity(constants::EMBEDDING_BATCH_SIZE);
+        loop {
+            let next = chunks.recv().await;
+            let at_end = next.is_none();
+            if let Some(chunk) = next {
+                let mut chunk = chunk?;
+                if let Some(old) = reusable.reuse(&mut chunk) {
+                    replaced.push(old);
+                    reused.push(chunk);
+                } else {
+                    batch.push(chunk);
+                }
+            }
+
+            if reused.len() >= constants::EMBEDDING_BATCH_SIZE || (at_end && !reused.is_empty()) {
+                vector_store.store_documents(&reused).await?;
+                summary.chunks_reused += reused.len();
+                kept_ids.extend(reused.drain(..).map(|chunk| chunk.id));
+            }
+            if batch.len() >= constants::EMBEDDING_BATCH_SIZE || (at_end && !batch.is_empty()) {
+                let ids = embed_and_store(embedding_service, vector_store, std::mem::take(&mut batch)).await?;
+                summary.chunks_embedded += ids.len();
+                new_ids.extend(ids);
+            }
+
+            if at_end {
+                return Ok(());
+            }
+        }
+    }.await;
+
+    if let Err(e) = result {
+        // Reused chunks were rewritten with the new file version; put the old records back
+        let _ = vector_store.store_documents(&replaced).await;
+        for id in &new_ids {
+            let _ = vector_store.delete_document(id).await;
+        }
+        let _ = vector_store.flush().await;
+        return Err(e);
+    }
+
+    for id in old_ids.iter().filter(|id| !kept_ids.contains(id)) {
+        if vector_store.delete_document(id).await? {
+            summary.chunks_removed += 1;
+        }
+    }
+    vector_store.flush().await?;
+
+    summary.chunk_count = summary.chunks_reused + summary.chunks_embedded;
+    Ok(summary)
+}
+
+/// Bring one file's chunks up to date with the file on disk
+#[tauri::command]
+pub async fn reindex_file(app: AppHandle, file_path: String) -> Result<ReindexSummary, String> {
+    path_policy::check_str(&file_path, Access::Read)?;
+    log_operation_start!("Re-index file", file = %file_path);
+
+    crate::ensure_ovms_initialized(&app).await;
+    let vector_store = backend::open_default()?;
+    let _file = ingest::lock_file(&file_path).await;
+    let summary = reindex(vector_store.as_ref(), &EmbeddingService::new(), &file_path).await.map_err(|e| {
+        log_operation_error!("Re-index file", &e, file = %file_path);
+        e
+    })?;
+    if summary.status == ReindexStatus::Updated {
+        graph::extract_file_in_background(&app, file_path.clone());
+    }
+
+    log_operation_success!(
+        "Re-index file",
+        status = ?summary.status,
+        embedded = summary.chunks_embedded,
+        reused = summary.chunks_reused,
+        removed = summary.chunks_removed
+    );
+    Ok(summary)
+}
+
+/// `reindex_file` for every indexed file; a file that fails does not stop the rest
+#[tauri::command]
+pub async fn reindex_all(app: AppHandle) -> Result<Vec<ReindexSummary>, String> {
+    log_operation_start!("Re-index all files");
+    crate::ensure_ovms_initialized(&app).await;
+
+    let vector_store = backend::open_default()?;
+    let embedding_service = EmbeddingService::new();
+    let files = vector_store.list_files().await?;
+
+    let mut summaries = Vec::with_capacity(files.len());
+    for file in files {
+        let summary = match path_policy::check_str(&file.file_path, Access::Read) {
+            Ok(()) => {
+                let _file = ingest::lock_file(&file.file_path).await;
+                reindex(vector_store.as_ref(), &embedding_service, &file.file_path).await
+            }
+            Err(e) => Err(e),
+        };
+        let summary = summary.unwrap_or_else(|e| {
+            log_warning!("Re-indexing file failed", error = %e, file = %file.file_path);
+            ReindexSummary {
+                error: Some(e),
+                ..ReindexSummary::new(&file.file_path, ReindexStatus::Failed, file.chunk_count)
+            }
+        });
+        if summary.status == ReindexStatus::Updated {
+            graph::extract_file_in_background(&app, file.file_path.clone());
+        }
+        let _ = app.emit("reindex-progress", &summary);
+        summaries.push(summary);
+    }
+
+    let updated = summaries.iter().filter(|summary| summary.status == ReindexStatus::Updated).count();
+    log_operation_success!("Re-index all files", files = summaries.len(), updated = updated);
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &str, embedding: Option<Vec<f32>>) -> Document {
+        let mut document = Document::new("t".to_string(), content.to_string(), "md".to_string(), "a.md".to_string(), Some(0));
+        document.embedding = embedding;
+        document
+    }
+
+    #[test]
+    fn test_reusable_chunks_match_by_content_once() {
+        let old = chunk("same text", Some(vec![1.0, 0.0]));
+        let old_id = old.id.clone();
+        let mut reusable = ReusableChunks::new(vec![old, chunk("unembedded", None)]);
+
+        let mut first = chunk("same text", None);
+        assert_eq!(reusable.reuse(&mut first).map(|old| old.id), Some(old_id.clone()));
+        assert_eq!(first.id, old_id);
+        assert_eq!(first.embedding, Some(vec![1.0, 0.0]));
+
+        assert!(reusable.reuse(&mut chunk("same text", None)).is_none());
+        assert!(reusable.reuse(&mut chunk("unembedded", None)).is_none());
+        assert!(reusable.reuse(&mut chunk("new text", None)).is_none());
+    }
+
+    #[test]
+    fn test_recorded_version_requires_every_chunk_to_agree() {
+        let version = FileVersion { mtime: 1_700_000_000_000, sha256: "ab".repeat(32) };
+        let mut chunks = vec![chunk("a", None), chunk("b", None)];
+        for chunk in &mut chunks {
+            version.record(&mut chunk.metadata);
+        }
+        assert_eq!(recorded_version(&chunks), Some(version.clone()));
+
+        let newer = FileVersion { mtime: version.mtime + 1, ..version };
+        newer.record(&mut chunks[1].metadata);
+        assert_eq!(recorded_version(&chunks), None);
+
+        chunks.push(chunk("c", None));
+        assert_eq!(recorded_version(&chunks[2..]), None);
+        assert_eq!(recorded_version(&[]), None);
+    }
+
+    #[test]
+    fn test_file_version_round_trips_through_metadata() {
+        let version = FileVersion { mtime: 1_700_000_000_000, sha256: "ab".repeat(32) };
+        let mut document = chunk("text", None);
+        assert_eq!(FileVersion::of_document(&document), None);
+        version.record(&mut document.metadata);
+        assert_eq!(FileVersion::of_document(&document), Some(version));
+    }
+}
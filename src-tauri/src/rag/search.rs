@@ -1,29 +1,45 @@
 use super::SearchResult;
+use super::filter::SearchFilter;
 use crate::rag::embeddings::EmbeddingService;
-use crate::rag::vector_store::VectorStore;
+use crate::rag::backend::{ self, VectorBackend };
 use crate::rag::reranker::RerankerService;
 
 pub struct SearchService {
     embedding_service: EmbeddingService,
-    vector_store: VectorStore,
+    vector_store: Box<dyn VectorBackend>,
     reranker_service: RerankerService,
 }
 
 impl SearchService {
     pub fn new() -> Result<Self, String> {
+        Self::for_collection(crate::rag::vector_store::DEFAULT_COLLECTION)
+    }
+
+    pub fn for_collection(collection: &str) -> Result<Self, String> {
         Ok(Self {
             embedding_service: EmbeddingService::new(),
-            vector_store: VectorStore::new()?,
+            vector_store: backend::open(collection)?,
             reranker_service: RerankerService::new(),
         })
     }
     
     pub async fn search(&self, query: &str, limit: usize, use_reranking: bool) -> Result<Vec<SearchResult>, String> {
+        self.search_filtered(query, limit, &SearchFilter::default(), use_reranking).await
+    }
+
+    /// Search only the chunks `filter` accepts; the filter applies before scoring
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+        use_reranking: bool
+    ) -> Result<Vec<SearchResult>, String> {
         // Step 1: Create query embedding
         let query_embedding = self.embedding_service.create_single_embedding(query.to_string()).await?;
         
         // Step 2: Vector similarity search
-        let initial_results = self.vector_store.search_similar(&query_embedding, limit * 2)?; // Get more for reranking
+        let initial_results = self.vector_store.search_similar_filtered(&query_embedding, filter, limit * 2).await?; // Get more for reranking
         
         // Step 3: Rerank if requested
         let final_results = if use_reranking && !initial_results.is_empty() {
@@ -35,26 +51,6 @@ impl SearchService {
         
         Ok(final_results)
     }
-    
-    pub async fn search_with_filters(
-        &self, 
-        query: &str, 
-        limit: usize, 
-        file_types: Option<Vec<String>>,
-        use_reranking: bool
-    ) -> Result<Vec<SearchResult>, String> {
-        let mut results = self.search(query, limit * 2, use_reranking).await?;
-        
-        // Apply file type filters if specified
-        if let Some(types) = file_types {
-            results = results.into_iter()
-                .filter(|result| types.contains(&result.document.file_type))
-                .collect();
-        }
-        
-        results.truncate(limit);
-        Ok(results)
-    }
 }
 
 #[tauri::command]
@@ -62,7 +58,8 @@ pub async fn search_documents_by_query(
     query: String, 
     limit: Option<usize>, 
     use_reranking: Option<bool>,
-    file_types: Option<Vec<String>>
+    file_types: Option<Vec<String>>,
+    filter: Option<SearchFilter>
 ) -> Result<Vec<SearchResult>, String> {
     log_operation_start!("Search documents");
     
@@ -80,14 +77,14 @@ pub async fn search_documents_by_query(
         limit = search_limit,
         rerank = should_rerank,
         file_types = ?file_types,
+        filter = ?filter,
         "Searching documents"
     );
     
-    let results = if let Some(types) = file_types {
-        search_service.search_with_filters(&query, search_limit, Some(types), should_rerank).await?
-    } else {
-        search_service.search(&query, search_limit, should_rerank).await?
-    };
+    // `file_types` predates the filter and adds to its file types
+    let mut filter = filter.unwrap_or_default();
+    filter.file_types.extend(file_types.unwrap_or_default());
+    let results = search_service.search_filtered(&query, search_limit, &filter, should_rerank).await?;
     
     log_operation_success!("Search documents");
     tracing::debug!(query = %query, results_count = results.len(), "Document search completed");
@@ -0,0 +1,220 @@
+//! "Find similar conversations": chat sessions embedded into their own collection.
+//!
+//! Each saved session is indexed as one document in the `chat_sessions`
+//! collection, whose content is a summary of the title and messages. The
+//! index is brought up to date lazily when related sessions are requested:
+//! sessions whose `updated_at` changed since they were embedded are
+//! re-embedded, and sessions that no longer exist are dropped.
+
+use std::collections::{ HashMap, HashSet };
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::Document;
+use super::backend::{ self, VectorBackend };
+use super::embeddings::EmbeddingService;
+use crate::chat::{ self, ChatSession };
+use crate::constants;
+
+pub const SESSIONS_COLLECTION: &str = "chat_sessions";
+
+const SESSION_FILE_TYPE: &str = "chat_session";
+
+/// Metadata key holding the `updated_at` of the session when it was embedded
+const UPDATED_AT_KEY: &str = "updated_at";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedSession {
+    pub session_id: String,
+    pub title: String,
+    pub score: f32,
+    pub updated_at: i64,
+}
+
+/// Title plus messages in order, cut to `SESSION_SUMMARY_MAX_CHARS`
+pub(crate) fn session_summary(session: &ChatSession) -> String {
+    let mut summary = session.title.clone();
+    for message in &session.messages {
+        if message.is_error == Some(true) || message.content.trim().is_empty() {
+            continue;
+        }
+        summary.push('\n');
+        summary.push_str(&message.role);
+        summary.push_str(": ");
+        summary.push_str(message.content.trim());
+        if summary.chars().count() >= constants::SESSION_SUMMARY_MAX_CHARS {
+            break;
+        }
+    }
+    summary.chars().take(constants::SESSION_SUMMARY_MAX_CHARS).collect()
+}
+
+fn session_document(session: &ChatSession, embedding: Vec<f32>) -> Document {
+    let mut document = Document::new(
+        session.title.clone(),
+        session_summary(session),
+        SESSION_FILE_TYPE.to_string(),
+        String::new(),
+        None
+    );
+    document.id = session.id.clone();
+    document.metadata.insert(UPDATED_AT_KEY.to_string(), session.updated_at.to_string());
+    document.embedding = Some(embedding);
+    document
+}
+
+/// Re-embed new and changed sessions and drop deleted ones
+async fn sync_index(
+    index: &dyn VectorBackend,
+    embedding_service: &EmbeddingService,
+    sessions: &HashMap<String, ChatSession>
+) -> Result<usize, String> {
+    let indexed: HashMap<String, i64> = index
+        .list_documents()
+        .await?
+        .into_iter()
+        .map(|doc| {
+            let updated_at = doc.metadata.get(UPDATED_AT_KEY).and_then(|v| v.parse().ok()).unwrap_or(0);
+            (doc.id, updated_at)
+        })
+        .collect();
+
+    for id in indexed.keys().filter(|id| !sessions.contains_key(*id)) {
+        index.delete_document(id).await?;
+    }
+
+    let stale: Vec<&ChatSession> = sessions
+        .values()
+        .filter(|session| !session.messages.is_empty())
+        .filter(|session| indexed.get(&session.id) != Some(&session.updated_at))
+        .collect();
+
+    for batch in stale.chunks(constants::EMBEDDING_BATCH_SIZE) {
+        let texts = batch.iter().map(|session| session_summary(session)).collect();
+        let embeddings = embedding_service.create_embeddings(texts).await?;
+        let documents: Vec<Document> = batch
+            .iter()
+            .zip(embeddings)
+            .map(|(session, embedding)| session_document(session, embedding))
+            .collect();
+        index.store_documents(&documents).await?;
+    }
+
+    index.flush().await?;
+    Ok(stale.len())
+}
+
+/// Saved sessions about the same topic as `session_id`, most similar first
+#[tauri::command]
+pub async fn find_related_sessions(
+    app: AppHandle,
+    session_id: String,
+    limit: Option<usize>
+) -> Result<Vec<RelatedSession>, String> {
+    log_operation_start!("Find related sessions", session_id = %session_id);
+
+    let storage = chat::load_chat_sessions().await?;
+    let session = storage.sessions
+        .get(&session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+    if session.messages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    crate::ensure_ovms_initialized(&app).await;
+
+    let index = backend::open(SESSIONS_COLLECTION)?;
+    let embedding_service = EmbeddingService::new();
+    let reindexed = sync_index(index.as_ref(), &embedding_service, &storage.sessions).await.map_err(|e| {
+        log_operation_error!("Find related sessions", &e, session_id = %session_id);
+        e
+    })?;
+
+    let query = embedding_service.create_single_embedding(session_summary(session)).await?;
+    let limit = limit.unwrap_or(5);
+    let mut seen = HashSet::new();
+
+    let related: Vec<RelatedSession> = index
+        .search_similar(&query, limit + 1)
+        .await?
+        .into_iter()
+        .filter(|result| result.document.id != session_id && seen.insert(result.document.id.clone()))
+        .filter_map(|result| {
+            let other = storage.sessions.get(&result.document.id)?;
+            Some(RelatedSession {
+                session_id: other.id.clone(),
+                title: other.title.clone(),
+                score: result.score,
+                updated_at: other.updated_at,
+            })
+        })
+        .take(limit)
+        .collect();
+
+    log_operation_success!("Find related sessions", found = related.len(), reindexed);
+    Ok(related)
+}
+
+/// Drop a deleted session from the index. Failures are only logged: the next
+/// `find_related_sessions` removes sessions that no longer exist anyway.
+pub async fn forget_session(session_id: &str) {
+    let result = async {
+        let index = backend::open(SESSIONS_COLLECTION)?;
+        index.delete_document(session_id).await
+    }.await;
+
+    if let Err(e) = result {
+        log_warning!("Failed to remove session from the related-session index", error = %e, session_id = %session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatMessage;
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: 0,
+            tokens_per_second: None,
+            is_error: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            total_tokens: None,
+            attachments: None,
+            pinned: false,
+            generation: None,
+            citations: None,
+        }
+    }
+
+    #[test]
+    fn test_session_summary_is_bounded() {
+        let session = ChatSession {
+            id: "s1".to_string(),
+            title: "Rust lifetimes".to_string(),
+            created_at: 0,
+            updated_at: 0,
+            model_id: None,
+            messages: vec![
+                message("user", "Why does the borrow checker complain?"),
+                message("assistant", &"é".repeat(constants::SESSION_SUMMARY_MAX_CHARS)),
+                message("user", "never reached"),
+            ],
+            preferred_language: None,
+            tool_policy: Default::default(),
+            sampling: Default::default(),
+            prompt_preset: None,
+            revision: 0,
+        };
+
+        let summary = session_summary(&session);
+        assert!(summary.starts_with("Rust lifetimes\nuser: Why does"));
+        assert_eq!(summary.chars().count(), constants::SESSION_SUMMARY_MAX_CHARS);
+        assert!(!summary.contains("never reached"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Bounded best-k selection for similarity search.
+//!
+//! Keeps the `k` highest scores seen so far in a min-heap, so picking the top
+//! results is O(n log k) instead of sorting every scored document. Partial
+//! selections from different threads combine with `merge`, which is what the
+//! rayon `fold`/`reduce` scoring loops rely on.
+
+use std::cmp::{ Ordering, Reverse };
+use std::collections::BinaryHeap;
+
+struct Scored<T> {
+    score: f32,
+    item: T,
+}
+
+impl<T> PartialEq for Scored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.score.total_cmp(&other.score) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Scored<T> {}
+
+impl<T> PartialOrd for Scored<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Scored<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.total_cmp(&other.score)
+    }
+}
+
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<Scored<T>>>,
+}
+
+impl<T> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self { k, heap: BinaryHeap::with_capacity(k.saturating_add(1).min(1024)) }
+    }
+
+    /// Offer a candidate; NaN and infinite scores are ignored
+    pub fn push(&mut self, score: f32, item: T) {
+        if self.k == 0 || !score.is_finite() {
+            return;
+        }
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(Scored { score, item }));
+        } else if let Some(Reverse(lowest)) = self.heap.peek() {
+            if score > lowest.score {
+                self.heap.pop();
+                self.heap.push(Reverse(Scored { score, item }));
+            }
+        }
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        for Reverse(scored) in other.heap {
+            self.push(scored.score, scored.item);
+        }
+        self
+    }
+
+    /// The kept candidates, highest score first
+    pub fn into_sorted_vec(self) -> Vec<(f32, T)> {
+        // Ascending order of `Reverse` is descending order of score
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| (scored.score, scored.item))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_highest_scores_in_order() {
+        let mut top = TopK::new(3);
+        for (i, score) in [0.1, 0.9, f32::NAN, 0.5, 0.7, 0.3].into_iter().enumerate() {
+            top.push(score, i);
+        }
+        let kept: Vec<usize> = top.into_sorted_vec().into_iter().map(|(_, i)| i).collect();
+        assert_eq!(kept, vec![1, 4, 3]);
+    }
+
+    #[test]
+    fn test_merge_matches_single_pass() {
+        let scores = [0.2, 0.8, 0.4, 0.95, 0.6, 0.1, 0.75];
+
+        let mut single = TopK::new(4);
+        let mut left = TopK::new(4);
+        let mut right = TopK::new(4);
+        for (i, score) in scores.into_iter().enumerate() {
+            single.push(score, i);
+            if i % 2 == 0 { left.push(score, i) } else { right.push(score, i) }
+        }
+
+        let merged: Vec<usize> = left.merge(right).into_sorted_vec().into_iter().map(|(_, i)| i).collect();
+        let expected: Vec<usize> = single.into_sorted_vec().into_iter().map(|(_, i)| i).collect();
+        assert_eq!(merged, expected);
+    }
+}
@@ -1,23 +1,128 @@
 use super::{Document, SearchResult, FileInfo, FileInfoSummary};
+use super::filter::SearchFilter;
+use super::quantization::{ l2_norm, Quantization, QuantizedVector };
+use super::embedding_matrix::{ self, EmbeddingMatrix };
+use super::hnsw;
+use super::top_k::TopK;
+use super::backend;
+use rayon::prelude::*;
+use serde::{ Deserialize, Serialize };
 use sled::Db;
+use sled::transaction::{ ConflictableTransactionError, Transactional };
 use nalgebra::DVector;
-use crate::paths;
+use std::path::Path;
+use std::sync::Arc;
+use crate::{ constants, paths, permissions, settings };
+use crate::state::AppState;
 
 // Database schema version for future migrations
 const DB_SCHEMA_VERSION: &str = "v1.0.0";
 
+/// Collection stored in the database's default tree; the one the UI uses
+pub const DEFAULT_COLLECTION: &str = "default";
+
+/// Key of the per-collection `CollectionConfig` inside the collection tree
+const COLLECTION_CONFIG_KEY: &str = "__collection_config__";
+
+/// Random token replaced on every change to the collection, so the
+/// embedding matrix can tell whether it still matches the documents
+const REVISION_KEY: &str = "__revision__";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CollectionConfig {
+    pub quantization: Quantization,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequantizeReport {
+    pub collection: String,
+    pub from: Quantization,
+    pub to: Quantization,
+    pub migrated: usize,
+    /// Documents without any embedding, left untouched
+    pub skipped: usize,
+}
+
+/// Clones share the open database and trees
+#[derive(Clone)]
 pub struct VectorStore {
     db: Db,
+    collection: String,
+    /// Documents of the open collection
+    tree: sled::Tree,
+    /// Quantized embeddings by document id; empty for unquantized collections
+    vectors: sled::Tree,
+    config: CollectionConfig,
 }
 
 impl VectorStore {
-    pub fn new() -> Result<Self, String> {
+    /// Open the database with the configured background flush interval
+    fn open_db(path: &Path) -> sled::Result<Db> {
+        let interval_ms = settings::current().rag.flush_interval_ms;
+        sled::Config::new()
+            .path(path)
+            .flush_every_ms(if interval_ms == 0 { None } else { Some(interval_ms) })
+            .open()
+    }
+
+    pub fn open_collection(name: &str) -> Result<Self, String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Invalid collection name: '{}'", name));
+        }
+
+        let db = Self::open_database()?;
+        let tree = if name == DEFAULT_COLLECTION {
+            (*db).clone()
+        } else {
+            db.open_tree(format!("collection::{}", name))
+                .map_err(|e| format!("Failed to open collection '{}': {}", name, e))?
+        };
+        let vectors = db.open_tree(format!("vectors::{}", name))
+            .map_err(|e| format!("Failed to open vectors of collection '{}': {}", name, e))?;
+
+        let config = match tree.get(COLLECTION_CONFIG_KEY) {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!(collection = %name, error = %e, "Invalid collection config, using defaults");
+                CollectionConfig::default()
+            }),
+            _ => CollectionConfig::default(),
+        };
+
+        let store = Self { db, collection: name.to_string(), tree, vectors, config };
+        if store.revision() == 0 {
+            // Collections written before revisions existed start with a fresh one
+            store.bump_revision()?;
+        }
+        Ok(store)
+    }
+
+    fn revision(&self) -> u64 {
+        match self.tree.get(REVISION_KEY) {
+            Ok(Some(bytes)) => decode_revision(&bytes),
+            _ => 0,
+        }
+    }
+
+    /// Mark the collection changed, returning its new revision
+    fn bump_revision(&self) -> Result<u64, String> {
+        let revision = new_revision();
+        self.tree.insert(REVISION_KEY, revision.to_be_bytes().to_vec())
+            .map_err(|e| format!("Failed to update collection revision: {}", e))?;
+        Ok(revision)
+    }
+
+    pub fn config(&self) -> &CollectionConfig {
+        &self.config
+    }
+
+    fn open_database() -> Result<Db, String> {
         let data_dir = paths::get_vector_store_path().map_err(|e| e.to_string())?;
         
         tracing::debug!(path = %data_dir.display(), "Opening vector store database");
         
         // Try to open the database, with retry for lock errors
-        let db = match sled::open(&data_dir) {
+        let db = match Self::open_db(&data_dir) {
             Ok(db) => {
                 tracing::debug!("Database opened successfully, validating schema");
                 // Check if we can deserialize existing data
@@ -43,7 +148,7 @@ impl VectorStore {
                     }
                     
                     tracing::info!("Creating fresh database after schema validation failure");
-                    sled::open(&data_dir)
+                    Self::open_db(&data_dir)
                         .map_err(|e| format!("Failed to create new database after schema migration: {}", e))?
                 }
             }
@@ -55,7 +160,7 @@ impl VectorStore {
                     tracing::warn!("Database is locked by another instance - this is normal for concurrent access");
                     // Retry opening after a short delay
                     std::thread::sleep(std::time::Duration::from_millis(50));
-                    sled::open(&data_dir)
+                    Self::open_db(&data_dir)
                         .map_err(|retry_err| {
                             tracing::error!(error = %retry_err, "Failed to open database after retry");
                             format!("Database is busy, please try again: {}", retry_err)
@@ -79,7 +184,7 @@ impl VectorStore {
                     
                     // Try to open a fresh database
                     tracing::info!("Creating fresh database after corruption");
-                    sled::open(&data_dir)
+                    Self::open_db(&data_dir)
                         .map_err(|e| format!("Failed to create new vector store after corruption recovery: {}", e))?
                 }
             }
@@ -92,7 +197,7 @@ impl VectorStore {
             .map_err(|e| format!("Failed to flush schema version: {}", e))?;
         tracing::debug!("Schema version written and flushed");
         
-        Ok(Self { db })
+        Ok(db)
     }
     
     /// Validate that existing database entries can be deserialized with current Document schema
@@ -184,16 +289,175 @@ impl VectorStore {
         is_valid
     }
     
-    pub fn store_document(&self, document: &Document) -> Result<(), String> {
-        let key = document.id.as_bytes();
-        let value = bincode::serialize(document)
-            .map_err(|e| format!("Failed to serialize document: {}", e))?;
-        
-        self.db.insert(key, value)
-            .map_err(|e| format!("Failed to store document: {}", e))?;
-        
+    /// Store documents as one atomic sled batch: after a crash either all of
+    /// them are present or none are. Embeddings are quantized according to
+    /// the collection config.
+    pub fn store_documents_batch(&self, documents: &[Document]) -> Result<(), String> {
+        self.write_documents(documents, self.config.quantization)
+    }
+
+    fn write_documents(&self, documents: &[Document], mode: Quantization) -> Result<(), String> {
+        // Pure inserts can be appended to the embedding matrix; overwrites make it stale
+        let appendable = mode == Quantization::None
+            && documents.iter().all(|doc| matches!(self.tree.contains_key(doc.id.as_bytes()), Ok(false)));
+        let revision = new_revision();
+
+        let mut doc_batch = sled::Batch::default();
+        let mut vector_batch = sled::Batch::default();
+
+        for document in documents {
+            let quantized = document.embedding
+                .as_deref()
+                .and_then(|embedding| QuantizedVector::quantize(embedding, mode));
+
+            let value = match quantized {
+                Some(quantized) => {
+                    // The full embedding is dropped; only the compact form is kept
+                    let mut stripped = document.clone();
+                    stripped.embedding = None;
+                    let vector = bincode::serialize(&quantized)
+                        .map_err(|e| format!("Failed to serialize embedding: {}", e))?;
+                    vector_batch.insert(document.id.as_bytes(), vector);
+                    bincode::serialize(&stripped)
+                }
+                None => {
+                    vector_batch.remove(document.id.as_bytes());
+                    bincode::serialize(document)
+                }
+            }.map_err(|e| format!("Failed to serialize document: {}", e))?;
+
+            doc_batch.insert(document.id.as_bytes(), value);
+        }
+
+        let previous = (&self.tree, &self.vectors)
+            .transaction(|(tree, vectors)| {
+                let previous = tree.get(REVISION_KEY)?.map_or(0, |bytes| decode_revision(&bytes));
+                tree.apply_batch(&doc_batch)?;
+                vectors.apply_batch(&vector_batch)?;
+                tree.insert(REVISION_KEY, revision.to_be_bytes().to_vec())?;
+                Ok::<u64, ConflictableTransactionError<()>>(previous)
+            })
+            .map_err(|e| format!("Failed to store documents: {:?}", e))?;
+
+        if appendable {
+            let rows: Vec<(&str, &[f32])> = documents
+                .iter()
+                .filter_map(|doc| Some((doc.id.as_str(), doc.embedding.as_deref()?)))
+                .collect();
+            if let Err(e) = embedding_matrix::append(&self.collection, previous, revision, &rows) {
+                tracing::debug!(collection = %self.collection, error = %e, "Embedding matrix append failed; it will be rebuilt");
+            }
+            hnsw::insert(&self.collection, previous, revision, &rows);
+        }
         Ok(())
     }
+
+    /// The collection's embedding matrix for `revision`, rebuilt from the
+    /// documents if it is stale. `None` if it cannot be built, e.g. for mixed
+    /// dimensions.
+    fn embedding_matrix(&self, revision: u64) -> Option<Arc<EmbeddingMatrix>> {
+        if let Some(matrix) = embedding_matrix::load(&self.collection, revision) {
+            return Some(matrix);
+        }
+
+        let rows = self.tree.iter().filter_map(|item_result| {
+            let (key, value) = item_result.ok()?;
+            if key.starts_with(b"__") {
+                return None;
+            }
+            let document = bincode::deserialize::<Document>(&value).ok()?;
+            Some((document.id, document.embedding?))
+        });
+        if let Err(e) = embedding_matrix::rebuild(&self.collection, revision, rows) {
+            tracing::debug!(collection = %self.collection, error = %e, "Falling back to scanning documents");
+            return None;
+        }
+        embedding_matrix::load(&self.collection, revision)
+    }
+
+    fn quantized_vector(&self, id: &str) -> Option<QuantizedVector> {
+        let bytes = self.vectors.get(id.as_bytes()).ok()??;
+        bincode::deserialize(&bytes).ok()
+    }
+
+    /// Similarity between the query and a stored document, from its full
+    /// embedding or, in quantized collections, from its compact vector
+    fn score_document(&self, document: &Document, query_embedding: &[f32], query_norm: f32) -> Option<f32> {
+        let score = match &document.embedding {
+            Some(embedding) => cosine_similarity(query_embedding, embedding),
+            None => self.quantized_vector(&document.id)?.cosine(query_embedding, query_norm),
+        };
+        score.is_finite().then_some(score)
+    }
+
+    /// Convert every embedding in the collection to `target`.
+    ///
+    /// The new mode is recorded first, so documents written while the
+    /// migration runs already use it. If the migration is interrupted,
+    /// running it again converts the remaining documents, but it cannot
+    /// restore precision: documents already converted are read back in the
+    /// new mode, so whatever a lossy mode dropped stays lost even if a
+    /// lossless mode is picked next. Only re-indexing brings back full
+    /// embeddings.
+    pub fn requantize(&mut self, target: Quantization) -> Result<RequantizeReport, String> {
+        let from = self.config.quantization;
+        if from == Quantization::Binary && target != Quantization::Binary {
+            return Err(
+                "Binary embeddings only keep signs and cannot be converted back; re-index the documents instead".to_string()
+            );
+        }
+
+        self.config.quantization = target;
+        let config_bytes = serde_json::to_vec(&self.config)
+            .map_err(|e| format!("Failed to serialize collection config: {}", e))?;
+        self.tree.insert(COLLECTION_CONFIG_KEY, config_bytes)
+            .map_err(|e| format!("Failed to save collection config: {}", e))?;
+
+        let mut migrated = 0;
+        let mut skipped = 0;
+        let mut batch = Vec::with_capacity(constants::VECTOR_STORE_WRITE_BATCH_SIZE);
+
+        for item_result in self.tree.iter() {
+            let Ok((key, value)) = item_result else { continue };
+            if key.starts_with(b"__") {
+                continue;
+            }
+            let Ok(mut document) = bincode::deserialize::<Document>(&value) else { continue };
+
+            if document.embedding.is_none() {
+                match self.quantized_vector(&document.id) {
+                    Some(quantized) => document.embedding = Some(quantized.dequantize()),
+                    None => {
+                        skipped += 1;
+                        continue;
+                    }
+                }
+            }
+
+            batch.push(document);
+            if batch.len() >= constants::VECTOR_STORE_WRITE_BATCH_SIZE {
+                self.write_documents(&batch, target)?;
+                migrated += batch.len();
+                batch.clear();
+            }
+        }
+
+        if !batch.is_empty() {
+            self.write_documents(&batch, target)?;
+            migrated += batch.len();
+        }
+        self.flush()?;
+
+        tracing::info!(collection = %self.collection, from = ?from, to = ?target, migrated, skipped, "Re-quantized collection");
+
+        Ok(RequantizeReport {
+            collection: self.collection.clone(),
+            from,
+            to: target,
+            migrated,
+            skipped,
+        })
+    }
     
     pub fn flush(&self) -> Result<(), String> {
         self.db.flush()
@@ -204,54 +468,126 @@ impl VectorStore {
     
     
     pub fn search_similar(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>, String> {
-        let mut results = Vec::new();
+        if self.config.quantization != Quantization::None {
+            return self.search_quantized(query_embedding, limit);
+        }
+        if let Some(results) = self.search_ann(query_embedding, limit) {
+            return Ok(results);
+        }
+        if let Some(results) = self.search_matrix(query_embedding, limit) {
+            return Ok(results);
+        }
+
+        Ok(self.search_where(query_embedding, limit, |_| true))
+    }
+    
+    /// Like `search_similar`, scoring only the chunks the filter accepts
+    pub fn search_similar_filtered(
+        &self,
+        query_embedding: &[f32],
+        filter: &SearchFilter,
+        limit: usize
+    ) -> Result<Vec<SearchResult>, String> {
+        if filter.is_empty() {
+            return self.search_similar(query_embedding, limit);
+        }
+        Ok(self.search_where(query_embedding, limit, |document| filter.matches(document)))
+    }
+
+    /// Score every document `accept` lets through and keep the best `limit`
+    fn search_where(&self, query_embedding: &[f32], limit: usize, accept: impl Fn(&Document) -> bool + Sync) -> Vec<SearchResult> {
+        let query_norm = l2_norm(query_embedding);
         
-        for item_result in self.db.iter() {
-            match item_result {
-                Ok((key, value)) => {
-                    // Skip metadata keys
-                    if key.starts_with(b"__") {
-                        continue;
-                    }
-                    
-                    match bincode::deserialize::<Document>(&value) {
-                        Ok(document) => {
-                            if let Some(embedding) = &document.embedding {
-                                let similarity = cosine_similarity(query_embedding, embedding);
-                                // Only add if similarity is valid (not NaN)
-                                if similarity.is_finite() {
-                                    results.push(SearchResult {
-                                        document,
-                                        score: similarity,
-                                        rerank_score: None,
-                                    });
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            // Skip corrupted documents
-                            continue;
+        // Read serially (sled iteration is sequential), deserialize and score in parallel
+        let values: Vec<sled::IVec> = self.tree
+            .iter()
+            .filter_map(|item_result| item_result.ok())
+            .filter(|(key, _)| !key.starts_with(b"__")) // Skip metadata keys
+            .map(|(_, value)| value)
+            .collect();
+        
+        let top = values
+            .par_iter()
+            .fold(|| TopK::new(limit), |mut top, value| {
+                // Corrupted documents are skipped
+                if let Ok(document) = bincode::deserialize::<Document>(value) {
+                    if accept(&document) {
+                        if let Some(similarity) = self.score_document(&document, query_embedding, query_norm) {
+                            top.push(similarity, document);
                         }
                     }
                 }
-                Err(_) => {
-                    // Skip database iteration errors
-                    continue;
+                top
+            })
+            .reduce(|| TopK::new(limit), TopK::merge);
+        
+        top
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(score, document)| SearchResult { document, score, rerank_score: None })
+            .collect()
+    }
+    
+    /// Walk the collection's HNSW index if it is current and load the documents found
+    fn search_ann(&self, query_embedding: &[f32], limit: usize) -> Option<Vec<SearchResult>> {
+        let query_unit = embedding_matrix::normalize(query_embedding)?;
+        let scored = hnsw::search(&self.collection, self.revision(), &query_unit, limit)?;
+        Some(self.load_scored(scored.iter().map(|(id, score)| (id.as_str(), *score))))
+    }
+
+    /// Score every row of the embedding matrix and only load the top documents.
+    /// Large collections get their HNSW index built for later searches.
+    fn search_matrix(&self, query_embedding: &[f32], limit: usize) -> Option<Vec<SearchResult>> {
+        let revision = self.revision();
+        let matrix = self.embedding_matrix(revision)?;
+        if matrix.rows() > 0 && matrix.dim() != query_embedding.len() {
+            return None;
+        }
+        let query_unit = embedding_matrix::normalize(query_embedding)?;
+        hnsw::build_in_background(&self.collection, revision, Arc::clone(&matrix));
+
+        let scored = matrix.top_k(&query_unit, limit);
+        Some(self.load_scored(scored.into_iter().map(|(row, score)| (matrix.id(row), score))))
+    }
+
+    /// Documents of scored ids, in the given order; missing ones are skipped
+    fn load_scored<'a>(&self, scored: impl Iterator<Item = (&'a str, f32)>) -> Vec<SearchResult> {
+        scored
+            .filter_map(|(id, score)| {
+                let value = self.tree.get(id.as_bytes()).ok()??;
+                let document = bincode::deserialize::<Document>(&value).ok()?;
+                Some(SearchResult { document, score, rerank_score: None })
+            })
+            .collect()
+    }
+
+    /// Score the compact vectors directly and only load the top documents
+    fn search_quantized(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>, String> {
+        let query_norm = l2_norm(query_embedding);
+        let entries: Vec<(sled::IVec, sled::IVec)> = self.vectors
+            .iter()
+            .filter_map(|item_result| item_result.ok())
+            .collect();
+
+        let scored = entries
+            .par_iter()
+            .fold(|| TopK::new(limit), |mut top, (key, value)| {
+                if let Ok(quantized) = bincode::deserialize::<QuantizedVector>(value) {
+                    top.push(quantized.cosine(query_embedding, query_norm), key.clone());
+                }
+                top
+            })
+            .reduce(|| TopK::new(limit), TopK::merge)
+            .into_sorted_vec();
+
+        let mut results = Vec::with_capacity(scored.len());
+        for (score, key) in scored {
+            if let Ok(Some(value)) = self.tree.get(&key) {
+                if let Ok(document) = bincode::deserialize::<Document>(&value) {
+                    results.push(SearchResult { document, score, rerank_score: None });
                 }
             }
         }
-        
-        // Sort by similarity score (highest first) with safe comparison
-        results.sort_by(|a, b| {
-            match (a.score.is_finite(), b.score.is_finite()) {
-                (true, true) => b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal),
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                (false, false) => std::cmp::Ordering::Equal,
-            }
-        });
-        results.truncate(limit);
-        
         Ok(results)
     }
     
@@ -261,87 +597,66 @@ impl VectorStore {
         file_paths: &[String],
         limit: usize
     ) -> Result<Vec<SearchResult>, String> {
-        let mut results = Vec::new();
-        
         tracing::debug!(
             file_count = file_paths.len(),
             files = ?file_paths,
             "Searching for similar documents in specific files"
         );
         
-        for item_result in self.db.iter() {
-            match item_result {
-                Ok((key, value)) => {
-                    // Skip metadata keys
-                    if key.starts_with(b"__") {
-                        continue;
-                    }
-                    
-                    match bincode::deserialize::<Document>(&value) {
-                        Ok(document) => {
-                            // Only include documents from the specified files
-                            if !file_paths.contains(&document.file_path) {
-                                continue;
-                            }
-                            
-                            if let Some(embedding) = &document.embedding {
-                                let similarity = cosine_similarity(query_embedding, embedding);
-                                // Only add if similarity is valid (not NaN)
-                                if similarity.is_finite() {
-                                    results.push(SearchResult {
-                                        document,
-                                        score: similarity,
-                                        rerank_score: None,
-                                    });
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            // Skip corrupted documents
-                            continue;
-                        }
-                    }
-                }
-                Err(_) => {
-                    // Skip database iteration errors
-                    continue;
-                }
-            }
-        }
+        let results = self.search_where(query_embedding, limit, |document| file_paths.contains(&document.file_path));
         
         tracing::debug!(
             results_found = results.len(),
             "Found documents in specified files"
         );
         
-        // Sort by similarity score (highest first) with safe comparison
-        results.sort_by(|a, b| {
-            match (a.score.is_finite(), b.score.is_finite()) {
-                (true, true) => b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal),
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                (false, false) => std::cmp::Ordering::Equal,
-            }
-        });
-        results.truncate(limit);
-        
         Ok(results)
     }
     
     pub fn delete_document(&self, id: &str) -> Result<bool, String> {
         let key = id.as_bytes();
-        let result = self.db.remove(key)
+        let previous = self.revision();
+        let result = self.tree.remove(key)
             .map_err(|e| format!("Failed to delete document: {}", e))?;
+        self.vectors.remove(key)
+            .map_err(|e| format!("Failed to delete document embedding: {}", e))?;
+        if result.is_some() {
+            let revision = self.bump_revision()?;
+            hnsw::remove(&self.collection, previous, revision, &[id.to_string()]);
+        }
         
         Ok(result.is_some())
     }
     
+    /// Iterate the collection's documents in key order, skipping corrupted entries
+    pub fn documents(&self) -> impl Iterator<Item = Document> + '_ {
+        self.tree.iter().filter_map(|item_result| {
+            let (key, value) = item_result.ok()?;
+            if key.starts_with(b"__") {
+                return None;
+            }
+            bincode::deserialize::<Document>(&value).ok()
+        })
+    }
+
+    /// The document's embedding, dequantized if the collection stores a compact form
+    pub fn embedding_of(&self, document: &Document) -> Option<Vec<f32>> {
+        match &document.embedding {
+            Some(embedding) => Some(embedding.clone()),
+            None => Some(self.quantized_vector(&document.id)?.dequantize()),
+        }
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+    
     pub fn list_all_documents(&self) -> Result<Vec<Document>, String> {
         let mut documents = Vec::new();
         let mut errors = Vec::new();
         
         // Use a safer iteration approach
-        for item_result in self.db.iter() {
+        for item_result in self.tree.iter() {
             match item_result {
                 Ok((key, value)) => {
                     // Skip metadata keys
@@ -382,7 +697,7 @@ impl VectorStore {
         // Use a safer count method that actually iterates and counts valid documents
         let mut count = 0;
         
-        for item_result in self.db.iter() {
+        for item_result in self.tree.iter() {
             match item_result {
                 Ok((key, value)) => {
                     // Skip metadata keys
@@ -406,94 +721,47 @@ impl VectorStore {
     }
     
     pub fn clear_all(&self) -> Result<(), String> {
-        self.db.clear()
+        self.tree.clear()
             .map_err(|e| format!("Failed to clear database: {}", e))?;
+        self.vectors.clear()
+            .map_err(|e| format!("Failed to clear embeddings: {}", e))?;
+        // Keep the collection's settings across a clear
+        if self.config.quantization != Quantization::None {
+            let config_bytes = serde_json::to_vec(&self.config)
+                .map_err(|e| format!("Failed to serialize collection config: {}", e))?;
+            self.tree.insert(COLLECTION_CONFIG_KEY, config_bytes)
+                .map_err(|e| format!("Failed to save collection config: {}", e))?;
+        }
+        hnsw::forget(&self.collection);
+        self.bump_revision().map(|_| ())
+    }
+
+    /// Remove a named collection's trees and cached indexes. The default
+    /// collection is the database's own tree and can only be cleared.
+    pub fn drop_collection(&self) -> Result<(), String> {
+        if self.collection == DEFAULT_COLLECTION {
+            return Err("The default collection cannot be dropped".to_string());
+        }
+        self.db.drop_tree(format!("collection::{}", self.collection))
+            .map_err(|e| format!("Failed to drop collection '{}': {}", self.collection, e))?;
+        self.db.drop_tree(format!("vectors::{}", self.collection))
+            .map_err(|e| format!("Failed to drop vectors of collection '{}': {}", self.collection, e))?;
+        hnsw::forget(&self.collection);
+        embedding_matrix::forget(&self.collection);
         Ok(())
     }
     
     pub fn list_files(&self) -> Result<Vec<FileInfo>, String> {
-        let mut file_map: std::collections::HashMap<String, FileInfo> = std::collections::HashMap::new();
-        
-        for item_result in self.db.iter() {
-            match item_result {
-                Ok((key, value)) => {
-                    // Skip metadata keys
-                    if key.starts_with(b"__") {
-                        continue;
-                    }
-                    
-                    match bincode::deserialize::<Document>(&value) {
-                        Ok(document) => {
-                            // Safe key generation
-                            let file_key = format!("{}:{}", 
-                                document.file_path.trim(),
-                                document.file_type.trim()
-                            );
-                            
-                            match file_map.get_mut(&file_key) {
-                                Some(file_info) => {
-                                    file_info.chunk_count += 1;
-                                    file_info.documents.push(document);
-                                }
-                                None => {
-                                    // Safe file name extraction
-                                    let safe_file_name = if document.file_path.is_empty() {
-                                        document.title.clone()
-                                    } else {
-                                        match std::path::Path::new(&document.file_path).file_name() {
-                                            Some(os_str) => {
-                                                match os_str.to_str() {
-                                                    Some(name) => name.to_string(),
-                                                    None => document.title.clone(),
-                                                }
-                                            }
-                                            None => document.title.clone(),
-                                        }
-                                    };
-                                    
-                                    let file_info = FileInfo {
-                                        file_path: document.file_path.clone(),
-                                        file_name: safe_file_name,
-                                        file_type: document.file_type.clone(),
-                                        chunk_count: 1,
-                                        created_at: document.created_at,
-                                        documents: vec![document],
-                                    };
-                                    file_map.insert(file_key, file_info);
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            // Skip corrupted documents
-                            continue;
-                        }
-                    }
-                }
-                Err(_) => {
-                    // Skip database iteration errors
-                    continue;
-                }
-            }
-        }
-        
-        let mut files: Vec<FileInfo> = file_map.into_values().collect();
-        
-        // Safe sorting with error handling
-        files.sort_by(|a, b| {
-            match (a.created_at, b.created_at) {
-                (a_time, b_time) => b_time.cmp(&a_time),
-            }
-        });
-        
-        Ok(files)
+        Ok(group_by_file(self.documents()))
     }
     
     pub fn delete_file(&self, file_path: &str) -> Result<usize, String> {
         let mut deleted_count = 0;
         let mut keys_to_delete = Vec::new();
+        let previous = self.revision();
         
         // Find all documents for this file
-        for item_result in self.db.iter() {
+        for item_result in self.tree.iter() {
             match item_result {
                 Ok((key, value)) => {
                     // Skip metadata keys
@@ -521,16 +789,100 @@ impl VectorStore {
         }
         
         // Delete all found keys
+        let mut deleted_ids = Vec::new();
         for key in keys_to_delete {
-            if let Ok(Some(_)) = self.db.remove(&key) {
+            if let Ok(Some(_)) = self.tree.remove(&key) {
                 deleted_count += 1;
+                deleted_ids.push(String::from_utf8_lossy(&key).into_owned());
             }
+            let _ = self.vectors.remove(&key);
+        }
+        if deleted_count > 0 {
+            let revision = self.bump_revision()?;
+            hnsw::remove(&self.collection, previous, revision, &deleted_ids);
         }
         
         Ok(deleted_count)
     }
 }
 
+/// Load the default collection's embedding matrix and start building its
+/// HNSW index, so the first searches of a large collection need not scan it
+pub fn warm_ann_index() -> Result<(), String> {
+    let rag = settings::current().rag;
+    if rag.backend != settings::VectorBackendKind::Sled || !rag.hnsw.enabled {
+        return Ok(());
+    }
+    let store = VectorStore::open_collection(DEFAULT_COLLECTION)?;
+    if store.config.quantization != Quantization::None {
+        return Ok(());
+    }
+    let revision = store.revision();
+    if let Some(matrix) = store.embedding_matrix(revision) {
+        hnsw::build_in_background(&store.collection, revision, matrix);
+    }
+    Ok(())
+}
+
+/// Group document chunks into the files they came from, newest file first
+pub fn group_by_file(documents: impl IntoIterator<Item = Document>) -> Vec<FileInfo> {
+    let mut file_map: std::collections::HashMap<String, FileInfo> = std::collections::HashMap::new();
+    
+    for document in documents {
+        // Safe key generation
+        let file_key = format!("{}:{}", 
+            document.file_path.trim(),
+            document.file_type.trim()
+        );
+        
+        match file_map.get_mut(&file_key) {
+            Some(file_info) => {
+                file_info.chunk_count += 1;
+                file_info.documents.push(document);
+            }
+            None => {
+                // Safe file name extraction
+                let safe_file_name = if document.file_path.is_empty() {
+                    document.title.clone()
+                } else {
+                    match std::path::Path::new(&document.file_path).file_name() {
+                        Some(os_str) => {
+                            match os_str.to_str() {
+                                Some(name) => name.to_string(),
+                                None => document.title.clone(),
+                            }
+                        }
+                        None => document.title.clone(),
+                    }
+                };
+                
+                let file_info = FileInfo {
+                    file_path: document.file_path.clone(),
+                    file_name: safe_file_name,
+                    file_type: document.file_type.clone(),
+                    chunk_count: 1,
+                    created_at: document.created_at,
+                    documents: vec![document],
+                };
+                file_map.insert(file_key, file_info);
+            }
+        }
+    }
+    
+    let mut files: Vec<FileInfo> = file_map.into_values().collect();
+    files.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    files
+}
+
+fn decode_revision(bytes: &[u8]) -> u64 {
+    <[u8; 8]>::try_from(bytes).map_or(0, u64::from_be_bytes)
+}
+
+fn new_revision() -> u64 {
+    // Never 0, which stands for "no revision recorded"
+    uuid::Uuid::new_v4().as_u64_pair().0 | 1
+}
+
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
         return 0.0;
@@ -557,57 +909,72 @@ pub async fn store_documents(documents: Vec<Document>) -> Result<String, String>
     }
 
     tracing::info!(count = documents.len(), "Storing documents to vector store");
-    let vector_store = VectorStore::new()?;
+    let vector_store = backend::open_default()?;
     
-    for document in &documents {
-        vector_store.store_document(document)?;
+    for batch in documents.chunks(constants::VECTOR_STORE_WRITE_BATCH_SIZE) {
+        vector_store.store_documents(batch).await?;
     }
     
-    // Flush to ensure data is written to disk immediately
-    vector_store.flush()?;
+    // Flush once at the end so the whole upload is durable before returning
+    vector_store.flush().await?;
     tracing::info!(count = documents.len(), "Documents stored and flushed successfully");
     
     Ok(format!("Successfully stored {} documents", documents.len()))
 }
 
 #[tauri::command]
-pub async fn search_documents(query_embedding: Vec<f32>, limit: Option<usize>) -> Result<Vec<SearchResult>, String> {
-    let vector_store = VectorStore::new()?;
+pub async fn search_documents(
+    query_embedding: Vec<f32>,
+    limit: Option<usize>,
+    filter: Option<SearchFilter>
+) -> Result<Vec<SearchResult>, String> {
+    let vector_store = backend::open_default()?;
     let search_limit = limit.unwrap_or(10);
     
-    vector_store.search_similar(&query_embedding, search_limit)
+    match filter.filter(|filter| !filter.is_empty()) {
+        Some(filter) => vector_store.search_similar_filtered(&query_embedding, &filter, search_limit).await,
+        None => vector_store.search_similar(&query_embedding, search_limit).await,
+    }
 }
 
 #[tauri::command]
 pub async fn get_all_documents() -> Result<Vec<Document>, String> {
-    let vector_store = VectorStore::new()?;
-    vector_store.list_all_documents()
+    let vector_store = backend::open_default()?;
+    vector_store.list_documents().await
 }
 
 #[tauri::command]
 pub async fn delete_document_by_id(id: String) -> Result<bool, String> {
-    let vector_store = VectorStore::new()?;
-    vector_store.delete_document(&id)
+    let vector_store = backend::open_default()?;
+    vector_store.delete_document(&id).await
 }
 
 #[tauri::command]
 pub async fn get_document_count() -> Result<usize, String> {
-    let vector_store = VectorStore::new()?;
-    vector_store.count_documents()
+    let vector_store = backend::open_default()?;
+    vector_store.count_documents().await
 }
 
 #[tauri::command]
-pub async fn clear_all_documents() -> Result<String, String> {
-    let vector_store = VectorStore::new()?;
-    vector_store.clear_all()?;
+pub async fn clear_all_documents(
+    state: tauri::State<'_, AppState>,
+    window: tauri::Window,
+    confirmation_token: Option<String>
+) -> Result<String, String> {
+    permissions::authorize(&state, window.label(), "clear_all_documents", confirmation_token.as_deref())?;
+    let vector_store = backend::open_default()?;
+    vector_store.clear_all().await?;
+    if let Err(e) = super::graph::KnowledgeGraph::open().and_then(|graph| graph.clear()) {
+        tracing::warn!(error = %e, "Failed to clear knowledge graph");
+    }
     Ok("All documents cleared successfully".to_string())
 }
 
 #[tauri::command]
 pub async fn get_all_files() -> Result<Vec<FileInfoSummary>, String> {
     tracing::debug!("Getting all files from vector store");
-    let vector_store = VectorStore::new()?;
-    let files = vector_store.list_files()?;
+    let vector_store = backend::open_default()?;
+    let files = vector_store.list_files().await?;
     
     tracing::info!(file_count = files.len(), "Retrieved files from vector store");
     
@@ -627,58 +994,73 @@ pub async fn get_all_files() -> Result<Vec<FileInfoSummary>, String> {
 
 #[tauri::command]
 pub async fn get_file_chunks(#[allow(non_snake_case)] filePath: String) -> Result<Vec<Document>, String> {
-    let vector_store = VectorStore::new()?;
-    
-    let mut chunks = Vec::new();
-    
-    for item_result in vector_store.db.iter() {
-        match item_result {
-            Ok((key, value)) => {
-                // Skip metadata keys
-                if key.starts_with(b"__") {
-                    continue;
-                }
-                
-                match bincode::deserialize::<Document>(&value) {
-                    Ok(document) => {
-                        if document.file_path == filePath {
-                            chunks.push(document);
-                        }
-                    }
-                    Err(_) => {
-                        // Skip corrupted documents
-                        continue;
-                    }
-                }
-            }
-            Err(_) => {
-                // Skip database iteration errors
-                continue;
-            }
-        }
-    }
-    
-    // Sort by chunk index
-    chunks.sort_by(|a, b| {
-        match (a.chunk_index, b.chunk_index) {
-            (Some(a_idx), Some(b_idx)) => a_idx.cmp(&b_idx),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => a.created_at.cmp(&b.created_at),
-        }
-    });
-    
-    Ok(chunks)
+    let vector_store = backend::open_default()?;
+    vector_store.file_chunks(&filePath).await
 }
 
 #[tauri::command]
 pub async fn delete_file_by_path(#[allow(non_snake_case)] filePath: String) -> Result<usize, String> {
-    let vector_store = VectorStore::new()?;
-    vector_store.delete_file(&filePath)
+    let vector_store = backend::open_default()?;
+    let removed = vector_store.delete_file(&filePath).await?;
+    if let Err(e) = super::graph::KnowledgeGraph::open().and_then(|graph| graph.remove_file(&filePath)) {
+        tracing::warn!(error = %e, file = %filePath, "Failed to remove file from knowledge graph");
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn get_collection_config(collection: Option<String>) -> Result<CollectionConfig, String> {
+    super::backend::require_sled("Collection configuration")?;
+    let name = collection.unwrap_or_else(|| DEFAULT_COLLECTION.to_string());
+    tokio::task::spawn_blocking(move || Ok(VectorStore::open_collection(&name)?.config().clone()))
+        .await
+        .map_err(|e| format!("Vector store task failed: {}", e))?
 }
 
+/// Switch a collection's embedding storage format, converting existing embeddings
 #[tauri::command]
-pub async fn clear_vector_store() -> Result<String, String> {
+pub async fn requantize_collection(
+    collection: Option<String>,
+    quantization: Quantization
+) -> Result<RequantizeReport, String> {
+    super::backend::require_sled("Quantization")?;
+    let name = collection.unwrap_or_else(|| DEFAULT_COLLECTION.to_string());
+    log_operation_start!("Re-quantize collection", collection = %name, target = ?quantization);
+
+    // Rewrites the whole collection; keep it off the async runtime
+    let result = {
+        let name = name.clone();
+        tokio::task::spawn_blocking(move || VectorStore::open_collection(&name)?.requantize(quantization))
+            .await
+            .map_err(|e| format!("Vector store task failed: {}", e))
+            .and_then(|result| result)
+    };
+    let report = result.map_err(|e| {
+        log_operation_error!("Re-quantize collection", &e, collection = %name);
+        e
+    })?;
+
+    log_operation_success!("Re-quantize collection", migrated = report.migrated);
+    Ok(report)
+}
+
+/// Delete every collection of the configured backend
+#[tauri::command]
+pub async fn clear_vector_store(
+    state: tauri::State<'_, AppState>,
+    window: tauri::Window,
+    confirmation_token: Option<String>
+) -> Result<String, String> {
+    permissions::authorize(&state, window.label(), "clear_vector_store", confirmation_token.as_deref())?;
+    let rag = settings::current().rag;
+    if rag.backend == settings::VectorBackendKind::Qdrant {
+        tracing::info!("Clearing Qdrant collections");
+        let known = [DEFAULT_COLLECTION, crate::journal::JOURNAL_COLLECTION, super::sessions::SESSIONS_COLLECTION];
+        let dropped = super::qdrant::drop_all(&rag.qdrant, &known).await?;
+        tracing::info!(collections = dropped, "Qdrant collections cleared");
+        return Ok(format!("Removed {} Qdrant collections", dropped));
+    }
+
     tracing::info!("Clearing vector store database");
     
     let data_dir = paths::get_vector_store_path().map_err(|e| e.to_string())?;
@@ -686,6 +1068,8 @@ pub async fn clear_vector_store() -> Result<String, String> {
     if data_dir.exists() {
         std::fs::remove_dir_all(&data_dir)
             .map_err(|e| format!("Failed to remove vector store: {}", e))?;
+        embedding_matrix::remove_all();
+        hnsw::remove_all();
         
         tracing::info!("Vector store database cleared successfully");
         Ok("Vector store cleared successfully".to_string())
@@ -0,0 +1,474 @@
+//! Research mode: answer a question from the web, with citations.
+//!
+//! `research_query` plans search queries (the question itself, plus
+//! follow-up queries from the model at higher depths), searches the web,
+//! fetches the top pages and indexes them into a collection of their own.
+//! The excerpts most relevant to the question are numbered by page and the
+//! model writes an answer citing them as `[n]`; the answer is split into
+//! claims with the sources each one cites. Each step is emitted as a
+//! `research-step` event, and the collection and staged pages are removed
+//! once the answer is written (or the run fails). `cancel_operation` with
+//! the research id stops a run between steps.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+
+use crate::cancellation::{ self, OperationGuard, OperationKind };
+use crate::rag::search::SearchService;
+use crate::rag::{ backend, ingest };
+use crate::{ temp_files, web };
+
+const MAX_DEPTH: u32 = 3;
+const PAGES_PER_QUERY: usize = 3;
+const EXCERPTS_PER_QUERY: usize = 4;
+const MAX_EXCERPTS: usize = 12;
+const RESEARCH_CANCELLED: &str = "Research cancelled";
+
+const PLAN_PROMPT: &str = "You plan web searches for a research question. \
+Reply with search engine queries that together cover the parts of the question, one per line, \
+without numbering or any other text.";
+
+const ANSWER_PROMPT: &str = "You answer a research question using only the numbered sources given. \
+Cite the source of every claim with its number in square brackets, e.g. [2] or [1][3], \
+right after the claim. If the sources do not answer part of the question, say so instead of guessing.";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum ResearchStep {
+    Planned { queries: Vec<String> },
+    Searched { query: String, results: usize },
+    Fetched { url: String, title: String },
+    FetchFailed { url: String, error: String },
+    Indexed { url: String, chunks: usize },
+    Retrieved { excerpts: usize, sources: usize },
+    Synthesizing,
+}
+
+/// Payload of `research-step` events
+#[derive(Debug, Clone, Serialize)]
+struct ResearchEvent<'a> {
+    research_id: &'a str,
+    #[serde(flatten)]
+    step: ResearchStep,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchSource {
+    /// Number the answer cites it by
+    pub index: usize,
+    pub url: String,
+    pub title: String,
+    pub cited: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResearchClaim {
+    pub text: String,
+    /// Indexes into `sources`; empty for uncited text
+    pub sources: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchResult {
+    pub research_id: String,
+    pub question: String,
+    pub answer: String,
+    pub claims: Vec<ResearchClaim>,
+    pub sources: Vec<ResearchSource>,
+    pub elapsed_ms: u64,
+}
+
+/// A fetched page staged for indexing
+struct Page {
+    url: String,
+    title: String,
+    file_path: String,
+}
+
+struct Research<'a> {
+    app: &'a AppHandle,
+    id: &'a str,
+    operation: OperationGuard,
+    client: Client<OpenAIConfig>,
+    model: String,
+}
+
+/// `line` without a leading `1.`, `2)`, `-`, `*` or `•`
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    let numbered = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if numbered.len() < line.len() && (numbered.starts_with('.') || numbered.starts_with(')')) {
+        return numbered[1..].trim();
+    }
+    line.trim_start_matches(['-', '*', '•']).trim()
+}
+
+/// Queries from the model's plan: one per line, list markers and quotes removed
+fn parse_queries(plan: &str, max: usize) -> Vec<String> {
+    let mut queries: Vec<String> = Vec::new();
+    for line in plan.lines() {
+        let query = strip_list_marker(line).trim_matches('"').trim();
+        if !query.is_empty() && !queries.iter().any(|q| q.eq_ignore_ascii_case(query)) {
+            queries.push(query.to_string());
+        }
+    }
+    queries.truncate(max);
+    queries
+}
+
+/// Text of `sentence` without its `[n]` markers, and the valid source numbers among them
+fn strip_citations(sentence: &str, source_count: usize) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(sentence.len());
+    let mut sources = Vec::new();
+    let mut rest = sentence;
+    while let Some(open) = rest.find('[') {
+        text.push_str(&rest[..open]);
+        let marker = rest[open + 1..].find(']').map(|close| &rest[open + 1..open + 1 + close]);
+        let numbers: Option<Vec<usize>> = marker.and_then(|marker| {
+            marker.split(',').map(|n| n.trim().parse().ok()).collect()
+        });
+        match (marker, numbers) {
+            (Some(marker), Some(numbers)) => {
+                for number in numbers {
+                    if (1..=source_count).contains(&number) && !sources.contains(&number) {
+                        sources.push(number);
+                    }
+                }
+                rest = &rest[open + marker.len() + 2..];
+            }
+            _ => {
+                text.push('[');
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    let mut text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    for punctuation in [".", ",", ";", ":", "!", "?"] {
+        text = text.replace(&format!(" {}", punctuation), punctuation);
+    }
+    (text, sources)
+}
+
+fn push_claim(claims: &mut Vec<ResearchClaim>, sentence: &str, source_count: usize) {
+    let (text, sources) = strip_citations(sentence, source_count);
+    if text.chars().any(char::is_alphanumeric) {
+        claims.push(ResearchClaim { text, sources });
+    } else if let Some(last) = claims.last_mut() {
+        // Markers after the full stop belong to the sentence before
+        for source in sources {
+            if !last.sources.contains(&source) {
+                last.sources.push(source);
+            }
+        }
+    }
+}
+
+/// Split an answer into sentences with the sources each one cites
+fn split_claims(answer: &str, source_count: usize) -> Vec<ResearchClaim> {
+    let mut claims = Vec::new();
+    for line in answer.lines() {
+        let mut sentence = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            sentence.push(c);
+            if matches!(c, '.' | '!' | '?') && chars.peek().map_or(true, |next| next.is_whitespace()) {
+                push_claim(&mut claims, &sentence, source_count);
+                sentence.clear();
+            }
+        }
+        push_claim(&mut claims, &sentence, source_count);
+    }
+    claims
+}
+
+impl Research<'_> {
+    fn emit(&self, step: ResearchStep) {
+        let _ = self.app.emit("research-step", ResearchEvent { research_id: self.id, step });
+    }
+
+    fn check_cancelled(&self) -> Result<(), String> {
+        if self.operation.is_cancelled() {
+            Err(RESEARCH_CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn complete(&self, system: &str, user: String, max_tokens: u32) -> Result<String, String> {
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(self.model.clone())
+            .messages(vec![
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .content(system)
+                    .build()
+                    .map_err(|e| format!("Failed to build system message: {}", e))?
+                    .into(),
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content(user)
+                    .build()
+                    .map_err(|e| format!("Failed to build user message: {}", e))?
+                    .into(),
+            ])
+            .temperature(0.2)
+            .max_tokens(max_tokens)
+            .build()
+            .map_err(|e| format!("Failed to build request: {}", e))?;
+
+        let response = tokio::select! {
+            _ = self.operation.cancelled() => return Err(RESEARCH_CANCELLED.to_string()),
+            response = self.client.chat().create(request) => response,
+        };
+        response
+            .map_err(|e| format!("Research request failed: {}", e))?
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .filter(|content| !content.trim().is_empty())
+            .ok_or_else(|| "The model returned an empty reply".to_string())
+    }
+
+    async fn plan(&self, question: &str, depth: u32) -> Result<Vec<String>, String> {
+        let mut queries = vec![question.to_string()];
+        if depth > 1 {
+            let extra = (depth - 1) as usize * 2;
+            let plan = self.complete(PLAN_PROMPT, format!("Question: {}\n\nWrite {} queries.", question, extra), 200).await?;
+            queries.extend(parse_queries(&plan, extra).into_iter().filter(|q| !q.eq_ignore_ascii_case(question)));
+        }
+        self.emit(ResearchStep::Planned { queries: queries.clone() });
+        Ok(queries)
+    }
+
+    /// Search each query and stage the pages found, skipping ones that fail to load
+    async fn gather(&self, queries: &[String], pages: &mut Vec<Page>) -> Result<(), String> {
+        for query in queries {
+            self.check_cancelled()?;
+            let results = match web::search(query, PAGES_PER_QUERY).await {
+                Ok(results) => results,
+                Err(e) => {
+                    log_warning!("Research search failed", query = %query, error = %e);
+                    Vec::new()
+                }
+            };
+            self.emit(ResearchStep::Searched { query: query.clone(), results: results.len() });
+
+            for result in results {
+                if pages.iter().any(|page| page.url == result.url) {
+                    continue;
+                }
+                self.check_cancelled()?;
+                let page = match web::fetch_page(&result.url).await {
+                    Ok(page) => page,
+                    Err(error) => {
+                        self.emit(ResearchStep::FetchFailed { url: result.url, error });
+                        continue;
+                    }
+                };
+                let title = if page.title == page.url { result.title } else { page.title };
+                let document = format!("# {}\n\nSource: {}\n\n{}\n", title, page.url, page.text);
+                let file_path = temp_files::stage(&format!("page-{}.md", pages.len() + 1), document.as_bytes())?;
+                self.emit(ResearchStep::Fetched { url: page.url.clone(), title: title.clone() });
+                pages.push(Page { url: page.url, title, file_path: file_path.to_string_lossy().to_string() });
+            }
+        }
+        Ok(())
+    }
+
+    async fn index(&self, pages: &[Page], collection: &str) -> Result<usize, String> {
+        let mut indexed = 0;
+        for page in pages {
+            self.check_cancelled()?;
+            match ingest::ingest_into(self.app, page.file_path.clone(), collection).await {
+                Ok(summary) => {
+                    indexed += 1;
+                    self.emit(ResearchStep::Indexed { url: page.url.clone(), chunks: summary.chunk_count });
+                }
+                Err(e) => log_warning!("Failed to index research page", url = %page.url, error = %e),
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Excerpts for the answer, numbered by page, and the pages as sources
+    async fn retrieve(&self, queries: &[String], pages: &[Page], collection: &str) -> Result<(String, Vec<ResearchSource>), String> {
+        let search = SearchService::for_collection(collection)?;
+        let mut excerpts: Vec<(String, String)> = Vec::new();
+        for query in queries {
+            self.check_cancelled()?;
+            // Reranking needs the reranker model; plain similarity does without
+            let results = match search.search(query, EXCERPTS_PER_QUERY, true).await {
+                Ok(results) => results,
+                Err(_) => search.search(query, EXCERPTS_PER_QUERY, false).await?,
+            };
+            for result in results {
+                if excerpts.len() < MAX_EXCERPTS && !excerpts.iter().any(|(_, content)| *content == result.document.content) {
+                    excerpts.push((result.document.file_path, result.document.content));
+                }
+            }
+        }
+
+        let mut sources: Vec<ResearchSource> = Vec::new();
+        let mut by_source: HashMap<usize, Vec<String>> = HashMap::new();
+        for (file_path, content) in excerpts {
+            let Some(page) = pages.iter().find(|page| page.file_path == file_path) else {
+                continue;
+            };
+            let index = match sources.iter().find(|source| source.url == page.url) {
+                Some(source) => source.index,
+                None => {
+                    sources.push(ResearchSource {
+                        index: sources.len() + 1,
+                        url: page.url.clone(),
+                        title: page.title.clone(),
+                        cited: false,
+                    });
+                    sources.len()
+                }
+            };
+            by_source.entry(index).or_default().push(content);
+        }
+        self.emit(ResearchStep::Retrieved {
+            excerpts: by_source.values().map(Vec::len).sum(),
+            sources: sources.len(),
+        });
+
+        let context = sources
+            .iter()
+            .map(|source| format!("[{}] {} ({})\n{}", source.index, source.title, source.url, by_source[&source.index].join("\n...\n")))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Ok((context, sources))
+    }
+
+    async fn run(&self, question: &str, depth: u32, collection: &str, pages: &mut Vec<Page>) -> Result<(String, Vec<ResearchSource>), String> {
+        let queries = self.plan(question, depth).await?;
+        self.gather(&queries, pages).await?;
+        if pages.is_empty() {
+            return Err("No web pages could be fetched for this question".to_string());
+        }
+        if self.index(pages, collection).await? == 0 {
+            return Err("None of the fetched pages could be indexed".to_string());
+        }
+
+        let (context, sources) = self.retrieve(&queries, pages, collection).await?;
+        if sources.is_empty() {
+            return Err("The fetched pages have nothing relevant to the question".to_string());
+        }
+
+        self.check_cancelled()?;
+        self.emit(ResearchStep::Synthesizing);
+        let answer = self.complete(ANSWER_PROMPT, format!("Sources:\n\n{}\n\nQuestion: {}", context, question), 1500).await?;
+        Ok((answer, sources))
+    }
+}
+
+/// Remove the research collection and the staged pages
+async fn clean_up(collection: &str, pages: &[Page]) {
+    let dropped = async {
+        let store = backend::open(collection)?;
+        store.drop_collection().await
+    }.await;
+    if let Err(e) = dropped {
+        log_warning!("Failed to drop research collection", collection = %collection, error = %e);
+    }
+    for page in pages {
+        if let Some(dir) = std::path::Path::new(&page.file_path).parent() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Research `question` on the web; `depth` (1-3) sets how many searches are made
+#[tauri::command]
+pub async fn research_query(
+    app: AppHandle,
+    question: String,
+    depth: Option<u32>,
+    research_id: Option<String>
+) -> Result<ResearchResult, String> {
+    let question = question.trim().to_string();
+    if question.is_empty() {
+        return Err("No question given".to_string());
+    }
+    let depth = depth.unwrap_or(1).clamp(1, MAX_DEPTH);
+    let research_id = research_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let started = Instant::now();
+    log_operation_start!("Research", research_id = %research_id, depth = depth);
+
+    crate::ensure_ovms_initialized(&app).await;
+    let model = crate::ovms::get_loaded_model(app.clone()).await?
+        .ok_or("No model is loaded; load a text model to research a question")?;
+
+    let research = Research {
+        app: &app,
+        id: &research_id,
+        operation: cancellation::try_register(&app, &research_id, OperationKind::Research)?,
+        client: Client::with_config(
+            OpenAIConfig::new()
+                .with_api_key("unused")
+                .with_api_base(crate::ovms::openai_api_base())
+        ),
+        model,
+    };
+    let collection = format!("research-{}", uuid::Uuid::new_v4().simple());
+    let mut pages = Vec::new();
+    let result = research.run(&question, depth, &collection, &mut pages).await;
+    clean_up(&collection, &pages).await;
+
+    let (answer, mut sources) = result.map_err(|e| {
+        log_operation_error!("Research", &e, research_id = %research_id);
+        e
+    })?;
+    let claims = split_claims(&answer, sources.len());
+    for source in &mut sources {
+        source.cited = claims.iter().any(|claim| claim.sources.contains(&source.index));
+    }
+
+    let result = ResearchResult {
+        research_id,
+        question,
+        answer,
+        claims,
+        sources,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    log_operation_success!("Research", sources = result.sources.len(), elapsed_ms = result.elapsed_ms);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queries_strips_list_markers() {
+        let plan = "1. tea caffeine content\n- \"green tea health\"\n\n* Tea Caffeine Content\n2) oolong processing\n2024 tea harvest";
+        assert_eq!(
+            parse_queries(plan, 5),
+            vec!["tea caffeine content", "green tea health", "oolong processing", "2024 tea harvest"]
+        );
+        assert_eq!(parse_queries(plan, 1), vec!["tea caffeine content"]);
+    }
+
+    #[test]
+    fn test_split_claims_collects_citations() {
+        let answer = "Green tea has less caffeine than coffee [1][3]. It is unoxidised. [2]\nOolong sits in between [2, 9].";
+        assert_eq!(split_claims(answer, 3), vec![
+            ResearchClaim { text: "Green tea has less caffeine than coffee.".to_string(), sources: vec![1, 3] },
+            ResearchClaim { text: "It is unoxidised.".to_string(), sources: vec![2] },
+            ResearchClaim { text: "Oolong sits in between.".to_string(), sources: vec![2] },
+        ]);
+        assert_eq!(strip_citations("See [note] here", 3), ("See [note] here".to_string(), vec![]));
+    }
+}
@@ -0,0 +1,180 @@
+//! "Send selection to chat": text captured in another app, turned into a chat prompt.
+//!
+//! A system-wide helper (a hotkey script, a context-menu entry) launches
+//! Sparrow with the selected text:
+//!
+//! ```text
+//! SparrowAI --send-selection [--action translate] [--session <id>] (--text "..." | --text-file path)
+//! ```
+//!
+//! If Sparrow is already running, the single-instance plugin forwards the
+//! arguments to it instead of starting a second copy. The text is rendered
+//! through a quick action, parked in `AppState::pending_selection`, and the
+//! frontend is told with a `selection-received` event to take it and send it
+//! in a new chat (or `session_id`, when given).
+
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager };
+
+use crate::quick_actions;
+use crate::settings;
+use crate::state::AppState;
+use crate::tray;
+
+pub const SEND_SELECTION_ARG: &str = "--send-selection";
+
+/// Selections are usually a sentence or a paragraph; this bounds accidental huge pastes
+const MAX_SELECTION_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionRequest {
+    pub text: String,
+    pub action: Option<String>,
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionPrompt {
+    pub action_id: String,
+    pub action_name: String,
+    /// The original selection
+    pub text: String,
+    /// The message to send
+    pub prompt: String,
+    /// Existing session to send into; a new chat when absent
+    pub session_id: Option<String>,
+    /// Model the action pins; the loaded text model when absent
+    pub model: Option<String>,
+}
+
+/// Parse `--send-selection` launch arguments; `None` if they are not present
+pub fn parse_args(argv: &[String]) -> Option<Result<SelectionRequest, String>> {
+    if !argv.iter().any(|arg| arg == SEND_SELECTION_ARG) {
+        return None;
+    }
+
+    let value_of = |flag: &str| {
+        argv.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| argv.get(index + 1))
+            .cloned()
+    };
+
+    let text = match (value_of("--text"), value_of("--text-file")) {
+        (Some(text), _) => Ok(text),
+        (None, Some(path)) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read selection file {}: {}", path, e)),
+        (None, None) => Err("No text given; pass --text or --text-file".to_string()),
+    };
+
+    Some(text.map(|text| SelectionRequest {
+        text,
+        action: value_of("--action"),
+        session_id: value_of("--session"),
+    }))
+}
+
+/// Render the selection into a prompt and hand it to the frontend
+pub fn dispatch(app: &AppHandle, request: SelectionRequest) -> Result<SelectionPrompt, String> {
+    let text = request.text.trim();
+    if text.is_empty() {
+        return Err("The selection is empty".to_string());
+    }
+    let text: String = text.chars().take(MAX_SELECTION_CHARS).collect();
+
+    let action_id = request.action
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| settings::current().quick_actions.selection_action);
+    let action = quick_actions::find_action(&action_id)
+        .ok_or_else(|| format!("Unknown quick action: '{}'", action_id))?;
+
+    let prompt = SelectionPrompt {
+        action_id: action.id.clone(),
+        action_name: action.name.clone(),
+        prompt: quick_actions::render_prompt(&action, &text),
+        text,
+        session_id: request.session_id,
+        model: action.model.clone(),
+    };
+
+    tracing::info!(action = %prompt.action_id, chars = prompt.text.len(), "Received text selection");
+    deliver(app, prompt.clone());
+    Ok(prompt)
+}
+
+/// Park a rendered prompt for the frontend and bring the window forward
+pub fn deliver(app: &AppHandle, prompt: SelectionPrompt) {
+    *app.state::<AppState>().pending_selection.lock() = Some(prompt);
+
+    tray::show_main_window(app);
+    let _ = app.emit("selection-received", ());
+}
+
+/// Act on `--send-selection` launch arguments; `false` if there are none
+pub fn handle_launch_args(app: &AppHandle, argv: &[String]) -> bool {
+    let Some(request) = parse_args(argv) else {
+        return false;
+    };
+    if let Err(e) = request.and_then(|request| dispatch(app, request)) {
+        log_warning!("Ignoring text selection", error = %e);
+    }
+    true
+}
+
+/// Called by the single-instance plugin with the arguments of a second launch
+pub fn handle_second_instance(app: &AppHandle, argv: Vec<String>) {
+    if !handle_launch_args(app, &argv) {
+        // A plain second launch just brings the existing window forward
+        tray::show_main_window(app);
+    }
+}
+
+/// Send text to chat from the frontend or an overlay window
+#[tauri::command]
+pub async fn send_selection_to_chat(
+    app: AppHandle,
+    text: String,
+    action: Option<String>,
+    session_id: Option<String>
+) -> Result<SelectionPrompt, String> {
+    dispatch(&app, SelectionRequest { text, action, session_id })
+}
+
+/// Take the selection waiting to be sent, if any. The frontend calls this on
+/// startup and on every `selection-received` event.
+#[tauri::command]
+pub async fn take_pending_selection(app: AppHandle) -> Result<Option<SelectionPrompt>, String> {
+    Ok(app.state::<AppState>().pending_selection.lock().take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_requires_flag() {
+        assert!(parse_args(&args(&["SparrowAI", "--text", "hello"])).is_none());
+    }
+
+    #[test]
+    fn test_parse_args_reads_text_and_action() {
+        let request = parse_args(&args(&[
+            "SparrowAI", SEND_SELECTION_ARG, "--action", "translate", "--text", "bonjour",
+        ])).unwrap().unwrap();
+
+        assert_eq!(request, SelectionRequest {
+            text: "bonjour".to_string(),
+            action: Some("translate".to_string()),
+            session_id: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_args_without_text_is_an_error() {
+        assert!(parse_args(&args(&["SparrowAI", SEND_SELECTION_ARG])).unwrap().is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Retrying completion streams while OVMS is busy.
+//!
+//! Under load OVMS answers a completion request with 429 or 503 (its request
+//! queue is full) instead of streaming. `open_stream` retries such a request
+//! up to `chat.busy_retries` times, doubling `chat.busy_retry_delay_ms` each
+//! time, and reports each step as a `server-busy` event so the UI can show
+//! that the reply is waiting rather than failed. Other errors are returned at
+//! once. The status only shows up once the stream is read, so the first item
+//! is read here and handed back in front of the rest.
+
+use std::pin::Pin;
+use std::time::Duration;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::error::OpenAIError;
+use async_openai::types::chat::{ CreateChatCompletionRequest, CreateChatCompletionStreamResponse };
+use futures::{ Stream, StreamExt };
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+use tokio_util::sync::CancellationToken;
+
+use crate::cancellation::OperationKind;
+use crate::settings;
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+pub type ResponseStream = Pin<Box<dyn Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>> + Send>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusyState {
+    /// Waiting `retry_in_ms` before the next attempt
+    Retrying,
+    /// An attempt after a busy answer got through
+    Recovered,
+    /// Out of retries; the request fails with the busy error
+    GaveUp,
+}
+
+/// Payload of `server-busy` events
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerBusy {
+    /// Session id (or "temp") for chat, request id for text assist
+    pub id: String,
+    pub kind: OperationKind,
+    pub state: BusyState,
+    pub status: Option<u16>,
+    /// Retries made so far
+    pub attempt: u32,
+    pub max_retries: u32,
+    pub retry_in_ms: Option<u64>,
+}
+
+/// 429 or 503 when an error message says OVMS turned the request away
+fn busy_status(error: &str) -> Option<u16> {
+    let error = error.to_ascii_lowercase();
+    if error.contains("429 too many requests") || error.contains("status code: 429") {
+        Some(429)
+    } else if error.contains("503 service unavailable")
+        || error.contains("status code: 503")
+        || error.contains("queue is full")
+    {
+        Some(503)
+    } else {
+        None
+    }
+}
+
+/// Wait before retry `attempt` (0-based)
+fn retry_delay(base_ms: u64, attempt: u32) -> Duration {
+    Duration::from_millis(base_ms.max(1))
+        .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// First item of a new stream, or the error that kept it from starting
+async fn start(client: &Client<OpenAIConfig>, request: CreateChatCompletionRequest) -> Result<ResponseStream, String> {
+    let mut stream = client.chat().create_stream(request).await.map_err(|e| e.to_string())?;
+    match stream.next().await {
+        Some(Err(e)) => Err(e.to_string()),
+        first => Ok(futures::stream::iter(first).chain(stream).boxed()),
+    }
+}
+
+/// Start a completion stream, retrying while OVMS is busy. A cancelled
+/// request gets a stream that never yields, so the caller's own cancel
+/// branch handles it.
+pub async fn open_stream(
+    app: &AppHandle,
+    client: &Client<OpenAIConfig>,
+    request: CreateChatCompletionRequest,
+    id: &str,
+    kind: OperationKind,
+    cancel: &CancellationToken
+) -> Result<ResponseStream, String> {
+    let config = settings::current().chat;
+    let emit = |state: BusyState, status: Option<u16>, attempt: u32, retry_in: Option<Duration>| {
+        let _ = app.emit("server-busy", ServerBusy {
+            id: id.to_string(),
+            kind,
+            state,
+            status,
+            attempt,
+            max_retries: config.busy_retries,
+            retry_in_ms: retry_in.map(|delay| delay.as_millis() as u64),
+        });
+    };
+
+    let mut attempt = 0;
+    loop {
+        let result = tokio::select! {
+            _ = cancel.cancelled() => return Ok(futures::stream::pending().boxed()),
+            result = start(client, request.clone()) => result,
+        };
+        let error = match result {
+            Ok(stream) => {
+                if attempt > 0 {
+                    tracing::info!(id = %id, retries = attempt, "OVMS accepted the request after being busy");
+                    emit(BusyState::Recovered, None, attempt, None);
+                }
+                return Ok(stream);
+            }
+            Err(error) => error,
+        };
+
+        let Some(status) = busy_status(&error) else {
+            return Err(error);
+        };
+        if attempt >= config.busy_retries {
+            log_warning!("OVMS stayed busy, giving up", id = %id, status = status, retries = attempt);
+            emit(BusyState::GaveUp, Some(status), attempt, None);
+            return Err(format!("The model server is busy ({}); tried {} more times: {}", status, attempt, error));
+        }
+
+        let delay = retry_delay(config.busy_retry_delay_ms, attempt);
+        log_warning!("OVMS is busy, retrying", id = %id, status = status, attempt = attempt + 1, retry_in_ms = delay.as_millis() as u64);
+        emit(BusyState::Retrying, Some(status), attempt, Some(delay));
+        tokio::select! {
+            _ = cancel.cancelled() => return Ok(futures::stream::pending().boxed()),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_busy_status_from_error_messages() {
+        assert_eq!(busy_status("stream failed: Invalid status code: 503 Service Unavailable"), Some(503));
+        assert_eq!(busy_status("Invalid status code: 429 Too Many Requests"), Some(429));
+        assert_eq!(busy_status("Server error: request queue is full"), Some(503));
+        assert_eq!(busy_status("Invalid status code: 404 Not Found"), None);
+        assert_eq!(busy_status("Model with requested name is not found"), None);
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_max() {
+        assert_eq!(retry_delay(1000, 0), Duration::from_secs(1));
+        assert_eq!(retry_delay(1000, 2), Duration::from_secs(4));
+        assert_eq!(retry_delay(1000, 10), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(0, 0), Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,697 @@
+//! Shareable bundles of a chat session.
+//!
+//! `export_session_bundle` writes a folder (or a zip of it) that opens
+//! without Sparrow:
+//!
+//! - `transcript.md`: the conversation, with links to the copied files;
+//! - `excerpts.md`: the document passages each answer cited, as saved with
+//!   the answer, i.e. what the answers were based on;
+//! - `attachments/` and `images/`: attached files, and charts or images the
+//!   session produced;
+//! - `metadata.json`: session details and the list of files.
+//!
+//! `export_chat_session` writes a single file instead: the transcript as
+//! Markdown, or the session as JSON with its tool calls split out of the
+//! message text and token totals added. `import_chat_sessions` reads such a
+//! JSON file (or a single session, or a copy of the sessions file) back and
+//! merges it into the stored sessions the way a second window would.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{ Path, PathBuf };
+
+use chrono::{ TimeZone, Utc };
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+
+use crate::chat::{ self, ChatSession };
+use crate::{ path_policy, paths };
+
+/// `format` of JSON chat exports
+const EXPORT_FORMAT: &str = "sparrow-chat";
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatExport {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    /// Sessions that were not stored before
+    pub imported: usize,
+    /// Sessions merged into a stored one with the same id
+    pub merged: usize,
+    /// Messages added across both
+    pub messages_added: usize,
+}
+
+/// Tool call written into a reply, with the response that followed it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ExportedToolCall {
+    name: String,
+    arguments: Value,
+    response: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+struct TokenStats {
+    messages: usize,
+    tool_calls: usize,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
+    /// Mean over the replies that recorded a speed
+    avg_tokens_per_second: Option<f64>,
+}
+
+/// What `import_chat_sessions` accepts
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    /// `export_chat_session` JSON
+    Export { sessions: Vec<ChatSession> },
+    /// A copy of the sessions file
+    Storage { sessions: HashMap<String, ChatSession> },
+    Session(ChatSession),
+}
+
+/// Piece of a message: plain text, or a `<tool_call>`/`<tool_response>` block
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    Text(&'a str),
+    ToolCall(&'a str),
+    ToolResponse(&'a str),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionBundle {
+    /// Bundle folder, or the zip file when zipped
+    pub path: String,
+    pub files: usize,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct BundleMetadata<'a> {
+    session_id: &'a str,
+    title: &'a str,
+    model_id: Option<&'a str>,
+    created_at: i64,
+    updated_at: i64,
+    message_count: usize,
+    exported_at: String,
+    app_version: &'static str,
+    files: Vec<String>,
+}
+
+/// Folder name for a bundle: the title reduced to safe characters, plus the date
+fn bundle_name(title: &str, exported_at: chrono::DateTime<Utc>) -> String {
+    let slug: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug: String = slug.chars().take(60).collect();
+    let slug = if slug.is_empty() { "chat".to_string() } else { slug };
+    format!("{}-{}", slug, exported_at.format("%Y%m%d-%H%M%S"))
+}
+
+fn split_tool_blocks(content: &str) -> Vec<Part<'_>> {
+    const BLOCKS: [(&str, &str); 2] = [("<tool_call>", "</tool_call>"), ("<tool_response>", "</tool_response>")];
+    let mut parts = Vec::new();
+    let mut rest = content;
+    loop {
+        let next = BLOCKS
+            .iter()
+            .filter_map(|(open, close)| rest.find(open).map(|at| (at, *open, *close)))
+            .min_by_key(|(at, _, _)| *at);
+        let Some((at, open, close)) = next else {
+            break;
+        };
+        let body_start = at + open.len();
+        // An unclosed block stays in the text
+        let Some(body_len) = rest[body_start..].find(close) else {
+            break;
+        };
+        if !rest[..at].trim().is_empty() {
+            parts.push(Part::Text(rest[..at].trim()));
+        }
+        let body = rest[body_start..body_start + body_len].trim();
+        parts.push(if open == "<tool_call>" { Part::ToolCall(body) } else { Part::ToolResponse(body) });
+        rest = &rest[body_start + body_len + close.len()..];
+    }
+    if !rest.trim().is_empty() {
+        parts.push(Part::Text(rest.trim()));
+    }
+    parts
+}
+
+/// Name and arguments of a `<tool_call>` body; unparsable bodies keep their text
+fn parse_tool_call(body: &str) -> (String, Value) {
+    match serde_json::from_str::<Value>(body) {
+        Ok(call) => (
+            call["name"].as_str().unwrap_or_default().to_string(),
+            call.get("arguments").cloned().unwrap_or(Value::Null),
+        ),
+        Err(_) => (String::new(), Value::String(body.to_string())),
+    }
+}
+
+/// Tool calls in a message, each with the first response after it
+fn tool_calls(content: &str) -> Vec<ExportedToolCall> {
+    let mut calls: Vec<ExportedToolCall> = Vec::new();
+    for part in split_tool_blocks(content) {
+        match part {
+            Part::ToolCall(body) => {
+                let (name, arguments) = parse_tool_call(body);
+                calls.push(ExportedToolCall { name, arguments, response: None });
+            }
+            Part::ToolResponse(body) => {
+                if let Some(call) = calls.iter_mut().find(|call| call.response.is_none()) {
+                    call.response = Some(body.to_string());
+                }
+            }
+            Part::Text(_) => {}
+        }
+    }
+    calls
+}
+
+fn token_stats(session: &ChatSession) -> TokenStats {
+    let speeds: Vec<f64> = session.messages.iter().filter_map(|m| m.tokens_per_second).collect();
+    TokenStats {
+        messages: session.messages.len(),
+        tool_calls: session.messages.iter().map(|m| tool_calls(&m.content).len()).sum(),
+        prompt_tokens: session.messages.iter().filter_map(|m| m.prompt_tokens).map(u64::from).sum(),
+        completion_tokens: session.messages.iter().filter_map(|m| m.completion_tokens).map(u64::from).sum(),
+        total_tokens: session.messages.iter().filter_map(|m| m.total_tokens).map(u64::from).sum(),
+        avg_tokens_per_second: (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64),
+    }
+}
+
+fn format_timestamp(millis: i64) -> String {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// Markdown transcript; `links` maps original attachment paths to bundle paths
+fn render_transcript(session: &ChatSession, links: &HashMap<String, String>) -> String {
+    let mut out = format!("# {}\n\n", session.title);
+    out.push_str(&format!("_Exported from SparrowAI · started {}", format_timestamp(session.created_at)));
+    if let Some(model) = &session.model_id {
+        out.push_str(&format!(" · model {}", model));
+    }
+    out.push_str("_\n");
+
+    for message in &session.messages {
+        let speaker = match message.role.as_str() {
+            "user" => "You",
+            "assistant" => "Assistant",
+            other => other,
+        };
+        let pinned = if message.pinned { " 📌" } else { "" };
+        out.push_str(&format!("\n---\n\n### {}{} · {}\n\n", speaker, pinned, format_timestamp(message.timestamp)));
+        let parts: Vec<String> = split_tool_blocks(&message.content)
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => text.to_string(),
+                Part::ToolCall(body) => {
+                    let (name, arguments) = parse_tool_call(body);
+                    format!("> 🔧 Called `{}` with `{}`", name, arguments)
+                }
+                Part::ToolResponse(body) => format!("```text\n{}\n```", body),
+            })
+            .collect();
+        out.push_str(&parts.join("\n\n"));
+        out.push('\n');
+
+        if let Some(completion_tokens) = message.completion_tokens {
+            out.push_str(&format!("\n_{} tokens", completion_tokens));
+            if let Some(speed) = message.tokens_per_second {
+                out.push_str(&format!(" · {:.1} tokens/s", speed));
+            }
+            out.push_str("_\n");
+        }
+
+        for attachment in message.attachments.iter().flatten() {
+            match links.get(&attachment.file_path) {
+                Some(link) if attachment.is_image => out.push_str(&format!("\n![{}]({})\n", attachment.file_name, link)),
+                Some(link) => out.push_str(&format!("\n📎 [{}]({})\n", attachment.file_name, link)),
+                None => out.push_str(&format!("\n📎 {} (not included)\n", attachment.file_name)),
+            }
+        }
+    }
+    out
+}
+
+/// Copy `source` into `dir`, numbering the name if it is taken; returns the new name
+fn copy_unique(source: &Path, dir: &Path, file_name: &str) -> Result<String, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let file_name = path_policy::safe_file_name(file_name)
+        .map(str::to_string)
+        .unwrap_or_else(|_| "file".to_string());
+
+    let mut name = file_name.clone();
+    let mut n = 1;
+    while dir.join(&name).exists() {
+        n += 1;
+        name = format!("{}-{}", n, file_name);
+    }
+    fs::copy(source, dir.join(&name)).map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+    Ok(name)
+}
+
+/// Copy attachments and session assets; returns attachment path -> bundle link
+fn copy_files(session: &ChatSession, bundle: &Path) -> HashMap<String, String> {
+    let mut links = HashMap::new();
+    for attachment in session.messages.iter().flat_map(|m| m.attachments.iter().flatten()) {
+        let source = Path::new(&attachment.file_path);
+        if links.contains_key(&attachment.file_path) || !source.is_file() {
+            continue;
+        }
+        let folder = if attachment.is_image { "images" } else { "attachments" };
+        match copy_unique(source, &bundle.join(folder), &attachment.file_name) {
+            Ok(name) => {
+                links.insert(attachment.file_path.clone(), format!("{}/{}", folder, name.replace(' ', "%20")));
+            }
+            Err(e) => tracing::warn!(error = %e, "Skipping attachment in session bundle"),
+        }
+    }
+
+    // Charts and images produced during the session
+    let assets = paths::get_session_assets_dir(Some(&session.id)).ok();
+    if let Some(entries) = assets.and_then(|dir| fs::read_dir(dir).ok()) {
+        for entry in entries.flatten().filter(|entry| entry.path().is_file()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Err(e) = copy_unique(&entry.path(), &bundle.join("images"), &name) {
+                tracing::warn!(error = %e, "Skipping session asset in session bundle");
+            }
+        }
+    }
+    links
+}
+
+/// Document paths attached to a user message
+/// The sources each answer cited, as saved with the answer, under the
+/// question it answered
+fn render_excerpts(session: &ChatSession) -> Option<String> {
+    let mut out = String::from("# Cited document excerpts\n");
+    let mut found = false;
+    let mut question = "";
+
+    for message in &session.messages {
+        if message.role == "user" {
+            question = message.content.trim();
+            continue;
+        }
+        let Some(citations) = message.citations.as_ref().filter(|c| !c.is_empty()) else {
+            continue;
+        };
+        found = true;
+        out.push_str(&format!("\n## {}\n", if question.is_empty() { "(no question)" } else { question }));
+        for citation in citations {
+            let chunk = citation.chunk_index.map(|i| format!(", chunk {}", i + 1)).unwrap_or_default();
+            out.push_str(&format!(
+                "\n**[{}] {}** (`{}`{})\n\n> {}\n",
+                citation.marker,
+                citation.title,
+                citation.file_path,
+                chunk,
+                citation.excerpt
+            ));
+        }
+    }
+    found.then_some(out)
+}
+
+fn list_files(dir: &Path) -> Vec<(String, u64)> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(dir).ok()?.to_string_lossy().replace('\\', "/");
+            Some((relative, entry.metadata().map(|m| m.len()).unwrap_or(0)))
+        })
+        .collect()
+}
+
+fn zip_dir(dir: &Path, zip_path: &Path) -> Result<(), String> {
+    let file = fs::File::create(zip_path).map_err(|e| format!("Failed to create zip: {}", e))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let root = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+    for (relative, _) in list_files(dir) {
+        writer
+            .start_file(format!("{}/{}", root, relative), options)
+            .map_err(|e| format!("Failed to add {} to zip: {}", relative, e))?;
+        let data = fs::read(dir.join(&relative)).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        writer.write_all(&data).map_err(|e| format!("Failed to write {} to zip: {}", relative, e))?;
+    }
+    writer.finish().map_err(|e| format!("Failed to finish zip: {}", e))?;
+    Ok(())
+}
+
+/// Export a session as a self-contained folder, or a zip with `as_zip`, under
+/// `output_dir` (default `~/.sparrow/exports`)
+#[tauri::command]
+pub async fn export_session_bundle(
+    session_id: String,
+    output_dir: Option<String>,
+    as_zip: Option<bool>
+) -> Result<SessionBundle, String> {
+    log_operation_start!("Export session bundle", session_id = %session_id);
+
+    let storage = chat::load_chat_sessions().await?;
+    let session = storage.sessions
+        .get(&session_id)
+        .cloned()
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+
+    let output_dir = match output_dir {
+        Some(dir) => path_policy::check_str(&dir, path_policy::Access::Write)?,
+        None => paths::get_exports_dir().map_err(|e| e.to_string())?,
+    };
+    let exported_at = Utc::now();
+    let bundle = output_dir.join(bundle_name(&session.title, exported_at));
+    fs::create_dir_all(&bundle).map_err(|e| format!("Failed to create bundle folder: {}", e))?;
+
+    let links = {
+        let session = session.clone();
+        let bundle = bundle.clone();
+        tokio::task::spawn_blocking(move || copy_files(&session, &bundle)).await
+            .map_err(|e| format!("Failed to copy session files: {}", e))?
+    };
+    let write = |name: &str, contents: &str| {
+        fs::write(bundle.join(name), contents).map_err(|e| format!("Failed to write {}: {}", name, e))
+    };
+    write("transcript.md", &render_transcript(&session, &links))?;
+
+    if let Some(excerpts) = render_excerpts(&session) {
+        write("excerpts.md", &excerpts)?;
+    }
+
+    let mut files: Vec<String> = list_files(&bundle).into_iter().map(|(name, _)| name).collect();
+    files.push("metadata.json".to_string());
+    let metadata = BundleMetadata {
+        session_id: &session.id,
+        title: &session.title,
+        model_id: session.model_id.as_deref(),
+        created_at: session.created_at,
+        updated_at: session.updated_at,
+        message_count: session.messages.len(),
+        exported_at: exported_at.to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION"),
+        files,
+    };
+    let metadata = serde_json::to_string_pretty(&metadata).map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    write("metadata.json", &metadata)?;
+
+    let contents = list_files(&bundle);
+    let file_count = contents.len();
+    let mut result = SessionBundle {
+        path: bundle.to_string_lossy().to_string(),
+        files: file_count,
+        size_bytes: contents.iter().map(|(_, size)| size).sum(),
+    };
+
+    if as_zip.unwrap_or(false) {
+        let zip_path = PathBuf::from(format!("{}.zip", bundle.to_string_lossy()));
+        {
+            let (bundle, zip_path) = (bundle.clone(), zip_path.clone());
+            tokio::task::spawn_blocking(move || zip_dir(&bundle, &zip_path)).await
+                .map_err(|e| format!("Failed to zip bundle: {}", e))??;
+        }
+        if let Err(e) = fs::remove_dir_all(&bundle) {
+            log_warning!("Failed to remove unzipped bundle folder", error = %e);
+        }
+        result.size_bytes = fs::metadata(&zip_path).map(|m| m.len()).unwrap_or(0);
+        result.path = zip_path.to_string_lossy().to_string();
+    }
+
+    log_operation_success!("Export session bundle", path = %result.path, files = result.files);
+    Ok(result)
+}
+
+/// Session as exported to JSON: messages gain `tool_calls`, the session `stats`
+fn session_json(session: &ChatSession) -> Result<Value, String> {
+    let mut value = serde_json::to_value(session).map_err(|e| format!("Failed to serialize session: {}", e))?;
+    if let Some(messages) = value["messages"].as_array_mut() {
+        for (message, stored) in messages.iter_mut().zip(&session.messages) {
+            message["tool_calls"] = serde_json::to_value(tool_calls(&stored.content)).unwrap_or_default();
+        }
+    }
+    value["stats"] = serde_json::to_value(token_stats(session)).unwrap_or_default();
+    Ok(value)
+}
+
+/// Export one session as a Markdown transcript or as JSON that
+/// `import_chat_sessions` reads back, under `output_dir` (default `~/.sparrow/exports`)
+#[tauri::command]
+pub async fn export_chat_session(
+    session_id: String,
+    format: ExportFormat,
+    output_dir: Option<String>
+) -> Result<ChatExport, String> {
+    let storage = chat::load_chat_sessions().await?;
+    let session = storage.sessions
+        .get(&session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))?;
+
+    let output_dir = match output_dir {
+        Some(dir) => path_policy::check_str(&dir, path_policy::Access::Write)?,
+        None => paths::get_exports_dir().map_err(|e| e.to_string())?,
+    };
+    fs::create_dir_all(&output_dir).map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+
+    let exported_at = Utc::now();
+    let (extension, contents) = match format {
+        ExportFormat::Markdown => ("md", render_transcript(session, &HashMap::new())),
+        ExportFormat::Json => {
+            let export = serde_json::json!({
+                "format": EXPORT_FORMAT,
+                "version": EXPORT_VERSION,
+                "exported_at": exported_at.to_rfc3339(),
+                "app_version": env!("CARGO_PKG_VERSION"),
+                "sessions": [session_json(session)?],
+            });
+            let json = serde_json::to_string_pretty(&export).map_err(|e| format!("Failed to serialize export: {}", e))?;
+            ("json", json)
+        }
+    };
+
+    let path = output_dir.join(format!("{}.{}", bundle_name(&session.title, exported_at), extension));
+    fs::write(&path, &contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    tracing::info!(session_id = %session_id, path = %path.display(), "Exported chat session");
+    Ok(ChatExport {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: contents.len() as u64,
+    })
+}
+
+fn parse_import(contents: &str) -> Result<Vec<ChatSession>, String> {
+    let file: ImportFile = serde_json::from_str(contents).map_err(|e| {
+        format!("Not a chat export: {} (Markdown exports are for reading and cannot be imported)", e)
+    })?;
+    Ok(match file {
+        ImportFile::Export { sessions } => sessions,
+        ImportFile::Storage { sessions } => sessions.into_values().collect(),
+        ImportFile::Session(session) => vec![session],
+    })
+}
+
+/// Merge the sessions of an exported JSON file into the stored ones
+#[tauri::command]
+pub async fn import_chat_sessions(path: String) -> Result<ImportReport, String> {
+    let path = path_policy::check_str(&path, path_policy::Access::Read)?;
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let sessions = parse_import(&contents)?;
+
+    let report = chat::modify_sessions(|storage| {
+        let mut report = ImportReport::default();
+        for session in sessions {
+            match storage.sessions.get_mut(&session.id) {
+                Some(existing) => {
+                    let before = existing.messages.len();
+                    chat::merge_sessions(existing, session);
+                    report.messages_added += existing.messages.len() - before;
+                    report.merged += 1;
+                }
+                None => {
+                    report.messages_added += session.messages.len();
+                    report.imported += 1;
+                    storage.sessions.insert(session.id.clone(), session);
+                }
+            }
+        }
+        Ok(report)
+    }).await?;
+
+    tracing::info!(path = %path.display(), imported = report.imported, merged = report.merged, "Imported chat sessions");
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{ AttachmentInfo, ChatMessage };
+    use crate::rag::citations::Citation;
+
+    fn message(role: &str, content: &str, attachments: Option<Vec<AttachmentInfo>>) -> ChatMessage {
+        ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: 1_700_000_000_000,
+            tokens_per_second: None,
+            is_error: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            total_tokens: None,
+            attachments,
+            pinned: false,
+            generation: None,
+            citations: None,
+        }
+    }
+
+    #[test]
+    fn test_bundle_name() {
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+        assert_eq!(bundle_name("Q3 report: what's new?", at), "Q3-report-what-s-new-20260301-093000");
+        assert_eq!(bundle_name("../..", at), "chat-20260301-093000");
+    }
+
+    #[test]
+    fn test_transcript_links_attachments() {
+        let report = AttachmentInfo {
+            file_path: "/home/me/report.pdf".to_string(),
+            file_name: "report.pdf".to_string(),
+            file_type: "pdf".to_string(),
+            is_image: false,
+        };
+        let missing = AttachmentInfo { file_path: "/gone.png".to_string(), file_name: "gone.png".to_string(), ..report.clone() };
+        let session = ChatSession {
+            id: "s1".to_string(),
+            title: "Report review".to_string(),
+            created_at: 1_700_000_000_000,
+            updated_at: 1_700_000_000_000,
+            model_id: Some("Qwen3-8B-int4-ov".to_string()),
+            messages: vec![
+                message("user", "Summarize this", Some(vec![report, missing])),
+                message("assistant", "It covers Q3.", None),
+            ],
+            preferred_language: None,
+            tool_policy: Default::default(),
+            sampling: Default::default(),
+            prompt_preset: None,
+            revision: 0,
+        };
+        let links = HashMap::from([("/home/me/report.pdf".to_string(), "attachments/report.pdf".to_string())]);
+
+        let transcript = render_transcript(&session, &links);
+        assert!(transcript.starts_with("# Report review\n"));
+        assert!(transcript.contains("model Qwen3-8B-int4-ov"));
+        assert!(transcript.contains("### You · 2023-11-14 22:13 UTC\n\nSummarize this"));
+        assert!(transcript.contains("📎 [report.pdf](attachments/report.pdf)"));
+        assert!(transcript.contains("📎 gone.png (not included)"));
+        assert!(transcript.contains("### Assistant · "));
+        assert_eq!(render_excerpts(&session), None);
+    }
+
+    #[test]
+    fn test_excerpts_are_the_saved_citations() {
+        let mut reply = message("assistant", "Revenue grew [1].", None);
+        reply.citations = Some(vec![Citation {
+            marker: 1,
+            file_path: "/home/me/report.pdf".to_string(),
+            title: "Q3 report".to_string(),
+            chunk_index: Some(2),
+            score: 0.8,
+            rerank_score: None,
+            excerpt: "Revenue grew 12% over Q2.".to_string(),
+        }]);
+        let session = ChatSession {
+            id: "s1".to_string(),
+            title: "Report review".to_string(),
+            created_at: 0,
+            updated_at: 0,
+            model_id: None,
+            messages: vec![message("user", "How did revenue develop?", None), reply],
+            preferred_language: None,
+            tool_policy: Default::default(),
+            sampling: Default::default(),
+            prompt_preset: None,
+            revision: 0,
+        };
+
+        let excerpts = render_excerpts(&session).unwrap();
+        assert!(excerpts.contains("\n## How did revenue develop?\n"));
+        assert!(excerpts.contains("**[1] Q3 report** (`/home/me/report.pdf`, chunk 3)\n\n> Revenue grew 12% over Q2.\n"));
+    }
+
+    #[test]
+    fn test_tool_calls_and_stats_from_messages() {
+        let mut reply = message(
+            "assistant",
+            "Let me check.\n<tool_call>\n{\"name\": \"builtin_get_current_time\", \"arguments\": {\"format\": \"unix\"}}\n</tool_call>\n<tool_response>\n1700000000\n</tool_response>\nIt is 1700000000.",
+            None,
+        );
+        reply.completion_tokens = Some(12);
+        reply.total_tokens = Some(40);
+        reply.tokens_per_second = Some(20.0);
+
+        assert_eq!(tool_calls(&reply.content), vec![ExportedToolCall {
+            name: "builtin_get_current_time".to_string(),
+            arguments: serde_json::json!({ "format": "unix" }),
+            response: Some("1700000000".to_string()),
+        }]);
+        assert_eq!(split_tool_blocks("open <tool_call> never closed"), vec![Part::Text("open <tool_call> never closed")]);
+
+        let session = ChatSession {
+            id: "s1".to_string(),
+            title: "Time".to_string(),
+            created_at: 0,
+            updated_at: 0,
+            model_id: None,
+            messages: vec![message("user", "What time is it?", None), reply],
+            preferred_language: None,
+            tool_policy: Default::default(),
+            sampling: Default::default(),
+            prompt_preset: None,
+            revision: 0,
+        };
+        let stats = token_stats(&session);
+        assert_eq!((stats.messages, stats.tool_calls, stats.total_tokens), (2, 1, 40));
+        assert_eq!(stats.avg_tokens_per_second, Some(20.0));
+
+        let transcript = render_transcript(&session, &HashMap::new());
+        assert!(transcript.contains("> 🔧 Called `builtin_get_current_time` with `{\"format\":\"unix\"}`"));
+        assert!(transcript.contains("```text\n1700000000\n```"));
+        assert!(transcript.contains("_12 tokens · 20.0 tokens/s_"));
+
+        // The JSON export reads back as sessions
+        let export = serde_json::json!({ "format": EXPORT_FORMAT, "sessions": [session_json(&session).unwrap()] });
+        let imported = parse_import(&export.to_string()).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].messages.len(), 2);
+        assert_eq!(parse_import(&serde_json::to_string(&session).unwrap()).unwrap()[0].id, "s1");
+        assert!(parse_import("# Time").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Session titles written by the model.
+//!
+//! A new session is named after the start of its first message. With
+//! `chat.llm_titles` on, the first exchange is then handed to the loaded
+//! model in the background with a request for a short title. The title is
+//! saved through `update_chat_session`, like a rename from the UI, but only
+//! while the session still has its automatic title, and
+//! `session-title-updated` tells the UI about it.
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter };
+
+use crate::{ chat, constants };
+
+/// Characters of each message shown to the model
+const MAX_EXCERPT_CHARS: usize = 1500;
+
+const TITLE_INSTRUCTION: &str = "You name chat conversations. Reply with a title of at most six words \
+    that says what the conversation is about. Use the conversation's language. Reply with the title only: \
+    no quotes, no trailing period.";
+
+/// The first user message and reply of a session
+#[derive(Debug, Clone)]
+pub(crate) struct FirstExchange {
+    pub session_id: String,
+    /// Title the session was given from the first message
+    pub auto_title: String,
+    pub user: String,
+    pub assistant: String,
+}
+
+/// Payload of `session-title-updated`
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTitleUpdated {
+    pub session_id: String,
+    pub title: String,
+}
+
+fn excerpt(text: &str) -> String {
+    let text = chat::strip_tool_xml_tags(text);
+    match text.char_indices().nth(MAX_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// The model's answer reduced to a title: first line, without reasoning,
+/// labels, quotes or a trailing period
+fn clean_title(raw: &str) -> Option<String> {
+    let raw = match raw.rfind("</think>") {
+        Some(end) => &raw[end + "</think>".len()..],
+        None => raw,
+    };
+    let line = raw.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = ["title:", "**title:**"]
+        .iter()
+        .find_map(|label| {
+            line.get(..label.len())
+                .filter(|start| start.eq_ignore_ascii_case(label))
+                .map(|_| &line[label.len()..])
+        })
+        .unwrap_or(line);
+    let title = line
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '*' | '#' | '`' | '“' | '”'))
+        .trim_end_matches('.')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let title = match title.char_indices().nth(constants::MAX_CHAT_TITLE_LENGTH) {
+        Some((end, _)) => format!("{}...", title[..end].trim_end()),
+        None => title,
+    };
+    (!title.is_empty()).then_some(title)
+}
+
+async fn generate(app: &AppHandle, exchange: &FirstExchange) -> Result<String, String> {
+    let model = crate::ovms::route_model(app, None).await?;
+
+    let config = OpenAIConfig::new()
+        .with_api_key("unused")
+        .with_api_base(crate::ovms::openai_api_base());
+    let client = Client::with_config(config);
+
+    let conversation = format!("User: {}\n\nAssistant: {}", excerpt(&exchange.user), excerpt(&exchange.assistant));
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(vec![
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(TITLE_INSTRUCTION)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(conversation)
+                .build()
+                .map_err(|e| format!("Failed to build user message: {}", e))?
+                .into(),
+        ])
+        .temperature(0.3)
+        .max_tokens(32u32)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let response = client.chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Title request failed: {}", e))?;
+
+    response.choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .as_deref()
+        .and_then(clean_title)
+        .ok_or_else(|| "The model returned no title".to_string())
+}
+
+async fn retitle(app: &AppHandle, exchange: FirstExchange) -> Result<(), String> {
+    let title = generate(app, &exchange).await?;
+
+    // The user may have renamed the session while the model was busy
+    let storage = chat::load_chat_sessions().await?;
+    let Some(session) = storage.sessions.get(&exchange.session_id) else {
+        return Ok(());
+    };
+    if session.title != exchange.auto_title {
+        tracing::debug!(session_id = %exchange.session_id, "Session was renamed, keeping its title");
+        return Ok(());
+    }
+
+    chat::update_chat_session(exchange.session_id.clone(), Some(title.clone()), None, Some(session.revision)).await?;
+    tracing::info!(session_id = %exchange.session_id, title = %title, "Generated session title");
+    let _ = app.emit("session-title-updated", SessionTitleUpdated { session_id: exchange.session_id, title });
+    Ok(())
+}
+
+/// Title the session from its first exchange in the background
+pub(crate) fn spawn(app: AppHandle, exchange: FirstExchange) {
+    tauri::async_runtime::spawn(async move {
+        let session_id = exchange.session_id.clone();
+        if let Err(e) = retitle(&app, exchange).await {
+            log_warning!("Could not generate a session title", session_id = %session_id, error = %e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_title_strips_labels_quotes_and_reasoning() {
+        assert_eq!(clean_title("\"Rust Lifetimes Explained.\"").as_deref(), Some("Rust Lifetimes Explained"));
+        assert_eq!(clean_title("Title: Planning a trip to Kyoto\nExtra line").as_deref(), Some("Planning a trip to Kyoto"));
+        assert_eq!(clean_title("<think>The user asks about tea.</think>\n\n**Green Tea Benefits**").as_deref(), Some("Green Tea Benefits"));
+        assert_eq!(clean_title("  \n ").as_deref(), None);
+
+        let long = "word ".repeat(30);
+        let title = clean_title(&long).unwrap();
+        assert!(title.ends_with("...") && title.chars().count() <= constants::MAX_CHAT_TITLE_LENGTH + 3);
+    }
+}
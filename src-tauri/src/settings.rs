@@ -0,0 +1,770 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::{debug, warn};
+
+use crate::{constants, paths};
+use crate::coalesce::CoalesceMode;
+
+/// Backend settings persisted in `~/.sparrow/settings.json`.
+///
+/// UI-only preferences stay in the frontend store; this file holds what the
+/// backend needs before (or without) the frontend, such as startup behavior.
+/// Every section uses `#[serde(default)]` so older files keep loading as new
+/// fields are added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub autostart: AutostartSettings,
+    pub ovms: OvmsSettings,
+    pub rag: RagSettings,
+    pub quick_actions: QuickActionSettings,
+    pub language: LanguageSettings,
+    pub downloads: DownloadSettings,
+    pub huggingface: HuggingFaceSettings,
+    pub streaming: StreamingSettings,
+    pub updates: UpdateSettings,
+    pub plugins: PluginSettings,
+    pub code_sandbox: CodeSandboxSettings,
+    pub digest: DigestSettings,
+    pub filesystem: FilesystemSettings,
+    pub temp_files: TempFileSettings,
+    pub http: HttpSettings,
+    pub model_cache: ModelCacheSettings,
+    pub journal: JournalSettings,
+    pub chat: ChatSettings,
+    pub disk: DiskSettings,
+    /// Locale for backend-rendered messages, e.g. "de-DE"; English when unset
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutostartSettings {
+    /// Seconds to wait after an autostart launch before starting background work
+    pub delay_secs: u64,
+    /// Hide the main window to the tray when launched by autostart
+    pub start_minimized: bool,
+    /// Do not start OVMS on an autostart launch; start it on the first chat instead
+    pub defer_ovms: bool,
+}
+
+impl Default for AutostartSettings {
+    fn default() -> Self {
+        Self {
+            delay_secs: 0,
+            start_minimized: true,
+            defer_ovms: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OvmsSettings {
+    /// Skip OVMS download/startup at launch; bring it up on the first operation that needs it
+    pub lazy_init: bool,
+    /// Retry a model on `fallback_device` when it fails to load on a GPU/NPU
+    pub device_fallback: bool,
+    pub fallback_device: String,
+    /// REST port OVMS listens on
+    pub rest_port: u16,
+    /// gRPC port; OVMS serves no gRPC when unset
+    pub grpc_port: Option<u16>,
+    /// Use a free port when the configured one is taken by another program
+    pub auto_port: bool,
+    /// How long to wait for a starting OVMS to answer before giving up
+    pub startup_timeout_secs: u64,
+    /// Restart OVMS when it crashes or stops answering
+    pub watchdog: bool,
+    pub watchdog_interval_secs: u64,
+    /// Text model chat and tasks use when a request does not name one
+    pub default_model: Option<String>,
+    /// Command-line flags OVMS is started with
+    pub runtime: OvmsRuntimeParams,
+    /// Start OVMS with `--metrics_enable` and scrape it for the dashboard
+    pub metrics: bool,
+    pub metrics_interval_secs: u64,
+    /// How embedding and reranker models are placed next to the LLM
+    pub rag_models: RagModelSettings,
+}
+
+impl Default for OvmsSettings {
+    fn default() -> Self {
+        Self {
+            lazy_init: false,
+            device_fallback: true,
+            fallback_device: "CPU".to_string(),
+            rest_port: constants::OVMS_DEFAULT_PORT,
+            grpc_port: None,
+            auto_port: true,
+            startup_timeout_secs: 120,
+            watchdog: true,
+            watchdog_interval_secs: 15,
+            default_model: None,
+            runtime: OvmsRuntimeParams::default(),
+            metrics: false,
+            metrics_interval_secs: 5,
+            rag_models: RagModelSettings::default(),
+        }
+    }
+}
+
+/// OVMS launch flags; unset values leave the OVMS default. Changes apply the
+/// next time OVMS starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OvmsRuntimeParams {
+    /// `--log_level`: TRACE, DEBUG, INFO, WARNING or ERROR
+    pub log_level: String,
+    /// `--grpc_workers`
+    pub grpc_workers: Option<u32>,
+    /// `--rest_workers`
+    pub rest_workers: Option<u32>,
+    /// `--cache_dir`: where OVMS caches compiled models
+    pub cache_dir: Option<String>,
+    /// `--file_system_poll_wait_seconds`: how often OVMS checks the config file (0 = never)
+    pub file_system_poll_wait_seconds: Option<u32>,
+}
+
+impl Default for OvmsRuntimeParams {
+    fn default() -> Self {
+        Self {
+            log_level: "INFO".to_string(),
+            grpc_workers: None,
+            rest_workers: None,
+            cache_dir: None,
+            file_system_poll_wait_seconds: None,
+        }
+    }
+}
+
+/// Placement of the embedding and reranker models, so retrieval stays
+/// responsive while a large LLM is generating. Graph options apply the next
+/// time the model is loaded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RagModelSettings {
+    /// Device the RAG graphs run on (e.g. CPU to keep them off a busy GPU); unset keeps the graph's own
+    pub device: Option<String>,
+    /// `NUM_STREAMS` plugin option of the RAG graphs; unset leaves the OpenVINO default
+    pub num_streams: Option<u32>,
+    /// List RAG models ahead of the LLMs in the OVMS config so they load first
+    pub load_first: bool,
+}
+
+impl Default for RagModelSettings {
+    fn default() -> Self {
+        Self {
+            device: None,
+            num_streams: None,
+            load_first: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RagSettings {
+    /// Largest file accepted for indexing, in megabytes (0 = no limit)
+    pub max_file_size_mb: u64,
+    /// Background flush interval of the vector store in milliseconds (0 = only explicit flushes)
+    pub flush_interval_ms: u64,
+    /// Where documents and embeddings are stored
+    pub backend: VectorBackendKind,
+    pub qdrant: QdrantSettings,
+    pub knowledge_graph: KnowledgeGraphSettings,
+    pub retrieval: RetrievalSettings,
+    pub hnsw: HnswSettings,
+}
+
+impl Default for RagSettings {
+    fn default() -> Self {
+        Self {
+            max_file_size_mb: 100,
+            flush_interval_ms: 500,
+            backend: VectorBackendKind::default(),
+            qdrant: QdrantSettings::default(),
+            knowledge_graph: KnowledgeGraphSettings::default(),
+            retrieval: RetrievalSettings::default(),
+            hnsw: HnswSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KnowledgeGraphSettings {
+    /// Extract entities and relations from documents after ingestion, and add
+    /// related facts to RAG context
+    pub enabled: bool,
+    /// Served model name used for extraction; the loaded text model when unset
+    pub model: Option<String>,
+    /// Facts added to the RAG context at most
+    pub max_facts: usize,
+}
+
+impl Default for KnowledgeGraphSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            max_facts: 12,
+        }
+    }
+}
+
+/// Approximate nearest-neighbor index of large sled collections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HnswSettings {
+    /// Search large collections through the index instead of scoring every chunk
+    pub enabled: bool,
+    /// Collections with fewer chunks are always searched exactly
+    pub min_documents: usize,
+    /// Links per node; more give better recall at the cost of memory and build time
+    pub m: usize,
+    /// Candidates considered while linking a new node
+    pub ef_construction: usize,
+    /// Candidates considered per search; raised to the result count when lower
+    pub ef_search: usize,
+}
+
+impl Default for HnswSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_documents: 20_000,
+            m: 16,
+            ef_construction: 200,
+            ef_search: 96,
+        }
+    }
+}
+
+/// Defaults of the RAG retrieval pipeline; a chat request may override them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrievalSettings {
+    /// Chunks returned by vector search and handed to the reranker
+    pub candidates: usize,
+    /// Reranked chunks put into the prompt
+    pub top_n: usize,
+    /// `candidates` when a message has documents attached and only their chunks are searched
+    pub attached_candidates: usize,
+    /// `top_n` when a message has documents attached
+    pub attached_top_n: usize,
+    /// Characters of each chunk put into the prompt (0 = the whole chunk)
+    pub max_chunk_chars: usize,
+    /// Chunks less similar to the question than this are dropped before reranking
+    pub min_score: f32,
+}
+
+impl RetrievalSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("candidates", self.candidates),
+            ("top_n", self.top_n),
+            ("attached_candidates", self.attached_candidates),
+            ("attached_top_n", self.attached_top_n),
+        ] {
+            if value == 0 {
+                return Err(format!("rag.retrieval.{} must be at least 1", name));
+            }
+        }
+        if !self.min_score.is_finite() {
+            return Err(format!("rag.retrieval.min_score must be a number, got {}", self.min_score));
+        }
+        Ok(())
+    }
+}
+
+impl Default for RetrievalSettings {
+    fn default() -> Self {
+        Self {
+            candidates: 10,
+            top_n: 5,
+            attached_candidates: 100,
+            attached_top_n: 10,
+            max_chunk_chars: 0,
+            min_score: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorBackendKind {
+    /// Embedded sled database under `~/.sparrow/vector_store`
+    #[default]
+    Sled,
+    /// External Qdrant server, for corpora too large for brute-force search
+    Qdrant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QdrantSettings {
+    pub url: String,
+    /// Only read to move a key saved by older versions into the credential
+    /// store; `set_qdrant_api_key` keeps the key there
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Prepended to Sparrow collection names to form Qdrant collection names
+    pub collection_prefix: String,
+}
+
+impl Default for QdrantSettings {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:6333".to_string(),
+            api_key: None,
+            collection_prefix: "sparrow_".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickActionSettings {
+    /// Action run on text sent from other apps when the sender does not name one
+    pub selection_action: String,
+    /// Target language of the `translate` action
+    pub translate_language: String,
+}
+
+impl Default for QuickActionSettings {
+    fn default() -> Self {
+        Self {
+            selection_action: "explain".to_string(),
+            translate_language: "English".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageSettings {
+    /// Language replies should be written in (e.g. "German"); chat sessions may override it
+    pub preferred: Option<String>,
+    /// Check finished replies and re-ask when they are clearly in another language
+    pub verify: bool,
+    /// Re-asks per reply when verification fails
+    pub max_reasks: u32,
+}
+
+impl Default for LanguageSettings {
+    fn default() -> Self {
+        Self {
+            preferred: None,
+            verify: true,
+            max_reasks: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadSettings {
+    /// Files of a model fetched at the same time (1 = sequential)
+    pub concurrency: usize,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: constants::DEFAULT_DOWNLOAD_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HuggingFaceSettings {
+    /// Send the access token from the credential store with Hugging Face requests
+    pub use_token: bool,
+    /// Account the stored token belongs to, for display
+    pub username: Option<String>,
+    /// Organizations whose models can be searched and downloaded
+    pub allowed_orgs: Vec<String>,
+}
+
+impl Default for HuggingFaceSettings {
+    fn default() -> Self {
+        Self {
+            use_token: true,
+            username: None,
+            allowed_orgs: vec![constants::OPENVINO_ORG.to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingSettings {
+    /// How streamed tokens are batched before reaching the UI
+    pub coalesce: CoalesceMode,
+    /// Longest a token is held back, in milliseconds
+    pub interval_ms: u64,
+    /// Buffered characters that force a chunk out early
+    pub max_chars: usize,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self {
+            coalesce: CoalesceMode::Off,
+            interval_ms: 250,
+            max_chars: 400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Pre-releases as well as stable releases
+    Beta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    /// GitHub releases API endpoint listing the app's releases
+    pub releases_url: String,
+    /// Versions the user chose not to be offered again
+    pub skipped_versions: Vec<String>,
+    /// No update prompt before this time (Unix ms)
+    pub deferred_until: Option<i64>,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            releases_url: constants::APP_RELEASES_URL.to_string(),
+            skipped_versions: Vec::new(),
+            deferred_until: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginSettings {
+    /// Enabled plugin ids, each with the fingerprint of the module and
+    /// capabilities the user approved (see `plugins::fingerprint`)
+    pub enabled: HashMap<String, String>,
+}
+
+/// Limits and interpreters for the `run_sandboxed_code` tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeSandboxSettings {
+    /// WASI build of CPython, downloaded on first use
+    pub python_runtime_url: String,
+    /// Hex SHA-256 the Python runtime must have; nothing is downloaded without it
+    pub python_runtime_sha256: Option<String>,
+    pub max_memory_mb: u64,
+    pub timeout_ms: u64,
+    /// Instruction budget per run (wasmtime fuel)
+    pub fuel: u64,
+}
+
+impl Default for CodeSandboxSettings {
+    fn default() -> Self {
+        Self {
+            python_runtime_url: constants::SANDBOX_PYTHON_RUNTIME_URL.to_string(),
+            python_runtime_sha256: None,
+            max_memory_mb: 256,
+            timeout_ms: 10_000,
+            fuel: 10_000_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestSettings {
+    /// Folder the memory review notes are written to (`~/.sparrow/digests` when unset)
+    pub output_dir: Option<String>,
+    /// The user deleted the built-in weekly review task; don't add it again
+    pub builtin_task_removed: bool,
+}
+
+/// Extra folders filesystem commands and tools may use (see `path_policy`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilesystemSettings {
+    /// Readable in addition to the home directory
+    pub read_roots: Vec<String>,
+    /// Writable (and deletable) in addition to `~/.sparrow`, e.g. a model folder on another drive
+    pub write_roots: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TempFileSettings {
+    /// Hours staged uploads are kept before maintenance removes them (0 = keep)
+    pub ttl_hours: u64,
+}
+
+impl Default for TempFileSettings {
+    fn default() -> Self {
+        Self { ttl_hours: 24 }
+    }
+}
+
+/// Size cap for the compiled-model caches (see `model_cache`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelCacheSettings {
+    /// Total size of all `.ovms_cache` folders kept by maintenance (0 = no limit)
+    pub max_size_mb: u64,
+}
+
+/// Options for every outgoing HTTP client (see `http`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+    pub user_agent: String,
+    /// Sent with every request, e.g. for a corporate proxy
+    pub extra_headers: HashMap<String, String>,
+    /// PEM file with an extra root certificate (TLS-intercepting proxies)
+    pub ca_certificate_path: Option<String>,
+    /// Skip certificate validation; only for debugging proxy setups
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            user_agent: constants::USER_AGENT.to_string(),
+            extra_headers: HashMap::new(),
+            ca_certificate_path: None,
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+/// Daily notes (see `journal`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalSettings {
+    /// Folder the notes are kept in (`~/.sparrow/journal` when unset)
+    pub folder: Option<String>,
+    /// Markdown a new note starts from; `{{date}}`, `{{weekday}}` and `{{time}}` are filled in
+    pub template: Option<String>,
+    /// Index each note into the "journal" collection whenever it changes
+    pub auto_ingest: bool,
+    /// Append the outcome of every scheduled task run to the day's note
+    pub log_task_runs: bool,
+}
+
+impl Default for JournalSettings {
+    fn default() -> Self {
+        Self {
+            folder: None,
+            template: None,
+            auto_ingest: true,
+            log_task_runs: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatSettings {
+    /// Rounds of tool calls one reply may make before the model must answer
+    pub max_tool_rounds: u32,
+    /// Per-tool override of whether a call waits for the user's approval
+    /// (see `tool_approval`), keyed by the tool name the model sees
+    pub confirm_tools: HashMap<String, bool>,
+    /// Unanswered approval requests are declined after this long
+    pub tool_approval_timeout_secs: u64,
+    /// Times a completion request is retried while OVMS answers 429/503
+    pub busy_retries: u32,
+    /// Wait before the first retry; doubles with each one
+    pub busy_retry_delay_ms: u64,
+    /// Times a reply that fails its `response_format` is asked for again
+    pub json_retries: u32,
+    /// Name for `{{user_name}}` in prompt presets; the account name when unset
+    pub user_name: Option<String>,
+    /// After the first exchange, have the model title the session (see `session_titles`)
+    pub llm_titles: bool,
+}
+
+impl Default for ChatSettings {
+    fn default() -> Self {
+        Self {
+            max_tool_rounds: 5,
+            confirm_tools: HashMap::new(),
+            tool_approval_timeout_secs: 120,
+            busy_retries: 3,
+            busy_retry_delay_ms: 1000,
+            json_retries: 1,
+            user_name: None,
+            llm_titles: false,
+        }
+    }
+}
+
+/// Free-space watchdog for the data drive (see `disk_watchdog`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskSettings {
+    pub watchdog: bool,
+    pub check_interval_secs: u64,
+    /// Warn when less than this is free on the drive holding `~/.sparrow`
+    pub warn_free_mb: u64,
+    /// Warn again, more urgently, below this
+    pub critical_free_mb: u64,
+}
+
+impl Default for DiskSettings {
+    fn default() -> Self {
+        Self {
+            watchdog: true,
+            check_interval_secs: 300,
+            warn_free_mb: 10 * 1024,
+            critical_free_mb: 2 * 1024,
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Arc<Mutex<AppSettings>>> = OnceLock::new();
+
+fn settings_cell() -> &'static Arc<Mutex<AppSettings>> {
+    SETTINGS.get_or_init(|| Arc::new(Mutex::new(load_settings_from_file())))
+}
+
+fn load_settings_from_file() -> AppSettings {
+    let path = match paths::get_settings_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!(error = %e, "Failed to resolve settings path, using defaults");
+            return AppSettings::default();
+        }
+    };
+
+    if !path.exists() {
+        return AppSettings::default();
+    }
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to parse settings file, using defaults");
+            AppSettings::default()
+        }),
+        Err(e) => {
+            warn!(error = %e, "Failed to read settings file, using defaults");
+            AppSettings::default()
+        }
+    }
+}
+
+fn save_settings_to_file(settings: &AppSettings) -> Result<(), String> {
+    let path = paths::get_settings_path().map_err(|e| e.to_string())?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+
+    debug!("Saved settings");
+    Ok(())
+}
+
+/// Snapshot of the current settings
+pub fn current() -> AppSettings {
+    settings_cell().lock().unwrap().clone()
+}
+
+/// Apply `f` to the settings and persist the result
+pub fn update<F>(f: F) -> Result<AppSettings, String>
+where
+    F: FnOnce(&mut AppSettings),
+{
+    let mut settings = settings_cell().lock().unwrap();
+    let mut updated = settings.clone();
+    f(&mut updated);
+    save_settings_to_file(&updated)?;
+    *settings = updated.clone();
+    Ok(updated)
+}
+
+/// Recursively merge `patch` into `base`; objects merge key by key, anything else replaces
+fn merge_json(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base_map), Value::Object(patch_map)) => {
+            for (key, value) in patch_map {
+                merge_json(base_map.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+#[tauri::command]
+pub async fn get_app_settings() -> Result<AppSettings, String> {
+    Ok(current())
+}
+
+/// Update settings from a partial object, e.g. `{ "autostart": { "delay_secs": 30 } }`
+#[tauri::command]
+pub async fn update_app_settings(patch: Value) -> Result<AppSettings, String> {
+    let mut merged = serde_json::to_value(current())
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    merge_json(&mut merged, patch);
+
+    let new_settings: AppSettings = serde_json::from_value(merged)
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    new_settings.rag.retrieval.validate()?;
+
+    update(|settings| *settings = new_settings)
+}
+
+#[tauri::command]
+pub async fn reset_app_settings() -> Result<AppSettings, String> {
+    update(|settings| *settings = AppSettings::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_json_partial_update() {
+        let mut base = serde_json::to_value(AppSettings::default()).unwrap();
+        merge_json(&mut base, json!({ "autostart": { "delay_secs": 30 } }));
+
+        let settings: AppSettings = serde_json::from_value(base).unwrap();
+        assert_eq!(settings.autostart.delay_secs, 30);
+        assert!(settings.autostart.start_minimized);
+    }
+
+    #[test]
+    fn test_missing_sections_use_defaults() {
+        let settings: AppSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.autostart.delay_secs, 0);
+        assert!(!settings.autostart.defer_ovms);
+    }
+
+    #[test]
+    fn test_retrieval_settings_validation() {
+        assert!(RetrievalSettings::default().validate().is_ok());
+        let zero_top_n = RetrievalSettings { attached_top_n: 0, ..Default::default() };
+        assert_eq!(zero_top_n.validate().unwrap_err(), "rag.retrieval.attached_top_n must be at least 1");
+    }
+}
@@ -0,0 +1,107 @@
+//! Everything a window needs to resynchronize, in one call.
+//!
+//! A reloaded or newly opened window has missed every event sent before it
+//! subscribed. `get_app_state_snapshot` collects the state those events
+//! describe, so the window can start from the snapshot and apply events
+//! from there on.
+
+use std::time::Duration;
+
+use chrono::{ DateTime, Utc };
+use serde::Serialize;
+use tauri::{ AppHandle, Manager };
+
+use crate::cancellation::{ self, OperationKind };
+use crate::huggingface::DownloadControl;
+use crate::init::InitializationStatus;
+use crate::notifications::{ self, AppNotification };
+use crate::ovms;
+use crate::state::AppState;
+use crate::tasks;
+
+/// A stuck OVMS must not stall the snapshot
+const OVMS_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OvmsHealth {
+    pub reachable: bool,
+    /// Models OVMS reports AVAILABLE
+    pub available_models: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ActiveJob {
+    Task { task_id: String, name: String },
+    Download { model_id: String, state: DownloadControl },
+}
+
+#[derive(Clone, Serialize)]
+pub struct AppStateSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub init_status: InitializationStatus,
+    pub ovms: OvmsHealth,
+    /// The text model in the OVMS config
+    pub loaded_model: Option<String>,
+    /// Ids of chat and text-assist streams in flight
+    pub active_streams: Vec<String>,
+    pub active_jobs: Vec<ActiveJob>,
+    pub unread_notifications: Vec<AppNotification>,
+}
+
+async fn ovms_health() -> OvmsHealth {
+    let error = match tokio::time::timeout(OVMS_PROBE_TIMEOUT, ovms::check_ovms_status()).await {
+        Ok(Ok(status)) => {
+            return OvmsHealth { reachable: true, available_models: status.loaded_models, error: None };
+        }
+        Ok(Err(e)) => e,
+        Err(_) => format!("OVMS did not answer within {}s", OVMS_PROBE_TIMEOUT.as_secs()),
+    };
+    OvmsHealth { reachable: false, available_models: Vec::new(), error: Some(error) }
+}
+
+#[tauri::command]
+pub async fn get_app_state_snapshot(app: AppHandle) -> Result<AppStateSnapshot, String> {
+    let (ovms, loaded_model) = tokio::join!(ovms_health(), ovms::get_loaded_model(app.clone()));
+    let state = app.state::<AppState>();
+
+    let active_streams: Vec<String> = cancellation::running(&state, &[OperationKind::Chat, OperationKind::TextAssist])
+        .into_iter()
+        .map(|operation| operation.id)
+        .collect();
+
+    let mut active_jobs: Vec<ActiveJob> = tasks::running_tasks()
+        .into_iter()
+        .map(|task| ActiveJob::Task { task_id: task.id, name: task.name })
+        .collect();
+    let mut downloads: Vec<ActiveJob> = state.downloads
+        .lock()
+        .iter()
+        .map(|(model_id, control)| ActiveJob::Download { model_id: model_id.clone(), state: *control.borrow() })
+        .collect();
+    downloads.sort_by(|a, b| job_key(a).cmp(job_key(b)));
+    active_jobs.sort_by(|a, b| job_key(a).cmp(job_key(b)));
+    active_jobs.extend(downloads);
+
+    Ok(AppStateSnapshot {
+        taken_at: Utc::now(),
+        init_status: state.init_status.lock().clone(),
+        ovms,
+        loaded_model: loaded_model.unwrap_or_else(|e| {
+            log_warning!("Snapshot without loaded model", error = %e);
+            None
+        }),
+        active_streams,
+        active_jobs,
+        unread_notifications: notifications::unread(&state),
+    })
+}
+
+/// Stable ordering, so two snapshots of the same state compare equal
+fn job_key(job: &ActiveJob) -> &str {
+    match job {
+        ActiveJob::Task { task_id, .. } => task_id,
+        ActiveJob::Download { model_id, .. } => model_id,
+    }
+}
@@ -0,0 +1,89 @@
+//! Backend state shared across commands, registered with `app.manage()`.
+//!
+//! Uses `parking_lot` locks, which are not poisoned when a holder panics:
+//! one failed command can no longer turn every later `lock()` into a panic.
+//! Commands take `State<'_, AppState>`; code holding only an `AppHandle`
+//! uses `app_handle.state::<AppState>()`.
+
+use std::collections::HashMap;
+use std::process::Child;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use tokio::sync::{ oneshot, watch };
+
+use crate::cancellation::Operation;
+use crate::disk_watchdog::DiskStatus;
+use crate::huggingface::DownloadControl;
+use crate::init::InitializationStatus;
+use crate::notifications::AppNotification;
+use crate::ovms_metrics::OvmsMetrics;
+use crate::ovms_watchdog::OvmsHealth;
+use crate::permissions::Confirmation;
+use crate::selection::SelectionPrompt;
+
+pub struct AppState {
+    pub init_status: Mutex<InitializationStatus>,
+    /// Cancellable operations in flight (streams, downloads, ingestion), keyed by job id
+    pub operations: Mutex<HashMap<String, Operation>>,
+    /// The OVMS child process started by this app, if any
+    pub ovms_process: Mutex<Option<Child>>,
+    /// Last result of the OVMS watchdog
+    pub ovms_health: Mutex<OvmsHealth>,
+    /// Last scrape of the OVMS metrics endpoint
+    pub ovms_metrics: Mutex<OvmsMetrics>,
+    /// Last result of the disk space watchdog
+    pub disk_status: Mutex<DiskStatus>,
+    /// Text sent from another app, waiting for the frontend to pick it up
+    pub pending_selection: Mutex<Option<SelectionPrompt>>,
+    /// Pause/cancel switches for in-flight model downloads, keyed by model id
+    pub downloads: Mutex<HashMap<String, watch::Sender<DownloadControl>>>,
+    /// Notifications shown since launch, newest last
+    pub notifications: Mutex<Vec<AppNotification>>,
+    /// Unspent confirmation tokens for sensitive commands, keyed by token
+    pub confirmations: Mutex<HashMap<String, Confirmation>>,
+    /// Windows allowed to run sensitive commands without tokens, until the given time
+    pub elevated_windows: Mutex<HashMap<String, Instant>>,
+    /// Tool calls waiting for the user's approval, keyed by request id
+    pub tool_requests: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            init_status: Mutex::new(InitializationStatus::new("not_started")),
+            operations: Mutex::new(HashMap::new()),
+            ovms_process: Mutex::new(None),
+            ovms_health: Mutex::new(OvmsHealth::default()),
+            ovms_metrics: Mutex::new(OvmsMetrics::default()),
+            disk_status: Mutex::new(DiskStatus::default()),
+            pending_selection: Mutex::new(None),
+            downloads: Mutex::new(HashMap::new()),
+            notifications: Mutex::new(Vec::new()),
+            confirmations: Mutex::new(HashMap::new()),
+            elevated_windows: Mutex::new(HashMap::new()),
+            tool_requests: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lock_usable_after_panic_while_held() {
+        let state = Arc::new(AppState::default());
+
+        let panicking = Arc::clone(&state);
+        let result = std::thread::spawn(move || {
+            let _guard = panicking.elevated_windows.lock();
+            panic!("command failed while holding the lock");
+        }).join();
+        assert!(result.is_err());
+
+        state.elevated_windows.lock().insert("main".to_string(), Instant::now());
+        assert_eq!(state.elevated_windows.lock().len(), 1);
+    }
+}
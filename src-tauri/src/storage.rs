@@ -0,0 +1,67 @@
+//! Async file helpers for the JSON files under `~/.sparrow`.
+//!
+//! Commands run on the async runtime, so persistence goes through `tokio::fs`
+//! instead of blocking `std::fs`. Writes go to a sibling temp file that is
+//! then renamed over the target, so a crash or a concurrent reader never sees
+//! a half-written file. Each write gets its own temp file, so concurrent
+//! writers of one file cannot clobber each other's half-written data.
+
+use std::io;
+use std::path::{ Path, PathBuf };
+
+/// Read a file as UTF-8 text, returning `None` if it does not exist
+pub async fn read_string(path: &Path) -> io::Result<Option<String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Replace the contents of `path`, creating parent directories as needed
+pub async fn write_string(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let tmp_path = temp_path_for(path);
+    let result = match tokio::fs::write(&tmp_path, contents).await {
+        Ok(()) => tokio::fs::rename(&tmp_path, path).await,
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+    }
+    result
+}
+
+/// `<file>.<random>.tmp` next to `path`
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_then_read_round_trip() {
+        let dir = std::env::temp_dir().join(format!("sparrow-storage-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("data.json");
+
+        assert_eq!(read_string(&path).await.unwrap(), None);
+
+        write_string(&path, "{\"a\":1}").await.unwrap();
+        write_string(&path, "{\"a\":2}").await.unwrap();
+        assert_eq!(read_string(&path).await.unwrap().as_deref(), Some("{\"a\":2}"));
+        assert_ne!(temp_path_for(&path), temp_path_for(&path));
+
+        let (first, second) = tokio::join!(write_string(&path, "1"), write_string(&path, "2"));
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
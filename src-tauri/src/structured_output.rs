@@ -0,0 +1,440 @@
+//! Structured output: JSON mode for chat replies.
+//!
+//! A chat request may carry a `response_format`, either `json_object` (any
+//! JSON object) or `json_schema` (JSON matching a schema). It is passed to
+//! OVMS as the OpenAI `response_format`, which constrains generation where
+//! the graph supports it. Since that is not guaranteed, the finished reply is
+//! checked here as well: it must parse, and with a schema it must satisfy the
+//! schema's keywords covered by `check`. Only the text after the last tool
+//! exchange is checked, since that is the answer. A reply that does not pass
+//! is asked for again, with the problems listed, up to `chat.json_retries`
+//! times; the re-ask streams like any chat turn, so it waits out a busy
+//! server and stops when the chat is cancelled.
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestAssistantMessageArgs,
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+    ResponseFormat,
+    ResponseFormatJsonSchema,
+};
+use futures::StreamExt;
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use tauri::AppHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::cancellation::OperationKind;
+use crate::server_busy;
+
+/// Problems listed in a validation error; the rest are counted
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// `response_format` of a chat request, in the OpenAI shape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatResponseFormat {
+    JsonObject,
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub schema: Value,
+    /// Asks the server to follow the schema exactly
+    #[serde(default)]
+    pub strict: Option<bool>,
+}
+
+impl ChatResponseFormat {
+    pub fn to_request(&self) -> ResponseFormat {
+        match self {
+            Self::JsonObject => ResponseFormat::JsonObject,
+            Self::JsonSchema { json_schema } => ResponseFormat::JsonSchema {
+                json_schema: ResponseFormatJsonSchema {
+                    name: json_schema.name.clone(),
+                    description: json_schema.description.clone(),
+                    schema: Some(json_schema.schema.clone()),
+                    strict: json_schema.strict,
+                },
+            },
+        }
+    }
+
+    /// The JSON in `reply`, or what is wrong with it
+    pub fn validate(&self, reply: &str) -> Result<Value, String> {
+        let json = extract_json(reply);
+        let value: Value = serde_json::from_str(json).map_err(|e| format!("The reply is not valid JSON: {}", e))?;
+        match self {
+            Self::JsonObject if !value.is_object() => {
+                Err(format!("The reply is {} rather than a JSON object", type_name(&value)))
+            }
+            Self::JsonObject => Ok(value),
+            Self::JsonSchema { json_schema } => {
+                let mut errors = Vec::new();
+                check(&value, &json_schema.schema, "$", &mut errors);
+                if errors.is_empty() {
+                    return Ok(value);
+                }
+                let more = errors.len().saturating_sub(MAX_REPORTED_ERRORS);
+                errors.truncate(MAX_REPORTED_ERRORS);
+                let mut message = format!("The reply does not match the schema: {}", errors.join("; "));
+                if more > 0 {
+                    message.push_str(&format!(" (and {} more)", more));
+                }
+                Err(message)
+            }
+        }
+    }
+}
+
+/// Where the answer starts in a reply: after the last `<tool_call>` or
+/// `<tool_response>` block the chat wrote into it, or at 0 without tools
+pub(crate) fn answer_start(reply: &str) -> usize {
+    ["</tool_call>", "</tool_response>"]
+        .iter()
+        .filter_map(|tag| reply.rfind(tag).map(|at| at + tag.len()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// The JSON part of a reply: a fenced block's content, or the span from the
+/// first opening to the last closing bracket when there is text around it
+fn extract_json(reply: &str) -> &str {
+    let reply = reply.trim();
+    if let Some(fenced) = reply.strip_prefix("```") {
+        let body = fenced.split_once('\n').map_or("", |(_, body)| body);
+        return body.rsplit_once("```").map_or(body, |(body, _)| body).trim();
+    }
+    let start = reply.find(['{', '[']);
+    let end = reply.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => reply,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // Unknown types are not ours to reject
+        _ => true,
+    }
+}
+
+/// Check `value` against `schema`, adding one entry per problem to `errors`.
+/// Covers type, enum, const, required, properties, additionalProperties,
+/// items, anyOf/oneOf/allOf (oneOf requiring exactly one match) and the
+/// length and range bounds; `$ref` and formats are not followed.
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{} is not allowed", path));
+        }
+        return;
+    };
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+        errors.push(format!("{} should be {} but is {}", path, types.join(" or "), type_name(value)));
+        return;
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!("{} should be one of {}", path, Value::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            errors.push(format!("{} should be {}", path, expected));
+        }
+    }
+
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        for sub in all {
+            check(value, sub, path, errors);
+        }
+    }
+    let matching = |options: &[Value]| {
+        options.iter().filter(|sub| {
+            let mut sub_errors = Vec::new();
+            check(value, sub, path, &mut sub_errors);
+            sub_errors.is_empty()
+        }).count()
+    };
+    if let Some(Value::Array(options)) = schema.get("anyOf") {
+        if matching(options) == 0 {
+            errors.push(format!("{} matches none of the allowed shapes", path));
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("oneOf") {
+        match matching(options) {
+            0 => errors.push(format!("{} matches none of the allowed shapes", path)),
+            1 => {}
+            count => errors.push(format!("{} matches {} of the shapes but should match exactly one", path, count)),
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(key) {
+                        errors.push(format!("{} is missing \"{}\"", path, key));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in object {
+                let item_path = format!("{}.{}", path, key);
+                match (properties.and_then(|properties| properties.get(key)), schema.get("additionalProperties")) {
+                    (Some(sub), _) => check(item, sub, &item_path, errors),
+                    (None, Some(Value::Bool(false))) => errors.push(format!("{} is not an allowed property", item_path)),
+                    (None, Some(sub @ Value::Object(_))) => check(item, sub, &item_path, errors),
+                    (None, _) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{} should have at least {} items", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if (items.len() as u64) > max {
+                    errors.push(format!("{} should have at most {} items", path, max));
+                }
+            }
+            if let Some(sub) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(item, sub, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    errors.push(format!("{} should be at least {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    errors.push(format!("{} should be at most {} characters", path, max));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    errors.push(format!("{} should be at least {}", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    errors.push(format!("{} should be at most {}", path, max));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Ask for `answer` again after it failed validation with `error`. `answer` is
+/// the reply as streamed, tool exchanges included so their results stay in
+/// view; the result is only the new answer. Fails when `cancel` fires.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn reask(
+    app: &AppHandle,
+    client: &Client<OpenAIConfig>,
+    model_name: &str,
+    messages: &[ChatCompletionRequestMessage],
+    answer: &str,
+    format: &ChatResponseFormat,
+    error: &str,
+    stream_id: &str,
+    cancel: &CancellationToken
+) -> Result<String, String> {
+    let mut messages = messages.to_vec();
+    messages.push(
+        ChatCompletionRequestAssistantMessageArgs::default()
+            .content(answer.to_string())
+            .build()
+            .map_err(|e| format!("Failed to build assistant message: {}", e))?
+            .into()
+    );
+    let instruction = match format {
+        ChatResponseFormat::JsonObject => "Reply again with only a JSON object.".to_string(),
+        ChatResponseFormat::JsonSchema { json_schema } => format!(
+            "Reply again with only JSON that matches this schema:\n{}",
+            json_schema.schema
+        ),
+    };
+    messages.push(
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(format!("{} {}", error, instruction))
+            .build()
+            .map_err(|e| format!("Failed to build user message: {}", e))?
+            .into()
+    );
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model_name.to_string())
+        .messages(messages)
+        .temperature(0.2)
+        .response_format(format.to_request())
+        .stream(true)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let mut stream = server_busy::open_stream(app, client, request, stream_id, OperationKind::Chat, cancel).await
+        .map_err(|e| format!("Failed to ask for valid JSON: {}", e))?;
+    let mut corrected = String::new();
+    loop {
+        let next = tokio::select! {
+            _ = cancel.cancelled() => return Err("Re-ask cancelled".to_string()),
+            next = stream.next() => next,
+        };
+        let Some(result) = next else { break };
+        let response = result.map_err(|e| format!("Failed to ask for valid JSON: {}", e))?;
+        for choice in response.choices {
+            corrected.push_str(choice.delta.content.as_deref().unwrap_or_default());
+        }
+    }
+
+    if corrected.trim().is_empty() {
+        return Err("The model returned an empty answer".to_string());
+    }
+    Ok(corrected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema_format(schema: Value) -> ChatResponseFormat {
+        ChatResponseFormat::JsonSchema {
+            json_schema: JsonSchemaFormat { name: "answer".to_string(), description: None, schema, strict: None },
+        }
+    }
+
+    #[test]
+    fn test_format_deserializes_from_openai_shape() {
+        let format: ChatResponseFormat = serde_json::from_value(json!({ "type": "json_object" })).unwrap();
+        assert_eq!(format, ChatResponseFormat::JsonObject);
+
+        let format: ChatResponseFormat = serde_json::from_value(json!({
+            "type": "json_schema",
+            "json_schema": { "name": "city", "schema": { "type": "object" }, "strict": true }
+        })).unwrap();
+        assert_eq!(format, ChatResponseFormat::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: "city".to_string(),
+                description: None,
+                schema: json!({ "type": "object" }),
+                strict: Some(true),
+            },
+        });
+    }
+
+    #[test]
+    fn test_extract_json_from_fences_and_surrounding_text() {
+        assert_eq!(extract_json("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+        assert_eq!(extract_json("Here you go: [1, 2] hope it helps"), "[1, 2]");
+        assert_eq!(extract_json("  {\"a\": 1}  "), "{\"a\": 1}");
+        assert_eq!(extract_json("no json"), "no json");
+    }
+
+    #[test]
+    fn test_json_object_mode() {
+        let format = ChatResponseFormat::JsonObject;
+        assert!(format.validate("{\"ok\": true}").is_ok());
+        assert!(format.validate("[1, 2]").unwrap_err().contains("an array"));
+        assert!(format.validate("{\"ok\": tru").unwrap_err().contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_schema_validation_reports_each_problem() {
+        let format = schema_format(json!({
+            "type": "object",
+            "required": ["city", "population"],
+            "additionalProperties": false,
+            "properties": {
+                "city": { "type": "string", "minLength": 1 },
+                "population": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "enum": ["capital", "port"] } }
+            }
+        }));
+
+        let value = format.validate("{\"city\": \"Oslo\", \"population\": 709000, \"tags\": [\"capital\"]}").unwrap();
+        assert_eq!(value["city"], "Oslo");
+
+        let error = format.validate("{\"city\": \"\", \"population\": -3.5, \"tags\": [\"village\"], \"mayor\": \"x\"}").unwrap_err();
+        assert!(error.contains("$.city should be at least 1 characters"), "{}", error);
+        assert!(error.contains("$.population should be integer but is a number"), "{}", error);
+        assert!(error.contains("$.tags[0] should be one of"), "{}", error);
+        assert!(error.contains("$.mayor is not an allowed property"), "{}", error);
+
+        let error = format.validate("{\"city\": \"Oslo\"}").unwrap_err();
+        assert!(error.contains("$ is missing \"population\""), "{}", error);
+    }
+
+    #[test]
+    fn test_schema_type_lists_and_any_of() {
+        let format = schema_format(json!({
+            "anyOf": [{ "type": "string" }, { "type": ["integer", "null"] }]
+        }));
+        assert!(format.validate("\"text\"").is_ok());
+        assert!(format.validate("null").is_ok());
+        assert!(format.validate("42").is_ok());
+        assert!(format.validate("true").unwrap_err().contains("matches none of the allowed shapes"));
+    }
+
+    #[test]
+    fn test_one_of_requires_exactly_one_match() {
+        let format = schema_format(json!({
+            "oneOf": [{ "type": "integer" }, { "type": "number", "minimum": 10 }]
+        }));
+        assert!(format.validate("3").is_ok());
+        assert!(format.validate("10.5").is_ok());
+        assert!(format.validate("12").unwrap_err().contains("should match exactly one"));
+        assert!(format.validate("\"x\"").unwrap_err().contains("matches none"));
+    }
+
+    #[test]
+    fn test_answer_start_skips_tool_exchanges() {
+        assert_eq!(answer_start("{\"a\": 1}"), 0);
+        let reply = "Checking.\n<tool_call>\n{}\n</tool_call>\n<tool_response>\n{\"t\": 3}\n</tool_response>\n{\"a\": 1}";
+        assert_eq!(reply[answer_start(reply)..].trim(), "{\"a\": 1}");
+    }
+}
@@ -0,0 +1,76 @@
+//! Lightweight probes of the machine state (user idle time, power source)
+//! used to decide whether background work should run right now.
+//!
+//! Every probe returns `None` when the platform cannot answer, so callers can
+//! decide whether an unknown state counts as satisfied.
+
+/// Seconds since the last keyboard or mouse input.
+#[cfg(target_os = "windows")]
+pub fn idle_seconds() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+
+    // SAFETY: `info` is a properly sized, initialized LASTINPUTINFO
+    let ok = unsafe { GetLastInputInfo(&mut info) };
+    if ok == 0 {
+        return None;
+    }
+
+    // Both values are 32-bit tick counts, wrapping_sub handles rollover
+    let now = unsafe { GetTickCount() };
+    Some((now.wrapping_sub(info.dwTime) / 1000) as u64)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn idle_seconds() -> Option<u64> {
+    None
+}
+
+/// Whether the machine is running from mains power.
+#[cfg(target_os = "windows")]
+pub fn on_ac_power() -> Option<bool> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    // SAFETY: zeroed is a valid bit pattern for this plain C struct
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    let ok = unsafe { GetSystemPowerStatus(&mut status) };
+    if ok == 0 {
+        return None;
+    }
+
+    match status.ACLineStatus {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn on_ac_power() -> Option<bool> {
+    let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    let mut saw_mains = false;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let kind = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        if kind.trim() != "Mains" {
+            continue;
+        }
+        saw_mains = true;
+        if std::fs::read_to_string(path.join("online")).unwrap_or_default().trim() == "1" {
+            return Some(true);
+        }
+    }
+
+    if saw_mains { Some(false) } else { None }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn on_ac_power() -> Option<bool> {
+    None
+}
@@ -0,0 +1,269 @@
+//! Bounded agent loop used by `ActionType::AgentTask`.
+//!
+//! The loop alternates model calls and tool executions until the model
+//! answers without requesting a tool, or until the step/time budget runs out.
+//! Every model turn, retrieval and tool call is recorded as an `AgentStep`
+//! so the complete run can be inspected from the task log afterwards.
+
+use std::time::{Duration, Instant};
+
+use async_openai::{Client, config::OpenAIConfig};
+use async_openai::types::chat::{
+    ChatCompletionRequestAssistantMessageArgs,
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+use crate::{chat, constants, mcp};
+
+/// Tools an unattended agent may not call (it must not schedule more agents)
+const BLOCKED_TOOLS: &[&str] = &["builtin_create_task"];
+
+/// Maximum characters of a tool result fed back to the model
+const MAX_TOOL_RESULT_CHARS: usize = 4000;
+
+/// Maximum characters of step content kept in the task log
+const MAX_LOGGED_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AgentStepKind {
+    Retrieval,
+    Model,
+    ToolCall,
+    FinalAnswer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStep {
+    pub index: u32,
+    pub kind: AgentStepKind,
+    pub content: String,
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub arguments: Option<serde_json::Value>,
+    pub success: bool,
+    pub elapsed_ms: u64,
+}
+
+pub struct AgentRun {
+    pub result: Result<String, String>,
+    pub steps: Vec<AgentStep>,
+}
+
+pub struct AgentBudget {
+    pub max_steps: u32,
+    pub max_duration: Duration,
+}
+
+impl AgentBudget {
+    pub fn new(max_steps: Option<u32>, max_duration_secs: Option<u64>) -> Self {
+        Self {
+            max_steps: max_steps
+                .unwrap_or(constants::DEFAULT_AGENT_MAX_STEPS)
+                .clamp(1, constants::MAX_AGENT_STEPS),
+            max_duration: Duration::from_secs(
+                max_duration_secs.unwrap_or(constants::DEFAULT_AGENT_TIMEOUT_SECS).max(10),
+            ),
+        }
+    }
+}
+
+struct StepRecorder {
+    steps: Vec<AgentStep>,
+}
+
+impl StepRecorder {
+    fn record(
+        &mut self,
+        kind: AgentStepKind,
+        content: &str,
+        tool: Option<(&str, &serde_json::Value)>,
+        success: bool,
+        started: Instant,
+    ) {
+        self.steps.push(AgentStep {
+            index: self.steps.len() as u32 + 1,
+            kind,
+            content: truncate_chars(content, MAX_LOGGED_CHARS),
+            tool_name: tool.map(|(name, _)| name.to_string()),
+            arguments: tool.map(|(_, args)| args.clone()),
+            success,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+pub async fn run_agent_task(
+    app: &AppHandle,
+    goal: &str,
+    model_name: Option<&str>,
+    budget: AgentBudget,
+    use_rag: bool,
+) -> AgentRun {
+    let mut recorder = StepRecorder { steps: Vec::new() };
+    let result = run_loop(app, goal, model_name, &budget, use_rag, &mut recorder).await;
+
+    match &result {
+        Ok(_) => info!(steps = recorder.steps.len(), "Agent task completed"),
+        Err(e) => warn!(steps = recorder.steps.len(), error = %e, "Agent task stopped"),
+    }
+
+    AgentRun {
+        result,
+        steps: recorder.steps,
+    }
+}
+
+async fn run_loop(
+    app: &AppHandle,
+    goal: &str,
+    model_name: Option<&str>,
+    budget: &AgentBudget,
+    use_rag: bool,
+    recorder: &mut StepRecorder,
+) -> Result<String, String> {
+    let started = Instant::now();
+
+    crate::ensure_ovms_initialized(app).await;
+
+    let model = match model_name {
+        Some(name) => name.to_string(),
+        None => crate::ovms::get_loaded_model(app.clone()).await?
+            .ok_or("No model is loaded; load a text model or set model_name on the task")?,
+    };
+
+    let config = OpenAIConfig::new()
+        .with_api_key("unused")
+        .with_api_base(crate::ovms::openai_api_base());
+    let client = Client::with_config(config);
+
+    let tools: Vec<_> = mcp::get_all_mcp_tools_for_chat(app.clone()).await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|tool| !BLOCKED_TOOLS.contains(&tool.function.name.as_str()))
+        .collect();
+
+    let system_message = format!(
+        "You are an autonomous assistant running a scheduled task with no user present. \
+        Work toward the goal step by step, calling tools whenever you need information or need to act. \
+        When the goal is achieved, reply with the final result and no tool calls.{}",
+        chat::format_tools_prompt(&tools)
+    );
+
+    let mut goal_message = format!("Goal: {}", goal);
+    if use_rag {
+        let step_started = Instant::now();
+        match chat::perform_rag_retrieval(goal, Some(5), None).await {
+            Ok(context) if !context.is_empty() => {
+                recorder.record(AgentStepKind::Retrieval, &context, None, true, step_started);
+                goal_message.push_str(&format!("\n\nRelevant excerpts from the user's documents:\n{}", context));
+            },
+            Ok(_) => recorder.record(AgentStepKind::Retrieval, "No relevant documents found", None, true, step_started),
+            Err(e) => recorder.record(AgentStepKind::Retrieval, &e, None, false, step_started),
+        }
+    }
+
+    let mut messages: Vec<ChatCompletionRequestMessage> = vec![
+        ChatCompletionRequestSystemMessageArgs::default()
+            .content(system_message)
+            .build()
+            .map_err(|e| format!("Failed to build system message: {}", e))?
+            .into(),
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(goal_message)
+            .build()
+            .map_err(|e| format!("Failed to build user message: {}", e))?
+            .into(),
+    ];
+
+    for step in 1..=budget.max_steps {
+        let remaining = budget.max_duration
+            .checked_sub(started.elapsed())
+            .ok_or_else(|| format!("Time budget of {}s exhausted", budget.max_duration.as_secs()))?;
+
+        debug!(step, model = %model, "Agent step");
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(model.clone())
+            .messages(messages.clone())
+            .temperature(0.3)
+            .max_tokens(1000u32)
+            .build()
+            .map_err(|e| format!("Failed to build agent request: {}", e))?;
+
+        let step_started = Instant::now();
+        let response = tokio::time::timeout(remaining, client.chat().create(request)).await
+            .map_err(|_| format!("Time budget of {}s exhausted", budget.max_duration.as_secs()))?
+            .map_err(|e| {
+                let message = format!("Model request failed: {}", e);
+                recorder.record(AgentStepKind::Model, &message, None, false, step_started);
+                message
+            })?;
+
+        let content = response.choices
+            .first()
+            .and_then(|choice| choice.message.content.clone())
+            .unwrap_or_default();
+
+        let tool_calls = chat::extract_all_tool_calls_from_xml(&content);
+        if tool_calls.is_empty() {
+            recorder.record(AgentStepKind::FinalAnswer, &content, None, true, step_started);
+            return Ok(content.trim().to_string());
+        }
+
+        recorder.record(AgentStepKind::Model, &content, None, true, step_started);
+        messages.push(
+            ChatCompletionRequestAssistantMessageArgs::default()
+                .content(content)
+                .build()
+                .map_err(|e| format!("Failed to build assistant message: {}", e))?
+                .into()
+        );
+
+        let mut responses = Vec::new();
+        for (tool_name, args_json) in tool_calls {
+            let arguments: serde_json::Value = serde_json::from_str(&args_json)
+                .unwrap_or_else(|_| serde_json::json!({}));
+            let tool_started = Instant::now();
+
+            let outcome = if BLOCKED_TOOLS.contains(&tool_name.as_str()) {
+                Err(format!("Tool '{}' is not available to agent tasks", tool_name))
+            } else {
+                mcp::call_mcp_tool(app.clone(), tool_name.clone(), arguments.as_object().cloned()).await
+            };
+
+            let (text, success) = match outcome {
+                Ok(text) => (text, true),
+                Err(e) => (format!("Error: {}", e), false),
+            };
+            recorder.record(AgentStepKind::ToolCall, &text, Some((&tool_name, &arguments)), success, tool_started);
+            responses.push(format!(
+                "<tool_response>\n{}\n</tool_response>",
+                truncate_chars(&text, MAX_TOOL_RESULT_CHARS)
+            ));
+        }
+
+        messages.push(
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(responses.join("\n"))
+                .build()
+                .map_err(|e| format!("Failed to build tool response message: {}", e))?
+                .into()
+        );
+    }
+
+    Err(format!("Step budget of {} exhausted before reaching the goal", budget.max_steps))
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text.to_string(),
+    }
+}
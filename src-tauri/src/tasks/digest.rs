@@ -0,0 +1,308 @@
+//! Periodic "memory review": a Markdown digest of recent conversations,
+//! indexed documents and task runs, summarized by the local model.
+//!
+//! Runs as `ActionType::MemoryDigest`. A weekly task using it is added
+//! (disabled) on first start so users only have to switch it on.
+
+use std::path::PathBuf;
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use chrono::{ DateTime, Duration, Local, Utc };
+use tauri::AppHandle;
+
+use super::{ ActionType, ExecutionStatus, RunConditions, Task, TaskScheduler, TriggerTime, scheduler };
+use crate::{ chat, notifications, paths, settings };
+
+/// Id of the weekly digest task created on first start
+pub const BUILTIN_TASK_ID: &str = "builtin-memory-review";
+
+const DEFAULT_DAYS: u32 = 7;
+
+/// Characters of each conversation excerpt given to the model
+const EXCERPT_CHARS: usize = 400;
+
+/// Items of each kind listed at most
+const MAX_ITEMS: usize = 40;
+
+const DIGEST_PROMPT: &str = "You write a short weekly review for the user from notes about their recent activity. \
+Use Markdown with these sections: ## Highlights, ## Conversations, ## Documents, ## Tasks, ## Follow-ups. \
+Group related items, mention concrete topics, and suggest follow-ups only where the notes support them. \
+Do not invent activity that is not in the notes.";
+
+/// The weekly digest task, disabled until the user turns it on
+pub fn builtin_task() -> Task {
+    Task {
+        id: BUILTIN_TASK_ID.to_string(),
+        name: "Weekly memory review".to_string(),
+        enabled: false,
+        action_type: ActionType::MemoryDigest { days: None, model_name: None },
+        action_params: serde_json::Value::Null,
+        trigger_time: TriggerTime::Weekly { day_of_week: 0, time: "18:00".to_string() },
+        repeat_interval: None,
+        created_at: Utc::now(),
+        last_run: None,
+        next_run: None,
+        run_count: 0,
+        auto_delete: false,
+        snoozed_until: None,
+        conditions: RunConditions { require_model_loaded: true, ..Default::default() },
+    }
+}
+
+/// Returns whether the task had to be added
+pub fn ensure_builtin_task(scheduler: &mut TaskScheduler) -> bool {
+    if scheduler.get_task(BUILTIN_TASK_ID).is_some() || settings::current().digest.builtin_task_removed {
+        return false;
+    }
+    let mut task = builtin_task();
+    task.next_run = scheduler.calculate_next_run(&task);
+    scheduler.add_task(task);
+    true
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+fn conversation_notes(sessions: &[chat::ChatSession], since: DateTime<Utc>) -> Vec<String> {
+    let since_ms = since.timestamp_millis();
+    let mut recent: Vec<&chat::ChatSession> = sessions.iter()
+        .filter(|session| session.updated_at >= since_ms && !session.messages.is_empty())
+        .collect();
+    recent.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+    recent.into_iter()
+        .take(MAX_ITEMS)
+        .map(|session| {
+            let messages: Vec<&chat::ChatMessage> = session.messages.iter()
+                .filter(|message| message.timestamp >= since_ms && message.is_error != Some(true))
+                .collect();
+            let question = messages.iter().find(|m| m.role == "user").map(|m| excerpt(&m.content));
+            let answer = messages.iter().rev().find(|m| m.role == "assistant").map(|m| excerpt(&m.content));
+            let mut note = format!("- \"{}\" ({} messages)", session.title, messages.len());
+            if let Some(question) = question {
+                note.push_str(&format!("\n  Asked: {}", question));
+            }
+            if let Some(answer) = answer {
+                note.push_str(&format!("\n  Last answer: {}", answer));
+            }
+            note
+        })
+        .collect()
+}
+
+fn task_notes(since: DateTime<Utc>) -> Vec<String> {
+    let scheduler = scheduler().lock();
+    let mut notes: Vec<String> = scheduler.get_all_tasks()
+        .into_iter()
+        .filter(|task| task.id != BUILTIN_TASK_ID)
+        .filter_map(|task| {
+            let runs: Vec<_> = scheduler.get_task_logs(&task.id)
+                .into_iter()
+                .filter(|log| log.executed_at >= since && !matches!(log.status, ExecutionStatus::Skipped))
+                .collect();
+            if runs.is_empty() {
+                return None;
+            }
+            let failed = runs.iter().filter(|log| matches!(log.status, ExecutionStatus::Failed)).count();
+            let last = runs.last().and_then(|log| log.message.clone().or_else(|| log.error.clone())).unwrap_or_default();
+            Some(format!("- {}: ran {} times, {} failed. Last result: {}", task.name, runs.len(), failed, excerpt(&last)))
+        })
+        .collect();
+    notes.truncate(MAX_ITEMS);
+    notes
+}
+
+async fn document_notes(since: DateTime<Utc>) -> Vec<String> {
+    let files = match crate::rag::backend::open_default() {
+        Ok(store) => store.list_files().await.unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    files.into_iter()
+        .filter(|file| file.created_at >= since.timestamp_millis())
+        .take(MAX_ITEMS)
+        .map(|file| {
+            let preview = file.documents.first().map(|chunk| excerpt(&chunk.content)).unwrap_or_default();
+            format!("- {} ({} chunks): {}", file.file_name, file.chunk_count, preview)
+        })
+        .collect()
+}
+
+fn section(title: &str, notes: &[String]) -> String {
+    if notes.is_empty() {
+        format!("{}:\n(none)\n", title)
+    } else {
+        format!("{}:\n{}\n", title, notes.join("\n"))
+    }
+}
+
+fn output_dir() -> Result<PathBuf, String> {
+    let dir = match settings::current().digest.output_dir.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => paths::get_sparrow_dir().map_err(|e| e.to_string())?.join("digests"),
+    };
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+async fn summarize(app: &AppHandle, model: Option<&str>, notes: String) -> Result<String, String> {
+    crate::ensure_ovms_initialized(app).await;
+    let model = match model {
+        Some(model) => model.to_string(),
+        None => crate::ovms::get_loaded_model(app.clone()).await?
+            .ok_or("No model is loaded; load a text model to write the digest")?,
+    };
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages(vec![
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(DIGEST_PROMPT)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into(),
+            ChatCompletionRequestUserMessageArgs::default()
+                .content(notes)
+                .build()
+                .map_err(|e| format!("Failed to build user message: {}", e))?
+                .into(),
+        ])
+        .temperature(0.4)
+        .max_tokens(1500u32)
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let client = Client::with_config(
+        OpenAIConfig::new()
+            .with_api_key("unused")
+            .with_api_base(crate::ovms::openai_api_base())
+    );
+    let response = client.chat()
+        .create(request)
+        .await
+        .map_err(|e| format!("Digest request failed: {}", e))?;
+    response.choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .filter(|content| !content.trim().is_empty())
+        .ok_or_else(|| "The model returned an empty digest".to_string())
+}
+
+/// Write the digest for the last `days` days; returns a summary for the task log
+pub async fn run_digest(app: &AppHandle, days: Option<u32>, model: Option<&str>) -> Result<String, String> {
+    let days = days.unwrap_or(DEFAULT_DAYS).max(1);
+    let since = Utc::now() - Duration::days(days as i64);
+
+    let sessions: Vec<chat::ChatSession> = chat::load_chat_sessions().await?.sessions.into_values().collect();
+    let conversations = conversation_notes(&sessions, since);
+    let documents = document_notes(since).await;
+    let tasks = task_notes(since);
+
+    if conversations.is_empty() && documents.is_empty() && tasks.is_empty() {
+        return Ok(format!("Nothing happened in the last {} days; no digest written", days));
+    }
+
+    let notes = format!(
+        "Activity of the last {} days.\n\n{}\n{}\n{}",
+        days,
+        section("Conversations", &conversations),
+        section("Documents indexed", &documents),
+        section("Scheduled tasks", &tasks)
+    );
+    let summary = summarize(app, model, notes).await?;
+
+    let today = Local::now().format("%Y-%m-%d");
+    let path = output_dir()?.join(format!("memory-review-{}.md", today));
+    let content = format!("# Memory review, {}\n\n{}\n", today, summary.trim());
+    tokio::fs::write(&path, content).await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let body = format!(
+        "{} conversations, {} documents and {} tasks reviewed. Saved to {}",
+        conversations.len(),
+        documents.len(),
+        tasks.len(),
+        path.display()
+    );
+    {
+        use tauri_plugin_notification::NotificationExt;
+        let _ = app.notification().builder().title("Your weekly review is ready").body(&body).show();
+    }
+    notifications::record(app, "Your weekly review is ready", &body, "digest");
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str, timestamp: i64) -> chat::ChatMessage {
+        chat::ChatMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp,
+            tokens_per_second: None,
+            is_error: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            total_tokens: None,
+            attachments: None,
+            pinned: false,
+            generation: None,
+            citations: None,
+        }
+    }
+
+    #[test]
+    fn test_conversation_notes_only_cover_the_window() {
+        let since = Utc::now() - Duration::days(7);
+        let old = (since - Duration::days(1)).timestamp_millis();
+        let new = Utc::now().timestamp_millis();
+        let session = |id: &str, updated_at: i64, messages| chat::ChatSession {
+            id: id.to_string(),
+            title: id.to_string(),
+            created_at: old,
+            updated_at,
+            model_id: None,
+            messages,
+            preferred_language: None,
+            tool_policy: Default::default(),
+            sampling: Default::default(),
+            prompt_preset: None,
+            revision: 0,
+        };
+
+        let sessions = vec![
+            session("stale", old, vec![message("user", "old question", old)]),
+            session("recent", new, vec![
+                message("user", "before the window", old),
+                message("user", "How do I   export charts?", new),
+                message("assistant", "Use render_chart.", new),
+            ]),
+        ];
+
+        let notes = conversation_notes(&sessions, since);
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].starts_with("- \"recent\" (2 messages)"));
+        assert!(notes[0].contains("Asked: How do I export charts?"));
+    }
+
+    #[test]
+    fn test_excerpt_truncates_on_char_boundary() {
+        let long = "é".repeat(EXCERPT_CHARS + 5);
+        assert_eq!(excerpt(&long).chars().count(), EXCERPT_CHARS + 1);
+        assert_eq!(excerpt("short  text"), "short text");
+    }
+}
@@ -0,0 +1,247 @@
+//! Filesystem watchers backing `TriggerTime::FileEvent`.
+//!
+//! One `notify` watcher is kept per enabled file-event task. Raw events are
+//! forwarded over a channel to an async loop that filters them by event kind
+//! and file name pattern, debounces bursts, and then runs the task with the
+//! triggering file exposed as `{{file_path}}` / `{{file_name}}`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use super::{Task, TriggerTime};
+use crate::paths;
+
+/// Events for the same task and file within this window are collapsed
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Delay before running, so files that are still being written can settle
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileEventKind {
+    /// A file appeared (created, or moved/renamed into the folder)
+    #[default]
+    Created,
+    Modified,
+    Removed,
+    Any,
+}
+
+impl FileEventKind {
+    fn matches(&self, kind: &EventKind) -> bool {
+        let observed = match kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => FileEventKind::Created,
+            EventKind::Modify(_) => FileEventKind::Modified,
+            EventKind::Remove(_) => FileEventKind::Removed,
+            _ => return false,
+        };
+        *self == FileEventKind::Any || *self == observed
+    }
+}
+
+struct WatchEntry {
+    // Dropping the watcher stops it, so it is only held here
+    _watcher: RecommendedWatcher,
+    path: PathBuf,
+    recursive: bool,
+}
+
+static WATCHERS: OnceLock<Mutex<HashMap<String, WatchEntry>>> = OnceLock::new();
+static EVENT_TX: OnceLock<mpsc::UnboundedSender<(String, Event)>> = OnceLock::new();
+
+fn watchers() -> &'static Mutex<HashMap<String, WatchEntry>> {
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Expand a leading `~` to the user's home directory
+pub fn expand_path(raw: &str) -> PathBuf {
+    if let Some(rest) = raw.strip_prefix("~") {
+        if let Ok(home) = paths::get_home_dir() {
+            return home.join(rest.trim_start_matches(['/', '\\']));
+        }
+    }
+    PathBuf::from(raw)
+}
+
+/// Case-insensitive `*` / `?` wildcard match against a file name
+pub fn matches_pattern(pattern: &str, file_name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = file_name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Start the event loop. Must run before `sync_watchers` can attach anything.
+pub fn start_file_event_loop(app_handle: AppHandle) {
+    let (tx, mut rx) = mpsc::unbounded_channel::<(String, Event)>();
+    if EVENT_TX.set(tx).is_err() {
+        warn!("File event loop already started");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut last_fired: HashMap<(String, PathBuf), Instant> = HashMap::new();
+
+        while let Some((task_id, event)) = rx.recv().await {
+            let task = {
+                let scheduler = super::scheduler().lock();
+                scheduler.get_task(&task_id).cloned()
+            };
+            let Some(task) = task else { continue };
+
+            let TriggerTime::FileEvent { pattern, event: wanted, .. } = &task.trigger_time else {
+                continue;
+            };
+            if !wanted.matches(&event.kind) {
+                continue;
+            }
+
+            for path in &event.paths {
+                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                if let Some(pattern) = pattern.as_deref().filter(|p| !p.is_empty()) {
+                    if !matches_pattern(pattern, &file_name) {
+                        continue;
+                    }
+                }
+
+                let key = (task_id.clone(), path.clone());
+                let now = Instant::now();
+                if last_fired.get(&key).map_or(false, |t| now.duration_since(*t) < DEBOUNCE) {
+                    continue;
+                }
+                last_fired.insert(key, now);
+
+                info!(task = %task.name, file = %path.display(), "File event triggered task");
+
+                let context = HashMap::from([
+                    ("file_path".to_string(), path.to_string_lossy().to_string()),
+                    ("file_name".to_string(), file_name),
+                ]);
+                let task = task.clone();
+                let app_handle = app_handle.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(SETTLE_DELAY).await;
+                    super::run_triggered_task(task, context, app_handle).await;
+                });
+            }
+
+            // Keep the debounce map from growing without bound
+            last_fired.retain(|_, t| t.elapsed() < DEBOUNCE);
+        }
+    });
+}
+
+/// Reconcile running watchers with the current set of enabled file-event tasks
+pub fn sync_watchers() {
+    let Some(tx) = EVENT_TX.get() else {
+        return;
+    };
+
+    let desired: HashMap<String, (PathBuf, bool)> = {
+        let scheduler = super::scheduler().lock();
+        scheduler
+            .get_all_tasks()
+            .into_iter()
+            .filter(|task| task.enabled)
+            .filter_map(|task: Task| match &task.trigger_time {
+                TriggerTime::FileEvent { path, recursive, .. } => Some((task.id.clone(), (expand_path(path), *recursive))),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut watchers = watchers().lock().unwrap();
+
+    watchers.retain(|task_id, entry| {
+        let keep = desired.get(task_id).map_or(false, |(path, recursive)| {
+            *path == entry.path && *recursive == entry.recursive
+        });
+        if !keep {
+            debug!(task_id = %task_id, "Stopping file watcher");
+        }
+        keep
+    });
+
+    for (task_id, (path, recursive)) in desired {
+        if watchers.contains_key(&task_id) {
+            continue;
+        }
+        match create_watcher(&task_id, &path, recursive, tx.clone()) {
+            Ok(watcher) => {
+                info!(task_id = %task_id, path = %path.display(), "Watching folder for task");
+                watchers.insert(task_id, WatchEntry { _watcher: watcher, path, recursive });
+            },
+            Err(e) => error!(task_id = %task_id, path = %path.display(), error = %e, "Failed to watch folder"),
+        }
+    }
+}
+
+fn create_watcher(
+    task_id: &str,
+    path: &Path,
+    recursive: bool,
+    tx: mpsc::UnboundedSender<(String, Event)>,
+) -> Result<RecommendedWatcher, String> {
+    if !path.is_dir() {
+        return Err(format!("Folder does not exist: {}", path.display()));
+    }
+
+    let task_id = task_id.to_string();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) => {
+            let _ = tx.send((task_id.clone(), event));
+        },
+        Err(e) => warn!(error = %e, "File watcher error"),
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+    watcher
+        .watch(path, mode)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*.pdf", "Scan 001.PDF"));
+        assert!(matches_pattern("invoice-??.txt", "invoice-07.txt"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(!matches_pattern("*.pdf", "notes.txt"));
+        assert!(!matches_pattern("invoice-??.txt", "invoice-7.txt"));
+        assert!(matches_pattern("*report*.xlsx", "q3-report-final.xlsx"));
+    }
+}
@@ -1,5 +1,6 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::sync::Arc;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration, NaiveTime, Datelike, TimeZone};
 use tauri::{AppHandle, Emitter};
@@ -7,7 +8,17 @@ use tracing::{info, error, debug};
 use std::path::PathBuf;
 use tokio::time::sleep;
 
-use crate::paths;
+use crate::{notifications, paths, quick_actions, system_state};
+use crate::quick_actions::QuickActionResult;
+
+pub mod agent;
+pub mod digest;
+pub mod file_watch;
+pub mod system_events;
+pub mod validation;
+
+/// How long a due task waits before its run conditions are checked again
+const CONDITION_RECHECK_SECS: i64 = 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -23,6 +34,98 @@ pub struct Task {
     pub next_run: Option<DateTime<Utc>>,
     pub run_count: u32,
     pub auto_delete: bool,
+    /// While set and in the future, the task is held back even if it is due
+    #[serde(default)]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub conditions: RunConditions,
+}
+
+/// Conditions checked right before a scheduled run. A due task whose
+/// conditions are not met is deferred and re-checked periodically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunConditions {
+    /// Only run after this many minutes without keyboard/mouse input
+    #[serde(default)]
+    pub idle_minutes: Option<u32>,
+    /// Only run while on AC power
+    #[serde(default)]
+    pub require_ac_power: bool,
+    /// Only run while OVMS has a model loaded
+    #[serde(default)]
+    pub require_model_loaded: bool,
+    /// Specific model that must be loaded (any model when unset)
+    #[serde(default)]
+    pub model_name: Option<String>,
+}
+
+impl RunConditions {
+    pub fn is_empty(&self) -> bool {
+        self.idle_minutes.is_none() && !self.require_ac_power && !self.require_model_loaded
+    }
+}
+
+impl Task {
+    pub fn is_snoozed(&self, now: DateTime<Utc>) -> bool {
+        self.snoozed_until.map_or(false, |until| until > now)
+    }
+
+    /// Copy of the task with `{{key}}` placeholders in its action filled from
+    /// an event context. Agent goals also get the context appended verbatim.
+    pub fn with_trigger_context(&self, context: &HashMap<String, String>) -> Task {
+        if context.is_empty() {
+            return self.clone();
+        }
+
+        let fill = |text: &str| {
+            context.iter().fold(text.to_string(), |acc, (key, value)| {
+                acc.replace(&format!("{{{{{}}}}}", key), value)
+            })
+        };
+
+        let mut task = self.clone();
+        task.action_type = match &self.action_type {
+            ActionType::ShowNotification { title, message } => ActionType::ShowNotification {
+                title: fill(title),
+                message: fill(message),
+            },
+            ActionType::RunMcpFunction { server_name, tool_name, arguments } => ActionType::RunMcpFunction {
+                server_name: server_name.clone(),
+                tool_name: tool_name.clone(),
+                arguments: fill_json_strings(arguments, &fill),
+            },
+            ActionType::AgentTask { goal, model_name, max_steps, max_duration_secs, use_rag } => {
+                let mut details: Vec<String> = context.iter().map(|(k, v)| format!("- {}: {}", k, v)).collect();
+                details.sort();
+                ActionType::AgentTask {
+                    goal: format!("{}\n\nTrigger details:\n{}", fill(goal), details.join("\n")),
+                    model_name: model_name.clone(),
+                    max_steps: *max_steps,
+                    max_duration_secs: *max_duration_secs,
+                    use_rag: *use_rag,
+                }
+            },
+            ActionType::RunQuickAction { action_id, text } => ActionType::RunQuickAction {
+                action_id: action_id.clone(),
+                text: fill(text),
+            },
+            ActionType::MemoryDigest { .. } => self.action_type.clone(),
+        };
+        task
+    }
+}
+
+fn fill_json_strings(value: &serde_json::Value, fill: &dyn Fn(&str) -> String) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(fill(s)),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|v| fill_json_strings(v, fill)).collect())
+        },
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter().map(|(k, v)| (k.clone(), fill_json_strings(v, fill))).collect()
+        ),
+        other => other.clone(),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +133,28 @@ pub struct Task {
 pub enum ActionType {
     ShowNotification { title: String, message: String },
     RunMcpFunction { server_name: String, tool_name: String, arguments: serde_json::Value },
+    /// Work toward a written goal with a bounded LLM + tools (+ optional RAG) loop
+    AgentTask {
+        goal: String,
+        #[serde(default)]
+        model_name: Option<String>,
+        #[serde(default)]
+        max_steps: Option<u32>,
+        #[serde(default)]
+        max_duration_secs: Option<u64>,
+        #[serde(default)]
+        use_rag: bool,
+    },
+    /// Run a quick action on `text`; the action decides where the result goes
+    RunQuickAction { action_id: String, text: String },
+    /// Summarize recent chats, documents and task runs into a Markdown note
+    MemoryDigest {
+        /// Days covered (7 when unset)
+        #[serde(default)]
+        days: Option<u32>,
+        #[serde(default)]
+        model_name: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +172,21 @@ pub enum TriggerTime {
     EveryNMinutes { minutes: u32 },
     /// Run every N hours
     EveryNHours { hours: u32 },
+    /// Run when a matching file event happens in a folder.
+    /// The file is available to the action as `{{file_path}}` / `{{file_name}}`.
+    FileEvent {
+        path: String,
+        /// Wildcard file name filter such as `*.pdf`; all files when unset
+        #[serde(default)]
+        pattern: Option<String>,
+        #[serde(default)]
+        event: file_watch::FileEventKind,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Run on app startup, system resume or a connectivity change.
+    /// The event name is available to the action as `{{event}}`.
+    SystemEvent { event: system_events::SystemEventKind },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,12 +206,16 @@ pub enum TimeUnit {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskStorage {
     pub tasks: HashMap<String, Task>,
+    /// Global pause flag; persisted so automations stay silenced across restarts
+    #[serde(default)]
+    pub paused: bool,
 }
 
 impl TaskStorage {
     pub fn new() -> Self {
         Self {
             tasks: HashMap::new(),
+            paused: false,
         }
     }
 }
@@ -83,6 +227,9 @@ pub struct TaskExecutionLog {
     pub status: ExecutionStatus,
     pub message: Option<String>,
     pub error: Option<String>,
+    /// Step-by-step record of agent task runs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<agent::AgentStep>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,10 +242,28 @@ pub enum ExecutionStatus {
 // Global task scheduler state
 static TASK_SCHEDULER: std::sync::OnceLock<Arc<Mutex<TaskScheduler>>> = std::sync::OnceLock::new();
 
+fn scheduler() -> &'static Arc<Mutex<TaskScheduler>> {
+    TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())))
+}
+
+/// Let only focus-session tasks run until `until` (see `focus`)
+pub(crate) fn silence_background_tasks(until: Option<DateTime<Utc>>) {
+    scheduler().lock().set_silenced_until(until);
+}
+
+/// Tasks executing right now
+pub fn running_tasks() -> Vec<Task> {
+    scheduler().lock().running_tasks()
+}
+
 pub struct TaskScheduler {
     tasks: HashMap<String, Task>,
     execution_logs: Vec<TaskExecutionLog>,
     app_handle: Option<AppHandle>,
+    paused: bool,
+    running: HashSet<String>,
+    /// Until then only focus-session tasks run; others wait (see `focus`)
+    silenced_until: Option<DateTime<Utc>>,
 }
 
 impl TaskScheduler {
@@ -107,6 +272,9 @@ impl TaskScheduler {
             tasks: HashMap::new(),
             execution_logs: Vec::new(),
             app_handle: None,
+            paused: false,
+            running: HashSet::new(),
+            silenced_until: None,
         }
     }
 
@@ -137,6 +305,70 @@ impl TaskScheduler {
         self.tasks.insert(task.id.clone(), task);
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        info!("Task scheduler {}", if paused { "paused" } else { "resumed" });
+        self.paused = paused;
+    }
+
+    /// Hold back background tasks until `until`; `None` lifts the hold
+    pub fn set_silenced_until(&mut self, until: Option<DateTime<Utc>>) {
+        self.silenced_until = until;
+    }
+
+    pub fn to_storage(&self) -> TaskStorage {
+        TaskStorage {
+            tasks: self.tasks.iter().map(|(id, t)| (id.clone(), t.clone())).collect(),
+            paused: self.paused,
+        }
+    }
+
+    /// Tasks that are due and not held back by a global pause or a snooze.
+    /// Held tasks keep their `next_run`, so they fire once when released.
+    /// Returned tasks are marked running until `finish_running` is called.
+    pub fn take_due_tasks(&mut self, now: DateTime<Utc>) -> Vec<Task> {
+        if self.paused {
+            return Vec::new();
+        }
+
+        let silenced = self.silenced_until.map_or(false, |until| until > now);
+        let due: Vec<Task> = self.tasks
+            .values()
+            .filter(|task| {
+                task.enabled &&
+                !task.is_snoozed(now) &&
+                (!silenced || crate::focus::is_focus_task(task)) &&
+                !self.running.contains(&task.id) &&
+                task.next_run.map_or(false, |next| next <= now)
+            })
+            .cloned()
+            .collect();
+
+        for task in &due {
+            self.running.insert(task.id.clone());
+        }
+        due
+    }
+
+    pub fn finish_running(&mut self, task_id: &str) {
+        self.running.remove(task_id);
+    }
+
+    pub fn running_tasks(&self) -> Vec<Task> {
+        self.running.iter().filter_map(|id| self.tasks.get(id)).cloned().collect()
+    }
+
+    fn last_log_status(&self, task_id: &str) -> Option<&ExecutionStatus> {
+        self.execution_logs
+            .iter()
+            .rev()
+            .find(|log| log.task_id == task_id)
+            .map(|log| &log.status)
+    }
+
     pub fn add_execution_log(&mut self, log: TaskExecutionLog) {
         // Keep only last 100 logs per task
         let task_logs: Vec<_> = self.execution_logs
@@ -313,7 +545,9 @@ impl TaskScheduler {
                 } else {
                     Some(now + Duration::hours(*hours as i64))
                 }
-            }
+            },
+            // Event-driven, never picked up by the time-based loop
+            TriggerTime::FileEvent { .. } | TriggerTime::SystemEvent { .. } => None,
         }
     }
 
@@ -385,6 +619,7 @@ pub async fn create_task(
     trigger_time: TriggerTime,
     repeat_interval: Option<RepeatInterval>,
     auto_delete: Option<bool>,
+    conditions: Option<RunConditions>,
 ) -> Result<Task, String> {
     let task_id = uuid::Uuid::new_v4().to_string();
     
@@ -401,12 +636,14 @@ pub async fn create_task(
         next_run: None,
         run_count: 0,
         auto_delete: auto_delete.unwrap_or(false),
+        snoozed_until: None,
+        conditions: conditions.unwrap_or_default(),
     };
 
     // Calculate next run
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     let next_run = {
-        let sched = scheduler.lock().unwrap();
+        let sched = scheduler.lock();
         sched.calculate_next_run(&task)
     };
 
@@ -415,30 +652,30 @@ pub async fn create_task(
 
     // Add to scheduler and save
     {
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         scheduler.add_task(task.clone());
         
-        let storage = TaskStorage {
-            tasks: scheduler.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-        };
+        let storage = scheduler.to_storage();
         save_tasks_to_file(&storage)?;
     }
 
+    file_watch::sync_watchers();
+
     info!("Created task: {} ({})", task.name, task.id);
     Ok(task)
 }
 
 #[tauri::command]
 pub async fn get_tasks() -> Result<Vec<Task>, String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
-    let scheduler = scheduler.lock().unwrap();
+    let scheduler = scheduler();
+    let scheduler = scheduler.lock();
     Ok(scheduler.get_all_tasks())
 }
 
 #[tauri::command]
 pub async fn get_task(task_id: String) -> Result<Task, String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
-    let scheduler = scheduler.lock().unwrap();
+    let scheduler = scheduler();
+    let scheduler = scheduler.lock();
     scheduler.get_task(&task_id)
         .cloned()
         .ok_or_else(|| format!("Task not found: {}", task_id))
@@ -446,11 +683,11 @@ pub async fn get_task(task_id: String) -> Result<Task, String> {
 
 #[tauri::command]
 pub async fn update_task(task: Task) -> Result<Task, String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     
     // Recalculate next run
     let next_run = {
-        let sched = scheduler.lock().unwrap();
+        let sched = scheduler.lock();
         sched.calculate_next_run(&task)
     };
 
@@ -458,44 +695,48 @@ pub async fn update_task(task: Task) -> Result<Task, String> {
     task.next_run = next_run;
 
     {
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         scheduler.update_task(task.clone());
         
-        let storage = TaskStorage {
-            tasks: scheduler.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-        };
+        let storage = scheduler.to_storage();
         save_tasks_to_file(&storage)?;
     }
 
+    file_watch::sync_watchers();
+
     info!("Updated task: {} ({})", task.name, task.id);
     Ok(task)
 }
 
 #[tauri::command]
 pub async fn delete_task(task_id: String) -> Result<(), String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     
     {
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         scheduler.remove_task(&task_id)
             .ok_or_else(|| format!("Task not found: {}", task_id))?;
         
-        let storage = TaskStorage {
-            tasks: scheduler.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-        };
+        let storage = scheduler.to_storage();
         save_tasks_to_file(&storage)?;
     }
 
+    if task_id == digest::BUILTIN_TASK_ID {
+        crate::settings::update(|s| s.digest.builtin_task_removed = true)?;
+    }
+
+    file_watch::sync_watchers();
+
     info!("Deleted task: {}", task_id);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn toggle_task(task_id: String) -> Result<Task, String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     
     let task = {
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         let mut task = scheduler.get_task(&task_id)
             .cloned()
             .ok_or_else(|| format!("Task not found: {}", task_id))?;
@@ -507,24 +748,24 @@ pub async fn toggle_task(task_id: String) -> Result<Task, String> {
         
         scheduler.update_task(task.clone());
         
-        let storage = TaskStorage {
-            tasks: scheduler.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-        };
+        let storage = scheduler.to_storage();
         save_tasks_to_file(&storage)?;
         
         task
     };
 
+    file_watch::sync_watchers();
+
     info!("Toggled task: {} (enabled: {})", task_id, task.enabled);
     Ok(task)
 }
 
 #[tauri::command]
 pub async fn execute_task_manually(task_id: String, app_handle: AppHandle) -> Result<(), String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     
     let task = {
-        let scheduler = scheduler.lock().unwrap();
+        let scheduler = scheduler.lock();
         scheduler.get_task(&task_id)
             .cloned()
             .ok_or_else(|| format!("Task not found: {}", task_id))?
@@ -541,21 +782,97 @@ pub async fn execute_task_manually(task_id: String, app_handle: AppHandle) -> Re
 
 #[tauri::command]
 pub async fn get_task_logs(task_id: String) -> Result<Vec<TaskExecutionLog>, String> {
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
-    let scheduler = scheduler.lock().unwrap();
+    let scheduler = scheduler();
+    let scheduler = scheduler.lock();
     Ok(scheduler.get_task_logs(&task_id))
 }
 
+/// Validate a loosely structured task definition (same shape as the
+/// `create_task` builtin tool) and preview its schedule without creating it.
+#[tauri::command]
+pub async fn validate_task_definition(
+    arguments: serde_json::Value,
+    preview_count: Option<usize>,
+) -> Result<validation::TaskValidationReport, String> {
+    Ok(validation::build_report(&arguments, preview_count.unwrap_or(3).clamp(1, 20)))
+}
+
+#[tauri::command]
+pub async fn pause_all_tasks() -> Result<(), String> {
+    set_scheduler_paused(true)
+}
+
+#[tauri::command]
+pub async fn resume_all_tasks() -> Result<(), String> {
+    set_scheduler_paused(false)
+}
+
+#[tauri::command]
+pub async fn get_scheduler_paused() -> Result<bool, String> {
+    let scheduler = scheduler();
+    let scheduler = scheduler.lock();
+    Ok(scheduler.is_paused())
+}
+
+fn set_scheduler_paused(paused: bool) -> Result<(), String> {
+    let scheduler = scheduler();
+    let mut scheduler = scheduler.lock();
+    scheduler.set_paused(paused);
+
+    let storage = scheduler.to_storage();
+    save_tasks_to_file(&storage)
+}
+
+/// Hold a task back for `minutes` from now. Passing 0 clears an existing snooze.
+#[tauri::command]
+pub async fn snooze_task(task_id: String, minutes: u32) -> Result<Task, String> {
+    let scheduler = scheduler();
+
+    let task = {
+        let mut scheduler = scheduler.lock();
+        let mut task = scheduler.get_task(&task_id)
+            .cloned()
+            .ok_or_else(|| format!("Task not found: {}", task_id))?;
+
+        task.snoozed_until = if minutes == 0 {
+            None
+        } else {
+            Some(Utc::now() + Duration::minutes(minutes as i64))
+        };
+
+        scheduler.update_task(task.clone());
+
+        let storage = scheduler.to_storage();
+        save_tasks_to_file(&storage)?;
+
+        task
+    };
+
+    info!("Snoozed task: {} (until: {:?})", task_id, task.snoozed_until);
+    Ok(task)
+}
+
 // Task execution
 async fn execute_task_action(task: &Task, app_handle: AppHandle) {
     info!("Executing task action: {} ({})", task.name, task.id);
     
-    let result = match &task.action_type {
+    let (result, steps) = match &task.action_type {
         ActionType::ShowNotification { title, message } => {
-            execute_show_notification(title, message, &app_handle).await
+            (execute_show_notification(title, message, &app_handle).await, Vec::new())
         },
         ActionType::RunMcpFunction { server_name, tool_name, arguments } => {
-            execute_mcp_function(server_name, tool_name, arguments, &app_handle).await
+            (execute_mcp_function(server_name, tool_name, arguments, &app_handle).await, Vec::new())
+        },
+        ActionType::AgentTask { goal, model_name, max_steps, max_duration_secs, use_rag } => {
+            let budget = agent::AgentBudget::new(*max_steps, *max_duration_secs);
+            let run = agent::run_agent_task(&app_handle, goal, model_name.as_deref(), budget, *use_rag).await;
+            (run.result, run.steps)
+        },
+        ActionType::RunQuickAction { action_id, text } => {
+            (execute_quick_action(action_id, text, &app_handle).await, Vec::new())
+        },
+        ActionType::MemoryDigest { days, model_name } => {
+            (digest::run_digest(&app_handle, *days, model_name.as_deref()).await, Vec::new())
         },
     };
 
@@ -568,6 +885,7 @@ async fn execute_task_action(task: &Task, app_handle: AppHandle) {
             status: ExecutionStatus::Success,
             message: Some(msg),
             error: None,
+            steps,
         },
         Err(err) => TaskExecutionLog {
             task_id: task.id.clone(),
@@ -575,13 +893,14 @@ async fn execute_task_action(task: &Task, app_handle: AppHandle) {
             status: ExecutionStatus::Failed,
             message: None,
             error: Some(err.clone()),
+            steps,
         },
     };
 
     // Update task and save log
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     let should_delete = {
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         
         if let Some(mut updated_task) = scheduler.get_task(&task.id).cloned() {
             updated_task.last_run = Some(Utc::now());
@@ -597,9 +916,7 @@ async fn execute_task_action(task: &Task, app_handle: AppHandle) {
                 scheduler.update_task(updated_task.clone());
             }
             
-            let storage = TaskStorage {
-                tasks: scheduler.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-            };
+            let storage = scheduler.to_storage();
             let _ = save_tasks_to_file(&storage);
             
             should_delete
@@ -611,23 +928,138 @@ async fn execute_task_action(task: &Task, app_handle: AppHandle) {
     // Delete task if needed (outside the lock to avoid deadlock)
     if should_delete {
         info!("Auto-deleting one-time task: {} ({})", task.name, task.id);
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         scheduler.remove_task(&task.id);
-        let storage = TaskStorage {
-            tasks: scheduler.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-        };
+        let storage = scheduler.to_storage();
         let _ = save_tasks_to_file(&storage);
     }
     
     {
-        let mut scheduler = scheduler.lock().unwrap();
+        let mut scheduler = scheduler.lock();
         scheduler.add_execution_log(log.clone());
     }
 
+    crate::journal::record_task_run(&app_handle, task, &log).await;
+
     // Emit event to UI
     let _ = app_handle.emit("task-executed", log);
 }
 
+/// Run a task fired by an event rather than the clock. Pause, snooze and run
+/// conditions still apply, but an unmet condition skips this occurrence
+/// since there is no schedule to defer it to.
+pub(crate) async fn run_triggered_task(task: Task, context: HashMap<String, String>, app_handle: AppHandle) {
+    let blocked = {
+        let scheduler = scheduler().lock();
+        if scheduler.is_paused() {
+            Some("Task scheduler is paused".to_string())
+        } else if !task.enabled {
+            Some("Task is disabled".to_string())
+        } else if task.is_snoozed(Utc::now()) {
+            Some("Task is snoozed".to_string())
+        } else {
+            None
+        }
+    };
+
+    let blocked = match blocked {
+        Some(reason) => Some(reason),
+        None => check_run_conditions(&task.conditions).await.err(),
+    };
+
+    if let Some(reason) = blocked {
+        info!("Skipping triggered task {} ({}): {}", task.name, task.id, reason);
+        let log = TaskExecutionLog {
+            task_id: task.id.clone(),
+            executed_at: Utc::now(),
+            status: ExecutionStatus::Skipped,
+            message: Some(reason),
+            error: None,
+            steps: Vec::new(),
+        };
+        scheduler().lock().add_execution_log(log.clone());
+        let _ = app_handle.emit("task-executed", log);
+        return;
+    }
+
+    execute_task_action(&task.with_trigger_context(&context), app_handle).await;
+}
+
+/// Returns the reason the task should not run yet, if any condition fails.
+/// Conditions the platform cannot probe are treated as satisfied.
+async fn check_run_conditions(conditions: &RunConditions) -> Result<(), String> {
+    if conditions.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(minutes) = conditions.idle_minutes {
+        match system_state::idle_seconds() {
+            Some(idle) if idle < minutes as u64 * 60 => {
+                return Err(format!("Waiting for {} idle minutes (idle for {}s)", minutes, idle));
+            },
+            None => debug!("Idle time unavailable on this platform, ignoring idle condition"),
+            _ => {}
+        }
+    }
+
+    if conditions.require_ac_power && system_state::on_ac_power() == Some(false) {
+        return Err("Waiting for AC power".to_string());
+    }
+
+    if conditions.require_model_loaded {
+        let loaded = crate::ovms::check_ovms_status().await
+            .map(|status| status.loaded_models)
+            .unwrap_or_default();
+
+        let satisfied = match &conditions.model_name {
+            Some(required) => loaded.iter().any(|m| {
+                m == required || required.ends_with(&format!("/{}", m))
+            }),
+            None => !loaded.is_empty(),
+        };
+
+        if !satisfied {
+            return Err(match &conditions.model_name {
+                Some(required) => format!("Waiting for model '{}' to be loaded", required),
+                None => "Waiting for a model to be loaded".to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Push a due task back while its run conditions are unmet. Only the first
+/// deferral of a streak is logged so retries don't flood the execution log.
+fn defer_task(task: &Task, reason: String, app_handle: &AppHandle) {
+    debug!("Deferring task {} ({}): {}", task.name, task.id, reason);
+
+    let scheduler = scheduler();
+    let mut scheduler = scheduler.lock();
+
+    let Some(mut updated_task) = scheduler.get_task(&task.id).cloned() else {
+        return;
+    };
+    updated_task.next_run = Some(Utc::now() + Duration::seconds(CONDITION_RECHECK_SECS));
+    scheduler.update_task(updated_task);
+
+    let storage = scheduler.to_storage();
+    let _ = save_tasks_to_file(&storage);
+
+    if !matches!(scheduler.last_log_status(&task.id), Some(ExecutionStatus::Skipped)) {
+        let log = TaskExecutionLog {
+            task_id: task.id.clone(),
+            executed_at: Utc::now(),
+            status: ExecutionStatus::Skipped,
+            message: Some(reason),
+            error: None,
+            steps: Vec::new(),
+        };
+        scheduler.add_execution_log(log.clone());
+        let _ = app_handle.emit("task-executed", log);
+    }
+}
+
 async fn execute_show_notification(title: &str, message: &str, app_handle: &AppHandle) -> Result<String, String> {
     info!("Executing ShowNotification action: {} - {}", title, message);
     
@@ -641,10 +1073,19 @@ async fn execute_show_notification(title: &str, message: &str, app_handle: &AppH
         .body(message)
         .show()
         .map_err(|e| format!("Failed to show notification: {}", e))?;
+    notifications::record(app_handle, title, message, "task");
     
     Ok(format!("Notification shown: {}", title))
 }
 
+async fn execute_quick_action(action_id: &str, text: &str, app_handle: &AppHandle) -> Result<String, String> {
+    match quick_actions::execute(app_handle, action_id, text, None).await? {
+        QuickActionResult::Chat { .. } => Ok(format!("Sent quick action '{}' to chat", action_id)),
+        QuickActionResult::Clipboard { text } => Ok(format!("Copied {} characters to the clipboard", text.chars().count())),
+        QuickActionResult::File { path, .. } => Ok(format!("Wrote result to {}", path)),
+    }
+}
+
 async fn execute_mcp_function(
     server_name: &str,
     tool_name: &str,
@@ -658,7 +1099,7 @@ async fn execute_mcp_function(
         info!("Executing builtin tool: {}", tool_name);
         
         // Execute builtin tool directly using the command
-        match crate::mcp::execute_builtin_tool(tool_name.to_string(), arguments.clone()).await {
+        match crate::mcp::run_builtin_tool(tool_name.to_string(), arguments.clone()).await {
             Ok(_result) => {
                 info!("Builtin tool executed successfully");
                 Ok(format!("Built-in function {} executed successfully", tool_name))
@@ -701,18 +1142,19 @@ pub async fn start_task_scheduler(app_handle: AppHandle) {
     info!("Starting task scheduler");
     
     // Initialize scheduler
-    let scheduler = TASK_SCHEDULER.get_or_init(|| Arc::new(Mutex::new(TaskScheduler::new())));
+    let scheduler = scheduler();
     
     // Set app handle
     {
-        let mut sched = scheduler.lock().unwrap();
+        let mut sched = scheduler.lock();
         sched.set_app_handle(app_handle.clone());
     }
 
     // Load tasks from file
     match load_tasks_from_file() {
         Ok(storage) => {
-            let mut sched = scheduler.lock().unwrap();
+            let mut sched = scheduler.lock();
+            sched.set_paused(storage.paused);
             for task in storage.tasks.values() {
                 // Recalculate next_run with correct timezone logic
                 let mut task = task.clone();
@@ -720,11 +1162,10 @@ pub async fn start_task_scheduler(app_handle: AppHandle) {
                 sched.add_task(task);
             }
             info!("Loaded {} tasks", storage.tasks.len());
+            digest::ensure_builtin_task(&mut sched);
             
             // Save updated tasks with recalculated next_run times
-            let storage = TaskStorage {
-                tasks: sched.get_all_tasks().into_iter().map(|t| (t.id.clone(), t)).collect(),
-            };
+            let storage = sched.to_storage();
             let _ = save_tasks_to_file(&storage);
         },
         Err(e) => {
@@ -732,30 +1173,30 @@ pub async fn start_task_scheduler(app_handle: AppHandle) {
         }
     }
 
+    file_watch::start_file_event_loop(app_handle.clone());
+    file_watch::sync_watchers();
+    system_events::start_system_event_monitor(app_handle.clone());
+
     // Start scheduler loop
     tokio::spawn(async move {
         loop {
             sleep(std::time::Duration::from_secs(1)).await; // Check every second for accurate timing
             
             let tasks_to_execute = {
-                let scheduler = scheduler.lock().unwrap();
-                let now = Utc::now();
-                
-                scheduler.get_all_tasks()
-                    .into_iter()
-                    .filter(|task| {
-                        task.enabled && 
-                        task.next_run.is_some() && 
-                        task.next_run.unwrap() <= now
-                    })
-                    .collect::<Vec<_>>()
+                let mut scheduler = scheduler.lock();
+                scheduler.take_due_tasks(Utc::now())
             };
 
             for task in tasks_to_execute {
                 info!("Triggering scheduled task: {} ({})", task.name, task.id);
                 let app_handle = app_handle.clone();
                 tokio::spawn(async move {
-                    execute_task_action(&task, app_handle).await;
+                    match check_run_conditions(&task.conditions).await {
+                        Ok(()) => execute_task_action(&task, app_handle).await,
+                        Err(reason) => defer_task(&task, reason, &app_handle),
+                    }
+
+                    scheduler.lock().finish_running(&task.id);
                 });
             }
         }
@@ -0,0 +1,127 @@
+//! Detection of system-level events backing `TriggerTime::SystemEvent`.
+//!
+//! None of these need OS hooks: startup fires once after tasks are loaded,
+//! resume is detected as a jump in wall-clock time between two short sleeps
+//! (the process was suspended), and connectivity is probed by periodically
+//! opening a TCP connection to the model hub.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{debug, info};
+
+use super::TriggerTime;
+
+const RESUME_TICK: Duration = Duration::from_secs(5);
+
+/// A wall-clock gap this much larger than the tick means the machine slept
+const RESUME_GAP_SECS: i64 = 30;
+
+const NETWORK_POLL: Duration = Duration::from_secs(30);
+const NETWORK_PROBE_HOST: (&str, u16) = ("huggingface.co", 443);
+const NETWORK_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SystemEventKind {
+    AppStartup,
+    SystemResume,
+    NetworkOnline,
+    NetworkOffline,
+}
+
+impl SystemEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemEventKind::AppStartup => "app_startup",
+            SystemEventKind::SystemResume => "system_resume",
+            SystemEventKind::NetworkOnline => "network_online",
+            SystemEventKind::NetworkOffline => "network_offline",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            SystemEventKind::AppStartup => "When SparrowAI starts",
+            SystemEventKind::SystemResume => "When the computer wakes from sleep",
+            SystemEventKind::NetworkOnline => "When the computer goes online",
+            SystemEventKind::NetworkOffline => "When the computer goes offline",
+        }
+    }
+}
+
+/// Run every enabled task subscribed to `kind`
+pub fn fire(kind: SystemEventKind, app_handle: &AppHandle) {
+    let tasks: Vec<_> = {
+        let scheduler = super::scheduler().lock();
+        scheduler
+            .get_all_tasks()
+            .into_iter()
+            .filter(|task| task.enabled)
+            .filter(|task| matches!(&task.trigger_time, TriggerTime::SystemEvent { event } if *event == kind))
+            .collect()
+    };
+
+    if tasks.is_empty() {
+        return;
+    }
+
+    info!(event = kind.as_str(), count = tasks.len(), "System event triggered tasks");
+
+    for task in tasks {
+        let context = HashMap::from([
+            ("event".to_string(), kind.as_str().to_string()),
+            ("occurred_at".to_string(), Utc::now().to_rfc3339()),
+        ]);
+        let app_handle = app_handle.clone();
+        tokio::spawn(async move {
+            super::run_triggered_task(task, context, app_handle).await;
+        });
+    }
+}
+
+/// Fire the startup event and start the resume / connectivity monitors
+pub fn start_system_event_monitor(app_handle: AppHandle) {
+    fire(SystemEventKind::AppStartup, &app_handle);
+
+    let resume_handle = app_handle.clone();
+    tokio::spawn(async move {
+        let mut last = Utc::now();
+        loop {
+            tokio::time::sleep(RESUME_TICK).await;
+            let now = Utc::now();
+            let gap = (now - last).num_seconds();
+            if gap > RESUME_TICK.as_secs() as i64 + RESUME_GAP_SECS {
+                info!(gap_secs = gap, "Detected system resume");
+                fire(SystemEventKind::SystemResume, &resume_handle);
+            }
+            last = now;
+        }
+    });
+
+    tokio::spawn(async move {
+        // The first probe only establishes the baseline, it is not a change
+        let mut online = is_online().await;
+        debug!(online, "Initial connectivity state");
+
+        loop {
+            tokio::time::sleep(NETWORK_POLL).await;
+            let now_online = is_online().await;
+            if now_online != online {
+                online = now_online;
+                info!(online, "Network connectivity changed");
+                let kind = if online { SystemEventKind::NetworkOnline } else { SystemEventKind::NetworkOffline };
+                fire(kind, &app_handle);
+            }
+        }
+    });
+}
+
+async fn is_online() -> bool {
+    matches!(
+        tokio::time::timeout(NETWORK_PROBE_TIMEOUT, tokio::net::TcpStream::connect(NETWORK_PROBE_HOST)).await,
+        Ok(Ok(_))
+    )
+}
@@ -0,0 +1,633 @@
+//! Validation and normalization of loosely structured task definitions.
+//!
+//! The `create_task` builtin tool receives JSON written by the model, which is
+//! frequently close-but-wrong ("9:5", "monday", a datetime in the past). This
+//! layer normalizes what it can, reports everything else as machine-readable
+//! issues, and renders a preview of the resulting schedule so the task can be
+//! confirmed before it is committed.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::file_watch::{self, FileEventKind};
+use super::system_events::SystemEventKind;
+use super::{ActionType, RepeatInterval, RunConditions, Task, TaskScheduler, TimeUnit, TriggerTime};
+
+const WEEKDAYS: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    /// Dotted path of the offending field, e.g. `trigger_time.time`
+    pub field: String,
+    /// Stable identifier the model (or UI) can branch on
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(field: &str, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A fully validated task definition, ready to be passed to `create_task`
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskDraft {
+    pub name: String,
+    pub action_type: ActionType,
+    pub trigger_time: TriggerTime,
+    pub repeat_interval: Option<RepeatInterval>,
+    pub auto_delete: bool,
+    pub conditions: RunConditions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulePreview {
+    pub summary: String,
+    pub next_runs: Vec<DateTime<Utc>>,
+    /// Same instants as `next_runs`, formatted in the user's local timezone
+    pub next_runs_local: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskValidationReport {
+    pub valid: bool,
+    pub errors: Vec<ValidationIssue>,
+    pub task: Option<TaskDraft>,
+    pub preview: Option<SchedulePreview>,
+}
+
+/// Validate raw tool arguments, collecting every issue rather than stopping at the first
+pub fn validate_task_arguments(arguments: &Value) -> Result<TaskDraft, Vec<ValidationIssue>> {
+    let mut issues = Vec::new();
+
+    let name = match arguments.get("name").and_then(|v| v.as_str()).map(str::trim) {
+        Some(name) if !name.is_empty() => Some(name.to_string()),
+        Some(_) => {
+            issues.push(ValidationIssue::new("name", "empty_value", "Task name must not be empty"));
+            None
+        },
+        None => {
+            issues.push(ValidationIssue::new("name", "missing_field", "Missing 'name'"));
+            None
+        },
+    };
+
+    let action_type = match arguments.get("action_type") {
+        Some(action) => parse_action(action, &mut issues),
+        None => {
+            issues.push(ValidationIssue::new("action_type", "missing_field", "Missing 'action_type'"));
+            None
+        },
+    };
+
+    let trigger_time = match arguments.get("trigger_time") {
+        Some(trigger) => parse_trigger(trigger, &mut issues),
+        None => {
+            issues.push(ValidationIssue::new("trigger_time", "missing_field", "Missing 'trigger_time'"));
+            None
+        },
+    };
+
+    let repeat_interval = match arguments.get("repeat_interval") {
+        Some(Value::Null) | None => None,
+        Some(value) => match serde_json::from_value::<RepeatInterval>(value.clone()) {
+            Ok(interval) if interval.value == 0 => {
+                issues.push(ValidationIssue::new("repeat_interval.value", "out_of_range", "Repeat interval must be at least 1"));
+                None
+            },
+            Ok(interval) => Some(interval),
+            Err(e) => {
+                issues.push(ValidationIssue::new("repeat_interval", "invalid_type", e.to_string()));
+                None
+            },
+        },
+    };
+
+    let auto_delete = match arguments.get("auto_delete") {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Null) | None => false,
+        Some(_) => {
+            issues.push(ValidationIssue::new("auto_delete", "invalid_type", "'auto_delete' must be a boolean"));
+            false
+        },
+    };
+
+    let conditions = match arguments.get("conditions") {
+        Some(Value::Null) | None => RunConditions::default(),
+        Some(value) => serde_json::from_value::<RunConditions>(value.clone()).unwrap_or_else(|e| {
+            issues.push(ValidationIssue::new("conditions", "invalid_type", e.to_string()));
+            RunConditions::default()
+        }),
+    };
+
+    if let Some(TriggerTime::DateTime { datetime }) = &trigger_time {
+        if *datetime <= Utc::now() && repeat_interval.is_none() {
+            issues.push(ValidationIssue::new(
+                "trigger_time.datetime",
+                "past_datetime",
+                format!(
+                    "{} is in the past (current local time is {})",
+                    datetime.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    Local::now().format("%Y-%m-%d %H:%M"),
+                ),
+            ));
+        }
+    }
+
+    match (name, action_type, trigger_time) {
+        (Some(name), Some(action_type), Some(trigger_time)) if issues.is_empty() => Ok(TaskDraft {
+            name,
+            action_type,
+            trigger_time,
+            repeat_interval,
+            auto_delete,
+            conditions,
+        }),
+        _ => Err(issues),
+    }
+}
+
+/// Validate and, when valid, attach a preview of the next `preview_count` runs
+pub fn build_report(arguments: &Value, preview_count: usize) -> TaskValidationReport {
+    match validate_task_arguments(arguments) {
+        Ok(draft) => {
+            let preview = preview_schedule(&draft, preview_count);
+            TaskValidationReport {
+                valid: true,
+                errors: Vec::new(),
+                task: Some(draft),
+                preview: Some(preview),
+            }
+        },
+        Err(errors) => TaskValidationReport {
+            valid: false,
+            errors,
+            task: None,
+            preview: None,
+        },
+    }
+}
+
+fn parse_action(action: &Value, issues: &mut Vec<ValidationIssue>) -> Option<ActionType> {
+    let Some(kind) = action.get("type").and_then(|v| v.as_str()) else {
+        issues.push(ValidationIssue::new("action_type.type", "missing_field", "Missing 'action_type.type'"));
+        return None;
+    };
+
+    match kind {
+        "ShowNotification" => {
+            let title = required_str(action, "action_type", "title", issues);
+            let message = required_str(action, "action_type", "message", issues);
+            Some(ActionType::ShowNotification { title: title?, message: message? })
+        },
+        "RunMcpFunction" => {
+            let server_name = required_str(action, "action_type", "server_name", issues);
+            let tool_name = required_str(action, "action_type", "tool_name", issues);
+            let arguments = match action.get("arguments") {
+                Some(Value::Object(map)) => Value::Object(map.clone()),
+                Some(Value::Null) | None => Value::Object(Default::default()),
+                Some(_) => {
+                    issues.push(ValidationIssue::new("action_type.arguments", "invalid_type", "'arguments' must be an object"));
+                    return None;
+                },
+            };
+            Some(ActionType::RunMcpFunction { server_name: server_name?, tool_name: tool_name?, arguments })
+        },
+        "AgentTask" => {
+            let goal = required_str(action, "action_type", "goal", issues)?;
+            let model_name = action.get("model_name").and_then(|v| v.as_str()).map(str::to_string);
+            let max_steps = action.get("max_steps").and_then(|v| v.as_u64()).map(|n| n as u32);
+            let max_duration_secs = action.get("max_duration_secs").and_then(|v| v.as_u64());
+            let use_rag = action.get("use_rag").and_then(|v| v.as_bool()).unwrap_or(false);
+            Some(ActionType::AgentTask { goal, model_name, max_steps, max_duration_secs, use_rag })
+        },
+        "RunQuickAction" => {
+            let action_id = required_str(action, "action_type", "action_id", issues);
+            let text = required_str(action, "action_type", "text", issues);
+            Some(ActionType::RunQuickAction { action_id: action_id?, text: text? })
+        },
+        "MemoryDigest" => {
+            let days = action.get("days").and_then(|v| v.as_u64()).map(|n| n as u32);
+            let model_name = action.get("model_name").and_then(|v| v.as_str()).map(str::to_string);
+            Some(ActionType::MemoryDigest { days, model_name })
+        },
+        other => {
+            issues.push(ValidationIssue::new(
+                "action_type.type",
+                "unknown_variant",
+                format!("Unknown action type '{}' (expected ShowNotification, RunMcpFunction, AgentTask, RunQuickAction or MemoryDigest)", other),
+            ));
+            None
+        },
+    }
+}
+
+fn parse_trigger(trigger: &Value, issues: &mut Vec<ValidationIssue>) -> Option<TriggerTime> {
+    let Some(kind) = trigger.get("type").and_then(|v| v.as_str()) else {
+        issues.push(ValidationIssue::new("trigger_time.type", "missing_field", "Missing 'trigger_time.type'"));
+        return None;
+    };
+
+    match kind {
+        "DateTime" => {
+            let raw = required_str(trigger, "trigger_time", "datetime", issues)?;
+            match parse_datetime(&raw) {
+                Ok(datetime) => Some(TriggerTime::DateTime { datetime }),
+                Err(issue) => {
+                    issues.push(issue);
+                    None
+                },
+            }
+        },
+        "Daily" => {
+            let time = required_time(trigger, issues)?;
+            Some(TriggerTime::Daily { time })
+        },
+        "Weekly" => {
+            let day = parse_weekday(trigger.get("day_of_week"), issues);
+            let time = required_time(trigger, issues);
+            Some(TriggerTime::Weekly { day_of_week: day?, time: time? })
+        },
+        "Monthly" => {
+            let day = match trigger.get("day_of_month").and_then(|v| v.as_u64()) {
+                Some(day) if (1..=31).contains(&day) => Some(day as u8),
+                Some(day) => {
+                    issues.push(ValidationIssue::new("trigger_time.day_of_month", "out_of_range", format!("Day of month {} is not between 1 and 31", day)));
+                    None
+                },
+                None => {
+                    issues.push(ValidationIssue::new("trigger_time.day_of_month", "missing_field", "Missing integer 'day_of_month'"));
+                    None
+                },
+            };
+            let time = required_time(trigger, issues);
+            Some(TriggerTime::Monthly { day_of_month: day?, time: time? })
+        },
+        "EveryNMinutes" => {
+            let minutes = required_positive(trigger, "minutes", issues)?;
+            Some(TriggerTime::EveryNMinutes { minutes })
+        },
+        "EveryNHours" => {
+            let hours = required_positive(trigger, "hours", issues)?;
+            Some(TriggerTime::EveryNHours { hours })
+        },
+        "FileEvent" => {
+            let path = required_str(trigger, "trigger_time", "path", issues)?;
+            if !file_watch::expand_path(&path).is_dir() {
+                issues.push(ValidationIssue::new(
+                    "trigger_time.path",
+                    "path_not_found",
+                    format!("Folder '{}' does not exist", path),
+                ));
+                return None;
+            }
+            let pattern = trigger.get("pattern").and_then(|v| v.as_str()).map(str::to_string);
+            let event = match trigger.get("event") {
+                Some(Value::Null) | None => FileEventKind::default(),
+                Some(value) => match serde_json::from_value::<FileEventKind>(value.clone()) {
+                    Ok(event) => event,
+                    Err(_) => {
+                        issues.push(ValidationIssue::new(
+                            "trigger_time.event",
+                            "unknown_variant",
+                            "Event must be one of Created, Modified, Removed, Any",
+                        ));
+                        return None;
+                    },
+                },
+            };
+            let recursive = trigger.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
+            Some(TriggerTime::FileEvent { path, pattern, event, recursive })
+        },
+        "SystemEvent" => {
+            let raw = required_str(trigger, "trigger_time", "event", issues)?;
+            match serde_json::from_value::<SystemEventKind>(Value::String(raw.clone())) {
+                Ok(event) => Some(TriggerTime::SystemEvent { event }),
+                Err(_) => {
+                    issues.push(ValidationIssue::new(
+                        "trigger_time.event",
+                        "unknown_variant",
+                        format!("'{}' is not a system event (expected AppStartup, SystemResume, NetworkOnline or NetworkOffline)", raw),
+                    ));
+                    None
+                },
+            }
+        },
+        other => {
+            issues.push(ValidationIssue::new(
+                "trigger_time.type",
+                "unknown_variant",
+                format!("Unknown trigger type '{}'", other),
+            ));
+            None
+        },
+    }
+}
+
+fn required_str(obj: &Value, parent: &str, key: &str, issues: &mut Vec<ValidationIssue>) -> Option<String> {
+    let field = format!("{}.{}", parent, key);
+    match obj.get(key) {
+        Some(Value::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Some(Value::String(_)) => {
+            issues.push(ValidationIssue::new(&field, "empty_value", format!("'{}' must not be empty", key)));
+            None
+        },
+        Some(_) => {
+            issues.push(ValidationIssue::new(&field, "invalid_type", format!("'{}' must be a string", key)));
+            None
+        },
+        None => {
+            issues.push(ValidationIssue::new(&field, "missing_field", format!("Missing '{}'", field)));
+            None
+        },
+    }
+}
+
+fn required_time(trigger: &Value, issues: &mut Vec<ValidationIssue>) -> Option<String> {
+    let raw = required_str(trigger, "trigger_time", "time", issues)?;
+    match normalize_time(&raw) {
+        Some(time) => Some(time),
+        None => {
+            issues.push(ValidationIssue::new(
+                "trigger_time.time",
+                "invalid_time_format",
+                format!("'{}' is not a valid time, expected 24-hour HH:MM", raw),
+            ));
+            None
+        },
+    }
+}
+
+fn required_positive(trigger: &Value, key: &str, issues: &mut Vec<ValidationIssue>) -> Option<u32> {
+    let field = format!("trigger_time.{}", key);
+    match trigger.get(key).and_then(|v| v.as_u64()) {
+        Some(0) => {
+            issues.push(ValidationIssue::new(&field, "out_of_range", format!("'{}' must be at least 1", key)));
+            None
+        },
+        Some(n) => Some(n.min(u32::MAX as u64) as u32),
+        None => {
+            issues.push(ValidationIssue::new(&field, "missing_field", format!("Missing positive integer '{}'", key)));
+            None
+        },
+    }
+}
+
+fn parse_weekday(value: Option<&Value>, issues: &mut Vec<ValidationIssue>) -> Option<u8> {
+    let field = "trigger_time.day_of_week";
+    match value {
+        Some(Value::Number(n)) => match n.as_u64() {
+            Some(day) if day <= 6 => Some(day as u8),
+            _ => {
+                issues.push(ValidationIssue::new(field, "out_of_range", "Day of week must be 0 (Sunday) to 6 (Saturday)"));
+                None
+            },
+        },
+        Some(Value::String(s)) => {
+            let lower = s.trim().to_lowercase();
+            let found = WEEKDAYS.iter().position(|day| lower.len() >= 3 && day.starts_with(&lower));
+            if found.is_none() {
+                issues.push(ValidationIssue::new(field, "unknown_variant", format!("'{}' is not a day of the week", s)));
+            }
+            found.map(|i| i as u8)
+        },
+        _ => {
+            issues.push(ValidationIssue::new(field, "missing_field", "Missing 'day_of_week'"));
+            None
+        },
+    }
+}
+
+/// Accept common spellings of a time of day and return canonical `HH:MM`
+pub fn normalize_time(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    ["%H:%M", "%H:%M:%S", "%I:%M %p", "%I:%M%p", "%H%M"]
+        .iter()
+        .find_map(|fmt| NaiveTime::parse_from_str(trimmed, fmt).ok())
+        .map(|t| t.format("%H:%M").to_string())
+}
+
+/// RFC 3339 instants are taken as-is; naive datetimes are interpreted in local time
+fn parse_datetime(raw: &str) -> Result<DateTime<Utc>, ValidationIssue> {
+    let field = "trigger_time.datetime";
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(raw, fmt).ok())
+        .ok_or_else(|| ValidationIssue::new(
+            field,
+            "invalid_datetime_format",
+            format!("'{}' is not a valid datetime, expected ISO 8601 such as 2025-01-31T09:00:00", raw),
+        ))?;
+
+    Local.from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| ValidationIssue::new(
+            field,
+            "ambiguous_local_time",
+            format!("'{}' does not exist or is ambiguous in the local timezone", raw),
+        ))
+}
+
+pub fn preview_schedule(draft: &TaskDraft, count: usize) -> SchedulePreview {
+    let next_runs = preview_runs(draft, count);
+    SchedulePreview {
+        summary: describe_schedule(&draft.trigger_time, draft.repeat_interval.as_ref()),
+        next_runs_local: next_runs
+            .iter()
+            .map(|dt| dt.with_timezone(&Local).format("%a %Y-%m-%d %H:%M").to_string())
+            .collect(),
+        next_runs,
+    }
+}
+
+fn preview_runs(draft: &TaskDraft, count: usize) -> Vec<DateTime<Utc>> {
+    let task = Task {
+        id: String::new(),
+        name: draft.name.clone(),
+        enabled: true,
+        action_type: draft.action_type.clone(),
+        action_params: Value::Null,
+        trigger_time: draft.trigger_time.clone(),
+        repeat_interval: draft.repeat_interval.clone(),
+        created_at: Utc::now(),
+        last_run: None,
+        next_run: None,
+        run_count: 0,
+        auto_delete: draft.auto_delete,
+        snoozed_until: None,
+        conditions: draft.conditions.clone(),
+    };
+
+    let Some(first) = TaskScheduler::new().calculate_next_run(&task) else {
+        return Vec::new();
+    };
+
+    let mut runs = vec![first];
+    while runs.len() < count {
+        let last = runs[runs.len() - 1];
+        let next = match &draft.trigger_time {
+            TriggerTime::DateTime { .. } => draft.repeat_interval.as_ref().map(|i| last + interval_duration(i)),
+            TriggerTime::Daily { .. } => shift_local_days(last, 1),
+            TriggerTime::Weekly { .. } => shift_local_days(last, 7),
+            TriggerTime::Monthly { day_of_month, .. } => next_monthly(last, *day_of_month),
+            TriggerTime::EveryNMinutes { minutes } => Some(last + Duration::minutes(*minutes as i64)),
+            TriggerTime::EveryNHours { hours } => Some(last + Duration::hours(*hours as i64)),
+            TriggerTime::FileEvent { .. } | TriggerTime::SystemEvent { .. } => None,
+        };
+        match next {
+            Some(next) => runs.push(next),
+            None => break,
+        }
+    }
+    runs
+}
+
+fn interval_duration(interval: &RepeatInterval) -> Duration {
+    match interval.unit {
+        TimeUnit::Minutes => Duration::minutes(interval.value as i64),
+        TimeUnit::Hours => Duration::hours(interval.value as i64),
+        TimeUnit::Days => Duration::days(interval.value as i64),
+        TimeUnit::Weeks => Duration::weeks(interval.value as i64),
+    }
+}
+
+/// Same local wall-clock time `days` later (keeps DST transitions honest)
+fn shift_local_days(from: DateTime<Utc>, days: i64) -> Option<DateTime<Utc>> {
+    let local = from.with_timezone(&Local);
+    let target = (local.date_naive() + Duration::days(days)).and_time(local.time());
+    Local.from_local_datetime(&target).single().map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Next month that actually has `day` (e.g. the 31st skips 30-day months)
+fn next_monthly(from: DateTime<Utc>, day: u8) -> Option<DateTime<Utc>> {
+    let local = from.with_timezone(&Local);
+    let (mut year, mut month) = (local.year(), local.month());
+
+    for _ in 0..12 {
+        if month == 12 {
+            year += 1;
+            month = 1;
+        } else {
+            month += 1;
+        }
+        if let Some(date) = chrono::NaiveDate::from_ymd_opt(year, month, day as u32) {
+            let target = date.and_time(local.time());
+            return Local.from_local_datetime(&target).single().map(|dt| dt.with_timezone(&Utc));
+        }
+    }
+    None
+}
+
+pub fn describe_schedule(trigger: &TriggerTime, repeat: Option<&RepeatInterval>) -> String {
+    match trigger {
+        TriggerTime::DateTime { datetime } => {
+            let when = datetime.with_timezone(&Local).format("%a %Y-%m-%d %H:%M");
+            match repeat {
+                Some(interval) => format!(
+                    "Starting {}, repeating every {} {}",
+                    when,
+                    interval.value,
+                    format!("{:?}", interval.unit).to_lowercase(),
+                ),
+                None => format!("Once at {}", when),
+            }
+        },
+        TriggerTime::Daily { time } => format!("Every day at {}", time),
+        TriggerTime::Weekly { day_of_week, time } => {
+            let day = WEEKDAYS.get(*day_of_week as usize).copied().unwrap_or("?");
+            format!("Every {}{} at {}", day[..1].to_uppercase(), &day[1..], time)
+        },
+        TriggerTime::Monthly { day_of_month, time } => format!("On day {} of every month at {}", day_of_month, time),
+        TriggerTime::EveryNMinutes { minutes } => format!("Every {} minute(s)", minutes),
+        TriggerTime::EveryNHours { hours } => format!("Every {} hour(s)", hours),
+        TriggerTime::FileEvent { path, pattern, event, .. } => format!(
+            "When a file matching '{}' is {} in {}",
+            pattern.as_deref().unwrap_or("*"),
+            match event {
+                FileEventKind::Created => "added",
+                FileEventKind::Modified => "modified",
+                FileEventKind::Removed => "removed",
+                FileEventKind::Any => "added, modified or removed",
+            },
+            path,
+        ),
+        TriggerTime::SystemEvent { event } => event.describe().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_time() {
+        assert_eq!(normalize_time("9:05"), Some("09:05".to_string()));
+        assert_eq!(normalize_time("21:30:00"), Some("21:30".to_string()));
+        assert_eq!(normalize_time("7:15 PM"), Some("19:15".to_string()));
+        assert_eq!(normalize_time("25:00"), None);
+        assert_eq!(normalize_time("noon"), None);
+    }
+
+    #[test]
+    fn test_collects_all_issues() {
+        let issues = validate_task_arguments(&json!({
+            "action_type": { "type": "ShowNotification", "title": "Hi" },
+            "trigger_time": { "type": "Daily", "time": "9h" }
+        }))
+        .unwrap_err();
+
+        let codes: Vec<_> = issues.iter().map(|i| (i.field.as_str(), i.code)).collect();
+        assert!(codes.contains(&("name", "missing_field")));
+        assert!(codes.contains(&("action_type.message", "missing_field")));
+        assert!(codes.contains(&("trigger_time.time", "invalid_time_format")));
+    }
+
+    #[test]
+    fn test_rejects_past_datetime() {
+        let issues = validate_task_arguments(&json!({
+            "name": "Old",
+            "action_type": { "type": "ShowNotification", "title": "Hi", "message": "There" },
+            "trigger_time": { "type": "DateTime", "datetime": "2000-01-01T00:00:00Z" }
+        }))
+        .unwrap_err();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].code, "past_datetime");
+    }
+
+    #[test]
+    fn test_weekday_names_and_preview() {
+        let draft = validate_task_arguments(&json!({
+            "name": "Standup",
+            "action_type": { "type": "ShowNotification", "title": "Standup", "message": "Now" },
+            "trigger_time": { "type": "Weekly", "day_of_week": "Mon", "time": "9:30" }
+        }))
+        .unwrap();
+
+        match &draft.trigger_time {
+            TriggerTime::Weekly { day_of_week, time } => {
+                assert_eq!(*day_of_week, 1);
+                assert_eq!(time, "09:30");
+            },
+            other => panic!("unexpected trigger: {:?}", other),
+        }
+
+        let preview = preview_schedule(&draft, 3);
+        assert_eq!(preview.next_runs.len(), 3);
+        assert_eq!(preview.summary, "Every Monday at 09:30");
+        assert!(preview.next_runs.windows(2).all(|w| w[1] > w[0]));
+    }
+}
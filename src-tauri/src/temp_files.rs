@@ -0,0 +1,139 @@
+//! Uploads staged for document processing, under `~/.sparrow/tmp`.
+//!
+//! `save_temp_file` puts each upload in its own subfolder so the original
+//! file name (which the vector store shows) survives without collisions.
+//! Folders older than `temp_files.ttl_hours` are removed by the periodic
+//! maintenance task; `purge_temp_files` clears them on demand.
+
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, SystemTime };
+
+use serde::Serialize;
+
+use crate::{ path_policy, paths, settings };
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    pub removed_entries: usize,
+    pub reclaimed_bytes: u64,
+}
+
+/// Write `data` as `file_name` into a fresh staging folder and return its path
+pub fn stage(file_name: &str, data: &[u8]) -> Result<PathBuf, String> {
+    let file_name = path_policy::safe_file_name(file_name)?;
+    let dir = paths::get_temp_dir()
+        .map_err(|e| e.to_string())?
+        .join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp folder: {}", e))?;
+
+    let file_path = dir.join(file_name);
+    fs::write(&file_path, data).map_err(|e| format!("Failed to save temp file: {}", e))?;
+    Ok(file_path)
+}
+
+pub(crate) fn size_of(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Remove entries of `dir` last modified before `cutoff` (all of them when `None`)
+fn purge_dir(dir: &Path, cutoff: Option<SystemTime>) -> PurgeReport {
+    let mut report = PurgeReport::default();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return report;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let modified = entry.metadata().and_then(|m| m.modified()).ok();
+        if let (Some(cutoff), Some(modified)) = (cutoff, modified) {
+            if modified >= cutoff {
+                continue;
+            }
+        }
+
+        let size = size_of(&path);
+        let removed = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        match removed {
+            Ok(()) => {
+                report.removed_entries += 1;
+                report.reclaimed_bytes += size;
+            }
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "Failed to remove temp file"),
+        }
+    }
+    report
+}
+
+/// Remove staged uploads older than the configured TTL
+pub fn cleanup_expired() -> Result<PurgeReport, String> {
+    let ttl_hours = settings::current().temp_files.ttl_hours;
+    if ttl_hours == 0 {
+        return Ok(PurgeReport::default());
+    }
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(ttl_hours * 60 * 60))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let dir = paths::get_temp_dir().map_err(|e| e.to_string())?;
+    let report = purge_dir(&dir, Some(cutoff));
+    if report.removed_entries > 0 {
+        tracing::info!(
+            removed = report.removed_entries,
+            reclaimed_bytes = report.reclaimed_bytes,
+            "Removed expired temp files"
+        );
+    }
+    Ok(report)
+}
+
+/// Remove all staged uploads, or only those older than `older_than_hours`
+#[tauri::command]
+pub async fn purge_temp_files(older_than_hours: Option<u64>) -> Result<PurgeReport, String> {
+    let dir = paths::get_temp_dir().map_err(|e| e.to_string())?;
+    let cutoff = older_than_hours.map(|hours| {
+        SystemTime::now()
+            .checked_sub(Duration::from_secs(hours * 60 * 60))
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    });
+
+    let report = tokio::task::spawn_blocking(move || purge_dir(&dir, cutoff))
+        .await
+        .map_err(|e| format!("Temp file purge failed: {}", e))?;
+    tracing::info!(
+        removed = report.removed_entries,
+        reclaimed_bytes = report.reclaimed_bytes,
+        "Purged temp files"
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_dir_respects_cutoff() {
+        let dir = std::env::temp_dir().join(format!("sparrow-tmp-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("upload")).unwrap();
+        fs::write(dir.join("upload").join("a.txt"), b"hello").unwrap();
+
+        let past = SystemTime::now() - Duration::from_secs(60 * 60);
+        let report = purge_dir(&dir, Some(past));
+        assert_eq!(report.removed_entries, 0);
+        assert!(dir.join("upload").exists());
+
+        let report = purge_dir(&dir, None);
+        assert_eq!(report.removed_entries, 1);
+        assert_eq!(report.reclaimed_bytes, 5);
+        assert!(!dir.join("upload").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
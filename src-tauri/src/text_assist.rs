@@ -0,0 +1,257 @@
+//! Session-less writing helpers: rewrite text in a style, or fix its grammar.
+//!
+//! Output streams as `text-assist-token` events tagged with the caller's
+//! `request_id`, so an overlay or clipboard tool can show it as it arrives,
+//! and the full text is also returned. `cancel_operation(request_id)` (or
+//! `stop_chat_streaming`) cancels a run. Tasks and other backend code call
+//! `rewrite` and `correct_grammar` directly.
+
+use async_openai::{ Client, config::OpenAIConfig };
+use async_openai::types::chat::{
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use futures::StreamExt;
+use serde::{ Deserialize, Serialize };
+use tauri::{ AppHandle, Emitter };
+
+use crate::cancellation::{ self, OperationKind };
+use crate::coalesce::TokenCoalescer;
+use crate::server_busy;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewriteStyle {
+    #[default]
+    Formal,
+    Concise,
+    Friendly,
+}
+
+impl RewriteStyle {
+    fn instruction(self) -> &'static str {
+        match self {
+            RewriteStyle::Formal => "Use a formal, professional tone suitable for business writing.",
+            RewriteStyle::Concise => "Make it as short as possible while keeping every important point.",
+            RewriteStyle::Friendly => "Use a warm, friendly and conversational tone.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TextAssistResult {
+    pub request_id: String,
+    pub text: String,
+    pub cancelled: bool,
+}
+
+const OUTPUT_ONLY: &str =
+    "Reply with the resulting text only: no preamble, no explanations, no quotes around it. Keep the language of the original.";
+
+fn rewrite_system_prompt(style: RewriteStyle) -> String {
+    format!("You rewrite text for the user. {} {}", style.instruction(), OUTPUT_ONLY)
+}
+
+fn grammar_system_prompt() -> String {
+    format!(
+        "You correct spelling, grammar and punctuation. Change as little as possible and do not alter the meaning or tone. {}",
+        OUTPUT_ONLY
+    )
+}
+
+/// Stream one completion of `text`, under `system_prompt` when given
+pub(crate) async fn complete(
+    app: &AppHandle,
+    request_id: String,
+    system_prompt: Option<String>,
+    text: &str,
+    model: Option<String>
+) -> Result<TextAssistResult, String> {
+    if text.trim().is_empty() {
+        return Err("No text given".to_string());
+    }
+
+    crate::ensure_ovms_initialized(app).await;
+
+    let model = match model {
+        Some(model) => model,
+        None => crate::ovms::get_loaded_model(app.clone()).await?
+            .ok_or("No model is loaded; load a text model first")?,
+    };
+
+    let config = OpenAIConfig::new()
+        .with_api_key("unused")
+        .with_api_base(crate::ovms::openai_api_base());
+    let client = Client::with_config(config);
+
+    let mut messages: Vec<ChatCompletionRequestMessage> = Vec::new();
+    if let Some(system_prompt) = system_prompt {
+        messages.push(
+            ChatCompletionRequestSystemMessageArgs::default()
+                .content(system_prompt)
+                .build()
+                .map_err(|e| format!("Failed to build system message: {}", e))?
+                .into()
+        );
+    }
+    messages.push(
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(text.to_string())
+            .build()
+            .map_err(|e| format!("Failed to build user message: {}", e))?
+            .into()
+    );
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model.clone())
+        .messages(messages)
+        .stream(true)
+        .temperature(0.3)
+        // Rewrites are roughly as long as the input; leave room for expansion
+        .max_tokens((text.len() as u32 / 2).clamp(256, 4096))
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let operation = cancellation::register(app, &request_id, OperationKind::TextAssist);
+    let mut stream = server_busy::open_stream(app, &client, request, &request_id, OperationKind::TextAssist, &operation.token())
+        .await
+        .map_err(|e| format!("Failed to start text assist: {}", e))?;
+
+    let mut output = String::new();
+    let mut cancelled = false;
+    let mut failure = None;
+    let mut coalescer = TokenCoalescer::from_settings();
+    let emit_token = |token: &str| {
+        let _ = app.emit("text-assist-token", serde_json::json!({
+            "request_id": request_id,
+            "token": token,
+            "finished": false
+        }));
+    };
+
+    loop {
+        tokio::select! {
+            _ = operation.cancelled() => {
+                cancelled = true;
+                break;
+            }
+            _ = tokio::time::sleep_until(coalescer.deadline()), if coalescer.has_pending() => {
+                if let Some(chunk) = coalescer.flush() {
+                    emit_token(&chunk);
+                }
+            }
+            next = stream.next() => match next {
+                None => break,
+                Some(Ok(response)) => {
+                    for choice in response.choices {
+                        if let Some(token) = choice.delta.content {
+                            output.push_str(&token);
+                            if let Some(chunk) = coalescer.push(&token) {
+                                emit_token(&chunk);
+                            }
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    failure = Some(format!("Text assist stream failed: {}", e));
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(chunk) = coalescer.flush() {
+        emit_token(&chunk);
+    }
+    drop(operation);
+    let _ = app.emit("text-assist-token", serde_json::json!({
+        "request_id": request_id,
+        "token": "",
+        "finished": true,
+        "cancelled": cancelled
+    }));
+
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    tracing::debug!(request_id = %request_id, model = %model, chars = output.len(), cancelled, "Text assist finished");
+
+    Ok(TextAssistResult { request_id, text: output.trim().to_string(), cancelled })
+}
+
+pub async fn rewrite(
+    app: &AppHandle,
+    request_id: String,
+    text: &str,
+    style: RewriteStyle,
+    model: Option<String>
+) -> Result<TextAssistResult, String> {
+    complete(app, request_id, Some(rewrite_system_prompt(style)), text, model).await
+}
+
+pub async fn correct_grammar(
+    app: &AppHandle,
+    request_id: String,
+    text: &str,
+    model: Option<String>
+) -> Result<TextAssistResult, String> {
+    complete(app, request_id, Some(grammar_system_prompt()), text, model).await
+}
+
+pub(crate) fn request_id_or_new(request_id: Option<String>) -> String {
+    request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Rewrite text in a preset style (formal by default)
+#[tauri::command]
+pub async fn rewrite_text(
+    app: AppHandle,
+    text: String,
+    style: Option<RewriteStyle>,
+    model: Option<String>,
+    request_id: Option<String>
+) -> Result<TextAssistResult, String> {
+    let style = style.unwrap_or_default();
+    log_operation_start!("Rewrite text", style = ?style, chars = text.len());
+
+    let result = rewrite(&app, request_id_or_new(request_id), &text, style, model).await.map_err(|e| {
+        log_operation_error!("Rewrite text", &e);
+        e
+    })?;
+
+    log_operation_success!("Rewrite text", chars = result.text.len());
+    Ok(result)
+}
+
+/// Fix spelling, grammar and punctuation with minimal edits
+#[tauri::command]
+pub async fn fix_grammar(
+    app: AppHandle,
+    text: String,
+    model: Option<String>,
+    request_id: Option<String>
+) -> Result<TextAssistResult, String> {
+    log_operation_start!("Fix grammar", chars = text.len());
+
+    let result = correct_grammar(&app, request_id_or_new(request_id), &text, model).await.map_err(|e| {
+        log_operation_error!("Fix grammar", &e);
+        e
+    })?;
+
+    log_operation_success!("Fix grammar", chars = result.text.len());
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles_deserialize_from_snake_case() {
+        let style: RewriteStyle = serde_json::from_str("\"concise\"").unwrap();
+        assert_eq!(style, RewriteStyle::Concise);
+        assert!(rewrite_system_prompt(style).contains(RewriteStyle::Concise.instruction()));
+    }
+}
@@ -0,0 +1,146 @@
+//! Approval gate for tool calls the model makes during a chat.
+//!
+//! Tools run as soon as the model calls them, which is fine for reading the
+//! time but not for running code or creating tasks. A tool flagged in
+//! `chat.confirm_tools` (or confirmed by default and not unflagged there)
+//! pauses the reply: `tool-approval-requested` tells the UI what the
+//! model wants to run, and the call proceeds once `respond_to_tool_request`
+//! approves it. A declined, timed-out or cancelled request is reported back to
+//! the model as a failed call, so it can answer without the tool.
+//!
+//! By default that covers the built-ins in `CONFIRM_BY_DEFAULT` and MCP tools
+//! whose name says they write files or run commands, e.g.
+//! `filesystem_write_file` or `shell_execute_command`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{ AppHandle, Emitter, Manager };
+use tokio::sync::oneshot;
+
+use crate::state::AppState;
+use crate::{ cancellation, settings };
+
+/// Built-in tools that act on the system rather than just reading it
+const CONFIRM_BY_DEFAULT: &[&str] = &["builtin_run_sandboxed_code", "builtin_create_task"];
+
+/// Words of an MCP tool name (`<server>_<tool>`) for tools that change files
+/// or run commands
+const CONFIRM_MCP_WORDS: &[&str] = &[
+    "write", "edit", "create", "move", "rename", "delete", "remove", "exec", "execute", "run", "command", "shell",
+    "bash", "terminal",
+];
+
+/// Payload of `tool-approval-requested`
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolApprovalRequest {
+    pub request_id: String,
+    pub session_id: Option<String>,
+    pub tool_name: String,
+    pub arguments: String,
+    pub expires_in_secs: u64,
+}
+
+fn requires_confirmation(tool_name: &str, overrides: &HashMap<String, bool>) -> bool {
+    overrides
+        .get(tool_name)
+        .copied()
+        .unwrap_or_else(|| CONFIRM_BY_DEFAULT.contains(&tool_name) || is_acting_mcp_tool(tool_name))
+}
+
+fn is_acting_mcp_tool(tool_name: &str) -> bool {
+    !tool_name.starts_with("builtin_") &&
+        tool_name
+            .to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| CONFIRM_MCP_WORDS.contains(&word))
+}
+
+fn resolved(app: &AppHandle, request_id: &str, approved: bool, reason: &str) {
+    let _ = app.emit("tool-approval-resolved", serde_json::json!({
+        "request_id": request_id,
+        "approved": approved,
+        "reason": reason,
+    }));
+}
+
+/// Wait for the user to approve `tool_name` if it needs approval; errors when it was not approved
+pub async fn approve(app: &AppHandle, session_id: Option<&str>, tool_name: &str, arguments: &str) -> Result<(), String> {
+    let config = settings::current().chat;
+    if !requires_confirmation(tool_name, &config.confirm_tools) {
+        return Ok(());
+    }
+
+    let request = ToolApprovalRequest {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.map(str::to_string),
+        tool_name: tool_name.to_string(),
+        arguments: arguments.to_string(),
+        expires_in_secs: config.tool_approval_timeout_secs.max(1),
+    };
+    let (reply_tx, reply_rx) = oneshot::channel();
+    app.state::<AppState>().tool_requests.lock().insert(request.request_id.clone(), reply_tx);
+    tracing::info!(tool = %tool_name, request_id = %request.request_id, "Waiting for tool approval");
+    let _ = app.emit("tool-approval-requested", &request);
+
+    // Stopping the reply also withdraws the request
+    let stream_cancel = cancellation::token(app, crate::chat::stream_key(session_id));
+    let outcome = tokio::select! {
+        reply = reply_rx => match reply {
+            Ok(true) => Ok(()),
+            _ => Err("declined"),
+        },
+        _ = tokio::time::sleep(Duration::from_secs(request.expires_in_secs)) => Err("timed out"),
+        _ = async {
+            match &stream_cancel {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        } => Err("cancelled"),
+    };
+
+    app.state::<AppState>().tool_requests.lock().remove(&request.request_id);
+    match outcome {
+        Ok(()) => {
+            resolved(app, &request.request_id, true, "approved");
+            Ok(())
+        }
+        Err(reason) => {
+            tracing::info!(tool = %tool_name, request_id = %request.request_id, reason, "Tool call not approved");
+            resolved(app, &request.request_id, false, reason);
+            Err(format!("The user did not approve running {} ({})", tool_name, reason))
+        }
+    }
+}
+
+/// Answer a `tool-approval-requested` event
+#[tauri::command]
+pub async fn respond_to_tool_request(app: AppHandle, request_id: String, approve: bool) -> Result<(), String> {
+    let reply = app.state::<AppState>().tool_requests.lock().remove(&request_id)
+        .ok_or_else(|| format!("No pending tool request {}", request_id))?;
+    reply.send(approve).map_err(|_| format!("Tool request {} is no longer waiting", request_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires_confirmation_defaults_and_overrides() {
+        let mut overrides = HashMap::new();
+        assert!(requires_confirmation("builtin_run_sandboxed_code", &overrides));
+        assert!(!requires_confirmation("builtin_get_current_time", &overrides));
+        assert!(requires_confirmation("filesystem_write_file", &overrides));
+        assert!(requires_confirmation("desktop-commander_execute_command", &overrides));
+        assert!(!requires_confirmation("filesystem_read_file", &overrides));
+        assert!(!requires_confirmation("builtin_search_documents", &overrides));
+
+        overrides.insert("builtin_run_sandboxed_code".to_string(), false);
+        overrides.insert("filesystem_write_file".to_string(), false);
+        overrides.insert("filesystem_read_file".to_string(), true);
+        assert!(!requires_confirmation("builtin_run_sandboxed_code", &overrides));
+        assert!(!requires_confirmation("filesystem_write_file", &overrides));
+        assert!(requires_confirmation("filesystem_read_file", &overrides));
+    }
+}
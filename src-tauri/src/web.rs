@@ -0,0 +1,278 @@
+//! Web search and page fetching for tools and research mode.
+//!
+//! Search goes to the DuckDuckGo HTML endpoint, which needs no API key, and
+//! its result list is read out of the page. Fetched pages are reduced to
+//! their text (scripts, styles and markup dropped, entities decoded) so they
+//! can be indexed like any other document.
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::Serialize;
+
+const SEARCH_URL: &str = "https://html.duckduckgo.com/html/";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+/// Pages are cut off after this much HTML
+const MAX_PAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Elements whose content is not page text
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "svg", "template", "title"];
+/// Formatting tags that sit inside a word or sentence
+const INLINE_TAGS: &[&str] = &["a", "b", "i", "u", "em", "strong", "span", "code", "small", "sub", "sup", "mark", "abbr"];
+/// Tags that end a line of text
+const BLOCK_TAGS: &[&str] = &[
+    "p", "br", "div", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "section", "article", "blockquote", "pre",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebPage {
+    pub url: String,
+    pub title: String,
+    pub text: String,
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').filter(|end| *end <= 10).map(|end| &rest[1..end]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Name of the tag starting at `tag` (after the `<`), lower-cased, without a leading `/`
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Page title and text of an HTML document, one block per line
+pub(crate) fn html_to_text(html: &str) -> (Option<String>, String) {
+    let lower = html.to_ascii_lowercase();
+    let title = lower.find("<title").and_then(|start| {
+        let open_end = start + lower[start..].find('>')? + 1;
+        let close = open_end + lower[open_end..].find("</title")?;
+        Some(decode_entities(html[open_end..close].trim()))
+    }).filter(|title| !title.is_empty());
+
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut position = 0;
+    while let Some(offset) = lower[position..].find('<') {
+        let start = position + offset;
+        text.push_str(&html[position..start]);
+
+        if lower[start..].starts_with("<!--") {
+            position = lower[start..].find("-->").map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+        let Some(end) = lower[start..].find('>').map(|end| start + end + 1) else {
+            position = html.len();
+            break;
+        };
+        let name = tag_name(&lower[start + 1..end]);
+        position = end;
+
+        if SKIPPED_ELEMENTS.contains(&name.as_str()) && !lower[start + 1..].starts_with('/') {
+            let close = format!("</{}", name);
+            position = lower[end..].find(&close)
+                .and_then(|close_at| lower[end + close_at..].find('>').map(|gt| end + close_at + gt + 1))
+                .unwrap_or(html.len());
+        } else if BLOCK_TAGS.contains(&name.as_str()) {
+            text.push('\n');
+        } else if !INLINE_TAGS.contains(&name.as_str()) {
+            text.push(' ');
+        }
+    }
+    if position < html.len() {
+        text.push_str(&html[position..]);
+    }
+
+    let text = decode_entities(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (title, text)
+}
+
+/// Target of a DuckDuckGo result link, which goes through a redirect
+fn result_url(href: &str) -> Option<String> {
+    let href = decode_entities(href);
+    let absolute = if href.starts_with("//") { format!("https:{}", href) } else { href };
+    let url = reqwest::Url::parse(&absolute).ok()?;
+    let target = if url.host_str().is_some_and(|host| host.ends_with("duckduckgo.com")) {
+        // Ads go through y.js and have no uddg target
+        url.query_pairs().find(|(key, _)| key == "uddg").map(|(_, value)| value.into_owned())?
+    } else {
+        absolute
+    };
+    target.starts_with("http").then_some(target)
+}
+
+fn parse_search_results(html: &str, limit: usize) -> Vec<WebResult> {
+    let mut results: Vec<WebResult> = Vec::new();
+    for block in html.split("class=\"result__a\"").skip(1) {
+        let href = block.split_once("href=\"").and_then(|(_, rest)| rest.split_once('"')).map(|(href, _)| href);
+        let Some(url) = href.and_then(result_url) else {
+            continue;
+        };
+        let inner = |html: &str| {
+            html.split_once('>')
+                .and_then(|(_, rest)| rest.split_once("</a>"))
+                .map(|(inner, _)| html_to_text(inner).1.replace('\n', " "))
+                .unwrap_or_default()
+        };
+        let title = inner(block);
+        let snippet = block
+            .split_once("class=\"result__snippet\"")
+            .map(|(_, rest)| inner(rest))
+            .unwrap_or_default();
+
+        if !results.iter().any(|result| result.url == url) {
+            results.push(WebResult { title, url, snippet });
+        }
+        if results.len() >= limit {
+            break;
+        }
+    }
+    results
+}
+
+pub async fn search(query: &str, limit: usize) -> Result<Vec<WebResult>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let response = crate::http::client_with_timeout(FETCH_TIMEOUT)?
+        .post(SEARCH_URL)
+        .form(&[("q", query)])
+        .send()
+        .await
+        .map_err(|e| format!("Web search failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Web search failed with status {}", response.status()));
+    }
+    let html = response.text().await.map_err(|e| format!("Failed to read search results: {}", e))?;
+
+    let results = parse_search_results(&html, limit.max(1));
+    tracing::debug!(query = %query, results = results.len(), "Web search");
+    Ok(results)
+}
+
+/// Download `url` and reduce it to text; plain-text responses are kept as they are
+pub async fn fetch_page(url: &str) -> Result<WebPage, String> {
+    let response = crate::http::client_with_timeout(FETCH_TIMEOUT)?
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Fetching {} failed with status {}", url, response.status()));
+    }
+    let content_type = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/html")
+        .to_ascii_lowercase();
+    if !content_type.contains("html") && !content_type.starts_with("text/") {
+        return Err(format!("{} is not a web page ({})", url, content_type));
+    }
+
+    // Stop reading at the cap instead of downloading the whole body first
+    let mut bytes = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to read {}: {}", url, e))?;
+        bytes.extend_from_slice(&chunk[..chunk.len().min(MAX_PAGE_BYTES - bytes.len())]);
+        if bytes.len() >= MAX_PAGE_BYTES {
+            break;
+        }
+    }
+    let body = String::from_utf8_lossy(&bytes);
+    let (title, text) = if content_type.contains("html") {
+        html_to_text(&body)
+    } else {
+        (None, body.into_owned())
+    };
+    if text.trim().is_empty() {
+        return Err(format!("{} has no readable text", url));
+    }
+
+    Ok(WebPage {
+        url: url.to_string(),
+        title: title.unwrap_or_else(|| url.to_string()),
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text_drops_scripts_and_markup() {
+        let html = "<html><head><title>Tea &amp; Coffee</title><style>p { color: red }</style></head>\
+            <body><p>Green <b>tea</b> has&nbsp;caffeine.</p><script>alert('x')</script>\
+            <!-- hidden --><ul><li>Black</li><li>Oolong &#8211; partly oxidised</li></ul></body></html>";
+        let (title, text) = html_to_text(html);
+        assert_eq!(title.as_deref(), Some("Tea & Coffee"));
+        assert_eq!(text, "Green tea has caffeine.\nBlack\nOolong \u{2013} partly oxidised");
+    }
+
+    #[test]
+    fn test_parse_search_results_follows_redirects_and_skips_ads() {
+        let html = r#"
+            <a rel="nofollow" class="result__a" href="//duckduckgo.com/y.js?ad_domain=ads.example">Ad</a>
+            <a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fexample.com%2Ftea&amp;rut=abc">All about <b>tea</b></a>
+            <a class="result__snippet" href="x">Tea is a <b>drink</b>.</a>
+            <a rel="nofollow" class="result__a" href="https://example.org/">Example</a>
+        "#;
+        assert_eq!(parse_search_results(html, 5), vec![
+            WebResult {
+                title: "All about tea".to_string(),
+                url: "https://example.com/tea".to_string(),
+                snippet: "Tea is a drink.".to_string(),
+            },
+            WebResult { title: "Example".to_string(), url: "https://example.org/".to_string(), snippet: String::new() },
+        ]);
+        assert_eq!(parse_search_results(html, 1).len(), 1);
+    }
+}
@@ -32,6 +32,7 @@ function App() {
     setActiveChatSessionId,
     clearCurrentChatMessages,
     clearTemporarySession,
+    setPendingPrompt,
   } = useChat();
 
   const [initStatus, setInitStatus] = useState<any>(null);
@@ -92,6 +93,40 @@ function App() {
     };
   }, [showNotification]);
 
+  // Text selected in another app and sent to Sparrow (see selection.rs)
+  useEffect(() => {
+    const takeSelection = async () => {
+      try {
+        const selection = await invoke<{
+          action_name: string;
+          prompt: string;
+          session_id: string | null;
+          model: string | null;
+        } | null>("take_pending_selection");
+        if (!selection) return;
+
+        logInfo("Received text selection", { action: selection.action_name });
+        clearTemporarySession();
+        if (selection.session_id) {
+          setActiveChatSessionId(selection.session_id);
+        } else {
+          clearCurrentChatMessages();
+          setActiveChatSessionId(null);
+        }
+        setCurrentPage("chat");
+        setPendingPrompt({ prompt: selection.prompt, model: selection.model });
+      } catch (error) {
+        logError("Failed to take text selection", error as Error);
+      }
+    };
+
+    takeSelection();
+    const unlisten = listen("selection-received", takeSelection);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Monitor OVMS initialization status
   useEffect(() => {
     // Skip if already initialized
@@ -106,8 +141,9 @@ function App() {
         const status: any = await invoke("get_initialization_status");
         setInitStatus(status);
 
+        // "deferred" means OVMS starts on first use, so don't block the UI
         if (!status.is_complete && !status.has_error) {
-          setShowInitDialog(true);
+          setShowInitDialog(status.step !== "deferred");
         }
 
         if (status.is_complete) {
@@ -16,7 +16,11 @@ import {
   X,
 } from "lucide-react";
 import ReactMarkdown from "react-markdown";
-import type { ChatMessage as ChatMessageType } from "@/store/types";
+import type {
+  ChatMessage as ChatMessageType,
+  GenerationMetrics,
+} from "@/store/types";
+import type { ChatMetrics, Citation, RagCitations } from "@/types/app";
 import { categorizeModel } from "@/lib/modelUtils";
 import {
   logUserAction,
@@ -32,6 +36,26 @@ interface ToolCall {
   result: string;
 }
 
+/** Stream id of a chat without a saved session */
+const TEMP_STREAM_ID = "temp";
+
+/** A reply being streamed; kept per stream so several sessions can stream at once */
+interface StreamState {
+  message: string;
+  toolCalls: ToolCall[];
+  startTime: number | null;
+  generation: GenerationMetrics | null;
+  citations: Citation[] | null;
+}
+
+const newStream = (): StreamState => ({
+  message: "",
+  toolCalls: [],
+  startTime: null,
+  generation: null,
+  citations: null,
+});
+
 interface AttachmentInfo {
   file_path: string;
   file_name: string;
@@ -47,12 +71,17 @@ export const ChatPage = () => {
     setCurrentChatMessages,
     temporarySession,
     setActiveChatSessionId,
+    pendingPrompt,
+    setPendingPrompt,
   } = useAppStore();
   const { settings } = useAppStore();
   const { downloadedModels, loadedModelsByType, setLoadedModelByType } =
     useAppStore();
   const [input, setInput] = useState("");
   const [isStreaming, setIsStreaming] = useState(false);
+  const autoSendRef = useRef(false);
+  // Model pinned by the quick action whose prompt is sent next
+  const pendingModelRef = useRef<string | null>(null);
   const [currentStreamingMessage, setCurrentStreamingMessage] = useState("");
   const [, setToolCalls] = useState<ToolCall[]>([]);
   const [, setUsageData] = useState<{
@@ -90,6 +119,11 @@ export const ChatPage = () => {
 
   const messagesEndRef = useRef<HTMLDivElement>(null);
 
+  // Replies being streamed, by stream id: the session id, or TEMP_STREAM_ID
+  const streamsRef = useRef(new Map<string, StreamState>());
+  const viewedStreamId = activeChatSessionId || TEMP_STREAM_ID;
+  const viewedStreamRef = useRef(viewedStreamId);
+
   // Organize downloaded models by category using state instead of ref
   const [modelsByCategory, setModelsByCategory] = useState<{
     text: string[];
@@ -164,6 +198,10 @@ export const ChatPage = () => {
               "docx",
               "xlsx",
               "xls",
+              "txt",
+              "md",
+              "log",
+              "csv",
               "png",
               "jpg",
               "jpeg",
@@ -203,15 +241,8 @@ export const ChatPage = () => {
               },
             ]);
           } else {
-            // Process document into chunks for RAG
-            const documents = await invoke<any[]>("process_document", {
-              filePath,
-            });
-            const embeddedDocs = await invoke<any[]>(
-              "create_document_embeddings",
-              { documents }
-            );
-            await invoke("store_documents", { documents: embeddedDocs });
+            // Chunk, embed and store the document for RAG
+            await invoke("ingest_document", { filePath });
             logInfo(`Successfully processed and stored ${filePath}`);
           }
         } catch (error) {
@@ -311,25 +342,59 @@ export const ChatPage = () => {
     currentChatMessages.length,
   ]);
 
-  // Listen for streaming tokens
+  // Show the stream of the session being viewed, if it has one
   useEffect(() => {
-    let accumulatedMessage = "";
-    let accumulatedToolCalls: ToolCall[] = [];
-    let streamStartTime: number | null = null;
+    viewedStreamRef.current = viewedStreamId;
+    const stream = streamsRef.current.get(viewedStreamId);
+    setIsStreaming(!!stream);
+    setCurrentStreamingMessage(stream?.message ?? "");
+    setToolCalls(stream?.toolCalls ?? []);
+  }, [viewedStreamId]);
+
+  // Listen for streaming tokens; every payload names its stream, so replies
+  // streaming in other sessions are collected and saved there
+  useEffect(() => {
+    const streams = streamsRef.current;
+    const streamFor = (streamId: string) => {
+      let stream = streams.get(streamId);
+      if (!stream) {
+        stream = newStream();
+        streams.set(streamId, stream);
+      }
+      return stream;
+    };
+
+    // `chat-metrics` arrives just before the finished token
+    const unlistenMetrics = listen<ChatMetrics>("chat-metrics", (event) => {
+      streamFor(event.payload.stream_id).generation = event.payload.generation;
+    });
+
+    // `rag-citations` arrives before the first token of a RAG answer
+    const unlistenCitations = listen<RagCitations>("rag-citations", (event) => {
+      streamFor(event.payload.stream_id).citations = event.payload.citations;
+    });
 
     const unlisten = listen<{
+      stream_id: string;
       token: string;
       finished: boolean;
       cancelled?: boolean;
+      reset?: boolean;
       usage?: {
         prompt_tokens: number;
         completion_tokens: number;
         total_tokens: number;
       };
     }>("chat-token", async (event) => {
+      const streamId = event.payload.stream_id;
+      const stream = streamFor(streamId);
+      const isViewed = () => viewedStreamRef.current === streamId;
+
       if (event.payload.finished) {
+        streams.delete(streamId);
         logDebug("Chat streaming finished", {
-          messageLength: accumulatedMessage.length,
+          streamId,
+          messageLength: stream.message.length,
           cancelled: event.payload.cancelled,
         });
 
@@ -342,102 +407,119 @@ export const ChatPage = () => {
             }
           : null;
 
-        console.log("Usage data from finished event:", usageFromPayload);
-
         // Calculate tokens per second
         let tokensPerSecond: number | null = null;
-        if (usageFromPayload && streamStartTime) {
-          const elapsedSeconds = (Date.now() - streamStartTime) / 1000;
+        if (usageFromPayload && stream.startTime) {
+          const elapsedSeconds = (Date.now() - stream.startTime) / 1000;
           tokensPerSecond = usageFromPayload.completionTokens / elapsedSeconds;
           logInfo("Chat stream completed", {
+            streamId,
             tokensPerSecond: tokensPerSecond.toFixed(2),
             completionTokens: usageFromPayload.completionTokens,
             elapsedSeconds: elapsedSeconds.toFixed(2),
           });
         }
 
-        // Save the complete assistant message to chat session
-        if (accumulatedMessage.trim()) {
+        // Save the complete assistant message to the session it was streamed for
+        if (stream.message.trim()) {
           try {
-            // Save to backend
-            if (activeChatSessionId) {
+            if (streamId !== TEMP_STREAM_ID) {
               logDebug("Saving assistant message", {
-                sessionId: activeChatSessionId,
+                sessionId: streamId,
                 hasUsageData: !!usageFromPayload,
                 cancelled: event.payload.cancelled,
               });
               await invoke("add_message_to_session", {
-                sessionId: activeChatSessionId,
+                sessionId: streamId,
                 role: "assistant",
-                content: accumulatedMessage,
+                content: stream.message,
                 tokensPerSecond: tokensPerSecond,
                 isError: false,
                 promptTokens: usageFromPayload?.promptTokens ?? null,
                 completionTokens: usageFromPayload?.completionTokens ?? null,
                 totalTokens: usageFromPayload?.totalTokens ?? null,
+                generation: stream.generation,
+                citations: stream.citations,
               });
               logInfo("Assistant message saved", {
-                sessionId: activeChatSessionId,
-                contentLength: accumulatedMessage.length,
+                sessionId: streamId,
+                contentLength: stream.message.length,
                 cancelled: event.payload.cancelled,
               });
 
-              // Add to UI with usage data
-              const assistantMessage: ChatMessageType = {
-                id: crypto.randomUUID(),
-                role: "assistant",
-                content: accumulatedMessage,
-                timestamp: Date.now(),
-                tokens_per_second: tokensPerSecond,
-                completion_tokens: usageFromPayload?.completionTokens,
-                total_tokens: usageFromPayload?.totalTokens,
-                prompt_tokens: usageFromPayload?.promptTokens,
-                toolCalls:
-                  accumulatedToolCalls.length > 0
-                    ? accumulatedToolCalls
-                    : undefined,
-              };
-              addMessageToCurrentChat(assistantMessage);
+              // Add to UI with usage data when the user is still looking at it
+              if (isViewed()) {
+                const assistantMessage: ChatMessageType = {
+                  id: crypto.randomUUID(),
+                  role: "assistant",
+                  content: stream.message,
+                  timestamp: Date.now(),
+                  tokens_per_second: tokensPerSecond,
+                  completion_tokens: usageFromPayload?.completionTokens,
+                  total_tokens: usageFromPayload?.totalTokens,
+                  prompt_tokens: usageFromPayload?.promptTokens,
+                  generation: stream.generation,
+                  citations: stream.citations,
+                  toolCalls:
+                    stream.toolCalls.length > 0 ? stream.toolCalls : undefined,
+                };
+                addMessageToCurrentChat(assistantMessage);
+              }
             } else {
               logWarn("No active session ID to save message");
             }
           } catch (error) {
             logError("Failed to save assistant message", error as Error, {
-              sessionId: activeChatSessionId,
+              sessionId: streamId,
             });
           }
         }
 
         // Clear streaming state
-        setCurrentStreamingMessage("");
-        setToolCalls([]);
-        setIsStreaming(false);
-        setUsageData(null); // Clear usage data for next message
-        accumulatedMessage = "";
-        accumulatedToolCalls = [];
-        streamStartTime = null;
+        if (isViewed()) {
+          setCurrentStreamingMessage("");
+          setToolCalls([]);
+          setIsStreaming(false);
+          setUsageData(null); // Clear usage data for next message
+        }
       } else {
         // Set start time on first token
-        if (!streamStartTime) {
-          streamStartTime = Date.now();
+        if (!stream.startTime) {
+          stream.startTime = Date.now();
         }
 
-        // Accumulate the token
-        accumulatedMessage += event.payload.token;
-        setCurrentStreamingMessage((prev) => prev + event.payload.token);
+        // A reset replaces the streamed text, e.g. with a reply re-asked in the preferred language
+        if (event.payload.reset) {
+          stream.message = event.payload.token;
+        } else {
+          stream.message += event.payload.token;
+        }
+        if (isViewed()) {
+          setCurrentStreamingMessage(stream.message);
+        }
       }
     });
 
     return () => {
       unlisten.then((fn) => fn());
+      unlistenMetrics.then((fn) => fn());
+      unlistenCitations.then((fn) => fn());
     };
-  }, [activeChatSessionId, addMessageToCurrentChat]);
+  }, [addMessageToCurrentChat]);
 
   // Listen for tool calls
   useEffect(() => {
-    const unlisten = listen<ToolCall>("tool-call", (event) => {
-      setToolCalls((prev) => [...prev, event.payload]);
-    });
+    const unlisten = listen<ToolCall & { stream_id: string }>(
+      "tool-call",
+      (event) => {
+        const { stream_id: streamId, ...toolCall } = event.payload;
+        const stream = streamsRef.current.get(streamId);
+        stream?.toolCalls.push(toolCall);
+        if (viewedStreamRef.current === streamId) {
+          setToolCalls((prev) => [...prev, toolCall]);
+        }
+      }
+    );
 
     return () => {
       unlisten.then((fn) => fn());
@@ -447,10 +529,12 @@ export const ChatPage = () => {
   // Listen for usage statistics
   useEffect(() => {
     const unlisten = listen<{
+      stream_id: string;
       prompt_tokens: number;
       completion_tokens: number;
       total_tokens: number;
     }>("chat-usage", (event) => {
+      if (viewedStreamRef.current !== event.payload.stream_id) return;
       logDebug("Received usage statistics", event.payload);
       setUsageData({
         promptTokens: event.payload.prompt_tokens,
@@ -464,21 +548,27 @@ export const ChatPage = () => {
     };
   }, []);
 
-  // Listen for chat errors
+  // Listen for chat errors; the finished token that follows ends the stream
   useEffect(() => {
-    const unlisten = listen<{ error: string }>("chat-error", (event) => {
-      setIsStreaming(false);
-      setCurrentStreamingMessage("");
-      logError("Chat error occurred", new Error(event.payload.error));
+    const unlisten = listen<{ stream_id: string; error: string }>(
+      "chat-error",
+      (event) => {
+        logError("Chat error occurred", new Error(event.payload.error), {
+          streamId: event.payload.stream_id,
+        });
+        if (viewedStreamRef.current !== event.payload.stream_id) return;
+        setIsStreaming(false);
+        setCurrentStreamingMessage("");
 
-      const errorMessage: ChatMessageType = {
-        id: crypto.randomUUID(),
-        role: "assistant",
-        content: `Error: ${event.payload.error}`,
-        timestamp: Date.now(),
-      };
-      addMessageToCurrentChat(errorMessage);
-    });
+        const errorMessage: ChatMessageType = {
+          id: crypto.randomUUID(),
+          role: "assistant",
+          content: `Error: ${event.payload.error}`,
+          timestamp: Date.now(),
+        };
+        addMessageToCurrentChat(errorMessage);
+      }
+    );
 
     return () => {
       unlisten.then((fn) => fn());
@@ -488,6 +578,9 @@ export const ChatPage = () => {
   const handleSend = async () => {
     if (!input.trim() || isStreaming) return;
 
+    const pinnedModel = pendingModelRef.current;
+    pendingModelRef.current = null;
+
     // Check if images are attached
     const hasImageAttachments = attachments.some((a) => a.is_image);
 
@@ -497,8 +590,8 @@ export const ChatPage = () => {
       return;
     }
 
-    // Check if text model is loaded (required for chat)
-    if (!loadedModelsByType.text) {
+    // Check if text model is loaded (required for chat unless a quick action pins one)
+    if (!loadedModelsByType.text && !pinnedModel) {
       alert("Please select a Text Generation model first");
       return;
     }
@@ -521,8 +614,15 @@ export const ChatPage = () => {
     setAttachments([]); // Clear attachments after sending
     setIsStreaming(true);
     setToolCalls([]);
+    streamsRef.current.set(viewedStreamId, newStream());
 
     let sessionToUse = activeChatSessionId; // Declare outside try block
+    // The stream moves along when the message goes to a newly created session
+    const moveStream = (sessionId: string) => {
+      const stream = streamsRef.current.get(viewedStreamId) ?? newStream();
+      streamsRef.current.delete(viewedStreamId);
+      streamsRef.current.set(sessionId, stream);
+    };
 
     try {
       console.log("handleSend - activeChatSessionId:", activeChatSessionId);
@@ -536,6 +636,7 @@ export const ChatPage = () => {
         });
         logInfo("New session created", { sessionId: newSession.id });
         sessionToUse = newSession.id;
+        moveStream(newSession.id);
         setActiveChatSessionId(newSession.id);
 
         // Add user message to the new session
@@ -591,6 +692,7 @@ export const ChatPage = () => {
           });
           logInfo("New session created", { sessionId: newSession.id });
           sessionToUse = newSession.id;
+          moveStream(newSession.id);
           setActiveChatSessionId(newSession.id);
 
           // Add user message to the new session
@@ -612,7 +714,7 @@ export const ChatPage = () => {
 
       logDebug("Starting chat with session", {
         sessionId: sessionToUse,
-        modelId: loadedModelsByType.text,
+        modelId: pinnedModel ?? loadedModelsByType.text,
       });
       logUserAction("Send chat message", {
         sessionId: sessionToUse,
@@ -631,7 +733,7 @@ export const ChatPage = () => {
       const hasImageAttachments = currentAttachments.some((a) => a.is_image);
       const modelToUse = hasImageAttachments
         ? loadedModelsByType["image-to-text"]!
-        : loadedModelsByType.text!;
+        : pinnedModel ?? loadedModelsByType.text!;
 
       // Strip 'OpenVINO/' prefix from model name if present
       const modelNameForChat = modelToUse.startsWith("OpenVINO/")
@@ -661,7 +763,7 @@ export const ChatPage = () => {
         maxCompletionTokens: settings.maxCompletionTokens,
         // RAG-specific parameters (only used if chatCommand is chat_with_rag_streaming)
         useRag: settings.useRAG || currentAttachments.some((a) => !a.is_image), // Use RAG if enabled OR if there are document attachments
-        ragLimit: currentAttachments.some((a) => !a.is_image) ? null : 5, // null means use only attached documents
+        ragLimit: null, // Retrieval limits come from the rag.retrieval settings
         // Pass full attachment objects (includes both images and documents)
         attachments: currentAttachments.length > 0 ? currentAttachments : null,
       });
@@ -669,6 +771,7 @@ export const ChatPage = () => {
       logError("Failed to send message", error as Error, {
         sessionId: sessionToUse,
       });
+      streamsRef.current.delete(sessionToUse || TEMP_STREAM_ID);
       setIsStreaming(false);
     }
   };
@@ -680,6 +783,22 @@ export const ChatPage = () => {
     }
   };
 
+  // Send prompts queued from outside the page, e.g. text selected in another app
+  useEffect(() => {
+    if (!pendingPrompt || isStreaming) return;
+    setPendingPrompt(null);
+    autoSendRef.current = true;
+    pendingModelRef.current = pendingPrompt.model;
+    setInput(pendingPrompt.prompt);
+  }, [pendingPrompt, isStreaming]);
+
+  useEffect(() => {
+    if (autoSendRef.current && input.trim()) {
+      autoSendRef.current = false;
+      handleSend();
+    }
+  }, [input]);
+
   const handleStop = async () => {
     if (!isStreaming) return;
 
@@ -687,7 +806,7 @@ export const ChatPage = () => {
       logUserAction("Stopping chat streaming");
 
       // Get the active session ID
-      const sessionToStop = activeChatSessionId || "temp";
+      const sessionToStop = viewedStreamId;
 
       await invoke("stop_chat_streaming", {
         sessionId: sessionToStop,
@@ -102,7 +102,7 @@ export const DocumentsPage = () => {
         filters: [
           {
             name: "Documents",
-            extensions: ["pdf", "docx", "xlsx", "xls"],
+            extensions: ["pdf", "docx", "xlsx", "xls", "txt", "md", "log", "csv"],
           },
         ],
       });
@@ -116,23 +116,8 @@ export const DocumentsPage = () => {
 
       for (const filePath of filePaths) {
         try {
-          // Process document into chunks
-          const documents = await invoke<Document[]>("process_document", {
-            filePath,
-          });
-
-          // Create embeddings for all chunks at once
-          const embeddedDocs = await invoke<Document[]>(
-            "create_document_embeddings",
-            {
-              documents,
-            }
-          );
-
-          // Store documents in vector store
-          await invoke("store_documents", {
-            documents: embeddedDocs,
-          });
+          // Chunk, embed and store in one pass so large files stay out of memory
+          await invoke("ingest_document", { filePath });
 
           logInfo(`Successfully processed and stored ${filePath}`);
         } catch (error) {
@@ -184,17 +169,21 @@ export const DocumentsPage = () => {
   };
 
   const handleClearVectorStore = async () => {
-    if (
-      !confirm(
-        "Are you sure you want to clear the entire vector store? This will delete ALL documents and cannot be undone."
-      )
-    ) {
+    // The backend asks for confirmation in a native dialog and refuses to
+    // clear the store without the token it returns
+    let confirmation: { token: string };
+    try {
+      confirmation = await invoke<{ token: string }>("request_command_confirmation", {
+        command: "clear_vector_store",
+        detail: "This will delete ALL documents and cannot be undone.",
+      });
+    } catch {
       return;
     }
 
     try {
       setIsLoading(true);
-      await invoke("clear_vector_store");
+      await invoke("clear_vector_store", { confirmationToken: confirmation.token });
       await loadFiles();
 
       // Clear all UI state
@@ -165,27 +165,16 @@ export const GalleryPage = () => {
       }
 
       // Generate image
-      const result = await invoke<{
-        image_path: string;
-        id: string;
-      }>("generate_image", {
-        modelId: selectedModel,
+      const newImage = await invoke<GeneratedImage>("generate_image", {
         prompt,
-        size: imageSize,
-        numInferenceSteps,
-        referenceImages: attachments.map((a) => a.file_path),
+        params: {
+          modelId: selectedModel,
+          size: imageSize,
+          numInferenceSteps,
+          referenceImages: attachments.map((a) => a.file_path),
+        },
       });
 
-      const newImage: GeneratedImage = {
-        id: result.id,
-        prompt,
-        imagePath: result.image_path,
-        timestamp: Date.now(),
-        modelId: selectedModel,
-        size: imageSize,
-        numInferenceSteps,
-      };
-
       addGeneratedImage(newImage);
       setCurrentGeneratingImage(newImage);
       showNotification("Image generated successfully!", "success", 3000);
@@ -224,9 +213,9 @@ export const GalleryPage = () => {
     }
   };
 
-  const handleDeleteImage = async (imageId: string, imagePath: string) => {
+  const handleDeleteImage = async (imageId: string) => {
     try {
-      await invoke("delete_generated_image", { imageId, imagePath });
+      await invoke("delete_generated_image", { imageId });
       setGeneratedImages(generatedImages.filter((img) => img.id !== imageId));
       if (currentGeneratingImage?.id === imageId) {
         setCurrentGeneratingImage(null);
@@ -297,10 +286,7 @@ export const GalleryPage = () => {
                         </Button>
                         <Button
                           onClick={() =>
-                            handleDeleteImage(
-                              currentGeneratingImage.id,
-                              currentGeneratingImage.imagePath
-                            )
+                            handleDeleteImage(currentGeneratingImage.id)
                           }
                           variant="secondary"
                           size="sm"
@@ -356,9 +342,7 @@ export const GalleryPage = () => {
                             <ExternalLink className="h-4 w-4" />
                           </Button>
                           <Button
-                            onClick={() =>
-                              handleDeleteImage(image.id, image.imagePath)
-                            }
+                            onClick={() => handleDeleteImage(image.id)}
                             variant="secondary"
                             size="sm"
                           >
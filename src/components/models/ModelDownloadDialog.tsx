@@ -2,7 +2,7 @@ import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Dialog, Button, Input } from "../ui";
 import { Download, Settings, X } from "lucide-react";
-import { GraphGenerationParams, ModelTaskType } from "@/types/models";
+import { GraphGenerationParams, HardwareDevice, ModelTaskType } from "@/types/models";
 
 // Helper function to determine task type from model name
 const inferTaskTypeFromModelId = (modelId: string): ModelTaskType => {
@@ -133,6 +133,15 @@ export const ModelDownloadDialog = ({
     setGraphParams(baseParams);
   };
 
+  // Offer the devices OpenVINO can actually use on this machine
+  const [devices, setDevices] = useState<HardwareDevice[]>([]);
+  useEffect(() => {
+    if (!isOpen) return;
+    invoke<HardwareDevice[]>("get_available_devices")
+      .then(setDevices)
+      .catch((err) => console.error("Failed to list devices:", err));
+  }, [isOpen]);
+
   // Auto-detect and set task type when dialog opens with a new modelId
   useEffect(() => {
     if (isOpen && modelId) {
@@ -257,10 +266,20 @@ export const ModelDownloadDialog = ({
                   onChange={(e) => updateParam("target_device", e.target.value)}
                   className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded-lg bg-white dark:bg-gray-800 text-gray-900 dark:text-white text-sm"
                 >
-                  <option value="GPU">GPU</option>
-                  <option value="CPU">CPU</option>
-                  <option value="NPU">NPU</option>
-                  <option value="AUTO">AUTO</option>
+                  {devices.length > 0 ? (
+                    devices.map((device) => (
+                      <option key={device.id} value={device.id}>
+                        {device.id === device.name ? device.id : `${device.id} - ${device.name}`}
+                      </option>
+                    ))
+                  ) : (
+                    <>
+                      <option value="GPU">GPU</option>
+                      <option value="CPU">CPU</option>
+                      <option value="NPU">NPU</option>
+                      <option value="AUTO">AUTO</option>
+                    </>
+                  )}
                 </select>
               </div>
 
@@ -28,6 +28,7 @@ export const OvmsStatusDialog: React.FC<OvmsStatusDialogProps> = ({
   const [ovmsStatus, setOvmsStatus] = useState<OvmsStatus | null>(null);
   const [checkingStatus, setCheckingStatus] = useState(false);
   const [statusError, setStatusError] = useState("");
+  const [apiBase, setApiBase] = useState("http://localhost:1114");
 
   useEffect(() => {
     if (open) {
@@ -41,6 +42,8 @@ export const OvmsStatusDialog: React.FC<OvmsStatusDialogProps> = ({
     setOvmsStatus(null);
 
     try {
+      const endpoint = await invoke<{ api_base: string }>("get_ovms_endpoint");
+      setApiBase(endpoint.api_base);
       const ovmsStatusResponse: any = await invoke("check_ovms_status");
       setOvmsStatus(ovmsStatusResponse);
     } catch (error) {
@@ -92,7 +95,7 @@ export const OvmsStatusDialog: React.FC<OvmsStatusDialogProps> = ({
                 Error: {statusError}
               </p>
               <p className="mt-2 text-sm text-red-700 dark:text-red-200">
-                Make sure OVMS server is running on localhost:1114
+                Make sure OVMS server is running on {apiBase.replace("http://", "")}
               </p>
             </div>
           )}
@@ -164,6 +164,10 @@ export const TasksPage = () => {
         return `Notification: "${action.title}"`;
       case "RunMcpFunction":
         return `MCP: ${action.server_name}/${action.tool_name}`;
+      case "RunQuickAction":
+        return `Quick action: ${action.action_id}`;
+      case "MemoryDigest":
+        return `Memory review: last ${action.days ?? 7} days`;
       default:
         return "Unknown action";
     }
@@ -175,6 +179,10 @@ export const TasksPage = () => {
         return "🔔";
       case "RunMcpFunction":
         return "🔧";
+      case "RunQuickAction":
+        return "⚡";
+      case "MemoryDigest":
+        return "📝";
       default:
         return "❓";
     }
@@ -126,6 +126,7 @@ export const useChat = () =>
       activeChatSessionId: state.activeChatSessionId,
       currentChatMessages: state.currentChatMessages,
       temporarySession: state.temporarySession,
+      pendingPrompt: state.pendingPrompt,
       setChatSessions: state.setChatSessions,
       setActiveChatSessionId: state.setActiveChatSessionId,
       setCurrentChatMessages: state.setCurrentChatMessages,
@@ -136,6 +137,7 @@ export const useChat = () =>
       addMessageToCurrentChat: state.addMessageToCurrentChat,
       clearCurrentChatMessages: state.clearCurrentChatMessages,
       clearTemporarySession: state.clearTemporarySession,
+      setPendingPrompt: state.setPendingPrompt,
       getActiveSession: state.getActiveSession,
       getChatSessionsArray: state.getChatSessionsArray,
       getRecentChatSessions: state.getRecentChatSessions,
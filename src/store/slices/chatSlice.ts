@@ -10,6 +10,7 @@ export const createChatSlice: StateCreator<AppState, [], [], ChatSlice> = (
   activeChatSessionId: null,
   currentChatMessages: [],
   temporarySession: null,
+  pendingPrompt: null,
 
   setChatSessions: (sessions) => set({ chatSessions: sessions }),
   setActiveChatSessionId: (sessionId) => {
@@ -60,6 +61,7 @@ export const createChatSlice: StateCreator<AppState, [], [], ChatSlice> = (
 
   clearCurrentChatMessages: () => set({ currentChatMessages: [] }),
   clearTemporarySession: () => set({ temporarySession: null }),
+  setPendingPrompt: (prompt) => set({ pendingPrompt: prompt }),
 
   getActiveSession: () => {
     const state = get();
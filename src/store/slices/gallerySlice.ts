@@ -10,6 +10,11 @@ export interface GeneratedImage {
   modelId: string;
   size: string;
   numInferenceSteps?: number;
+  seed?: number;
+  negativePrompt?: string;
+  guidanceScale?: number;
+  title?: string;
+  favorite?: boolean;
 }
 
 export interface GallerySlice {
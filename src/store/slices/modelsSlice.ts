@@ -120,6 +120,8 @@ export const createModelsSlice: StateCreator<AppState, [], [], ModelsSlice> = (
           reranker: null,
         };
 
+        const defaultModel = await invoke<string | null>("get_default_model");
+
         // Categorize each loaded model
         modelNames.forEach((modelName) => {
           // Try with OpenVINO/ prefix first (metadata key format)
@@ -138,7 +140,10 @@ export const createModelsSlice: StateCreator<AppState, [], [], ModelsSlice> = (
             const modelIdToStore = fullModelId;
 
             if (modelType === "text") {
-              loadedByType.text = modelIdToStore;
+              // Several text models can be loaded; chat uses the default one
+              if (loadedByType.text === null || modelName === defaultModel) {
+                loadedByType.text = modelIdToStore;
+              }
             } else if (
               modelType === "vision" ||
               modelType === "image-to-text"
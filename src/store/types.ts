@@ -1,4 +1,6 @@
 // Type definitions for the entire store
+import type { Citation } from "@/types/app";
+
 export type ThemeMode = "light" | "dark";
 export type ThemeColor = "orange" | "blue" | "green" | "purple" | "red";
 
@@ -143,23 +145,69 @@ export interface ChatMessage {
     file_type: string;
     is_image?: boolean;
   }>;
+  pinned?: boolean;
+  generation?: GenerationMetrics | null;
+  citations?: Citation[] | null;
   [key: string]: any;
 }
 
+/** Model and timings of a generated reply */
+export interface GenerationMetrics {
+  model: string | null;
+  time_to_first_token_ms: number | null;
+  generation_time_ms: number | null;
+}
+
+/** Tools a session offers the model; a trailing `*` matches a prefix */
+export interface ToolPolicy {
+  allow: string[];
+  deny: string[];
+}
+
+/** Sampling options a session keeps; unset ones use the model's defaults */
+export interface SamplingOptions {
+  top_k?: number | null;
+  repetition_penalty?: number | null;
+  frequency_penalty?: number | null;
+  presence_penalty?: number | null;
+  stop?: string[];
+}
+
+/** Per-request overrides of the `rag.retrieval` settings of `chat_with_rag_streaming` */
+export interface RetrievalOptions {
+  candidates?: number | null;
+  top_n?: number | null;
+  /** 0 keeps whole chunks */
+  max_chunk_chars?: number | null;
+  min_score?: number | null;
+}
+
 export interface ChatSession {
   id: string;
   title: string;
   messages: ChatMessage[];
   created_at: number;
   updated_at: number;
+  tool_policy?: ToolPolicy;
+  sampling?: SamplingOptions;
+  /** Id of the prompt preset used when no system prompt is sent */
+  prompt_preset?: string | null;
   [key: string]: any;
 }
 
+/** A prompt to send, with the model a quick action pins (the loaded text model when null) */
+export interface PendingPrompt {
+  prompt: string;
+  model: string | null;
+}
+
 export interface ChatSlice {
   chatSessions: Record<string, ChatSession>;
   activeChatSessionId: string | null;
   currentChatMessages: ChatMessage[];
   temporarySession: ChatSession | null;
+  /** Message queued from outside the chat page (e.g. a text selection), sent on arrival */
+  pendingPrompt: PendingPrompt | null;
   setChatSessions: (sessions: Record<string, ChatSession>) => void;
   setActiveChatSessionId: (sessionId: string | null) => void;
   setCurrentChatMessages: (messages: ChatMessage[]) => void;
@@ -170,6 +218,7 @@ export interface ChatSlice {
   addMessageToCurrentChat: (message: ChatMessage) => void;
   clearCurrentChatMessages: () => void;
   clearTemporarySession: () => void;
+  setPendingPrompt: (prompt: PendingPrompt | null) => void;
   getActiveSession: () => ChatSession | null | undefined;
   getChatSessionsArray: () => ChatSession[];
   getRecentChatSessions: (limit?: number) => ChatSession[];
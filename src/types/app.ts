@@ -0,0 +1,378 @@
+import type { DownloadControlState } from "./models";
+import type { LatencyStats } from "./rag";
+import type { GenerationMetrics } from "@/store/types";
+
+/** Backend text as a code plus params; `text` is rendered in the configured locale */
+export interface Message {
+  code: string;
+  params: Record<string, string | number | boolean>;
+  text: string;
+}
+
+export interface AppNotification {
+  id: string;
+  title: string;
+  body: string;
+  source: string;
+  created_at: string;
+  read: boolean;
+}
+
+export interface OvmsHealth {
+  reachable: boolean;
+  available_models: string[];
+  error: string | null;
+}
+
+export type ActiveJob =
+  | { kind: "task"; task_id: string; name: string }
+  | { kind: "download"; model_id: string; state: DownloadControlState };
+
+export interface InitializationStatus {
+  step: string;
+  message: string;
+  message_code: string;
+  progress: number;
+  is_complete: boolean;
+  has_error: boolean;
+  error_message: string | null;
+  error_code: string | null;
+  failed_step: "download" | "config" | "start_server" | null;
+}
+
+export interface AppStateSnapshot {
+  taken_at: string;
+  init_status: InitializationStatus;
+  ovms: OvmsHealth;
+  loaded_model: string | null;
+  active_streams: string[];
+  active_jobs: ActiveJob[];
+  unread_notifications: AppNotification[];
+}
+
+export type UpdateChannel = "stable" | "beta";
+
+export interface AppUpdateInfo {
+  version: string;
+  prerelease: boolean;
+  published_at: string | null;
+  release_url: string;
+  installer_name: string | null;
+  installer_size: number | null;
+  /** tauri-updater `latest.json` of this release */
+  updater_manifest_url: string | null;
+}
+
+export interface AppUpdateCheck {
+  current_version: string;
+  channel: UpdateChannel;
+  latest: AppUpdateInfo | null;
+  should_prompt: boolean;
+  skipped: boolean;
+  deferred_until: number | null;
+}
+
+export interface ReleaseNotes {
+  version: string;
+  title: string;
+  notes: string;
+  published_at: string | null;
+  release_url: string;
+}
+
+export interface DownloadedAppUpdate {
+  version: string;
+  path: string;
+  sha256: string;
+  bytes: number;
+}
+
+export interface PluginTool {
+  name: string;
+  description: string;
+  input_schema: Record<string, unknown>;
+}
+
+export interface PluginCapabilities {
+  read_dirs: { host: string; guest: string }[];
+  data_dir: boolean;
+  env: string[];
+  max_memory_mb: number | null;
+  timeout_ms: number | null;
+}
+
+export interface PluginManifest {
+  id: string;
+  name: string;
+  version: string;
+  description: string;
+  author: string | null;
+  module: string;
+  tools: PluginTool[];
+  parsers: { extensions: string[] }[];
+  capabilities: PluginCapabilities;
+}
+
+export interface PluginInfo {
+  id: string;
+  dir: string;
+  manifest: PluginManifest | null;
+  enabled: boolean;
+  /** Enabled before, but the module or capabilities changed since */
+  needs_approval: boolean;
+  error: string | null;
+}
+
+/** Result of the render_chart tool */
+export interface RenderedChart {
+  path: string;
+  width: number;
+  height: number;
+}
+
+/** Payload of `ovms-health` events and result of `get_ovms_health` */
+export interface OvmsHealth {
+  status: "idle" | "healthy" | "degraded" | "restarting" | "down";
+  checked_at: string | null;
+  loaded_models: string[];
+  consecutive_failures: number;
+  restarts: number;
+  last_error: string | null;
+}
+
+/** Payload of `ovms-log` events and item of `get_ovms_logs` */
+export interface OvmsLogLine {
+  run: number;
+  stream: "stdout" | "stderr";
+  line: string;
+  timestamp: string;
+}
+
+/** A focus (Pomodoro) session from `start_focus_session` */
+export interface FocusSession {
+  id: string;
+  goal: string | null;
+  duration_minutes: number;
+  break_minutes: number;
+  started_at: string;
+  ends_at: string;
+  stopped_at: string | null;
+  silence_background: boolean;
+  task_ids: string[];
+}
+
+/** Result of `get_focus_stats` */
+export interface FocusStats {
+  days: number;
+  sessions: number;
+  completed: number;
+  total_minutes: number;
+  minutes_by_day: Record<string, number>;
+  active: FocusSession | null;
+  goals: string[];
+}
+
+/** Argument of `append_to_daily_note` */
+export type JournalEntry =
+  | { type: "text"; text: string }
+  | { type: "chat_summary"; session_id: string }
+  | { type: "task_outcome"; task_id: string };
+
+/** Result of `create_daily_note` and `append_to_daily_note`, payload of `journal-note-updated` */
+export interface JournalNote {
+  date: string;
+  path: string;
+  content: string;
+  created: boolean;
+}
+
+export interface ToolCallRecord {
+  name: string;
+  arguments: string;
+  result: string;
+  success: boolean;
+}
+
+/** Payload of `agent-step`, emitted after each round of tool calls in a chat reply */
+export interface ChatAgentStep {
+  session_id: string | null;
+  round: number;
+  max_rounds: number;
+  tool_calls: ToolCallRecord[];
+  final_round: boolean;
+}
+
+export interface CleanupCandidate {
+  kind: "model_cache" | "unloaded_model" | "temp_files";
+  id: string;
+  size_bytes: number;
+  last_modified: string | null;
+}
+
+/** Payload of `disk-space` events and result of `get_disk_status` */
+export interface DiskStatus {
+  level: "ok" | "low" | "critical";
+  available_bytes: number | null;
+  warn_free_bytes: number;
+  critical_free_bytes: number;
+  checked_at: string | null;
+  candidates: CleanupCandidate[];
+  reclaimable_bytes: number;
+}
+
+export type OperationKind = "chat" | "text_assist" | "download" | "ingestion" | "embeddings" | "research";
+
+/** Item of `list_operations`; any `id` can be passed to `cancel_operation` */
+export interface OperationInfo {
+  id: string;
+  kind: OperationKind;
+  started_at: string;
+  cancelled: boolean;
+}
+
+/** Payload of `tool-approval-requested`; answer with `respond_to_tool_request` */
+export interface ToolApprovalRequest {
+  request_id: string;
+  session_id: string | null;
+  tool_name: string;
+  arguments: string;
+  expires_in_secs: number;
+}
+
+/** Payload of `tool-approval-resolved` */
+export interface ToolApprovalResolved {
+  request_id: string;
+  approved: boolean;
+  reason: "approved" | "declined" | "timed out" | "cancelled";
+}
+
+/** Payload of `server-busy`: OVMS answered 429/503 and the request is retried */
+export interface ServerBusy {
+  id: string;
+  kind: OperationKind;
+  state: "retrying" | "recovered" | "gave_up";
+  status: number | null;
+  attempt: number;
+  max_retries: number;
+  retry_in_ms: number | null;
+}
+
+/** Payload of `chat-metrics`, emitted just before the finished `chat-token` */
+export interface ChatMetrics {
+  /** Session id, or "temp" for an unsaved chat; also on `chat-token`, `chat-usage`, `chat-error` and `tool-call` */
+  stream_id: string;
+  session_id: string | null;
+  generation: GenerationMetrics;
+  prompt_tokens: number | null;
+  completion_tokens: number | null;
+  tokens_per_second: number | null;
+  cancelled: boolean;
+}
+
+/** Result of `get_session_metrics` */
+export interface SessionMetrics {
+  session_id: string;
+  replies: number;
+  timed_replies: number;
+  time_to_first_token: LatencyStats;
+  generation_time: LatencyStats;
+  total_generation_time_ms: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  mean_tokens_per_second: number | null;
+  models: Record<string, number>;
+}
+
+/** Payload of `session-title-updated`: the model titled a session after its first exchange */
+export interface SessionTitleUpdated {
+  session_id: string;
+  title: string;
+}
+
+/** Source of a `[n]` marker in a RAG answer */
+export interface Citation {
+  marker: number;
+  file_path: string;
+  title: string;
+  chunk_index: number | null;
+  score: number;
+  rerank_score: number | null;
+  excerpt: string;
+}
+
+/** Payload of `rag-citations`, emitted before a RAG answer starts streaming */
+export interface RagCitations {
+  stream_id: string;
+  session_id: string | null;
+  citations: Citation[];
+}
+
+/** Saved system prompt; `{{date}}`, `{{time}}`, `{{weekday}}` and `{{user_name}}` are filled in on use */
+export interface PromptPreset {
+  id: string;
+  name: string;
+  description: string | null;
+  content: string;
+  created_at: number;
+  updated_at: number;
+}
+
+/** `responseFormat` of the chat commands; the finished `chat-token` carries `format_error` when the reply still fails it */
+export type ChatResponseFormat =
+  | { type: "json_object" }
+  | {
+      type: "json_schema";
+      json_schema: {
+        name: string;
+        description?: string;
+        schema: Record<string, unknown>;
+        strict?: boolean;
+      };
+    };
+
+/** Payload of `research-step` events */
+export type ResearchStep = { research_id: string } & (
+  | { step: "planned"; queries: string[] }
+  | { step: "searched"; query: string; results: number }
+  | { step: "fetched"; url: string; title: string }
+  | { step: "fetch_failed"; url: string; error: string }
+  | { step: "indexed"; url: string; chunks: number }
+  | { step: "retrieved"; excerpts: number; sources: number }
+  | { step: "synthesizing" }
+);
+
+export interface ResearchSource {
+  index: number;
+  url: string;
+  title: string;
+  cited: boolean;
+}
+
+/** Sentence of a research answer; `sources` are `ResearchSource.index` values */
+export interface ResearchClaim {
+  text: string;
+  sources: number[];
+}
+
+/** Result of `research_query` */
+export interface ResearchResult {
+  research_id: string;
+  question: string;
+  answer: string;
+  claims: ResearchClaim[];
+  sources: ResearchSource[];
+  elapsed_ms: number;
+}
+
+/** Result of `export_chat_session` */
+export interface ChatExport {
+  path: string;
+  size_bytes: number;
+}
+
+/** Result of `import_chat_sessions` */
+export interface ImportReport {
+  imported: number;
+  merged: number;
+  messages_added: number;
+}
@@ -1,3 +1,5 @@
+import type { Message } from "./app";
+
 // TypeScript interfaces for model-related types
 
 export type ModelTaskType =
@@ -25,6 +27,10 @@ export interface GraphGenerationParams {
   dynamic_split_fuse?: boolean;
   pipeline_type?: string;
 
+  // Speculative decoding
+  draft_model_path?: string;
+  num_assistant_tokens?: number;
+
   // Embeddings specific
   normalize?: boolean;
   pooling?: string;
@@ -55,8 +61,16 @@ export interface ModelMetadata {
   model_type: ModelType;
   pipeline_tag: string;
   commit_sha: string | null;
+  /** Draft model paired with `pair_draft_model` for speculative decoding */
+  draft_model_id?: string | null;
+  num_assistant_tokens?: number | null;
+  /** How chat gives tools to the model, set with `set_tool_call_mode` */
+  tool_call_mode?: ToolCallMode;
 }
 
+/** "auto" uses the request's tools field when the graph has a tool parser, the prompt scheme otherwise */
+export type ToolCallMode = "auto" | "native" | "prompt";
+
 export interface ModelInfo {
   id: string;
   author: string | null;
@@ -87,4 +101,99 @@ export interface DownloadProgress {
   totalBytes?: number;
   currentFileDownloaded?: number;
   currentFileTotal?: number;
+  completedFiles?: number;
+  activeFiles?: number;
+}
+
+export type DownloadControlState = "running" | "paused" | "cancelled";
+
+export interface DownloadStateEvent {
+  modelId: string;
+  state: DownloadControlState;
+  message: Message;
+}
+
+export interface DownloadFinishedEvent {
+  modelId: string;
+  success: boolean;
+  message: Message;
+}
+
+export interface ModelUpdateResult {
+  model_id: string;
+  updated_files: string[];
+  removed_files: string[];
+  unchanged_files: number;
+  downloaded_bytes: number;
+  commit_sha: string | null;
+  graph_regenerated: boolean;
+  message: Message;
+}
+
+export interface ModelSizeEstimate {
+  model_id: string;
+  file_count: number;
+  total_bytes: number;
+  /** Bytes the download adds to the drive, net of files already present */
+  required_bytes: number;
+  target_dir: string;
+  available_bytes: number | null;
+  fits: boolean | null;
+}
+
+/** Globs over repository paths; a pattern without "/" matches the file name */
+export interface FileFilter {
+  include: string[];
+  exclude: string[];
+}
+
+export interface ModelFile {
+  path: string;
+  size: number | null;
+  lfs: boolean;
+}
+
+export interface MemoryEstimate {
+  model_id: string;
+  device: string;
+  quantization: string | null;
+  weights_bytes: number;
+  runtime_overhead_bytes: number;
+  kv_cache_bytes: number;
+  kv_bytes_per_token: number | null;
+  total_bytes: number;
+  available_bytes: number | null;
+  fits: boolean | null;
+  warning: string | null;
+}
+
+/** An inference device from `get_available_devices` */
+export interface HardwareDevice {
+  /** Value for `target_device` and `load_model`'s `device`: CPU, GPU, GPU.1, NPU, AUTO */
+  id: string;
+  kind: "cpu" | "integrated_gpu" | "discrete_gpu" | "npu" | "auto";
+  name: string;
+  memory_bytes: number | null;
+  shared_memory: boolean;
+}
+
+export type FileProblem =
+  | { problem: "missing" }
+  | { problem: "size_mismatch"; expected: number; actual: number }
+  | { problem: "checksum_mismatch"; expected: string; actual: string };
+
+export type CorruptedFile = { path: string } & FileProblem;
+
+export interface ModelVerification {
+  model_id: string;
+  checked_files: number;
+  checksummed_files: number;
+  corrupted: CorruptedFile[];
+  ok: boolean;
+}
+
+export interface HfTokenStatus {
+  configured: boolean;
+  enabled: boolean;
+  username: string | null;
 }
@@ -0,0 +1,170 @@
+export interface LatencyStats {
+  samples: number;
+  min_ms: number;
+  mean_ms: number;
+  p50_ms: number;
+  p95_ms: number;
+  max_ms: number;
+}
+
+/** Result of `benchmark_rag` */
+export interface RagBenchmarkReport {
+  backend: "sled" | "qdrant";
+  corpus_size: number;
+  query_count: number;
+  limit: number;
+  embedding_dim: number;
+  reranking: boolean;
+  answer_model: string | null;
+  indexing_ms: number;
+  indexing_chunks_per_sec: number;
+  embedding: LatencyStats;
+  retrieval: LatencyStats;
+  rerank: LatencyStats | null;
+  answer: LatencyStats | null;
+  end_to_end: LatencyStats;
+  top1_accuracy: number;
+}
+
+/** Payload of `rag-benchmark-progress` */
+export interface RagBenchmarkProgress {
+  stage: "indexing" | "querying";
+  completed: number;
+  total: number;
+}
+
+/** Entity of the knowledge graph */
+export interface GraphEntity {
+  name: string;
+  kind: string;
+  chunk_ids: string[];
+}
+
+export interface GraphNeighbor {
+  name: string;
+  kind: string;
+  relation: string;
+  direction: "outgoing" | "incoming";
+  mentions: number;
+}
+
+/** Result of `get_entity_neighbors` */
+export interface EntityNeighbors {
+  entity: GraphEntity;
+  neighbors: GraphNeighbor[];
+}
+
+/** Result of `build_knowledge_graph`, one per file */
+export interface GraphBuildSummary {
+  file_path: string;
+  chunks: number;
+  failed_chunks: number;
+  entities: number;
+  relations: number;
+}
+
+/** Payload of `knowledge-graph-progress` */
+export interface GraphProgress {
+  file_path: string;
+  processed: number;
+  total: number;
+}
+
+export interface Flashcard {
+  question: string;
+  answer: string;
+  source: string;
+}
+
+/** Result of `generate_flashcards` */
+export interface FlashcardDeck {
+  cards: Flashcard[];
+  output_path: string;
+  duplicates: number;
+  failed_chunks: number;
+}
+
+/** Payload of `flashcards-progress` */
+export interface FlashcardProgress {
+  generated: number;
+  target: number;
+  processed_chunks: number;
+  total_chunks: number;
+}
+
+/** Optional `filter` of `search_documents` and `search_documents_by_query`, checked before chunks are scored */
+export interface SearchFilter {
+  /** Any of these file types */
+  file_types?: string[];
+  /** Files in this folder or below it */
+  path_prefix?: string | null;
+  /** Unix milliseconds, inclusive */
+  created_after?: number | null;
+  /** Unix milliseconds, exclusive */
+  created_before?: number | null;
+  /** Chunks with at least one of these tags (the `tags` metadata entry) */
+  tags?: string[];
+}
+
+export type ChunkingStrategy = "fixed" | "sentence" | "markdown" | "code";
+
+/** Passed as `chunking` to `process_document` / `ingest_document` */
+export interface ChunkingOptions {
+  strategy?: ChunkingStrategy;
+  /** Longest chunk, in characters */
+  chunk_size?: number;
+  /** Characters shared by consecutive chunks; less than chunk_size */
+  overlap?: number;
+}
+
+export interface WatchOptions {
+  /** Include subfolders (default true) */
+  recursive?: boolean;
+  /** Index files already in the folder that are not in the collection yet (default true) */
+  ingest_existing?: boolean;
+  chunking?: ChunkingOptions;
+}
+
+/** Returned by `watch_folder` / `get_watched_folders` */
+export interface WatchedFolder {
+  path: string;
+  collection: string;
+  options: WatchOptions;
+}
+
+/** Payload of `rag-ingest` */
+export interface RagIngestEvent {
+  folder: string;
+  collection: string;
+  file_path: string;
+  action: "ingested" | "removed" | "failed";
+  chunk_count: number;
+  error: string | null;
+}
+
+export type IngestJobStatus = "queued" | "running" | "completed" | "failed" | "cancelled";
+
+/** Returned by `get_ingest_jobs` and emitted as `ingest-progress` */
+export interface IngestJob {
+  id: string;
+  file_path: string;
+  status: IngestJobStatus;
+  chunks_stored: number;
+  error: string | null;
+  queued_at: string;
+  started_at: string | null;
+  finished_at: string | null;
+}
+
+export type ReindexStatus = "unchanged" | "updated" | "missing" | "failed";
+
+/** Returned by `reindex_file`, and per file by `reindex_all` (emitted as `reindex-progress`) */
+export interface ReindexSummary {
+  file_path: string;
+  status: ReindexStatus;
+  chunk_count: number;
+  chunks_embedded: number;
+  chunks_reused: number;
+  chunks_removed: number;
+  error: string | null;
+}
@@ -6,7 +6,17 @@ export type ActionType =
       server_name: string;
       tool_name: string;
       arguments: any;
-    };
+    }
+  | {
+      type: "AgentTask";
+      goal: string;
+      model_name?: string;
+      max_steps?: number;
+      max_duration_secs?: number;
+      use_rag?: boolean;
+    }
+  | { type: "RunQuickAction"; action_id: string; text: string }
+  | { type: "MemoryDigest"; days?: number; model_name?: string };
 
 export type TriggerTime =
   | { type: "DateTime"; datetime: string }
@@ -14,7 +24,18 @@ export type TriggerTime =
   | { type: "Weekly"; day_of_week: number; time: string }
   | { type: "Monthly"; day_of_month: number; time: string }
   | { type: "EveryNMinutes"; minutes: number }
-  | { type: "EveryNHours"; hours: number };
+  | { type: "EveryNHours"; hours: number }
+  | {
+      type: "FileEvent";
+      path: string;
+      pattern?: string;
+      event?: "Created" | "Modified" | "Removed" | "Any";
+      recursive?: boolean;
+    }
+  | {
+      type: "SystemEvent";
+      event: "AppStartup" | "SystemResume" | "NetworkOnline" | "NetworkOffline";
+    };
 
 export type TimeUnit = "Minutes" | "Hours" | "Days" | "Weeks";
 
@@ -36,6 +57,15 @@ export interface Task {
   next_run?: string;
   run_count: number;
   auto_delete: boolean;
+  snoozed_until?: string;
+  conditions?: RunConditions;
+}
+
+export interface RunConditions {
+  idle_minutes?: number;
+  require_ac_power?: boolean;
+  require_model_loaded?: boolean;
+  model_name?: string;
 }
 
 export interface TaskExecutionLog {
@@ -44,4 +74,15 @@ export interface TaskExecutionLog {
   status: "Success" | "Failed" | "Skipped";
   message?: string;
   error?: string;
+  steps?: AgentStep[];
+}
+
+export interface AgentStep {
+  index: number;
+  kind: "Retrieval" | "Model" | "ToolCall" | "FinalAnswer";
+  content: string;
+  tool_name?: string;
+  arguments?: any;
+  success: boolean;
+  elapsed_ms: number;
 }
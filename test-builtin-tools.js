@@ -16,6 +16,9 @@ async function testBuiltinTools() {
     );
     console.log("");
 
+    // execute_builtin_tool needs confirmation; allow it for this run
+    await invoke("elevate_permissions", { durationSecs: 120 });
+
     // Test 2: Get system info
     console.log("2️⃣ Testing get_system_info...");
     const sysInfoResult = await invoke("execute_builtin_tool", {